@@ -0,0 +1,239 @@
+//! Declarative job specs executed by `storify apply`.
+//!
+//! A spec is a TOML file with an ordered list of `[[step]]` tables. Each step moves or
+//! deletes the files selected by its include/exclude globs, and may prune the destination
+//! and write a manifest of what it transferred. The whole file is validated before any
+//! step runs.
+
+use crate::error::{Error, Result};
+use crate::storage::PathFilter;
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const SPEC_VERSION: u32 = 1;
+const DEFAULT_STEP_CONCURRENCY: usize = 4;
+
+/// What a step does with the files selected under its source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StepAction {
+    /// Local source to remote destination
+    Upload,
+    /// Remote source to local destination
+    Download,
+    /// Remote source to remote destination
+    Copy,
+    /// Remove the selected remote objects
+    Delete,
+}
+
+impl StepAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StepAction::Upload => "upload",
+            StepAction::Download => "download",
+            StepAction::Copy => "copy",
+            StepAction::Delete => "delete",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawSpec {
+    #[serde(default = "default_version")]
+    version: u32,
+    /// Default for steps that do not set their own
+    concurrency: Option<usize>,
+    #[serde(default, rename = "step")]
+    steps: Vec<RawStep>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawStep {
+    name: Option<String>,
+    action: StepAction,
+    source: String,
+    destination: Option<String>,
+    #[serde(default)]
+    include: Vec<String>,
+    #[serde(default)]
+    exclude: Vec<String>,
+    concurrency: Option<usize>,
+    #[serde(default)]
+    skip_existing: bool,
+    #[serde(default)]
+    prune: bool,
+    manifest: Option<PathBuf>,
+}
+
+fn default_version() -> u32 {
+    SPEC_VERSION
+}
+
+/// A validated step.
+#[derive(Debug, Clone)]
+pub struct JobStep {
+    pub name: String,
+    pub action: StepAction,
+    pub source: String,
+    /// Always set except for `delete`
+    pub destination: Option<String>,
+    pub filter: PathFilter,
+    pub concurrency: usize,
+    /// Skip files whose destination already exists with the same size
+    pub skip_existing: bool,
+    /// Delete destination files that match the filter but are absent from the source
+    pub prune: bool,
+    /// Local JSON file listing the transferred files, written after the step
+    pub manifest: Option<PathBuf>,
+}
+
+/// A validated job spec.
+#[derive(Debug, Clone)]
+pub struct JobSpec {
+    pub steps: Vec<JobStep>,
+}
+
+impl JobSpec {
+    /// Read and validate the spec at `path`.
+    pub fn load(path: &Path) -> Result<Self> {
+        let invalid = |message: String| Error::InvalidJobSpec {
+            path: path.to_path_buf(),
+            message,
+        };
+        let extension = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_ascii_lowercase());
+        if matches!(extension.as_deref(), Some("yaml" | "yml")) {
+            return Err(invalid(
+                "YAML specs are not supported; write the job as TOML".to_string(),
+            ));
+        }
+        let text = fs::read_to_string(path)?;
+        Self::parse(&text).map_err(invalid)
+    }
+
+    /// Parse and validate a TOML spec, describing the first problem found.
+    pub fn parse(text: &str) -> std::result::Result<Self, String> {
+        let raw: RawSpec = toml::from_str(text).map_err(|e| e.message().to_string())?;
+        if raw.version != SPEC_VERSION {
+            return Err(format!(
+                "unsupported version {} (expected {SPEC_VERSION})",
+                raw.version
+            ));
+        }
+        if raw.steps.is_empty() {
+            return Err("no [[step]] defined".to_string());
+        }
+        let default_concurrency = raw.concurrency.unwrap_or(DEFAULT_STEP_CONCURRENCY);
+        let steps = raw
+            .steps
+            .into_iter()
+            .enumerate()
+            .map(|(index, step)| {
+                let label = step
+                    .name
+                    .clone()
+                    .unwrap_or_else(|| format!("step {}", index + 1));
+                validate_step(step, label.clone(), default_concurrency)
+                    .map_err(|message| format!("{label}: {message}"))
+            })
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(Self { steps })
+    }
+}
+
+fn validate_step(
+    step: RawStep,
+    name: String,
+    default_concurrency: usize,
+) -> std::result::Result<JobStep, String> {
+    if step.source.trim().is_empty() {
+        return Err("source must not be empty".to_string());
+    }
+    let destination = step.destination.filter(|d| !d.trim().is_empty());
+    match (step.action, &destination) {
+        (StepAction::Delete, Some(_)) => {
+            return Err("delete takes no destination".to_string());
+        }
+        (StepAction::Delete, None) if step.prune || step.skip_existing => {
+            return Err("prune and skip_existing do not apply to delete".to_string());
+        }
+        (StepAction::Upload | StepAction::Download | StepAction::Copy, None) => {
+            return Err(format!("{} requires a destination", step.action.as_str()));
+        }
+        _ => {}
+    }
+    if step.action == StepAction::Upload && !Path::new(&step.source).exists() {
+        return Err(format!("local source '{}' does not exist", step.source));
+    }
+    let concurrency = step.concurrency.unwrap_or(default_concurrency);
+    if concurrency == 0 {
+        return Err("concurrency must be at least 1".to_string());
+    }
+    let filter = PathFilter::new(&step.include, &step.exclude).map_err(|e| e.to_string())?;
+
+    Ok(JobStep {
+        name,
+        action: step.action,
+        source: step.source,
+        destination,
+        filter,
+        concurrency,
+        skip_existing: step.skip_existing,
+        prune: step.prune,
+        manifest: step.manifest,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_validates_steps() {
+        let spec = JobSpec::parse(
+            r#"
+            concurrency = 2
+
+            [[step]]
+            name = "mirror reports"
+            action = "copy"
+            source = "reports/"
+            destination = "archive/reports/"
+            include = ["*.csv"]
+            exclude = ["tmp/*"]
+            prune = true
+
+            [[step]]
+            action = "delete"
+            source = "scratch/"
+            concurrency = 8
+            "#,
+        )
+        .unwrap();
+        assert_eq!(spec.steps.len(), 2);
+        let copy = &spec.steps[0];
+        assert_eq!(copy.action, StepAction::Copy);
+        assert_eq!(copy.concurrency, 2);
+        assert!(copy.filter.matches("2024/q1.csv"));
+        assert!(!copy.filter.matches("tmp/q1.csv"));
+        assert!(!copy.filter.matches("q1.txt"));
+        assert_eq!(spec.steps[1].name, "step 2");
+        assert_eq!(spec.steps[1].concurrency, 8);
+
+        let err = JobSpec::parse("[[step]]\naction = \"copy\"\nsource = \"a/\"\n").unwrap_err();
+        assert_eq!(err, "step 1: copy requires a destination");
+        let err = JobSpec::parse("[[step]]\naction = \"delete\"\nsource = \"a/\"\nprune = true\n")
+            .unwrap_err();
+        assert!(err.contains("prune"));
+        assert!(JobSpec::parse("[[step]]\naction = \"move\"\nsource = \"a\"\n").is_err());
+        assert!(
+            JobSpec::parse("version = 2\n[[step]]\naction = \"delete\"\nsource = \"a\"\n").is_err()
+        );
+        assert!(JobSpec::parse("").is_err());
+    }
+}
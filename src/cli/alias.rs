@@ -0,0 +1,108 @@
+//! Command aliases of the active profile (`ll` for `ls -L`).
+//!
+//! An unknown subcommand reaches clap's external-subcommand slot; it is looked up in the
+//! aliases of the configuration the context resolved, and the command line is parsed again
+//! with the alias spelled out.
+
+use std::ffi::OsString;
+
+use clap::error::ErrorKind;
+use clap::{CommandFactory, Parser};
+
+use crate::config::storage_config::parse_alias;
+use crate::error::{Error, Result};
+
+use super::context::CliContext;
+use super::entry::{Args, Command};
+
+/// Expand the alias `ctx` was parsed with, if any, from the aliases of its resolved
+/// profile; `argv` is the command line `ctx` came from. Unknown names fail like any
+/// unrecognized subcommand.
+pub fn expand(ctx: CliContext, argv: Vec<OsString>) -> Result<CliContext> {
+    let Command::Alias(words) = ctx.command() else {
+        return Ok(ctx);
+    };
+    let name = &words[0];
+    let Some(expansion) = ctx
+        .resolved()
+        .storage
+        .as_ref()
+        .and_then(|config| config.aliases.get(name))
+    else {
+        Args::command()
+            .error(
+                ErrorKind::InvalidSubcommand,
+                format!("unrecognized subcommand '{name}'"),
+            )
+            .exit()
+    };
+    // The configuration was resolved for the global options before the alias, so the
+    // expansion must not bring options of its own.
+    if expansion.starts_with('-') {
+        return Err(starts_with_option(name));
+    }
+
+    // The alias and everything after it were captured verbatim, so they end argv.
+    let at = argv.len() - words.len();
+    let mut expanded = argv[..at].to_vec();
+    expanded.extend(expansion.split_whitespace().map(OsString::from));
+    expanded.extend_from_slice(&argv[at + 1..]);
+    let args = Args::try_parse_from(expanded).unwrap_or_else(|err| err.exit());
+    match &args.command {
+        Command::Alias(words) => Err(Error::InvalidArgument {
+            message: format!(
+                "alias '{name}' runs '{}', which is not a storify command",
+                words[0]
+            ),
+        }),
+        _ => Ok(ctx.with_command(args.command)),
+    }
+}
+
+/// `--alias NAME=COMMAND`, refusing names a storify command already has.
+pub fn parse_alias_arg(raw: &str) -> Result<(String, String)> {
+    let (name, command) = parse_alias(raw)?;
+    if Args::command().find_subcommand(&name).is_some() {
+        return Err(Error::InvalidArgument {
+            message: format!("alias '{name}' would be hidden by the storify command of that name"),
+        });
+    }
+    if command.starts_with('-') {
+        return Err(starts_with_option(&name));
+    }
+    Ok((name, command))
+}
+
+fn starts_with_option(name: &str) -> Error {
+    Error::InvalidArgument {
+        message: format!("alias '{name}' must start with a storify command, not an option"),
+    }
+}
+
+/// Error for a subcommand that is neither a storify command nor an alias.
+pub fn unknown_command(name: &str) -> Error {
+    Error::InvalidArgument {
+        message: format!("unknown command '{name}'"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alias_names_cannot_shadow_commands() {
+        assert_eq!(
+            parse_alias_arg("ll= ls  -L ").unwrap(),
+            ("ll".to_string(), "ls -L".to_string())
+        );
+        assert_eq!(
+            parse_alias_arg("ll=").unwrap(),
+            ("ll".to_string(), String::new())
+        );
+        assert!(parse_alias_arg("ls=ls -L").is_err());
+        assert!(parse_alias_arg("pls=--profile prod ls").is_err());
+        assert!(parse_alias_arg("-l=ls -L").is_err());
+        assert!(parse_alias_arg("ls -L").is_err());
+    }
+}
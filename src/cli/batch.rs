@@ -0,0 +1,309 @@
+//! `storify batch`: run the operations listed in a JSON Lines manifest.
+//!
+//! Each non-blank line is one operation, e.g.
+//! `{"op": "cp", "src": "logs/a.log", "dst": "archive/a.log"}`. The whole manifest is
+//! validated before anything runs; operations then run with bounded concurrency and in
+//! no particular order, so lines must not depend on each other.
+
+use crate::error::{Error, Result};
+use crate::storage::constants::{
+    DEFAULT_DOWNLOAD_CONCURRENCY, DEFAULT_DOWNLOAD_PART_SIZE, DEFAULT_UPLOAD_CONCURRENCY,
+};
+use crate::storage::{
+    Clobber, DownloadOptions, PathFilter, StorageClient, SymlinkPolicy, UploadOptions,
+    normalize_under_root,
+};
+use clap::Args as ClapArgs;
+use futures::stream::{self, StreamExt};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+const DEFAULT_BATCH_CONCURRENCY: usize = 4;
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct BatchArgs {
+    /// JSON Lines file with one operation per line (put, get, rm or cp)
+    #[arg(long, value_name = "FILE")]
+    pub manifest: PathBuf,
+
+    /// Operations run at the same time
+    #[arg(
+        short = 'j',
+        long,
+        value_name = "N",
+        default_value_t = DEFAULT_BATCH_CONCURRENCY,
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    pub concurrency: usize,
+
+    /// Keep running the remaining operations after one fails
+    #[arg(long)]
+    pub continue_on_error: bool,
+}
+
+/// One line of a batch manifest.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase", deny_unknown_fields)]
+pub enum BatchOp {
+    /// Upload local `src` to remote `dst`
+    Put {
+        src: String,
+        dst: String,
+        #[serde(default)]
+        recursive: bool,
+    },
+    /// Download remote `src` to local `dst`
+    Get { src: String, dst: String },
+    /// Delete remote `src`
+    Rm {
+        src: String,
+        #[serde(default)]
+        recursive: bool,
+    },
+    /// Copy remote `src` to remote `dst`
+    Cp { src: String, dst: String },
+}
+
+impl BatchOp {
+    /// Short description for status lines.
+    fn describe(&self) -> String {
+        match self {
+            BatchOp::Put { src, dst, .. } => format!("put {src} → {dst}"),
+            BatchOp::Get { src, dst } => format!("get {src} → {dst}"),
+            BatchOp::Rm { src, .. } => format!("rm {src}"),
+            BatchOp::Cp { src, dst } => format!("cp {src} → {dst}"),
+        }
+    }
+
+    /// Paths of the operation that name objects in storage.
+    fn remote_paths(&self) -> Vec<&str> {
+        match self {
+            BatchOp::Put { dst, .. } => vec![dst],
+            BatchOp::Get { src, .. } | BatchOp::Rm { src, .. } => vec![src],
+            BatchOp::Cp { src, dst } => vec![src, dst],
+        }
+    }
+
+    fn validate(&self) -> std::result::Result<(), String> {
+        let (src, dst) = match self {
+            BatchOp::Put { src, dst, .. }
+            | BatchOp::Get { src, dst }
+            | BatchOp::Cp { src, dst } => (src, Some(dst)),
+            BatchOp::Rm { src, .. } => (src, None),
+        };
+        if src.trim().is_empty() || dst.is_some_and(|dst| dst.trim().is_empty()) {
+            return Err("src and dst must not be empty".to_string());
+        }
+        for path in self.remote_paths() {
+            if path.contains("://") {
+                return Err(format!("bucket URIs are not supported here: '{path}'"));
+            }
+            if normalize_under_root(path).is_none() {
+                return Err(format!("'{path}' escapes the storage root"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Run the operation with the same client methods as the matching subcommand.
+    async fn run(&self, client: &StorageClient) -> Result<()> {
+        match self {
+            BatchOp::Put {
+                src,
+                dst,
+                recursive,
+            } => {
+                let options = UploadOptions {
+                    concurrency: DEFAULT_UPLOAD_CONCURRENCY,
+                    per_file_limit: None,
+                    total_limit: None,
+                    verbose: false,
+                    dry_run: false,
+                    filter: PathFilter::default(),
+                    preserve: false,
+                    symlinks: SymlinkPolicy::Follow,
+                    replication: None,
+                    compression: None,
+                    encrypt: false,
+                    extract: false,
+                    clobber: Clobber::default(),
+                };
+                client
+                    .upload_files(src, dst, *recursive, &options)
+                    .await?
+                    .ensure_complete()
+            }
+            BatchOp::Get { src, dst } => {
+                let options = DownloadOptions {
+                    check_space: true,
+                    concurrency: DEFAULT_DOWNLOAD_CONCURRENCY,
+                    part_size: DEFAULT_DOWNLOAD_PART_SIZE,
+                    resume: false,
+                    filter: PathFilter::default(),
+                    newer_than: None,
+                    preserve: false,
+                    preserve_links: false,
+                    verbose: false,
+                    dry_run: false,
+                    decompress: false,
+                    decrypt: false,
+                    archive: false,
+                };
+                client
+                    .download_files(src, dst, None, &options)
+                    .await?
+                    .ensure_complete()
+            }
+            BatchOp::Rm { src, recursive } => {
+                client
+                    .delete_files(std::slice::from_ref(src), *recursive, None, false)
+                    .await
+            }
+            BatchOp::Cp { src, dst } => client.copy_files(src, dst, &Clobber::default()).await,
+        }
+    }
+}
+
+/// Parse and validate a manifest, keeping the line number of each operation.
+pub fn parse_manifest(text: &str) -> std::result::Result<Vec<(usize, BatchOp)>, String> {
+    let mut ops = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        let line_no = index + 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let op: BatchOp = serde_json::from_str(line).map_err(|e| format!("line {line_no}: {e}"))?;
+        op.validate()
+            .map_err(|message| format!("line {line_no}: {message}"))?;
+        ops.push((line_no, op));
+    }
+    if ops.is_empty() {
+        return Err("no operations".to_string());
+    }
+    Ok(ops)
+}
+
+fn load_manifest(path: &Path) -> Result<Vec<(usize, BatchOp)>> {
+    let text = std::fs::read_to_string(path)?;
+    parse_manifest(&text).map_err(|message| Error::InvalidBatchManifest {
+        path: path.to_path_buf(),
+        message,
+    })
+}
+
+/// Outcome of one operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Done,
+    Failed,
+    /// Not started because an earlier operation failed
+    Skipped,
+}
+
+pub async fn execute(args: &BatchArgs, client: &StorageClient) -> Result<()> {
+    let ops = load_manifest(&args.manifest)?;
+    let total = ops.len();
+    let concurrency = client.concurrency(args.concurrency);
+    tracing::debug!(
+        "batch manifest={:?} ops={total} concurrency={concurrency}",
+        args.manifest
+    );
+
+    let stop = AtomicBool::new(false);
+    let results: Vec<Status> = stream::iter(ops)
+        .map(|(line_no, op)| {
+            let stop = &stop;
+            async move {
+                if stop.load(Ordering::Relaxed) {
+                    return Status::Skipped;
+                }
+                match op.run(client).await {
+                    Ok(()) => {
+                        println!("[ok] line {line_no}: {}", op.describe());
+                        Status::Done
+                    }
+                    Err(e) => {
+                        eprintln!("[failed] line {line_no}: {}: {e}", op.describe());
+                        if !args.continue_on_error {
+                            stop.store(true, Ordering::Relaxed);
+                        }
+                        Status::Failed
+                    }
+                }
+            }
+        })
+        .buffer_unordered(concurrency)
+        .collect()
+        .await;
+
+    let count = |wanted: Status| results.iter().filter(|s| **s == wanted).count();
+    let done = count(Status::Done);
+    let failed = count(Status::Failed);
+    let skipped = count(Status::Skipped);
+    println!("Batch: {done} succeeded, {failed} failed, {skipped} skipped of {total}");
+    if failed > 0 {
+        if skipped > 0 {
+            eprintln!("stopped after the first failure; pass --continue-on-error to run the rest");
+        }
+        return Err(Error::BatchFailed { failed, total });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_operations_with_line_numbers() {
+        let ops = parse_manifest(
+            r#"{"op": "put", "src": "a.txt", "dst": "up/a.txt"}
+
+{"op": "rm", "src": "old/", "recursive": true}
+{"op": "cp", "src": "x", "dst": "y"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            ops,
+            vec![
+                (
+                    1,
+                    BatchOp::Put {
+                        src: "a.txt".to_string(),
+                        dst: "up/a.txt".to_string(),
+                        recursive: false
+                    }
+                ),
+                (
+                    3,
+                    BatchOp::Rm {
+                        src: "old/".to_string(),
+                        recursive: true
+                    }
+                ),
+                (
+                    4,
+                    BatchOp::Cp {
+                        src: "x".to_string(),
+                        dst: "y".to_string()
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_lines() {
+        let err = parse_manifest(r#"{"op": "mv", "src": "a", "dst": "b"}"#).unwrap_err();
+        assert!(err.starts_with("line 1:"), "{err}");
+        let err = parse_manifest(
+            "{\"op\": \"get\", \"src\": \"a\", \"dst\": \"b\"}\n{\"op\": \"rm\", \"src\": \"a\", \"dst\": \"b\"}",
+        )
+        .unwrap_err();
+        assert!(err.starts_with("line 2:"), "{err}");
+        let err = parse_manifest(r#"{"op": "cp", "src": "../x", "dst": "b"}"#).unwrap_err();
+        assert!(err.contains("escapes"), "{err}");
+        assert_eq!(parse_manifest("\n\n").unwrap_err(), "no operations");
+    }
+}
@@ -1,10 +1,16 @@
 use crate::config::{
-    ConfigSource, ProfileStore, StorageConfig, StorageProvider, StoredProfile,
+    CompatPreset, ConfigSource, ProfileField, ProfileStore, StorageConfig, StorageProvider,
+    StoredProfile,
+    crypto::DataKey,
+    env_vars,
     loader::ResolvedConfig,
     prepare_storage_config,
     spec::{ProviderSpec, Requirement, provider_spec},
+    storage_config::parse_header,
 };
 use crate::error::{Error, Result};
+use crate::storage::StorageClient;
+use secrecy::SecretString;
 use std::str::FromStr;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tokio::runtime::Handle;
@@ -13,8 +19,8 @@ use tokio::task;
 use super::{
     context::CliContext,
     entry::{
-        ConfigCommand, CreateArgs, DeleteArgs, ListArgs, SetArgs, ShowArgs, TempClearArgs,
-        TempCommand, TempShowArgs,
+        ConfigCommand, CreateArgs, DeleteArgs, EditArgs, EnvArgs, ListArgs, RotatePasswordArgs,
+        SetArgs, ShowArgs, TempClearArgs, TempCommand, TempShowArgs, TestArgs,
     },
     prompts::Prompt,
 };
@@ -87,6 +93,9 @@ pub fn execute(command: &ConfigCommand, ctx: &CliContext) -> Result<()> {
         ConfigCommand::Set(args) => set_default_profile(args, ctx),
         ConfigCommand::List(args) => list_profiles(args, ctx),
         ConfigCommand::Delete(args) => delete_profile(args, ctx),
+        ConfigCommand::Edit(args) => edit_profile(args, ctx),
+        ConfigCommand::RotatePassword(args) => rotate_password(args, ctx),
+        ConfigCommand::Test(args) => test_command(args, ctx),
         ConfigCommand::Temp(cmd) => temp_command(cmd, ctx),
     }
 }
@@ -127,8 +136,114 @@ fn show_command(args: &ShowArgs, ctx: &CliContext) -> Result<()> {
     Ok(())
 }
 
+fn rotate_password(args: &RotatePasswordArgs, ctx: &CliContext) -> Result<()> {
+    let mut store = open_profile_store(ctx)?;
+    if !store.path().exists() {
+        println!(
+            "No profile store at {}; nothing to rotate.",
+            store.path().display()
+        );
+        return Ok(());
+    }
+
+    let new_password = if args.auto {
+        None
+    } else if let Some(var) = &args.new_password_env {
+        let value = std::env::var(var)
+            .ok()
+            .filter(|value| !value.is_empty())
+            .ok_or_else(|| Error::InvalidArgument {
+                message: format!("environment variable '{var}' is not set or empty"),
+            })?;
+        Some(SecretString::new(value.into()))
+    } else {
+        ctx.ensure_interactive("rotate master password")?;
+        let prompt = *ctx.prompt();
+        let read = |label: &str| {
+            task::block_in_place(|| Handle::current().block_on(prompt.input(label, true)))
+        };
+        let first = read("New master password")?;
+        if first.is_empty() {
+            return Err(Error::InvalidArgument {
+                message: "New password cannot be empty; use --auto for the auto-derived password."
+                    .into(),
+            });
+        }
+        if read("Confirm new master password")? != first {
+            return Err(Error::InvalidArgument {
+                message: "Passwords do not match.".into(),
+            });
+        }
+        Some(SecretString::new(first.into()))
+    };
+
+    let uses_auto = new_password.is_none();
+    store.rekey(new_password)?;
+    println!(
+        "✅ Profile store re-encrypted: {} (previous files kept as .bak)",
+        store.path().display()
+    );
+    if uses_auto {
+        println!(
+            "Profiles now use the auto-derived password; drop --master-password / STORIFY_PROFILE_PASS."
+        );
+    } else {
+        println!(
+            "Pass the new password via --master-password or STORIFY_PROFILE_PASS from now on."
+        );
+    }
+    Ok(())
+}
+
+fn test_command(args: &TestArgs, ctx: &CliContext) -> Result<()> {
+    let (config, target) = match &args.profile {
+        Some(name) => {
+            let store = open_profile_store(ctx)?;
+            let config = store.get_profile(name)?.into_config()?;
+            (config, format!("profile '{}'", name))
+        }
+        None => {
+            let config = ctx.storage_config()?.clone();
+            let target = build_source_hint(ctx.resolved().source, ctx.resolved())
+                .unwrap_or_else(|| "active configuration".to_string());
+            (config, target)
+        }
+    };
+
+    println!("Testing {} ({})", target, config.provider.as_str());
+    let report = task::block_in_place(|| {
+        Handle::current().block_on(async {
+            let client = StorageClient::new(config).await?;
+            Ok::<_, Error>(client.health_check().await)
+        })
+    })?;
+
+    println!(
+        "Backend: {} (name: {}, root: {})",
+        report.scheme, report.name, report.root
+    );
+    for check in &report.checks {
+        let millis = check.elapsed.as_millis();
+        match &check.error {
+            None => println!("  [ok]   {} ({} ms)", check.name, millis),
+            Some(err) => println!("  [fail] {} ({} ms): {}", check.name, millis, err),
+        }
+    }
+
+    match report.diagnosis {
+        None => {
+            println!("Connection OK");
+            Ok(())
+        }
+        Some(diagnosis) => Err(Error::HealthCheckFailed {
+            target,
+            diagnosis: diagnosis.to_string(),
+        }),
+    }
+}
+
 fn build_source_hint(source: Option<ConfigSource>, resolved: &ResolvedConfig) -> Option<String> {
-    match source {
+    let hint = match source {
         Some(ConfigSource::ExplicitProfile) => {
             let profile = resolved.profile.as_deref().unwrap_or("unknown");
             Some(format!("--profile '{}'", profile))
@@ -145,7 +260,15 @@ fn build_source_hint(source: Option<ConfigSource>, resolved: &ResolvedConfig) ->
             }
             Some(hint)
         }
+        Some(ConfigSource::CommandLine) => Some("--provider".to_string()),
+        Some(ConfigSource::Recording) => Some("--replay recording".to_string()),
+        Some(ConfigSource::Anonymous) => Some("--anonymous".to_string()),
         None => None,
+    };
+    if resolved.overridden {
+        hint.map(|hint| format!("{hint} with --bucket/--endpoint overrides"))
+    } else {
+        hint
     }
 }
 
@@ -211,32 +334,84 @@ fn create_profile(args: &CreateArgs, ctx: &CliContext) -> Result<()> {
     let provider_input = match &args.provider {
         Some(provider) => provider.clone(),
         None => {
-            println!("Select a storage provider (oss, s3, minio, cos, fs, hdfs).");
+            println!(
+                "Select a storage provider (oss, s3, minio, r2, b2, cos, fs, hdfs, azblob, gcs)."
+            );
             session.input_required(ctx, "Storage provider", false)?
         }
     };
     let provider = StorageProvider::from_str(&provider_input)?;
+    let compat = match (
+        args.compat,
+        CompatPreset::from_provider_alias(&provider_input),
+    ) {
+        (Some(flag), Some(alias)) if flag != alias => {
+            return Err(Error::InvalidArgument {
+                message: format!(
+                    "--compat {} conflicts with provider '{provider_input}'",
+                    flag.as_str()
+                ),
+            });
+        }
+        (flag, alias) => flag.or(alias),
+    };
     print_provider_help(provider, provider_spec(provider));
+    print_compat_help(compat);
 
     let mut bucket = args.bucket.clone();
     let mut root_path = args.root_path.clone();
     let mut name_node = args.name_node.clone();
+    let mut credential_path = args.credential_path.clone();
+    let mut sas_token = args.sas_token.clone();
+    let mut connection_string = args.connection_string.clone();
+    let mut aws_profile = args.aws_profile.clone();
+    let mut use_instance_metadata = args.use_instance_metadata;
     let mut access_key_id = args.access_key_id.clone();
     let mut access_key_secret = args.access_key_secret.clone();
+    let mut session_token = args.session_token.clone();
     let mut endpoint = args.endpoint.clone();
     let mut region = args.region.clone();
+    let mut account_id = args.account_id.clone();
+    let mut manifest = args.manifest.clone();
+    let mut request_payer = args.request_payer;
+    let mut headers = args.headers.clone();
 
     match provider {
-        StorageProvider::Oss
-        | StorageProvider::S3
-        | StorageProvider::Cos
-        | StorageProvider::Azblob => {
+        StorageProvider::Oss | StorageProvider::S3 | StorageProvider::Cos => {
             if bucket.is_none() {
                 println!("Bucket name (required).");
                 bucket = Some(session.input_required(ctx, "Bucket", false)?);
             }
 
-            if access_key_id.is_none() {
+            if provider == StorageProvider::S3
+                && !args.anonymous
+                && access_key_id.is_none()
+                && aws_profile.is_none()
+                && !use_instance_metadata
+            {
+                println!("Credential mode: keys, profile or instance-metadata (default: keys).");
+                let mode = session
+                    .input_optional(ctx, "Credential mode", false)?
+                    .unwrap_or_else(|| "keys".to_string());
+                match mode.to_ascii_lowercase().as_str() {
+                    "keys" => {}
+                    "profile" => {
+                        println!("AWS profile name from ~/.aws/credentials (required).");
+                        aws_profile = Some(session.input_required(ctx, "AWS profile", false)?);
+                    }
+                    "instance-metadata" => use_instance_metadata = true,
+                    other => {
+                        return Err(Error::InvalidArgument {
+                            message: format!(
+                                "unknown credential mode '{other}' (expected keys, profile or instance-metadata)"
+                            ),
+                        });
+                    }
+                }
+            }
+            let uses_keys = aws_profile.is_none() && !use_instance_metadata;
+
+            if access_key_id.is_none() && uses_keys {
                 if provider == StorageProvider::Cos {
                     println!("Secret ID (required for COS).");
                     access_key_id = Some(session.input_required(ctx, "Secret ID", false)?);
@@ -246,7 +421,7 @@ fn create_profile(args: &CreateArgs, ctx: &CliContext) -> Result<()> {
                 }
             }
 
-            if access_key_secret.is_none() {
+            if access_key_secret.is_none() && uses_keys {
                 if provider == StorageProvider::Cos {
                     println!("Secret key (required for COS).");
                     access_key_secret = Some(session.input_required(ctx, "Secret key", false)?);
@@ -256,14 +431,66 @@ fn create_profile(args: &CreateArgs, ctx: &CliContext) -> Result<()> {
                 }
             }
 
-            if endpoint.is_none() {
-                println!("Endpoint URL (leave blank for provider default).");
-                endpoint = session.input_optional(ctx, "Endpoint", false)?;
+            if session_token.is_none()
+                && provider != StorageProvider::Cos
+                && access_key_id.is_some()
+                && session.used
+            {
+                println!(
+                    "Session token (only for temporary STS credentials; leave blank otherwise)."
+                );
+                session_token = session.input_optional(ctx, "Session token", true)?;
+            }
+
+            match compat {
+                Some(CompatPreset::R2) => {
+                    if endpoint.is_none() && account_id.is_none() {
+                        println!(
+                            "Cloudflare account ID (required; the endpoint is derived from it)."
+                        );
+                        account_id = Some(session.input_required(ctx, "Account ID", false)?);
+                    }
+                }
+                Some(CompatPreset::B2) => {
+                    if region.is_none() && endpoint.is_none() {
+                        println!(
+                            "B2 region from the bucket's endpoint, e.g. us-west-004 (required)."
+                        );
+                        region = Some(session.input_required(ctx, "Region", false)?);
+                    }
+                }
+                _ => {
+                    if endpoint.is_none() {
+                        println!("Endpoint URL (leave blank for provider default).");
+                        endpoint = session.input_optional(ctx, "Endpoint", false)?;
+                    }
+
+                    if region.is_none() && provider != StorageProvider::Cos {
+                        println!("Region (leave blank for provider default).");
+                        region = session.input_optional(ctx, "Region", false)?;
+                    }
+                }
             }
 
-            if region.is_none() && provider != StorageProvider::Cos {
-                println!("Region (leave blank for provider default).");
-                region = session.input_optional(ctx, "Region", false)?;
+            if provider == StorageProvider::S3 && !request_payer && session.used {
+                request_payer = session.confirm(
+                    ctx,
+                    "Bill requests to the requester (requester-pays bucket)?",
+                    false,
+                )?;
+            }
+
+            if headers.is_empty() && session.used {
+                println!(
+                    "Extra request headers as 'Name: value', separated by ';' (leave blank for none)."
+                );
+                if let Some(line) = session.input_optional(ctx, "Headers", false)? {
+                    headers = line
+                        .split(';')
+                        .filter(|header| !header.trim().is_empty())
+                        .map(parse_header)
+                        .collect::<Result<_>>()?;
+                }
             }
         }
         StorageProvider::Fs => {
@@ -282,6 +509,92 @@ fn create_profile(args: &CreateArgs, ctx: &CliContext) -> Result<()> {
                 root_path = session.input_optional(ctx, "Root path", false)?;
             }
         }
+        StorageProvider::Azblob => {
+            if bucket.is_none() {
+                println!("Container name (required).");
+                bucket = Some(session.input_required(ctx, "Container", false)?);
+            }
+
+            let has_credentials =
+                access_key_secret.is_some() || sas_token.is_some() || connection_string.is_some();
+            if !has_credentials {
+                println!("Credential mode: key, sas or connection-string (default: key).");
+                let mode = session
+                    .input_optional(ctx, "Credential mode", false)?
+                    .unwrap_or_else(|| "key".to_string());
+                match mode.to_ascii_lowercase().as_str() {
+                    "key" => {
+                        if access_key_id.is_none() {
+                            println!("Storage account name (required).");
+                            access_key_id =
+                                Some(session.input_required(ctx, "Account name", false)?);
+                        }
+                        println!("Account key (required).");
+                        access_key_secret =
+                            Some(session.input_required(ctx, "Account key", true)?);
+                    }
+                    "sas" => {
+                        if access_key_id.is_none() {
+                            println!(
+                                "Storage account name (leave blank if the endpoint includes it)."
+                            );
+                            access_key_id = session.input_optional(ctx, "Account name", false)?;
+                        }
+                        println!("SAS token (required).");
+                        sas_token = Some(session.input_required(ctx, "SAS token", true)?);
+                    }
+                    "connection-string" => {
+                        println!("Connection string (required).");
+                        connection_string =
+                            Some(session.input_required(ctx, "Connection string", true)?);
+                    }
+                    other => {
+                        return Err(Error::InvalidArgument {
+                            message: format!(
+                                "unknown credential mode '{other}' (expected key, sas or connection-string)"
+                            ),
+                        });
+                    }
+                }
+            }
+
+            if endpoint.is_none() && connection_string.is_none() {
+                println!("Endpoint URL (e.g. https://<account>.blob.core.windows.net).");
+                endpoint = session.input_optional(ctx, "Endpoint", false)?;
+            }
+        }
+        StorageProvider::Gcs => {
+            if bucket.is_none() {
+                println!("Bucket name (required).");
+                bucket = Some(session.input_required(ctx, "Bucket", false)?);
+            }
+            if credential_path.is_none() {
+                println!(
+                    "Service account JSON path (leave blank to use GOOGLE_APPLICATION_CREDENTIALS or VM metadata)."
+                );
+                credential_path = session.input_optional(ctx, "Credential path", false)?;
+            }
+            if endpoint.is_none() {
+                println!("Endpoint URL (leave blank for provider default).");
+                endpoint = session.input_optional(ctx, "Endpoint", false)?;
+            }
+        }
+        StorageProvider::Memory => {
+            if bucket.is_none() {
+                println!("Store name (leave blank for 'default'); same-named stores share data.");
+                bucket = session.input_optional(ctx, "Name", false)?;
+            }
+        }
+        StorageProvider::Http => {
+            if endpoint.is_none() {
+                println!("Base URL (required), e.g. https://example.com/datasets.");
+                endpoint = Some(session.input_required(ctx, "Endpoint", false)?);
+            }
+            if manifest.is_none() {
+                println!("Manifest file listing the keys (leave blank to parse index pages).");
+                manifest = session.input_optional(ctx, "Manifest", false)?;
+            }
+        }
     }
 
     let mut config = match provider {
@@ -291,21 +604,59 @@ fn create_profile(args: &CreateArgs, ctx: &CliContext) -> Result<()> {
         StorageProvider::Fs => StorageConfig::fs(root_path.clone()),
         StorageProvider::Hdfs => StorageConfig::hdfs(name_node.clone(), root_path.clone()),
         StorageProvider::Azblob => StorageConfig::azblob(bucket.expect("bucket required")),
+        StorageProvider::Gcs => StorageConfig::gcs(bucket.expect("bucket required")),
+        StorageProvider::Http => StorageConfig::http(endpoint.clone()),
+        StorageProvider::Memory => {
+            StorageConfig::memory(bucket.unwrap_or_else(|| "default".to_string()))
+        }
     };
 
     config.access_key_id = access_key_id;
     config.access_key_secret = access_key_secret;
+    config.session_token = session_token;
     config.endpoint = endpoint;
     config.region = region;
     config.root_path = root_path;
     config.name_node = name_node;
+    config.credential_path = credential_path;
+    config.sas_token = sas_token;
+    config.connection_string = connection_string;
+    config.role_arn = args.role_arn.clone();
+    config.aws_profile = aws_profile;
+    config.use_instance_metadata = use_instance_metadata;
+    config.compat = compat.map(|preset| preset.as_str().to_string());
+    config.account_id = account_id;
+    config.manifest = manifest;
+    config.retries = args.retries;
+    config.retry_backoff_ms = args.retry_backoff_ms;
+    config.rate_limit = args.rate_limit;
+    config.timeout_secs = args.timeout_secs;
+    config.connect_timeout_secs = args.connect_timeout_secs;
+    config.pool_size = args.pool_size;
+    config.request_payer = request_payer;
+    config.headers = headers;
+    config.trash = args.trash;
+    config.default_jobs = args.default_jobs;
+    config.human_readable = args.human_readable;
+    config.aliases = args
+        .aliases
+        .iter()
+        .filter(|(_, command)| !command.is_empty())
+        .cloned()
+        .collect();
+    config.data_key = if args.generate_data_key {
+        Some(DataKey::generate().encode())
+    } else {
+        args.data_key.clone()
+    };
+    config.fallback_endpoints = args.fallback_endpoints.clone();
     config.anonymous = args.anonymous;
 
     prepare_storage_config(&mut config)?;
 
     let stored = StoredProfile::from_config(&config);
     if temp_mode {
-        let ttl = parse_ttl(&args.ttl)?;
+        let ttl = parse_duration(&args.ttl)?;
         store.set_temp_profile(stored, ttl)?;
         println!("Temporary config cache saved to {}", store.path().display());
         println!("Name hint: {}", name);
@@ -324,25 +675,41 @@ fn create_profile(args: &CreateArgs, ctx: &CliContext) -> Result<()> {
             println!("'{}' marked as default.", name);
         }
     }
+    if args.generate_data_key {
+        print_data_key_warning((!temp_mode).then_some(name.as_str()));
+    }
     Ok(())
 }
 
-fn parse_ttl(input: &str) -> Result<std::time::Duration> {
+/// Remind that objects written with `put --encrypt` are lost along with the data key.
+fn print_data_key_warning(profile: Option<&str>) {
+    let show = match profile {
+        Some(name) => format!("storify config show --profile {name} --show-secrets"),
+        None => "storify config show --show-secrets".to_string(),
+    };
+    println!(
+        "Generated a data key for `put --encrypt`. Objects encrypted with it cannot be read \
+         without it: back it up (`{show}`)."
+    );
+}
+
+/// Parse `90`, `30s`, `15m`, `12h` or `7d`.
+pub(super) fn parse_duration(input: &str) -> Result<std::time::Duration> {
     let s = input.trim();
     if s.is_empty() {
         return Err(Error::InvalidArgument {
-            message: "ttl cannot be empty".to_string(),
+            message: "duration cannot be empty".to_string(),
         });
     }
     if s.chars().all(|c| c.is_ascii_digit()) {
         let secs: u64 = s.parse().map_err(|_| Error::InvalidArgument {
-            message: format!("invalid ttl: {s}"),
+            message: format!("invalid duration: {s}"),
         })?;
         return Ok(std::time::Duration::from_secs(secs.max(1)));
     }
     let (num, unit) = s.split_at(s.len().saturating_sub(1));
     let n: u64 = num.parse().map_err(|_| Error::InvalidArgument {
-        message: format!("invalid ttl: {s}"),
+        message: format!("invalid duration: {s}"),
     })?;
     let secs = match unit {
         "s" => n,
@@ -351,7 +718,7 @@ fn parse_ttl(input: &str) -> Result<std::time::Duration> {
         "d" => n.saturating_mul(24 * 60 * 60),
         _ => {
             return Err(Error::InvalidArgument {
-                message: format!("invalid ttl unit: {unit} (expected s|m|h|d)"),
+                message: format!("invalid duration unit: {unit} (expected s|m|h|d)"),
             });
         }
     };
@@ -437,6 +804,119 @@ fn print_provider_help(provider: StorageProvider, spec: ProviderSpec) {
             println!("  - {}: {}", info.name, label);
         }
     }
+    if spec.allows_instance_metadata() {
+        println!("  - use_instance_metadata: optional");
+    }
+}
+
+fn print_compat_help(compat: Option<CompatPreset>) {
+    match compat {
+        Some(CompatPreset::R2) => println!(
+            "Cloudflare R2: use an R2 API token's key pair; the endpoint is derived from the account ID and region is always 'auto'."
+        ),
+        Some(CompatPreset::B2) => println!(
+            "Backblaze B2: use an application key pair; the endpoint is derived from the region (e.g. us-west-004)."
+        ),
+        Some(preset) => println!("Compatibility preset: {}", preset.as_str()),
+        None => {}
+    }
+}
+
+fn edit_profile(args: &EditArgs, ctx: &CliContext) -> Result<()> {
+    let mut store = open_profile_store(ctx)?;
+    let current = store.get_profile(&args.name)?;
+
+    let mut changes = args.changes();
+    if changes.is_empty() {
+        changes = prompt_profile_changes(&args.name, &current, ctx)?;
+        if changes.is_empty() {
+            println!("No changes.");
+            return Ok(());
+        }
+    }
+    for (i, (field, _)) in changes.iter().enumerate() {
+        if changes[..i].iter().any(|(other, _)| other == field) {
+            return Err(Error::InvalidArgument {
+                message: format!("field '{}' is both set and unset", field.name()),
+            });
+        }
+    }
+
+    store.update_profile(&args.name, &changes)?;
+    let names: Vec<&str> = changes.iter().map(|(field, _)| field.name()).collect();
+    println!("✅ Profile '{}' updated ({}).", args.name, names.join(", "));
+    if args.generate_data_key {
+        print_data_key_warning(Some(&args.name));
+    }
+    Ok(())
+}
+
+/// Walk the provider's fields with current values pre-filled and collect what changed.
+fn prompt_profile_changes(
+    name: &str,
+    current: &StoredProfile,
+    ctx: &CliContext,
+) -> Result<Vec<(ProfileField, Option<String>)>> {
+    ctx.ensure_interactive("edit profile")?;
+    let provider = StorageProvider::from_str(&current.provider)?;
+    let spec = provider_spec(provider);
+    let prompt = *ctx.prompt();
+    println!(
+        "Editing profile '{}' ({}). Edit a value in place or clear it to unset; leave secrets blank to keep them.",
+        name,
+        provider.as_str()
+    );
+
+    let mut fields = Vec::new();
+    if !matches!(provider, StorageProvider::Fs | StorageProvider::Hdfs) {
+        fields.push(ProfileField::Bucket);
+    }
+    fields.extend(
+        spec.field_matrix()
+            .into_iter()
+            .filter(|info| info.rule.requirement() != Requirement::Unsupported)
+            .filter_map(|info| ProfileField::from_name(info.name)),
+    );
+    if let Some(index) = fields.iter().position(|f| *f == ProfileField::Endpoint) {
+        fields.insert(index + 1, ProfileField::FallbackEndpoints);
+    }
+
+    let mut changes = Vec::new();
+    for field in fields {
+        let current_value = current.field(field);
+        let new_value = if field.is_secret() {
+            let label = if current_value.is_some() {
+                format!("{} (set)", field.name())
+            } else {
+                field.name().to_string()
+            };
+            let input =
+                task::block_in_place(|| Handle::current().block_on(prompt.input(&label, true)))?;
+            match input.trim() {
+                "" => continue,
+                value => Some(value.to_string()),
+            }
+        } else {
+            let input = task::block_in_place(|| {
+                Handle::current().block_on(prompt.edit(field.name(), current_value.as_deref()))
+            })?;
+            Some(input.trim().to_string()).filter(|value| !value.is_empty())
+        };
+        if new_value != current_value {
+            changes.push((field, new_value));
+        }
+    }
+
+    if spec.allows_instance_metadata() {
+        let enabled = task::block_in_place(|| {
+            Handle::current()
+                .block_on(prompt.confirm("use_instance_metadata", current.use_instance_metadata))
+        })?;
+        if enabled != current.use_instance_metadata {
+            changes.push((ProfileField::UseInstanceMetadata, Some(enabled.to_string())));
+        }
+    }
+    Ok(changes)
 }
 
 fn set_default_profile(args: &SetArgs, ctx: &CliContext) -> Result<()> {
@@ -530,6 +1010,14 @@ fn print_config(config: &StorageConfig, indent: &str, credential_mode: Credentia
         println!("{}endpoint: {}", indent, endpoint);
     }
 
+    if !config.fallback_endpoints.is_empty() {
+        println!(
+            "{}fallback_endpoints: {}",
+            indent,
+            config.fallback_endpoints.join(", ")
+        );
+    }
+
     if let Some(region) = config.region.as_deref() {
         println!("{}region: {}", indent, region);
     }
@@ -542,6 +1030,77 @@ fn print_config(config: &StorageConfig, indent: &str, credential_mode: Credentia
         println!("{}name_node: {}", indent, name_node);
     }
 
+    if let Some(credential_path) = config.credential_path.as_deref() {
+        println!("{}credential_path: {}", indent, credential_path);
+    }
+
+    if let Some(role_arn) = config.role_arn.as_deref() {
+        println!("{}role_arn: {}", indent, role_arn);
+    }
+
+    if let Some(aws_profile) = config.aws_profile.as_deref() {
+        println!("{}aws_profile: {}", indent, aws_profile);
+    }
+
+    if config.use_instance_metadata {
+        println!("{}use_instance_metadata: true", indent);
+    }
+
+    if let Some(compat) = config.compat.as_deref() {
+        println!("{}compat: {}", indent, compat);
+    }
+
+    if let Some(account_id) = config.account_id.as_deref() {
+        println!("{}account_id: {}", indent, account_id);
+    }
+    if let Some(manifest) = config.manifest.as_deref() {
+        println!("{}manifest: {}", indent, manifest);
+    }
+
+    if let Some(retries) = config.retries {
+        println!("{}retries: {}", indent, retries);
+    }
+
+    if let Some(backoff) = config.retry_backoff_ms {
+        println!("{}retry_backoff_ms: {}", indent, backoff);
+    }
+
+    if let Some(rate_limit) = config.rate_limit {
+        println!("{}rate_limit: {}", indent, rate_limit);
+    }
+
+    if let Some(timeout) = config.timeout_secs {
+        println!("{}timeout_secs: {}", indent, timeout);
+    }
+
+    if let Some(timeout) = config.connect_timeout_secs {
+        println!("{}connect_timeout_secs: {}", indent, timeout);
+    }
+
+    if let Some(pool_size) = config.pool_size {
+        println!("{}pool_size: {}", indent, pool_size);
+    }
+
+    if config.request_payer {
+        println!("{}request_payer: true", indent);
+    }
+
+    if config.trash {
+        println!("{}trash: true", indent);
+    }
+
+    if let Some(jobs) = config.default_jobs {
+        println!("{}default_jobs: {}", indent, jobs);
+    }
+
+    if config.human_readable {
+        println!("{}human_readable: true", indent);
+    }
+
+    for (name, command) in &config.aliases {
+        println!("{}alias: {} = {}", indent, name, command);
+    }
+
     // Credentials and anonymous mode
     if config.anonymous {
         println!("{}anonymous: true", indent);
@@ -566,6 +1125,26 @@ fn print_credentials(config: &StorageConfig, indent: &str, mode: CredentialMode)
             if config.access_key_secret.is_some() {
                 println!("{}access_key_secret: ****", indent);
             }
+
+            if config.session_token.is_some() {
+                println!("{}session_token: ****", indent);
+            }
+
+            if config.sas_token.is_some() {
+                println!("{}sas_token: ****", indent);
+            }
+
+            if config.connection_string.is_some() {
+                println!("{}connection_string: ****", indent);
+            }
+
+            if config.data_key.is_some() {
+                println!("{}data_key: ****", indent);
+            }
+
+            for (name, _) in &config.headers {
+                println!("{}header: {}: ****", indent, name);
+            }
         }
         CredentialMode::PlainText => {
             // Show credentials in plaintext
@@ -576,10 +1155,49 @@ fn print_credentials(config: &StorageConfig, indent: &str, mode: CredentialMode)
             if let Some(secret_key) = config.access_key_secret.as_deref() {
                 println!("{}access_key_secret: {}", indent, secret_key);
             }
+
+            if let Some(session_token) = config.session_token.as_deref() {
+                println!("{}session_token: {}", indent, session_token);
+            }
+
+            if let Some(sas_token) = config.sas_token.as_deref() {
+                println!("{}sas_token: {}", indent, sas_token);
+            }
+
+            if let Some(connection_string) = config.connection_string.as_deref() {
+                println!("{}connection_string: {}", indent, connection_string);
+            }
+
+            if let Some(data_key) = config.data_key.as_deref() {
+                println!("{}data_key: {}", indent, data_key);
+            }
+
+            for (name, value) in &config.headers {
+                println!("{}header: {}: {}", indent, name, value);
+            }
         }
     }
 }
 
+/// `storify env`: print the active configuration as variables other tools understand.
+pub fn print_env(args: &EnvArgs, ctx: &CliContext) -> Result<()> {
+    let config = ctx.storage_config()?;
+    let (vars, omitted): (Vec<_>, Vec<_>) = env_vars(config)
+        .into_iter()
+        .partition(|var| args.show_secrets || !var.secret);
+    for var in &vars {
+        println!("{}", args.format.render(var));
+    }
+    if !omitted.is_empty() {
+        let keys: Vec<&str> = omitted.iter().map(|var| var.key.as_str()).collect();
+        eprintln!(
+            "note: omitted {} (use --show-secrets to include them)",
+            keys.join(", ")
+        );
+    }
+    Ok(())
+}
+
 /// Mask a secret string by showing first 4 characters followed by ***
 fn mask_secret(secret: &str) -> String {
     if secret.len() <= 4 {
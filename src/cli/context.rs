@@ -1,8 +1,11 @@
 use std::env;
 
 use crate::config::StorageConfig;
-use crate::config::loader::{ConfigRequest, ResolvedConfig, resolve};
+use crate::config::loader::{
+    ConfigOverrides, ConfigRequest, ConfigSource, ResolvedConfig, resolve,
+};
 use crate::error::{Error, Result};
+use crate::storage::recording::Replayer;
 use secrecy::SecretString;
 
 use super::entry::{Args, Command, GlobalOptions};
@@ -13,32 +16,58 @@ pub struct CliContext {
     command: Command,
     resolved: ResolvedConfig,
     prompt: Prompt,
+    replayer: Option<Replayer>,
 }
 
 impl CliContext {
     pub async fn from_args(args: Args, prompt: Prompt) -> Result<Self> {
-        let require_storage = !matches!(args.command, Command::Config(_));
-        let non_interactive = args.global.non_interactive;
-        let master_password = Self::resolve_master_password(&args.global);
-
-        let request = ConfigRequest {
-            profile: args.global.profile.clone(),
-            profile_store_path: args.global.profile_store.clone(),
-            non_interactive,
-            require_storage,
-            master_password,
+        let request = Self::config_request(&args.global, args.command.requires_storage());
+
+        // A replay takes its configuration from the recording, never from profiles.
+        let (resolved, replayer) = match args.global.replay.as_deref() {
+            Some(path) => {
+                let replayer = Replayer::open(path)?;
+                let resolved = ResolvedConfig {
+                    storage: Some(replayer.storage_config()?),
+                    source: Some(ConfigSource::Recording),
+                    ..ResolvedConfig::default()
+                };
+                (resolved, Some(replayer))
+            }
+            None => (resolve(request)?, None),
         };
 
-        let resolved = resolve(request)?;
-
         Ok(Self {
             options: args.global,
             command: args.command,
             resolved,
             prompt,
+            replayer,
         })
     }
 
+    /// The same context running `command` instead, e.g. the expansion of an alias.
+    pub fn with_command(self, command: Command) -> Self {
+        Self { command, ..self }
+    }
+
+    /// How the global options select the configuration to resolve.
+    fn config_request(options: &GlobalOptions, require_storage: bool) -> ConfigRequest {
+        ConfigRequest {
+            profile: options.profile.clone(),
+            profile_store_path: options.profile_store.clone(),
+            non_interactive: options.non_interactive,
+            require_storage,
+            master_password: Self::resolve_master_password(options),
+            overrides: ConfigOverrides {
+                provider: options.provider.clone(),
+                bucket: options.bucket.clone(),
+                endpoint: options.endpoint.clone(),
+            },
+            anonymous: options.anonymous,
+        }
+    }
+
     pub fn command(&self) -> &Command {
         &self.command
     }
@@ -71,6 +100,11 @@ impl CliContext {
         &self.options
     }
 
+    /// Recording that answers storage requests under `--replay`.
+    pub fn replayer(&self) -> Option<&Replayer> {
+        self.replayer.as_ref()
+    }
+
     pub fn storage_config(&self) -> Result<&StorageConfig> {
         self.resolved.storage.as_ref().ok_or_else(|| {
             let profiles = if self.resolved.available_profiles.is_empty() {
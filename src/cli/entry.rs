@@ -1,15 +1,31 @@
 use clap::{ArgGroup, Args as ClapArgs, Parser, Subcommand};
+use std::ffi::OsString;
 
-use crate::error::Result;
+use crate::config::crypto::DataKey;
+use crate::config::storage_config::{header_lines, parse_header};
+use crate::config::{CompatPreset, EnvFormat, ProfileField};
+use crate::error::{ErrorFormat, Result};
+use crate::logging::{self, LogFormat};
 
 use super::{
+    alias::{self, parse_alias_arg},
+    batch::BatchArgs,
     config,
     context::CliContext,
+    jobs,
     prompts::Prompt,
+    schema,
     storage::{
-        self, CatArgs, CpArgs, DiffArgs, DuArgs, GetArgs, GrepArgs, HeadArgs, LsArgs, MkdirArgs,
-        MvArgs, PutArgs, RmArgs, StatArgs, TailArgs, TouchArgs, TreeArgs,
+        self, AclArgs, AppendArgs, ApplyArgs, BucketArgs, CatArgs, ChclassArgs, ChmodArgs,
+        ConcatArgs, CpArgs, DiffArgs, DuArgs, GcArgs, GetArgs, GrepArgs, HeadArgs, IndexArgs,
+        InventoryArgs, LifecycleArgs, LnArgs, LsArgs, MkdirArgs, MpuArgs, MvArgs, PutArgs,
+        RenameArgs, RestoreArgs, RmArgs, ServeArgs, StatArgs, TagArgs, TailArgs, TouchArgs,
+        TrashArgs, TreeArgs, VerifyArgs, VersionsArgs,
     },
+    sync::SyncArgs,
+    undo::UndoArgs,
+    update,
+    watch::WatchArgs,
 };
 
 #[derive(Parser, Debug, Clone)]
@@ -32,6 +48,31 @@ pub struct GlobalOptions {
     /// Disable interactive prompts (fail fast when input is required)
     #[arg(long)]
     pub non_interactive: bool,
+
+    /// Log more detail to stderr: -v operations and failed requests, -vv every request,
+    /// -vvv everything (RUST_LOG overrides)
+    #[arg(short = 'v', long = "verbose", action = clap::ArgAction::Count)]
+    pub verbose: u8,
+    /// Layout of log lines
+    #[arg(
+        long = "log-format",
+        value_name = "FORMAT",
+        value_enum,
+        default_value_t
+    )]
+    pub log_format: LogFormat,
+    /// Append log lines to PATH instead of stderr
+    #[arg(long = "log-file", value_name = "PATH")]
+    pub log_file: Option<std::path::PathBuf>,
+    /// Layout of the error printed to stderr when the command fails
+    #[arg(
+        long = "error-format",
+        value_name = "FORMAT",
+        value_enum,
+        default_value_t
+    )]
+    pub error_format: ErrorFormat,
+
     /// Preferred profile name when resolving configuration
     #[arg(short = 'p', long = "profile", value_name = "NAME")]
     pub profile: Option<String>,
@@ -49,6 +90,103 @@ pub struct GlobalOptions {
         default_value = "STORIFY_PROFILE_PASS"
     )]
     pub profile_pass_env: String,
+
+    /// Use this provider for one command instead of a profile (credentials from environment)
+    #[arg(long = "provider", value_name = "PROVIDER", conflicts_with = "profile")]
+    pub provider: Option<String>,
+    /// Override the bucket/container of the resolved config
+    #[arg(long = "bucket", value_name = "NAME")]
+    pub bucket: Option<String>,
+    /// Override the endpoint of the resolved config
+    #[arg(long = "endpoint", value_name = "URL")]
+    pub endpoint: Option<String>,
+    /// Browse a public bucket with unsigned requests, without profiles or credentials
+    /// (needs a bucket URI or --bucket)
+    #[arg(long = "anonymous", conflicts_with_all = ["profile", "replay"])]
+    pub anonymous: bool,
+
+    /// Cap on concurrent storage requests and parallel tasks, shared by everything this
+    /// command runs (transfers, stats, job steps)
+    #[arg(
+        long = "max-concurrency",
+        value_name = "N",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    pub max_concurrency: Option<usize>,
+
+    /// Entries per listing request for ls, du, find and index (tuned from page latency
+    /// when omitted)
+    #[arg(
+        long = "page-size",
+        value_name = "N",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    pub page_size: Option<usize>,
+
+    /// Retries of transiently failed storage requests (default 3, or the profile's
+    /// `retries`; 0 disables them)
+    #[arg(long = "retries", value_name = "N")]
+    pub retries: Option<usize>,
+    /// Delay before the first retry in milliseconds, doubled for each further one
+    /// (default 200)
+    #[arg(
+        long = "retry-backoff",
+        value_name = "MS",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub retry_backoff: Option<u64>,
+    /// Send at most N storage requests per second
+    #[arg(
+        long = "rate-limit",
+        value_name = "N",
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    pub rate_limit: Option<u32>,
+    /// Fail a storage request, or a single read or write of a transfer, that takes longer
+    /// than SECS
+    #[arg(
+        long = "timeout",
+        value_name = "SECS",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub timeout: Option<u64>,
+    /// Give up connecting to the storage endpoint after SECS
+    #[arg(
+        long = "connect-timeout",
+        value_name = "SECS",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub connect_timeout: Option<u64>,
+    /// Idle HTTP connections kept open per host (0 opens a new one for every request)
+    #[arg(long = "pool-size", value_name = "N")]
+    pub pool_size: Option<usize>,
+    /// Bill S3 requests to the requester instead of the bucket owner (requester-pays
+    /// buckets)
+    #[arg(long = "request-payer")]
+    pub request_payer: bool,
+    /// Send an extra header with every storage request (repeatable; replaces a profile
+    /// header of the same name)
+    #[arg(long = "header", value_name = "NAME:VALUE", value_parser = parse_header)]
+    pub headers: Vec<(String, String)>,
+
+    /// Print bytes transferred, requests, retries and elapsed time to stderr when a storage
+    /// command finishes
+    #[arg(long = "stats")]
+    pub stats: bool,
+    /// Write run outcome, bytes, duration and failures here in Prometheus textfile format
+    #[arg(long = "metrics-textfile", value_name = "PATH")]
+    pub metrics_textfile: Option<std::path::PathBuf>,
+
+    /// Record the storage HTTP traffic of this command (credentials redacted) as JSON Lines
+    #[arg(long = "record", value_name = "PATH", conflicts_with = "replay")]
+    pub record: Option<std::path::PathBuf>,
+    /// Answer storage requests from a file written by --record instead of the network
+    #[arg(
+        long = "replay",
+        value_name = "PATH",
+        conflicts_with_all = ["profile", "provider", "bucket", "endpoint"]
+    )]
+    pub replay: Option<std::path::PathBuf>,
 }
 
 #[derive(Subcommand, Debug, Clone)]
@@ -56,6 +194,8 @@ pub enum Command {
     /// Inspect Storify configuration
     #[command(subcommand)]
     Config(ConfigCommand),
+    /// Print the active configuration as environment variables for other tools
+    Env(EnvArgs),
 
     /// List directory contents
     Ls(LsArgs),
@@ -71,8 +211,15 @@ pub enum Command {
     Cp(CpArgs),
     /// Move files/directories from remote to remote
     Mv(MvArgs),
+    /// Rename the files a glob matches by replacing text in their names
+    Rename(RenameArgs),
     /// Create directories in remote storage
     Mkdir(MkdirArgs),
+    /// Set POSIX permission bits (fs only; other backends warn and change nothing)
+    Chmod(ChmodArgs),
+    /// Change the owner and group of HDFS files
+    #[cfg(feature = "hdfs")]
+    Chown(storage::ChownArgs),
     /// Display object metadata
     Stat(StatArgs),
     /// Display file contents
@@ -89,8 +236,197 @@ pub enum Command {
     Tree(TreeArgs),
     /// Diff two files and print unified diff
     Diff(DiffArgs),
+    /// Check a local directory against a remote prefix by MD5, without transferring data
+    Verify(VerifyArgs),
     /// Create empty files or update metadata (best-effort)
     Touch(TouchArgs),
+    /// Append a local file or standard input to a remote file
+    Append(AppendArgs),
+    /// Concatenate remote objects into a new one (server-side on S3/OSS/COS/GCS)
+    Concat(ConcatArgs),
+    /// List object versions (versioned buckets)
+    Versions(VersionsArgs),
+    /// Restore an older object version as the current one
+    Restore(RestoreArgs),
+    /// Move an object to another storage class (S3/OSS/COS)
+    Chclass(ChclassArgs),
+    /// Create a link object that points at another key or prefix
+    Ln(LnArgs),
+    /// List, restore or empty objects moved into the trash by `rm --trash`
+    Trash(TrashArgs),
+    /// Revert the last rm, mv, truncating touch, append, concat or overwriting put
+    Undo(UndoArgs),
+    /// Remove scratch files and incomplete uploads left by interrupted runs
+    Gc(GcArgs),
+    /// Get, set or remove object tags (S3/OSS/COS)
+    Tag(TagArgs),
+    /// Get or set object ACLs such as public-read (S3/OSS/COS)
+    Acl(AclArgs),
+    /// View or configure bucket lifecycle rules: expiration and transitions (S3/OSS/COS)
+    Lifecycle(LifecycleArgs),
+    /// List and abort incomplete multipart uploads (S3/OSS/COS)
+    Mpu(MpuArgs),
+    /// View or set bucket policy and CORS rules (S3/OSS/COS)
+    Bucket(BucketArgs),
+    /// Export a prefix listing to a local SQLite index, or query one
+    Index(IndexArgs),
+    /// Write every object below a prefix to a local CSV or JSON Lines manifest
+    Inventory(InventoryArgs),
+    /// Mount a remote directory as a read-only local filesystem (FUSE, Linux)
+    #[cfg(feature = "fuse")]
+    Mount(storage::MountArgs),
+    /// Serve the remote over HTTP for browsing, with optional token-authenticated uploads
+    Serve(ServeArgs),
+    /// Run the steps of a declarative job spec (TOML)
+    Apply(ApplyArgs),
+    /// Run put, get, rm and cp operations listed in a JSON Lines manifest
+    Batch(BatchArgs),
+    /// Upload the files of a local directory as they change
+    Watch(WatchArgs),
+    /// Upload what changed in a local directory, once or on a schedule with --daemon
+    Sync(SyncArgs),
+    /// List or cancel long-running transfers
+    #[command(subcommand)]
+    Jobs(JobsCommand),
+    /// Print the JSON Schema of a command's JSON output
+    Schema(SchemaArgs),
+    /// Update storify to the latest GitHub release
+    SelfUpdate(SelfUpdateArgs),
+    /// A command alias of the active profile, with its arguments
+    #[command(external_subcommand)]
+    Alias(Vec<String>),
+}
+
+impl Command {
+    /// Whether the command needs a resolved storage configuration.
+    pub fn requires_storage(&self) -> bool {
+        let standalone = matches!(
+            self,
+            Command::Config(_)
+                | Command::Jobs(_)
+                | Command::Schema(_)
+                | Command::SelfUpdate(_)
+                | Command::Alias(_)
+                | Command::Index(storage::IndexArgs {
+                    command: Some(storage::IndexCommand::Query(_)),
+                    ..
+                })
+        );
+        let local_gc = matches!(self, Command::Gc(args) if args.local_only());
+        !standalone && !local_gc && !storage::addresses_only_uris(self)
+    }
+
+    /// Subcommand name as typed on the command line.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Command::Config(_) => "config",
+            Command::Env(_) => "env",
+            Command::Ls(_) => "ls",
+            Command::Get(_) => "get",
+            Command::Du(_) => "du",
+            Command::Put(_) => "put",
+            Command::Rm(_) => "rm",
+            Command::Cp(_) => "cp",
+            Command::Mv(_) => "mv",
+            Command::Rename(_) => "rename",
+            Command::Mkdir(_) => "mkdir",
+            Command::Chmod(_) => "chmod",
+            #[cfg(feature = "hdfs")]
+            Command::Chown(_) => "chown",
+            Command::Stat(_) => "stat",
+            Command::Cat(_) => "cat",
+            Command::Head(_) => "head",
+            Command::Tail(_) => "tail",
+            Command::Grep(_) => "grep",
+            Command::Find(_) => "find",
+            Command::Tree(_) => "tree",
+            Command::Diff(_) => "diff",
+            Command::Verify(_) => "verify",
+            Command::Touch(_) => "touch",
+            Command::Append(_) => "append",
+            Command::Concat(_) => "concat",
+            Command::Versions(_) => "versions",
+            Command::Restore(_) => "restore",
+            Command::Chclass(_) => "chclass",
+            Command::Ln(_) => "ln",
+            Command::Trash(_) => "trash",
+            Command::Undo(_) => "undo",
+            Command::Gc(_) => "gc",
+            Command::Tag(_) => "tag",
+            Command::Acl(_) => "acl",
+            Command::Lifecycle(_) => "lifecycle",
+            Command::Mpu(_) => "mpu",
+            Command::Bucket(_) => "bucket",
+            Command::Index(_) => "index",
+            Command::Inventory(_) => "inventory",
+            #[cfg(feature = "fuse")]
+            Command::Mount(_) => "mount",
+            Command::Serve(_) => "serve",
+            Command::Apply(_) => "apply",
+            Command::Batch(_) => "batch",
+            Command::Watch(_) => "watch",
+            Command::Sync(_) => "sync",
+            Command::Jobs(_) => "jobs",
+            Command::Schema(_) => "schema",
+            Command::SelfUpdate(_) => "self-update",
+            Command::Alias(_) => "alias",
+        }
+    }
+
+    /// Whether per-request latency was asked for (`ls --timing`, `stat --timing`).
+    pub fn timing(&self) -> bool {
+        match self {
+            Command::Ls(args) => args.timing,
+            Command::Stat(args) => args.timing,
+            _ => false,
+        }
+    }
+
+    /// Long-running commands registered as cancellable jobs.
+    pub fn is_job(&self) -> bool {
+        matches!(
+            self,
+            Command::Get(_)
+                | Command::Put(_)
+                | Command::Cp(_)
+                | Command::Mv(_)
+                | Command::Apply(_)
+                | Command::Batch(_)
+                | Command::Sync(_)
+        )
+    }
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum JobsCommand {
+    /// List running transfers
+    List,
+    /// Request cancellation of a running transfer
+    Cancel(CancelJobArgs),
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct SchemaArgs {
+    /// Command whose JSON output to describe
+    #[arg(value_enum, value_name = "COMMAND")]
+    pub command: crate::storage::output::SchemaCommand,
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct SelfUpdateArgs {
+    /// Release channel to follow
+    #[arg(long, value_enum, default_value = "stable")]
+    pub channel: crate::update::Channel,
+    /// Only report whether an update is available (exits non-zero when one is)
+    #[arg(long)]
+    pub check_only: bool,
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct CancelJobArgs {
+    /// Job ID as shown by `storify jobs list`
+    #[arg(value_name = "ID")]
+    pub id: String,
 }
 
 #[derive(Subcommand, Debug, Clone)]
@@ -105,11 +441,27 @@ pub enum ConfigCommand {
     List(ListArgs),
     /// Delete a profile from the profile store
     Delete(DeleteArgs),
+    /// Change individual fields of a stored profile (interactive when no flags are given)
+    Edit(Box<EditArgs>),
+    /// Re-encrypt the profile store under a new master password
+    RotatePassword(RotatePasswordArgs),
+    /// Check connectivity and credentials for the active configuration or a profile
+    Test(TestArgs),
     /// Manage temporary config cache (encrypted, TTL-based)
     #[command(subcommand)]
     Temp(TempCommand),
 }
 
+#[derive(ClapArgs, Debug, Clone)]
+pub struct EnvArgs {
+    /// Output syntax
+    #[arg(long, value_enum, default_value = "shell")]
+    pub format: EnvFormat,
+    /// Include credentials (access keys, session and SAS tokens). Default: omitted
+    #[arg(long)]
+    pub show_secrets: bool,
+}
+
 #[derive(ClapArgs, Debug, Clone)]
 #[command(group = ArgGroup::new("show_target").args(["profile", "default"]).multiple(false))]
 pub struct ShowArgs {
@@ -129,7 +481,7 @@ pub struct CreateArgs {
     /// Profile name to create or update
     #[arg(value_name = "NAME")]
     pub name: Option<String>,
-    /// Storage provider (oss|s3|minio|cos|fs|hdfs)
+    /// Storage provider (oss|s3|minio|r2|b2|cos|fs|hdfs|azblob|gcs|http|mem)
     #[arg(long, value_name = "PROVIDER")]
     pub provider: Option<String>,
     /// Bucket name (cloud providers)
@@ -141,9 +493,15 @@ pub struct CreateArgs {
     /// Access key secret / secret key
     #[arg(long = "access-key-secret")]
     pub access_key_secret: Option<String>,
+    /// Temporary STS session token (S3) / security token (OSS)
+    #[arg(long = "session-token", visible_alias = "security-token")]
+    pub session_token: Option<String>,
     /// Endpoint override
     #[arg(long)]
     pub endpoint: Option<String>,
+    /// Endpoint to try when the previous ones are unreachable (repeatable, in order)
+    #[arg(long = "fallback-endpoint", value_name = "URL")]
+    pub fallback_endpoints: Vec<String>,
     /// Region setting (S3/OSS)
     #[arg(long)]
     pub region: Option<String>,
@@ -153,6 +511,99 @@ pub struct CreateArgs {
     /// HDFS name node address
     #[arg(long = "name-node")]
     pub name_node: Option<String>,
+    /// Service account credential file (GCS)
+    #[arg(long = "credential-path")]
+    pub credential_path: Option<String>,
+    /// Shared access signature token (Azblob)
+    #[arg(long = "sas-token")]
+    pub sas_token: Option<String>,
+    /// Full storage connection string (Azblob)
+    #[arg(long = "connection-string")]
+    pub connection_string: Option<String>,
+    /// IAM role to assume with the resolved credentials (S3)
+    #[arg(long = "role-arn")]
+    pub role_arn: Option<String>,
+    /// Named profile from ~/.aws/credentials (S3)
+    #[arg(long = "aws-profile")]
+    pub aws_profile: Option<String>,
+    /// Take credentials from the EC2/ECS instance metadata service (S3)
+    #[arg(long = "use-instance-metadata")]
+    pub use_instance_metadata: bool,
+    /// Compatibility preset for S3-compatible systems (S3)
+    #[arg(long, value_enum, value_name = "PRESET")]
+    pub compat: Option<CompatPreset>,
+    /// Cloudflare account ID the endpoint is derived from (R2)
+    #[arg(long = "account-id")]
+    pub account_id: Option<String>,
+    /// Key list used for listing instead of HTML index pages (HTTP)
+    #[arg(long, value_name = "PATH")]
+    pub manifest: Option<String>,
+    /// Retries of transiently failed requests (default 3, 0 disables them)
+    #[arg(long, value_name = "N")]
+    pub retries: Option<usize>,
+    /// Delay before the first retry in milliseconds, doubled for each further one
+    #[arg(
+        long = "retry-backoff-ms",
+        value_name = "MS",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub retry_backoff_ms: Option<u64>,
+    /// Send at most N requests per second
+    #[arg(
+        long = "rate-limit",
+        value_name = "N",
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    pub rate_limit: Option<u32>,
+    /// Request (and single read/write) timeout in seconds
+    #[arg(
+        long = "timeout-secs",
+        value_name = "SECS",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub timeout_secs: Option<u64>,
+    /// Connect timeout in seconds
+    #[arg(
+        long = "connect-timeout-secs",
+        value_name = "SECS",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub connect_timeout_secs: Option<u64>,
+    /// Idle HTTP connections kept open per host
+    #[arg(long = "pool-size", value_name = "N")]
+    pub pool_size: Option<usize>,
+    /// Bill S3 requests to the requester (requester-pays buckets)
+    #[arg(long = "request-payer")]
+    pub request_payer: bool,
+    /// Extra header sent with every request (repeatable)
+    #[arg(long = "header", value_name = "NAME:VALUE", value_parser = parse_header)]
+    pub headers: Vec<(String, String)>,
+    /// Make `rm` move objects into the trash by default
+    #[arg(long)]
+    pub trash: bool,
+    /// Default `--concurrency` of get, put and grep, and `--jobs` of ls -R, du and tree
+    #[arg(
+        long = "default-jobs",
+        value_name = "N",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    pub default_jobs: Option<usize>,
+    /// Make `du` print human-readable sizes by default (ls and stat are not affected)
+    #[arg(long = "human-readable")]
+    pub human_readable: bool,
+    /// Command alias, e.g. `ll=ls -L` (repeatable)
+    #[arg(long = "alias", value_name = "NAME=COMMAND", value_parser = parse_alias_arg)]
+    pub aliases: Vec<(String, String)>,
+    /// Base64 key for `put --encrypt` / `get --decrypt` (32 bytes)
+    #[arg(
+        long = "data-key",
+        value_name = "KEY",
+        conflicts_with = "generate_data_key"
+    )]
+    pub data_key: Option<String>,
+    /// Generate a random data key for client-side encryption
+    #[arg(long = "generate-data-key")]
+    pub generate_data_key: bool,
     /// Allow anonymous access when supported
     #[arg(long)]
     pub anonymous: bool,
@@ -172,6 +623,250 @@ pub struct CreateArgs {
     pub ttl: String,
 }
 
+#[derive(ClapArgs, Debug, Clone)]
+pub struct EditArgs {
+    /// Profile name to edit
+    #[arg(value_name = "NAME")]
+    pub name: String,
+    /// Bucket name (cloud providers)
+    #[arg(long)]
+    pub bucket: Option<String>,
+    /// Access key id / secret id
+    #[arg(long = "access-key-id")]
+    pub access_key_id: Option<String>,
+    /// Access key secret / secret key
+    #[arg(long = "access-key-secret")]
+    pub access_key_secret: Option<String>,
+    /// Temporary STS session token (S3) / security token (OSS)
+    #[arg(long = "session-token", visible_alias = "security-token")]
+    pub session_token: Option<String>,
+    /// Endpoint override
+    #[arg(long)]
+    pub endpoint: Option<String>,
+    /// Endpoint to try when the previous ones are unreachable (repeatable, in order)
+    #[arg(long = "fallback-endpoint", value_name = "URL")]
+    pub fallback_endpoints: Vec<String>,
+    /// Region setting (S3/OSS)
+    #[arg(long)]
+    pub region: Option<String>,
+    /// Local/HDFS root path
+    #[arg(long = "root-path")]
+    pub root_path: Option<String>,
+    /// HDFS name node address
+    #[arg(long = "name-node")]
+    pub name_node: Option<String>,
+    /// Service account credential file (GCS)
+    #[arg(long = "credential-path")]
+    pub credential_path: Option<String>,
+    /// Shared access signature token (Azblob)
+    #[arg(long = "sas-token")]
+    pub sas_token: Option<String>,
+    /// Full storage connection string (Azblob)
+    #[arg(long = "connection-string")]
+    pub connection_string: Option<String>,
+    /// IAM role to assume with the resolved credentials (S3)
+    #[arg(long = "role-arn")]
+    pub role_arn: Option<String>,
+    /// Named profile from ~/.aws/credentials (S3)
+    #[arg(long = "aws-profile")]
+    pub aws_profile: Option<String>,
+    /// Enable or disable instance metadata credentials (S3)
+    #[arg(long = "use-instance-metadata", value_name = "BOOL")]
+    pub use_instance_metadata: Option<bool>,
+    /// Compatibility preset for S3-compatible systems (S3)
+    #[arg(long, value_enum, value_name = "PRESET")]
+    pub compat: Option<CompatPreset>,
+    /// Cloudflare account ID the endpoint is derived from (R2)
+    #[arg(long = "account-id")]
+    pub account_id: Option<String>,
+    /// Key list used for listing instead of HTML index pages (HTTP)
+    #[arg(long, value_name = "PATH")]
+    pub manifest: Option<String>,
+    /// Retries of transiently failed requests (default 3, 0 disables them)
+    #[arg(long, value_name = "N")]
+    pub retries: Option<usize>,
+    /// Delay before the first retry in milliseconds, doubled for each further one
+    #[arg(
+        long = "retry-backoff-ms",
+        value_name = "MS",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub retry_backoff_ms: Option<u64>,
+    /// Send at most N requests per second
+    #[arg(
+        long = "rate-limit",
+        value_name = "N",
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    pub rate_limit: Option<u32>,
+    /// Request (and single read/write) timeout in seconds
+    #[arg(
+        long = "timeout-secs",
+        value_name = "SECS",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub timeout_secs: Option<u64>,
+    /// Connect timeout in seconds
+    #[arg(
+        long = "connect-timeout-secs",
+        value_name = "SECS",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub connect_timeout_secs: Option<u64>,
+    /// Idle HTTP connections kept open per host
+    #[arg(long = "pool-size", value_name = "N")]
+    pub pool_size: Option<usize>,
+    /// Enable or disable billing S3 requests to the requester
+    #[arg(long = "request-payer", value_name = "BOOL")]
+    pub request_payer: Option<bool>,
+    /// Extra header sent with every request, replacing the profile's headers (repeatable)
+    #[arg(long = "header", value_name = "NAME:VALUE", value_parser = parse_header)]
+    pub headers: Vec<(String, String)>,
+    /// Enable or disable moving objects into the trash on `rm` by default
+    #[arg(long = "trash", value_name = "BOOL")]
+    pub trash: Option<bool>,
+    /// Default `--concurrency` of get, put and grep, and `--jobs` of ls -R, du and tree
+    #[arg(
+        long = "default-jobs",
+        value_name = "N",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    pub default_jobs: Option<usize>,
+    /// Enable or disable human-readable sizes in `du` by default (ls and stat are not affected)
+    #[arg(long = "human-readable", value_name = "BOOL")]
+    pub human_readable: Option<bool>,
+    /// Add or replace a command alias, e.g. `ll=ls -L`; `ll=` removes it (repeatable)
+    #[arg(long = "alias", value_name = "NAME=COMMAND", value_parser = parse_alias_arg)]
+    pub aliases: Vec<(String, String)>,
+    /// Base64 key for `put --encrypt` / `get --decrypt` (32 bytes)
+    #[arg(
+        long = "data-key",
+        value_name = "KEY",
+        conflicts_with = "generate_data_key"
+    )]
+    pub data_key: Option<String>,
+    /// Generate a random data key for client-side encryption, replacing any previous one
+    #[arg(long = "generate-data-key")]
+    pub generate_data_key: bool,
+    /// Remove a field from the profile (repeatable)
+    #[arg(long, value_enum, value_name = "FIELD")]
+    pub unset: Vec<ProfileField>,
+}
+
+impl EditArgs {
+    /// Field changes requested via flags, in field order; unset fields map to `None`.
+    pub fn changes(&self) -> Vec<(ProfileField, Option<String>)> {
+        let set = [
+            (ProfileField::Bucket, &self.bucket),
+            (ProfileField::AccessKeyId, &self.access_key_id),
+            (ProfileField::AccessKeySecret, &self.access_key_secret),
+            (ProfileField::SessionToken, &self.session_token),
+            (ProfileField::Endpoint, &self.endpoint),
+            (ProfileField::Region, &self.region),
+            (ProfileField::RootPath, &self.root_path),
+            (ProfileField::NameNode, &self.name_node),
+            (ProfileField::CredentialPath, &self.credential_path),
+            (ProfileField::SasToken, &self.sas_token),
+            (ProfileField::ConnectionString, &self.connection_string),
+            (ProfileField::RoleArn, &self.role_arn),
+            (ProfileField::AwsProfile, &self.aws_profile),
+            (ProfileField::AccountId, &self.account_id),
+            (ProfileField::Manifest, &self.manifest),
+            (ProfileField::DataKey, &self.data_key),
+        ];
+        let mut changes: Vec<_> = set
+            .into_iter()
+            .filter_map(|(field, value)| value.clone().map(|value| (field, Some(value))))
+            .collect();
+        if !self.fallback_endpoints.is_empty() {
+            changes.push((
+                ProfileField::FallbackEndpoints,
+                Some(self.fallback_endpoints.join(",")),
+            ));
+        }
+        if let Some(enabled) = self.use_instance_metadata {
+            changes.push((ProfileField::UseInstanceMetadata, Some(enabled.to_string())));
+        }
+        if let Some(enabled) = self.trash {
+            changes.push((ProfileField::Trash, Some(enabled.to_string())));
+        }
+        if let Some(enabled) = self.human_readable {
+            changes.push((ProfileField::HumanReadable, Some(enabled.to_string())));
+        }
+        if !self.aliases.is_empty() {
+            let lines: Vec<String> = self
+                .aliases
+                .iter()
+                .map(|(name, command)| format!("{name}={command}"))
+                .collect();
+            changes.push((ProfileField::Aliases, Some(lines.join("\n"))));
+        }
+        if let Some(enabled) = self.request_payer {
+            changes.push((ProfileField::RequestPayer, Some(enabled.to_string())));
+        }
+        if let Some(lines) = header_lines(&self.headers) {
+            changes.push((ProfileField::Headers, Some(lines)));
+        }
+        if let Some(preset) = self.compat {
+            changes.push((ProfileField::Compat, Some(preset.as_str().to_string())));
+        }
+        if self.generate_data_key {
+            changes.push((ProfileField::DataKey, Some(DataKey::generate().encode())));
+        }
+        let numbers = [
+            (ProfileField::Retries, self.retries.map(|n| n.to_string())),
+            (
+                ProfileField::RetryBackoffMs,
+                self.retry_backoff_ms.map(|ms| ms.to_string()),
+            ),
+            (
+                ProfileField::RateLimit,
+                self.rate_limit.map(|n| n.to_string()),
+            ),
+            (
+                ProfileField::TimeoutSecs,
+                self.timeout_secs.map(|secs| secs.to_string()),
+            ),
+            (
+                ProfileField::ConnectTimeoutSecs,
+                self.connect_timeout_secs.map(|secs| secs.to_string()),
+            ),
+            (
+                ProfileField::PoolSize,
+                self.pool_size.map(|n| n.to_string()),
+            ),
+            (
+                ProfileField::DefaultJobs,
+                self.default_jobs.map(|n| n.to_string()),
+            ),
+        ];
+        changes.extend(
+            numbers
+                .into_iter()
+                .filter_map(|(field, value)| value.map(|value| (field, Some(value)))),
+        );
+        changes.extend(self.unset.iter().map(|field| (*field, None)));
+        changes
+    }
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct RotatePasswordArgs {
+    /// Switch to the auto-derived password instead of prompting for a new one
+    #[arg(long, conflicts_with = "new_password_env")]
+    pub auto: bool,
+    /// Read the new password from this environment variable instead of prompting
+    #[arg(long = "new-password-env", value_name = "VAR")]
+    pub new_password_env: Option<String>,
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct TestArgs {
+    /// Test a stored profile by name instead of the active configuration
+    #[arg(long)]
+    pub profile: Option<String>,
+}
+
 #[derive(Subcommand, Debug, Clone)]
 pub enum TempCommand {
     /// Show temporary config cache (if present and not expired)
@@ -223,15 +918,30 @@ pub struct DeleteArgs {
 }
 
 pub async fn run(args: Args) -> Result<()> {
-    run_with_prompt(args, None).await
+    logging::init(
+        args.global.verbose,
+        args.global.log_format,
+        args.global.log_file.as_deref(),
+    )?;
+    run_with_prompt(args, std::env::args_os().collect(), None).await
 }
 
-pub async fn run_with_prompt(args: Args, prompt: Option<Prompt>) -> Result<()> {
+/// Run the command `args` was parsed from `argv` with.
+pub async fn run_with_prompt(
+    args: Args,
+    argv: Vec<OsString>,
+    prompt: Option<Prompt>,
+) -> Result<()> {
     let prompt = prompt.unwrap_or_else(|| Prompt::new(!args.global.non_interactive));
 
-    let ctx = CliContext::from_args(args, prompt).await?;
+    let ctx = alias::expand(CliContext::from_args(args, prompt).await?, argv)?;
     match ctx.command() {
         Command::Config(cmd) => config::execute(cmd, &ctx),
+        Command::Env(args) => config::print_env(args, &ctx),
+        Command::Jobs(cmd) => jobs::execute(cmd),
+        Command::Schema(args) => schema::execute(args),
+        Command::SelfUpdate(args) => update::execute(args).await,
+        Command::Alias(words) => Err(alias::unknown_command(&words[0])),
         storage_cmd => storage::execute(storage_cmd, &ctx).await,
     }
 }
@@ -0,0 +1,44 @@
+use crate::error::Result;
+use crate::jobs::{JobRegistry, format_age};
+
+use super::entry::JobsCommand;
+
+pub fn execute(command: &JobsCommand) -> Result<()> {
+    let registry = JobRegistry::open()?;
+    match command {
+        JobsCommand::List => {
+            let jobs = registry.list()?;
+            if jobs.is_empty() {
+                println!("No running jobs.");
+                return Ok(());
+            }
+            println!(
+                "{:<8} {:>7} {:<10} {:>5}  COMMAND",
+                "ID", "PID", "STATE", "AGE"
+            );
+            for job in jobs {
+                let state = if job.cancel_requested {
+                    "cancelling"
+                } else {
+                    "running"
+                };
+                println!(
+                    "{:<8} {:>7} {:<10} {:>5}  {}",
+                    job.record.id,
+                    job.record.pid,
+                    state,
+                    format_age(job.record.started_at),
+                    job.record.command
+                );
+            }
+        }
+        JobsCommand::Cancel(args) => {
+            let record = registry.cancel(&args.id)?;
+            println!(
+                "Cancellation requested for job {} (pid {}): {}",
+                record.id, record.pid, record.command
+            );
+        }
+    }
+    Ok(())
+}
@@ -1,7 +1,15 @@
+pub mod alias;
+pub mod batch;
 pub mod config;
 pub mod context;
 pub mod entry;
+pub mod jobs;
 pub mod prompts;
+pub mod schema;
 pub mod storage;
+pub mod sync;
+pub mod undo;
+pub mod update;
+pub mod watch;
 
 pub use entry::{Args, run};
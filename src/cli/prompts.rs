@@ -76,6 +76,34 @@ impl Prompt {
             }),
         }
     }
+
+    /// Ask for a text value pre-filled with `current` so it can be edited in place.
+    pub async fn edit(&self, field: &str, current: Option<&str>) -> Result<String> {
+        match self {
+            Prompt::Console => {
+                let prompt = field.to_string();
+                let initial = current.unwrap_or_default().to_string();
+                let result = task::spawn_blocking(move || {
+                    Input::<String>::new()
+                        .with_prompt(prompt)
+                        .with_initial_text(initial)
+                        .allow_empty(true)
+                        .interact_text()
+                })
+                .await
+                .map_err(join_error)?;
+
+                result.map_err(|err| Error::InvalidArgument {
+                    message: err.to_string(),
+                })
+            }
+            Prompt::NonInteractive => Err(Error::InvalidArgument {
+                message: format!(
+                    "Input required for '{field}', but prompts are disabled. Pass the new value as a flag instead."
+                ),
+            }),
+        }
+    }
 }
 
 fn join_error(err: task::JoinError) -> Error {
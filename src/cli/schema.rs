@@ -0,0 +1,9 @@
+use crate::error::Result;
+
+use super::entry::SchemaArgs;
+
+pub fn execute(args: &SchemaArgs) -> Result<()> {
+    let schema = args.command.schema();
+    println!("{}", serde_json::to_string_pretty(&schema)?);
+    Ok(())
+}
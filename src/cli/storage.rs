@@ -1,14 +1,44 @@
+use crate::apply::JobSpec;
+use crate::config::loader::{ad_hoc_config, anonymous_config};
+use crate::config::{ServerSideEncryption, SseAlgorithm, prepare_storage_config};
 use crate::error::{Error, Result};
-use crate::storage::{OutputFormat, StorageClient};
+use crate::index::IndexQuery;
+use crate::jobs::JobRegistry;
+use crate::journal::Change;
+use crate::scratch::stale_staging_files;
+use crate::storage::constants::{
+    DEFAULT_DOWNLOAD_CONCURRENCY, DEFAULT_DOWNLOAD_PART_SIZE, DEFAULT_GREP_CONCURRENCY,
+    DEFAULT_UPLOAD_CONCURRENCY,
+};
+use crate::storage::metrics::{RunMetrics, TransferCounters, task_from_path};
+use crate::storage::recording::Recorder;
+use crate::storage::{
+    ByteRange, CannedAcl, CatOptions, Clobber, Comparison, Compression, ConcurrencyLimit,
+    CorsConfig, DiffSource, DirDiffOptions, DownloadOptions, EmptyTrashOptions, EntryFields,
+    FollowOptions, GcOptions, GrepOptions, InventoryFormat, LatestBy, LifecycleConfig, ListOptions,
+    ListSort, MAX_TAG_KEY_LEN, MAX_TAG_VALUE_LEN, MetadataMode, ObjectMeta, OutputFormat, PageSize,
+    PathFilter, Replacement, ReportOutput, RequestTimer, StorageClient, StorageConfig,
+    StorageProvider, SymlinkPolicy, Template, TransferReport, TreeFormat, TreeOptions,
+    UploadOptions, UsageGroup, UsageLine, UsageOptions, UsageSort, normalize_under_root,
+    parse_mode, parse_size, transfer_target, trash_batch_name,
+};
 use crate::utils::format_deletion_message;
-use clap::Args as ClapArgs;
+use chrono::{DateTime, Utc};
+use clap::{Args as ClapArgs, Subcommand};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 use tokio::runtime::Handle;
 use tokio::task;
+use tracing::Instrument;
 
 use super::context::CliContext;
-use super::entry::Command;
+use super::entry::{Command, GlobalOptions};
+use super::prompts::Prompt;
+use super::undo::{self, Snapshot};
 
-fn parse_validated_path(path_str: &str) -> Result<String> {
+pub(super) fn parse_validated_path(path_str: &str) -> Result<String> {
     if path_str.trim().is_empty() {
         Err(Error::InvalidPath {
             path: path_str.to_string(),
@@ -18,6 +48,35 @@ fn parse_validated_path(path_str: &str) -> Result<String> {
     }
 }
 
+/// `--latest` selector for commands that read objects by path.
+#[derive(ClapArgs, Debug, Clone, Default)]
+pub struct LatestArgs {
+    /// Treat the path as a prefix and use the newest object under it
+    #[arg(long)]
+    pub latest: bool,
+
+    /// How --latest orders objects: by modification time or by name
+    #[arg(
+        long = "latest-by",
+        value_enum,
+        value_name = "ORDER",
+        default_value_t,
+        requires = "latest"
+    )]
+    pub latest_by: LatestBy,
+}
+
+impl LatestArgs {
+    /// Resolve `path` to the newest object under it when `--latest` is set.
+    async fn resolve(&self, client: &StorageClient, path: &str) -> Result<String> {
+        if self.latest {
+            client.resolve_latest(path, self.latest_by).await
+        } else {
+            Ok(path.to_string())
+        }
+    }
+}
+
 #[derive(ClapArgs, Debug, Clone)]
 pub struct LsArgs {
     /// The path to list
@@ -31,6 +90,85 @@ pub struct LsArgs {
     /// Process directories recursively
     #[arg(short = 'R', long)]
     pub recursive: bool,
+
+    /// Long format without per-entry stat calls (may show missing sizes/times)
+    #[arg(long, requires = "long", conflicts_with = "full")]
+    pub fast: bool,
+
+    /// Long format with a stat call for every file (slower, most complete)
+    #[arg(long, requires = "long")]
+    pub full: bool,
+
+    /// Long format with a column of MD5 checksums (etags where the store keeps no MD5)
+    #[arg(long, requires = "long")]
+    pub checksum: bool,
+
+    /// Sort entries by name, size (largest first) or mtime (newest first)
+    #[arg(long, value_enum, value_name = "KEY")]
+    pub sort: Option<ListSort>,
+
+    /// Reverse the output order
+    #[arg(short = 'r', long)]
+    pub reverse: bool,
+
+    /// Print at most N entries
+    #[arg(long = "max-keys", value_name = "N")]
+    pub max_keys: Option<usize>,
+
+    /// Only show directories (common prefixes), not objects
+    #[arg(long = "prefix-only")]
+    pub prefix_only: bool,
+
+    /// Print each entry through a template, e.g. '{path}\t{size}'
+    /// (fields: path, name, type, size, human_size, last_modified, storage_class)
+    #[arg(
+        long,
+        value_name = "TEMPLATE",
+        conflicts_with = "long",
+        value_parser = Template::parse::<EntryFields>
+    )]
+    pub format: Option<Template>,
+
+    /// With -R, list up to N top-level directories at once, merging them in key order
+    /// (default 1, or the profile's `default_jobs`)
+    #[arg(
+        long,
+        value_name = "N",
+        requires = "recursive",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    pub jobs: Option<usize>,
+
+    /// Report time to first byte and total time of each storage request (on stderr)
+    #[arg(long)]
+    pub timing: bool,
+}
+
+impl LsArgs {
+    pub fn list_options(&self) -> ListOptions {
+        ListOptions {
+            long: self.long,
+            recursive: self.recursive,
+            metadata: self.metadata_mode(),
+            sort: self.sort,
+            reverse: self.reverse,
+            max_keys: self.max_keys,
+            prefix_only: self.prefix_only,
+            format: self.format.clone(),
+            checksum: self.checksum,
+            jobs: self.jobs.unwrap_or(1),
+        }
+    }
+
+    fn metadata_mode(&self) -> MetadataMode {
+        if self.fast {
+            MetadataMode::Fast
+        } else if self.full {
+            MetadataMode::Full
+        } else {
+            MetadataMode::Auto
+        }
+    }
 }
 
 #[derive(ClapArgs, Debug, Clone)]
@@ -39,35 +177,329 @@ pub struct GetArgs {
     #[arg(value_name = "REMOTE", value_parser = parse_validated_path)]
     pub remote: String,
 
-    /// The local path to download to
+    /// The local path to download to; a trailing '/' downloads into it as a directory
     #[arg(value_name = "LOCAL", value_parser = parse_validated_path)]
     pub local: String,
+
+    /// Download a specific object version (versioned buckets only)
+    #[arg(long = "version-id", value_name = "ID")]
+    pub version_id: Option<String>,
+
+    /// Skip the pre-flight check that the destination has enough free space
+    #[arg(long = "no-space-check")]
+    pub no_space_check: bool,
+
+    /// Concurrent range requests per file (default 4, or the profile's `default_jobs`)
+    #[arg(long, value_name = "N")]
+    pub concurrency: Option<usize>,
+
+    /// Size of each range request in MB
+    #[arg(long = "part-size", value_name = "MB", default_value_t = DEFAULT_DOWNLOAD_PART_SIZE / (1024 * 1024))]
+    pub part_size_mb: u64,
+
+    /// Continue an interrupted download from its .part file if the object has not changed
+    #[arg(long, conflicts_with = "version_id")]
+    pub resume: bool,
+
+    /// Only download files matching this glob, relative to REMOTE (repeatable)
+    #[arg(long, value_name = "GLOB", conflicts_with = "version_id")]
+    pub include: Vec<String>,
+
+    /// Skip files matching this glob, relative to REMOTE (repeatable)
+    #[arg(long, value_name = "GLOB", conflicts_with = "version_id")]
+    pub exclude: Vec<String>,
+
+    /// Only download files modified after a time (RFC 3339, a date, or an age like 2h, 1d)
+    #[arg(long = "newer-than", value_name = "TIME", value_parser = parse_since, conflicts_with = "version_id")]
+    pub newer_than: Option<DateTime<Utc>>,
+
+    /// Restore modification times and permissions stored by `put --preserve`
+    #[arg(long, conflicts_with = "version_id")]
+    pub preserve: bool,
+
+    /// Recreate symlinks stored by `put --preserve-links` instead of downloading their markers
+    #[arg(long = "preserve-links", conflicts_with = "version_id")]
+    pub preserve_links: bool,
+
+    /// Print each file's size and a summary of the download
+    #[arg(short = 'v', long)]
+    pub verbose: bool,
+
+    /// Print what would be downloaded, with sizes and destinations, without downloading
+    #[arg(long = "dry-run", conflicts_with = "version_id")]
+    pub dry_run: bool,
+
+    /// Decompress gzip (.gz) and zstd (.zst) files, dropping the extension from their names
+    #[arg(long, conflicts_with = "version_id")]
+    pub decompress: bool,
+
+    /// Decrypt files uploaded with `put --encrypt`, using the profile's data key
+    #[arg(long, conflicts_with = "version_id")]
+    pub decrypt: bool,
+
+    /// Read through link objects created by `storify ln` to their targets
+    #[arg(long = "follow-links")]
+    pub follow_links: bool,
+
+    /// Write the files into one tar archive at LOCAL (.tar, .tar.gz or .tar.zst)
+    #[arg(
+        long,
+        conflicts_with_all = ["version_id", "resume", "decompress", "decrypt", "preserve_links"]
+    )]
+    pub archive: bool,
+
+    #[command(flatten)]
+    pub selector: LatestArgs,
+}
+
+impl GetArgs {
+    pub fn download_options(&self) -> Result<DownloadOptions> {
+        Ok(DownloadOptions {
+            check_space: !self.no_space_check,
+            concurrency: self
+                .concurrency
+                .unwrap_or(DEFAULT_DOWNLOAD_CONCURRENCY)
+                .max(1),
+            part_size: self.part_size_mb.max(1) * 1024 * 1024,
+            resume: self.resume,
+            filter: PathFilter::new(&self.include, &self.exclude)?,
+            newer_than: self.newer_than,
+            preserve: self.preserve,
+            preserve_links: self.preserve_links,
+            verbose: self.verbose,
+            dry_run: self.dry_run,
+            decompress: self.decompress,
+            decrypt: self.decrypt,
+            archive: self.archive,
+        })
+    }
 }
 
 #[derive(ClapArgs, Debug, Clone)]
+#[command(disable_help_flag = true)]
 pub struct DuArgs {
     /// The path to check usage for
     #[arg(value_name = "PATH", value_parser = parse_validated_path)]
     pub path: String,
 
     /// Show summary only
-    #[arg(short = 's', long)]
+    #[arg(short = 's', long, conflicts_with = "max_depth")]
     pub summary: bool,
+
+    /// Print sizes in human-readable units (e.g. 1.5K, 234.0M)
+    #[arg(short = 'h', long = "human-readable")]
+    pub human_readable: bool,
+
+    /// Print directory totals down to N levels below PATH instead of every file
+    #[arg(short = 'd', long = "max-depth", value_name = "N")]
+    pub max_depth: Option<usize>,
+
+    /// Sort output by size (largest first) or name
+    #[arg(long, value_enum, value_name = "KEY")]
+    pub sort: Option<UsageSort>,
+
+    /// Print each line through a template, e.g. '{size}\t{path}' (fields: path, size, human_size)
+    #[arg(long, value_name = "TEMPLATE", value_parser = Template::parse::<UsageLine>)]
+    pub format: Option<Template>,
+
+    /// Print object count, size and share of the total per group instead of per path
+    #[arg(long, conflicts_with_all = ["summary", "max_depth", "format"])]
+    pub report: bool,
+
+    /// What --report groups objects by
+    #[arg(
+        long = "group-by",
+        value_enum,
+        value_name = "GROUP",
+        default_value = "top-level",
+        requires = "report"
+    )]
+    pub group_by: UsageGroup,
+
+    /// Layout of --report: aligned table or CSV with a header row
+    #[arg(
+        long,
+        value_enum,
+        value_name = "FORMAT",
+        default_value = "table",
+        requires = "report"
+    )]
+    pub output: ReportOutput,
+
+    /// List up to N top-level directories at once, merging them in key order (default 1,
+    /// or the profile's `default_jobs`)
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    pub jobs: Option<usize>,
+
+    /// Print help
+    #[arg(long, action = clap::ArgAction::Help)]
+    pub help: Option<bool>,
+}
+
+impl DuArgs {
+    pub fn usage_options(&self) -> UsageOptions {
+        UsageOptions {
+            summary: self.summary,
+            human_readable: self.human_readable,
+            max_depth: self.max_depth,
+            sort: self.sort,
+            format: self.format.clone(),
+            report: self.report.then_some(self.group_by),
+            output: self.output,
+            jobs: self.jobs.unwrap_or(1),
+        }
+    }
 }
 
 #[derive(ClapArgs, Debug, Clone)]
 pub struct PutArgs {
-    /// The local path to upload from
+    /// The local path to upload from, or `-` to stream standard input
     #[arg(value_name = "LOCAL", value_parser = parse_validated_path)]
     pub local: String,
 
-    /// The remote path to upload to
+    /// The remote path to upload to; a trailing '/' uploads into it as a directory
     #[arg(value_name = "REMOTE", value_parser = parse_validated_path)]
     pub remote: String,
 
     /// Process directories recursively
     #[arg(short = 'R', long)]
     pub recursive: bool,
+
+    /// Files uploaded at the same time during recursive uploads (default 4, or the
+    /// profile's `default_jobs`)
+    #[arg(long, value_name = "N")]
+    pub concurrency: Option<usize>,
+
+    /// Bandwidth cap for each file (bytes per second; accepts K/M/G suffixes, e.g. 512K)
+    #[arg(long = "per-file-limit", value_name = "RATE", value_parser = parse_rate)]
+    pub per_file_limit: Option<u64>,
+
+    /// Bandwidth cap shared fairly by all files of the upload (e.g. 20M)
+    #[arg(long = "total-limit", value_name = "RATE", value_parser = parse_rate)]
+    pub total_limit: Option<u64>,
+
+    /// Storage class of the uploaded objects, e.g. STANDARD_IA, GLACIER or IA (s3 only)
+    #[arg(long = "storage-class", value_name = "CLASS")]
+    pub storage_class: Option<String>,
+
+    /// Encrypt the uploaded objects at rest (s3 and oss)
+    #[arg(long = "sse", value_name = "ALGORITHM", value_enum)]
+    pub sse: Option<SseAlgorithm>,
+
+    /// Customer-managed KMS key for `--sse kms` (the provider's default key when omitted)
+    #[arg(long = "kms-key-id", value_name = "ID", requires = "sse")]
+    pub kms_key_id: Option<String>,
+
+    /// Print the server-side encryption applied to each uploaded object and a summary
+    #[arg(short = 'v', long)]
+    pub verbose: bool,
+
+    /// Print what would be uploaded, with sizes and destinations, without uploading
+    #[arg(long = "dry-run")]
+    pub dry_run: bool,
+
+    /// With -R, only upload files matching this glob, relative to LOCAL (repeatable)
+    #[arg(long, value_name = "GLOB", requires = "recursive")]
+    pub include: Vec<String>,
+
+    /// With -R, skip files and directories matching this glob, relative to LOCAL (repeatable)
+    #[arg(long, value_name = "GLOB", requires = "recursive")]
+    pub exclude: Vec<String>,
+
+    /// Store each file's modification time and permissions as object metadata
+    #[arg(long)]
+    pub preserve: bool,
+
+    /// With -R, upload what symlinks point to (the default); linked directories are walked
+    #[arg(long = "follow-symlinks", group = "symlinks", requires = "recursive")]
+    pub follow_symlinks: bool,
+
+    /// With -R, leave symlinks out of the upload
+    #[arg(
+        long = "no-follow-symlinks",
+        group = "symlinks",
+        requires = "recursive"
+    )]
+    pub no_follow_symlinks: bool,
+
+    /// With -R, store each symlink as a small marker object holding its target
+    #[arg(long = "preserve-links", group = "symlinks", requires = "recursive")]
+    pub preserve_links: bool,
+
+    /// Compress each file while uploading it, adding .gz or .zst to its name
+    #[arg(long, value_name = "FORMAT", value_enum)]
+    pub compress: Option<Compression>,
+
+    /// Encrypt each file with the profile's data key before uploading it
+    #[arg(long)]
+    pub encrypt: bool,
+
+    /// Upload the files of the tar archive LOCAL (.tar, .tar.gz, .tar.zst or `-`) below REMOTE
+    #[arg(long, conflicts_with_all = ["recursive", "compress"])]
+    pub extract: bool,
+
+    #[command(flatten)]
+    pub clobber: ClobberArgs,
+
+    /// Replication factor of the uploaded files (hdfs only)
+    #[cfg(feature = "hdfs")]
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u16).range(1..))]
+    pub replication: Option<u16>,
+}
+
+impl PutArgs {
+    pub fn upload_options(&self, prompt: Prompt) -> Result<UploadOptions> {
+        Ok(UploadOptions {
+            concurrency: self
+                .concurrency
+                .unwrap_or(DEFAULT_UPLOAD_CONCURRENCY)
+                .max(1),
+            per_file_limit: self.per_file_limit,
+            total_limit: self.total_limit,
+            verbose: self.verbose,
+            dry_run: self.dry_run,
+            filter: PathFilter::new(&self.include, &self.exclude)?,
+            preserve: self.preserve,
+            symlinks: if self.preserve_links {
+                SymlinkPolicy::Preserve
+            } else if self.no_follow_symlinks {
+                SymlinkPolicy::Skip
+            } else {
+                SymlinkPolicy::Follow
+            },
+            #[cfg(feature = "hdfs")]
+            replication: self.replication,
+            #[cfg(not(feature = "hdfs"))]
+            replication: None,
+            compression: self.compress,
+            encrypt: self.encrypt,
+            extract: self.extract,
+            clobber: self.clobber.clobber(prompt),
+        })
+    }
+
+    /// Server-side encryption requested on the command line.
+    fn encryption(&self) -> Option<ServerSideEncryption> {
+        self.sse.map(|algorithm| ServerSideEncryption {
+            algorithm,
+            kms_key_id: self.kms_key_id.clone(),
+        })
+    }
+}
+
+/// Parse a transfer rate such as `1048576`, `512K`, `20M` or `1G/s` into bytes per second.
+fn parse_rate(input: &str) -> Result<u64> {
+    let s = input.trim();
+    let s = s.strip_suffix("/s").unwrap_or(s);
+    parse_size(s)
+        .filter(|rate| *rate > 0)
+        .ok_or_else(|| Error::InvalidArgument {
+            message: format!("invalid rate '{input}' (expected e.g. 512K, 20M or 1G)"),
+        })
 }
 
 #[derive(ClapArgs, Debug, Clone)]
@@ -83,6 +515,22 @@ pub struct RmArgs {
     /// Force deletion without confirmation
     #[arg(short = 'f', long)]
     pub force: bool,
+
+    /// Permanently delete a specific object version (versioned buckets only)
+    #[arg(long = "version-id", value_name = "ID", conflicts_with = "recursive")]
+    pub version_id: Option<String>,
+
+    /// Allow deleting the root directory of an fs backend
+    #[arg(long = "allow-root")]
+    pub allow_root: bool,
+
+    /// Move the paths under `.storify-trash/<timestamp>/` instead of deleting them
+    #[arg(long, conflicts_with_all = ["version_id", "no_trash", "allow_root"])]
+    pub trash: bool,
+
+    /// Delete permanently even when the profile moves deleted paths into the trash
+    #[arg(long = "no-trash")]
+    pub no_trash: bool,
 }
 
 #[derive(ClapArgs, Debug, Clone)]
@@ -91,9 +539,12 @@ pub struct CpArgs {
     #[arg(value_name = "SRC", value_parser = parse_validated_path)]
     pub src_path: String,
 
-    /// The remote path to copy to
+    /// The remote path to copy to; a trailing '/' copies into it as a directory
     #[arg(value_name = "DEST", value_parser = parse_validated_path)]
     pub dest_path: String,
+
+    #[command(flatten)]
+    pub clobber: ClobberArgs,
 }
 
 #[derive(ClapArgs, Debug, Clone)]
@@ -102,9 +553,63 @@ pub struct MvArgs {
     #[arg(value_name = "SRC", value_parser = parse_validated_path)]
     pub src_path: String,
 
-    /// The remote path to move to
+    /// The remote path to move to; a trailing '/' moves into it as a directory
     #[arg(value_name = "DEST", value_parser = parse_validated_path)]
     pub dest_path: String,
+
+    /// Delete sources without first checking that their copies match (when not renamed)
+    #[arg(long = "no-verify")]
+    pub no_verify: bool,
+
+    #[command(flatten)]
+    pub clobber: ClobberArgs,
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct RenameArgs {
+    /// Files to rename: a path or glob such as 'logs/*.txt' ('**' matches across directories)
+    #[arg(value_name = "PATTERN", value_parser = parse_validated_path)]
+    pub pattern: String,
+
+    /// Replace FROM with TO in the name of each matching file, e.g. '.txt:.log'
+    #[arg(long, value_name = "FROM:TO")]
+    pub replace: Replacement,
+
+    /// Show what would be renamed without renaming anything
+    #[arg(long = "dry-run")]
+    pub dry_run: bool,
+}
+
+/// Overwrite flags shared by `cp`, `mv` and `put`; the last one given wins.
+#[derive(ClapArgs, Debug, Clone, Default)]
+pub struct ClobberArgs {
+    /// Do not overwrite existing objects; their sources are skipped
+    #[arg(short = 'n', long = "no-clobber", overrides_with_all = ["interactive", "force"])]
+    pub no_clobber: bool,
+
+    /// Ask before overwriting each existing object (skips it under --non-interactive)
+    #[arg(short = 'i', long, overrides_with_all = ["no_clobber", "force"])]
+    pub interactive: bool,
+
+    /// Overwrite existing objects without asking (the default)
+    #[arg(short = 'f', long, overrides_with_all = ["no_clobber", "interactive"])]
+    pub force: bool,
+}
+
+impl ClobberArgs {
+    pub fn clobber(&self, prompt: Prompt) -> Clobber {
+        if self.no_clobber {
+            Clobber::Skip
+        } else if self.interactive {
+            Clobber::Ask(Arc::new(move |dest: &str| {
+                let message = format!("Overwrite '{dest}'?");
+                task::block_in_place(|| Handle::current().block_on(prompt.confirm(&message, false)))
+                    .unwrap_or(false)
+            }))
+        } else {
+            Clobber::Overwrite
+        }
+    }
 }
 
 #[derive(ClapArgs, Debug, Clone)]
@@ -116,20 +621,86 @@ pub struct MkdirArgs {
     /// Create parent directories as needed
     #[arg(short, long)]
     pub parents: bool,
+
+    /// Permission bits of the new directory in octal, e.g. 750 (fs only)
+    #[arg(short, long, value_name = "MODE", value_parser = parse_mode)]
+    pub mode: Option<u32>,
 }
 
 #[derive(ClapArgs, Debug, Clone)]
-pub struct CatArgs {
-    /// The remote file path to display
+pub struct ChmodArgs {
+    /// Permission bits in octal, e.g. 640
+    #[arg(value_name = "MODE", value_parser = parse_mode)]
+    pub mode: u32,
+
+    /// The file or directory to change
+    #[arg(value_name = "PATH", value_parser = parse_validated_path)]
+    pub path: String,
+
+    /// Change everything below a directory too
+    #[arg(short = 'R', long)]
+    pub recursive: bool,
+}
+
+#[cfg(feature = "hdfs")]
+#[derive(ClapArgs, Debug, Clone)]
+pub struct ChownArgs {
+    /// New owner and/or group: OWNER, OWNER:GROUP or :GROUP
+    #[arg(value_name = "OWNER[:GROUP]")]
+    pub owner: String,
+
+    /// The file or directory to change
     #[arg(value_name = "PATH", value_parser = parse_validated_path)]
     pub path: String,
 
+    /// Change everything below a directory too
+    #[arg(short = 'R', long)]
+    pub recursive: bool,
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct CatArgs {
+    /// Remote file path(s) to display, concatenated in order
+    #[arg(value_name = "PATH", required = true, value_parser = parse_validated_path)]
+    pub paths: Vec<String>,
+
     #[arg(short = 'f', long)]
     pub force: bool,
 
-    /// Limit file size in MB (default: 10)
+    /// Limit file size in MB when printing to a terminal (default: 10)
     #[arg(short = 's', long = "size-limit", default_value_t = 10)]
     pub size_limit_mb: u64,
+
+    /// Only print bytes START-END (inclusive) of each file; START- reads to the end, -N the last N bytes
+    #[arg(long, value_name = "START-END", allow_hyphen_values = true)]
+    pub range: Option<ByteRange>,
+
+    /// Write to FILE instead of stdout (no size limit applies)
+    #[arg(short = 'o', long, value_name = "FILE")]
+    pub output: Option<PathBuf>,
+
+    /// Print gzip and zstd files as stored instead of decompressing them
+    #[arg(long)]
+    pub raw: bool,
+
+    /// Read through link objects created by `storify ln` to their targets
+    #[arg(long = "follow-links")]
+    pub follow_links: bool,
+
+    #[command(flatten)]
+    pub selector: LatestArgs,
+}
+
+impl CatArgs {
+    fn cat_options(&self) -> CatOptions {
+        CatOptions {
+            force: self.force,
+            size_limit_mb: self.size_limit_mb,
+            range: self.range,
+            output: self.output.clone(),
+            raw: self.raw,
+        }
+    }
 }
 
 #[derive(ClapArgs, Debug, Clone)]
@@ -145,6 +716,32 @@ pub struct StatArgs {
     /// Output as raw key=value lines (compatible with opendal-mkdir)
     #[arg(long, conflicts_with = "json")]
     pub raw: bool,
+
+    /// Show metadata of a specific object version (versioned buckets only)
+    #[arg(long = "version-id", value_name = "ID")]
+    pub version_id: Option<String>,
+
+    /// Read through link objects created by `storify ln` to their targets
+    #[arg(long = "follow-links")]
+    pub follow_links: bool,
+
+    /// For directories, total only the direct children instead of walking all levels
+    #[arg(long)]
+    pub shallow: bool,
+
+    /// Print fields through a template instead, e.g. '{path}\t{size}\t{etag}'
+    /// (fields: the keys of the default output, plus human_size)
+    #[arg(
+        long,
+        value_name = "TEMPLATE",
+        conflicts_with_all = ["json", "raw"],
+        value_parser = Template::parse::<ObjectMeta>
+    )]
+    pub format: Option<Template>,
+
+    /// Report time to first byte and total time of each storage request (on stderr)
+    #[arg(long)]
+    pub timing: bool,
 }
 
 #[derive(ClapArgs, Debug, Clone)]
@@ -191,6 +788,9 @@ pub struct TailArgs {
     /// Always print headers
     #[arg(short = 'v', long, conflicts_with = "quiet")]
     pub verbose: bool,
+
+    #[command(flatten)]
+    pub selector: LatestArgs,
 }
 
 #[derive(ClapArgs, Debug, Clone)]
@@ -210,9 +810,163 @@ pub struct GrepArgs {
     #[arg(short = 'n', long = "line-number")]
     pub line_number: bool,
 
+    /// Treat the pattern as a regular expression
+    #[arg(short = 'E', long = "regex")]
+    pub regex: bool,
+
+    /// Print lines that do not match
+    #[arg(short = 'v', long = "invert-match")]
+    pub invert_match: bool,
+
+    /// Print the number of matching lines per file instead of the lines
+    #[arg(short = 'c', long = "count", conflicts_with = "follow")]
+    pub count: bool,
+
+    /// Print only the paths of files with a match
+    #[arg(
+        short = 'l',
+        long = "files-with-matches",
+        conflicts_with_all = ["follow", "count"]
+    )]
+    pub files_with_matches: bool,
+
+    /// Stop reading a file after N matching lines
+    #[arg(long = "max-count", value_name = "N", conflicts_with = "follow")]
+    pub max_count: Option<usize>,
+
+    /// Print N lines of context after each matching line
+    #[arg(
+        short = 'A',
+        long = "after-context",
+        value_name = "N",
+        conflicts_with_all = ["follow", "count", "files_with_matches"]
+    )]
+    pub after_context: Option<usize>,
+
+    /// Print N lines of context before each matching line
+    #[arg(
+        short = 'B',
+        long = "before-context",
+        value_name = "N",
+        conflicts_with_all = ["follow", "count", "files_with_matches"]
+    )]
+    pub before_context: Option<usize>,
+
+    /// Print N lines of context around each matching line (-A and -B take precedence)
+    #[arg(
+        short = 'C',
+        long = "context",
+        value_name = "N",
+        conflicts_with_all = ["follow", "count", "files_with_matches"]
+    )]
+    pub context: Option<usize>,
+
+    /// Search files that contain NUL bytes instead of skipping them as binary
+    #[arg(short = 'a', long = "text", conflicts_with = "follow")]
+    pub text: bool,
+
     /// Recursively search directories
     #[arg(short = 'R', long = "recursive")]
     pub recursive: bool,
+
+    /// With -R, only search files matching this glob, relative to PATH (repeatable)
+    #[arg(long, value_name = "GLOB", requires = "recursive")]
+    pub include: Vec<String>,
+
+    /// With -R, skip files matching this glob, relative to PATH (repeatable)
+    #[arg(long, value_name = "GLOB", requires = "recursive")]
+    pub exclude: Vec<String>,
+
+    /// With -R, files searched at the same time; each file's output stays together
+    /// (default 8, or the profile's `default_jobs`)
+    #[arg(long, value_name = "N", requires = "recursive")]
+    pub concurrency: Option<usize>,
+
+    /// With -R, stop after the file whose output reaches N lines and print the key to
+    /// continue from
+    #[arg(
+        long = "max-results",
+        value_name = "N",
+        requires = "recursive",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    pub max_results: Option<usize>,
+
+    /// With -R, continue a search after this key, as printed by an earlier --max-results run
+    #[arg(long = "start-after", value_name = "KEY", requires = "recursive")]
+    pub start_after: Option<String>,
+
+    /// Keep polling for new lines (a path ending in '/' follows its newest object)
+    #[arg(short = 'f', long = "follow", conflicts_with = "recursive")]
+    pub follow: bool,
+
+    /// Seconds between polls in follow mode
+    #[arg(long, value_name = "SECS", default_value_t = 2, requires = "follow")]
+    pub interval: u64,
+
+    /// With --follow, also scan content written since a time (RFC 3339 or a relative age like 15m, 2h, 1d)
+    #[arg(long, value_name = "TIME", value_parser = parse_since, requires = "follow")]
+    pub since: Option<DateTime<Utc>>,
+}
+
+impl GrepArgs {
+    pub fn grep_options(&self) -> Result<GrepOptions> {
+        Ok(GrepOptions {
+            ignore_case: self.ignore_case,
+            line_number: self.line_number,
+            regex: self.regex,
+            invert_match: self.invert_match,
+            count: self.count,
+            files_with_matches: self.files_with_matches,
+            max_count: self.max_count,
+            after_context: self.after_context.or(self.context).unwrap_or(0),
+            before_context: self.before_context.or(self.context).unwrap_or(0),
+            text: self.text,
+            filter: PathFilter::new(&self.include, &self.exclude)?,
+            concurrency: self.concurrency.unwrap_or(DEFAULT_GREP_CONCURRENCY),
+            max_results: self.max_results,
+            start_after: self.start_after.clone(),
+        })
+    }
+
+    pub fn follow_options(&self) -> FollowOptions {
+        FollowOptions {
+            ignore_case: self.ignore_case,
+            line_number: self.line_number,
+            regex: self.regex,
+            invert_match: self.invert_match,
+            interval: Duration::from_secs(self.interval.max(1)),
+            since: self.since,
+        }
+    }
+}
+
+/// Parse an absolute RFC 3339 timestamp, a `YYYY-MM-DD` date (midnight UTC) or a relative
+/// age (`<n>s|m|h|d`) counted back from now.
+fn parse_since(input: &str) -> Result<DateTime<Utc>> {
+    let s = input.trim();
+    if let Ok(ts) = DateTime::parse_from_rfc3339(s) {
+        return Ok(ts.with_timezone(&Utc));
+    }
+    if let Ok(date) = chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        return Ok(date.and_time(chrono::NaiveTime::MIN).and_utc());
+    }
+    let invalid = || Error::InvalidArgument {
+        message: format!(
+            "invalid time '{s}' (expected RFC 3339, a date like 2024-05-01, or e.g. 15m, 2h, 1d)"
+        ),
+    };
+    let (num, unit) = s.split_at(s.len().saturating_sub(1));
+    let n: i64 = num.parse().map_err(|_| invalid())?;
+    let age = match unit {
+        "s" => chrono::Duration::try_seconds(n),
+        "m" => chrono::Duration::try_minutes(n),
+        "h" => chrono::Duration::try_hours(n),
+        "d" => chrono::Duration::try_days(n),
+        _ => None,
+    }
+    .ok_or_else(invalid)?;
+    Ok(Utc::now() - age)
 }
 
 #[derive(ClapArgs, Debug, Clone)]
@@ -233,6 +987,62 @@ pub struct FindArgs {
     /// Filter by entry type: f (file), d (dir), o (other)
     #[arg(long = "type", value_name = "f|d|o")]
     pub r#type: Option<String>,
+
+    /// Filter by size: +10M (larger than), -1k (smaller than) or an exact size
+    #[arg(long, value_name = "[+|-]SIZE", allow_hyphen_values = true, value_parser = parse_size_test)]
+    pub size: Option<Comparison>,
+
+    /// Filter by days since modification: +7 (older than 7 days), -1 (within the last day) or exact
+    #[arg(long, value_name = "[+|-]DAYS", allow_hyphen_values = true, value_parser = parse_days_test)]
+    pub mtime: Option<Comparison>,
+
+    /// Skip paths fewer than N levels below PATH (its direct children are at level 1)
+    #[arg(long = "mindepth", value_name = "N")]
+    pub min_depth: Option<usize>,
+
+    /// Skip paths more than N levels below PATH
+    #[arg(long = "maxdepth", value_name = "N")]
+    pub max_depth: Option<usize>,
+
+    /// Delete matching paths instead of printing them
+    #[arg(long, conflicts_with = "exec")]
+    pub delete: bool,
+
+    /// Run a shell command for each matching path; {} is replaced by the quoted path
+    #[arg(long, value_name = "CMD")]
+    pub exec: Option<String>,
+
+    /// Print each match through a template, e.g. '{path}\t{size}'
+    /// (fields: path, name, type, size, human_size, last_modified, depth)
+    #[arg(
+        long,
+        value_name = "TEMPLATE",
+        conflicts_with_all = ["delete", "exec"],
+        value_parser = Template::parse::<EntryFields>
+    )]
+    pub format: Option<Template>,
+
+    /// Stop after N paths and print the key to continue from
+    #[arg(
+        long = "max-results",
+        value_name = "N",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    pub max_results: Option<usize>,
+
+    /// Continue a scan after this key, as printed by an earlier --max-results run
+    #[arg(long = "start-after", value_name = "KEY")]
+    pub start_after: Option<String>,
+}
+
+fn parse_size_test(input: &str) -> Result<Comparison> {
+    Comparison::parse(input, "--size", "e.g. +10M, -1k or 4096", parse_size)
+}
+
+fn parse_days_test(input: &str) -> Result<Comparison> {
+    Comparison::parse(input, "--mtime", "e.g. +7, -1 or 3", |days| {
+        days.parse().ok()
+    })
 }
 
 #[derive(ClapArgs, Debug, Clone)]
@@ -248,18 +1058,54 @@ pub struct TreeArgs {
     /// Show directories only
     #[arg(long = "dirs-only")]
     pub dirs_only: bool,
+
+    /// Show file sizes and cumulative directory sizes
+    #[arg(short = 's', long = "size")]
+    pub size: bool,
+
+    /// Output format
+    #[arg(long, value_enum, default_value = "text")]
+    pub format: TreeFormat,
+
+    /// Without a depth limit, list up to N top-level directories at once (default 1, or the
+    /// profile's `default_jobs`)
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    pub jobs: Option<usize>,
 }
 
+impl TreeArgs {
+    pub fn tree_options(&self) -> TreeOptions {
+        TreeOptions {
+            max_depth: self.depth,
+            dirs_only: self.dirs_only,
+            show_size: self.size,
+            format: self.format,
+            jobs: self.jobs.unwrap_or(1),
+        }
+    }
+}
+
+/// Prefix marking a `diff` operand as a local file.
+const LOCAL_PREFIX: &str = "local:";
+
 #[derive(ClapArgs, Debug, Clone)]
 pub struct DiffArgs {
-    /// The left file path
+    /// The left file or directory path (`local:PATH` for a file on disk)
     #[arg(value_name = "LEFT", value_parser = parse_validated_path)]
     pub left: String,
 
-    /// The right file path
+    /// The right file or directory path (`local:PATH` for a file on disk)
     #[arg(value_name = "RIGHT", value_parser = parse_validated_path)]
     pub right: String,
 
+    /// Read RIGHT from the local filesystem instead of storage
+    #[arg(long)]
+    pub local: bool,
+
     /// Number of context lines to show around changes
     #[arg(short = 'U', long = "context", default_value_t = 3)]
     pub context: usize,
@@ -275,87 +1121,1334 @@ pub struct DiffArgs {
     /// Bypass size-limit check
     #[arg(short = 'f', long)]
     pub force: bool,
+
+    /// When diffing directories, compare the bytes of same-size objects whose etags
+    /// differ or are missing
+    #[arg(long)]
+    pub content: bool,
 }
 
 #[derive(ClapArgs, Debug, Clone)]
-pub struct TouchArgs {
-    /// Remote path(s) to touch (create if not exists)
-    #[arg(value_name = "PATH", value_parser = parse_validated_path)]
-    pub paths: Vec<String>,
-
-    /// Do not create files; succeed silently if they do not exist
-    #[arg(short = 'c', long = "no-create")]
-    pub no_create: bool,
-
-    /// Truncate existing files to zero length (dangerous)
-    #[arg(short = 't', long = "truncate")]
-    pub truncate: bool,
+pub struct VerifyArgs {
+    /// The local directory to check
+    #[arg(value_name = "LOCAL_DIR")]
+    pub local: PathBuf,
 
-    /// Create parent directories when needed (filesystem providers)
-    #[arg(short = 'p', long = "parents")]
-    pub parents: bool,
+    /// The remote prefix holding the same files at the same relative paths
+    #[arg(value_name = "REMOTE_PREFIX", value_parser = parse_validated_path)]
+    pub remote: String,
 }
 
-pub async fn execute(command: &Command, ctx: &CliContext) -> Result<()> {
-    let config = ctx.storage_config()?;
-    let client = StorageClient::new(config.clone()).await?;
+impl DiffArgs {
+    fn is_local(&self, path: &str, right: bool) -> bool {
+        path.starts_with(LOCAL_PREFIX) || (right && self.local)
+    }
 
-    match command {
-        Command::Ls(ls_args) => {
-            client
-                .list_directory(&ls_args.path, ls_args.long, ls_args.recursive)
-                .await?;
-        }
-        Command::Get(get_args) => {
-            client
-                .download_files(&get_args.remote, &get_args.local)
-                .await?;
+    /// Both operands, local ones read from disk.
+    pub fn sources(&self) -> Result<(DiffSource, DiffSource)> {
+        let source = |path: &str, right: bool| {
+            if self.is_local(path, right) {
+                let local = path.strip_prefix(LOCAL_PREFIX).unwrap_or(path);
+                DiffSource::Local(PathBuf::from(local))
+            } else {
+                DiffSource::Remote(path.to_string())
+            }
+        };
+        let sources = (source(&self.left, false), source(&self.right, true));
+        if let (DiffSource::Local(_), DiffSource::Local(_)) = &sources {
+            return Err(Error::InvalidArgument {
+                message: "diff needs at least one remote path".to_string(),
+            });
         }
-        Command::Du(du_args) => {
-            client.disk_usage(&du_args.path, du_args.summary).await?;
+        Ok(sources)
+    }
+
+    /// Operands that name objects in storage.
+    fn remote_paths_mut(&mut self) -> Vec<&mut String> {
+        let left_local = self.is_local(&self.left, false);
+        let right_local = self.is_local(&self.right, true);
+        let mut paths = Vec::new();
+        if !left_local {
+            paths.push(&mut self.left);
         }
-        Command::Put(put_args) => {
-            client
-                .upload_files(&put_args.local, &put_args.remote, put_args.recursive)
-                .await?;
+        if !right_local {
+            paths.push(&mut self.right);
         }
-        Command::Rm(rm_args) => {
-            if !rm_args.force {
-                let prompt = ctx.prompt();
-                let message = format_deletion_message(&rm_args.paths);
-                let confirmed = task::block_in_place(|| {
-                    Handle::current().block_on(prompt.confirm(&message, false))
-                })?;
+        paths
+    }
+}
 
-                if !confirmed {
-                    println!("Operation cancelled.");
-                    return Ok(());
-                }
+#[derive(ClapArgs, Debug, Clone)]
+pub struct VersionsArgs {
+    /// Object key, or a prefix ending with '/' to list all versions below it
+    #[arg(value_name = "PATH", value_parser = parse_validated_path)]
+    pub path: String,
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct RestoreArgs {
+    /// Object key to restore
+    #[arg(value_name = "PATH", value_parser = parse_validated_path)]
+    pub path: String,
+
+    /// Version to restore as the current object
+    #[arg(long = "version-id", value_name = "ID")]
+    pub version_id: String,
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct LnArgs {
+    /// Key the link points at (a prefix ending in '/' links a directory)
+    #[arg(value_name = "TARGET", value_parser = parse_validated_path)]
+    pub target: String,
+
+    /// Key of the link object to create or replace
+    #[arg(value_name = "ALIAS", value_parser = parse_validated_path)]
+    pub alias: String,
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct ChclassArgs {
+    /// Object key to transition
+    #[arg(value_name = "PATH", value_parser = parse_validated_path)]
+    pub path: String,
+
+    /// Target class: the provider's name (e.g. DEEP_ARCHIVE, ColdArchive) or GLACIER, IA, ARCHIVE
+    #[arg(long = "class", value_name = "CLASS")]
+    pub class: String,
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct TrashArgs {
+    #[command(subcommand)]
+    pub command: TrashCommand,
+}
+
+impl TrashArgs {
+    fn paths_mut(&mut self) -> Vec<&mut String> {
+        match &mut self.command {
+            TrashCommand::Restore(args) => args.paths.iter_mut().collect(),
+            TrashCommand::List | TrashCommand::Empty(_) => Vec::new(),
+        }
+    }
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum TrashCommand {
+    /// List trashed objects with their batch, size and original path
+    List,
+    /// Move trashed paths back to where they were removed from
+    Restore(TrashRestoreArgs),
+    /// Permanently delete trash batches
+    Empty(TrashEmptyArgs),
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct TrashRestoreArgs {
+    /// Original path(s) to restore
+    #[arg(value_name = "PATH", required = true, value_parser = parse_validated_path)]
+    pub paths: Vec<String>,
+
+    /// Restore from this batch (as printed by `trash list`) instead of the newest one
+    #[arg(long, value_name = "BATCH")]
+    pub batch: Option<String>,
+
+    /// Overwrite paths that exist again
+    #[arg(short = 'f', long)]
+    pub force: bool,
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct TrashEmptyArgs {
+    /// Only remove batches at least this old (e.g. 30m, 12h, 7d); everything by default
+    #[arg(long = "older-than", value_name = "DURATION", value_parser = super::config::parse_duration)]
+    pub older_than: Option<Duration>,
+
+    /// Print what would be removed without removing anything
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Empty without confirmation
+    #[arg(short = 'f', long)]
+    pub force: bool,
+}
+
+impl TrashEmptyArgs {
+    fn options(&self) -> EmptyTrashOptions {
+        EmptyTrashOptions {
+            older_than: self.older_than.unwrap_or_default(),
+            dry_run: self.dry_run,
+        }
+    }
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct GcArgs {
+    /// Prefix whose incomplete multipart uploads are aborted (defaults to the whole bucket)
+    #[arg(value_name = "PATH", value_parser = parse_validated_path)]
+    pub path: Option<String>,
+
+    /// Also remove orphaned staging files below this local directory (repeatable); without
+    /// PATH only local directories are swept
+    #[arg(long = "local", value_name = "DIR")]
+    pub local: Vec<PathBuf>,
+
+    /// Only remove leftovers at least this old (e.g. 30m, 12h, 7d)
+    #[arg(long = "older-than", value_name = "DURATION", default_value = "24h", value_parser = super::config::parse_duration)]
+    pub older_than: Duration,
+
+    /// Print what would be removed without removing anything
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+impl GcArgs {
+    /// Whether only local directories are swept.
+    pub fn local_only(&self) -> bool {
+        self.path.is_none() && !self.local.is_empty()
+    }
+
+    fn options(&self) -> GcOptions {
+        GcOptions {
+            older_than: self.older_than,
+            dry_run: self.dry_run,
+        }
+    }
+}
+
+/// Print the totals of a directory transfer, failing if any file did not make it.
+fn finish_transfer(report: &TransferReport) -> Result<()> {
+    if report.directory {
+        println!("{report}");
+    }
+    report.ensure_complete()
+}
+
+/// Remove staging files that crashed runs left below `dirs`.
+fn sweep_local(dirs: &[PathBuf], options: GcOptions) -> Result<()> {
+    let mut removed = 0;
+    for dir in dirs {
+        for path in stale_staging_files(dir, options.older_than)? {
+            if options.dry_run {
+                println!("Would remove {}", path.display());
+            } else {
+                std::fs::remove_file(&path)?;
+                println!("Removed {}", path.display());
             }
+            removed += 1;
+        }
+    }
+    let verb = if options.dry_run {
+        "Would remove"
+    } else {
+        "Removed"
+    };
+    println!("{verb} {removed} local staging file(s)");
+    Ok(())
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct TagArgs {
+    #[command(subcommand)]
+    pub command: TagCommand,
+}
+
+impl TagArgs {
+    fn path_mut(&mut self) -> &mut String {
+        match &mut self.command {
+            TagCommand::Set(args) => &mut args.path,
+            TagCommand::Get(args) => &mut args.path,
+            TagCommand::Rm(args) => &mut args.path,
+        }
+    }
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum TagCommand {
+    /// Add or overwrite tags; other tags of the object are kept
+    Set(TagSetArgs),
+    /// Print the tags of an object as key=value lines
+    Get(TagGetArgs),
+    /// Remove the given tag keys, or every tag when none are given
+    Rm(TagRmArgs),
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct TagSetArgs {
+    /// Object key to tag
+    #[arg(value_name = "PATH", value_parser = parse_validated_path)]
+    pub path: String,
+
+    /// Tags to set
+    #[arg(value_name = "KEY=VALUE", required = true, value_parser = parse_tag)]
+    pub tags: Vec<(String, String)>,
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct TagGetArgs {
+    /// Object key
+    #[arg(value_name = "PATH", value_parser = parse_validated_path)]
+    pub path: String,
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct TagRmArgs {
+    /// Object key
+    #[arg(value_name = "PATH", value_parser = parse_validated_path)]
+    pub path: String,
+
+    /// Tag keys to remove
+    #[arg(value_name = "KEY")]
+    pub keys: Vec<String>,
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct AclArgs {
+    #[command(subcommand)]
+    pub command: AclCommand,
+}
+
+impl AclArgs {
+    fn path_mut(&mut self) -> &mut String {
+        match &mut self.command {
+            AclCommand::Get(args) => &mut args.path,
+            AclCommand::Set(args) => &mut args.path,
+        }
+    }
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum AclCommand {
+    /// Print the ACL of an object and its grants
+    Get(AclGetArgs),
+    /// Replace the ACL of an object with a canned ACL
+    Set(AclSetArgs),
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct AclGetArgs {
+    /// Object key
+    #[arg(value_name = "PATH", value_parser = parse_validated_path)]
+    pub path: String,
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct AclSetArgs {
+    /// Object key
+    #[arg(value_name = "PATH", value_parser = parse_validated_path)]
+    pub path: String,
+
+    /// ACL to apply
+    #[arg(long, value_enum)]
+    pub acl: CannedAcl,
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct LifecycleArgs {
+    #[command(subcommand)]
+    pub command: LifecycleCommand,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum LifecycleCommand {
+    /// Print the bucket's lifecycle rules
+    Get(LifecycleGetArgs),
+    /// Replace the bucket's lifecycle rules with the rules of a JSON or TOML file
+    Set(LifecycleSetArgs),
+    /// Remove the given rule ids, or every rule when none are given
+    Rm(LifecycleRmArgs),
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct LifecycleGetArgs {
+    /// Print the rules as a JSON rule file that `lifecycle set` accepts
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct LifecycleSetArgs {
+    /// Rule file (`.json` or `.toml`)
+    #[arg(value_name = "FILE")]
+    pub file: PathBuf,
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct LifecycleRmArgs {
+    /// Rule ids to remove
+    #[arg(value_name = "ID")]
+    pub ids: Vec<String>,
+
+    /// Remove without asking for confirmation
+    #[arg(short = 'f', long)]
+    pub force: bool,
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct MpuArgs {
+    #[command(subcommand)]
+    pub command: MpuCommand,
+}
+
+impl MpuArgs {
+    fn prefix_mut(&mut self) -> &mut String {
+        match &mut self.command {
+            MpuCommand::Ls(args) => &mut args.prefix,
+            MpuCommand::Abort(args) => &mut args.prefix,
+        }
+    }
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum MpuCommand {
+    /// List incomplete multipart uploads, oldest first
+    Ls(MpuLsArgs),
+    /// Abort incomplete multipart uploads, freeing their stored parts
+    Abort(MpuAbortArgs),
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct MpuLsArgs {
+    /// Key prefix to list uploads under (defaults to the whole bucket)
+    #[arg(value_name = "PREFIX", default_value = "")]
+    pub prefix: String,
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct MpuAbortArgs {
+    /// Key prefix whose uploads are aborted; use '' for the whole bucket
+    #[arg(value_name = "PREFIX")]
+    pub prefix: String,
+
+    /// Only abort uploads started at least this long ago (e.g. 12h, 7d)
+    #[arg(long = "older-than", value_name = "DURATION", value_parser = super::config::parse_duration)]
+    pub older_than: Option<Duration>,
+
+    /// Print what would be aborted without aborting anything
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Abort without asking for confirmation
+    #[arg(short = 'f', long)]
+    pub force: bool,
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct BucketArgs {
+    #[command(subcommand)]
+    pub command: BucketCommand,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum BucketCommand {
+    /// Get or set the bucket policy
+    Policy(BucketPolicyArgs),
+    /// Get or set the bucket's CORS rules
+    Cors(BucketCorsArgs),
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct BucketPolicyArgs {
+    #[command(subcommand)]
+    pub command: BucketPolicyCommand,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum BucketPolicyCommand {
+    /// Print the bucket policy
+    Get,
+    /// Replace the bucket policy with the JSON document in a file
+    Set(BucketPolicySetArgs),
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct BucketPolicySetArgs {
+    /// Policy document (JSON)
+    #[arg(value_name = "FILE")]
+    pub file: PathBuf,
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct BucketCorsArgs {
+    #[command(subcommand)]
+    pub command: BucketCorsCommand,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum BucketCorsCommand {
+    /// Print the bucket's CORS rules
+    Get(BucketCorsGetArgs),
+    /// Replace the bucket's CORS rules with the rules of a JSON or TOML file
+    Set(BucketCorsSetArgs),
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct BucketCorsGetArgs {
+    /// Print the rules as a JSON rule file that `bucket cors set` accepts
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct BucketCorsSetArgs {
+    /// Rule file (`.json` or `.toml`)
+    #[arg(value_name = "FILE")]
+    pub file: PathBuf,
+}
+
+/// Parse `key=value`; the value may be empty, the key may not.
+fn parse_tag(raw: &str) -> Result<(String, String)> {
+    let invalid = |message: String| Error::InvalidArgument { message };
+    let (key, value) = raw
+        .split_once('=')
+        .ok_or_else(|| invalid(format!("expected KEY=VALUE, got '{raw}'")))?;
+    if key.is_empty() {
+        return Err(invalid(format!("empty tag key in '{raw}'")));
+    }
+    if key.chars().count() > MAX_TAG_KEY_LEN {
+        return Err(invalid(format!(
+            "tag key '{key}' is longer than {MAX_TAG_KEY_LEN} characters"
+        )));
+    }
+    if value.chars().count() > MAX_TAG_VALUE_LEN {
+        return Err(invalid(format!(
+            "value of tag '{key}' is longer than {MAX_TAG_VALUE_LEN} characters"
+        )));
+    }
+    Ok((key.to_string(), value.to_string()))
+}
+
+#[cfg(feature = "fuse")]
+#[derive(ClapArgs, Debug, Clone)]
+pub struct MountArgs {
+    /// Remote directory to mount (`/` for the whole bucket)
+    #[arg(value_name = "REMOTE", value_parser = parse_validated_path)]
+    pub path: String,
+
+    /// Local directory to mount on
+    #[arg(value_name = "MOUNTPOINT")]
+    pub mountpoint: PathBuf,
+
+    /// How long file attributes and lookups (including misses) are cached
+    #[arg(long = "attr-ttl", value_name = "DURATION", default_value = "60s", value_parser = super::config::parse_duration)]
+    pub attr_ttl: Duration,
+
+    /// Let other users access the mount (non-root needs `user_allow_other` in /etc/fuse.conf)
+    #[arg(long)]
+    pub allow_other: bool,
+}
+
+#[cfg(feature = "fuse")]
+impl MountArgs {
+    fn options(&self) -> crate::storage::MountOptions {
+        crate::storage::MountOptions {
+            attr_ttl: self.attr_ttl,
+            allow_other: self.allow_other,
+        }
+    }
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct ServeArgs {
+    /// Address to listen on
+    #[arg(long, value_name = "ADDR", default_value = "127.0.0.1:8080")]
+    pub addr: std::net::SocketAddr,
+
+    /// Remote prefix served as `/`
+    #[arg(long, value_name = "REMOTE", default_value = "/", value_parser = parse_validated_path)]
+    pub prefix: String,
+
+    /// List directories that have no index.html
+    #[arg(long, conflicts_with = "s3")]
+    pub listings: bool,
+
+    /// Accept PUT uploads that present this bearer token
+    #[arg(
+        long,
+        value_name = "TOKEN",
+        env = "STORIFY_SERVE_TOKEN",
+        hide_env_values = true,
+        conflicts_with = "s3"
+    )]
+    pub token: Option<String>,
+
+    /// Speak the S3 API (path-style) instead of plain HTTP
+    #[arg(long)]
+    pub s3: bool,
+
+    /// Bucket name S3 clients address
+    #[arg(long, value_name = "NAME", default_value = "storify", requires = "s3")]
+    pub bucket: String,
+
+    /// Access key id S3 requests must be signed with; without a key pair the bucket is read-only
+    #[arg(
+        long,
+        value_name = "ID",
+        env = "STORIFY_SERVE_ACCESS_KEY_ID",
+        requires_all = ["s3", "secret_access_key"]
+    )]
+    pub access_key_id: Option<String>,
+
+    /// Secret access key S3 requests must be signed with
+    #[arg(
+        long,
+        value_name = "SECRET",
+        env = "STORIFY_SERVE_SECRET_ACCESS_KEY",
+        hide_env_values = true,
+        requires_all = ["s3", "access_key_id"]
+    )]
+    pub secret_access_key: Option<String>,
+}
+
+impl ServeArgs {
+    fn options(&self) -> crate::server::ServeOptions {
+        crate::server::ServeOptions {
+            addr: self.addr,
+            prefix: self.prefix.clone(),
+            listings: self.listings,
+            token: self.token.clone().filter(|token| !token.is_empty()),
+            s3: self.s3.then(|| crate::server::S3Options {
+                bucket: self.bucket.clone(),
+                credentials: self
+                    .access_key_id
+                    .clone()
+                    .zip(self.secret_access_key.clone())
+                    .map(
+                        |(access_key_id, secret_access_key)| crate::server::S3Credentials {
+                            access_key_id,
+                            secret_access_key,
+                        },
+                    ),
+            }),
+        }
+    }
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+pub struct IndexArgs {
+    #[command(subcommand)]
+    pub command: Option<IndexCommand>,
+
+    /// Prefix to export into the index
+    #[arg(value_name = "PATH", required = true, value_parser = parse_validated_path)]
+    pub path: Option<String>,
+
+    /// SQLite file to create
+    #[arg(short = 'o', long, value_name = "FILE", default_value = "index.db")]
+    pub output: PathBuf,
+
+    /// Replace the output file if it already exists
+    #[arg(short = 'f', long)]
+    pub force: bool,
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct InventoryArgs {
+    /// Prefix to list
+    #[arg(value_name = "PREFIX", value_parser = parse_validated_path)]
+    pub path: String,
+
+    /// Manifest file to write; .csv or .jsonl picks the format
+    #[arg(short = 'o', long, value_name = "FILE")]
+    pub output: PathBuf,
+
+    /// Manifest format, overriding the output extension
+    #[arg(long, value_enum)]
+    pub format: Option<InventoryFormat>,
+
+    /// Replace the output file if it already exists
+    #[arg(short = 'f', long)]
+    pub force: bool,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum IndexCommand {
+    /// Run a prebuilt query against an existing index (no storage access)
+    Query(IndexQueryArgs),
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct IndexQueryArgs {
+    /// Query to run
+    #[arg(value_enum, value_name = "QUERY")]
+    pub query: IndexQuery,
+
+    /// SQLite index file to read
+    #[arg(short = 'i', long, value_name = "FILE", default_value = "index.db")]
+    pub index: PathBuf,
+
+    /// Maximum number of rows to print
+    #[arg(short = 'n', long, default_value_t = 20)]
+    pub limit: usize,
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct ApplyArgs {
+    /// Job spec file (TOML)
+    #[arg(value_name = "FILE")]
+    pub file: PathBuf,
+
+    /// Validate the spec and print what each step would do without changing anything
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct TouchArgs {
+    /// Remote path(s) to touch (create if not exists)
+    #[arg(value_name = "PATH", value_parser = parse_validated_path)]
+    pub paths: Vec<String>,
+
+    /// Do not create files; succeed silently if they do not exist
+    #[arg(short = 'c', long = "no-create")]
+    pub no_create: bool,
+
+    /// Truncate existing files to zero length (dangerous)
+    #[arg(short = 't', long = "truncate")]
+    pub truncate: bool,
+
+    /// Create parent directories when needed (filesystem providers)
+    #[arg(short = 'p', long = "parents")]
+    pub parents: bool,
+
+    /// Set the modification time: RFC 3339, a date like 2024-05-01, or an age like 2h
+    #[arg(short = 'd', long = "date", value_name = "TIME", value_parser = parse_since, conflicts_with = "reference")]
+    pub date: Option<DateTime<Utc>>,
+
+    /// Use the modification time of this remote file instead of the current time
+    #[arg(long, value_name = "PATH", value_parser = parse_validated_path)]
+    pub reference: Option<String>,
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct AppendArgs {
+    /// The remote file to append to (created when missing)
+    #[arg(value_name = "REMOTE", value_parser = parse_validated_path)]
+    pub remote: String,
+
+    /// Local files to append in order, or `-` (the default) to read standard input
+    #[arg(value_name = "LOCAL", default_value = "-", value_parser = parse_validated_path)]
+    pub locals: Vec<String>,
+
+    /// Write SEP between consecutive sources; `\n`, `\t`, `\r`, `\0` and `\\` are unescaped
+    #[arg(long, value_name = "SEP", value_parser = parse_separator, default_value = "")]
+    pub separator: String,
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct ConcatArgs {
+    /// The object to write (replaced if it exists)
+    #[arg(value_name = "DEST", value_parser = parse_validated_path)]
+    pub dest: String,
+
+    /// Objects to concatenate, in order
+    #[arg(value_name = "SOURCE", required = true, value_parser = parse_validated_path)]
+    pub sources: Vec<String>,
+}
+
+/// Parse a separator with C-style escapes, so `--separator '\n'` works without `$'...'`.
+fn parse_separator(input: &str) -> Result<String> {
+    let mut separator = String::new();
+    let mut chars = input.chars();
+    while let Some(c) = chars.next() {
+        separator.push(match c {
+            '\\' => match chars.next() {
+                Some('n') => '\n',
+                Some('t') => '\t',
+                Some('r') => '\r',
+                Some('0') => '\0',
+                Some('\\') => '\\',
+                other => {
+                    return Err(Error::InvalidArgument {
+                        message: format!(
+                            "invalid escape '\\{}' in separator (expected \\n, \\t, \\r, \\0 or \\\\)",
+                            other.map(String::from).unwrap_or_default()
+                        ),
+                    });
+                }
+            },
+            c => c,
+        });
+    }
+    Ok(separator)
+}
+
+pub async fn execute(command: &Command, ctx: &CliContext) -> Result<()> {
+    // Index queries only read a local SQLite file and never touch storage.
+    if let Command::Index(IndexArgs {
+        command: Some(IndexCommand::Query(query_args)),
+        ..
+    }) = command
+    {
+        return crate::index::run_query(&query_args.index, query_args.query, query_args.limit);
+    }
+    if let Command::Gc(gc_args) = command
+        && !gc_args.local.is_empty()
+    {
+        sweep_local(&gc_args.local, gc_args.options())?;
+        if gc_args.local_only() {
+            return Ok(());
+        }
+    }
+
+    let started = Instant::now();
+    let counters = TransferCounters::default();
+    let result = run_routed(command, ctx, &counters).await;
+
+    if ctx.global_options().stats {
+        eprintln!("{}", counters.summary(started.elapsed()));
+    }
+    if let Some(path) = ctx.global_options().metrics_textfile.as_deref() {
+        let metrics = RunMetrics {
+            task: task_from_path(path),
+            command: command.name().to_string(),
+            success: result.is_ok(),
+            duration: started.elapsed(),
+            read_bytes: counters.read_bytes(),
+            written_bytes: counters.written_bytes(),
+            operation_errors: counters.operation_errors(),
+            finished_at: SystemTime::now(),
+        };
+        // A metrics failure must not mask the outcome of the command itself.
+        if let Err(err) = metrics.write_textfile(path) {
+            tracing::warn!("failed to write metrics to {}: {err}", path.display());
+        }
+    }
+    result
+}
+
+/// Run `command` once per bucket its paths address, building one client per bucket.
+async fn run_routed(
+    command: &Command,
+    ctx: &CliContext,
+    counters: &TransferCounters,
+) -> Result<()> {
+    let routes = route_command(command, ctx.resolved().storage.as_ref())?;
+    let recorder = ctx
+        .global_options()
+        .record
+        .as_deref()
+        .map(Recorder::create)
+        .transpose()?;
+    let limit = ctx
+        .global_options()
+        .max_concurrency
+        .map(ConcurrencyLimit::new);
+    let timer = command.timing().then(RequestTimer::default);
+    let job = if command.is_job() {
+        let job = JobRegistry::open()?.register(job_description())?;
+        tracing::debug!("registered job id={}", job.id());
+        Some(job)
+    } else {
+        None
+    };
+    let run = async {
+        for (target, routed) in &routes {
+            let mut config = match target {
+                Some(target) => {
+                    target.config(ctx.resolved().storage.as_ref(), ctx.global_options())?
+                }
+                None => ctx.storage_config()?.clone(),
+            };
+            if let Command::Put(put_args) = routed {
+                if let Some(class) = &put_args.storage_class {
+                    config.storage_class = Some(class.clone());
+                }
+                if let Some(encryption) = put_args.encryption() {
+                    config.encryption = Some(encryption);
+                }
+            }
+            apply_request_overrides(ctx.global_options(), &mut config);
+            let routed = &apply_profile_defaults(routed, &config);
+            if config.provider == StorageProvider::Fs {
+                ensure_within_root(routed)?;
+            }
+            if let Some(recorder) = &recorder {
+                recorder.begin(&config, command.name())?;
+            }
+            let span = tracing::info_span!(
+                "command",
+                command = routed.name(),
+                provider = config.provider.as_str(),
+                bucket = %config.bucket
+            );
+            let mut client = StorageClient::with_counters(config, counters.clone()).await?;
+            if let Some(recorder) = &recorder {
+                client = client.with_http_client(recorder.http_client());
+            } else if let Some(replayer) = ctx.replayer() {
+                client = client.with_http_client(replayer.http_client());
+            }
+            if let Some(timer) = &timer {
+                client = client.with_request_timer(timer.clone());
+            }
+            if let Some(limit) = &limit {
+                client = client.with_concurrency_limit(limit.clone());
+            }
+            if let Some(size) = ctx.global_options().page_size {
+                client = client.with_page_size(PageSize::Fixed(size));
+            }
+            if let Some(job) = &job {
+                client = client.with_cancel_token(job.token());
+            }
+            run_command(routed, ctx, &client).instrument(span).await?;
+        }
+        Ok(())
+    };
+
+    if let Some(job) = &job {
+        return job.run(run).await;
+    }
+    let result = run.await;
+    if let Some(timer) = &timer {
+        timer.report();
+    }
+    result
+}
+
+/// Apply per-profile command defaults the command line did not override (`trash`,
+/// `default_jobs`, `human_readable`).
+fn apply_profile_defaults(command: &Command, config: &StorageConfig) -> Command {
+    let mut command = command.clone();
+    match &mut command {
+        Command::Rm(args) if config.trash && !args.no_trash && args.version_id.is_none() => {
+            args.trash = true;
+        }
+        Command::Get(args) => args.concurrency = args.concurrency.or(config.default_jobs),
+        Command::Put(args) => args.concurrency = args.concurrency.or(config.default_jobs),
+        Command::Grep(args) => args.concurrency = args.concurrency.or(config.default_jobs),
+        Command::Ls(args) => args.jobs = args.jobs.or(config.default_jobs),
+        Command::Du(args) => {
+            args.jobs = args.jobs.or(config.default_jobs);
+            args.human_readable |= config.human_readable;
+        }
+        Command::Tree(args) => args.jobs = args.jobs.or(config.default_jobs),
+        _ => {}
+    }
+    command
+}
+
+/// Retry, rate, timeout, pool and header flags take precedence over the profile.
+fn apply_request_overrides(global: &GlobalOptions, config: &mut StorageConfig) {
+    if let Some(retries) = global.retries {
+        config.retries = Some(retries);
+    }
+    if let Some(backoff) = global.retry_backoff {
+        config.retry_backoff_ms = Some(backoff);
+    }
+    if let Some(rate_limit) = global.rate_limit {
+        config.rate_limit = Some(rate_limit);
+    }
+    if let Some(timeout) = global.timeout {
+        config.timeout_secs = Some(timeout);
+    }
+    if let Some(timeout) = global.connect_timeout {
+        config.connect_timeout_secs = Some(timeout);
+    }
+    if let Some(pool_size) = global.pool_size {
+        config.pool_size = Some(pool_size);
+    }
+    if global.request_payer {
+        config.request_payer = true;
+    }
+    for (name, value) in &global.headers {
+        config
+            .headers
+            .retain(|(existing, _)| !existing.eq_ignore_ascii_case(name));
+        config.headers.push((name.clone(), value.clone()));
+    }
+}
+
+/// Bucket addressed by a `scheme://bucket/key` path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct BucketTarget {
+    /// Scheme as written; selects provider-specific environment keys (e.g. `minio`)
+    scheme: String,
+    provider: StorageProvider,
+    bucket: String,
+}
+
+impl BucketTarget {
+    /// Reuse the active config's credentials for another bucket of the same provider, or
+    /// build one from environment credentials otherwise. HTTP hosts never share credentials.
+    fn config(
+        &self,
+        active: Option<&StorageConfig>,
+        global: &GlobalOptions,
+    ) -> Result<StorageConfig> {
+        if global.anonymous {
+            return anonymous_config(&self.scheme, &self.bucket, global.endpoint.clone());
+        }
+        match active {
+            Some(active)
+                if active.provider == self.provider && self.provider != StorageProvider::Http =>
+            {
+                let mut config = active.clone();
+                config.bucket = self.bucket.clone();
+                prepare_storage_config(&mut config)?;
+                Ok(config)
+            }
+            _ => ad_hoc_config(&self.scheme, &self.bucket),
+        }
+    }
+
+    fn is_active(&self, active: Option<&StorageConfig>) -> bool {
+        active
+            .is_some_and(|config| config.provider == self.provider && config.bucket == self.bucket)
+    }
+}
+
+/// Split a `scheme://bucket/key` path into its bucket and the key within it.
+///
+/// Returns `None` for plain paths, which address the active config.
+fn parse_uri(path: &str) -> Option<Result<(BucketTarget, String)>> {
+    let (scheme, rest) = path.split_once("://")?;
+    // `gs` is only a URI spelling; the environment loader knows the provider as `gcs`.
+    let scheme = match scheme.to_ascii_lowercase().as_str() {
+        "gs" => "gcs".to_string(),
+        lower => lower.to_string(),
+    };
+    let provider = match scheme.as_str() {
+        "fs" | "hdfs" => Err(Error::InvalidPath {
+            path: format!("{path} ({scheme}:// has no buckets; use a profile instead)"),
+        }),
+        other => StorageProvider::from_str(other),
+    };
+    let parsed = provider.and_then(|provider| {
+        let (bucket, key) = rest.split_once('/').unwrap_or((rest, ""));
+        if bucket.is_empty() {
+            return Err(Error::InvalidPath {
+                path: path.to_string(),
+            });
+        }
+        let key = if key.is_empty() { "/" } else { key };
+        Ok((
+            BucketTarget {
+                scheme: scheme.clone(),
+                provider,
+                bucket: bucket.to_string(),
+            },
+            key.to_string(),
+        ))
+    });
+    Some(parsed)
+}
+
+/// Remote path arguments of a command; local paths of `get`/`put` are not included.
+fn remote_paths_mut(command: &mut Command) -> Vec<&mut String> {
+    match command {
+        Command::Ls(args) => vec![&mut args.path],
+        Command::Get(args) => vec![&mut args.remote],
+        Command::Du(args) => vec![&mut args.path],
+        Command::Put(args) => vec![&mut args.remote],
+        Command::Rm(args) => args.paths.iter_mut().collect(),
+        Command::Cp(args) => vec![&mut args.src_path, &mut args.dest_path],
+        Command::Mv(args) => vec![&mut args.src_path, &mut args.dest_path],
+        Command::Rename(args) => vec![&mut args.pattern],
+        Command::Mkdir(args) => vec![&mut args.path],
+        Command::Chmod(args) => vec![&mut args.path],
+        #[cfg(feature = "hdfs")]
+        Command::Chown(args) => vec![&mut args.path],
+        Command::Stat(args) => vec![&mut args.path],
+        Command::Cat(args) => args.paths.iter_mut().collect(),
+        Command::Head(args) => args.paths.iter_mut().collect(),
+        Command::Tail(args) => args.paths.iter_mut().collect(),
+        Command::Grep(args) => vec![&mut args.path],
+        Command::Find(args) => vec![&mut args.path],
+        Command::Tree(args) => vec![&mut args.path],
+        Command::Diff(args) => args.remote_paths_mut(),
+        Command::Verify(args) => vec![&mut args.remote],
+        Command::Touch(args) => args.paths.iter_mut().chain(&mut args.reference).collect(),
+        Command::Append(args) => vec![&mut args.remote],
+        Command::Concat(args) => std::iter::once(&mut args.dest)
+            .chain(&mut args.sources)
+            .collect(),
+        Command::Versions(args) => vec![&mut args.path],
+        Command::Restore(args) => vec![&mut args.path],
+        Command::Chclass(args) => vec![&mut args.path],
+        Command::Ln(args) => vec![&mut args.target, &mut args.alias],
+        Command::Gc(args) => args.path.iter_mut().collect(),
+        Command::Trash(args) => args.paths_mut(),
+        Command::Tag(args) => vec![args.path_mut()],
+        Command::Acl(args) => vec![args.path_mut()],
+        Command::Mpu(args) => vec![args.prefix_mut()],
+        Command::Index(args) => args.path.iter_mut().collect(),
+        Command::Inventory(args) => vec![&mut args.path],
+        #[cfg(feature = "fuse")]
+        Command::Mount(args) => vec![&mut args.path],
+        Command::Serve(args) => vec![&mut args.prefix],
+        Command::Watch(args) => vec![&mut args.remote],
+        Command::Sync(args) => vec![&mut args.remote],
+        Command::Apply(_)
+        | Command::Batch(_)
+        | Command::Lifecycle(_)
+        | Command::Bucket(_)
+        | Command::Undo(_)
+        | Command::Config(_)
+        | Command::Env(_)
+        | Command::Jobs(_)
+        | Command::Schema(_)
+        | Command::SelfUpdate(_)
+        | Command::Alias(_) => Vec::new(),
+    }
+}
+
+/// Reject remote paths whose `..` segments would reach outside an fs backend's root.
+fn ensure_within_root(command: &Command) -> Result<()> {
+    let mut command = command.clone();
+    for path in remote_paths_mut(&mut command) {
+        if normalize_under_root(path).is_none() {
+            return Err(Error::PathEscapesRoot { path: path.clone() });
+        }
+    }
+    Ok(())
+}
+
+/// Path list of commands that treat each path independently and can be split per bucket.
+fn independent_paths_mut(command: &mut Command) -> Option<&mut Vec<String>> {
+    match command {
+        Command::Rm(args) => Some(&mut args.paths),
+        Command::Head(args) => Some(&mut args.paths),
+        Command::Tail(args) => Some(&mut args.paths),
+        // A reference file lives in one bucket, so its paths must too.
+        Command::Touch(args) if args.reference.is_none() => Some(&mut args.paths),
+        _ => None,
+    }
+}
+
+/// Whether every remote path is a `scheme://bucket/key` URI, so no profile is needed.
+pub fn addresses_only_uris(command: &Command) -> bool {
+    let mut command = command.clone();
+    let paths = remote_paths_mut(&mut command);
+    !paths.is_empty() && paths.iter().all(|path| path.contains("://"))
+}
+
+/// Rewrite URI paths to plain keys and group the command by the bucket they address.
+///
+/// `None` stands for the active config. Paths naming the active bucket stay in that group.
+fn route_command(
+    command: &Command,
+    active: Option<&StorageConfig>,
+) -> Result<Vec<(Option<BucketTarget>, Command)>> {
+    let mut command = command.clone();
+    let mut targets = Vec::new();
+    for path in remote_paths_mut(&mut command) {
+        let target = match parse_uri(path) {
+            Some(parsed) => {
+                let (target, key) = parsed?;
+                *path = key;
+                Some(target).filter(|target| !target.is_active(active))
+            }
+            None => None,
+        };
+        targets.push(target);
+    }
+
+    let mut distinct: Vec<Option<BucketTarget>> = Vec::new();
+    for target in &targets {
+        if !distinct.contains(target) {
+            distinct.push(target.clone());
+        }
+    }
+    if distinct.len() <= 1 {
+        return Ok(vec![(distinct.pop().flatten(), command)]);
+    }
+
+    let Some(paths) = independent_paths_mut(&mut command) else {
+        return Err(Error::InvalidArgument {
+            message: format!(
+                "`{}` cannot combine paths from different buckets",
+                command.name()
+            ),
+        });
+    };
+    let paths = std::mem::take(paths);
+    Ok(distinct
+        .into_iter()
+        .map(|target| {
+            let mut routed = command.clone();
+            if let Some(group) = independent_paths_mut(&mut routed) {
+                *group = paths
+                    .iter()
+                    .zip(&targets)
+                    .filter(|(_, path_target)| **path_target == target)
+                    .map(|(path, _)| path.clone())
+                    .collect();
+            }
+            (target, routed)
+        })
+        .collect())
+}
+
+/// Command line recorded for `storify jobs list` and `storify undo`.
+fn job_description() -> String {
+    std::env::args().skip(1).collect::<Vec<_>>().join(" ")
+}
+
+async fn run_command(command: &Command, ctx: &CliContext, client: &StorageClient) -> Result<()> {
+    match command {
+        Command::Ls(ls_args) => {
+            client
+                .list_directory(&ls_args.path, ls_args.list_options())
+                .await?;
+        }
+        Command::Get(get_args) => {
+            let mut remote = get_args.selector.resolve(client, &get_args.remote).await?;
+            if get_args.follow_links {
+                remote = client.resolve_links(&remote).await?;
+            }
+            let report = client
+                .download_files(
+                    &remote,
+                    &get_args.local,
+                    get_args.version_id.as_deref(),
+                    &get_args.download_options()?,
+                )
+                .await?;
+            finish_transfer(&report)?;
+        }
+        Command::Du(du_args) => {
             client
-                .delete_files(&rm_args.paths, rm_args.recursive)
+                .disk_usage(&du_args.path, du_args.usage_options())
                 .await?;
         }
+        Command::Put(put_args) => {
+            // Only a single overwritten object is kept for undo, not whole trees.
+            let snapshot = if put_args.recursive || put_args.extract || put_args.dry_run {
+                None
+            } else {
+                let target = match put_args.local.as_str() {
+                    "-" => put_args.remote.clone(),
+                    local => transfer_target(local, &put_args.remote),
+                };
+                Snapshot::take(client, &[target], false).await
+            };
+            let result = client
+                .upload_files(
+                    &put_args.local,
+                    &put_args.remote,
+                    put_args.recursive,
+                    &put_args.upload_options(*ctx.prompt())?,
+                )
+                .await;
+            if let Some(snapshot) = snapshot
+                && result.is_ok()
+            {
+                snapshot.record(client, job_description());
+            }
+            finish_transfer(&result?)?;
+        }
+        Command::Rm(rm_args) => {
+            if !rm_args.force {
+                let prompt = ctx.prompt();
+                let message = format_deletion_message(&rm_args.paths);
+                let confirmed = task::block_in_place(|| {
+                    Handle::current().block_on(prompt.confirm(&message, false))
+                })?;
+
+                if !confirmed {
+                    println!("Operation cancelled.");
+                    return Ok(());
+                }
+            }
+            if rm_args.trash {
+                let batch = trash_batch_name(Utc::now());
+                let result = client
+                    .trash_files(&rm_args.paths, &batch, rm_args.recursive)
+                    .await;
+                // Recorded even after a partial failure: undo restores what did get trashed.
+                let change = Change::Trashed {
+                    batch,
+                    paths: rm_args.paths.clone(),
+                };
+                undo::record(client, job_description(), change);
+                result?;
+            } else {
+                let snapshot = match rm_args.version_id {
+                    Some(_) => None,
+                    None => Snapshot::take(client, &rm_args.paths, rm_args.recursive).await,
+                };
+                let result = client
+                    .delete_files(
+                        &rm_args.paths,
+                        rm_args.recursive,
+                        rm_args.version_id.as_deref(),
+                        rm_args.allow_root,
+                    )
+                    .await;
+                if let Some(snapshot) = snapshot {
+                    snapshot.record(client, job_description());
+                }
+                result?;
+            }
+        }
         Command::Cp(cp_args) => {
+            let clobber = cp_args.clobber.clobber(*ctx.prompt());
             client
-                .copy_files(&cp_args.src_path, &cp_args.dest_path)
+                .copy_files(&cp_args.src_path, &cp_args.dest_path, &clobber)
                 .await?;
         }
         Command::Mv(mv_args) => {
+            let clobber = mv_args.clobber.clobber(*ctx.prompt());
+            let moved_all = client
+                .move_files(
+                    &mv_args.src_path,
+                    &mv_args.dest_path,
+                    &clobber,
+                    !mv_args.no_verify,
+                )
+                .await?;
+            // Undoing a partial move would take the kept destinations back too.
+            if moved_all {
+                let change = Change::Moved {
+                    from: mv_args.src_path.clone(),
+                    to: transfer_target(&mv_args.src_path, &mv_args.dest_path),
+                };
+                undo::record(client, job_description(), change);
+            }
+        }
+        Command::Rename(rename_args) => {
             client
-                .move_files(&mv_args.src_path, &mv_args.dest_path)
+                .rename_matching(
+                    &rename_args.pattern,
+                    &rename_args.replace,
+                    rename_args.dry_run,
+                )
                 .await?;
         }
         Command::Mkdir(mkdir_args) => {
             client
-                .create_directory(&mkdir_args.path, mkdir_args.parents)
+                .create_directory(&mkdir_args.path, mkdir_args.parents, mkdir_args.mode)
                 .await?;
         }
-        Command::Cat(cat_args) => {
+        Command::Chmod(chmod_args) => {
+            client
+                .change_mode(&chmod_args.path, chmod_args.mode, chmod_args.recursive)
+                .await?;
+        }
+        #[cfg(feature = "hdfs")]
+        Command::Chown(chown_args) => {
+            let (owner, group) = crate::storage::parse_owner(&chown_args.owner)?;
             client
-                .cat_file(&cat_args.path, cat_args.force, cat_args.size_limit_mb)
+                .change_owner(
+                    &chown_args.path,
+                    owner.as_deref(),
+                    group.as_deref(),
+                    chown_args.recursive,
+                )
                 .await?;
         }
+        Command::Cat(cat_args) => {
+            let mut paths = Vec::with_capacity(cat_args.paths.len());
+            for path in &cat_args.paths {
+                let mut path = cat_args.selector.resolve(client, path).await?;
+                if cat_args.follow_links {
+                    path = client.resolve_links(&path).await?;
+                }
+                paths.push(path);
+            }
+            client.cat_files(&paths, &cat_args.cat_options()).await?;
+        }
         Command::Head(head_args) => {
             if head_args.paths.len() <= 1 {
                 let path = head_args.paths.first().ok_or_else(|| Error::InvalidPath {
@@ -377,8 +2470,12 @@ pub async fn execute(command: &Command, ctx: &CliContext) -> Result<()> {
             }
         }
         Command::Tail(tail_args) => {
-            if tail_args.paths.len() <= 1 {
-                let path = tail_args.paths.first().ok_or_else(|| Error::InvalidPath {
+            let mut paths = Vec::with_capacity(tail_args.paths.len());
+            for path in &tail_args.paths {
+                paths.push(tail_args.selector.resolve(client, path).await?);
+            }
+            if paths.len() <= 1 {
+                let path = paths.first().ok_or_else(|| Error::InvalidPath {
                     path: "".to_string(),
                 })?;
                 client
@@ -387,7 +2484,7 @@ pub async fn execute(command: &Command, ctx: &CliContext) -> Result<()> {
             } else {
                 client
                     .tail_files(
-                        &tail_args.paths,
+                        &paths,
                         tail_args.lines,
                         tail_args.bytes,
                         tail_args.quiet,
@@ -404,15 +2501,36 @@ pub async fn execute(command: &Command, ctx: &CliContext) -> Result<()> {
             } else {
                 OutputFormat::Human
             };
-            client.stat_metadata(&stat_args.path, format).await?;
+            let path = if stat_args.follow_links {
+                client.resolve_links(&stat_args.path).await?
+            } else {
+                stat_args.path.clone()
+            };
+            client
+                .stat_metadata(
+                    &path,
+                    stat_args.version_id.as_deref(),
+                    format,
+                    stat_args.shallow,
+                    stat_args.format.as_ref(),
+                )
+                .await?;
+        }
+        Command::Grep(grep_args) if grep_args.follow => {
+            client
+                .follow_grep(
+                    &grep_args.path,
+                    &grep_args.pattern,
+                    &grep_args.follow_options(),
+                )
+                .await?;
         }
         Command::Grep(grep_args) => {
             client
                 .grep_path(
                     &grep_args.path,
                     &grep_args.pattern,
-                    grep_args.ignore_case,
-                    grep_args.line_number,
+                    &grep_args.grep_options()?,
                     grep_args.recursive,
                 )
                 .await?;
@@ -422,20 +2540,46 @@ pub async fn execute(command: &Command, ctx: &CliContext) -> Result<()> {
         }
         Command::Tree(tree_args) => {
             client
-                .print_tree(&tree_args.path, tree_args.depth, tree_args.dirs_only)
+                .print_tree(&tree_args.path, tree_args.tree_options())
                 .await?;
         }
         Command::Diff(diff_args) => {
+            let (left, right) = diff_args.sources()?;
+            let directories = match (&left, &right) {
+                (DiffSource::Remote(left), DiffSource::Remote(right)) => {
+                    client.both_directories(left, right).await?
+                }
+                _ => false,
+            };
+            if directories {
+                let options = DirDiffOptions {
+                    content: diff_args.content,
+                };
+                client
+                    .diff_directories(&diff_args.left, &diff_args.right, options)
+                    .await?;
+            } else if diff_args.content {
+                return Err(Error::InvalidArgument {
+                    message: "--content only applies when diffing two directories".to_string(),
+                });
+            } else {
+                client
+                    .diff_files(
+                        &left,
+                        &right,
+                        diff_args.context,
+                        diff_args.ignore_space,
+                        diff_args.size_limit_mb,
+                        diff_args.force,
+                    )
+                    .await?;
+            }
+        }
+        Command::Verify(verify_args) => {
             client
-                .diff_files(
-                    &diff_args.left,
-                    &diff_args.right,
-                    diff_args.context,
-                    diff_args.ignore_space,
-                    diff_args.size_limit_mb,
-                    diff_args.force,
-                )
-                .await?;
+                .verify_directory(&verify_args.local, &verify_args.remote)
+                .await?
+                .ensure_verified()?;
         }
         Command::Touch(touch_args) => {
             if touch_args.paths.is_empty() {
@@ -443,17 +2587,239 @@ pub async fn execute(command: &Command, ctx: &CliContext) -> Result<()> {
                     message: "missing PATH".to_string(),
                 });
             }
-            client
+            let snapshot = if touch_args.truncate {
+                Snapshot::take(client, &touch_args.paths, false).await
+            } else {
+                None
+            };
+            let mtime = match &touch_args.reference {
+                Some(reference) => Some(client.reference_mtime(reference).await?),
+                None => touch_args.date.map(SystemTime::from),
+            };
+            let result = client
                 .touch_files(
                     &touch_args.paths,
                     touch_args.no_create,
                     touch_args.truncate,
                     touch_args.parents,
+                    mtime,
                 )
+                .await;
+            if let Some(snapshot) = snapshot {
+                snapshot.record(client, job_description());
+            }
+            result?;
+        }
+        Command::Append(append_args) => {
+            let snapshot =
+                Snapshot::take(client, std::slice::from_ref(&append_args.remote), false).await;
+            let result = client
+                .append_files(
+                    &append_args.locals,
+                    &append_args.remote,
+                    append_args.separator.as_bytes(),
+                )
+                .await;
+            if let Some(snapshot) = snapshot
+                && result.is_ok()
+            {
+                snapshot.record(client, job_description());
+            }
+            result?;
+        }
+        Command::Concat(concat_args) => {
+            let snapshot =
+                Snapshot::take(client, std::slice::from_ref(&concat_args.dest), false).await;
+            let result = client
+                .concat_objects(&concat_args.dest, &concat_args.sources)
+                .await;
+            if let Some(snapshot) = snapshot
+                && result.is_ok()
+            {
+                snapshot.record(client, job_description());
+            }
+            result?;
+        }
+        Command::Versions(versions_args) => {
+            client.list_versions(&versions_args.path).await?;
+        }
+        Command::Restore(restore_args) => {
+            client
+                .restore_version(&restore_args.path, &restore_args.version_id)
                 .await?;
         }
-        Command::Config(_) => {
-            unreachable!("Config commands are handled separately")
+        Command::Chclass(chclass_args) => {
+            client
+                .change_storage_class(&chclass_args.path, &chclass_args.class)
+                .await?;
+        }
+        Command::Ln(ln_args) => {
+            client.create_link(&ln_args.target, &ln_args.alias).await?;
+        }
+        Command::Gc(gc_args) => {
+            let path = gc_args.path.as_deref().unwrap_or("/");
+            client.collect_garbage(path, gc_args.options()).await?;
+        }
+        Command::Trash(trash_args) => match &trash_args.command {
+            TrashCommand::List => client.list_trash().await?,
+            TrashCommand::Restore(args) => {
+                client
+                    .restore_from_trash(&args.paths, args.batch.as_deref(), args.force)
+                    .await?
+            }
+            TrashCommand::Empty(args) => {
+                if !args.force && !args.dry_run {
+                    let prompt = ctx.prompt();
+                    let message = match args.older_than {
+                        Some(_) => "Permanently delete old trash batches?",
+                        None => "Permanently delete everything in the trash?",
+                    };
+                    let confirmed = task::block_in_place(|| {
+                        Handle::current().block_on(prompt.confirm(message, false))
+                    })?;
+                    if !confirmed {
+                        println!("Operation cancelled.");
+                        return Ok(());
+                    }
+                }
+                client.empty_trash(args.options()).await?
+            }
+        },
+        Command::Undo(undo_args) => undo::execute(undo_args, ctx, client).await?,
+        Command::Tag(tag_args) => match &tag_args.command {
+            TagCommand::Set(args) => client.set_tags(&args.path, &args.tags).await?,
+            TagCommand::Get(args) => client.get_tags(&args.path).await?,
+            TagCommand::Rm(args) => client.remove_tags(&args.path, &args.keys).await?,
+        },
+        Command::Acl(acl_args) => match &acl_args.command {
+            AclCommand::Get(args) => client.get_acl(&args.path).await?,
+            AclCommand::Set(args) => client.set_acl(&args.path, args.acl).await?,
+        },
+        Command::Lifecycle(lifecycle_args) => match &lifecycle_args.command {
+            LifecycleCommand::Get(args) => client.get_lifecycle(args.json).await?,
+            LifecycleCommand::Set(args) => {
+                let config = LifecycleConfig::load(&args.file)?;
+                client.set_lifecycle(&config).await?
+            }
+            LifecycleCommand::Rm(args) => {
+                if !args.force {
+                    let prompt = ctx.prompt();
+                    let message = if args.ids.is_empty() {
+                        "Remove every lifecycle rule of the bucket?".to_string()
+                    } else {
+                        format!("Remove lifecycle rule(s) {}?", args.ids.join(", "))
+                    };
+                    let confirmed = task::block_in_place(|| {
+                        Handle::current().block_on(prompt.confirm(&message, false))
+                    })?;
+                    if !confirmed {
+                        println!("Operation cancelled.");
+                        return Ok(());
+                    }
+                }
+                client.remove_lifecycle(&args.ids).await?
+            }
+        },
+        Command::Mpu(mpu_args) => match &mpu_args.command {
+            MpuCommand::Ls(args) => client.list_multipart_uploads(&args.prefix).await?,
+            MpuCommand::Abort(args) => {
+                if !args.force && !args.dry_run {
+                    let prompt = ctx.prompt();
+                    let scope = if args.prefix.is_empty() {
+                        "in the bucket".to_string()
+                    } else {
+                        format!("under '{}'", args.prefix)
+                    };
+                    let age = args
+                        .older_than
+                        .map(|age| format!(" started over {}s ago", age.as_secs()))
+                        .unwrap_or_default();
+                    let message = format!("Abort the incomplete uploads{age} {scope}?");
+                    let confirmed = task::block_in_place(|| {
+                        Handle::current().block_on(prompt.confirm(&message, false))
+                    })?;
+                    if !confirmed {
+                        println!("Operation cancelled.");
+                        return Ok(());
+                    }
+                }
+                client
+                    .abort_multipart_uploads(&args.prefix, args.older_than, args.dry_run)
+                    .await?
+            }
+        },
+        Command::Bucket(bucket_args) => match &bucket_args.command {
+            BucketCommand::Policy(policy_args) => match &policy_args.command {
+                BucketPolicyCommand::Get => client.get_bucket_policy().await?,
+                BucketPolicyCommand::Set(args) => {
+                    let policy = std::fs::read_to_string(&args.file)?;
+                    client.set_bucket_policy(&policy).await?
+                }
+            },
+            BucketCommand::Cors(cors_args) => match &cors_args.command {
+                BucketCorsCommand::Get(args) => client.get_cors(args.json).await?,
+                BucketCorsCommand::Set(args) => {
+                    let config = CorsConfig::load(&args.file)?;
+                    client.set_cors(&config).await?
+                }
+            },
+        },
+        #[cfg(feature = "fuse")]
+        Command::Mount(mount_args) => {
+            client
+                .mount(
+                    &mount_args.path,
+                    &mount_args.mountpoint,
+                    &mount_args.options(),
+                )
+                .await?
+        }
+        Command::Serve(serve_args) => client.serve(&serve_args.options()).await?,
+        Command::Index(index_args) => {
+            let path = index_args
+                .path
+                .as_deref()
+                .expect("clap requires PATH without a subcommand");
+            client
+                .index_prefix(path, &index_args.output, index_args.force)
+                .await?;
+        }
+        Command::Inventory(inventory_args) => {
+            let format = match inventory_args.format {
+                Some(format) => format,
+                None => InventoryFormat::from_path(&inventory_args.output)?,
+            };
+            client
+                .export_inventory(
+                    &inventory_args.path,
+                    &inventory_args.output,
+                    format,
+                    inventory_args.force,
+                )
+                .await?;
+        }
+        Command::Apply(apply_args) => {
+            let spec = JobSpec::load(&apply_args.file)?;
+            client.apply_job(&spec, apply_args.dry_run).await?;
+        }
+        Command::Batch(batch_args) => {
+            super::batch::execute(batch_args, client).await?;
+        }
+        Command::Watch(watch_args) => {
+            super::watch::execute(watch_args, client).await?;
+        }
+        Command::Sync(sync_args) => {
+            super::sync::execute(sync_args, client).await?;
+        }
+        Command::Config(_)
+        | Command::Env(_)
+        | Command::Jobs(_)
+        | Command::Schema(_)
+        | Command::SelfUpdate(_)
+        | Command::Alias(_) => {
+            unreachable!(
+                "Config, env, jobs, schema, self-update and alias commands are handled separately"
+            )
         }
     }
     Ok(())
@@ -0,0 +1,298 @@
+//! `storify sync`: make a remote directory match a local one.
+//!
+//! Files that are missing remotely or differ from their object are uploaded; with
+//! `--delete`, objects without a local file are removed. `--daemon` repeats the sync every
+//! `--interval`, and a lock file keeps two syncs of the same pair from overlapping.
+
+use crate::error::{Error, Result};
+use crate::jobs::JobRegistry;
+use crate::storage::constants::DEFAULT_UPLOAD_CONCURRENCY;
+use crate::storage::{Clobber, PathFilter, StorageClient, SymlinkPolicy, UploadOptions};
+use clap::Args as ClapArgs;
+use futures::stream::{self, StreamExt};
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{Read, Seek, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct SyncArgs {
+    /// Local directory to sync from
+    #[arg(value_name = "LOCAL")]
+    pub local: PathBuf,
+
+    /// Remote directory to sync to
+    #[arg(value_name = "REMOTE", value_parser = super::storage::parse_validated_path)]
+    pub remote: String,
+
+    /// Delete objects that have no local file
+    #[arg(long)]
+    pub delete: bool,
+
+    /// Show what would be uploaded and deleted without doing it
+    #[arg(long = "dry-run", conflicts_with = "daemon")]
+    pub dry_run: bool,
+
+    /// Keep running, syncing again every --interval
+    #[arg(long)]
+    pub daemon: bool,
+
+    /// Time between the start of two syncs in daemon mode
+    #[arg(long, value_name = "DURATION", default_value = "15m", requires = "daemon", value_parser = super::config::parse_duration)]
+    pub interval: Duration,
+
+    /// Lock file held while syncing (default: one per LOCAL and REMOTE in the jobs directory)
+    #[arg(long = "lock-file", value_name = "PATH")]
+    pub lock_file: Option<PathBuf>,
+
+    /// Number of files uploaded at once
+    #[arg(short = 'c', long, default_value_t = DEFAULT_UPLOAD_CONCURRENCY, value_parser = clap::value_parser!(usize))]
+    pub concurrency: usize,
+}
+
+/// Options for uploading single files found by a scan of a directory.
+pub(super) fn upload_options() -> UploadOptions {
+    UploadOptions {
+        concurrency: DEFAULT_UPLOAD_CONCURRENCY,
+        per_file_limit: None,
+        total_limit: None,
+        verbose: false,
+        dry_run: false,
+        filter: PathFilter::default(),
+        preserve: false,
+        symlinks: SymlinkPolicy::Follow,
+        replication: None,
+        compression: None,
+        encrypt: false,
+        extract: false,
+        clobber: Clobber::default(),
+    }
+}
+
+/// Upload one file, reporting a failure instead of returning it.
+pub(super) async fn upload(
+    client: &StorageClient,
+    path: &Path,
+    key: &str,
+    options: &UploadOptions,
+) -> bool {
+    let local = path.to_string_lossy();
+    match client.upload_files(&local, key, false, options).await {
+        Ok(_) => true,
+        Err(e) => {
+            eprintln!("❌ Failed: {local} → {key}: {e}");
+            false
+        }
+    }
+}
+
+/// Exclusive lock on a file holding the owner's pid; released when dropped.
+#[derive(Debug)]
+struct SyncLock {
+    _file: File,
+}
+
+impl SyncLock {
+    fn acquire(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                let mut pid = String::new();
+                let _ = file.read_to_string(&mut pid);
+                return Err(Error::SyncLocked {
+                    path: path.to_path_buf(),
+                    pid: pid.trim().to_string(),
+                });
+            }
+            Err(TryLockError::Error(e)) => return Err(e.into()),
+        }
+        file.set_len(0)?;
+        file.rewind()?;
+        writeln!(file, "{}", std::process::id())?;
+        Ok(Self { _file: file })
+    }
+}
+
+/// Lock file used when `--lock-file` is not given, unique to the directory pair.
+fn default_lock_path(local: &Path, remote: &str) -> Result<PathBuf> {
+    let local = std::fs::canonicalize(local)?;
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(local.to_string_lossy().as_bytes());
+    hasher.update(b"\0");
+    hasher.update(remote.as_bytes());
+    let hash = hasher.finalize().to_hex();
+    Ok(JobRegistry::open()?.lock_path(&format!("sync-{}", &hash[..16])))
+}
+
+/// Counts of one sync.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct Cycle {
+    uploaded: usize,
+    deleted: usize,
+    failed: usize,
+    unchanged: usize,
+}
+
+async fn sync_once(args: &SyncArgs, client: &StorageClient, prefix: &str) -> Result<Cycle> {
+    let plan = client.sync_plan(&args.local, prefix).await?;
+    let mut cycle = Cycle {
+        unchanged: plan.unchanged,
+        ..Cycle::default()
+    };
+    if args.dry_run {
+        for (path, rel) in &plan.stale {
+            println!("Would upload: {} → {prefix}{rel}", path.display());
+        }
+        if args.delete {
+            for rel in &plan.orphaned {
+                println!("Would delete: {prefix}{rel}");
+            }
+        }
+        return Ok(cycle);
+    }
+
+    let options = upload_options();
+    let results: Vec<bool> = stream::iter(&plan.stale)
+        .map(|(path, rel)| {
+            let options = &options;
+            async move { upload(client, path, &format!("{prefix}{rel}"), options).await }
+        })
+        .buffer_unordered(args.concurrency.max(1))
+        .collect()
+        .await;
+    cycle.uploaded = results.iter().filter(|ok| **ok).count();
+    cycle.failed = results.len() - cycle.uploaded;
+
+    if args.delete && !plan.orphaned.is_empty() {
+        let keys: Vec<String> = plan
+            .orphaned
+            .iter()
+            .map(|rel| format!("{prefix}{rel}"))
+            .collect();
+        match client.delete_files(&keys, false, None, false).await {
+            Ok(()) => cycle.deleted = keys.len(),
+            Err(e) => {
+                eprintln!("❌ Failed to delete orphaned objects: {e}");
+                cycle.failed += keys.len();
+            }
+        }
+    }
+    Ok(cycle)
+}
+
+fn print_summary(cycle: &Cycle, elapsed: Duration) {
+    tracing::info!(
+        uploaded = cycle.uploaded,
+        deleted = cycle.deleted,
+        failed = cycle.failed,
+        unchanged = cycle.unchanged,
+        elapsed_ms = elapsed.as_millis() as u64,
+        "sync finished"
+    );
+    println!(
+        "[{}] Sync: {} uploaded, {} deleted, {} failed, {} up to date in {:.1}s",
+        chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, false),
+        cycle.uploaded,
+        cycle.deleted,
+        cycle.failed,
+        cycle.unchanged,
+        elapsed.as_secs_f64()
+    );
+}
+
+pub async fn execute(args: &SyncArgs, client: &StorageClient) -> Result<()> {
+    if !args.local.is_dir() {
+        return Err(Error::InvalidArgument {
+            message: format!("{} is not a directory", args.local.display()),
+        });
+    }
+    let prefix = if args.remote.is_empty() || args.remote.ends_with('/') {
+        args.remote.clone()
+    } else {
+        format!("{}/", args.remote)
+    };
+    tracing::debug!(
+        "sync local={} remote={prefix} delete={} daemon={} interval={:?}",
+        args.local.display(),
+        args.delete,
+        args.daemon,
+        args.interval
+    );
+    if args.dry_run {
+        sync_once(args, client, &prefix).await?;
+        return Ok(());
+    }
+
+    let lock_path = match &args.lock_file {
+        Some(path) => path.clone(),
+        None => default_lock_path(&args.local, &prefix)?,
+    };
+    let _lock = SyncLock::acquire(&lock_path)?;
+
+    if !args.daemon {
+        let started = Instant::now();
+        let cycle = sync_once(args, client, &prefix).await?;
+        print_summary(&cycle, started.elapsed());
+        if cycle.failed > 0 {
+            return Err(Error::TransferIncomplete {
+                failed: cycle.failed,
+                total: cycle.failed + cycle.uploaded + cycle.deleted,
+            });
+        }
+        return Ok(());
+    }
+
+    println!(
+        "Syncing {} → {prefix} every {}s (Ctrl-C to stop)",
+        args.local.display(),
+        args.interval.as_secs()
+    );
+    loop {
+        let started = Instant::now();
+        // A failed sync is retried on the next interval rather than ending the daemon.
+        match sync_once(args, client, &prefix).await {
+            Ok(cycle) => print_summary(&cycle, started.elapsed()),
+            Err(e) => {
+                tracing::warn!(error = %e, "sync failed");
+                eprintln!("❌ Sync failed: {e}");
+            }
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(args.interval.saturating_sub(started.elapsed())) => {}
+            _ = tokio::signal::ctrl_c() => break,
+        }
+    }
+    println!("Stopped syncing {}", args.local.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lock_is_exclusive_and_records_pid() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("locks").join("sync.lock");
+
+        let lock = SyncLock::acquire(&path).unwrap();
+        let pid = std::process::id().to_string();
+        assert_eq!(std::fs::read_to_string(&path).unwrap().trim(), pid);
+        match SyncLock::acquire(&path) {
+            Err(Error::SyncLocked { pid: holder, .. }) => assert_eq!(holder, pid),
+            other => panic!("expected SyncLocked, got {other:?}"),
+        }
+
+        drop(lock);
+        SyncLock::acquire(&path).unwrap();
+    }
+}
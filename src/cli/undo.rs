@@ -0,0 +1,124 @@
+use crate::error::{Error, Result};
+use crate::jobs::format_age;
+use crate::journal::{Change, Journal, JournalEntry, Prior};
+use crate::storage::StorageClient;
+use clap::Args as ClapArgs;
+use tokio::runtime::Handle;
+use tokio::task;
+
+use super::context::CliContext;
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct UndoArgs {
+    /// Undo without asking for confirmation
+    #[arg(short = 'f', long)]
+    pub force: bool,
+}
+
+/// Objects a destructive command is about to change, saved before it runs.
+pub struct Snapshot {
+    journal: Journal,
+    id: String,
+    change: Change,
+}
+
+impl Snapshot {
+    /// Save the state of `paths` before deleting or overwriting them; `None` when
+    /// journaling is disabled. A snapshot that fails or outgrows the limits is journaled
+    /// as not undoable, and the command still runs.
+    pub async fn take(client: &StorageClient, paths: &[String], recursive: bool) -> Option<Self> {
+        let journal = Journal::open()?;
+        let id = Journal::new_id();
+        let change = match journal.backup_dir(&id) {
+            Ok(dir) => client.snapshot_objects(paths, recursive, &dir).await,
+            Err(e) => Err(e),
+        };
+        let change = change.unwrap_or_else(|e| Change::NotUndoable {
+            reason: format!("saving the previous state failed: {e}"),
+        });
+        if let Change::NotUndoable { reason } = &change {
+            tracing::warn!("this operation cannot be undone: {reason}");
+            journal.remove_backups(&id);
+        }
+        Some(Self {
+            journal,
+            id,
+            change,
+        })
+    }
+
+    /// Journal the snapshot once the command ran, unless it only covered paths that did not
+    /// exist, which nothing was lost from.
+    pub fn record(self, client: &StorageClient, command: String) {
+        if let Change::Replaced { objects } = &self.change
+            && objects.iter().all(|object| object.prior == Prior::Missing)
+        {
+            self.journal.remove_backups(&self.id);
+            return;
+        }
+        append(&self.journal, self.id, client, command, self.change);
+    }
+}
+
+/// Journal a change that can be reverted without backups (moves, trashed paths).
+pub fn record(client: &StorageClient, command: String, change: Change) {
+    if let Some(journal) = Journal::open() {
+        append(&journal, Journal::new_id(), client, command, change);
+    }
+}
+
+fn append(journal: &Journal, id: String, client: &StorageClient, command: String, change: Change) {
+    let entry = JournalEntry::new(id, command, client.location(), change);
+    // The command itself succeeded; losing the ability to undo it is only worth a warning.
+    if let Err(e) = journal.append(&entry) {
+        tracing::warn!("cannot record this operation for undo: {e}");
+        journal.remove_backups(&entry.id);
+    }
+}
+
+/// Revert the most recent journaled operation.
+pub async fn execute(args: &UndoArgs, ctx: &CliContext, client: &StorageClient) -> Result<()> {
+    let journal = Journal::open().ok_or_else(|| Error::InvalidArgument {
+        message: "the undo journal is disabled (STORIFY_JOURNAL is empty)".to_string(),
+    })?;
+    let Some(entry) = journal.last()? else {
+        println!("Nothing to undo");
+        return Ok(());
+    };
+    let location = client.location();
+    if entry.location != location {
+        return Err(Error::InvalidArgument {
+            message: format!(
+                "the last operation ('{}') changed {}, not {location}; select its profile to undo it",
+                entry.command, entry.location
+            ),
+        });
+    }
+
+    // Nothing can be reverted; drop the entry so it does not hide the ones before it.
+    if let Change::NotUndoable { reason } = &entry.change {
+        journal.remove(&entry.id)?;
+        return Err(Error::InvalidArgument {
+            message: format!("'{}' cannot be undone: {reason}", entry.command),
+        });
+    }
+
+    println!(
+        "Undo: {} ({} ago): {}",
+        entry.command,
+        format_age(entry.recorded_at),
+        entry.change.describe()
+    );
+    if !args.force {
+        let prompt = ctx.prompt();
+        let confirmed = task::block_in_place(|| {
+            Handle::current().block_on(prompt.confirm("Undo this operation?", false))
+        })?;
+        if !confirmed {
+            println!("Operation cancelled.");
+            return Ok(());
+        }
+    }
+    client.undo(&entry).await?;
+    journal.remove(&entry.id)
+}
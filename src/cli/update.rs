@@ -0,0 +1,30 @@
+use crate::error::{Error, Result};
+use crate::update;
+
+use super::entry::SelfUpdateArgs;
+
+pub async fn execute(args: &SelfUpdateArgs) -> Result<()> {
+    let check = update::check(args.channel).await?;
+    if !check.update_available {
+        println!(
+            "storify {} is up to date ({}).",
+            check.current, check.latest
+        );
+        return Ok(());
+    }
+    if args.check_only {
+        return Err(Error::UpdateAvailable {
+            current: check.current,
+            latest: check.latest,
+        });
+    }
+
+    println!("Updating storify {} -> {}...", check.current, check.latest);
+    let path = update::install(&check).await?;
+    println!(
+        "Checksum verified; installed {} at {}",
+        check.latest,
+        path.display()
+    );
+    Ok(())
+}
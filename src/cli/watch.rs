@@ -0,0 +1,206 @@
+//! `storify watch`: keep a remote directory up to date with a local one.
+//!
+//! The local directory is rescanned every `--interval`, comparing each file's size and
+//! modification time with the previous scan, so it behaves the same on every platform and
+//! on network mounts. A changed file is uploaded once it has stayed unchanged for
+//! `--debounce`: a build that rewrites a file several times uploads it once.
+
+use super::sync::{upload, upload_options};
+use crate::error::{Error, Result};
+use crate::storage::{StorageClient, local_files};
+use clap::Args as ClapArgs;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct WatchArgs {
+    /// Local directory to watch
+    #[arg(value_name = "LOCAL")]
+    pub local: PathBuf,
+
+    /// Remote directory the files are uploaded below
+    #[arg(value_name = "REMOTE", value_parser = super::storage::parse_validated_path)]
+    pub remote: String,
+
+    /// How long a changed file must stay unchanged before it is uploaded
+    #[arg(long, value_name = "DURATION", default_value = "2s", value_parser = super::config::parse_duration)]
+    pub debounce: Duration,
+
+    /// How often the directory is rescanned
+    #[arg(long, value_name = "DURATION", default_value = "1s", value_parser = super::config::parse_duration)]
+    pub interval: Duration,
+
+    /// Delete the objects of files deleted locally
+    #[arg(long)]
+    pub delete: bool,
+
+    /// Do not upload files that differ from the remote before watching starts
+    #[arg(long = "no-initial-sync")]
+    pub no_initial_sync: bool,
+}
+
+/// Size and modification time of a local file, as seen by one scan.
+type Stamp = (u64, Option<SystemTime>);
+
+/// Files of one scan by relative path.
+type Scan = HashMap<String, (PathBuf, Stamp)>;
+
+/// Files seen by the last scan, and the changes not uploaded yet.
+#[derive(Debug, Default)]
+struct Tracker {
+    seen: Scan,
+    /// Changed files (`None` when deleted) and when they last changed
+    pending: HashMap<String, (Option<PathBuf>, Instant)>,
+}
+
+impl Tracker {
+    fn new(seen: Scan) -> Self {
+        Self {
+            seen,
+            pending: HashMap::new(),
+        }
+    }
+
+    /// Record the differences between `scan` and the previous one, made at `now`.
+    fn observe(&mut self, scan: Scan, now: Instant) {
+        for (rel, (path, stamp)) in &scan {
+            if self.seen.get(rel).is_none_or(|(_, seen)| seen != stamp) {
+                self.pending.insert(rel.clone(), (Some(path.clone()), now));
+            }
+        }
+        for rel in self.seen.keys() {
+            if !scan.contains_key(rel) {
+                self.pending.insert(rel.clone(), (None, now));
+            }
+        }
+        self.seen = scan;
+    }
+
+    /// Take the changes that have been quiet for `debounce`, in path order.
+    fn settled(&mut self, now: Instant, debounce: Duration) -> Vec<(String, Option<PathBuf>)> {
+        let mut ready: Vec<String> = self
+            .pending
+            .iter()
+            .filter(|(_, (_, changed))| now.duration_since(*changed) >= debounce)
+            .map(|(rel, _)| rel.clone())
+            .collect();
+        ready.sort();
+        ready
+            .into_iter()
+            .filter_map(|rel| {
+                let (path, _) = self.pending.remove(&rel)?;
+                Some((rel, path))
+            })
+            .collect()
+    }
+}
+
+/// Stamp every file `put -R` would upload from `dir`; files gone mid-scan are left out.
+fn scan(dir: &Path) -> Result<Scan> {
+    let mut files = HashMap::new();
+    for (path, rel) in local_files(dir)? {
+        let Ok(meta) = std::fs::metadata(&path) else {
+            continue;
+        };
+        files.insert(rel, (path, (meta.len(), meta.modified().ok())));
+    }
+    Ok(files)
+}
+
+pub async fn execute(args: &WatchArgs, client: &StorageClient) -> Result<()> {
+    if !args.local.is_dir() {
+        return Err(Error::InvalidArgument {
+            message: format!("{} is not a directory", args.local.display()),
+        });
+    }
+    let prefix = if args.remote.is_empty() || args.remote.ends_with('/') {
+        args.remote.clone()
+    } else {
+        format!("{}/", args.remote)
+    };
+    tracing::debug!(
+        "watch local={} remote={prefix} debounce={:?} interval={:?}",
+        args.local.display(),
+        args.debounce,
+        args.interval
+    );
+    let options = upload_options();
+
+    // Changes made during the initial sync are picked up by the first rescan.
+    let mut tracker = Tracker::new(scan(&args.local)?);
+    if !args.no_initial_sync {
+        for (path, rel) in client.sync_plan(&args.local, &prefix).await?.stale {
+            upload(client, &path, &format!("{prefix}{rel}"), &options).await;
+        }
+    }
+
+    println!(
+        "Watching {} → {prefix} (Ctrl-C to stop)",
+        args.local.display()
+    );
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(args.interval) => {}
+            _ = tokio::signal::ctrl_c() => break,
+        }
+        let now = Instant::now();
+        tracker.observe(scan(&args.local)?, now);
+        for (rel, path) in tracker.settled(now, args.debounce) {
+            let key = format!("{prefix}{rel}");
+            match path {
+                Some(path) => {
+                    // A failed upload is retried when the file changes again.
+                    upload(client, &path, &key, &options).await;
+                }
+                None if args.delete => {
+                    if let Err(e) = client
+                        .delete_files(std::slice::from_ref(&key), false, None, false)
+                        .await
+                    {
+                        eprintln!("❌ Failed to delete {key}: {e}");
+                    }
+                }
+                None => {}
+            }
+        }
+    }
+    println!("Stopped watching {}", args.local.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scan_of(files: &[(&str, u64)]) -> Scan {
+        files
+            .iter()
+            .map(|(rel, len)| (rel.to_string(), (PathBuf::from(rel), (*len, None))))
+            .collect()
+    }
+
+    #[test]
+    fn uploads_changes_once_they_settle() {
+        let start = Instant::now();
+        let second = Duration::from_secs(1);
+        let mut tracker = Tracker::new(scan_of(&[("a", 1), ("b", 1)]));
+
+        tracker.observe(scan_of(&[("a", 2), ("b", 1), ("c", 1)]), start);
+        assert!(tracker.settled(start + second, 2 * second).is_empty());
+        // `a` keeps changing, which restarts its quiet period.
+        tracker.observe(scan_of(&[("a", 3), ("c", 1)]), start + second);
+        let settled = tracker.settled(start + 2 * second, 2 * second);
+        assert_eq!(settled, vec![("c".to_string(), Some(PathBuf::from("c")))]);
+
+        let settled = tracker.settled(start + 3 * second, 2 * second);
+        assert_eq!(
+            settled,
+            vec![
+                ("a".to_string(), Some(PathBuf::from("a"))),
+                ("b".to_string(), None),
+            ]
+        );
+        assert!(tracker.settled(start + 10 * second, 2 * second).is_empty());
+    }
+}
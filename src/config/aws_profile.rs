@@ -0,0 +1,132 @@
+//! Minimal reader for AWS shared credential/config files (`~/.aws/credentials`, `~/.aws/config`).
+//!
+//! Only static credentials are resolved; profiles that chain through `source_profile` or
+//! `credential_process` should be combined with `role_arn` or exported to the environment instead.
+
+use crate::error::{Error, Result};
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const CREDENTIALS_FILE_ENV: &str = "AWS_SHARED_CREDENTIALS_FILE";
+const CONFIG_FILE_ENV: &str = "AWS_CONFIG_FILE";
+
+/// Credentials and defaults resolved from a named AWS profile.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AwsProfile {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    pub session_token: Option<String>,
+    pub region: Option<String>,
+}
+
+/// Load `name` from the shared credentials file, falling back to the config file.
+pub fn load_aws_profile(name: &str) -> Result<AwsProfile> {
+    let credentials_path = aws_file(CREDENTIALS_FILE_ENV, "credentials");
+    let config_path = aws_file(CONFIG_FILE_ENV, "config");
+    resolve(name, &credentials_path, &config_path)
+}
+
+fn resolve(name: &str, credentials_path: &Path, config_path: &Path) -> Result<AwsProfile> {
+    let credentials = read_section(credentials_path, name)?;
+    // The config file prefixes every profile except `default` with `profile `.
+    let config_section = if name == "default" {
+        name.to_string()
+    } else {
+        format!("profile {name}")
+    };
+    let config = read_section(config_path, &config_section)?;
+
+    let lookup = |key: &str| {
+        credentials
+            .get(key)
+            .or_else(|| config.get(key))
+            .filter(|value| !value.is_empty())
+            .cloned()
+    };
+
+    match (lookup("aws_access_key_id"), lookup("aws_secret_access_key")) {
+        (Some(access_key_id), Some(secret_access_key)) => Ok(AwsProfile {
+            access_key_id,
+            secret_access_key,
+            session_token: lookup("aws_session_token"),
+            region: lookup("region"),
+        }),
+        _ => Err(Error::AwsProfileNotFound {
+            profile: name.to_string(),
+            path: credentials_path.to_path_buf(),
+        }),
+    }
+}
+
+fn aws_file(env_key: &str, file_name: &str) -> PathBuf {
+    env::var(env_key)
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            directories::BaseDirs::new()
+                .map(|base_dirs| base_dirs.home_dir().join(".aws").join(file_name))
+        })
+        .unwrap_or_else(|| PathBuf::from(".aws").join(file_name))
+}
+
+/// Key/value pairs of one `[section]` in an INI file; a missing file yields an empty map.
+fn read_section(path: &Path, section: &str) -> Result<HashMap<String, String>> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(HashMap::new()),
+        Err(err) => return Err(err.into()),
+    };
+    Ok(parse_section(&content, section))
+}
+
+fn parse_section(content: &str, section: &str) -> HashMap<String, String> {
+    let mut values = HashMap::new();
+    let mut in_section = false;
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if let Some(header) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            in_section = header.trim() == section;
+            continue;
+        }
+        if in_section && let Some((key, value)) = line.split_once('=') {
+            values.insert(key.trim().to_ascii_lowercase(), value.trim().to_string());
+        }
+    }
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_credentials_and_config_region() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let credentials = dir.path().join("credentials");
+        let config = dir.path().join("config");
+        fs::write(
+            &credentials,
+            "[default]\naws_access_key_id = AKIADEFAULT\naws_secret_access_key = s1\n\n\
+             [ci]\naws_access_key_id=AKIACI\naws_secret_access_key=s2\naws_session_token=tok\n",
+        )
+        .unwrap();
+        fs::write(&config, "[profile ci]\nregion = eu-west-1\n").unwrap();
+
+        let profile = resolve("ci", &credentials, &config).unwrap();
+        assert_eq!(profile.access_key_id, "AKIACI");
+        assert_eq!(profile.secret_access_key, "s2");
+        assert_eq!(profile.session_token.as_deref(), Some("tok"));
+        assert_eq!(profile.region.as_deref(), Some("eu-west-1"));
+
+        assert!(matches!(
+            resolve("missing", &credentials, &config),
+            Err(Error::AwsProfileNotFound { .. })
+        ));
+    }
+}
@@ -0,0 +1,265 @@
+use crate::config::storage_config::StorageConfig;
+use crate::error::{Error, Result};
+use clap::ValueEnum;
+use std::str::FromStr;
+
+/// Named presets for S3-compatible systems that deviate from AWS.
+///
+/// A preset only adjusts behavior that the system is known to handle differently; explicit
+/// profile values such as `region` always win over the preset default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CompatPreset {
+    /// Ceph Object Gateway (RGW)
+    #[value(alias = "rgw")]
+    Ceph,
+    /// SeaweedFS S3 gateway
+    Seaweedfs,
+    /// Cloudflare R2
+    R2,
+    /// Backblaze B2 S3-compatible API
+    B2,
+}
+
+impl FromStr for CompatPreset {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "ceph" | "rgw" => Ok(Self::Ceph),
+            "seaweedfs" => Ok(Self::Seaweedfs),
+            "r2" => Ok(Self::R2),
+            "b2" => Ok(Self::B2),
+            _ => Err(Error::InvalidArgument {
+                message: format!(
+                    "unknown compat preset '{s}' (expected ceph, seaweedfs, r2 or b2)"
+                ),
+            }),
+        }
+    }
+}
+
+impl CompatPreset {
+    /// Preset implied by a provider alias (`--provider r2`, `STORAGE_PROVIDER=b2`).
+    pub fn from_provider_alias(provider: &str) -> Option<Self> {
+        match provider.to_ascii_lowercase().as_str() {
+            "r2" => Some(Self::R2),
+            "b2" => Some(Self::B2),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CompatPreset::Ceph => "ceph",
+            CompatPreset::Seaweedfs => "seaweedfs",
+            CompatPreset::R2 => "r2",
+            CompatPreset::B2 => "b2",
+        }
+    }
+
+    /// Endpoint and signing region for `config`, derived from the preset where unset.
+    ///
+    /// R2 endpoints come from the account ID and B2 endpoints from the region; values the
+    /// service would reject (an R2 region other than `auto`, a B2 region that does not match
+    /// its endpoint) are refused here instead of failing with a signature error later.
+    pub fn resolve_endpoint(
+        &self,
+        config: &StorageConfig,
+    ) -> Result<(Option<String>, Option<String>)> {
+        let endpoint = config.endpoint.clone();
+        let region = config
+            .region
+            .clone()
+            .or_else(|| self.default_region(endpoint.as_deref()));
+        match self {
+            CompatPreset::Ceph | CompatPreset::Seaweedfs => Ok((endpoint, region)),
+            CompatPreset::R2 => {
+                if let Some(region) = region.as_deref().filter(|region| *region != "auto") {
+                    return Err(Error::InvalidArgument {
+                        message: format!(
+                            "r2 only accepts region 'auto', got '{region}'; unset the region"
+                        ),
+                    });
+                }
+                let endpoint = match (endpoint, config.account_id.as_deref()) {
+                    (Some(endpoint), _) => endpoint,
+                    (None, Some(account_id)) => {
+                        format!("https://{account_id}.r2.cloudflarestorage.com")
+                    }
+                    (None, None) => {
+                        return Err(Error::MissingConfigField {
+                            provider: self.as_str().to_string(),
+                            field: "account_id (or endpoint)".to_string(),
+                        });
+                    }
+                };
+                Ok((Some(endpoint), region))
+            }
+            CompatPreset::B2 => {
+                let Some(region) = region else {
+                    return Err(Error::MissingConfigField {
+                        provider: self.as_str().to_string(),
+                        field: "region (e.g. us-west-004)".to_string(),
+                    });
+                };
+                if let Some(endpoint_region) = endpoint.as_deref().and_then(b2_region)
+                    && endpoint_region != region
+                {
+                    return Err(Error::InvalidArgument {
+                        message: format!(
+                            "b2 region '{region}' does not match endpoint region '{endpoint_region}'"
+                        ),
+                    });
+                }
+                let endpoint =
+                    endpoint.unwrap_or_else(|| format!("https://s3.{region}.backblazeb2.com"));
+                Ok((Some(endpoint), Some(region)))
+            }
+        }
+    }
+
+    /// Region to sign with when the profile sets none.
+    ///
+    /// B2 rejects signatures for any region but the one in its endpoint
+    /// (`s3.us-west-004.backblazeb2.com`); R2 only accepts `auto`.
+    fn default_region(&self, endpoint: Option<&str>) -> Option<String> {
+        match self {
+            CompatPreset::Ceph | CompatPreset::Seaweedfs => Some("us-east-1".to_string()),
+            CompatPreset::R2 => Some("auto".to_string()),
+            CompatPreset::B2 => endpoint.and_then(b2_region),
+        }
+    }
+
+    /// Address buckets as `bucket.host` instead of `host/bucket`.
+    ///
+    /// Self-hosted gateways rarely have wildcard DNS, so only the hosted services use it.
+    pub fn virtual_host_style(&self) -> bool {
+        matches!(self, CompatPreset::B2)
+    }
+
+    /// Whether `versionId` requests are understood.
+    pub fn supports_versioning(&self) -> bool {
+        matches!(self, CompatPreset::Ceph | CompatPreset::B2)
+    }
+
+    /// Whether bucket policies (`?policy`) can be read and set; the hosted services manage
+    /// access through their own dashboards instead.
+    pub fn supports_bucket_policy(&self) -> bool {
+        matches!(self, CompatPreset::Ceph)
+    }
+
+    /// Whether bucket CORS rules (`?cors`) can be read and set.
+    pub fn supports_cors(&self) -> bool {
+        !matches!(self, CompatPreset::Seaweedfs)
+    }
+
+    /// Whether objects carry ACLs of their own (`?acl` on a key).
+    pub fn supports_object_acl(&self) -> bool {
+        matches!(self, CompatPreset::Ceph | CompatPreset::Seaweedfs)
+    }
+
+    /// Whether the bucket has an ACL; on B2 it is the only one and applies to every object.
+    pub fn supports_bucket_acl(&self) -> bool {
+        !matches!(self, CompatPreset::R2)
+    }
+
+    /// Whether `response-content-*` overrides on GET/HEAD are honored.
+    pub fn supports_stat_with_override(&self) -> bool {
+        matches!(self, CompatPreset::Ceph | CompatPreset::R2)
+    }
+
+    /// Whether conditional writes (`If-Match` on PUT) are understood.
+    pub fn supports_write_with_if_match(&self) -> bool {
+        matches!(self, CompatPreset::R2)
+    }
+
+    /// Whether `ListObjectsV2` paginates correctly; V1 listing is used otherwise.
+    pub fn supports_list_objects_v2(&self) -> bool {
+        !matches!(self, CompatPreset::Seaweedfs)
+    }
+
+    /// Checksum sent with uploads; the others reject or ignore `x-amz-checksum-*` headers.
+    pub fn checksum_algorithm(&self) -> Option<&'static str> {
+        match self {
+            CompatPreset::R2 => Some("crc32c"),
+            _ => None,
+        }
+    }
+
+    /// Objects per batch delete request, when lower than the S3 limit of 1000.
+    pub fn delete_batch_limit(&self) -> Option<usize> {
+        match self {
+            CompatPreset::Seaweedfs => Some(100),
+            _ => None,
+        }
+    }
+}
+
+/// Region embedded in a B2 S3 endpoint, e.g. `https://s3.eu-central-003.backblazeb2.com`.
+fn b2_region(endpoint: &str) -> Option<String> {
+    let host = endpoint
+        .split("://")
+        .last()
+        .unwrap_or(endpoint)
+        .split(['/', ':'])
+        .next()?;
+    host.strip_prefix("s3.")?
+        .strip_suffix(".backblazeb2.com")
+        .filter(|region| !region.is_empty())
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derives_default_regions() {
+        assert_eq!(
+            CompatPreset::B2.default_region(Some("https://s3.us-west-004.backblazeb2.com")),
+            Some("us-west-004".to_string())
+        );
+        assert_eq!(
+            CompatPreset::B2.default_region(Some("http://minio:9000")),
+            None
+        );
+        assert_eq!(
+            CompatPreset::R2.default_region(None),
+            Some("auto".to_string())
+        );
+        assert_eq!("RGW".parse::<CompatPreset>().unwrap(), CompatPreset::Ceph);
+        assert!("wasabi".parse::<CompatPreset>().is_err());
+    }
+
+    #[test]
+    fn resolves_r2_and_b2_endpoints() {
+        let mut config = StorageConfig::s3("bucket".to_string());
+        config.account_id = Some("abc123".to_string());
+        assert_eq!(
+            CompatPreset::R2.resolve_endpoint(&config).unwrap(),
+            (
+                Some("https://abc123.r2.cloudflarestorage.com".to_string()),
+                Some("auto".to_string())
+            )
+        );
+        config.region = Some("us-east-1".to_string());
+        assert!(CompatPreset::R2.resolve_endpoint(&config).is_err());
+
+        let mut config = StorageConfig::s3("bucket".to_string());
+        assert!(matches!(
+            CompatPreset::B2.resolve_endpoint(&config),
+            Err(Error::MissingConfigField { .. })
+        ));
+        config.region = Some("eu-central-003".to_string());
+        assert_eq!(
+            CompatPreset::B2
+                .resolve_endpoint(&config)
+                .unwrap()
+                .0
+                .as_deref(),
+            Some("https://s3.eu-central-003.backblazeb2.com")
+        );
+        config.endpoint = Some("https://s3.us-west-004.backblazeb2.com".to_string());
+        assert!(CompatPreset::B2.resolve_endpoint(&config).is_err());
+    }
+}
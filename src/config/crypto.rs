@@ -60,6 +60,46 @@ impl Clone for EncryptionMetadata {
     }
 }
 
+/// Key for client-side object encryption, stored base64-encoded as a profile's `data_key`
+/// (or `STORAGE_DATA_KEY`).
+pub struct DataKey(Zeroizing<[u8; KEY_SIZE]>);
+
+impl DataKey {
+    /// Generate a fresh random key.
+    pub fn generate() -> Self {
+        Self(Zeroizing::new(rand::rng().random()))
+    }
+
+    /// Parse a base64 key as produced by [`DataKey::encode`].
+    pub fn decode(encoded: &str) -> Result<Self, Error> {
+        let invalid = || Error::InvalidArgument {
+            message: format!("data key must be {KEY_SIZE} bytes of base64"),
+        };
+        let bytes = Zeroizing::new(
+            BASE64_ENGINE
+                .decode(encoded.trim())
+                .map_err(|_| invalid())?,
+        );
+        let key: [u8; KEY_SIZE] = bytes.as_slice().try_into().map_err(|_| invalid())?;
+        Ok(Self(Zeroizing::new(key)))
+    }
+
+    pub fn encode(&self) -> String {
+        BASE64_ENGINE.encode(*self.0)
+    }
+
+    /// ChaCha20Poly1305 cipher keyed with this key.
+    pub fn cipher(&self) -> ChaCha20Poly1305 {
+        ChaCha20Poly1305::new(Key::from_slice(&*self.0))
+    }
+}
+
+impl std::fmt::Debug for DataKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("DataKey(****)")
+    }
+}
+
 /// Generate cryptographically secure random salt
 pub fn generate_salt() -> [u8; SALT_SIZE] {
     rand::rng().random()
@@ -0,0 +1,63 @@
+use clap::ValueEnum;
+
+use crate::config::StorageProvider;
+
+/// Server-side encryption algorithm requested with `put --sse`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SseAlgorithm {
+    /// Keys managed by the provider (SSE-S3 / OSS-managed AES-256)
+    Aes256,
+    /// Keys held in the provider's key management service
+    Kms,
+}
+
+/// Server-side encryption applied to uploaded objects.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerSideEncryption {
+    pub algorithm: SseAlgorithm,
+    /// Customer-managed KMS key; the provider's default KMS key when unset
+    pub kms_key_id: Option<String>,
+}
+
+impl ServerSideEncryption {
+    /// Value of the provider's `server-side-encryption` header.
+    pub fn header_value(&self, provider: StorageProvider) -> &'static str {
+        match (self.algorithm, provider) {
+            (SseAlgorithm::Aes256, _) => "AES256",
+            (SseAlgorithm::Kms, StorageProvider::Oss) => "KMS",
+            (SseAlgorithm::Kms, _) => "aws:kms",
+        }
+    }
+
+    /// Short description for verbose output, e.g. `aws:kms (key alias/backups)`.
+    pub fn describe(&self, provider: StorageProvider) -> String {
+        match &self.kms_key_id {
+            Some(key) => format!("{} (key {key})", self.header_value(provider)),
+            None => self.header_value(provider).to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn header_values_follow_provider_names() {
+        let kms = ServerSideEncryption {
+            algorithm: SseAlgorithm::Kms,
+            kms_key_id: Some("alias/backups".to_string()),
+        };
+        assert_eq!(kms.header_value(StorageProvider::S3), "aws:kms");
+        assert_eq!(kms.header_value(StorageProvider::Oss), "KMS");
+        assert_eq!(
+            kms.describe(StorageProvider::S3),
+            "aws:kms (key alias/backups)"
+        );
+        let aes = ServerSideEncryption {
+            algorithm: SseAlgorithm::Aes256,
+            kms_key_id: None,
+        };
+        assert_eq!(aes.describe(StorageProvider::Oss), "AES256");
+    }
+}
@@ -0,0 +1,285 @@
+use clap::ValueEnum;
+
+use crate::config::{CompatPreset, StorageConfig, StorageProvider};
+
+/// Name of the rclone remote defined by the exported `RCLONE_CONFIG_STORIFY_*` variables.
+pub const RCLONE_REMOTE: &str = "storify";
+
+/// Output syntax of `storify env`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum EnvFormat {
+    /// `export KEY='value'` lines for `eval "$(storify env)"`
+    #[default]
+    Shell,
+    /// `-e KEY=value` arguments for `docker run`
+    Docker,
+    /// `KEY=value` lines for `docker run --env-file` and systemd `EnvironmentFile=`
+    EnvFile,
+}
+
+impl EnvFormat {
+    pub fn render(&self, var: &EnvVar) -> String {
+        match self {
+            EnvFormat::Shell => format!("export {}={}", var.key, shell_quote(&var.value)),
+            EnvFormat::Docker => {
+                format!("-e {}", shell_quote(&format!("{}={}", var.key, var.value)))
+            }
+            EnvFormat::EnvFile => format!("{}={}", var.key, var.value),
+        }
+    }
+}
+
+/// One exported variable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvVar {
+    pub key: String,
+    pub value: String,
+    /// Credential material, only printed with `--show-secrets`
+    pub secret: bool,
+}
+
+impl EnvVar {
+    fn plain(key: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            key: key.into(),
+            value: value.into(),
+            secret: false,
+        }
+    }
+
+    fn secret(key: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            secret: true,
+            ..Self::plain(key, value)
+        }
+    }
+}
+
+/// Variables describing `config`: the `STORAGE_*` set storify reads back, followed by the
+/// provider's native variables (AWS CLI, ossutil, coscli, Azure CLI, Google SDKs) and an
+/// rclone remote named [`RCLONE_REMOTE`].
+pub fn env_vars(config: &StorageConfig) -> Vec<EnvVar> {
+    let mut vars = Vars::default();
+    storify_vars(config, &mut vars);
+    vars.secret("STORAGE_DATA_KEY", config.data_key.as_deref());
+    native_vars(config, &mut vars);
+    rclone_vars(config, &mut vars);
+    vars.0
+}
+
+/// Collects variables, skipping unset values.
+#[derive(Default)]
+struct Vars(Vec<EnvVar>);
+
+impl Vars {
+    fn plain(&mut self, key: impl Into<String>, value: Option<&str>) {
+        if let Some(value) = value {
+            self.0.push(EnvVar::plain(key, value));
+        }
+    }
+
+    fn secret(&mut self, key: impl Into<String>, value: Option<&str>) {
+        if let Some(value) = value {
+            self.0.push(EnvVar::secret(key, value));
+        }
+    }
+}
+
+fn storify_vars(config: &StorageConfig, vars: &mut Vars) {
+    vars.plain("STORAGE_PROVIDER", Some(config.provider.as_str()));
+    match config.provider {
+        StorageProvider::Hdfs => {
+            vars.plain("HDFS_NAME_NODE", config.name_node.as_deref());
+            vars.plain("HDFS_ROOT_PATH", config.root_path.as_deref());
+            return;
+        }
+        StorageProvider::Fs => {
+            vars.plain("STORAGE_ROOT_PATH", config.root_path.as_deref());
+            return;
+        }
+        // An http bucket is the host of a `https://host/key` path; the endpoint carries it.
+        StorageProvider::Http => vars.plain("STORAGE_ROOT_PATH", config.root_path.as_deref()),
+        _ => vars.plain("STORAGE_BUCKET", Some(&config.bucket)),
+    }
+    vars.secret("STORAGE_ACCESS_KEY_ID", config.access_key_id.as_deref());
+    vars.secret(
+        "STORAGE_ACCESS_KEY_SECRET",
+        config.access_key_secret.as_deref(),
+    );
+    vars.secret("STORAGE_SESSION_TOKEN", config.session_token.as_deref());
+    vars.plain("STORAGE_REGION", config.region.as_deref());
+    vars.plain("STORAGE_ENDPOINT", config.endpoint.as_deref());
+    let fallback = config.fallback_endpoints.join(",");
+    vars.plain(
+        "STORAGE_FALLBACK_ENDPOINTS",
+        Some(fallback.as_str()).filter(|list| !list.is_empty()),
+    );
+    vars.plain("STORAGE_CREDENTIAL_PATH", config.credential_path.as_deref());
+    vars.secret("STORAGE_SAS_TOKEN", config.sas_token.as_deref());
+    vars.secret(
+        "STORAGE_CONNECTION_STRING",
+        config.connection_string.as_deref(),
+    );
+    vars.plain("STORAGE_ROLE_ARN", config.role_arn.as_deref());
+    vars.plain("STORAGE_AWS_PROFILE", config.aws_profile.as_deref());
+    vars.plain(
+        "STORAGE_USE_INSTANCE_METADATA",
+        config.use_instance_metadata.then_some("true"),
+    );
+    vars.plain("STORAGE_COMPAT", config.compat.as_deref());
+    vars.plain("STORAGE_ACCOUNT_ID", config.account_id.as_deref());
+    vars.plain("STORAGE_MANIFEST", config.manifest.as_deref());
+}
+
+fn native_vars(config: &StorageConfig, vars: &mut Vars) {
+    match config.provider {
+        StorageProvider::S3 => {
+            vars.secret("AWS_ACCESS_KEY_ID", config.access_key_id.as_deref());
+            vars.secret("AWS_SECRET_ACCESS_KEY", config.access_key_secret.as_deref());
+            vars.secret("AWS_SESSION_TOKEN", config.session_token.as_deref());
+            vars.plain("AWS_REGION", config.region.as_deref());
+            vars.plain("AWS_DEFAULT_REGION", config.region.as_deref());
+            vars.plain("AWS_ENDPOINT_URL", config.endpoint.as_deref());
+            vars.plain("AWS_PROFILE", config.aws_profile.as_deref());
+        }
+        StorageProvider::Oss => {
+            vars.secret("OSS_ACCESS_KEY_ID", config.access_key_id.as_deref());
+            vars.secret("OSS_ACCESS_KEY_SECRET", config.access_key_secret.as_deref());
+            vars.secret("OSS_SECURITY_TOKEN", config.session_token.as_deref());
+            vars.plain("OSS_REGION", config.region.as_deref());
+            vars.plain("OSS_ENDPOINT", config.endpoint.as_deref());
+        }
+        StorageProvider::Cos => {
+            vars.secret("COS_SECRET_ID", config.access_key_id.as_deref());
+            vars.secret("COS_SECRET_KEY", config.access_key_secret.as_deref());
+            vars.plain("COS_REGION", config.region.as_deref());
+            vars.plain("COS_ENDPOINT", config.endpoint.as_deref());
+        }
+        StorageProvider::Azblob => {
+            vars.plain("AZURE_STORAGE_ACCOUNT", config.access_key_id.as_deref());
+            vars.secret("AZURE_STORAGE_KEY", config.access_key_secret.as_deref());
+            vars.secret("AZURE_STORAGE_SAS_TOKEN", config.sas_token.as_deref());
+            vars.secret(
+                "AZURE_STORAGE_CONNECTION_STRING",
+                config.connection_string.as_deref(),
+            );
+        }
+        StorageProvider::Gcs => vars.plain(
+            "GOOGLE_APPLICATION_CREDENTIALS",
+            config.credential_path.as_deref(),
+        ),
+        StorageProvider::Fs
+        | StorageProvider::Hdfs
+        | StorageProvider::Http
+        | StorageProvider::Memory => {}
+    }
+}
+
+/// rclone's own provider name for an S3-compatible `config`.
+fn rclone_s3_provider(config: &StorageConfig) -> &'static str {
+    let compat = config
+        .compat
+        .as_deref()
+        .and_then(|compat| compat.parse::<CompatPreset>().ok());
+    match (config.provider, compat) {
+        (StorageProvider::Oss, _) => "Alibaba",
+        (StorageProvider::Cos, _) => "TencentCOS",
+        (_, Some(CompatPreset::Ceph)) => "Ceph",
+        (_, Some(CompatPreset::Seaweedfs)) => "SeaweedFS",
+        (_, Some(CompatPreset::R2)) => "Cloudflare",
+        (_, Some(CompatPreset::B2)) => "Other",
+        _ if config.endpoint.is_some() => "Other",
+        _ => "AWS",
+    }
+}
+
+fn rclone_vars(config: &StorageConfig, vars: &mut Vars) {
+    let key = |name: &str| {
+        format!(
+            "RCLONE_CONFIG_{}_{name}",
+            RCLONE_REMOTE.to_ascii_uppercase()
+        )
+    };
+    match config.provider {
+        StorageProvider::S3 | StorageProvider::Oss | StorageProvider::Cos => {
+            vars.plain(key("TYPE"), Some("s3"));
+            vars.plain(key("PROVIDER"), Some(rclone_s3_provider(config)));
+            vars.secret(key("ACCESS_KEY_ID"), config.access_key_id.as_deref());
+            vars.secret(
+                key("SECRET_ACCESS_KEY"),
+                config.access_key_secret.as_deref(),
+            );
+            vars.secret(key("SESSION_TOKEN"), config.session_token.as_deref());
+            vars.plain(key("REGION"), config.region.as_deref());
+            vars.plain(key("ENDPOINT"), config.endpoint.as_deref());
+            // Without keys rclone should use the same ambient credentials storify does.
+            vars.plain(
+                key("ENV_AUTH"),
+                config.access_key_id.is_none().then_some("true"),
+            );
+        }
+        StorageProvider::Azblob => {
+            vars.plain(key("TYPE"), Some("azureblob"));
+            vars.plain(key("ACCOUNT"), config.access_key_id.as_deref());
+            vars.secret(key("KEY"), config.access_key_secret.as_deref());
+        }
+        StorageProvider::Gcs => {
+            vars.plain(key("TYPE"), Some("google cloud storage"));
+            vars.plain(
+                key("SERVICE_ACCOUNT_FILE"),
+                config.credential_path.as_deref(),
+            );
+        }
+        StorageProvider::Fs
+        | StorageProvider::Hdfs
+        | StorageProvider::Http
+        | StorageProvider::Memory => {}
+    }
+}
+
+/// Single-quote `value` for POSIX shells unless it only holds safe characters.
+pub(crate) fn shell_quote(value: &str) -> String {
+    let safe = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:,=@%+".contains(c));
+    if safe {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', r"'\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn s3_config_exports_storify_native_and_rclone_vars() {
+        let mut config = StorageConfig::s3("backups");
+        config.access_key_id = Some("AKIA".to_string());
+        config.access_key_secret = Some("it's secret".to_string());
+        config.region = Some("eu-west-1".to_string());
+        let vars = env_vars(&config);
+        let find = |key: &str| vars.iter().find(|var| var.key == key).cloned();
+
+        assert_eq!(find("STORAGE_BUCKET").unwrap().value, "backups");
+        assert!(find("AWS_SECRET_ACCESS_KEY").unwrap().secret);
+        assert_eq!(find("RCLONE_CONFIG_STORIFY_PROVIDER").unwrap().value, "AWS");
+        assert!(find("AWS_ENDPOINT_URL").is_none());
+
+        let secret = find("STORAGE_ACCESS_KEY_SECRET").unwrap();
+        assert_eq!(
+            EnvFormat::Shell.render(&secret),
+            r"export STORAGE_ACCESS_KEY_SECRET='it'\''s secret'"
+        );
+        assert_eq!(
+            EnvFormat::Docker.render(&secret),
+            r"-e 'STORAGE_ACCESS_KEY_SECRET=it'\''s secret'"
+        );
+        assert_eq!(
+            EnvFormat::EnvFile.render(&find("STORAGE_REGION").unwrap()),
+            "STORAGE_REGION=eu-west-1"
+        );
+    }
+}
@@ -1,8 +1,9 @@
 use crate::config::{
-    ProfileStore, ProfileStoreOpenOptions, StorageProvider, prepare_storage_config,
-    storage_config::StorageConfig,
+    CompatPreset, ProfileStore, ProfileStoreOpenOptions, StorageProvider, prepare_storage_config,
+    profile_store::split_endpoint_list, storage_config::StorageConfig,
 };
 use crate::error::{Error, Result};
+use crate::storage::constants::DEFAULT_IPFS_GATEWAY;
 use secrecy::SecretString;
 use std::env;
 use std::path::PathBuf;
@@ -15,6 +16,18 @@ pub struct ConfigRequest {
     pub non_interactive: bool,
     pub require_storage: bool,
     pub master_password: Option<SecretString>,
+    pub overrides: ConfigOverrides,
+    /// Build an unsigned config from the overrides alone, never opening the profile store
+    pub anonymous: bool,
+}
+
+/// Per-invocation values that take precedence over the resolved configuration.
+#[derive(Debug, Clone, Default)]
+pub struct ConfigOverrides {
+    /// Build a fresh config for this provider instead of resolving a profile
+    pub provider: Option<String>,
+    pub bucket: Option<String>,
+    pub endpoint: Option<String>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -23,6 +36,12 @@ pub enum ConfigSource {
     DefaultProfile,
     Environment,
     TempCache,
+    /// `--provider` on the command line, with credentials from the environment
+    CommandLine,
+    /// Session of a `--replay` recording, with placeholder credentials
+    Recording,
+    /// `--anonymous` with `--bucket`, sending unsigned requests
+    Anonymous,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -34,6 +53,8 @@ pub struct ResolvedConfig {
     pub default_profile: Option<String>,
     pub source: Option<ConfigSource>,
     pub temp_expires_at_unix: Option<u64>,
+    /// Whether `--bucket`/`--endpoint` replaced values from `source`
+    pub overridden: bool,
 }
 
 fn env_value(key: &str) -> Option<String> {
@@ -68,6 +89,7 @@ const OSS_ACCESS_KEY_ID_KEYS: &[&str] = &["STORAGE_ACCESS_KEY_ID", "OSS_ACCESS_K
 const OSS_SECRET_KEY_KEYS: &[&str] = &["STORAGE_ACCESS_KEY_SECRET", "OSS_ACCESS_KEY_SECRET"];
 const OSS_REGION_KEYS: &[&str] = &["STORAGE_REGION", "OSS_REGION"];
 const OSS_ENDPOINT_KEYS: &[&str] = &["STORAGE_ENDPOINT", "OSS_ENDPOINT"];
+const OSS_SESSION_TOKEN_KEYS: &[&str] = &["STORAGE_SESSION_TOKEN", "OSS_SECURITY_TOKEN"];
 
 const S3_BUCKET_KEYS: &[&str] = &["STORAGE_BUCKET", "AWS_S3_BUCKET", "MINIO_BUCKET"];
 const S3_ACCESS_KEY_ID_KEYS: &[&str] = &[
@@ -87,11 +109,25 @@ const S3_REGION_KEYS: &[&str] = &[
 ];
 const S3_ENDPOINT_KEYS: &[&str] = &["STORAGE_ENDPOINT", "MINIO_ENDPOINT"];
 
+const S3_SESSION_TOKEN_KEYS: &[&str] = &["STORAGE_SESSION_TOKEN", "AWS_SESSION_TOKEN"];
+const S3_ROLE_ARN_KEYS: &[&str] = &["STORAGE_ROLE_ARN"];
+const S3_AWS_PROFILE_KEYS: &[&str] = &["STORAGE_AWS_PROFILE"];
+const S3_INSTANCE_METADATA_KEYS: &[&str] = &["STORAGE_USE_INSTANCE_METADATA"];
+const S3_COMPAT_KEYS: &[&str] = &["STORAGE_COMPAT"];
+const R2_ACCOUNT_ID_KEYS: &[&str] = &[
+    "STORAGE_ACCOUNT_ID",
+    "R2_ACCOUNT_ID",
+    "CLOUDFLARE_ACCOUNT_ID",
+];
+const FALLBACK_ENDPOINTS_KEYS: &[&str] = &["STORAGE_FALLBACK_ENDPOINTS"];
+const DATA_KEY_KEYS: &[&str] = &["STORAGE_DATA_KEY"];
+
 const MINIO_BUCKET_KEYS: &[&str] = &["STORAGE_BUCKET", "MINIO_BUCKET"];
 const MINIO_ACCESS_KEY_ID_KEYS: &[&str] = &["STORAGE_ACCESS_KEY_ID", "MINIO_ACCESS_KEY"];
 const MINIO_SECRET_KEY_KEYS: &[&str] = &["STORAGE_ACCESS_KEY_SECRET", "MINIO_SECRET_KEY"];
 const MINIO_REGION_KEYS: &[&str] = &["STORAGE_REGION", "MINIO_DEFAULT_REGION"];
 const MINIO_ENDPOINT_KEYS: &[&str] = &["STORAGE_ENDPOINT", "MINIO_ENDPOINT"];
+const MINIO_SESSION_TOKEN_KEYS: &[&str] = &["STORAGE_SESSION_TOKEN"];
 
 const COS_BUCKET_KEYS: &[&str] = &["STORAGE_BUCKET", "COS_BUCKET"];
 const COS_ACCESS_KEY_ID_KEYS: &[&str] = &["STORAGE_ACCESS_KEY_ID", "COS_SECRET_ID"];
@@ -104,6 +140,29 @@ const AZBLOB_ACCESS_KEY_ID_KEYS: &[&str] = &["STORAGE_ACCESS_KEY_ID", "AZBLOB_AC
 const AZBLOB_SECRET_KEY_KEYS: &[&str] = &["STORAGE_ACCESS_KEY_SECRET", "AZBLOB_ACCOUNT_KEY"];
 const AZBLOB_REGION_KEYS: &[&str] = &["STORAGE_REGION", "AZBLOB_REGION"];
 const AZBLOB_ENDPOINT_KEYS: &[&str] = &["STORAGE_ENDPOINT", "AZBLOB_ENDPOINT"];
+const AZBLOB_SAS_TOKEN_KEYS: &[&str] = &[
+    "STORAGE_SAS_TOKEN",
+    "AZBLOB_SAS_TOKEN",
+    "AZURE_STORAGE_SAS_TOKEN",
+];
+const AZBLOB_CONNECTION_STRING_KEYS: &[&str] = &[
+    "STORAGE_CONNECTION_STRING",
+    "AZBLOB_CONNECTION_STRING",
+    "AZURE_STORAGE_CONNECTION_STRING",
+];
+
+const GCS_BUCKET_KEYS: &[&str] = &["STORAGE_BUCKET", "GCS_BUCKET"];
+const GCS_CREDENTIAL_PATH_KEYS: &[&str] =
+    &["STORAGE_CREDENTIAL_PATH", "GOOGLE_APPLICATION_CREDENTIALS"];
+const GCS_ENDPOINT_KEYS: &[&str] = &["STORAGE_ENDPOINT", "GCS_ENDPOINT"];
+
+const HTTP_ENDPOINT_KEYS: &[&str] = &["STORAGE_ENDPOINT", "HTTP_ENDPOINT"];
+const HTTP_ROOT_PATH_KEYS: &[&str] = &["STORAGE_ROOT_PATH", "HTTP_ROOT_PATH"];
+const HTTP_USERNAME_KEYS: &[&str] = &["STORAGE_ACCESS_KEY_ID", "HTTP_USERNAME"];
+const HTTP_PASSWORD_KEYS: &[&str] = &["STORAGE_ACCESS_KEY_SECRET", "HTTP_PASSWORD"];
+const HTTP_MANIFEST_KEYS: &[&str] = &["STORAGE_MANIFEST", "HTTP_MANIFEST"];
+const IPFS_CID_KEYS: &[&str] = &["STORAGE_BUCKET", "IPFS_CID"];
+const IPFS_GATEWAY_KEYS: &[&str] = &["IPFS_GATEWAY"];
 
 /// Provider-specific environment variable keys
 #[derive(Clone, Copy)]
@@ -174,7 +233,11 @@ fn provider_keys(provider: StorageProvider, raw_provider: &str) -> ProviderKeys
             COS_REGION_KEYS,
             COS_ENDPOINT_KEYS,
         ),
-        StorageProvider::Fs | StorageProvider::Hdfs => unreachable!(
+        StorageProvider::Fs
+        | StorageProvider::Hdfs
+        | StorageProvider::Gcs
+        | StorageProvider::Http
+        | StorageProvider::Memory => unreachable!(
             "provider '{}' does not use cloud environment keys",
             provider.as_str()
         ),
@@ -195,10 +258,22 @@ struct EnvConfig {
     bucket: Option<String>,
     access_key_id: Option<String>,
     access_key_secret: Option<String>,
+    session_token: Option<String>,
     region: Option<String>,
     endpoint: Option<String>,
+    fallback_endpoints: Vec<String>,
     root_path: Option<String>,
     name_node: Option<String>,
+    credential_path: Option<String>,
+    sas_token: Option<String>,
+    connection_string: Option<String>,
+    role_arn: Option<String>,
+    aws_profile: Option<String>,
+    use_instance_metadata: bool,
+    compat: Option<String>,
+    account_id: Option<String>,
+    manifest: Option<String>,
+    data_key: Option<String>,
 }
 
 impl EnvConfig {
@@ -208,10 +283,22 @@ impl EnvConfig {
             bucket: None,
             access_key_id: None,
             access_key_secret: None,
+            session_token: None,
             region: None,
             endpoint: None,
+            fallback_endpoints: Vec::new(),
             root_path: None,
             name_node: None,
+            credential_path: None,
+            sas_token: None,
+            connection_string: None,
+            role_arn: None,
+            aws_profile: None,
+            use_instance_metadata: false,
+            compat: None,
+            account_id: None,
+            manifest: None,
+            data_key: None,
         }
     }
 }
@@ -299,9 +386,157 @@ fn try_load_temp(store: &ProfileStore, resolved: &mut ResolvedConfig) -> Result<
     Ok(true)
 }
 
+/// Build a config for `--provider`, reading credentials from the environment as if
+/// `STORAGE_PROVIDER` were set. `--bucket`/`--endpoint` shadow `STORAGE_BUCKET`/`STORAGE_ENDPOINT`,
+/// which every provider checks first.
+fn load_command_line(
+    overrides: &ConfigOverrides,
+    provider: &str,
+    get: &dyn Fn(&str) -> Option<String>,
+) -> Result<StorageConfig> {
+    let get = |key: &str| match key {
+        "STORAGE_BUCKET" => overrides.bucket.clone().or_else(|| get(key)),
+        "STORAGE_ENDPOINT" => overrides.endpoint.clone().or_else(|| get(key)),
+        _ => get(key),
+    };
+    load_env_config(&get, Some(provider.to_string()))
+        .and_then(build_config)
+        .map_err(with_config_hint)
+}
+
+/// Config for `bucket` on `provider` without a profile, as used for `s3://bucket/key` paths.
+pub fn ad_hoc_config(provider: &str, bucket: &str) -> Result<StorageConfig> {
+    let overrides = ConfigOverrides {
+        bucket: Some(bucket.to_string()),
+        ..Default::default()
+    };
+    load_command_line(&overrides, provider, &env_value)
+}
+
+/// Unsigned config for browsing a public bucket with `--anonymous`.
+///
+/// Endpoints and regions still come from the environment as for `--provider`, but any
+/// credentials found there are dropped so no request is signed.
+pub fn anonymous_config(
+    provider: &str,
+    bucket: &str,
+    endpoint: Option<String>,
+) -> Result<StorageConfig> {
+    load_anonymous(provider, bucket, endpoint, &env_value)
+}
+
+fn load_anonymous(
+    provider: &str,
+    bucket: &str,
+    endpoint: Option<String>,
+    get: &dyn Fn(&str) -> Option<String>,
+) -> Result<StorageConfig> {
+    let overrides = ConfigOverrides {
+        bucket: Some(bucket.to_string()),
+        endpoint,
+        ..Default::default()
+    };
+    let parsed = StorageProvider::from_str(provider)?;
+    if !matches!(
+        parsed,
+        StorageProvider::S3 | StorageProvider::Oss | StorageProvider::Gcs | StorageProvider::Http
+    ) {
+        return Err(Error::InvalidArgument {
+            message: format!(
+                "--anonymous needs the s3, oss, gcs or http provider (got '{}')",
+                parsed.as_str()
+            ),
+        });
+    }
+    let mut config = load_command_line(&overrides, provider, get)?;
+    config.access_key_id = None;
+    config.access_key_secret = None;
+    config.session_token = None;
+    config.credential_path = None;
+    config.role_arn = None;
+    config.aws_profile = None;
+    config.use_instance_metadata = false;
+    config.unsigned = true;
+    prepare_storage_config(&mut config)?;
+    Ok(config)
+}
+
+/// `--anonymous` resolution: a bucket from `--bucket` (on `--provider`, s3 by default),
+/// or nothing when every path is a bucket URI.
+fn resolve_anonymous(request: &ConfigRequest) -> Result<ResolvedConfig> {
+    let overrides = &request.overrides;
+    let mut resolved = ResolvedConfig::default();
+    match overrides.bucket.as_deref() {
+        Some(bucket) => {
+            let provider = overrides.provider.as_deref().unwrap_or("s3");
+            resolved.storage = Some(anonymous_config(
+                provider,
+                bucket,
+                overrides.endpoint.clone(),
+            )?);
+            resolved.source = Some(ConfigSource::Anonymous);
+        }
+        None if request.require_storage => {
+            return Err(Error::InvalidArgument {
+                message: "--anonymous needs a bucket URI such as s3://bucket/path, or --bucket"
+                    .to_string(),
+            });
+        }
+        None => {}
+    }
+    Ok(resolved)
+}
+
+/// Replace bucket/endpoint of an already resolved config.
+fn apply_overrides(overrides: &ConfigOverrides, resolved: &mut ResolvedConfig) -> Result<()> {
+    if overrides.bucket.is_none() && overrides.endpoint.is_none() {
+        return Ok(());
+    }
+    let Some(config) = resolved.storage.as_mut() else {
+        return Err(Error::InvalidArgument {
+            message: "--bucket/--endpoint need a profile or environment config to override; pass --provider to start from scratch".to_string(),
+        });
+    };
+    if let Some(bucket) = &overrides.bucket {
+        config.bucket = bucket.clone();
+    }
+    if let Some(endpoint) = &overrides.endpoint {
+        config.endpoint = Some(endpoint.clone());
+        // Fallbacks belong to the endpoint being replaced.
+        config.fallback_endpoints.clear();
+    }
+    prepare_storage_config(config)?;
+    resolved.overridden = true;
+    Ok(())
+}
+
 pub fn resolve(request: ConfigRequest) -> Result<ResolvedConfig> {
+    if request.anonymous {
+        return resolve_anonymous(&request);
+    }
+    if let Some(provider) = request.overrides.provider.as_deref() {
+        let mut resolved = ResolvedConfig::default();
+        match load_command_line(&request.overrides, provider, &env_value) {
+            Ok(config) => {
+                resolved.storage = Some(config);
+                resolved.source = Some(ConfigSource::CommandLine);
+            }
+            Err(err) if request.require_storage => return Err(err),
+            Err(_) => {}
+        }
+        return Ok(resolved);
+    }
+
+    let mut resolved = resolve_stored(&request)?;
+    match apply_overrides(&request.overrides, &mut resolved) {
+        Err(err) if request.require_storage => Err(err),
+        _ => Ok(resolved),
+    }
+}
+
+fn resolve_stored(request: &ConfigRequest) -> Result<ResolvedConfig> {
     let mut resolved = ResolvedConfig::default();
-    let store = open_and_populate_store(&request, &mut resolved)?;
+    let store = open_and_populate_store(request, &mut resolved)?;
 
     if let Some(profile_name) = request.profile.as_deref() {
         let store = store.as_ref().ok_or_else(|| Error::ProfileStoreLocked {
@@ -342,7 +577,7 @@ pub fn resolve(request: ConfigRequest) -> Result<ResolvedConfig> {
     }
 
     if request.require_storage {
-        ensure_interactive(&request, "Resolving configuration from environment")?;
+        ensure_interactive(request, "Resolving configuration from environment")?;
         return Err(Error::NoConfiguration {
             profiles: resolved.available_profiles.join(", "),
         });
@@ -367,14 +602,21 @@ fn load_env_config(
 
     let provider = StorageProvider::from_str(&provider_str)?;
 
-    let env = match provider {
-        StorageProvider::Oss
-        | StorageProvider::S3
-        | StorageProvider::Cos
-        | StorageProvider::Azblob => load_cloud_env(provider, &provider_str, get),
+    let mut env = match provider {
+        StorageProvider::Oss => load_oss_env(&provider_str, get),
+        StorageProvider::Cos => load_cloud_env(provider, &provider_str, get),
+        StorageProvider::S3 => load_s3_env(&provider_str, get),
+        StorageProvider::Azblob => load_azblob_env(&provider_str, get),
         StorageProvider::Fs => load_fs_env(get),
         StorageProvider::Hdfs => load_hdfs_env(get),
+        StorageProvider::Gcs => load_gcs_env(get),
+        StorageProvider::Http => load_http_env(&provider_str, get),
+        StorageProvider::Memory => load_memory_env(get),
     }?;
+    env.fallback_endpoints = env_any_from(FALLBACK_ENDPOINTS_KEYS, get)
+        .map(|raw| split_endpoint_list(&raw))
+        .unwrap_or_default();
+    env.data_key = env_any_from(DATA_KEY_KEYS, get);
     Ok(env)
 }
 
@@ -406,6 +648,109 @@ fn load_hdfs_env(get: &dyn Fn(&str) -> Option<String>) -> Result<RawConfigValues
     Ok(env)
 }
 
+/// OSS: cloud keys plus an optional STS security token
+fn load_oss_env(
+    raw_provider: &str,
+    get: &dyn Fn(&str) -> Option<String>,
+) -> Result<RawConfigValues> {
+    let mut env = load_cloud_env(StorageProvider::Oss, raw_provider, get)?;
+    env.session_token = env_any_from(OSS_SESSION_TOKEN_KEYS, get);
+    Ok(env)
+}
+
+/// S3: cloud keys plus session token, role assumption, a named AWS profile or instance metadata,
+/// and an optional compatibility preset (implied by the `r2`/`b2` provider aliases).
+///
+/// `AWS_PROFILE`/`AWS_ROLE_ARN` are deliberately not read here: when no credentials are
+/// configured the default AWS chain already honours them (including web identity on EKS).
+fn load_s3_env(
+    raw_provider: &str,
+    get: &dyn Fn(&str) -> Option<String>,
+) -> Result<RawConfigValues> {
+    let mut env = load_cloud_env(StorageProvider::S3, raw_provider, get)?;
+    env.session_token = if raw_provider.eq_ignore_ascii_case("minio") {
+        env_any_from(MINIO_SESSION_TOKEN_KEYS, get)
+    } else {
+        env_any_from(S3_SESSION_TOKEN_KEYS, get)
+    };
+    env.role_arn = env_any_from(S3_ROLE_ARN_KEYS, get);
+    env.aws_profile = env_any_from(S3_AWS_PROFILE_KEYS, get);
+    env.use_instance_metadata = env_any_from(S3_INSTANCE_METADATA_KEYS, get)
+        .is_some_and(|value| matches!(value.to_ascii_lowercase().as_str(), "1" | "true" | "yes"));
+    env.compat = env_any_from(S3_COMPAT_KEYS, get);
+    if let Some(preset) = CompatPreset::from_provider_alias(raw_provider) {
+        match env.compat.as_deref() {
+            Some(compat) if !compat.eq_ignore_ascii_case(preset.as_str()) => {
+                return Err(Error::InvalidArgument {
+                    message: format!(
+                        "provider '{raw_provider}' conflicts with STORAGE_COMPAT={compat}"
+                    ),
+                });
+            }
+            _ => env.compat = Some(preset.as_str().to_string()),
+        }
+    }
+    env.account_id = env_any_from(R2_ACCOUNT_ID_KEYS, get);
+    Ok(env)
+}
+
+/// Azblob: cloud keys plus the alternative SAS token / connection string credentials
+fn load_azblob_env(
+    raw_provider: &str,
+    get: &dyn Fn(&str) -> Option<String>,
+) -> Result<RawConfigValues> {
+    let mut env = load_cloud_env(StorageProvider::Azblob, raw_provider, get)?;
+    env.sas_token = env_any_from(AZBLOB_SAS_TOKEN_KEYS, get);
+    env.connection_string = env_any_from(AZBLOB_CONNECTION_STRING_KEYS, get);
+    Ok(env)
+}
+
+/// GCS: `STORAGE_*` > `GCS_*`, credentials from `GOOGLE_APPLICATION_CREDENTIALS`
+fn load_gcs_env(get: &dyn Fn(&str) -> Option<String>) -> Result<RawConfigValues> {
+    let mut env = EnvConfig::new(StorageProvider::Gcs);
+    env.bucket = Some(env_any_required_from(GCS_BUCKET_KEYS, get)?);
+    env.credential_path = env_any_from(GCS_CREDENTIAL_PATH_KEYS, get);
+    env.endpoint = env_any_from(GCS_ENDPOINT_KEYS, get);
+    Ok(env)
+}
+
+/// Memory: `STORAGE_BUCKET` names the in-process store (default: `default`).
+fn load_memory_env(get: &dyn Fn(&str) -> Option<String>) -> Result<RawConfigValues> {
+    let mut env = EnvConfig::new(StorageProvider::Memory);
+    env.bucket = get("STORAGE_BUCKET");
+    Ok(env)
+}
+
+/// HTTP: `STORAGE_*` > `HTTP_*`, with basic-auth credentials in the access key pair.
+///
+/// A `https://host/key` path arrives as `STORAGE_BUCKET=host` and is read from that host;
+/// `ipfs://<cid>/key` is read through `IPFS_GATEWAY` (default: ipfs.io).
+fn load_http_env(
+    raw_provider: &str,
+    get: &dyn Fn(&str) -> Option<String>,
+) -> Result<RawConfigValues> {
+    let mut env = EnvConfig::new(StorageProvider::Http);
+    env.access_key_id = env_any_from(HTTP_USERNAME_KEYS, get);
+    env.access_key_secret = env_any_from(HTTP_PASSWORD_KEYS, get);
+    env.manifest = env_any_from(HTTP_MANIFEST_KEYS, get);
+    if raw_provider.eq_ignore_ascii_case("ipfs") {
+        let cid = env_any_required_from(IPFS_CID_KEYS, get)?;
+        env.endpoint = Some(
+            env_any_from(IPFS_GATEWAY_KEYS, get)
+                .unwrap_or_else(|| DEFAULT_IPFS_GATEWAY.to_string()),
+        );
+        env.root_path = Some(format!("/ipfs/{cid}/"));
+        env.bucket = Some(cid);
+    } else if let Some(host) = get("STORAGE_BUCKET") {
+        env.endpoint = Some(format!("{}://{host}", raw_provider.to_ascii_lowercase()));
+        env.bucket = Some(host);
+    } else {
+        env.endpoint = env_any_from(HTTP_ENDPOINT_KEYS, get);
+        env.root_path = env_any_from(HTTP_ROOT_PATH_KEYS, get);
+    }
+    Ok(env)
+}
+
 fn require_bucket(bucket: &mut Option<String>, provider: StorageProvider) -> Result<String> {
     bucket.take().ok_or_else(|| Error::MissingConfigField {
         provider: provider.as_str().to_string(),
@@ -419,10 +764,22 @@ fn build_config(env: RawConfigValues) -> Result<StorageConfig> {
         mut bucket,
         access_key_id,
         access_key_secret,
+        session_token,
         region,
         endpoint,
+        fallback_endpoints,
         mut root_path,
         mut name_node,
+        credential_path,
+        sas_token,
+        connection_string,
+        role_arn,
+        aws_profile,
+        use_instance_metadata,
+        compat,
+        account_id,
+        manifest,
+        data_key,
     } = env;
 
     let mut config = match provider {
@@ -432,12 +789,36 @@ fn build_config(env: RawConfigValues) -> Result<StorageConfig> {
         StorageProvider::Fs => StorageConfig::fs(root_path.take()),
         StorageProvider::Hdfs => StorageConfig::hdfs(name_node.take(), root_path.take()),
         StorageProvider::Azblob => StorageConfig::azblob(require_bucket(&mut bucket, provider)?),
+        StorageProvider::Gcs => StorageConfig::gcs(require_bucket(&mut bucket, provider)?),
+        StorageProvider::Memory => {
+            StorageConfig::memory(bucket.take().unwrap_or_else(|| "default".to_string()))
+        }
+        StorageProvider::Http => {
+            let mut config = StorageConfig::http(endpoint.clone());
+            config.root_path = root_path.take();
+            if let Some(label) = bucket.take() {
+                config.bucket = label;
+            }
+            config
+        }
     };
 
     config.access_key_id = access_key_id;
     config.access_key_secret = access_key_secret;
+    config.session_token = session_token;
     config.region = region;
     config.endpoint = endpoint;
+    config.fallback_endpoints = fallback_endpoints;
+    config.credential_path = credential_path;
+    config.sas_token = sas_token;
+    config.connection_string = connection_string;
+    config.role_arn = role_arn;
+    config.aws_profile = aws_profile;
+    config.use_instance_metadata = use_instance_metadata;
+    config.compat = compat;
+    config.account_id = account_id;
+    config.manifest = manifest;
+    config.data_key = data_key;
 
     prepare_storage_config(&mut config)?;
     Ok(config)
@@ -551,4 +932,285 @@ mod tests {
         assert_eq!(raw.region.as_deref(), Some("us-east-1"));
         assert_eq!(raw.endpoint.as_deref(), Some("https://minio.example"));
     }
+
+    #[test]
+    fn gcs_env_reads_google_application_credentials() {
+        let env = TestEnv::new(&[
+            ("STORAGE_PROVIDER", Some("gcs")),
+            ("GCS_BUCKET", Some("bucket")),
+            ("GOOGLE_APPLICATION_CREDENTIALS", Some("/etc/gcp/sa.json")),
+            ("STORAGE_ACCESS_KEY_ID", Some("ignored")),
+        ]);
+        let getter = env.getter();
+
+        let config = build_from_env(&getter, None).expect("gcs config should resolve");
+        assert_eq!(config.provider, StorageProvider::Gcs);
+        assert_eq!(config.bucket, "bucket");
+        assert_eq!(config.credential_path.as_deref(), Some("/etc/gcp/sa.json"));
+        assert_eq!(config.access_key_id, None);
+        assert!(!config.anonymous);
+    }
+
+    #[test]
+    fn s3_env_reads_compat_preset() {
+        let env = TestEnv::new(&[
+            ("STORAGE_PROVIDER", Some("s3")),
+            ("STORAGE_BUCKET", Some("bucket")),
+            ("STORAGE_COMPAT", Some("seaweedfs")),
+        ]);
+        let getter = env.getter();
+        let config = build_from_env(&getter, None).expect("known preset should resolve");
+        assert_eq!(config.compat.as_deref(), Some("seaweedfs"));
+        assert_eq!(config.region, None);
+
+        let env = TestEnv::new(&[
+            ("STORAGE_PROVIDER", Some("s3")),
+            ("STORAGE_BUCKET", Some("bucket")),
+            ("STORAGE_COMPAT", Some("wasabi")),
+        ]);
+        let getter = env.getter();
+        let err = build_from_env(&getter, None).expect_err("unknown preset");
+        assert!(matches!(err, Error::InvalidArgument { .. }));
+    }
+
+    #[test]
+    fn r2_and_b2_aliases_imply_compat_preset() {
+        let env = TestEnv::new(&[
+            ("STORAGE_PROVIDER", Some("r2")),
+            ("STORAGE_BUCKET", Some("bucket")),
+            ("R2_ACCOUNT_ID", Some("abc123")),
+        ]);
+        let getter = env.getter();
+        let config = build_from_env(&getter, None).expect("r2 with account id resolves");
+        assert_eq!(config.provider, StorageProvider::S3);
+        assert_eq!(config.compat.as_deref(), Some("r2"));
+        assert_eq!(config.account_id.as_deref(), Some("abc123"));
+
+        let env = TestEnv::new(&[
+            ("STORAGE_PROVIDER", Some("b2")),
+            ("STORAGE_BUCKET", Some("bucket")),
+        ]);
+        let getter = env.getter();
+        let err = build_from_env(&getter, None).expect_err("b2 needs a region");
+        assert!(matches!(err, Error::MissingConfigField { .. }));
+
+        let env = TestEnv::new(&[
+            ("STORAGE_PROVIDER", Some("b2")),
+            ("STORAGE_BUCKET", Some("bucket")),
+            ("STORAGE_COMPAT", Some("r2")),
+        ]);
+        let getter = env.getter();
+        let err = build_from_env(&getter, None).expect_err("alias conflicts with preset");
+        assert!(matches!(err, Error::InvalidArgument { .. }));
+    }
+
+    #[test]
+    fn anonymous_configs_drop_environment_credentials() {
+        let env = TestEnv::new(&[
+            ("AWS_ACCESS_KEY_ID", Some("AKIAEXAMPLE")),
+            ("AWS_SECRET_ACCESS_KEY", Some("secret")),
+            ("AWS_DEFAULT_REGION", Some("eu-west-1")),
+        ]);
+        let getter = env.getter();
+        let config = load_anonymous("s3", "open-data", None, &getter).expect("s3 is anonymous");
+        assert_eq!(config.bucket, "open-data");
+        assert_eq!(config.region.as_deref(), Some("eu-west-1"));
+        assert_eq!(config.access_key_id, None);
+        assert!(config.unsigned && config.anonymous);
+
+        let config = load_anonymous("gcs", "public", None, &getter).expect("gcs is anonymous");
+        assert!(config.unsigned);
+
+        let err = load_anonymous("azblob", "c", None, &getter).expect_err("azblob signs");
+        assert!(matches!(err, Error::InvalidArgument { .. }));
+    }
+
+    #[test]
+    fn http_and_ipfs_paths_derive_endpoint() {
+        let env = TestEnv::new(&[
+            ("STORAGE_PROVIDER", Some("http")),
+            ("STORAGE_ENDPOINT", Some("https://data.example.com/pub")),
+            ("HTTP_MANIFEST", Some("MANIFEST.txt")),
+        ]);
+        let getter = env.getter();
+        let config = build_from_env(&getter, None).expect("http endpoint resolves");
+        assert_eq!(config.provider, StorageProvider::Http);
+        assert_eq!(config.bucket, "data.example.com");
+        assert_eq!(config.manifest.as_deref(), Some("MANIFEST.txt"));
+        assert!(config.anonymous);
+
+        let env = TestEnv::new(&[("STORAGE_BUCKET", Some("mirror.example.org"))]);
+        let getter = env.getter();
+        let config = build_from_env(&getter, Some("https".to_string())).expect("https uri");
+        assert_eq!(
+            config.endpoint.as_deref(),
+            Some("https://mirror.example.org")
+        );
+
+        let env = TestEnv::new(&[("STORAGE_BUCKET", Some("bafycid"))]);
+        let getter = env.getter();
+        let config = build_from_env(&getter, Some("ipfs".to_string())).expect("ipfs uri");
+        assert_eq!(config.endpoint.as_deref(), Some(DEFAULT_IPFS_GATEWAY));
+        assert_eq!(config.root_path.as_deref(), Some("/ipfs/bafycid/"));
+        assert_eq!(config.bucket, "bafycid");
+    }
+
+    #[test]
+    fn s3_env_aws_profile_replaces_static_keys() {
+        let env = TestEnv::new(&[
+            ("STORAGE_PROVIDER", Some("s3")),
+            ("STORAGE_BUCKET", Some("bucket")),
+            ("STORAGE_AWS_PROFILE", Some("ci")),
+            (
+                "STORAGE_ROLE_ARN",
+                Some("arn:aws:iam::123456789012:role/reader"),
+            ),
+        ]);
+        let getter = env.getter();
+
+        let config = build_from_env(&getter, None).expect("profile is a credential source");
+        assert_eq!(config.aws_profile.as_deref(), Some("ci"));
+        assert!(config.role_arn.is_some());
+        assert!(!config.anonymous);
+
+        let env = TestEnv::new(&[
+            ("STORAGE_PROVIDER", Some("s3")),
+            ("STORAGE_BUCKET", Some("bucket")),
+            ("STORAGE_ACCESS_KEY_ID", Some("id")),
+            ("STORAGE_ACCESS_KEY_SECRET", Some("secret")),
+            ("STORAGE_USE_INSTANCE_METADATA", Some("true")),
+        ]);
+        let getter = env.getter();
+        let err = build_from_env(&getter, None).expect_err("keys and IMDS are exclusive");
+        assert!(matches!(err, Error::ConflictingConfigFields { .. }));
+    }
+
+    #[test]
+    fn oss_env_reads_security_token() {
+        let env = TestEnv::new(&[
+            ("STORAGE_PROVIDER", Some("oss")),
+            ("OSS_BUCKET", Some("bucket")),
+            ("OSS_ACCESS_KEY_ID", Some("STS.id")),
+            ("OSS_ACCESS_KEY_SECRET", Some("secret")),
+            ("OSS_SECURITY_TOKEN", Some("token")),
+        ]);
+        let getter = env.getter();
+
+        let config = build_from_env(&getter, None).expect("sts credentials should resolve");
+        assert_eq!(config.session_token.as_deref(), Some("token"));
+        assert!(!config.anonymous);
+
+        let env = TestEnv::new(&[
+            ("STORAGE_PROVIDER", Some("s3")),
+            ("STORAGE_BUCKET", Some("bucket")),
+            ("AWS_SESSION_TOKEN", Some("token")),
+        ]);
+        let getter = env.getter();
+        let err = build_from_env(&getter, None).expect_err("token needs access keys");
+        assert!(matches!(err, Error::MissingConfigField { .. }));
+    }
+
+    #[test]
+    fn fallback_endpoints_follow_endpoint_support() {
+        let env = TestEnv::new(&[
+            ("STORAGE_PROVIDER", Some("s3")),
+            ("STORAGE_BUCKET", Some("bucket")),
+            ("STORAGE_ENDPOINT", Some("http://vpc.internal")),
+            (
+                "STORAGE_FALLBACK_ENDPOINTS",
+                Some("https://public.example, ,https://backup.example"),
+            ),
+        ]);
+        let getter = env.getter();
+        let config = build_from_env(&getter, None).expect("s3 config");
+        assert_eq!(
+            config.fallback_endpoints,
+            vec!["https://public.example", "https://backup.example"]
+        );
+
+        let env = TestEnv::new(&[
+            ("STORAGE_PROVIDER", Some("fs")),
+            (
+                "STORAGE_FALLBACK_ENDPOINTS",
+                Some("https://ignored.example"),
+            ),
+        ]);
+        let getter = env.getter();
+        let config = build_from_env(&getter, None).expect("fs config");
+        assert!(config.fallback_endpoints.is_empty());
+    }
+
+    #[test]
+    fn azblob_env_accepts_connection_string() {
+        let env = TestEnv::new(&[
+            ("STORAGE_PROVIDER", Some("azblob")),
+            ("AZBLOB_CONTAINER", Some("container")),
+            (
+                "AZURE_STORAGE_CONNECTION_STRING",
+                Some("DefaultEndpointsProtocol=https;AccountName=acct;AccountKey=a2V5"),
+            ),
+        ]);
+        let getter = env.getter();
+
+        let config = build_from_env(&getter, None).expect("connection string is a credential");
+        assert_eq!(config.bucket, "container");
+        assert!(config.connection_string.is_some());
+        assert_eq!(config.access_key_secret, None);
+    }
+
+    #[test]
+    fn azblob_env_rejects_multiple_credential_modes() {
+        let env = TestEnv::new(&[
+            ("STORAGE_PROVIDER", Some("azblob")),
+            ("AZBLOB_CONTAINER", Some("container")),
+            ("AZBLOB_ACCOUNT_NAME", Some("acct")),
+            ("AZBLOB_ACCOUNT_KEY", Some("key")),
+            ("AZBLOB_SAS_TOKEN", Some("sv=2022&sig=abc")),
+        ]);
+        let getter = env.getter();
+
+        let err = build_from_env(&getter, None).expect_err("key and SAS are exclusive");
+        assert!(matches!(err, Error::ConflictingConfigFields { .. }));
+    }
+
+    #[test]
+    fn command_line_overrides_shadow_environment() {
+        let env = TestEnv::new(&[
+            ("STORAGE_PROVIDER", Some("oss")),
+            ("STORAGE_BUCKET", Some("env-bucket")),
+            ("AWS_ACCESS_KEY_ID", Some("id")),
+            ("AWS_SECRET_ACCESS_KEY", Some("secret")),
+            ("STORAGE_FALLBACK_ENDPOINTS", Some("http://fallback")),
+        ]);
+        let getter = env.getter();
+        let overrides = ConfigOverrides {
+            provider: Some("s3".to_string()),
+            bucket: Some("tmp".to_string()),
+            endpoint: Some("http://localhost:9000".to_string()),
+        };
+
+        let config = load_command_line(&overrides, "s3", &getter).unwrap();
+        assert_eq!(config.provider, StorageProvider::S3);
+        assert_eq!(config.bucket, "tmp");
+        assert_eq!(config.endpoint.as_deref(), Some("http://localhost:9000"));
+        assert_eq!(config.access_key_id.as_deref(), Some("id"));
+
+        let mut resolved = ResolvedConfig {
+            storage: Some(config),
+            ..Default::default()
+        };
+        resolved.storage.as_mut().unwrap().fallback_endpoints = vec!["http://fallback".into()];
+        let overrides = ConfigOverrides {
+            endpoint: Some("http://other:9000".to_string()),
+            ..Default::default()
+        };
+        apply_overrides(&overrides, &mut resolved).unwrap();
+        let config = resolved.storage.as_ref().unwrap();
+        assert_eq!(config.endpoint.as_deref(), Some("http://other:9000"));
+        assert!(config.fallback_endpoints.is_empty());
+        assert!(resolved.overridden);
+
+        let mut empty = ResolvedConfig::default();
+        assert!(apply_overrides(&overrides, &mut empty).is_err());
+    }
 }
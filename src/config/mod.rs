@@ -1,12 +1,19 @@
+pub mod aws_profile;
+pub mod compat;
 pub mod crypto;
+pub mod encryption;
+pub mod env_export;
 pub mod loader;
 pub mod profile_store;
 pub mod provider;
 pub mod spec;
 pub mod storage_config;
 
+pub use compat::CompatPreset;
+pub use encryption::{ServerSideEncryption, SseAlgorithm};
+pub use env_export::{EnvFormat, env_vars};
 pub use loader::ConfigSource;
-pub use profile_store::{ProfileStore, ProfileStoreOpenOptions, StoredProfile};
+pub use profile_store::{ProfileField, ProfileStore, ProfileStoreOpenOptions, StoredProfile};
 pub use provider::StorageProvider;
 pub use spec::{ProviderBackend, prepare_storage_backend, prepare_storage_config};
 pub use storage_config::StorageConfig;
@@ -1,14 +1,17 @@
 use crate::config::{
     StorageProvider,
     crypto::{
-        EncryptionMetadata, decrypt_field, derive_master_key, encrypt_field, generate_salt,
-        resolve_master_password,
+        EncryptionMetadata, FIELD_ENCRYPTED_PREFIX, decrypt_field, derive_master_key,
+        encrypt_field, generate_salt, resolve_master_password,
     },
-    storage_config::StorageConfig,
+    spec::{Requirement, provider_spec},
+    storage_config::{StorageConfig, header_lines, parse_alias, parse_header_lines},
 };
 use crate::error::{Error, Result};
+use crate::scratch::StagedFile;
 use base64::Engine;
 use base64::engine::general_purpose::STANDARD as BASE64_ENGINE;
+use clap::ValueEnum;
 use secrecy::SecretString;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
@@ -18,7 +21,6 @@ use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
-use uuid::Uuid;
 
 #[cfg(unix)]
 use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
@@ -35,13 +37,62 @@ pub struct StoredProfile {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub access_key_secret: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub endpoint: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fallback_endpoints: Vec<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub region: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub root_path: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub name_node: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub credential_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sas_token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub connection_string: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub role_arn: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aws_profile: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub use_instance_metadata: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub compat: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub manifest: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retries: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_backoff_ms: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate_limit: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connect_timeout_secs: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pool_size: Option<usize>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub request_payer: bool,
+    /// Extra request headers as `Name: value` lines; encrypted, as they may carry auth
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub headers: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub trash: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_jobs: Option<usize>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub human_readable: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_key: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub aliases: BTreeMap<String, String>,
     // Not serialized to file; derived at runtime based on presence of credentials
     #[serde(skip)]
     pub anonymous: bool,
@@ -54,10 +105,34 @@ impl StoredProfile {
             bucket: config.bucket.clone(),
             access_key_id: config.access_key_id.clone(),
             access_key_secret: config.access_key_secret.clone(),
+            session_token: config.session_token.clone(),
             endpoint: config.endpoint.clone(),
+            fallback_endpoints: config.fallback_endpoints.clone(),
             region: config.region.clone(),
             root_path: config.root_path.clone(),
             name_node: config.name_node.clone(),
+            credential_path: config.credential_path.clone(),
+            sas_token: config.sas_token.clone(),
+            connection_string: config.connection_string.clone(),
+            role_arn: config.role_arn.clone(),
+            aws_profile: config.aws_profile.clone(),
+            use_instance_metadata: config.use_instance_metadata,
+            compat: config.compat.clone(),
+            account_id: config.account_id.clone(),
+            manifest: config.manifest.clone(),
+            retries: config.retries,
+            retry_backoff_ms: config.retry_backoff_ms,
+            rate_limit: config.rate_limit,
+            timeout_secs: config.timeout_secs,
+            connect_timeout_secs: config.connect_timeout_secs,
+            pool_size: config.pool_size,
+            request_payer: config.request_payer,
+            headers: header_lines(&config.headers),
+            trash: config.trash,
+            default_jobs: config.default_jobs,
+            human_readable: config.human_readable,
+            data_key: config.data_key.clone(),
+            aliases: config.aliases.clone(),
             anonymous: config.anonymous,
         }
     }
@@ -69,15 +144,375 @@ impl StoredProfile {
             bucket: self.bucket,
             access_key_id: self.access_key_id,
             access_key_secret: self.access_key_secret,
+            session_token: self.session_token,
             endpoint: self.endpoint,
+            fallback_endpoints: self.fallback_endpoints,
             region: self.region,
             root_path: self.root_path,
             name_node: self.name_node,
+            credential_path: self.credential_path,
+            sas_token: self.sas_token,
+            connection_string: self.connection_string,
+            role_arn: self.role_arn,
+            aws_profile: self.aws_profile,
+            use_instance_metadata: self.use_instance_metadata,
+            compat: self.compat,
+            account_id: self.account_id,
+            manifest: self.manifest,
+            storage_class: None,
+            encryption: None,
+            retries: self.retries,
+            retry_backoff_ms: self.retry_backoff_ms,
+            rate_limit: self.rate_limit,
+            timeout_secs: self.timeout_secs,
+            connect_timeout_secs: self.connect_timeout_secs,
+            pool_size: self.pool_size,
+            request_payer: self.request_payer,
+            headers: self
+                .headers
+                .as_deref()
+                .map(parse_header_lines)
+                .transpose()?
+                .unwrap_or_default(),
+            trash: self.trash,
+            default_jobs: self.default_jobs,
+            human_readable: self.human_readable,
+            aliases: self.aliases,
+            data_key: self.data_key,
             anonymous: self.anonymous,
+            unsigned: false,
         };
         crate::config::prepare_storage_config(&mut config)?;
         Ok(config)
     }
+
+    /// Current value of `field`; `use_instance_metadata` and `trash` read as `"true"` when
+    /// enabled,
+    /// `fallback_endpoints` as a comma-separated list, `aliases` as `NAME=COMMAND` lines and
+    /// numeric fields in decimal.
+    pub fn field(&self, field: ProfileField) -> Option<String> {
+        match field {
+            ProfileField::Bucket => Some(self.bucket.clone()).filter(|b| !b.is_empty()),
+            ProfileField::FallbackEndpoints => {
+                Some(self.fallback_endpoints.join(",")).filter(|list| !list.is_empty())
+            }
+            ProfileField::UseInstanceMetadata => {
+                self.use_instance_metadata.then(|| "true".to_string())
+            }
+            ProfileField::Retries => self.retries.map(|n| n.to_string()),
+            ProfileField::RetryBackoffMs => self.retry_backoff_ms.map(|ms| ms.to_string()),
+            ProfileField::RateLimit => self.rate_limit.map(|n| n.to_string()),
+            ProfileField::TimeoutSecs => self.timeout_secs.map(|secs| secs.to_string()),
+            ProfileField::ConnectTimeoutSecs => {
+                self.connect_timeout_secs.map(|secs| secs.to_string())
+            }
+            ProfileField::PoolSize => self.pool_size.map(|n| n.to_string()),
+            ProfileField::RequestPayer => self.request_payer.then(|| "true".to_string()),
+            ProfileField::Trash => self.trash.then(|| "true".to_string()),
+            ProfileField::DefaultJobs => self.default_jobs.map(|n| n.to_string()),
+            ProfileField::HumanReadable => self.human_readable.then(|| "true".to_string()),
+            ProfileField::Aliases => (!self.aliases.is_empty()).then(|| {
+                self.aliases
+                    .iter()
+                    .map(|(name, command)| format!("{name}={command}"))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            }),
+            _ => self.optional_field_ref(field).clone(),
+        }
+    }
+
+    /// Set (`Some`) or clear (`None`) a single field.
+    ///
+    /// `aliases` is merged: each `NAME=COMMAND` line adds or replaces one alias and
+    /// `NAME=` removes it.
+    pub fn set_field(&mut self, field: ProfileField, value: Option<String>) -> Result<()> {
+        match field {
+            ProfileField::Bucket => {
+                self.bucket = value.ok_or_else(|| Error::InvalidArgument {
+                    message: "bucket cannot be unset; pass --bucket to change it".into(),
+                })?;
+            }
+            ProfileField::FallbackEndpoints => {
+                self.fallback_endpoints = value
+                    .as_deref()
+                    .map(split_endpoint_list)
+                    .unwrap_or_default();
+            }
+            ProfileField::UseInstanceMetadata => {
+                self.use_instance_metadata = match value.as_deref() {
+                    None => false,
+                    Some(raw) => raw.parse().map_err(|_| Error::InvalidArgument {
+                        message: format!(
+                            "use_instance_metadata expects true or false, got '{raw}'"
+                        ),
+                    })?,
+                };
+            }
+            ProfileField::Retries => self.retries = parse_number(field, value.as_deref(), 0)?,
+            ProfileField::RetryBackoffMs => {
+                self.retry_backoff_ms = parse_number(field, value.as_deref(), 1)?;
+            }
+            ProfileField::RateLimit => {
+                self.rate_limit = parse_number(field, value.as_deref(), 1)?;
+            }
+            ProfileField::TimeoutSecs => {
+                self.timeout_secs = parse_number(field, value.as_deref(), 1)?;
+            }
+            ProfileField::ConnectTimeoutSecs => {
+                self.connect_timeout_secs = parse_number(field, value.as_deref(), 1)?;
+            }
+            ProfileField::PoolSize => self.pool_size = parse_number(field, value.as_deref(), 0)?,
+            ProfileField::RequestPayer => {
+                self.request_payer = match value.as_deref() {
+                    None => false,
+                    Some(raw) => raw.parse().map_err(|_| Error::InvalidArgument {
+                        message: format!("request_payer expects true or false, got '{raw}'"),
+                    })?,
+                };
+            }
+            ProfileField::Headers => {
+                if let Some(lines) = value.as_deref() {
+                    parse_header_lines(lines)?;
+                }
+                self.headers = value;
+            }
+            ProfileField::Trash => {
+                self.trash = match value.as_deref() {
+                    None => false,
+                    Some(raw) => raw.parse().map_err(|_| Error::InvalidArgument {
+                        message: format!("trash expects true or false, got '{raw}'"),
+                    })?,
+                };
+            }
+            ProfileField::DefaultJobs => {
+                self.default_jobs = parse_number(field, value.as_deref(), 1)?;
+            }
+            ProfileField::HumanReadable => {
+                self.human_readable = match value.as_deref() {
+                    None => false,
+                    Some(raw) => raw.parse().map_err(|_| Error::InvalidArgument {
+                        message: format!("human_readable expects true or false, got '{raw}'"),
+                    })?,
+                };
+            }
+            ProfileField::Aliases => match value.as_deref() {
+                None => self.aliases.clear(),
+                Some(lines) => {
+                    for line in lines.lines().filter(|line| !line.trim().is_empty()) {
+                        let (name, command) = parse_alias(line)?;
+                        if command.is_empty() {
+                            self.aliases.remove(&name);
+                        } else {
+                            self.aliases.insert(name, command);
+                        }
+                    }
+                }
+            },
+            _ => *self.optional_field_mut(field) = value,
+        }
+        Ok(())
+    }
+
+    fn optional_field_ref(&self, field: ProfileField) -> &Option<String> {
+        match field {
+            ProfileField::AccessKeyId => &self.access_key_id,
+            ProfileField::AccessKeySecret => &self.access_key_secret,
+            ProfileField::SessionToken => &self.session_token,
+            ProfileField::Endpoint => &self.endpoint,
+            ProfileField::Region => &self.region,
+            ProfileField::RootPath => &self.root_path,
+            ProfileField::NameNode => &self.name_node,
+            ProfileField::CredentialPath => &self.credential_path,
+            ProfileField::SasToken => &self.sas_token,
+            ProfileField::ConnectionString => &self.connection_string,
+            ProfileField::RoleArn => &self.role_arn,
+            ProfileField::AwsProfile => &self.aws_profile,
+            ProfileField::Compat => &self.compat,
+            ProfileField::AccountId => &self.account_id,
+            ProfileField::Manifest => &self.manifest,
+            ProfileField::DataKey => &self.data_key,
+            ProfileField::Headers => &self.headers,
+            ProfileField::Bucket
+            | ProfileField::FallbackEndpoints
+            | ProfileField::UseInstanceMetadata
+            | ProfileField::Retries
+            | ProfileField::RetryBackoffMs
+            | ProfileField::RateLimit
+            | ProfileField::TimeoutSecs
+            | ProfileField::ConnectTimeoutSecs
+            | ProfileField::PoolSize
+            | ProfileField::RequestPayer
+            | ProfileField::Trash
+            | ProfileField::DefaultJobs
+            | ProfileField::HumanReadable
+            | ProfileField::Aliases => {
+                unreachable!("{field:?} is not an optional string field")
+            }
+        }
+    }
+
+    fn optional_field_mut(&mut self, field: ProfileField) -> &mut Option<String> {
+        match field {
+            ProfileField::AccessKeyId => &mut self.access_key_id,
+            ProfileField::AccessKeySecret => &mut self.access_key_secret,
+            ProfileField::SessionToken => &mut self.session_token,
+            ProfileField::Endpoint => &mut self.endpoint,
+            ProfileField::Region => &mut self.region,
+            ProfileField::RootPath => &mut self.root_path,
+            ProfileField::NameNode => &mut self.name_node,
+            ProfileField::CredentialPath => &mut self.credential_path,
+            ProfileField::SasToken => &mut self.sas_token,
+            ProfileField::ConnectionString => &mut self.connection_string,
+            ProfileField::RoleArn => &mut self.role_arn,
+            ProfileField::AwsProfile => &mut self.aws_profile,
+            ProfileField::Compat => &mut self.compat,
+            ProfileField::AccountId => &mut self.account_id,
+            ProfileField::Manifest => &mut self.manifest,
+            ProfileField::DataKey => &mut self.data_key,
+            ProfileField::Headers => &mut self.headers,
+            ProfileField::Bucket
+            | ProfileField::FallbackEndpoints
+            | ProfileField::UseInstanceMetadata
+            | ProfileField::Retries
+            | ProfileField::RetryBackoffMs
+            | ProfileField::RateLimit
+            | ProfileField::TimeoutSecs
+            | ProfileField::ConnectTimeoutSecs
+            | ProfileField::PoolSize
+            | ProfileField::RequestPayer
+            | ProfileField::Trash
+            | ProfileField::DefaultJobs
+            | ProfileField::HumanReadable
+            | ProfileField::Aliases => {
+                unreachable!("{field:?} is not an optional string field")
+            }
+        }
+    }
+}
+
+/// Parse a numeric field value of at least `min`; `None` clears the field.
+fn parse_number<T>(field: ProfileField, raw: Option<&str>, min: T) -> Result<Option<T>>
+where
+    T: FromStr + PartialOrd + std::fmt::Display,
+{
+    let Some(raw) = raw else {
+        return Ok(None);
+    };
+    match raw.trim().parse::<T>() {
+        Ok(value) if value >= min => Ok(Some(value)),
+        _ => Err(Error::InvalidArgument {
+            message: format!(
+                "{} expects a whole number of at least {min}, got '{raw}'",
+                field.name()
+            ),
+        }),
+    }
+}
+
+/// Split a comma-separated endpoint list, dropping blanks.
+pub fn split_endpoint_list(raw: &str) -> Vec<String> {
+    raw.split(',')
+        .map(str::trim)
+        .filter(|endpoint| !endpoint.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Profile fields that can be changed individually (`storify config edit`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ProfileField {
+    Bucket,
+    AccessKeyId,
+    AccessKeySecret,
+    SessionToken,
+    Endpoint,
+    FallbackEndpoints,
+    Region,
+    RootPath,
+    NameNode,
+    CredentialPath,
+    SasToken,
+    ConnectionString,
+    RoleArn,
+    AwsProfile,
+    UseInstanceMetadata,
+    Compat,
+    AccountId,
+    Manifest,
+    Retries,
+    RetryBackoffMs,
+    RateLimit,
+    TimeoutSecs,
+    ConnectTimeoutSecs,
+    PoolSize,
+    RequestPayer,
+    Headers,
+    Trash,
+    DefaultJobs,
+    HumanReadable,
+    Aliases,
+    DataKey,
+}
+
+impl ProfileField {
+    /// Field name as used in the profile store and provider field matrix.
+    pub fn name(self) -> &'static str {
+        match self {
+            ProfileField::Bucket => "bucket",
+            ProfileField::AccessKeyId => "access_key_id",
+            ProfileField::AccessKeySecret => "access_key_secret",
+            ProfileField::SessionToken => "session_token",
+            ProfileField::Endpoint => "endpoint",
+            ProfileField::FallbackEndpoints => "fallback_endpoints",
+            ProfileField::Region => "region",
+            ProfileField::RootPath => "root_path",
+            ProfileField::NameNode => "name_node",
+            ProfileField::CredentialPath => "credential_path",
+            ProfileField::SasToken => "sas_token",
+            ProfileField::ConnectionString => "connection_string",
+            ProfileField::RoleArn => "role_arn",
+            ProfileField::AwsProfile => "aws_profile",
+            ProfileField::UseInstanceMetadata => "use_instance_metadata",
+            ProfileField::Compat => "compat",
+            ProfileField::AccountId => "account_id",
+            ProfileField::Manifest => "manifest",
+            ProfileField::Retries => "retries",
+            ProfileField::RetryBackoffMs => "retry_backoff_ms",
+            ProfileField::RateLimit => "rate_limit",
+            ProfileField::TimeoutSecs => "timeout_secs",
+            ProfileField::ConnectTimeoutSecs => "connect_timeout_secs",
+            ProfileField::PoolSize => "pool_size",
+            ProfileField::RequestPayer => "request_payer",
+            ProfileField::Headers => "headers",
+            ProfileField::Trash => "trash",
+            ProfileField::DefaultJobs => "default_jobs",
+            ProfileField::HumanReadable => "human_readable",
+            ProfileField::Aliases => "aliases",
+            ProfileField::DataKey => "data_key",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::value_variants()
+            .iter()
+            .copied()
+            .find(|field| field.name() == name)
+    }
+
+    /// Whether the field is encrypted at rest.
+    pub fn is_secret(self) -> bool {
+        matches!(
+            self,
+            ProfileField::AccessKeyId
+                | ProfileField::AccessKeySecret
+                | ProfileField::SessionToken
+                | ProfileField::SasToken
+                | ProfileField::ConnectionString
+                | ProfileField::Headers
+                | ProfileField::DataKey
+        )
+    }
 }
 
 /// Profile store file structure (in-memory and persisted)
@@ -133,6 +568,15 @@ pub struct ProfileStore {
     path: PathBuf,
     file: ProfileStoreFile,
     encryption: EncryptionMetadata,
+    /// Profiles as last read or written, used to keep ciphertexts of unchanged secrets
+    sealed: BTreeMap<String, SealedProfile>,
+}
+
+/// A stored profile alongside its on-disk (encrypted) form.
+#[derive(Debug, Clone)]
+struct SealedProfile {
+    plain: StoredProfile,
+    sealed: StoredProfile,
 }
 
 impl ProfileStore {
@@ -159,20 +603,21 @@ impl ProfileStore {
             });
         }
 
-        let (file, encryption) = if path.exists() {
+        let (file, encryption, sealed) = if path.exists() {
             Self::read_file(&path, options.master_password.as_ref())?
         } else {
             let password = resolve_master_password(options.master_password.clone(), &path);
             let salt = generate_salt();
             let key = derive_master_key(&password, &salt)?;
             let encryption = EncryptionMetadata::new(key, salt.to_vec());
-            (ProfileStoreFile::default(), encryption)
+            (ProfileStoreFile::default(), encryption, BTreeMap::new())
         };
 
         Ok(Self {
             path,
             file,
             encryption,
+            sealed,
         })
     }
 
@@ -367,6 +812,41 @@ impl ProfileStore {
         self.persist()
     }
 
+    /// Apply field-level changes to an existing profile and persist it.
+    ///
+    /// `None` clears a field. The result is validated against the provider spec before
+    /// anything is written; secrets that are not part of `changes` keep their ciphertext.
+    pub fn update_profile(
+        &mut self,
+        name: &str,
+        changes: &[(ProfileField, Option<String>)],
+    ) -> Result<StoredProfile> {
+        let mut profile = self.get_profile(name)?;
+        let provider = StorageProvider::from_str(&profile.provider)?;
+        let matrix = provider_spec(provider).field_matrix();
+        for (field, value) in changes {
+            // Fallback endpoints follow the support of the primary endpoint.
+            let rule_name = match field {
+                ProfileField::FallbackEndpoints => ProfileField::Endpoint.name(),
+                _ => field.name(),
+            };
+            let unsupported = matrix.iter().any(|info| {
+                info.name == rule_name && info.rule.requirement() == Requirement::Unsupported
+            });
+            if value.is_some() && unsupported {
+                return Err(Error::UnsupportedConfigField {
+                    provider: profile.provider.clone(),
+                    field: field.name().to_string(),
+                });
+            }
+            profile.set_field(*field, value.clone())?;
+        }
+        profile.clone().into_config()?;
+        self.file.profiles.insert(name.to_string(), profile.clone());
+        self.persist()?;
+        Ok(profile)
+    }
+
     /// Delete a profile (returns error if not found)
     pub fn delete_profile(&mut self, name: &str) -> Result<()> {
         self.file
@@ -396,15 +876,65 @@ impl ProfileStore {
         self.persist()
     }
 
+    /// Re-encrypt the whole store under a new master password and a fresh salt.
+    ///
+    /// `None` switches to the auto-derived password. Both `profiles.toml` and the salt file are
+    /// backed up (`.bak`) first; if the salt cannot be written the store is rolled back so the
+    /// pair on disk stays consistent.
+    pub fn rekey(&mut self, new_password: Option<SecretString>) -> Result<()> {
+        let password = resolve_master_password(new_password, &self.path);
+        let salt = generate_salt();
+        let key = derive_master_key(&password, &salt)?;
+
+        let salt_path = Self::salt_file_path(&self.path);
+        let salt_backup = backup_path(&salt_path);
+        if salt_path.exists() {
+            fs::copy(&salt_path, &salt_backup).map_err(|source| Error::ProfileStoreIo {
+                path: salt_backup.clone(),
+                source,
+            })?;
+        }
+
+        let previous_encryption =
+            std::mem::replace(&mut self.encryption, EncryptionMetadata::new(key, salt));
+        // Drop cached ciphertexts so every secret is encrypted under the new key.
+        let previous_sealed = std::mem::take(&mut self.sealed);
+
+        if let Err(err) = self.write_store() {
+            self.encryption = previous_encryption;
+            self.sealed = previous_sealed;
+            return Err(err);
+        }
+        if let Err(err) = Self::write_salt_file(&salt_path, self.encryption.salt()) {
+            fs::copy(backup_path(&self.path), &self.path).ok();
+            fs::copy(&salt_backup, &salt_path).ok();
+            self.encryption = previous_encryption;
+            self.sealed = previous_sealed;
+            return Err(err);
+        }
+        Ok(())
+    }
+
     fn persist(&mut self) -> Result<()> {
+        self.write_store()?;
+
+        // Synchronize salt file to ensure it matches the in-memory state
+        // This is critical for operations that regenerate the encryption metadata
+        let salt_path = Self::salt_file_path(&self.path);
+        Self::write_salt_file(&salt_path, self.encryption.salt())
+    }
+
+    /// Encrypt and atomically write `profiles.toml` (the salt file is left untouched).
+    fn write_store(&mut self) -> Result<()> {
         let mut payload = self.file.clone();
         payload.normalize_default();
         payload.normalize_temp();
 
         let key = self.encryption.key();
-        let salt = self.encryption.salt();
 
-        encrypt_all_profiles(&mut payload.profiles, key)?;
+        for (name, profile) in payload.profiles.iter_mut() {
+            seal_profile_secrets(profile, self.sealed.get(name), key)?;
+        }
         if let Some(t) = payload.temp.as_mut() {
             encrypt_profile_secrets(&mut t.profile, key)?;
         }
@@ -429,11 +959,7 @@ impl ProfileStore {
 
         write_atomic(&self.path, serialized.as_bytes())?;
 
-        // Synchronize salt file to ensure it matches the in-memory state
-        // This is critical for operations that regenerate the encryption metadata
-        let salt_path = Self::salt_file_path(&self.path);
-        Self::write_salt_file(&salt_path, salt)?;
-
+        self.sealed = seal_snapshot(&self.file.profiles, payload.profiles);
         Ok(())
     }
 
@@ -441,7 +967,11 @@ impl ProfileStore {
     fn read_file(
         path: &Path,
         master_password: Option<&SecretString>,
-    ) -> Result<(ProfileStoreFile, EncryptionMetadata)> {
+    ) -> Result<(
+        ProfileStoreFile,
+        EncryptionMetadata,
+        BTreeMap<String, SealedProfile>,
+    )> {
         // Resolve password once at the beginning
         let password = resolve_master_password(master_password.cloned(), path);
 
@@ -455,7 +985,7 @@ impl ProfileStore {
             let salt = Self::load_or_create_salt(path)?;
             let key = derive_master_key(&password, &salt)?;
             let encryption = EncryptionMetadata::new(key, salt);
-            return Ok((ProfileStoreFile::default(), encryption));
+            return Ok((ProfileStoreFile::default(), encryption, BTreeMap::new()));
         }
 
         let text = String::from_utf8(raw).map_err(|source| Error::ProfileStoreUtf8 {
@@ -473,9 +1003,11 @@ impl ProfileStore {
 
         let key = derive_master_key(&password, &salt)?;
 
+        let on_disk = file.profiles.clone();
         for profile in file.profiles.values_mut() {
             decrypt_profile_secrets(profile, &key)?;
         }
+        let sealed = seal_snapshot(&file.profiles, on_disk);
         if let Some(t) = file.temp.as_mut() {
             decrypt_profile_secrets(&mut t.profile, &key)?;
         }
@@ -483,7 +1015,7 @@ impl ProfileStore {
         file.normalize_default();
         file.normalize_temp();
         let metadata = EncryptionMetadata::new(key, salt);
-        Ok((file, metadata))
+        Ok((file, metadata, sealed))
     }
 
     /// Load salt from dedicated salt file
@@ -493,35 +1025,89 @@ impl ProfileStore {
     }
 }
 
-/// Encrypt all profiles' sensitive fields
+/// Encrypt a profile's sensitive fields.
 ///
 /// All fields use unified encryption format: `ENC:<base64([nonce:12][ciphertext:var])>`
 /// Salt is stored separately in `.encryption_salt` file.
-fn encrypt_all_profiles(
-    profiles: &mut BTreeMap<String, StoredProfile>,
+fn encrypt_profile_secrets(profile: &mut StoredProfile, key: &[u8; 32]) -> Result<()> {
+    seal_profile_secrets(profile, None, key)
+}
+
+/// Encrypt a profile's sensitive fields, reusing the previous ciphertext of every secret
+/// whose plaintext is unchanged so edits do not rewrite untouched secrets.
+fn seal_profile_secrets(
+    profile: &mut StoredProfile,
+    previous: Option<&SealedProfile>,
     key: &[u8; 32],
 ) -> Result<()> {
-    for profile in profiles.values_mut() {
-        encrypt_profile_secrets(profile, key)?;
+    let reusable: [Option<(&String, &String)>; 7] = match previous {
+        Some(previous) => {
+            let plain = secret_fields(&previous.plain);
+            let sealed = secret_fields(&previous.sealed);
+            std::array::from_fn(|i| {
+                plain[i]
+                    .as_ref()
+                    .zip(sealed[i].as_ref())
+                    .filter(|(_, sealed)| sealed.starts_with(FIELD_ENCRYPTED_PREFIX))
+            })
+        }
+        None => [None; 7],
+    };
+    for (field, reusable) in secret_fields_mut(profile).into_iter().zip(reusable) {
+        let Some(value) = field.as_deref() else {
+            continue;
+        };
+        *field = Some(match reusable {
+            Some((plain, sealed)) if plain == value => sealed.clone(),
+            _ => encrypt_field(value, key)?,
+        });
     }
-
     Ok(())
 }
 
-fn encrypt_profile_secrets(profile: &mut StoredProfile, key: &[u8; 32]) -> Result<()> {
-    if let Some(value) = profile.access_key_id.as_deref() {
-        profile.access_key_id = Some(encrypt_field(value, key)?);
-    }
-    if let Some(value) = profile.access_key_secret.as_deref() {
-        profile.access_key_secret = Some(encrypt_field(value, key)?);
+fn decrypt_profile_secrets(profile: &mut StoredProfile, key: &[u8; 32]) -> Result<()> {
+    for field in secret_fields_mut(profile) {
+        decrypt_sensitive_field(field, key)?;
     }
     Ok(())
 }
 
-fn decrypt_profile_secrets(profile: &mut StoredProfile, key: &[u8; 32]) -> Result<()> {
-    decrypt_sensitive_field(&mut profile.access_key_id, key)?;
-    decrypt_sensitive_field(&mut profile.access_key_secret, key)?;
-    Ok(())
+/// Pair decrypted profiles with their on-disk form.
+fn seal_snapshot(
+    plain: &BTreeMap<String, StoredProfile>,
+    sealed: BTreeMap<String, StoredProfile>,
+) -> BTreeMap<String, SealedProfile> {
+    sealed
+        .into_iter()
+        .filter_map(|(name, sealed)| {
+            let plain = plain.get(&name)?.clone();
+            Some((name, SealedProfile { plain, sealed }))
+        })
+        .collect()
+}
+
+fn secret_fields(profile: &StoredProfile) -> [&Option<String>; 7] {
+    [
+        &profile.access_key_id,
+        &profile.access_key_secret,
+        &profile.session_token,
+        &profile.sas_token,
+        &profile.connection_string,
+        &profile.headers,
+        &profile.data_key,
+    ]
+}
+
+fn secret_fields_mut(profile: &mut StoredProfile) -> [&mut Option<String>; 7] {
+    [
+        &mut profile.access_key_id,
+        &mut profile.access_key_secret,
+        &mut profile.session_token,
+        &mut profile.sas_token,
+        &mut profile.connection_string,
+        &mut profile.headers,
+        &mut profile.data_key,
+    ]
 }
 
 fn decrypt_sensitive_field(field: &mut Option<String>, key: &[u8; 32]) -> Result<()> {
@@ -540,40 +1126,6 @@ fn now_unix() -> u64 {
         .as_secs()
 }
 
-/// RAII guard for temporary files (auto-cleanup on drop)
-struct TempFile {
-    path: PathBuf,
-    should_cleanup: bool,
-}
-
-impl TempFile {
-    fn new(path: PathBuf) -> Self {
-        Self {
-            path,
-            should_cleanup: true,
-        }
-    }
-
-    fn path(&self) -> &Path {
-        &self.path
-    }
-
-    /// Mark file as persistent (don't cleanup on drop)
-    fn keep(mut self) {
-        self.should_cleanup = false;
-        // Drop will not trigger cleanup
-    }
-}
-
-impl Drop for TempFile {
-    fn drop(&mut self) {
-        if self.should_cleanup && self.path.exists() {
-            // Best-effort cleanup (ignore errors)
-            let _ = fs::remove_file(&self.path);
-        }
-    }
-}
-
 /// Get default profile store path
 fn default_store_path() -> PathBuf {
     const ENV_VARS: &[&str] = &["STORIFY_PROFILE_PATH", "STORIFY_CONFIG"];
@@ -599,20 +1151,7 @@ fn default_store_path() -> PathBuf {
 }
 
 fn write_atomic(path: &Path, data: &[u8]) -> Result<()> {
-    let parent = path
-        .parent()
-        .map(Path::to_path_buf)
-        .unwrap_or_else(|| PathBuf::from("."));
-
-    let tmp_path = parent.join(format!(
-        ".{}.tmp-{}",
-        path.file_name()
-            .and_then(|s| s.to_str())
-            .unwrap_or("storify"),
-        Uuid::new_v4().simple()
-    ));
-
-    let temp_file = TempFile::new(tmp_path);
+    let temp_file = StagedFile::new(path);
 
     write_atomic_inner(path, temp_file.path(), data)?;
 
@@ -704,6 +1243,9 @@ mod tests {
                 StorageConfig::hdfs(Some("nn".to_string()), Some("/".to_string()))
             }
             StorageProvider::Azblob => StorageConfig::azblob(bucket.to_string()),
+            StorageProvider::Gcs => StorageConfig::gcs(bucket.to_string()),
+            StorageProvider::Http => StorageConfig::http(Some(format!("https://{bucket}"))),
+            StorageProvider::Memory => StorageConfig::memory(bucket),
         }
     }
 
@@ -750,4 +1292,153 @@ mod tests {
         assert!(store.temp_profile().is_none());
         assert!(!store.clear_temp_profile().unwrap());
     }
+
+    #[test]
+    fn update_profile_keeps_untouched_secret_ciphertext() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("profiles.toml");
+        let mut store =
+            ProfileStore::open_with_password(Some(path.clone()), Some(test_password())).unwrap();
+
+        let mut cfg = test_config(StorageProvider::S3, "b1");
+        cfg.access_key_id = Some("id".to_string());
+        cfg.access_key_secret = Some("secret".to_string());
+        store
+            .save_profile("prod".into(), StoredProfile::from_config(&cfg), false)
+            .unwrap();
+        let before = fs::read_to_string(&path).unwrap();
+
+        let mut store =
+            ProfileStore::open_with_password(Some(path.clone()), Some(test_password())).unwrap();
+        let updated = store
+            .update_profile(
+                "prod",
+                &[
+                    (ProfileField::Endpoint, Some("https://new".into())),
+                    (ProfileField::Region, Some("eu-west-1".into())),
+                ],
+            )
+            .unwrap();
+        assert_eq!(updated.endpoint.as_deref(), Some("https://new"));
+        assert_eq!(updated.access_key_secret.as_deref(), Some("secret"));
+
+        let after = fs::read_to_string(&path).unwrap();
+        let secret_line = |text: &str| {
+            text.lines()
+                .find(|line| line.starts_with("access_key_secret"))
+                .map(str::to_string)
+        };
+        assert!(secret_line(&after).unwrap().contains("ENC:"));
+        assert_eq!(secret_line(&before), secret_line(&after));
+
+        store
+            .update_profile(
+                "prod",
+                &[
+                    (ProfileField::AccessKeyId, None),
+                    (ProfileField::AccessKeySecret, None),
+                ],
+            )
+            .unwrap();
+        assert!(store.profile("prod").unwrap().access_key_secret.is_none());
+        assert!(matches!(
+            store.update_profile("prod", &[(ProfileField::SasToken, Some("sv=1".into()))]),
+            Err(Error::UnsupportedConfigField { .. })
+        ));
+    }
+
+    #[test]
+    fn numeric_fields_are_validated_and_round_trip() {
+        let mut profile = StoredProfile::from_config(&test_config(StorageProvider::S3, "b1"));
+        profile
+            .set_field(ProfileField::Retries, Some("0".into()))
+            .unwrap();
+        profile
+            .set_field(ProfileField::RateLimit, Some("50".into()))
+            .unwrap();
+        assert_eq!(profile.field(ProfileField::Retries).as_deref(), Some("0"));
+        assert!(
+            profile
+                .set_field(ProfileField::RetryBackoffMs, Some("0".into()))
+                .is_err()
+        );
+        assert!(
+            profile
+                .set_field(ProfileField::RateLimit, Some("fast".into()))
+                .is_err()
+        );
+        assert!(
+            profile
+                .set_field(ProfileField::TimeoutSecs, Some("0".into()))
+                .is_err()
+        );
+        profile
+            .set_field(ProfileField::PoolSize, Some("0".into()))
+            .unwrap();
+        assert_eq!(profile.pool_size, Some(0));
+
+        let config = profile.clone().into_config().unwrap();
+        assert_eq!(config.retries, Some(0));
+        assert_eq!(config.rate_limit, Some(50));
+        let stored = StoredProfile::from_config(&config);
+        assert_eq!((stored.retries, stored.rate_limit), (Some(0), Some(50)));
+
+        profile.set_field(ProfileField::RateLimit, None).unwrap();
+        assert!(profile.rate_limit.is_none());
+    }
+
+    #[test]
+    fn aliases_merge_and_persist() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("profiles.toml");
+        let mut store =
+            ProfileStore::open_with_password(Some(path.clone()), Some(test_password())).unwrap();
+        let cfg = test_config(StorageProvider::S3, "b1");
+        store
+            .save_profile("prod".into(), StoredProfile::from_config(&cfg), false)
+            .unwrap();
+
+        let alias = |lines: &str| (ProfileField::Aliases, Some(lines.to_string()));
+        store
+            .update_profile("prod", &[alias("ll=ls -L\nlt=ls -L --sort time")])
+            .unwrap();
+        store
+            .update_profile("prod", &[alias("lt=\nla=ls -L -R")])
+            .unwrap();
+
+        let store = ProfileStore::open_with_password(Some(path), Some(test_password())).unwrap();
+        let profile = store.get_profile("prod").unwrap();
+        assert_eq!(
+            profile.field(ProfileField::Aliases).as_deref(),
+            Some("la=ls -L -R\nll=ls -L")
+        );
+        assert_eq!(profile.into_config().unwrap().aliases.len(), 2);
+    }
+
+    #[test]
+    fn rekey_reencrypts_under_new_password() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("profiles.toml");
+        let mut store =
+            ProfileStore::open_with_password(Some(path.clone()), Some(test_password())).unwrap();
+        let mut cfg = test_config(StorageProvider::Oss, "b1");
+        cfg.access_key_id = Some("id".to_string());
+        cfg.access_key_secret = Some("secret".to_string());
+        store
+            .save_profile("prod".into(), StoredProfile::from_config(&cfg), true)
+            .unwrap();
+
+        let new_password = SecretString::new("rotated".into());
+        store.rekey(Some(new_password.clone())).unwrap();
+        assert!(backup_path(&path).exists());
+        assert!(backup_path(&ProfileStore::salt_file_path(&path)).exists());
+
+        assert!(
+            ProfileStore::open_with_password(Some(path.clone()), Some(test_password())).is_err()
+        );
+        let reopened = ProfileStore::open_with_password(Some(path), Some(new_password)).unwrap();
+        let profile = reopened.get_profile("prod").unwrap();
+        assert_eq!(profile.access_key_secret.as_deref(), Some("secret"));
+        assert_eq!(reopened.default_profile(), Some("prod"));
+    }
 }
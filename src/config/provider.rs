@@ -10,6 +10,9 @@ pub enum StorageProvider {
     Fs,
     Hdfs,
     Azblob,
+    Gcs,
+    Http,
+    Memory,
 }
 
 impl FromStr for StorageProvider {
@@ -18,11 +21,14 @@ impl FromStr for StorageProvider {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s.to_lowercase().as_str() {
             "oss" => Ok(Self::Oss),
-            "s3" | "minio" => Ok(Self::S3),
+            "s3" | "minio" | "r2" | "b2" => Ok(Self::S3),
             "cos" => Ok(Self::Cos),
             "fs" => Ok(Self::Fs),
             "hdfs" => Ok(Self::Hdfs),
             "azblob" => Ok(Self::Azblob),
+            "gcs" => Ok(Self::Gcs),
+            "http" | "https" | "ipfs" => Ok(Self::Http),
+            "mem" | "memory" => Ok(Self::Memory),
             _ => Err(Error::UnsupportedProvider {
                 provider: s.to_string(),
             }),
@@ -39,6 +45,9 @@ impl StorageProvider {
             StorageProvider::Fs => "fs",
             StorageProvider::Hdfs => "hdfs",
             StorageProvider::Azblob => "azblob",
+            StorageProvider::Gcs => "gcs",
+            StorageProvider::Http => "http",
+            StorageProvider::Memory => "mem",
         }
     }
 }
@@ -1,6 +1,11 @@
-use crate::config::{StorageProvider, storage_config::StorageConfig};
+use crate::config::crypto::DataKey;
+use crate::config::{
+    CompatPreset, ServerSideEncryption, SseAlgorithm, StorageProvider,
+    storage_config::StorageConfig,
+};
 use crate::error::{Error, Result};
 use crate::storage::constants::{DEFAULT_COS_ENDPOINT, DEFAULT_FS_ROOT, DEFAULT_HDFS_ROOT};
+use std::str::FromStr;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Requirement {
@@ -89,12 +94,22 @@ impl FieldRule {
 #[derive(Clone, Copy, Debug)]
 pub struct ProviderSpec {
     allow_anonymous: bool,
+    allow_instance_metadata: bool,
     access_key: FieldRule,
     secret_key: FieldRule,
+    session_token: FieldRule,
     region: FieldRule,
     endpoint: FieldRule,
     root_path: FieldRule,
     name_node: FieldRule,
+    credential_path: FieldRule,
+    sas_token: FieldRule,
+    connection_string: FieldRule,
+    role_arn: FieldRule,
+    aws_profile: FieldRule,
+    compat: FieldRule,
+    account_id: FieldRule,
+    manifest: FieldRule,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -115,16 +130,32 @@ pub enum ProviderBackend {
         bucket: String,
         access_key: Option<String>,
         secret_key: Option<String>,
+        security_token: Option<String>,
         endpoint: Option<String>,
         anonymous: bool,
+        /// Server-side encryption for new objects; the bucket default when unset
+        encryption: Option<ServerSideEncryption>,
     },
     S3 {
         bucket: String,
         access_key: Option<String>,
         secret_key: Option<String>,
+        session_token: Option<String>,
         region: Option<String>,
         endpoint: Option<String>,
         anonymous: bool,
+        /// Skip credential discovery (environment, AWS profiles, instance metadata)
+        unsigned: bool,
+        role_arn: Option<String>,
+        aws_profile: Option<String>,
+        use_instance_metadata: bool,
+        compat: Option<CompatPreset>,
+        /// Storage class for new objects; the bucket default when unset
+        storage_class: Option<String>,
+        /// Server-side encryption for new objects; the bucket default when unset
+        encryption: Option<ServerSideEncryption>,
+        /// Send `x-amz-request-payer: requester` with every request
+        request_payer: bool,
     },
     Cos {
         bucket: String,
@@ -143,8 +174,27 @@ pub enum ProviderBackend {
         container: String,
         account_name: Option<String>,
         account_key: Option<String>,
+        sas_token: Option<String>,
+        connection_string: Option<String>,
         endpoint: Option<String>,
     },
+    Gcs {
+        bucket: String,
+        credential_path: Option<String>,
+        endpoint: Option<String>,
+        /// Send requests without an OAuth token
+        anonymous: bool,
+    },
+    Memory {
+        name: String,
+    },
+    Http {
+        endpoint: String,
+        root: Option<String>,
+        username: Option<String>,
+        password: Option<String>,
+        manifest: Option<String>,
+    },
 }
 
 impl ProviderSpec {
@@ -156,36 +206,158 @@ impl ProviderSpec {
         };
         Self {
             allow_anonymous,
+            allow_instance_metadata: false,
             access_key: cred_rule,
             secret_key: cred_rule,
+            session_token: FieldRule::unsupported(),
             region,
             endpoint,
             root_path: FieldRule::unsupported(),
             name_node: FieldRule::unsupported(),
+            credential_path: FieldRule::unsupported(),
+            sas_token: FieldRule::unsupported(),
+            connection_string: FieldRule::unsupported(),
+            role_arn: FieldRule::unsupported(),
+            aws_profile: FieldRule::unsupported(),
+            compat: FieldRule::unsupported(),
+            account_id: FieldRule::unsupported(),
+            manifest: FieldRule::unsupported(),
+        }
+    }
+
+    /// S3 additionally resolves credentials from a named AWS profile or the instance
+    /// metadata service, optionally assuming `role_arn` on top of them.
+    const fn s3(region: FieldRule, endpoint: FieldRule) -> Self {
+        Self {
+            allow_instance_metadata: true,
+            session_token: FieldRule::optional(),
+            role_arn: FieldRule::optional(),
+            aws_profile: FieldRule::optional(),
+            compat: FieldRule::optional(),
+            account_id: FieldRule::optional(),
+            ..Self::cloud(region, endpoint, true)
+        }
+    }
+
+    /// OSS accepts an STS security token alongside the access keys.
+    const fn oss(endpoint: FieldRule) -> Self {
+        Self {
+            session_token: FieldRule::optional(),
+            ..Self::cloud(FieldRule::unsupported(), endpoint, true)
+        }
+    }
+
+    /// Azure accepts an account key, a SAS token or a connection string; `prepare` checks
+    /// that exactly one of them is present.
+    const fn azblob(region: FieldRule, endpoint: FieldRule) -> Self {
+        Self {
+            allow_anonymous: false,
+            allow_instance_metadata: false,
+            access_key: FieldRule::optional(),
+            secret_key: FieldRule::optional(),
+            session_token: FieldRule::unsupported(),
+            region,
+            endpoint,
+            root_path: FieldRule::unsupported(),
+            name_node: FieldRule::unsupported(),
+            credential_path: FieldRule::unsupported(),
+            sas_token: FieldRule::optional(),
+            connection_string: FieldRule::optional(),
+            role_arn: FieldRule::unsupported(),
+            aws_profile: FieldRule::unsupported(),
+            compat: FieldRule::unsupported(),
+            account_id: FieldRule::unsupported(),
+            manifest: FieldRule::unsupported(),
+        }
+    }
+
+    const fn gcs(credential_rule: FieldRule, endpoint: FieldRule) -> Self {
+        Self {
+            allow_anonymous: false,
+            allow_instance_metadata: false,
+            access_key: FieldRule::unsupported(),
+            secret_key: FieldRule::unsupported(),
+            session_token: FieldRule::unsupported(),
+            region: FieldRule::unsupported(),
+            endpoint,
+            root_path: FieldRule::unsupported(),
+            name_node: FieldRule::unsupported(),
+            credential_path: credential_rule,
+            sas_token: FieldRule::unsupported(),
+            connection_string: FieldRule::unsupported(),
+            role_arn: FieldRule::unsupported(),
+            aws_profile: FieldRule::unsupported(),
+            compat: FieldRule::unsupported(),
+            account_id: FieldRule::unsupported(),
+            manifest: FieldRule::unsupported(),
         }
     }
 
     const fn filesystem(root_rule: FieldRule) -> Self {
         Self {
             allow_anonymous: false,
+            allow_instance_metadata: false,
             access_key: FieldRule::unsupported(),
             secret_key: FieldRule::unsupported(),
+            session_token: FieldRule::unsupported(),
             region: FieldRule::unsupported(),
             endpoint: FieldRule::unsupported(),
             root_path: root_rule,
             name_node: FieldRule::unsupported(),
+            credential_path: FieldRule::unsupported(),
+            sas_token: FieldRule::unsupported(),
+            connection_string: FieldRule::unsupported(),
+            role_arn: FieldRule::unsupported(),
+            aws_profile: FieldRule::unsupported(),
+            compat: FieldRule::unsupported(),
+            account_id: FieldRule::unsupported(),
+            manifest: FieldRule::unsupported(),
         }
     }
 
     const fn hdfs(root_rule: FieldRule, name_node_rule: FieldRule) -> Self {
         Self {
             allow_anonymous: false,
+            allow_instance_metadata: false,
             access_key: FieldRule::unsupported(),
             secret_key: FieldRule::unsupported(),
+            session_token: FieldRule::unsupported(),
             region: FieldRule::unsupported(),
             endpoint: FieldRule::unsupported(),
             root_path: root_rule,
             name_node: name_node_rule,
+            credential_path: FieldRule::unsupported(),
+            sas_token: FieldRule::unsupported(),
+            connection_string: FieldRule::unsupported(),
+            role_arn: FieldRule::unsupported(),
+            aws_profile: FieldRule::unsupported(),
+            compat: FieldRule::unsupported(),
+            account_id: FieldRule::unsupported(),
+            manifest: FieldRule::unsupported(),
+        }
+    }
+
+    /// Read-only HTTP: the access key pair doubles as optional basic-auth credentials and
+    /// `manifest` names a key list used instead of HTML index pages for listing.
+    const fn http(endpoint: FieldRule, root_rule: FieldRule) -> Self {
+        Self {
+            allow_anonymous: true,
+            allow_instance_metadata: false,
+            access_key: FieldRule::optional(),
+            secret_key: FieldRule::optional(),
+            session_token: FieldRule::unsupported(),
+            region: FieldRule::unsupported(),
+            endpoint,
+            root_path: root_rule,
+            name_node: FieldRule::unsupported(),
+            credential_path: FieldRule::unsupported(),
+            sas_token: FieldRule::unsupported(),
+            connection_string: FieldRule::unsupported(),
+            role_arn: FieldRule::unsupported(),
+            aws_profile: FieldRule::unsupported(),
+            compat: FieldRule::unsupported(),
+            account_id: FieldRule::unsupported(),
+            manifest: FieldRule::optional(),
         }
     }
 
@@ -193,14 +365,27 @@ impl ProviderSpec {
         self.allow_anonymous
     }
 
-    pub const fn field_matrix(&self) -> [FieldInfo; 6] {
+    pub const fn allows_instance_metadata(&self) -> bool {
+        self.allow_instance_metadata
+    }
+
+    pub const fn field_matrix(&self) -> [FieldInfo; 15] {
         [
             FieldInfo::new("access_key_id", self.access_key),
             FieldInfo::new("access_key_secret", self.secret_key),
+            FieldInfo::new("session_token", self.session_token),
             FieldInfo::new("region", self.region),
             FieldInfo::new("endpoint", self.endpoint),
             FieldInfo::new("root_path", self.root_path),
             FieldInfo::new("name_node", self.name_node),
+            FieldInfo::new("credential_path", self.credential_path),
+            FieldInfo::new("sas_token", self.sas_token),
+            FieldInfo::new("connection_string", self.connection_string),
+            FieldInfo::new("role_arn", self.role_arn),
+            FieldInfo::new("aws_profile", self.aws_profile),
+            FieldInfo::new("compat", self.compat),
+            FieldInfo::new("account_id", self.account_id),
+            FieldInfo::new("manifest", self.manifest),
         ]
     }
 
@@ -213,37 +398,120 @@ impl ProviderSpec {
             .apply(provider, "access_key_id", &mut config.access_key_id)?;
         self.secret_key
             .apply(provider, "access_key_secret", &mut config.access_key_secret)?;
+        self.session_token
+            .apply(provider, "session_token", &mut config.session_token)?;
         self.region.apply(provider, "region", &mut config.region)?;
         self.endpoint
             .apply(provider, "endpoint", &mut config.endpoint)?;
+        if self.endpoint.requirement == Requirement::Unsupported {
+            config.fallback_endpoints.clear();
+        }
         self.root_path
             .apply(provider, "root_path", &mut config.root_path)?;
         self.name_node
             .apply(provider, "name_node", &mut config.name_node)?;
+        self.credential_path
+            .apply(provider, "credential_path", &mut config.credential_path)?;
+        self.sas_token
+            .apply(provider, "sas_token", &mut config.sas_token)?;
+        self.connection_string.apply(
+            provider,
+            "connection_string",
+            &mut config.connection_string,
+        )?;
+        self.role_arn
+            .apply(provider, "role_arn", &mut config.role_arn)?;
+        self.aws_profile
+            .apply(provider, "aws_profile", &mut config.aws_profile)?;
+        self.compat.apply(provider, "compat", &mut config.compat)?;
+        self.account_id
+            .apply(provider, "account_id", &mut config.account_id)?;
+        self.manifest
+            .apply(provider, "manifest", &mut config.manifest)?;
+        let compat = config
+            .compat
+            .as_deref()
+            .map(CompatPreset::from_str)
+            .transpose()?;
+        if !self.allow_instance_metadata {
+            config.use_instance_metadata = false;
+        }
+        if config.storage_class.is_some() && provider != StorageProvider::S3 {
+            return Err(Error::InvalidArgument {
+                message: format!(
+                    "uploading with a storage class needs the s3 provider (got '{}'); use `storify chclass` on oss and cos",
+                    provider.as_str()
+                ),
+            });
+        }
+        if config.request_payer && provider != StorageProvider::S3 {
+            return Err(Error::InvalidArgument {
+                message: format!(
+                    "requester pays needs the s3 provider (got '{}')",
+                    provider.as_str()
+                ),
+            });
+        }
+        if let Some(encryption) = &config.encryption {
+            if !matches!(provider, StorageProvider::S3 | StorageProvider::Oss) {
+                return Err(Error::InvalidArgument {
+                    message: format!(
+                        "server-side encryption needs the s3 or oss provider (got '{}')",
+                        provider.as_str()
+                    ),
+                });
+            }
+            if encryption.kms_key_id.is_some() && encryption.algorithm != SseAlgorithm::Kms {
+                return Err(Error::InvalidArgument {
+                    message: "--kms-key-id needs --sse kms".to_string(),
+                });
+            }
+        }
 
-        if self.access_key.requirement != Requirement::Unsupported
+        if provider == StorageProvider::Azblob {
+            enforce_azblob_credentials(provider, config)?;
+        } else if provider == StorageProvider::S3 {
+            enforce_s3_credentials(provider, config)?;
+        } else if self.access_key.requirement != Requirement::Unsupported
             || self.secret_key.requirement != Requirement::Unsupported
         {
             enforce_credentials(self.allow_anonymous, provider, config)?;
         } else {
             config.anonymous = false;
         }
+        enforce_session_token(provider, config)?;
+
+        let (endpoint, region) = match compat {
+            Some(preset) => preset.resolve_endpoint(config)?,
+            None => (config.endpoint.clone(), config.region.clone()),
+        };
 
         let backend = match provider {
             StorageProvider::Oss => ProviderBackend::Oss {
                 bucket: config.bucket.clone(),
                 access_key: config.access_key_id.clone(),
                 secret_key: config.access_key_secret.clone(),
+                security_token: config.session_token.clone(),
                 endpoint: config.endpoint.clone(),
                 anonymous: config.anonymous,
+                encryption: config.encryption.clone(),
             },
             StorageProvider::S3 => ProviderBackend::S3 {
                 bucket: config.bucket.clone(),
                 access_key: config.access_key_id.clone(),
                 secret_key: config.access_key_secret.clone(),
-                region: config.region.clone(),
-                endpoint: config.endpoint.clone(),
+                session_token: config.session_token.clone(),
+                region,
+                endpoint,
                 anonymous: config.anonymous,
+                unsigned: config.unsigned,
+                role_arn: config.role_arn.clone(),
+                aws_profile: config.aws_profile.clone(),
+                use_instance_metadata: config.use_instance_metadata,
+                compat,
+                storage_class: config.storage_class.clone(),
+                encryption: config.encryption.clone(),
+                request_payer: config.request_payer,
             },
             StorageProvider::Cos => ProviderBackend::Cos {
                 bucket: config.bucket.clone(),
@@ -290,8 +558,32 @@ impl ProviderSpec {
                 container: config.bucket.clone(),
                 account_name: config.access_key_id.clone(),
                 account_key: config.access_key_secret.clone(),
+                sas_token: config.sas_token.clone(),
+                connection_string: config.connection_string.clone(),
                 endpoint: config.endpoint.clone(),
             },
+            StorageProvider::Gcs => ProviderBackend::Gcs {
+                bucket: config.bucket.clone(),
+                credential_path: config.credential_path.clone(),
+                endpoint: config.endpoint.clone(),
+                anonymous: config.unsigned,
+            },
+            StorageProvider::Memory => ProviderBackend::Memory {
+                name: config.bucket.clone(),
+            },
+            StorageProvider::Http => ProviderBackend::Http {
+                endpoint: config
+                    .endpoint
+                    .clone()
+                    .ok_or_else(|| Error::MissingConfigField {
+                        provider: provider.as_str().to_string(),
+                        field: "endpoint".to_string(),
+                    })?,
+                root: config.root_path.clone(),
+                username: config.access_key_id.clone(),
+                password: config.access_key_secret.clone(),
+                manifest: config.manifest.clone(),
+            },
         };
 
         Ok(backend)
@@ -300,12 +592,8 @@ impl ProviderSpec {
 
 pub fn provider_spec(provider: StorageProvider) -> ProviderSpec {
     match provider {
-        StorageProvider::Oss => {
-            ProviderSpec::cloud(FieldRule::unsupported(), FieldRule::optional(), true)
-        }
-        StorageProvider::S3 => {
-            ProviderSpec::cloud(FieldRule::optional(), FieldRule::optional(), true)
-        }
+        StorageProvider::Oss => ProviderSpec::oss(FieldRule::optional()),
+        StorageProvider::S3 => ProviderSpec::s3(FieldRule::optional(), FieldRule::optional()),
         StorageProvider::Cos => ProviderSpec::cloud(
             FieldRule::optional(),
             FieldRule::optional_with_default(DEFAULT_COS_ENDPOINT),
@@ -319,8 +607,11 @@ pub fn provider_spec(provider: StorageProvider) -> ProviderSpec {
             FieldRule::required(),
         ),
         StorageProvider::Azblob => {
-            ProviderSpec::cloud(FieldRule::optional(), FieldRule::optional(), false)
+            ProviderSpec::azblob(FieldRule::optional(), FieldRule::optional())
         }
+        StorageProvider::Gcs => ProviderSpec::gcs(FieldRule::optional(), FieldRule::optional()),
+        StorageProvider::Http => ProviderSpec::http(FieldRule::required(), FieldRule::optional()),
+        StorageProvider::Memory => ProviderSpec::filesystem(FieldRule::unsupported()),
     }
 }
 
@@ -329,6 +620,9 @@ pub fn prepare_storage_backend(config: &mut StorageConfig) -> Result<ProviderBac
 }
 
 pub fn prepare_storage_config(config: &mut StorageConfig) -> Result<()> {
+    if let Some(data_key) = config.data_key.as_deref() {
+        DataKey::decode(data_key)?;
+    }
     prepare_storage_backend(config).map(|_| ())
 }
 
@@ -366,3 +660,79 @@ fn enforce_credentials(
         }),
     }
 }
+
+/// Azure credential modes are mutually exclusive: account name + key, SAS token,
+/// or a connection string (which carries the account name itself).
+fn enforce_azblob_credentials(provider: StorageProvider, config: &mut StorageConfig) -> Result<()> {
+    config.anonymous = false;
+
+    let supplied: Vec<&str> = [
+        ("access_key_secret", config.access_key_secret.is_some()),
+        ("sas_token", config.sas_token.is_some()),
+        ("connection_string", config.connection_string.is_some()),
+    ]
+    .into_iter()
+    .filter_map(|(name, present)| present.then_some(name))
+    .collect();
+
+    let conflict = || Error::ConflictingConfigFields {
+        provider: provider.as_str().to_string(),
+        fields: "access_key_secret, sas_token, connection_string".to_string(),
+    };
+
+    match supplied.as_slice() {
+        [] => Err(Error::MissingConfigField {
+            provider: provider.as_str().to_string(),
+            field: "access_key_secret (or sas_token / connection_string)".to_string(),
+        }),
+        ["access_key_secret"] if config.access_key_id.is_none() => Err(Error::MissingConfigField {
+            provider: provider.as_str().to_string(),
+            field: "access_key_id".to_string(),
+        }),
+        ["connection_string"] if config.access_key_id.is_some() => Err(conflict()),
+        [_] => Ok(()),
+        _ => Err(conflict()),
+    }
+}
+
+/// S3 credential sources are mutually exclusive: static keys, a named AWS profile or the
+/// instance metadata service. `role_arn` is assumed using whichever source applies, falling
+/// back to the default AWS chain (environment, web identity, IMDS) when none is configured.
+fn enforce_s3_credentials(provider: StorageProvider, config: &mut StorageConfig) -> Result<()> {
+    let static_keys = config.access_key_id.is_some() || config.access_key_secret.is_some();
+    let profile = config.aws_profile.is_some();
+    let instance_metadata = config.use_instance_metadata;
+    let sources = [static_keys, profile, instance_metadata];
+    if sources.into_iter().filter(|present| *present).count() > 1 {
+        return Err(Error::ConflictingConfigFields {
+            provider: provider.as_str().to_string(),
+            fields: "access_key_id/access_key_secret, aws_profile, use_instance_metadata"
+                .to_string(),
+        });
+    }
+
+    if !static_keys && (profile || instance_metadata || config.role_arn.is_some()) {
+        config.anonymous = false;
+        return Ok(());
+    }
+    enforce_credentials(true, provider, config)
+}
+
+/// Temporary STS credentials are only valid together with the access key pair they were
+/// issued with.
+fn enforce_session_token(provider: StorageProvider, config: &StorageConfig) -> Result<()> {
+    if config.session_token.is_none() {
+        return Ok(());
+    }
+    let missing = if config.access_key_id.is_none() {
+        "access_key_id"
+    } else if config.access_key_secret.is_none() {
+        "access_key_secret"
+    } else {
+        return Ok(());
+    };
+    Err(Error::MissingConfigField {
+        provider: provider.as_str().to_string(),
+        field: format!("{missing} (required with session_token)"),
+    })
+}
@@ -1,4 +1,6 @@
-use crate::config::StorageProvider;
+use crate::config::{ServerSideEncryption, StorageProvider};
+use crate::error::{Error, Result};
+use std::collections::BTreeMap;
 
 /// Unified storage configuration for different providers
 #[derive(Debug, Clone)]
@@ -7,11 +9,59 @@ pub struct StorageConfig {
     pub bucket: String,
     pub access_key_id: Option<String>,
     pub access_key_secret: Option<String>,
+    /// Temporary STS credential paired with the access keys (S3 session / OSS security token)
+    pub session_token: Option<String>,
     pub endpoint: Option<String>,
+    /// Endpoints tried in order when `endpoint` is unreachable (e.g. VPC endpoint first, then public)
+    pub fallback_endpoints: Vec<String>,
     pub region: Option<String>,
     pub root_path: Option<String>,
     pub name_node: Option<String>,
+    pub credential_path: Option<String>,
+    pub sas_token: Option<String>,
+    pub connection_string: Option<String>,
+    pub role_arn: Option<String>,
+    pub aws_profile: Option<String>,
+    pub use_instance_metadata: bool,
+    /// S3-compatible system preset (`ceph`, `seaweedfs`, `r2`, `b2`)
+    pub compat: Option<String>,
+    /// Cloudflare account ID the R2 endpoint is derived from
+    pub account_id: Option<String>,
+    /// Key list (one path per line) the read-only `http` provider lists from
+    pub manifest: Option<String>,
+    /// Storage class new S3 objects are written with (set by `put --storage-class`)
+    pub storage_class: Option<String>,
+    /// Server-side encryption new S3/OSS objects are written with (set by `put --sse`)
+    pub encryption: Option<ServerSideEncryption>,
+    /// Retries of transiently failed requests (default 3, 0 disables them)
+    pub retries: Option<usize>,
+    /// Delay before the first retry in milliseconds, doubled for each further one
+    pub retry_backoff_ms: Option<u64>,
+    /// Storage requests sent per second at most
+    pub rate_limit: Option<u32>,
+    /// Longest a single request (or read/write of a transfer) may take, in seconds
+    pub timeout_secs: Option<u64>,
+    /// Longest establishing a connection may take, in seconds
+    pub connect_timeout_secs: Option<u64>,
+    /// Idle HTTP connections kept open per host
+    pub pool_size: Option<usize>,
+    /// Bill requests to the requester instead of the bucket owner (S3 requester-pays buckets)
+    pub request_payer: bool,
+    /// Extra headers sent with every request, e.g. for gateways with their own auth
+    pub headers: Vec<(String, String)>,
+    /// `rm` moves objects into the trash instead of deleting them
+    pub trash: bool,
+    /// `--concurrency` of get, put and grep when the command line does not set it
+    pub default_jobs: Option<usize>,
+    /// `du` prints human-readable sizes without `-h`
+    pub human_readable: bool,
+    /// Command aliases by name, e.g. `ll` for `ls -L`
+    pub aliases: BTreeMap<String, String>,
+    /// Base64 key for client-side encryption (`put --encrypt`, `get --decrypt`)
+    pub data_key: Option<String>,
     pub anonymous: bool,
+    /// Never sign requests, even with credentials in the environment (`--anonymous`)
+    pub unsigned: bool,
 }
 
 impl Default for StorageConfig {
@@ -21,11 +71,38 @@ impl Default for StorageConfig {
             bucket: String::new(),
             access_key_id: None,
             access_key_secret: None,
+            session_token: None,
             endpoint: None,
+            fallback_endpoints: Vec::new(),
             region: None,
             root_path: None,
             name_node: None,
+            credential_path: None,
+            sas_token: None,
+            connection_string: None,
+            role_arn: None,
+            aws_profile: None,
+            use_instance_metadata: false,
+            compat: None,
+            account_id: None,
+            manifest: None,
+            storage_class: None,
+            encryption: None,
+            retries: None,
+            retry_backoff_ms: None,
+            rate_limit: None,
+            timeout_secs: None,
+            connect_timeout_secs: None,
+            pool_size: None,
+            request_payer: false,
+            headers: Vec::new(),
+            trash: false,
+            default_jobs: None,
+            human_readable: false,
+            aliases: BTreeMap::new(),
+            data_key: None,
             anonymous: false,
+            unsigned: false,
         }
     }
 }
@@ -78,4 +155,99 @@ impl StorageConfig {
             ..Default::default()
         }
     }
+
+    pub fn gcs(bucket: impl Into<String>) -> Self {
+        Self::new(StorageProvider::Gcs, bucket)
+    }
+
+    /// In-process storage; operators with the same `name` share their contents.
+    pub fn memory(name: impl Into<String>) -> Self {
+        Self::new(StorageProvider::Memory, name)
+    }
+
+    /// The endpoint host stands in for the bucket, so `https://host/...` paths address it.
+    pub fn http(endpoint: Option<String>) -> Self {
+        let label = endpoint
+            .as_deref()
+            .and_then(|endpoint| endpoint.parse::<http::Uri>().ok())
+            .and_then(|uri| uri.host().map(str::to_string))
+            .unwrap_or_else(|| "http".to_string());
+        Self {
+            provider: StorageProvider::Http,
+            bucket: label,
+            endpoint,
+            ..Default::default()
+        }
+    }
+}
+
+/// Parse a `Name: value` request header, checking that both parts are valid in HTTP.
+pub fn parse_header(raw: &str) -> Result<(String, String)> {
+    let invalid = |reason: &str| Error::InvalidArgument {
+        message: format!("invalid header '{raw}': {reason}"),
+    };
+    let (name, value) = raw
+        .split_once(':')
+        .ok_or_else(|| invalid("expected 'Name: value'"))?;
+    let (name, value) = (name.trim(), value.trim());
+    http::HeaderName::from_bytes(name.as_bytes()).map_err(|_| invalid("bad header name"))?;
+    http::HeaderValue::from_str(value).map_err(|_| invalid("bad header value"))?;
+    Ok((name.to_string(), value.to_string()))
+}
+
+/// Headers from `Name: value` lines, skipping blank ones.
+pub fn parse_header_lines(text: &str) -> Result<Vec<(String, String)>> {
+    text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(parse_header)
+        .collect()
+}
+
+/// `Name: value` lines of `headers`, the form profiles store them in.
+pub fn header_lines(headers: &[(String, String)]) -> Option<String> {
+    (!headers.is_empty()).then(|| {
+        headers
+            .iter()
+            .map(|(name, value)| format!("{name}: {value}"))
+            .collect::<Vec<_>>()
+            .join("\n")
+    })
+}
+
+/// Alias from `NAME=COMMAND`; an empty COMMAND asks `config edit` to remove the alias.
+pub fn parse_alias(raw: &str) -> Result<(String, String)> {
+    let (name, command) = raw.split_once('=').ok_or_else(|| Error::InvalidArgument {
+        message: format!("invalid alias '{raw}': expected NAME=COMMAND"),
+    })?;
+    let name = name.trim();
+    if name.is_empty() || name.starts_with('-') || name.contains(char::is_whitespace) {
+        return Err(Error::InvalidArgument {
+            message: format!("invalid alias '{raw}': the name must be a single word"),
+        });
+    }
+    let command = command.split_whitespace().collect::<Vec<_>>().join(" ");
+    Ok((name.to_string(), command))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headers_roundtrip_through_lines() {
+        let headers = parse_header_lines("X-Api-Key: abc:def\n\nx-team:  data ").unwrap();
+        assert_eq!(
+            headers,
+            vec![
+                ("X-Api-Key".to_string(), "abc:def".to_string()),
+                ("x-team".to_string(), "data".to_string()),
+            ]
+        );
+        assert_eq!(
+            parse_header_lines(&header_lines(&headers).unwrap()).unwrap(),
+            headers
+        );
+        assert!(parse_header("no separator").is_err());
+        assert!(parse_header("bad name: x").is_err());
+    }
 }
@@ -1,3 +1,4 @@
+use serde::{Serialize, Serializer};
 use snafu::Snafu;
 use std::path::PathBuf;
 use std::string::FromUtf8Error;
@@ -5,8 +6,11 @@ use toml::{de::Error as TomlDeError, ser::Error as TomlSerError};
 
 pub type Result<T> = std::result::Result<T, Error>;
 
-#[derive(Debug, Snafu)]
+/// Errors serialize (for `--error-format json`) as an object tagged with the variant name
+/// under `type` and holding its fields; sources from other crates become their messages.
+#[derive(Debug, Snafu, Serialize)]
 #[snafu(visibility(pub))]
+#[serde(tag = "type")]
 pub enum Error {
     #[snafu(display("Environment variable '{key}' is required but not found"))]
     MissingEnvVar { key: String },
@@ -14,8 +18,22 @@ pub enum Error {
     #[snafu(display("Missing required configuration field '{field}' for provider '{provider}'"))]
     MissingConfigField { provider: String, field: String },
 
+    #[snafu(display("Configuration field '{field}' is not supported by provider '{provider}'"))]
+    UnsupportedConfigField { provider: String, field: String },
+
+    #[snafu(display(
+        "Conflicting credentials for provider '{provider}': supply exactly one of {fields}"
+    ))]
+    ConflictingConfigFields { provider: String, fields: String },
+
+    #[snafu(display(
+        "AWS profile '{profile}' with static credentials not found (looked in {})",
+        path.display()
+    ))]
+    AwsProfileNotFound { profile: String, path: PathBuf },
+
     #[snafu(display(
-        "Unsupported storage provider: {provider}. Allowed: 'oss' | 's3' | 'minio' | 'cos' | 'fs' | 'hdfs'"
+        "Unsupported storage provider: {provider}. Allowed: 'oss' | 's3' | 'minio' | 'r2' | 'b2' | 'cos' | 'fs' | 'hdfs' | 'azblob' | 'gcs' | 'http' | 'https' | 'ipfs' | 'mem'"
     ))]
     UnsupportedProvider { provider: String },
 
@@ -25,15 +43,67 @@ pub enum Error {
     #[snafu(display("Invalid path: {path}"))]
     InvalidPath { path: String },
 
+    #[snafu(display(
+        "Not enough free space in '{}': need {}, available {} (use --no-space-check to skip)",
+        path.display(),
+        crate::storage::format_size(*required),
+        crate::storage::format_size(*available)
+    ))]
+    InsufficientSpace {
+        path: PathBuf,
+        required: u64,
+        available: u64,
+    },
+
+    #[snafu(display("None of the configured endpoints is reachable: {endpoints}"))]
+    EndpointsUnreachable { endpoints: String },
+
+    #[snafu(display("Connectivity check failed for {target}: {diagnosis}"))]
+    HealthCheckFailed { target: String, diagnosis: String },
+
+    #[snafu(display("No objects found under '{prefix}'"))]
+    NoObjectsUnderPrefix { prefix: String },
+
     #[snafu(display("Cannot delete directory without -R flag: {path}"))]
     DirectoryDeletionNotRecursive { path: String },
 
+    #[snafu(display(
+        "Refusing to delete '{path}': it is the root of the fs storage (use --allow-root to override)"
+    ))]
+    RootDeletionRefused { path: String },
+
+    #[snafu(display("Path escapes the storage root: {path}"))]
+    PathEscapesRoot { path: String },
+
+    #[snafu(display(
+        "Destination '{path}' is a directory; add a trailing '/' to place the source inside it"
+    ))]
+    DestinationIsDirectory { path: String },
+
+    #[snafu(display("Destination '{path}' is a file, but the source is a directory"))]
+    DestinationIsFile { path: String },
+
+    #[snafu(display("Destination '{dest}' is inside the source directory '{src}'"))]
+    DestinationInsideSource { src: String, dest: String },
+
     #[snafu(display("Use -R to upload directories"))]
     DirectoryUploadNotRecursive,
 
     #[snafu(display("Partial deletion failure: {} path(s) failed to delete", failed_paths.len()))]
     PartialDeletion { failed_paths: Vec<String> },
 
+    #[snafu(display("{} of {total} path(s) failed", failed_paths.len()))]
+    PartialFailure {
+        failed_paths: Vec<String>,
+        total: usize,
+    },
+
+    #[snafu(display("Partial restore failure: {} path(s) could not be restored", failed_paths.len()))]
+    PartialRestore { failed_paths: Vec<String> },
+
+    #[snafu(display("--exec command failed for {failed} path(s)"))]
+    ExecFailed { failed: usize },
+
     #[snafu(display("Failed to delete '{paths}' (recursive: {recursive}): {source}"))]
     DeleteFailed {
         paths: String,
@@ -55,6 +125,19 @@ pub enum Error {
         source: Box<Error>,
     },
 
+    #[snafu(display("{failed} of {total} file(s) failed to transfer"))]
+    TransferIncomplete { failed: usize, total: usize },
+
+    #[snafu(display("Failed to verify '{local_path}' against '{remote_path}': {source}"))]
+    VerifyFailed {
+        local_path: String,
+        remote_path: String,
+        source: Box<Error>,
+    },
+
+    #[snafu(display("{failed} of {total} file(s) are missing or differ remotely"))]
+    VerificationFailed { failed: usize, total: usize },
+
     #[snafu(display("Failed to copy '{src_path}' to '{dest_path}': {source}"))]
     CopyFailed {
         src_path: String,
@@ -69,6 +152,27 @@ pub enum Error {
         source: Box<Error>,
     },
 
+    #[snafu(display("Job step '{step}' failed: {source}"))]
+    ApplyStepFailed { step: String, source: Box<Error> },
+
+    #[snafu(display("Invalid job spec '{}': {message}", path.display()))]
+    InvalidJobSpec { path: PathBuf, message: String },
+
+    #[snafu(display("Invalid lifecycle rules '{}': {message}", path.display()))]
+    InvalidLifecycleRules { path: PathBuf, message: String },
+
+    #[snafu(display("Invalid CORS rules '{}': {message}", path.display()))]
+    InvalidCorsRules { path: PathBuf, message: String },
+
+    #[snafu(display("Invalid batch manifest '{}': {message}", path.display()))]
+    InvalidBatchManifest { path: PathBuf, message: String },
+
+    #[snafu(display("{failed} of {total} batch operation(s) failed"))]
+    BatchFailed { failed: usize, total: usize },
+
+    #[snafu(display("Invalid recording '{}': {message}", path.display()))]
+    InvalidRecording { path: PathBuf, message: String },
+
     #[snafu(display("Failed to list directory '{path}': {source}"))]
     ListDirectoryFailed { path: String, source: Box<Error> },
 
@@ -78,6 +182,13 @@ pub enum Error {
     #[snafu(display("Failed to create directory '{path}': {source}"))]
     DirectoryCreationFailed { path: String, source: Box<Error> },
 
+    #[snafu(display("Failed to change the mode of '{path}': {source}"))]
+    ChmodFailed { path: String, source: Box<Error> },
+
+    #[cfg(feature = "hdfs")]
+    #[snafu(display("Failed to change the owner of '{path}': {source}"))]
+    ChownFailed { path: String, source: Box<Error> },
+
     #[snafu(display("Failed to read file '{path}': {source}"))]
     CatFailed { path: String, source: Box<Error> },
 
@@ -103,33 +214,147 @@ pub enum Error {
     #[snafu(display("Failed to touch '{path}': {source}"))]
     TouchFailed { path: String, source: Box<Error> },
 
+    #[snafu(display("Failed to append to '{path}': {source}"))]
+    AppendFailed { path: String, source: Box<Error> },
+
+    #[snafu(display("Failed to concatenate into '{path}': {source}"))]
+    ConcatFailed { path: String, source: Box<Error> },
+
+    #[snafu(display("Failed to list versions of '{path}': {source}"))]
+    VersionsFailed { path: String, source: Box<Error> },
+
+    #[snafu(display("Failed to restore '{path}' to version '{version_id}': {source}"))]
+    RestoreFailed {
+        path: String,
+        version_id: String,
+        source: Box<Error>,
+    },
+
+    #[snafu(display("Failed to link '{alias}' to '{target}': {source}"))]
+    LinkFailed {
+        alias: String,
+        target: String,
+        source: Box<Error>,
+    },
+
+    #[snafu(display("Failed to update tags of '{path}': {source}"))]
+    TaggingFailed { path: String, source: Box<Error> },
+
+    #[snafu(display("Failed to {action} ACL of '{path}': {source}"))]
+    AclFailed {
+        action: String,
+        path: String,
+        source: Box<Error>,
+    },
+
+    #[snafu(display("Failed to {action} lifecycle rules: {source}"))]
+    LifecycleFailed { action: String, source: Box<Error> },
+
+    #[snafu(display("Failed to {action} incomplete multipart uploads: {source}"))]
+    MultipartFailed { action: String, source: Box<Error> },
+
+    #[snafu(display("Failed to {action}: {source}"))]
+    BucketConfigFailed { action: String, source: Box<Error> },
+
+    #[snafu(display("Failed to change storage class of '{path}': {source}"))]
+    StorageClassFailed { path: String, source: Box<Error> },
+
+    #[snafu(display("Failed to {action} trash: {source}"))]
+    TrashFailed { action: String, source: Box<Error> },
+
+    #[snafu(display("Failed to undo '{command}': {source}"))]
+    UndoFailed { command: String, source: Box<Error> },
+
+    #[snafu(display("Failed to collect garbage under '{path}': {source}"))]
+    GcFailed { path: String, source: Box<Error> },
+
+    #[cfg(feature = "fuse")]
+    #[snafu(display("Failed to mount '{path}': {source}"))]
+    MountFailed { path: String, source: Box<Error> },
+
+    #[snafu(display("Failed to serve on {addr}: {source}"))]
+    ServeFailed { addr: String, source: Box<Error> },
+
+    #[snafu(display("Provider request failed: {message}"))]
+    ProviderRequest { message: String },
+
+    #[snafu(display("Failed to index '{path}': {source}"))]
+    IndexFailed { path: String, source: Box<Error> },
+
+    #[snafu(display("Failed to write the inventory of '{path}': {source}"))]
+    InventoryFailed { path: String, source: Box<Error> },
+
+    #[snafu(display("Index database error in '{}': {source}", path.display()))]
+    IndexDatabase {
+        path: PathBuf,
+        #[serde(serialize_with = "as_display")]
+        source: rusqlite::Error,
+    },
+
+    #[snafu(display("Job '{id}' not found (see `storify jobs list`)"))]
+    JobNotFound { id: String },
+
+    #[snafu(display("Job '{id}' was cancelled"))]
+    JobCancelled { id: String },
+
+    #[snafu(display("Self-update failed: {message}"))]
+    SelfUpdate { message: String },
+
+    #[snafu(display("Checksum mismatch: expected {expected}, got {actual}"))]
+    ChecksumMismatch { expected: String, actual: String },
+
+    #[snafu(display("Copy of '{path}' does not match its source ({detail}); the source was kept"))]
+    CopyMismatch { path: String, detail: String },
+
+    #[snafu(display("Update available: {current} -> {latest} (run `storify self-update`)"))]
+    UpdateAvailable { current: String, latest: String },
+
     #[snafu(display("Invalid argument: {message}"))]
     InvalidArgument { message: String },
 
     #[snafu(display("OpenDAL error: {source}"))]
-    OpenDal { source: opendal::Error },
+    OpenDal {
+        #[serde(serialize_with = "as_display")]
+        source: opendal::Error,
+    },
 
     #[snafu(display("IO error: {source}"))]
-    Io { source: std::io::Error },
+    Io {
+        #[serde(serialize_with = "as_display")]
+        source: std::io::Error,
+    },
 
     #[snafu(display("JSON serialization error: {source}"))]
-    Json { source: serde_json::Error },
+    Json {
+        #[serde(serialize_with = "as_display")]
+        source: serde_json::Error,
+    },
 
     #[snafu(display("Failed to access profile store '{}': {source}", path.display()))]
     ProfileStoreIo {
         path: PathBuf,
+        #[serde(serialize_with = "as_display")]
         source: std::io::Error,
     },
 
     #[snafu(display("Failed to parse profile store '{}': {source}", path.display()))]
-    ProfileStoreParse { path: PathBuf, source: TomlDeError },
+    ProfileStoreParse {
+        path: PathBuf,
+        #[serde(serialize_with = "as_display")]
+        source: TomlDeError,
+    },
 
     #[snafu(display("Failed to serialize profile store '{}': {source}", path.display()))]
-    ProfileStoreSerialize { path: PathBuf, source: TomlSerError },
+    ProfileStoreSerialize {
+        path: PathBuf,
+        #[serde(serialize_with = "as_display")]
+        source: TomlSerError,
+    },
 
     #[snafu(display("Invalid UTF-8 in profile store '{}': {source}", path.display()))]
     ProfileStoreUtf8 {
         path: PathBuf,
+        #[serde(serialize_with = "as_display")]
         source: FromUtf8Error,
     },
 
@@ -139,12 +364,21 @@ pub enum Error {
     #[snafu(display("Profile decryption error: {message}"))]
     ProfileDecryption { message: String },
 
+    #[snafu(display("Client-side encryption error: {message}"))]
+    ObjectEncryption { message: String },
+
     #[snafu(display(
         "Profile store '{}' is encrypted; supply a master password",
         path.display()
     ))]
     ProfileStoreLocked { path: PathBuf },
 
+    #[snafu(display(
+        "Another sync is running (pid {pid}); lock file '{}'",
+        path.display()
+    ))]
+    SyncLocked { path: PathBuf, pid: String },
+
     #[snafu(display("Profile '{name}' not found"))]
     ProfileNotFound { name: String },
 
@@ -155,6 +389,60 @@ pub enum Error {
     // Reserved for future extension.
 }
 
+fn as_display<T: std::fmt::Display, S: Serializer>(
+    value: &T,
+    serializer: S,
+) -> std::result::Result<S::Ok, S::Error> {
+    serializer.collect_str(value)
+}
+
+/// How a failed command reports its error on stderr.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ErrorFormat {
+    /// `Error: <message>`
+    #[default]
+    Text,
+    /// One JSON object with the kind, exit code, message and the error's fields
+    Json,
+}
+
+/// Failure classes with their own process exit code, so scripts can branch on them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    /// Anything not classified below
+    Other,
+    /// Invalid arguments, configuration or input files (clap's own usage errors exit 2 too)
+    Config,
+    /// The object, file, profile or job does not exist
+    NotFound,
+    /// Some of the items of a multi-item operation failed
+    Partial,
+    /// Credentials were rejected, or access is denied
+    Auth,
+    /// The service is unreachable, throttled or temporarily failing
+    Unavailable,
+    /// The destination exists, a precondition failed, or another process holds a lock
+    Conflict,
+    /// The job was cancelled with `storify jobs cancel`
+    Cancelled,
+}
+
+impl ErrorKind {
+    pub fn exit_code(self) -> i32 {
+        match self {
+            ErrorKind::Other => 1,
+            ErrorKind::Config => 2,
+            ErrorKind::NotFound => 3,
+            ErrorKind::Partial => 4,
+            ErrorKind::Auth => 5,
+            ErrorKind::Unavailable => 6,
+            ErrorKind::Conflict => 7,
+            ErrorKind::Cancelled => 8,
+        }
+    }
+}
+
 impl From<opendal::Error> for Error {
     fn from(error: opendal::Error) -> Self {
         Error::OpenDal { source: error }
@@ -174,6 +462,99 @@ impl From<serde_json::Error> for Error {
 }
 
 impl Error {
+    /// The innermost error of this crate that this one wraps (itself when it wraps none).
+    pub fn root_cause(&self) -> &Error {
+        let mut current = self;
+        // Wrapping variants hold their source boxed, and expose it as the box.
+        while let Some(inner) = std::error::Error::source(current).and_then(|source| {
+            source
+                .downcast_ref::<Box<Error>>()
+                .map(|inner| &**inner)
+                .or_else(|| source.downcast_ref::<Error>())
+        }) {
+            current = inner;
+        }
+        current
+    }
+
+    /// Class of the failure, decided by the root cause: a download that failed because the
+    /// object is missing is `NotFound`.
+    pub fn kind(&self) -> ErrorKind {
+        match self.root_cause() {
+            Error::MissingEnvVar { .. }
+            | Error::MissingConfigField { .. }
+            | Error::UnsupportedConfigField { .. }
+            | Error::ConflictingConfigFields { .. }
+            | Error::AwsProfileNotFound { .. }
+            | Error::UnsupportedProvider { .. }
+            | Error::InvalidPath { .. }
+            | Error::DirectoryDeletionNotRecursive { .. }
+            | Error::RootDeletionRefused { .. }
+            | Error::PathEscapesRoot { .. }
+            | Error::DirectoryUploadNotRecursive
+            | Error::InvalidJobSpec { .. }
+            | Error::InvalidLifecycleRules { .. }
+            | Error::InvalidCorsRules { .. }
+            | Error::InvalidBatchManifest { .. }
+            | Error::InvalidRecording { .. }
+            | Error::InvalidArgument { .. }
+            | Error::ProfileStoreParse { .. }
+            | Error::NoConfiguration { .. } => ErrorKind::Config,
+            Error::PathNotFound { .. }
+            | Error::NoObjectsUnderPrefix { .. }
+            | Error::JobNotFound { .. }
+            | Error::ProfileNotFound { .. } => ErrorKind::NotFound,
+            Error::PartialDeletion { .. }
+            | Error::PartialFailure { .. }
+            | Error::PartialRestore { .. }
+            | Error::ExecFailed { .. }
+            | Error::TransferIncomplete { .. }
+            | Error::VerificationFailed { .. }
+            | Error::BatchFailed { .. } => ErrorKind::Partial,
+            Error::ProfileStoreLocked { .. } | Error::ProfileDecryption { .. } => ErrorKind::Auth,
+            Error::EndpointsUnreachable { .. } | Error::HealthCheckFailed { .. } => {
+                ErrorKind::Unavailable
+            }
+            Error::DestinationIsDirectory { .. }
+            | Error::DestinationIsFile { .. }
+            | Error::DestinationInsideSource { .. }
+            | Error::SyncLocked { .. } => ErrorKind::Conflict,
+            Error::JobCancelled { .. } => ErrorKind::Cancelled,
+            Error::OpenDal { source } => match source.kind() {
+                opendal::ErrorKind::NotFound => ErrorKind::NotFound,
+                opendal::ErrorKind::PermissionDenied => ErrorKind::Auth,
+                opendal::ErrorKind::ConfigInvalid => ErrorKind::Config,
+                opendal::ErrorKind::AlreadyExists
+                | opendal::ErrorKind::ConditionNotMatch
+                | opendal::ErrorKind::IsSameFile => ErrorKind::Conflict,
+                opendal::ErrorKind::RateLimited => ErrorKind::Unavailable,
+                _ if source.is_temporary() => ErrorKind::Unavailable,
+                _ => ErrorKind::Other,
+            },
+            Error::Io { source } | Error::ProfileStoreIo { source, .. } => match source.kind() {
+                std::io::ErrorKind::NotFound => ErrorKind::NotFound,
+                std::io::ErrorKind::PermissionDenied => ErrorKind::Auth,
+                _ => ErrorKind::Other,
+            },
+            _ => ErrorKind::Other,
+        }
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        self.kind().exit_code()
+    }
+
+    /// The object written to stderr by `--error-format json`.
+    pub fn to_json(&self) -> serde_json::Value {
+        let kind = self.kind();
+        serde_json::json!({
+            "kind": kind,
+            "exit_code": kind.exit_code(),
+            "message": self.to_string(),
+            "error": self,
+        })
+    }
+
     pub fn non_interactive(action: &str) -> Self {
         Error::InvalidArgument {
             message: format!(
@@ -182,3 +563,44 @@ impl Error {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kind_comes_from_the_root_cause() {
+        let missing = Error::DownloadFailed {
+            remote_path: "a/b".into(),
+            local_path: "b".into(),
+            source: Box::new(Error::OpenDal {
+                source: opendal::Error::new(opendal::ErrorKind::NotFound, "no such object"),
+            }),
+        };
+        assert_eq!(missing.kind(), ErrorKind::NotFound);
+        assert_eq!(missing.exit_code(), 3);
+
+        let json = missing.to_json();
+        assert_eq!(json["kind"], "not_found");
+        assert_eq!(json["exit_code"], 3);
+        assert_eq!(json["error"]["type"], "DownloadFailed");
+        assert_eq!(json["error"]["remote_path"], "a/b");
+        assert_eq!(json["error"]["source"]["type"], "OpenDal");
+        assert!(
+            json["error"]["source"]["source"]
+                .as_str()
+                .unwrap()
+                .contains("no such object")
+        );
+
+        let partial = Error::TransferIncomplete {
+            failed: 1,
+            total: 3,
+        };
+        assert_eq!(partial.exit_code(), 4);
+        assert_eq!(
+            Error::DirectoryUploadNotRecursive.to_json()["kind"],
+            "config"
+        );
+    }
+}
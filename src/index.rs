@@ -0,0 +1,307 @@
+//! Offline SQLite index of object listings used by `storify index`.
+
+use crate::error::{Error, Result};
+use crate::scratch::StagedFile;
+use clap::ValueEnum;
+use rusqlite::{Connection, params};
+use std::path::{Path, PathBuf};
+
+const SCHEMA: &str = "
+CREATE TABLE meta (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+);
+CREATE TABLE objects (
+    path TEXT PRIMARY KEY,
+    size INTEGER NOT NULL,
+    last_modified INTEGER,
+    etag TEXT,
+    extension TEXT
+);
+CREATE INDEX objects_size ON objects(size);
+CREATE INDEX objects_last_modified ON objects(last_modified);
+CREATE INDEX objects_extension ON objects(extension);
+";
+
+/// A single object row stored in the index.
+#[derive(Debug, Clone)]
+pub struct IndexedObject {
+    pub path: String,
+    pub size: u64,
+    /// Last modification time as unix seconds
+    pub last_modified: Option<i64>,
+    pub etag: Option<String>,
+}
+
+/// Writes a fresh index database inside a single transaction, staged next to the target
+/// so a failed or interrupted listing leaves any previous index untouched.
+pub struct IndexWriter {
+    path: PathBuf,
+    staged: StagedFile,
+    conn: Connection,
+    count: usize,
+}
+
+impl IndexWriter {
+    /// Create a new index at `path` for objects under `prefix`.
+    ///
+    /// Fails when the file already exists unless `force` is set, in which case it is replaced
+    /// once [`Self::finish`] succeeds.
+    pub fn create(path: &Path, prefix: &str, force: bool) -> Result<Self> {
+        if path.exists() && !force {
+            return Err(Error::InvalidArgument {
+                message: format!(
+                    "index file '{}' already exists; use --force to replace it",
+                    path.display()
+                ),
+            });
+        }
+
+        let staged = StagedFile::new(path);
+        let conn = Connection::open(staged.path()).map_err(|source| index_error(path, source))?;
+        conn.execute_batch(SCHEMA)
+            .and_then(|_| conn.execute_batch("BEGIN"))
+            .map_err(|source| index_error(path, source))?;
+
+        let writer = Self {
+            path: path.to_path_buf(),
+            staged,
+            conn,
+            count: 0,
+        };
+        writer.set_meta("prefix", prefix)?;
+        writer.set_meta("storify_version", env!("CARGO_PKG_VERSION"))?;
+        Ok(writer)
+    }
+
+    /// Record an object in the index.
+    pub fn insert(&mut self, object: &IndexedObject) -> Result<()> {
+        let extension = extension_of(&object.path);
+        self.conn
+            .prepare_cached(
+                "INSERT OR REPLACE INTO objects (path, size, last_modified, etag, extension)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+            )
+            .and_then(|mut stmt| {
+                stmt.execute(params![
+                    object.path,
+                    object.size as i64,
+                    object.last_modified,
+                    object.etag,
+                    extension,
+                ])
+            })
+            .map_err(|source| index_error(&self.path, source))?;
+        self.count += 1;
+        Ok(())
+    }
+
+    /// Commit the transaction, move the index into place and return the number of indexed
+    /// objects.
+    pub fn finish(self) -> Result<usize> {
+        self.set_meta("object_count", &self.count.to_string())?;
+        self.set_meta(
+            "created_at_unix",
+            &std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0)
+                .to_string(),
+        )?;
+        self.conn
+            .execute_batch("COMMIT")
+            .map_err(|source| index_error(&self.path, source))?;
+        let Self {
+            path,
+            staged,
+            conn,
+            count,
+        } = self;
+        conn.close()
+            .map_err(|(_, source)| index_error(&path, source))?;
+        staged.persist(&path)?;
+        Ok(count)
+    }
+
+    fn set_meta(&self, key: &str, value: &str) -> Result<()> {
+        self.conn
+            .execute(
+                "INSERT OR REPLACE INTO meta (key, value) VALUES (?1, ?2)",
+                params![key, value],
+            )
+            .map_err(|source| index_error(&self.path, source))?;
+        Ok(())
+    }
+}
+
+/// Prebuilt queries available through `storify index query`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum IndexQuery {
+    /// Index metadata and totals
+    Summary,
+    /// Largest objects first
+    Largest,
+    /// Object count and total size per file extension
+    ByExtension,
+    /// Least recently modified objects first
+    Oldest,
+    /// Most recently modified objects first
+    Newest,
+}
+
+/// Run a prebuilt query against an existing index and print tab-separated rows.
+pub fn run_query(path: &Path, query: IndexQuery, limit: usize) -> Result<()> {
+    if !path.exists() {
+        return Err(Error::PathNotFound {
+            path: path.to_path_buf(),
+        });
+    }
+    let conn = Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|source| index_error(path, source))?;
+
+    let sql = match query {
+        IndexQuery::Summary => {
+            return print_summary(&conn, path);
+        }
+        IndexQuery::Largest => {
+            "SELECT size, COALESCE(datetime(last_modified, 'unixepoch'), '-'), path
+             FROM objects ORDER BY size DESC, path LIMIT ?1"
+        }
+        IndexQuery::ByExtension => {
+            "SELECT SUM(size), COUNT(*) || ' objects', COALESCE(extension, '(none)')
+             FROM objects GROUP BY extension ORDER BY SUM(size) DESC LIMIT ?1"
+        }
+        IndexQuery::Oldest => {
+            "SELECT size, datetime(last_modified, 'unixepoch'), path
+             FROM objects WHERE last_modified IS NOT NULL
+             ORDER BY last_modified ASC, path LIMIT ?1"
+        }
+        IndexQuery::Newest => {
+            "SELECT size, datetime(last_modified, 'unixepoch'), path
+             FROM objects WHERE last_modified IS NOT NULL
+             ORDER BY last_modified DESC, path LIMIT ?1"
+        }
+    };
+
+    let mut stmt = conn
+        .prepare(sql)
+        .map_err(|source| index_error(path, source))?;
+    let rows = stmt
+        .query_map(params![limit as i64], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })
+        .map_err(|source| index_error(path, source))?;
+
+    for row in rows {
+        let (size, second, third) = row.map_err(|source| index_error(path, source))?;
+        let size = crate::storage::format_size(size.max(0) as u64);
+        println!("{size}\t{second}\t{third}");
+    }
+    Ok(())
+}
+
+fn print_summary(conn: &Connection, path: &Path) -> Result<()> {
+    let mut stmt = conn
+        .prepare("SELECT key, value FROM meta ORDER BY key")
+        .map_err(|source| index_error(path, source))?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })
+        .map_err(|source| index_error(path, source))?;
+    for row in rows {
+        let (key, value) = row.map_err(|source| index_error(path, source))?;
+        println!("{key}={value}");
+    }
+
+    let total: i64 = conn
+        .query_row("SELECT COALESCE(SUM(size), 0) FROM objects", [], |row| {
+            row.get(0)
+        })
+        .map_err(|source| index_error(path, source))?;
+    println!(
+        "total_size={}",
+        crate::storage::format_size(total.max(0) as u64)
+    );
+    Ok(())
+}
+
+/// Lower-cased file extension of the last path segment, if any.
+fn extension_of(path: &str) -> Option<String> {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() && !ext.is_empty() => Some(ext.to_lowercase()),
+        _ => None,
+    }
+}
+
+fn index_error(path: &Path, source: rusqlite::Error) -> Error {
+    Error::IndexDatabase {
+        path: path.to_path_buf(),
+        source,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writer_roundtrip_and_queries() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let db = dir.path().join("index.db");
+
+        let mut writer = IndexWriter::create(&db, "logs/", false).unwrap();
+        for (path, size) in [("logs/a.txt", 10), ("logs/b.TXT", 30), ("logs/c", 5)] {
+            writer
+                .insert(&IndexedObject {
+                    path: path.to_string(),
+                    size,
+                    last_modified: Some(1_700_000_000),
+                    etag: None,
+                })
+                .unwrap();
+        }
+        assert_eq!(writer.finish().unwrap(), 3);
+
+        for query in [
+            IndexQuery::Summary,
+            IndexQuery::Largest,
+            IndexQuery::ByExtension,
+            IndexQuery::Oldest,
+            IndexQuery::Newest,
+        ] {
+            run_query(&db, query, 10).unwrap();
+        }
+
+        assert!(IndexWriter::create(&db, "logs/", false).is_err());
+        // Replacing only happens once the new index is complete.
+        let mut unfinished = IndexWriter::create(&db, "other/", true).unwrap();
+        unfinished
+            .insert(&IndexedObject {
+                path: "other/x".to_string(),
+                size: 1,
+                last_modified: None,
+                etag: None,
+            })
+            .unwrap();
+        drop(unfinished);
+        let count: i64 = Connection::open(&db)
+            .unwrap()
+            .query_row("SELECT COUNT(*) FROM objects", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 3);
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn extension_is_taken_from_last_segment() {
+        assert_eq!(extension_of("a.dir/file.TAR").as_deref(), Some("tar"));
+        assert_eq!(extension_of("a.dir/file"), None);
+        assert_eq!(extension_of(".hidden"), None);
+    }
+}
@@ -0,0 +1,346 @@
+//! File-backed registry of long-running operations with cooperative cancellation.
+//!
+//! Every registered job owns `<id>.json` in the registry directory. Cancelling a job
+//! drops a `<id>.cancel` marker next to it, which the owning process polls for. It then
+//! trips the job's [`CancelToken`], and the transfer loops stop at their next chunk or
+//! file, cleaning up what they staged.
+//!
+//! While it runs, a job refreshes the heartbeat in its record; records whose owner has
+//! exited (or, where liveness cannot be checked, stopped beating) are dropped on listing.
+
+use crate::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+/// Overrides the registry location (mainly for tests and multi-user hosts).
+const JOBS_DIR_ENV: &str = "STORIFY_JOBS_DIR";
+
+/// How often a running job checks for a cancellation request.
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How often a running job refreshes the heartbeat in its record.
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// A record whose heartbeat is older than this belongs to a process that is gone.
+const HEARTBEAT_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Persisted description of a running job.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct JobRecord {
+    pub id: String,
+    pub pid: u32,
+    pub command: String,
+    /// Start time as unix seconds
+    pub started_at: u64,
+    /// Last sign of life from the owning process, as unix seconds
+    #[serde(default)]
+    pub heartbeat_at: u64,
+}
+
+impl JobRecord {
+    /// Whether the owning process is still running the job.
+    fn alive(&self) -> bool {
+        let last_seen = self.heartbeat_at.max(self.started_at);
+        process_alive(self.pid)
+            && now_unix().saturating_sub(last_seen) < HEARTBEAT_TIMEOUT.as_secs()
+    }
+}
+
+/// A job as seen by `storify jobs list`.
+#[derive(Debug, Clone)]
+pub struct JobStatus {
+    pub record: JobRecord,
+    pub cancel_requested: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct JobRegistry {
+    dir: PathBuf,
+}
+
+impl JobRegistry {
+    /// Open the registry at `$STORIFY_JOBS_DIR` or `~/.config/storify/jobs`.
+    pub fn open() -> Result<Self> {
+        Self::at(default_jobs_dir())
+    }
+
+    /// Open (and create if needed) a registry rooted at `dir`.
+    pub fn at(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    /// Register a job for the current process; it is removed when the handle drops.
+    pub fn register(&self, command: impl Into<String>) -> Result<JobHandle> {
+        let now = now_unix();
+        let record = JobRecord {
+            id: Uuid::new_v4().simple().to_string()[..8].to_string(),
+            pid: std::process::id(),
+            command: command.into(),
+            started_at: now,
+            heartbeat_at: now,
+        };
+        self.write(&record)?;
+        Ok(JobHandle {
+            registry: self.clone(),
+            token: CancelToken::new(&record.id),
+            record,
+        })
+    }
+
+    /// List live jobs, removing records left behind by processes that exited.
+    pub fn list(&self) -> Result<Vec<JobStatus>> {
+        let mut jobs = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            let Ok(record) = fs::read(&path)
+                .map_err(Error::from)
+                .and_then(|bytes| serde_json::from_slice::<JobRecord>(&bytes).map_err(Error::from))
+            else {
+                continue;
+            };
+            if !record.alive() {
+                self.remove(&record.id);
+                continue;
+            }
+            jobs.push(JobStatus {
+                cancel_requested: self.cancel_path(&record.id).exists(),
+                record,
+            });
+        }
+        jobs.sort_by_key(|job| job.record.started_at);
+        Ok(jobs)
+    }
+
+    /// Ask the process owning `id` to stop.
+    pub fn cancel(&self, id: &str) -> Result<JobRecord> {
+        let job = self
+            .list()?
+            .into_iter()
+            .find(|job| job.record.id == id)
+            .ok_or_else(|| Error::JobNotFound { id: id.to_string() })?;
+        fs::write(self.cancel_path(id), b"")?;
+        Ok(job.record)
+    }
+
+    /// Path of the lock file guarding the recurring task `name`.
+    pub fn lock_path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{name}.lock"))
+    }
+
+    fn record_path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{id}.json"))
+    }
+
+    fn cancel_path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{id}.cancel"))
+    }
+
+    fn write(&self, record: &JobRecord) -> Result<()> {
+        fs::write(
+            self.record_path(&record.id),
+            serde_json::to_vec_pretty(record)?,
+        )?;
+        Ok(())
+    }
+
+    fn remove(&self, id: &str) {
+        let _ = fs::remove_file(self.record_path(id));
+        let _ = fs::remove_file(self.cancel_path(id));
+    }
+}
+
+/// Registration of a running job; unregisters itself on drop.
+#[derive(Debug)]
+pub struct JobHandle {
+    registry: JobRegistry,
+    record: JobRecord,
+    token: CancelToken,
+}
+
+impl JobHandle {
+    pub fn id(&self) -> &str {
+        &self.record.id
+    }
+
+    /// Token the work of this job checks between chunks and files.
+    pub fn token(&self) -> CancelToken {
+        self.token.clone()
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.registry.cancel_path(&self.record.id).exists()
+    }
+
+    /// Resolve once cancellation has been requested through the registry, refreshing the
+    /// heartbeat of the record meanwhile.
+    pub async fn cancelled(&self) {
+        let mut record = self.record.clone();
+        while !self.is_cancelled() {
+            tokio::time::sleep(CANCEL_POLL_INTERVAL).await;
+            let now = now_unix();
+            if now.saturating_sub(record.heartbeat_at) >= HEARTBEAT_INTERVAL.as_secs() {
+                record.heartbeat_at = now;
+                if let Err(err) = self.registry.write(&record) {
+                    tracing::debug!("failed to refresh heartbeat of job {}: {err}", record.id);
+                }
+            }
+        }
+    }
+
+    /// Run `work` until it finishes or the job is cancelled. On cancellation the token is
+    /// tripped and `work` is still awaited, so it stops at its next check and cleans up
+    /// rather than being dropped mid-write.
+    pub async fn run<T>(&self, work: impl Future<Output = Result<T>>) -> Result<T> {
+        let mut work = std::pin::pin!(work);
+        tokio::select! {
+            result = &mut work => result,
+            _ = self.cancelled() => {
+                self.token.cancel();
+                work.await.and(Err(Error::JobCancelled {
+                    id: self.record.id.clone(),
+                }))
+            }
+        }
+    }
+}
+
+impl Drop for JobHandle {
+    fn drop(&mut self) {
+        self.registry.remove(&self.record.id);
+    }
+}
+
+/// Cooperative cancellation of a job, shared by everything working on it. The default
+/// token belongs to no job and is never cancelled.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    id: Arc<str>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancelToken {
+    fn new(id: &str) -> Self {
+        Self {
+            id: id.into(),
+            cancelled: Arc::default(),
+        }
+    }
+
+    fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// `Err(JobCancelled)` once the job has been cancelled.
+    pub fn check(&self) -> Result<()> {
+        if self.is_cancelled() {
+            return Err(Error::JobCancelled {
+                id: self.id.to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+fn default_jobs_dir() -> PathBuf {
+    env::var(JOBS_DIR_ENV)
+        .ok()
+        .map(PathBuf::from)
+        .or_else(|| {
+            directories::BaseDirs::new().map(|base_dirs| {
+                base_dirs
+                    .home_dir()
+                    .join(".config")
+                    .join("storify")
+                    .join("jobs")
+            })
+        })
+        .unwrap_or_else(|| env::temp_dir().join("storify-jobs"))
+}
+
+#[cfg(target_os = "linux")]
+pub(crate) fn process_alive(pid: u32) -> bool {
+    std::path::Path::new("/proc").join(pid.to_string()).exists()
+}
+
+// Without a portable liveness check, records expire through their heartbeat alone.
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn process_alive(_pid: u32) -> bool {
+    true
+}
+
+fn now_unix() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Seconds elapsed since `started_at`, formatted compactly (e.g. `42s`, `3m`, `2h`).
+pub fn format_age(started_at: u64) -> String {
+    let secs = now_unix().saturating_sub(started_at);
+    match secs {
+        0..60 => format!("{secs}s"),
+        60..3600 => format!("{}m", secs / 60),
+        _ => format!("{}h", secs / 3600),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn register_cancel_and_cleanup() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let registry = JobRegistry::at(dir.path()).unwrap();
+
+        let job = registry.register("get big/file ./").unwrap();
+        let listed = registry.list().unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].record.id, job.id());
+        assert!(!listed[0].cancel_requested);
+
+        registry.cancel(job.id()).unwrap();
+        let token = job.token();
+        let result = job
+            .run(async {
+                loop {
+                    token.check()?;
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                }
+            })
+            .await;
+        assert!(matches!(result, Err::<(), _>(Error::JobCancelled { .. })));
+        assert!(token.is_cancelled());
+
+        drop(job);
+        assert!(registry.list().unwrap().is_empty());
+
+        // A record that stopped beating is dropped even if its pid is still taken.
+        let stale = registry.register("put ./ backup/").unwrap();
+        let mut record = stale.record.clone();
+        record.started_at -= HEARTBEAT_TIMEOUT.as_secs() + 1;
+        record.heartbeat_at = record.started_at;
+        registry.write(&record).unwrap();
+        assert!(registry.list().unwrap().is_empty());
+        assert!(matches!(
+            registry.cancel("missing"),
+            Err(Error::JobNotFound { .. })
+        ));
+    }
+}
@@ -0,0 +1,274 @@
+//! Local journal of destructive operations, replayed backwards by `storify undo`.
+//!
+//! `rm`, `mv`, `touch -t` and single-file `put` append one JSON line to
+//! `~/.local/state/storify/journal.jsonl` describing how to revert them. Objects they
+//! delete or overwrite are first copied into `undo/` beside the journal when they are
+//! small; larger ones are recorded but cannot be brought back. Operations over too many
+//! objects or bytes are journaled as not undoable. `STORIFY_JOURNAL` moves
+//! the journal elsewhere, or turns journaling off when set to an empty value.
+
+use crate::error::Result;
+use crate::scratch::StagedFile;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use uuid::Uuid;
+
+/// Overrides the journal location; an empty value disables journaling.
+const JOURNAL_ENV: &str = "STORIFY_JOURNAL";
+
+/// Entries kept; older ones are dropped together with their backups.
+const MAX_ENTRIES: usize = 50;
+
+/// Largest object copied aside before it is deleted or overwritten.
+pub const MAX_BACKUP_SIZE: u64 = 8 * 1024 * 1024;
+
+/// Most bytes copied aside for a single operation.
+pub const MAX_BACKUP_TOTAL: u64 = 64 * 1024 * 1024;
+
+/// Most objects recorded for a single operation.
+pub const MAX_BACKUP_OBJECTS: usize = 1000;
+
+/// One reversible operation.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct JournalEntry {
+    pub id: String,
+    /// Unix seconds the operation ran at
+    pub recorded_at: u64,
+    /// Command line as typed
+    pub command: String,
+    /// Storage the operation changed (see `StorageClient::location`)
+    pub location: String,
+    pub change: Change,
+}
+
+impl JournalEntry {
+    /// Entry for an operation that ran just now.
+    pub fn new(id: String, command: String, location: String, change: Change) -> Self {
+        Self {
+            id,
+            recorded_at: now_unix(),
+            command,
+            location,
+            change,
+        }
+    }
+}
+
+/// How an operation changed storage, and so how to revert it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Change {
+    /// Paths `rm --trash` moved into a trash batch
+    Trashed { batch: String, paths: Vec<String> },
+    /// A file or directory `mv` moved
+    Moved { from: String, to: String },
+    /// Objects deleted or overwritten in place, with what was there before
+    Replaced { objects: Vec<PriorObject> },
+    /// Objects deleted or overwritten without keeping their previous state, e.g. because
+    /// there were too many to snapshot
+    NotUndoable { reason: String },
+}
+
+impl Change {
+    /// What undoing the change does, for prompts.
+    pub fn describe(&self) -> String {
+        match self {
+            Change::Trashed { batch, paths } => {
+                format!("restore {} path(s) from trash batch {batch}", paths.len())
+            }
+            Change::Moved { from, to } => format!("move '{to}' back to '{from}'"),
+            Change::Replaced { objects } => {
+                format!("put back the previous state of {} object(s)", objects.len())
+            }
+            Change::NotUndoable { reason } => format!("nothing, it cannot be undone: {reason}"),
+        }
+    }
+}
+
+/// An object as it was before an operation deleted or overwrote it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PriorObject {
+    pub path: String,
+    pub prior: Prior,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum Prior {
+    /// Did not exist; undo removes it
+    Missing,
+    /// Content copied to a local backup file
+    Saved { backup: PathBuf },
+    /// Not copied aside, being over the size limits; cannot be restored
+    NotKept { size: u64 },
+}
+
+#[derive(Debug, Clone)]
+pub struct Journal {
+    path: PathBuf,
+}
+
+impl Journal {
+    /// Open the journal at `$STORIFY_JOURNAL` or `~/.local/state/storify/journal.jsonl`;
+    /// `None` when journaling is disabled.
+    pub fn open() -> Option<Self> {
+        match env::var(JOURNAL_ENV) {
+            Ok(path) if path.trim().is_empty() => None,
+            Ok(path) => Some(Self::at(path)),
+            Err(_) => Some(Self::at(default_journal_path())),
+        }
+    }
+
+    pub fn at(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Id for a new entry; also names its backup directory.
+    pub fn new_id() -> String {
+        Uuid::new_v4().simple().to_string()[..8].to_string()
+    }
+
+    /// Directory backups of entry `id` are written to (created on demand).
+    pub fn backup_dir(&self, id: &str) -> Result<PathBuf> {
+        let dir = self.path.with_file_name("undo").join(id);
+        fs::create_dir_all(&dir)?;
+        Ok(dir)
+    }
+
+    /// Append `entry`, dropping the oldest entries beyond the limit.
+    pub fn append(&self, entry: &JournalEntry) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?
+            .write_all(line.as_bytes())?;
+
+        let entries = self.entries()?;
+        if entries.len() > MAX_ENTRIES {
+            let (dropped, kept) = entries.split_at(entries.len() - MAX_ENTRIES);
+            self.rewrite(kept)?;
+            for entry in dropped {
+                self.remove_backups(&entry.id);
+            }
+        }
+        Ok(())
+    }
+
+    /// Most recent entry, if any.
+    pub fn last(&self) -> Result<Option<JournalEntry>> {
+        Ok(self.entries()?.pop())
+    }
+
+    /// Forget entry `id` and delete its backups, e.g. once it has been undone.
+    pub fn remove(&self, id: &str) -> Result<()> {
+        let mut entries = self.entries()?;
+        entries.retain(|entry| entry.id != id);
+        self.rewrite(&entries)?;
+        self.remove_backups(id);
+        Ok(())
+    }
+
+    /// Entries oldest first; lines that do not parse are skipped.
+    pub fn entries(&self) -> Result<Vec<JournalEntry>> {
+        let text = match fs::read_to_string(&self.path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(text
+            .lines()
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect())
+    }
+
+    fn rewrite(&self, entries: &[JournalEntry]) -> Result<()> {
+        let mut text = String::new();
+        for entry in entries {
+            text.push_str(&serde_json::to_string(entry)?);
+            text.push('\n');
+        }
+        let staged = StagedFile::new(&self.path);
+        fs::write(staged.path(), text)?;
+        staged.persist(&self.path)
+    }
+
+    /// Delete the backups of entry `id`, e.g. when it ends up not being recorded.
+    pub fn remove_backups(&self, id: &str) {
+        let _ = fs::remove_dir_all(self.path.with_file_name("undo").join(id));
+    }
+}
+
+fn now_unix() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn default_journal_path() -> PathBuf {
+    directories::BaseDirs::new()
+        .map(|base_dirs| {
+            base_dirs
+                .home_dir()
+                .join(".local")
+                .join("state")
+                .join("storify")
+        })
+        .unwrap_or_else(|| env::temp_dir().join("storify-state"))
+        .join("journal.jsonl")
+}
+
+/// Backup file for the `index`th object of an entry.
+pub fn backup_path(dir: &Path, index: usize) -> PathBuf {
+    dir.join(index.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: &str) -> JournalEntry {
+        JournalEntry {
+            id: id.to_string(),
+            recorded_at: 1_700_000_000,
+            command: "mv a b".to_string(),
+            location: "s3://bucket/".to_string(),
+            change: Change::Moved {
+                from: "a".to_string(),
+                to: "b".to_string(),
+            },
+        }
+    }
+
+    #[test]
+    fn append_prunes_and_remove_drops_backups() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = Journal::at(dir.path().join("journal.jsonl"));
+        assert_eq!(journal.last().unwrap(), None);
+
+        let backups = journal.backup_dir("first").unwrap();
+        fs::write(backup_path(&backups, 0), b"old").unwrap();
+        journal.append(&entry("first")).unwrap();
+        for n in 0..MAX_ENTRIES {
+            journal.append(&entry(&format!("e{n}"))).unwrap();
+        }
+        let entries = journal.entries().unwrap();
+        assert_eq!(entries.len(), MAX_ENTRIES);
+        assert_eq!(entries[0].id, "e0");
+        assert!(!backups.exists());
+
+        let last = journal.last().unwrap().unwrap();
+        assert_eq!(last.id, format!("e{}", MAX_ENTRIES - 1));
+        journal.remove(&last.id).unwrap();
+        assert_eq!(journal.entries().unwrap().len(), MAX_ENTRIES - 1);
+    }
+}
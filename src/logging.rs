@@ -0,0 +1,209 @@
+//! Diagnostic logging for `-v`, `--log-format` and `--log-file`.
+//!
+//! Warnings are always shown; each `-v` adds a level for storify and OpenDAL (`info`,
+//! `debug`, `trace`), while other crates stay at `warn` until `-vvv`. `RUST_LOG`, when set,
+//! replaces the level selection entirely. Storage operations run inside spans (the command,
+//! then each OpenDAL operation), and every HTTP request is logged at `debug` with its
+//! status and latency, so a failed request can be traced back to the operation that sent it.
+
+use crate::error::{Error, Result};
+use clap::ValueEnum;
+use serde_json::{Map, Value};
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::IsTerminal;
+use std::path::Path;
+use std::sync::Mutex;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::EnvFilter;
+use tracing_subscriber::fmt::format::Writer;
+use tracing_subscriber::fmt::{FmtContext, FormatEvent, FormatFields, FormattedFields};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Layout of log lines.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum LogFormat {
+    /// Human-readable lines
+    #[default]
+    Text,
+    /// One JSON object per line with the fields of the event and its spans
+    Json,
+}
+
+/// Install the global subscriber; `verbosity` counts the `-v` flags.
+pub fn init(verbosity: u8, format: LogFormat, file: Option<&Path>) -> Result<()> {
+    let filter = match std::env::var(EnvFilter::DEFAULT_ENV) {
+        Ok(directives) if !directives.trim().is_empty() => EnvFilter::new(directives),
+        _ => EnvFilter::new(directives(verbosity)),
+    };
+    let builder = tracing_subscriber::fmt().with_env_filter(filter);
+    let result = match (file, format) {
+        (Some(path), LogFormat::Text) => builder
+            .with_ansi(false)
+            .with_writer(Mutex::new(open_log_file(path)?))
+            .try_init(),
+        (Some(path), LogFormat::Json) => builder
+            .fmt_fields(JsonFields)
+            .event_format(JsonFormat)
+            .with_writer(Mutex::new(open_log_file(path)?))
+            .try_init(),
+        (None, LogFormat::Text) => builder
+            .with_ansi(std::io::stderr().is_terminal())
+            .with_writer(std::io::stderr)
+            .try_init(),
+        (None, LogFormat::Json) => builder
+            .fmt_fields(JsonFields)
+            .event_format(JsonFormat)
+            .with_writer(std::io::stderr)
+            .try_init(),
+    };
+    result.map_err(|err| Error::InvalidArgument {
+        message: format!("failed to set up logging: {err}"),
+    })
+}
+
+/// Filter directives for `verbosity` `-v` flags.
+fn directives(verbosity: u8) -> &'static str {
+    match verbosity {
+        0 => "warn",
+        1 => "warn,storify=info,opendal=info",
+        2 => "warn,storify=debug,opendal=debug",
+        _ => "info,storify=trace,opendal=trace",
+    }
+}
+
+fn open_log_file(path: &Path) -> Result<File> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|err| Error::InvalidArgument {
+            message: format!("cannot open log file '{}': {err}", path.display()),
+        })
+}
+
+/// Collects the fields of an event or span into a JSON object.
+#[derive(Default)]
+struct JsonVisitor(Map<String, Value>);
+
+impl Visit for JsonVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0
+            .insert(field.name().to_string(), Value::from(format!("{value:?}")));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().to_string(), Value::from(value));
+    }
+}
+
+/// Stores span fields as a JSON object so [`JsonFormat`] can nest them.
+struct JsonFields;
+
+impl<'writer> FormatFields<'writer> for JsonFields {
+    fn format_fields<R: tracing_subscriber::field::RecordFields>(
+        &self,
+        mut writer: Writer<'writer>,
+        fields: R,
+    ) -> fmt::Result {
+        let mut visitor = JsonVisitor::default();
+        fields.record(&mut visitor);
+        write!(writer, "{}", Value::Object(visitor.0))
+    }
+
+    fn add_fields(
+        &self,
+        current: &'writer mut FormattedFields<Self>,
+        fields: &tracing::span::Record<'_>,
+    ) -> fmt::Result {
+        let mut visitor = JsonVisitor(span_fields(current));
+        fields.record(&mut visitor);
+        current.fields = Value::Object(visitor.0).to_string();
+        Ok(())
+    }
+}
+
+fn span_fields(formatted: &str) -> Map<String, Value> {
+    match serde_json::from_str(formatted) {
+        Ok(Value::Object(fields)) => fields,
+        _ => Map::new(),
+    }
+}
+
+/// `{"timestamp", "level", "target", "message", ...fields, "spans": [...]}` per event,
+/// with the spans listed from the outermost.
+struct JsonFormat;
+
+impl<S, N> FormatEvent<S, N> for JsonFormat
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    N: for<'a> FormatFields<'a> + 'static,
+{
+    fn format_event(
+        &self,
+        ctx: &FmtContext<'_, S, N>,
+        mut writer: Writer<'_>,
+        event: &Event<'_>,
+    ) -> fmt::Result {
+        let metadata = event.metadata();
+        let mut line = Map::new();
+        line.insert(
+            "timestamp".to_string(),
+            Value::from(chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true)),
+        );
+        line.insert("level".to_string(), Value::from(metadata.level().as_str()));
+        line.insert("target".to_string(), Value::from(metadata.target()));
+        let mut visitor = JsonVisitor::default();
+        event.record(&mut visitor);
+        // Events bridged from the `log` crate carry their real target in `log.target`.
+        if let Some(target) = visitor.0.remove("log.target") {
+            line.insert("target".to_string(), target);
+        }
+        visitor.0.retain(|name, _| !name.starts_with("log."));
+        line.extend(visitor.0);
+
+        let spans: Vec<Value> = ctx
+            .event_scope()
+            .into_iter()
+            .flat_map(|scope| scope.from_root())
+            .map(|span| {
+                let mut fields = span
+                    .extensions()
+                    .get::<FormattedFields<N>>()
+                    .map(|formatted| span_fields(formatted))
+                    .unwrap_or_default();
+                fields.insert("name".to_string(), Value::from(span.name()));
+                Value::Object(fields)
+            })
+            .collect();
+        if !spans.is_empty() {
+            line.insert("spans".to_string(), Value::Array(spans));
+        }
+        writeln!(writer, "{}", Value::Object(line))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn verbosity_raises_storify_and_opendal_levels() {
+        assert_eq!(directives(0), "warn");
+        assert!(directives(2).contains("storify=debug"));
+        assert!(directives(7).contains("storify=trace"));
+    }
+}
@@ -1,7 +1,15 @@
+mod apply;
 mod cli;
 mod config;
 mod error;
+mod index;
+mod jobs;
+mod journal;
+mod logging;
+mod scratch;
+mod server;
 mod storage;
+mod update;
 mod utils;
 
 #[cfg(test)]
@@ -10,13 +18,18 @@ mod tests;
 use clap::Parser;
 
 use crate::cli::{Args, run};
+use crate::error::ErrorFormat;
 
 #[tokio::main]
 async fn main() {
     let args = Args::parse();
+    let error_format = args.global.error_format;
 
     if let Err(e) = run(args).await {
-        eprintln!("Error: {e}");
-        std::process::exit(1);
+        match error_format {
+            ErrorFormat::Text => eprintln!("Error: {e}"),
+            ErrorFormat::Json => eprintln!("{}", e.to_json()),
+        }
+        std::process::exit(e.exit_code());
     }
 }
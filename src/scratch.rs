@@ -0,0 +1,170 @@
+//! Naming and cleanup of scratch files.
+//!
+//! Local files that must never be seen half-written (downloads, the profile store, metrics
+//! textfiles, self-update binaries) are staged beside their target as
+//! `.<name>.storify-tmp-<pid>-<n>` and renamed into place. Temporary objects in a bucket
+//! belong under one prefix, `.storify-tmp/` unless `STORIFY_SCRATCH_PREFIX` says otherwise.
+//! `storify gc` removes whatever crashed runs left behind in either place.
+
+use crate::error::Result;
+use crate::jobs::process_alive;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime};
+
+/// Marker between the target name and the owner of a staging file.
+pub const STAGING_MARKER: &str = ".storify-tmp-";
+
+/// Overrides the bucket prefix reserved for temporary objects.
+const SCRATCH_PREFIX_ENV: &str = "STORIFY_SCRATCH_PREFIX";
+const DEFAULT_SCRATCH_PREFIX: &str = ".storify-tmp/";
+
+static NEXT_STAGING_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Bucket prefix (relative, ending in '/') for temporary objects.
+pub fn scratch_prefix() -> String {
+    let prefix = env::var(SCRATCH_PREFIX_ENV).unwrap_or_default();
+    let prefix = prefix.trim_matches('/');
+    if prefix.is_empty() {
+        DEFAULT_SCRATCH_PREFIX.to_string()
+    } else {
+        format!("{prefix}/")
+    }
+}
+
+/// Staging path beside `target`, unique within this process.
+pub fn staging_path(target: &Path) -> PathBuf {
+    let name = target
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "storify".to_string());
+    let id = NEXT_STAGING_ID.fetch_add(1, Ordering::Relaxed);
+    target.with_file_name(format!(
+        ".{name}{STAGING_MARKER}{}-{id}",
+        std::process::id()
+    ))
+}
+
+/// Process that created a staging file, or `None` for other names.
+fn staging_owner(name: &str) -> Option<u32> {
+    if !name.starts_with('.') {
+        return None;
+    }
+    let (_, owner) = name.rsplit_once(STAGING_MARKER)?;
+    let (pid, id) = owner.split_once('-')?;
+    id.parse::<u64>().ok()?;
+    pid.parse().ok()
+}
+
+/// A staging file removed on drop unless it was renamed into place.
+pub struct StagedFile {
+    path: PathBuf,
+    keep: bool,
+}
+
+impl StagedFile {
+    /// Reserve a staging path for `target`; nothing is created yet.
+    pub fn new(target: &Path) -> Self {
+        Self {
+            path: staging_path(target),
+            keep: false,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Rename the staged file over `target`.
+    pub fn persist(mut self, target: &Path) -> Result<()> {
+        fs::rename(&self.path, target)?;
+        self.keep = true;
+        Ok(())
+    }
+
+    /// Leave the file alone on drop, e.g. after the caller renamed it itself.
+    pub fn keep(mut self) {
+        self.keep = true;
+    }
+}
+
+impl Drop for StagedFile {
+    fn drop(&mut self) {
+        if !self.keep {
+            // Best effort: the file may never have been created.
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Staging files below `dir` whose process is gone and that were last written more than
+/// `older_than` ago.
+pub fn stale_staging_files(dir: &Path, older_than: Duration) -> Result<Vec<PathBuf>> {
+    let mut stale = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        for entry in fs::read_dir(&current)? {
+            let entry = entry?;
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                pending.push(entry.path());
+                continue;
+            }
+            let name = entry.file_name();
+            let Some(pid) = staging_owner(&name.to_string_lossy()) else {
+                continue;
+            };
+            if pid == std::process::id() || process_alive(pid) {
+                continue;
+            }
+            let age = entry
+                .metadata()?
+                .modified()
+                .ok()
+                .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+                .unwrap_or_default();
+            if age >= older_than {
+                stale.push(entry.path());
+            }
+        }
+    }
+    stale.sort();
+    Ok(stale)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn staging_names_identify_their_owner() {
+        let staged = staging_path(Path::new("/data/report.csv"));
+        let name = staged.file_name().unwrap().to_string_lossy().into_owned();
+        assert!(name.starts_with(".report.csv.storify-tmp-"));
+        assert_eq!(staging_owner(&name), Some(std::process::id()));
+        assert_eq!(staging_owner("report.csv"), None);
+        assert_eq!(staging_owner(".report.csv.storify-tmp-x-1"), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn stale_staging_files_skip_live_owners() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("nested")).unwrap();
+        let ours = staging_path(&dir.path().join("a.bin"));
+        let orphan = dir.path().join("nested/.b.bin.storify-tmp-4194303-0");
+        fs::write(&ours, b"x").unwrap();
+        fs::write(&orphan, b"x").unwrap();
+        fs::write(dir.path().join("c.bin"), b"x").unwrap();
+
+        let stale = stale_staging_files(dir.path(), Duration::ZERO).unwrap();
+        assert_eq!(stale, vec![orphan]);
+        assert!(
+            stale_staging_files(dir.path(), Duration::from_secs(3600))
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
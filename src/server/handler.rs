@@ -0,0 +1,638 @@
+//! Maps gateway requests onto the remote: files are streamed with range support, directories
+//! answer with their `index.html` or a listing, and PUT uploads when a token is configured.
+
+use chrono::{DateTime, Utc};
+use futures::TryStreamExt;
+use opendal::{ErrorKind, Metadata, Operator};
+use std::ops::Range;
+use tokio::io::{AsyncBufRead, AsyncReadExt, AsyncSeekExt, AsyncWrite, AsyncWriteExt};
+
+use super::http::{self, Request, Status};
+use super::listing::{self, ListingEntry};
+use super::{S3Options, ServeOptions};
+use crate::error::Error;
+use crate::jobs::{CancelToken, JobRegistry};
+use crate::scratch::StagedFile;
+use crate::storage::constants::DEFAULT_CHUNK_SIZE;
+use crate::storage::{ByteRange, is_remote_directory, normalize_under_root};
+
+const ALLOW: &str = "GET, HEAD, PUT";
+
+#[derive(Debug)]
+pub enum Body {
+    Empty,
+    Bytes(Vec<u8>),
+    /// Bytes `range` of the object at `path`, streamed from the remote
+    Object {
+        path: String,
+        range: Range<u64>,
+    },
+}
+
+#[derive(Debug)]
+pub struct Response {
+    pub status: Status,
+    pub headers: Vec<(&'static str, String)>,
+    pub body: Body,
+    /// The request body was left unread, so the connection cannot be reused
+    pub close: bool,
+}
+
+impl Response {
+    pub(super) fn new(status: Status) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: Body::Empty,
+            close: false,
+        }
+    }
+
+    /// A short plain-text response, mostly for errors.
+    pub fn text(status: Status, message: impl Into<String>) -> Self {
+        let mut body = message.into();
+        body.push('\n');
+        Self::new(status)
+            .header("Content-Type", "text/plain; charset=utf-8")
+            .with_body(Body::Bytes(body.into_bytes()))
+    }
+
+    pub(super) fn header(mut self, name: &'static str, value: impl Into<String>) -> Self {
+        self.headers.push((name, value.into()));
+        self
+    }
+
+    pub(super) fn with_body(mut self, body: Body) -> Self {
+        self.body = body;
+        self
+    }
+
+    fn length(&self) -> u64 {
+        match &self.body {
+            Body::Empty => 0,
+            Body::Bytes(bytes) => bytes.len() as u64,
+            Body::Object { range, .. } => range.end - range.start,
+        }
+    }
+}
+
+pub struct Gateway {
+    pub(super) operator: Operator,
+    /// Served key prefix: empty, or ending with `/`
+    pub(super) prefix: String,
+    listings: bool,
+    token: Option<String>,
+    /// Speak the S3 API instead of plain HTTP
+    pub(super) s3: Option<S3Options>,
+    /// Reported as the bucket creation date in S3 mode
+    pub(super) started: DateTime<Utc>,
+    /// Where uploads are registered, so `storify jobs` can list and cancel them
+    jobs: Option<JobRegistry>,
+}
+
+impl Gateway {
+    pub fn new(operator: Operator, options: &ServeOptions) -> Self {
+        let prefix = normalize_under_root(&options.prefix).unwrap_or_default();
+        Self {
+            operator,
+            prefix: if prefix.is_empty() {
+                prefix
+            } else {
+                format!("{prefix}/")
+            },
+            listings: options.listings,
+            token: options.token.clone(),
+            s3: options.s3.clone(),
+            started: Utc::now(),
+            jobs: JobRegistry::open()
+                .inspect_err(|e| tracing::warn!("uploads will not be listed as jobs: {e}"))
+                .ok(),
+        }
+    }
+
+    /// Copy a staged upload to `key` as a job of this process.
+    pub(super) async fn store(
+        &self,
+        staged: StagedUpload,
+        key: &str,
+        content_type: &str,
+    ) -> opendal::Result<()> {
+        let job = self.jobs.as_ref().and_then(|jobs| {
+            jobs.register(format!("serve PUT {key}"))
+                .inspect_err(|e| tracing::warn!("failed to register the upload of {key}: {e}"))
+                .ok()
+        });
+        let Some(job) = job else {
+            return staged
+                .commit(&self.operator, key, content_type, &CancelToken::default())
+                .await;
+        };
+        let token = job.token();
+        let commit = staged.commit(&self.operator, key, content_type, &token);
+        job.run(async { commit.await.map_err(Error::from) })
+            .await
+            .map_err(|e| match e {
+                Error::OpenDal { source } => source,
+                other => opendal::Error::new(ErrorKind::Unexpected, other.to_string()),
+            })
+    }
+
+    /// Answer `request`; PUT bodies are read from `body`, and `writer` receives the interim
+    /// `100 Continue` a client may wait for before sending one.
+    pub async fn handle<R, W>(&self, request: &Request, body: &mut R, writer: &mut W) -> Response
+    where
+        R: AsyncBufRead + Unpin,
+        W: AsyncWrite + Unpin,
+    {
+        if let Some(s3) = &self.s3 {
+            return self.handle_s3(s3, request, body, writer).await;
+        }
+        let Some(key) = self.key(&request.path) else {
+            return Response::text(Status::BAD_REQUEST, "path escapes the served prefix");
+        };
+        let response = match request.method.as_str() {
+            "GET" | "HEAD" => self.get(request, &key).await,
+            "PUT" => return self.put(request, &key, body, writer).await,
+            _ => Response::text(Status::METHOD_NOT_ALLOWED, "method not allowed")
+                .header("Allow", ALLOW),
+        };
+        unread(request, response)
+    }
+
+    /// Write `response`; HEAD gets the same headers without the body.
+    pub async fn write<W: AsyncWrite + Unpin>(
+        &self,
+        writer: &mut W,
+        response: Response,
+        head_only: bool,
+        keep_alive: bool,
+    ) -> std::io::Result<()> {
+        http::write_head(
+            writer,
+            response.status,
+            &response.headers,
+            response.length(),
+            keep_alive,
+        )
+        .await?;
+        if head_only {
+            return writer.flush().await;
+        }
+        match response.body {
+            Body::Empty => {}
+            Body::Bytes(bytes) => writer.write_all(&bytes).await?,
+            Body::Object { path, range } => {
+                if range.is_empty() {
+                    return writer.flush().await;
+                }
+                let mut stream = self
+                    .operator
+                    .reader_with(&path)
+                    .chunk(DEFAULT_CHUNK_SIZE)
+                    .await
+                    .map_err(std::io::Error::other)?
+                    .into_bytes_stream(range)
+                    .await
+                    .map_err(std::io::Error::other)?;
+                // The head is already out, so a failure here can only drop the connection.
+                while let Some(chunk) = stream.try_next().await? {
+                    writer.write_all(&chunk).await?;
+                }
+            }
+        }
+        writer.flush().await
+    }
+
+    /// Remote key for a request path; `None` when `..` climbs out of the prefix.
+    fn key(&self, path: &str) -> Option<String> {
+        let relative = normalize_under_root(path)?;
+        Some(format!("{}{relative}", self.prefix))
+    }
+
+    async fn get(&self, request: &Request, key: &str) -> Response {
+        let is_dir_request = request.path.ends_with('/');
+        if !is_dir_request && !key.is_empty() {
+            match self.operator.stat(key).await {
+                Ok(meta) if meta.mode().is_file() => return self.file(request, key, &meta),
+                Ok(_) => {}
+                Err(e) if e.kind() == ErrorKind::NotFound => {}
+                Err(e) => return error_response(&e),
+            }
+            if !is_remote_directory(&self.operator, &format!("{key}/")).await {
+                return Response::text(Status::NOT_FOUND, "not found");
+            }
+            let location = http::percent_encode(&format!("{}/", request.path), true);
+            return Response::text(Status::MOVED_PERMANENTLY, format!("see {location}"))
+                .header("Location", location);
+        }
+
+        let dir = if key.is_empty() || key.ends_with('/') {
+            key.to_string()
+        } else {
+            format!("{key}/")
+        };
+        let index = format!("{dir}index.html");
+        match self.operator.stat(&index).await {
+            Ok(meta) if meta.mode().is_file() => return self.file(request, &index, &meta),
+            Ok(_) => {}
+            Err(e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => return error_response(&e),
+        }
+        if !self.listings {
+            return Response::text(Status::NOT_FOUND, "not found");
+        }
+        self.listing(&request.path, &dir).await
+    }
+
+    pub(super) fn file(&self, request: &Request, key: &str, meta: &Metadata) -> Response {
+        let size = meta.content_length();
+        let mut headers = vec![
+            (
+                "Content-Type",
+                meta.content_type()
+                    .map(str::to_string)
+                    .unwrap_or_else(|| content_type(key).to_string()),
+            ),
+            ("Accept-Ranges", "bytes".to_string()),
+        ];
+        if let Some(modified) = meta.last_modified() {
+            headers.push(("Last-Modified", http::http_date(modified)));
+        }
+        let etag = meta.etag().map(|etag| {
+            if etag.starts_with('"') || etag.starts_with("W/") {
+                etag.to_string()
+            } else {
+                format!("\"{etag}\"")
+            }
+        });
+        if let Some(etag) = &etag {
+            headers.push(("ETag", etag.clone()));
+            if request.header("if-none-match").is_some_and(|tags| {
+                tags.split(',')
+                    .any(|tag| tag.trim() == etag || tag.trim() == "*")
+            }) {
+                let mut response = Response::new(Status::NOT_MODIFIED);
+                response.headers = headers;
+                return response;
+            }
+        }
+
+        let (status, range) = match request
+            .header("range")
+            .map(|value| parse_range(value, size))
+        {
+            None | Some(RangeRequest::Ignored) => (Status::OK, 0..size),
+            Some(RangeRequest::Satisfiable(range)) => {
+                headers.push((
+                    "Content-Range",
+                    format!("bytes {}-{}/{size}", range.start, range.end - 1),
+                ));
+                (Status::PARTIAL_CONTENT, range)
+            }
+            Some(RangeRequest::Unsatisfiable) => {
+                return Response::text(Status::RANGE_NOT_SATISFIABLE, "range not satisfiable")
+                    .header("Accept-Ranges", "bytes")
+                    .header("Content-Range", format!("bytes */{size}"));
+            }
+        };
+        let mut response = Response::new(status).with_body(Body::Object {
+            path: key.to_string(),
+            range,
+        });
+        response.headers = headers;
+        response
+    }
+
+    async fn listing(&self, url_path: &str, dir: &str) -> Response {
+        let entries: Vec<opendal::Entry> = match self.operator.list(dir).await {
+            Ok(entries) => entries,
+            Err(e) => return error_response(&e),
+        };
+        let mut children: Vec<ListingEntry> = entries
+            .iter()
+            .filter_map(|entry| {
+                let name = entry.path().strip_prefix(dir)?;
+                let is_dir = name.ends_with('/');
+                let name = name.trim_end_matches('/');
+                if name.is_empty() || name.contains('/') {
+                    return None;
+                }
+                let meta = entry.metadata();
+                // Listings of object stores carry sizes and times; others leave them out.
+                Some(ListingEntry {
+                    name: name.to_string(),
+                    is_dir,
+                    size: (!is_dir && meta.last_modified().is_some())
+                        .then(|| meta.content_length()),
+                    modified: meta.last_modified(),
+                })
+            })
+            .collect();
+        if children.is_empty() && dir != self.prefix {
+            return Response::text(Status::NOT_FOUND, "not found");
+        }
+        children.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.cmp(&b.name)));
+        Response::new(Status::OK)
+            .header("Content-Type", "text/html; charset=utf-8")
+            .with_body(Body::Bytes(
+                listing::render(url_path, &children).into_bytes(),
+            ))
+    }
+
+    async fn put<R, W>(
+        &self,
+        request: &Request,
+        key: &str,
+        body: &mut R,
+        writer: &mut W,
+    ) -> Response
+    where
+        R: AsyncBufRead + Unpin,
+        W: AsyncWrite + Unpin,
+    {
+        let rejected = |mut response: Response| {
+            response.close = true;
+            response
+        };
+        let Some(token) = &self.token else {
+            return rejected(
+                Response::text(
+                    Status::METHOD_NOT_ALLOWED,
+                    "uploads are disabled (start with --token)",
+                )
+                .header("Allow", "GET, HEAD"),
+            );
+        };
+        let presented = request
+            .header("authorization")
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(str::trim);
+        if !presented
+            .is_some_and(|presented| constant_time_eq(presented.as_bytes(), token.as_bytes()))
+        {
+            return rejected(
+                Response::text(Status::UNAUTHORIZED, "missing or wrong bearer token")
+                    .header("WWW-Authenticate", "Bearer realm=\"storify\""),
+            );
+        }
+        if key.is_empty() || key.ends_with('/') || request.path.ends_with('/') {
+            return rejected(Response::text(Status::BAD_REQUEST, "PUT needs a file path"));
+        }
+        let length = match request.content_length() {
+            Ok(Some(length)) => length,
+            Ok(None) => {
+                return rejected(Response::text(
+                    Status::LENGTH_REQUIRED,
+                    "Content-Length required",
+                ));
+            }
+            Err(status) => return rejected(Response::text(status, status.reason())),
+        };
+        if let Err(e) = send_continue(request, writer).await {
+            return rejected(Response::text(Status::BAD_REQUEST, e.to_string()));
+        }
+
+        match self.upload(key, length, body).await {
+            Ok(()) => {
+                tracing::info!("stored {length} bytes at {key}");
+                Response::text(Status::CREATED, "created")
+                    .header("Location", http::percent_encode(&request.path, true))
+            }
+            Err(UploadError::Client(e)) => {
+                rejected(Response::text(Status::BAD_REQUEST, e.to_string()))
+            }
+            Err(UploadError::Local(e)) => {
+                tracing::warn!("staging an upload failed: {e}");
+                rejected(Response::text(
+                    Status::INTERNAL_SERVER_ERROR,
+                    "staging the upload failed",
+                ))
+            }
+            Err(UploadError::Remote(e)) => rejected(error_response(&e)),
+        }
+    }
+
+    async fn upload<R: AsyncBufRead + Unpin>(
+        &self,
+        key: &str,
+        length: u64,
+        body: &mut R,
+    ) -> Result<(), UploadError> {
+        let mut staged = StagedUpload::new().await.map_err(UploadError::Local)?;
+        let mut left = length;
+        let mut buffer = vec![0u8; DEFAULT_CHUNK_SIZE.min(length.max(1) as usize)];
+        while left > 0 {
+            let want = buffer.len().min(left as usize);
+            let read = body
+                .read(&mut buffer[..want])
+                .await
+                .map_err(UploadError::Client)?;
+            if read == 0 {
+                return Err(UploadError::Client(std::io::Error::new(
+                    std::io::ErrorKind::UnexpectedEof,
+                    format!("body ended {left} bytes short of Content-Length"),
+                )));
+            }
+            staged
+                .write(&buffer[..read])
+                .await
+                .map_err(UploadError::Local)?;
+            left -= read as u64;
+        }
+        self.store(staged, key, content_type(key))
+            .await
+            .map_err(UploadError::Remote)
+    }
+}
+
+/// An upload body buffered in a local scratch file. Nothing reaches the remote until the
+/// body has arrived (and been checked) in full, as not every backend can abort a write.
+pub(super) struct StagedUpload {
+    staged: StagedFile,
+    file: tokio::fs::File,
+}
+
+impl StagedUpload {
+    pub(super) async fn new() -> std::io::Result<Self> {
+        let staged = StagedFile::new(&std::env::temp_dir().join("storify-serve-upload"));
+        let file = tokio::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(staged.path())
+            .await?;
+        Ok(Self { staged, file })
+    }
+
+    pub(super) async fn write(&mut self, data: &[u8]) -> std::io::Result<()> {
+        self.file.write_all(data).await
+    }
+
+    /// Copy the staged body to `key`, aborting the write if `cancel` trips; the scratch
+    /// file is removed either way.
+    pub(super) async fn commit(
+        mut self,
+        operator: &Operator,
+        key: &str,
+        content_type: &str,
+        cancel: &CancelToken,
+    ) -> opendal::Result<()> {
+        let local = |e: std::io::Error| {
+            opendal::Error::new(ErrorKind::Unexpected, "reading the staged upload failed")
+                .set_source(e)
+        };
+        self.file.flush().await.map_err(local)?;
+        self.file.rewind().await.map_err(local)?;
+        let mut writer = operator.writer_with(key).content_type(content_type).await?;
+        let mut buffer = vec![0u8; DEFAULT_CHUNK_SIZE];
+        loop {
+            if let Err(e) = cancel.check() {
+                writer.abort().await?;
+                return Err(opendal::Error::new(ErrorKind::Unexpected, e.to_string()));
+            }
+            let read = self.file.read(&mut buffer).await.map_err(local)?;
+            if read == 0 {
+                break;
+            }
+            writer.write(buffer[..read].to_vec()).await?;
+        }
+        writer.close().await?;
+        drop(self.staged);
+        Ok(())
+    }
+}
+
+/// Mark the response to a request whose body was left unread, so the connection is not reused.
+pub(super) fn unread(request: &Request, mut response: Response) -> Response {
+    response.close = !matches!(request.content_length(), Ok(None | Some(0)));
+    response
+}
+
+/// Send the interim `100 Continue` to a client that waits for it before sending a body.
+pub(super) async fn send_continue<W: AsyncWrite + Unpin>(
+    request: &Request,
+    writer: &mut W,
+) -> std::io::Result<()> {
+    if request
+        .header("expect")
+        .is_some_and(|value| value.eq_ignore_ascii_case("100-continue"))
+    {
+        writer.write_all(b"HTTP/1.1 100 Continue\r\n\r\n").await?;
+        writer.flush().await?;
+    }
+    Ok(())
+}
+
+enum UploadError {
+    /// Reading the request body failed
+    Client(std::io::Error),
+    /// Staging the body in a scratch file failed
+    Local(std::io::Error),
+    Remote(opendal::Error),
+}
+
+fn error_response(err: &opendal::Error) -> Response {
+    match err.kind() {
+        ErrorKind::NotFound => Response::text(Status::NOT_FOUND, "not found"),
+        ErrorKind::PermissionDenied => Response::text(Status::FORBIDDEN, "forbidden"),
+        _ => {
+            tracing::warn!("remote request failed: {err}");
+            Response::text(Status::BAD_GATEWAY, "remote request failed")
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum RangeRequest {
+    Satisfiable(Range<u64>),
+    Unsatisfiable,
+    /// Malformed or multi-range requests are answered with the whole object, as RFC 9110 allows.
+    Ignored,
+}
+
+fn parse_range(value: &str, size: u64) -> RangeRequest {
+    let Some(spec) = value.trim().strip_prefix("bytes=") else {
+        return RangeRequest::Ignored;
+    };
+    if spec.contains(',') {
+        return RangeRequest::Ignored;
+    }
+    let Ok(range) = spec.parse::<ByteRange>() else {
+        return RangeRequest::Ignored;
+    };
+    match range.resolve(size) {
+        Ok(range) if !range.is_empty() => RangeRequest::Satisfiable(range),
+        _ => RangeRequest::Unsatisfiable,
+    }
+}
+
+/// Compare secrets without leaking where they differ through timing.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// Content type by file extension, for objects stored without one.
+pub(super) fn content_type(key: &str) -> &'static str {
+    let extension = key
+        .rsplit_once('.')
+        .map(|(_, extension)| extension.to_ascii_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "json" | "map" => "application/json",
+        "txt" | "log" | "md" | "toml" | "yaml" | "yml" => "text/plain; charset=utf-8",
+        "csv" => "text/csv; charset=utf-8",
+        "xml" => "application/xml",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "ico" => "image/x-icon",
+        "pdf" => "application/pdf",
+        "wasm" => "application/wasm",
+        "zip" => "application/zip",
+        "gz" | "tgz" => "application/gzip",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        "mp3" => "audio/mpeg",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn range_headers_resolve_against_object_size() {
+        assert_eq!(
+            parse_range("bytes=0-9", 100),
+            RangeRequest::Satisfiable(0..10)
+        );
+        assert_eq!(
+            parse_range("bytes=90-", 100),
+            RangeRequest::Satisfiable(90..100)
+        );
+        assert_eq!(
+            parse_range("bytes=-5", 100),
+            RangeRequest::Satisfiable(95..100)
+        );
+        assert_eq!(
+            parse_range("bytes=50-500", 100),
+            RangeRequest::Satisfiable(50..100)
+        );
+        assert_eq!(parse_range("bytes=100-", 100), RangeRequest::Unsatisfiable);
+        assert_eq!(parse_range("bytes=0-", 0), RangeRequest::Unsatisfiable);
+        assert_eq!(parse_range("bytes=0-1,5-6", 100), RangeRequest::Ignored);
+        assert_eq!(parse_range("items=0-1", 100), RangeRequest::Ignored);
+
+        assert!(constant_time_eq(b"secret", b"secret"));
+        assert!(!constant_time_eq(b"secret", b"secreT"));
+        assert!(!constant_time_eq(b"secret", b"secret2"));
+        assert_eq!(content_type("site/INDEX.HTML"), "text/html; charset=utf-8");
+        assert_eq!(content_type("blob"), "application/octet-stream");
+    }
+}
@@ -0,0 +1,276 @@
+//! Just enough HTTP/1.1 for the gateway: request heads parsed with `httparse`, fixed-length
+//! bodies, keep-alive, and responses whose length is always known up front.
+
+use chrono::{DateTime, Utc};
+use std::io;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Largest request head accepted; browsers send a few KiB at most.
+const MAX_HEAD_SIZE: usize = 16 * 1024;
+const MAX_HEADERS: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Request {
+    pub method: String,
+    /// Percent-decoded path, without the query string
+    pub path: String,
+    /// Raw query string, without the `?`
+    pub query: String,
+    pub headers: Vec<(String, String)>,
+    /// HTTP/1.1 unless it says otherwise
+    http10: bool,
+}
+
+impl Request {
+    /// First value of header `name` (case-insensitive).
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Decoded `name=value` pairs of the query string, in order.
+    pub fn query_pairs(&self) -> Vec<(String, String)> {
+        self.query
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+                let decode = |part: &str| percent_decode(part).unwrap_or_else(|| part.to_string());
+                (decode(name), decode(value))
+            })
+            .collect()
+    }
+
+    /// First value of query parameter `name`.
+    pub fn query_param(&self, name: &str) -> Option<String> {
+        self.query_pairs()
+            .into_iter()
+            .find(|(param, _)| param == name)
+            .map(|(_, value)| value)
+    }
+
+    /// Whether the client wants the connection kept open after the response.
+    pub fn keep_alive(&self) -> bool {
+        match self.header("connection") {
+            Some(value) if value.eq_ignore_ascii_case("close") => false,
+            Some(value) if value.eq_ignore_ascii_case("keep-alive") => true,
+            _ => !self.http10,
+        }
+    }
+
+    /// Declared body length; `None` without a body, an error for bodies whose length is
+    /// not known up front.
+    pub fn content_length(&self) -> Result<Option<u64>, Status> {
+        if self.header("transfer-encoding").is_some() {
+            return Err(Status::LENGTH_REQUIRED);
+        }
+        self.header("content-length")
+            .map(|value| value.trim().parse().map_err(|_| Status::BAD_REQUEST))
+            .transpose()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Status(pub u16);
+
+impl Status {
+    pub const OK: Status = Status(200);
+    pub const CREATED: Status = Status(201);
+    pub const PARTIAL_CONTENT: Status = Status(206);
+    pub const MOVED_PERMANENTLY: Status = Status(301);
+    pub const NOT_MODIFIED: Status = Status(304);
+    pub const BAD_REQUEST: Status = Status(400);
+    pub const UNAUTHORIZED: Status = Status(401);
+    pub const FORBIDDEN: Status = Status(403);
+    pub const NOT_FOUND: Status = Status(404);
+    pub const METHOD_NOT_ALLOWED: Status = Status(405);
+    pub const LENGTH_REQUIRED: Status = Status(411);
+    pub const RANGE_NOT_SATISFIABLE: Status = Status(416);
+    pub const HEADERS_TOO_LARGE: Status = Status(431);
+    pub const INTERNAL_SERVER_ERROR: Status = Status(500);
+    pub const NOT_IMPLEMENTED: Status = Status(501);
+    pub const BAD_GATEWAY: Status = Status(502);
+
+    pub fn reason(&self) -> &'static str {
+        match self.0 {
+            200 => "OK",
+            201 => "Created",
+            206 => "Partial Content",
+            301 => "Moved Permanently",
+            304 => "Not Modified",
+            400 => "Bad Request",
+            401 => "Unauthorized",
+            403 => "Forbidden",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            411 => "Length Required",
+            416 => "Range Not Satisfiable",
+            431 => "Request Header Fields Too Large",
+            500 => "Internal Server Error",
+            501 => "Not Implemented",
+            502 => "Bad Gateway",
+            _ => "",
+        }
+    }
+}
+
+/// Read the next request head; `None` when the client closed the connection between requests.
+pub async fn read_request<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+) -> io::Result<Option<Result<Request, Status>>> {
+    let mut head = Vec::new();
+    loop {
+        let read = (&mut *reader)
+            .take((MAX_HEAD_SIZE - head.len()) as u64)
+            .read_until(b'\n', &mut head)
+            .await?;
+        if read == 0 {
+            if head.is_empty() {
+                return Ok(None);
+            }
+            return Ok(Some(Err(Status::BAD_REQUEST)));
+        }
+        if head.ends_with(b"\r\n\r\n") || head.ends_with(b"\n\n") {
+            break;
+        }
+        // Tolerate blank lines before a request line.
+        if head == b"\r\n" || head == b"\n" {
+            head.clear();
+        }
+        if head.len() >= MAX_HEAD_SIZE {
+            return Ok(Some(Err(Status::HEADERS_TOO_LARGE)));
+        }
+    }
+    Ok(Some(parse_head(&head)))
+}
+
+fn parse_head(head: &[u8]) -> Result<Request, Status> {
+    let mut headers = [httparse::EMPTY_HEADER; MAX_HEADERS];
+    let mut parsed = httparse::Request::new(&mut headers);
+    match parsed.parse(head) {
+        Ok(httparse::Status::Complete(_)) => {}
+        _ => return Err(Status::BAD_REQUEST),
+    }
+    let target = parsed.path.ok_or(Status::BAD_REQUEST)?;
+    let target = target.split('#').next().unwrap_or_default();
+    let (raw_path, query) = target.split_once('?').unwrap_or((target, ""));
+    if !raw_path.starts_with('/') {
+        return Err(Status::BAD_REQUEST);
+    }
+    Ok(Request {
+        method: parsed.method.ok_or(Status::BAD_REQUEST)?.to_string(),
+        path: percent_decode(raw_path).ok_or(Status::BAD_REQUEST)?,
+        query: query.to_string(),
+        headers: parsed
+            .headers
+            .iter()
+            .map(|header| {
+                (
+                    header.name.to_string(),
+                    String::from_utf8_lossy(header.value).into_owned(),
+                )
+            })
+            .collect(),
+        http10: parsed.version == Some(0),
+    })
+}
+
+/// Decode `%XX` escapes; `None` for malformed escapes or non-UTF-8 results.
+pub fn percent_decode(input: &str) -> Option<String> {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(index + 1..index + 3)?).ok()?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            index += 3;
+        } else {
+            decoded.push(bytes[index]);
+            index += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+/// Escape everything but unreserved characters (and `/` when `keep_slash`), as S3 and
+/// SigV4 expect; also used for links to keys.
+pub fn percent_encode(input: &str, keep_slash: bool) -> String {
+    let mut encoded = String::with_capacity(input.len());
+    for byte in input.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            b'/' if keep_slash => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
+
+/// `Last-Modified` / `Date` format (RFC 9110 IMF-fixdate).
+pub fn http_date(time: DateTime<Utc>) -> String {
+    time.format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+}
+
+/// Status line and headers of a response; `length` becomes `Content-Length`.
+pub async fn write_head<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    status: Status,
+    headers: &[(&str, String)],
+    length: u64,
+    keep_alive: bool,
+) -> io::Result<()> {
+    let mut head = format!("HTTP/1.1 {} {}\r\n", status.0, status.reason());
+    head.push_str(&format!("Date: {}\r\n", http_date(Utc::now())));
+    head.push_str("Server: storify\r\n");
+    for (name, value) in headers {
+        head.push_str(&format!("{name}: {value}\r\n"));
+    }
+    head.push_str(&format!("Content-Length: {length}\r\n"));
+    if !keep_alive {
+        head.push_str("Connection: close\r\n");
+    }
+    head.push_str("\r\n");
+    writer.write_all(head.as_bytes()).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn requests_parse_with_decoded_paths_and_keep_alive() {
+        let raw = b"GET /logs/a%20b.txt?x=1 HTTP/1.1\r\nHost: x\r\nRange: bytes=0-9\r\n\r\n\
+                    HEAD / HTTP/1.0\r\n\r\n";
+        let mut reader = &raw[..];
+        let first = read_request(&mut reader).await.unwrap().unwrap().unwrap();
+        assert_eq!(first.method, "GET");
+        assert_eq!(first.path, "/logs/a b.txt");
+        assert_eq!(
+            first.query_pairs(),
+            vec![("x".to_string(), "1".to_string())]
+        );
+        assert_eq!(first.header("range"), Some("bytes=0-9"));
+        assert!(first.keep_alive());
+
+        let second = read_request(&mut reader).await.unwrap().unwrap().unwrap();
+        assert_eq!(second.method, "HEAD");
+        assert!(!second.keep_alive());
+        assert_eq!(read_request(&mut reader).await.unwrap(), None);
+
+        let mut bad = &b"GET nope\r\n\r\n"[..];
+        assert_eq!(
+            read_request(&mut bad).await.unwrap(),
+            Some(Err(Status::BAD_REQUEST))
+        );
+
+        assert_eq!(percent_decode("%E2%9C%93/%41").as_deref(), Some("✓/A"));
+        assert_eq!(percent_decode("%4"), None);
+        assert_eq!(percent_encode("a b/ü#", true), "a%20b/%C3%BC%23");
+        assert_eq!(percent_encode("a b/c*~", false), "a%20b%2Fc%2A~");
+    }
+}
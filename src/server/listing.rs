@@ -0,0 +1,91 @@
+//! HTML directory listings, in the autoindex shape storify's own `http` provider can list.
+
+use chrono::{DateTime, Utc};
+
+use super::http::percent_encode;
+use crate::storage::format_size;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListingEntry {
+    /// Name within the directory, without a trailing `/`
+    pub name: String,
+    pub is_dir: bool,
+    pub size: Option<u64>,
+    pub modified: Option<DateTime<Utc>>,
+}
+
+/// Index page of the directory at `url_path` (which ends with `/`).
+pub fn render(url_path: &str, entries: &[ListingEntry]) -> String {
+    let title = escape_html(url_path);
+    let mut page = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Index of {title}</title>\n</head>\n<body>\n<h1>Index of {title}</h1>\n<table>\n"
+    );
+    if url_path != "/" {
+        page.push_str("<tr><td><a href=\"../\">../</a></td><td></td><td></td></tr>\n");
+    }
+    for entry in entries {
+        let suffix = if entry.is_dir { "/" } else { "" };
+        page.push_str(&format!(
+            "<tr><td><a href=\"{}{suffix}\">{}{suffix}</a></td><td>{}</td><td>{}</td></tr>\n",
+            percent_encode(&entry.name, true),
+            escape_html(&entry.name),
+            entry
+                .size
+                .map(format_size)
+                .unwrap_or_else(|| "-".to_string()),
+            entry
+                .modified
+                .map(|time| time.format("%Y-%m-%d %H:%M:%S").to_string())
+                .unwrap_or_default(),
+        ));
+    }
+    page.push_str("</table>\n</body>\n</html>\n");
+    page
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn listing_escapes_names_and_encodes_links() {
+        let page = render(
+            "/a&b/",
+            &[
+                ListingEntry {
+                    name: "sub dir".to_string(),
+                    is_dir: true,
+                    size: None,
+                    modified: None,
+                },
+                ListingEntry {
+                    name: "<x>\"#1.txt".to_string(),
+                    is_dir: false,
+                    size: Some(2048),
+                    modified: None,
+                },
+            ],
+        );
+        assert!(page.contains("<title>Index of /a&amp;b/</title>"));
+        assert!(page.contains("<a href=\"../\">../</a>"));
+        assert!(page.contains("<a href=\"sub%20dir/\">sub dir/</a>"));
+        assert!(page.contains("<a href=\"%3Cx%3E%22%231.txt\">&lt;x&gt;&quot;#1.txt</a>"));
+        assert!(page.contains("<td>2.0K</td>"));
+        assert!(!render("/", &[]).contains("../"));
+    }
+}
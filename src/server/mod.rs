@@ -0,0 +1,157 @@
+//! `storify serve`: an HTTP gateway to the remote, for previewing artifacts in a browser.
+//!
+//! The HTTP/1.1 side is deliberately small ([`http`]): request heads are parsed with
+//! `httparse`, responses always carry a `Content-Length`, and connections are kept alive.
+//! Requests are answered through the same OpenDAL operator the other commands use
+//! ([`handler`]), so retries, rate limits and `--stats` apply to served reads too.
+
+mod handler;
+mod http;
+mod listing;
+mod s3;
+mod sigv4;
+
+use crate::error::Result;
+use opendal::Operator;
+use std::fmt;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::io::BufReader;
+use tokio::net::{TcpListener, TcpStream};
+
+use self::handler::{Gateway, Response};
+use self::http::Status;
+
+#[derive(Clone)]
+pub struct ServeOptions {
+    pub addr: SocketAddr,
+    /// Remote prefix served as `/`
+    pub prefix: String,
+    /// Answer directory requests without an `index.html` with a listing
+    pub listings: bool,
+    /// Bearer token that enables PUT uploads
+    pub token: Option<String>,
+    /// Answer S3 API requests instead of plain HTTP ones
+    pub s3: Option<S3Options>,
+}
+
+/// The S3-compatible mode: the served prefix appears as one path-style bucket.
+#[derive(Debug, Clone)]
+pub struct S3Options {
+    /// Bucket name clients address
+    pub bucket: String,
+    /// Key pair requests must be signed with; without one the bucket is anonymous and read-only
+    pub credentials: Option<S3Credentials>,
+}
+
+#[derive(Clone)]
+pub struct S3Credentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+}
+
+impl fmt::Debug for S3Credentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("S3Credentials")
+            .field("access_key_id", &self.access_key_id)
+            .field("secret_access_key", &"****")
+            .finish()
+    }
+}
+
+impl fmt::Debug for ServeOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ServeOptions")
+            .field("addr", &self.addr)
+            .field("prefix", &self.prefix)
+            .field("listings", &self.listings)
+            .field("token", &self.token.as_ref().map(|_| "****"))
+            .field("s3", &self.s3)
+            .finish()
+    }
+}
+
+/// Serve the remote on `options.addr` until Ctrl-C is pressed.
+pub async fn serve(operator: Operator, options: &ServeOptions) -> Result<()> {
+    let listener = TcpListener::bind(options.addr).await?;
+    let addr = listener.local_addr()?;
+    let gateway = Arc::new(Gateway::new(operator, options));
+    let what = if options.prefix.trim_matches('/').is_empty() {
+        "the bucket".to_string()
+    } else {
+        format!("'{}'", options.prefix)
+    };
+    match &options.s3 {
+        Some(s3) => println!(
+            "Serving {what} as S3 bucket '{}' on http://{addr}/ ({}); press Ctrl-C to stop",
+            s3.bucket,
+            if s3.credentials.is_some() {
+                "signed requests only"
+            } else {
+                "anonymous, read-only"
+            },
+        ),
+        None => println!(
+            "Serving {what} on http://{addr}/ (uploads {}); press Ctrl-C to stop",
+            if options.token.is_some() {
+                "enabled"
+            } else {
+                "disabled"
+            },
+        ),
+    }
+
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                let (stream, peer) = match accepted {
+                    Ok(accepted) => accepted,
+                    Err(e) => {
+                        // Typically out of file descriptors; the next accept may succeed.
+                        tracing::warn!("accept failed: {e}");
+                        continue;
+                    }
+                };
+                let gateway = Arc::clone(&gateway);
+                tokio::spawn(async move {
+                    if let Err(e) = connection(&gateway, stream, peer).await {
+                        tracing::debug!("connection from {peer} ended: {e}");
+                    }
+                });
+            }
+            _ = tokio::signal::ctrl_c() => break,
+        }
+    }
+    println!("Stopped serving on http://{addr}/");
+    Ok(())
+}
+
+async fn connection(gateway: &Gateway, stream: TcpStream, peer: SocketAddr) -> std::io::Result<()> {
+    let (read, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(read);
+    while let Some(parsed) = http::read_request(&mut reader).await? {
+        let request = match parsed {
+            Ok(request) => request,
+            Err(status) => {
+                let response = Response::text(status, status.reason());
+                return gateway.write(&mut writer, response, false, false).await;
+            }
+        };
+        let response = gateway.handle(&request, &mut reader, &mut writer).await;
+        let keep_alive = request.keep_alive() && !response.close;
+        tracing::info!(
+            "{peer} {} {} {}",
+            request.method,
+            request.path,
+            response.status.0
+        );
+        let head_only = request.method == "HEAD" || response.status == Status::NOT_MODIFIED;
+        gateway
+            .write(&mut writer, response, head_only, keep_alive)
+            .await?;
+        if !keep_alive {
+            break;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,741 @@
+//! The S3-compatible mode of the gateway: path-style ListObjectsV2, GetObject, HeadObject and
+//! PutObject over the operator, so S3 clients and SDKs can reach fs or HDFS roots.
+//!
+//! Requests are checked against one SigV4 key pair when one is configured; without one the
+//! bucket is anonymous and read-only. Multipart uploads, copies, deletes and the bucket
+//! configuration APIs answer `NotImplemented`.
+
+use base64::Engine;
+use base64::engine::general_purpose::{STANDARD as BASE64_ENGINE, URL_SAFE_NO_PAD};
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use md5::Md5;
+use opendal::{EntryMode, ErrorKind, Metadata};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWrite};
+
+use super::S3Options;
+use super::handler::{
+    Body, Gateway, Response, StagedUpload, constant_time_eq, content_type, send_continue, unread,
+};
+use super::http::{Request, Status, percent_encode};
+use super::sigv4::{self, Verified, hex};
+use crate::storage::constants::DEFAULT_CHUNK_SIZE;
+use crate::storage::escape_xml;
+
+const XMLNS: &str = "http://s3.amazonaws.com/doc/2006-03-01/";
+const MAX_KEYS: usize = 1000;
+/// Largest `aws-chunked` chunk accepted; SDKs send between 8 KiB and a few MiB.
+const MAX_CHUNK_SIZE: usize = 64 * 1024 * 1024;
+/// Query parameters that select an API this gateway does not implement.
+const UNSUPPORTED: &[&str] = &[
+    "acl",
+    "attributes",
+    "cors",
+    "delete",
+    "legal-hold",
+    "lifecycle",
+    "partNumber",
+    "policy",
+    "restore",
+    "retention",
+    "select",
+    "tagging",
+    "uploadId",
+    "uploads",
+    "versionId",
+    "versioning",
+    "versions",
+];
+
+impl Gateway {
+    /// Answer an S3 API request.
+    pub(super) async fn handle_s3<R, W>(
+        &self,
+        s3: &S3Options,
+        request: &Request,
+        body: &mut R,
+        writer: &mut W,
+    ) -> Response
+    where
+        R: AsyncBufRead + Unpin,
+        W: AsyncWrite + Unpin,
+    {
+        let resource = request.path.as_str();
+        let verified = match &s3.credentials {
+            Some(credentials) => match sigv4::verify(
+                request,
+                &credentials.access_key_id,
+                &credentials.secret_access_key,
+                Utc::now(),
+            ) {
+                Ok(verified) => Some(verified),
+                Err(e) => {
+                    let status = if e.code == "AuthorizationHeaderMalformed" {
+                        Status::BAD_REQUEST
+                    } else {
+                        Status::FORBIDDEN
+                    };
+                    return unread(request, s3_error(status, e.code, e.message, resource));
+                }
+            },
+            None => None,
+        };
+        let query = request.query_pairs();
+        if let Some((name, _)) = query
+            .iter()
+            .find(|(name, _)| UNSUPPORTED.contains(&name.as_str()))
+        {
+            let what = format!("the '{name}' subresource");
+            return unread(request, not_implemented(&what, resource));
+        }
+
+        let path = request.path.trim_start_matches('/');
+        let (bucket, key) = path.split_once('/').unwrap_or((path, ""));
+        let response = if bucket.is_empty() {
+            match request.method.as_str() {
+                "GET" => self.list_buckets(s3),
+                _ => method_not_allowed(resource),
+            }
+        } else if bucket != s3.bucket {
+            s3_error(
+                Status::NOT_FOUND,
+                "NoSuchBucket",
+                format!("this gateway serves bucket '{}'", s3.bucket),
+                resource,
+            )
+        } else if key.is_empty() {
+            match request.method.as_str() {
+                "HEAD" => Response::new(Status::OK).header("x-amz-bucket-region", "us-east-1"),
+                "GET" if query.iter().any(|(name, _)| name == "location") => xml_response(format!(
+                    "<LocationConstraint xmlns=\"{XMLNS}\"></LocationConstraint>"
+                )),
+                "GET" if request.query_param("list-type").as_deref() == Some("2") => {
+                    self.list_objects_v2(s3, request).await
+                }
+                "GET" => not_implemented("ListObjects (v1); use ListObjectsV2", resource),
+                _ => method_not_allowed(resource),
+            }
+        } else if let Some(message) = invalid_key(key) {
+            s3_error(Status::BAD_REQUEST, "InvalidArgument", message, resource)
+        } else {
+            let full_key = format!("{}{key}", self.prefix);
+            match request.method.as_str() {
+                "GET" | "HEAD" => self.get_object(request, &full_key).await,
+                "PUT" if request.header("x-amz-copy-source").is_some() => {
+                    not_implemented("CopyObject", resource)
+                }
+                "PUT" => match &verified {
+                    Some(verified) => {
+                        return self
+                            .put_object(request, &full_key, verified, body, writer)
+                            .await;
+                    }
+                    None => s3_error(
+                        Status::FORBIDDEN,
+                        "AccessDenied",
+                        "uploads need --access-key-id and --secret-access-key",
+                        resource,
+                    ),
+                },
+                "DELETE" => not_implemented("DeleteObject", resource),
+                _ => method_not_allowed(resource),
+            }
+        };
+        unread(request, response)
+    }
+
+    fn list_buckets(&self, s3: &S3Options) -> Response {
+        xml_response(format!(
+            "<ListAllMyBucketsResult xmlns=\"{XMLNS}\"><Owner><ID>storify</ID><DisplayName>storify</DisplayName></Owner><Buckets><Bucket><Name>{}</Name><CreationDate>{}</CreationDate></Bucket></Buckets></ListAllMyBucketsResult>",
+            escape_xml(&s3.bucket),
+            iso8601(self.started)
+        ))
+    }
+
+    async fn get_object(&self, request: &Request, key: &str) -> Response {
+        let resource = request.path.as_str();
+        match self.operator.stat(key).await {
+            Ok(meta) if meta.mode().is_file() => {
+                let response = self.file(request, key, &meta);
+                if response.status == Status::RANGE_NOT_SATISFIABLE {
+                    return s3_error(
+                        Status::RANGE_NOT_SATISFIABLE,
+                        "InvalidRange",
+                        "the requested range is not satisfiable",
+                        resource,
+                    );
+                }
+                response
+            }
+            Ok(_) => no_such_key(resource),
+            Err(e) => remote_error(&e, resource),
+        }
+    }
+
+    async fn list_objects_v2(&self, s3: &S3Options, request: &Request) -> Response {
+        let resource = request.path.as_str();
+        let invalid =
+            |message: &str| s3_error(Status::BAD_REQUEST, "InvalidArgument", message, resource);
+        let param = |name: &str| request.query_param(name).unwrap_or_default();
+        let (prefix, delimiter) = (param("prefix"), param("delimiter"));
+        let url_encoded = match param("encoding-type").as_str() {
+            "" => false,
+            "url" => true,
+            _ => return invalid("encoding-type must be 'url'"),
+        };
+        let max_keys = match request.query_param("max-keys") {
+            None => MAX_KEYS,
+            Some(value) => match value.parse::<usize>() {
+                Ok(max_keys) => max_keys.min(MAX_KEYS),
+                Err(_) => return invalid("max-keys must be a non-negative integer"),
+            },
+        };
+        let token = request.query_param("continuation-token");
+        let after = match &token {
+            Some(token) => match URL_SAFE_NO_PAD
+                .decode(token)
+                .ok()
+                .and_then(|key| String::from_utf8(key).ok())
+            {
+                Some(key) => key,
+                None => return invalid("the continuation token is invalid"),
+            },
+            None => param("start-after"),
+        };
+        if let Some(message) = invalid_key(&prefix) {
+            return invalid(message);
+        }
+
+        let listed = match self.list_keys(&prefix, &delimiter).await {
+            Ok(listed) => listed,
+            Err(e) => return remote_error(&e, resource),
+        };
+        let mut page: Vec<(String, Option<Metadata>)> = listed
+            .into_iter()
+            .filter(|(key, _)| *key > after)
+            .take(max_keys + 1)
+            .collect();
+        let truncated = max_keys > 0 && page.len() > max_keys;
+        page.truncate(max_keys);
+        // Listings of object stores carry sizes and times; others leave them to stat.
+        let page: Vec<(String, Option<Metadata>)> = futures::stream::iter(page)
+            .map(|(key, meta)| async move {
+                match meta {
+                    Some(meta) if meta.last_modified().is_none() => {
+                        let full_key = format!("{}{key}", self.prefix);
+                        let meta = self.operator.stat(&full_key).await.unwrap_or(meta);
+                        (key, Some(meta))
+                    }
+                    meta => (key, meta),
+                }
+            })
+            .buffered(16)
+            .collect()
+            .await;
+
+        let encode = |text: &str| {
+            escape_xml(&if url_encoded {
+                percent_encode(text, true)
+            } else {
+                text.to_string()
+            })
+        };
+        let mut xml = format!(
+            "<ListBucketResult xmlns=\"{XMLNS}\"><Name>{}</Name><Prefix>{}</Prefix>",
+            escape_xml(&s3.bucket),
+            encode(&prefix)
+        );
+        if !delimiter.is_empty() {
+            xml.push_str(&format!("<Delimiter>{}</Delimiter>", encode(&delimiter)));
+        }
+        xml.push_str(&format!(
+            "<MaxKeys>{max_keys}</MaxKeys><KeyCount>{}</KeyCount><IsTruncated>{truncated}</IsTruncated>",
+            page.len()
+        ));
+        if url_encoded {
+            xml.push_str("<EncodingType>url</EncodingType>");
+        }
+        match &token {
+            Some(token) => xml.push_str(&format!(
+                "<ContinuationToken>{}</ContinuationToken>",
+                escape_xml(token)
+            )),
+            None if !after.is_empty() => {
+                xml.push_str(&format!("<StartAfter>{}</StartAfter>", encode(&after)))
+            }
+            None => {}
+        }
+        if truncated && let Some((last, _)) = page.last() {
+            xml.push_str(&format!(
+                "<NextContinuationToken>{}</NextContinuationToken>",
+                URL_SAFE_NO_PAD.encode(last)
+            ));
+        }
+        for (key, meta) in &page {
+            match meta {
+                Some(meta) => {
+                    xml.push_str(&format!(
+                        "<Contents><Key>{}</Key><LastModified>{}</LastModified>",
+                        encode(key),
+                        iso8601(meta.last_modified().unwrap_or(DateTime::UNIX_EPOCH))
+                    ));
+                    if let Some(etag) = meta.etag() {
+                        xml.push_str(&format!("<ETag>{}</ETag>", escape_xml(&quoted(etag))));
+                    }
+                    xml.push_str(&format!(
+                        "<Size>{}</Size><StorageClass>STANDARD</StorageClass></Contents>",
+                        meta.content_length()
+                    ));
+                }
+                None => xml.push_str(&format!(
+                    "<CommonPrefixes><Prefix>{}</Prefix></CommonPrefixes>",
+                    encode(key)
+                )),
+            }
+        }
+        xml.push_str("</ListBucketResult>");
+        xml_response(xml)
+    }
+
+    /// Keys (with metadata) and common prefixes (without) below `prefix`, in key order.
+    async fn list_keys(
+        &self,
+        prefix: &str,
+        delimiter: &str,
+    ) -> opendal::Result<BTreeMap<String, Option<Metadata>>> {
+        let dir = &prefix[..prefix.rfind('/').map_or(0, |index| index + 1)];
+        // With `/` as the delimiter one level is enough; anything else needs the whole tree.
+        let entries = match self
+            .operator
+            .list_with(&format!("{}{dir}", self.prefix))
+            .recursive(delimiter != "/")
+            .await
+        {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e),
+        };
+        let mut listed = BTreeMap::new();
+        for entry in entries {
+            let Some(key) = entry.path().strip_prefix(self.prefix.as_str()) else {
+                continue;
+            };
+            let Some(rest) = key.strip_prefix(prefix) else {
+                continue;
+            };
+            let is_dir = entry.metadata().mode() == EntryMode::DIR;
+            if let Some(index) = rest.find(delimiter).filter(|_| !delimiter.is_empty()) {
+                let common = format!("{prefix}{}", &rest[..index + delimiter.len()]);
+                listed.insert(common, None);
+            } else if !is_dir {
+                listed.insert(key.to_string(), Some(entry.metadata().clone()));
+            }
+        }
+        Ok(listed)
+    }
+
+    async fn put_object<R, W>(
+        &self,
+        request: &Request,
+        key: &str,
+        verified: &Verified,
+        body: &mut R,
+        writer: &mut W,
+    ) -> Response
+    where
+        R: AsyncBufRead + Unpin,
+        W: AsyncWrite + Unpin,
+    {
+        let resource = request.path.as_str();
+        let rejected = |mut response: Response| {
+            response.close = true;
+            response
+        };
+        let length = match request.content_length() {
+            Ok(Some(length)) => length,
+            _ => {
+                return rejected(s3_error(
+                    Status::LENGTH_REQUIRED,
+                    "MissingContentLength",
+                    "uploads need a Content-Length",
+                    resource,
+                ));
+            }
+        };
+        let chunked = verified.payload_hash.starts_with("STREAMING-")
+            || request
+                .header("content-encoding")
+                .is_some_and(|encoding| encoding.contains("aws-chunked"));
+        let expected_size = if chunked {
+            match request
+                .header("x-amz-decoded-content-length")
+                .and_then(|value| value.trim().parse::<u64>().ok())
+            {
+                Some(size) => size,
+                None => {
+                    return rejected(s3_error(
+                        Status::LENGTH_REQUIRED,
+                        "MissingContentLength",
+                        "aws-chunked uploads need x-amz-decoded-content-length",
+                        resource,
+                    ));
+                }
+            }
+        } else {
+            length
+        };
+        if key.ends_with('/') {
+            // Folder markers become directories; the (empty) body is left unread.
+            if expected_size != 0 {
+                return rejected(s3_error(
+                    Status::BAD_REQUEST,
+                    "InvalidArgument",
+                    "keys ending with '/' must be empty",
+                    resource,
+                ));
+            }
+            return rejected(match self.operator.create_dir(key).await {
+                Ok(()) => Response::new(Status::OK).header("ETag", quoted(&hex(&Md5::digest(b"")))),
+                Err(e) => remote_error(&e, resource),
+            });
+        }
+        if let Err(e) = send_continue(request, writer).await {
+            return rejected(s3_error(
+                Status::BAD_REQUEST,
+                "IncompleteBody",
+                e.to_string(),
+                resource,
+            ));
+        }
+
+        let mut staged = match StagedUpload::new().await {
+            Ok(staged) => staged,
+            Err(e) => return rejected(local_error(&e, resource)),
+        };
+        let mut limited = (&mut *body).take(length);
+        let signer = verified
+            .payload_hash
+            .starts_with("STREAMING-AWS4-HMAC-SHA256-PAYLOAD")
+            .then_some(verified);
+        let copied = if chunked {
+            copy_chunked(&mut limited, &mut staged, signer).await
+        } else {
+            copy_plain(&mut limited, &mut staged).await
+        };
+        let checked = copied.and_then(|digests| {
+            check_payload(
+                &digests,
+                expected_size,
+                verified,
+                request.header("content-md5"),
+            )
+            .map(|()| digests)
+        });
+        let digests = match checked {
+            Ok(digests) => digests,
+            Err(PutError::Client(status, code, message)) => {
+                return rejected(s3_error(status, code, message, resource));
+            }
+            Err(PutError::Local(e)) => return rejected(local_error(&e, resource)),
+        };
+        let content_type = request
+            .header("content-type")
+            .map(str::to_string)
+            .unwrap_or_else(|| content_type(key).to_string());
+        if let Err(e) = self.store(staged, key, &content_type).await {
+            return rejected(remote_error(&e, resource));
+        }
+        tracing::info!("stored {} bytes at {key}", digests.size);
+        // The body is consumed unless the client sent more than Content-Length announced.
+        Response::new(Status::OK).header("ETag", quoted(&hex(&digests.md5)))
+    }
+}
+
+/// What was uploaded, for the integrity checks and the `ETag`.
+struct Digests {
+    md5: Vec<u8>,
+    sha256: Vec<u8>,
+    size: u64,
+}
+
+#[derive(Default)]
+struct Hashers {
+    md5: Md5,
+    sha256: Sha256,
+    size: u64,
+}
+
+impl Hashers {
+    fn update(&mut self, data: &[u8]) {
+        self.md5.update(data);
+        self.sha256.update(data);
+        self.size += data.len() as u64;
+    }
+
+    fn finish(self) -> Digests {
+        Digests {
+            md5: self.md5.finalize().to_vec(),
+            sha256: self.sha256.finalize().to_vec(),
+            size: self.size,
+        }
+    }
+}
+
+enum PutError {
+    Client(Status, &'static str, String),
+    /// Staging the body in a scratch file failed
+    Local(std::io::Error),
+}
+
+impl PutError {
+    fn incomplete(message: impl Into<String>) -> Self {
+        PutError::Client(Status::BAD_REQUEST, "IncompleteBody", message.into())
+    }
+}
+
+async fn copy_plain<R: AsyncBufRead + Unpin>(
+    body: &mut R,
+    staged: &mut StagedUpload,
+) -> Result<Digests, PutError> {
+    let mut hashers = Hashers::default();
+    let mut buffer = vec![0u8; DEFAULT_CHUNK_SIZE];
+    loop {
+        let read = body
+            .read(&mut buffer)
+            .await
+            .map_err(|e| PutError::incomplete(e.to_string()))?;
+        if read == 0 {
+            return Ok(hashers.finish());
+        }
+        hashers.update(&buffer[..read]);
+        staged
+            .write(&buffer[..read])
+            .await
+            .map_err(PutError::Local)?;
+    }
+}
+
+/// Decode an `aws-chunked` body, checking the chunk signatures when the upload is signed
+/// per chunk. Trailing checksums are read but not verified.
+async fn copy_chunked<R: AsyncBufRead + Unpin>(
+    body: &mut R,
+    staged: &mut StagedUpload,
+    signer: Option<&Verified>,
+) -> Result<Digests, PutError> {
+    let mut hashers = Hashers::default();
+    let mut previous = signer.map(|verified| verified.signature.clone());
+    loop {
+        let line = read_line(body).await?;
+        let (size, extensions) = line.split_once(';').unwrap_or((&line, ""));
+        let size = usize::from_str_radix(size.trim(), 16)
+            .ok()
+            .filter(|size| *size <= MAX_CHUNK_SIZE)
+            .ok_or_else(|| PutError::incomplete("malformed aws-chunked chunk header"))?;
+        let mut chunk = vec![0u8; size];
+        body.read_exact(&mut chunk)
+            .await
+            .map_err(|_| PutError::incomplete("the body ended inside a chunk"))?;
+        if let (Some(verified), Some(previous)) = (signer, previous.as_mut()) {
+            let expected = verified.chunk_signature(previous, &chunk);
+            let presented = extensions
+                .split(';')
+                .find_map(|extension| extension.trim().strip_prefix("chunk-signature="))
+                .unwrap_or_default();
+            if !constant_time_eq(expected.as_bytes(), presented.as_bytes()) {
+                return Err(PutError::Client(
+                    Status::FORBIDDEN,
+                    "SignatureDoesNotMatch",
+                    "a chunk signature does not match".to_string(),
+                ));
+            }
+            *previous = expected;
+        }
+        if size == 0 {
+            // Trailing headers, if any, up to the closing empty line.
+            while !read_line(body).await?.is_empty() {}
+            return Ok(hashers.finish());
+        }
+        if !read_line(body).await?.is_empty() {
+            return Err(PutError::incomplete("chunk data is longer than its size"));
+        }
+        hashers.update(&chunk);
+        staged.write(&chunk).await.map_err(PutError::Local)?;
+    }
+}
+
+/// One CRLF-terminated line of a chunked body, without the line ending.
+async fn read_line<R: AsyncBufRead + Unpin>(body: &mut R) -> Result<String, PutError> {
+    let mut line = Vec::new();
+    (&mut *body)
+        .take(4096)
+        .read_until(b'\n', &mut line)
+        .await
+        .map_err(|e| PutError::incomplete(e.to_string()))?;
+    if !line.ends_with(b"\n") {
+        return Err(PutError::incomplete("the body ended inside a chunk header"));
+    }
+    String::from_utf8(line)
+        .map(|line| line.trim_end_matches(['\r', '\n']).to_string())
+        .map_err(|_| PutError::incomplete("chunk headers must be UTF-8"))
+}
+
+fn check_payload(
+    digests: &Digests,
+    expected_size: u64,
+    verified: &Verified,
+    content_md5: Option<&str>,
+) -> Result<(), PutError> {
+    if digests.size != expected_size {
+        return Err(PutError::incomplete(format!(
+            "received {} of {expected_size} bytes",
+            digests.size
+        )));
+    }
+    let payload_hash = verified.payload_hash.to_ascii_lowercase();
+    if payload_hash.len() == 64 && payload_hash != hex(&digests.sha256) {
+        return Err(PutError::Client(
+            Status::BAD_REQUEST,
+            "XAmzContentSHA256Mismatch",
+            "the body does not match x-amz-content-sha256".to_string(),
+        ));
+    }
+    if let Some(content_md5) = content_md5 {
+        let Ok(expected) = BASE64_ENGINE.decode(content_md5.trim()) else {
+            return Err(PutError::Client(
+                Status::BAD_REQUEST,
+                "InvalidDigest",
+                "Content-MD5 is not valid base64".to_string(),
+            ));
+        };
+        if expected != digests.md5 {
+            return Err(PutError::Client(
+                Status::BAD_REQUEST,
+                "BadDigest",
+                "the body does not match Content-MD5".to_string(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Why `key` cannot be mapped onto a path, if it cannot.
+fn invalid_key(key: &str) -> Option<&'static str> {
+    let segments: Vec<&str> = key.split('/').collect();
+    let (last, parents) = segments.split_last()?;
+    let dot = |segment: &str| segment == "." || segment == "..";
+    if dot(last)
+        || parents
+            .iter()
+            .any(|segment| segment.is_empty() || dot(segment))
+    {
+        return Some("keys with empty, '.' or '..' segments are not supported");
+    }
+    None
+}
+
+fn quoted(etag: &str) -> String {
+    if etag.starts_with('"') {
+        etag.to_string()
+    } else {
+        format!("\"{etag}\"")
+    }
+}
+
+fn iso8601(time: DateTime<Utc>) -> String {
+    time.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string()
+}
+
+fn xml_response(body: String) -> Response {
+    Response::new(Status::OK)
+        .header("Content-Type", "application/xml")
+        .with_body(Body::Bytes(
+            format!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n{body}").into_bytes(),
+        ))
+}
+
+fn s3_error(status: Status, code: &str, message: impl AsRef<str>, resource: &str) -> Response {
+    let body = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<Error><Code>{code}</Code><Message>{}</Message><Resource>{}</Resource></Error>",
+        escape_xml(message.as_ref()),
+        escape_xml(resource)
+    );
+    Response::new(status)
+        .header("Content-Type", "application/xml")
+        .with_body(Body::Bytes(body.into_bytes()))
+}
+
+fn no_such_key(resource: &str) -> Response {
+    s3_error(
+        Status::NOT_FOUND,
+        "NoSuchKey",
+        "the specified key does not exist",
+        resource,
+    )
+}
+
+fn not_implemented(what: &str, resource: &str) -> Response {
+    s3_error(
+        Status::NOT_IMPLEMENTED,
+        "NotImplemented",
+        format!("{what} is not supported by the storify gateway"),
+        resource,
+    )
+}
+
+fn method_not_allowed(resource: &str) -> Response {
+    s3_error(
+        Status::METHOD_NOT_ALLOWED,
+        "MethodNotAllowed",
+        "the method is not allowed against this resource",
+        resource,
+    )
+}
+
+fn local_error(err: &std::io::Error, resource: &str) -> Response {
+    tracing::warn!("staging an upload failed: {err}");
+    s3_error(
+        Status::INTERNAL_SERVER_ERROR,
+        "InternalError",
+        "staging the upload failed",
+        resource,
+    )
+}
+
+fn remote_error(err: &opendal::Error, resource: &str) -> Response {
+    match err.kind() {
+        ErrorKind::NotFound => no_such_key(resource),
+        ErrorKind::PermissionDenied => s3_error(
+            Status::FORBIDDEN,
+            "AccessDenied",
+            "the backend denied access",
+            resource,
+        ),
+        _ => {
+            tracing::warn!("remote request failed: {err}");
+            s3_error(
+                Status::INTERNAL_SERVER_ERROR,
+                "InternalError",
+                "the backend request failed",
+                resource,
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_that_would_leave_the_root_are_refused() {
+        assert_eq!(invalid_key("a/b.txt"), None);
+        assert_eq!(invalid_key("folder/"), None);
+        assert_eq!(invalid_key(""), None);
+        assert!(invalid_key("../etc/passwd").is_some());
+        assert!(invalid_key("a/./b").is_some());
+        assert!(invalid_key("a//b").is_some());
+        assert!(invalid_key("a/..").is_some());
+    }
+}
@@ -1,33 +1,123 @@
-use crate::config::{ProviderBackend, prepare_storage_backend};
+use crate::apply::{JobSpec, JobStep};
+use crate::config::{
+    CompatPreset, ProviderBackend, ServerSideEncryption, SseAlgorithm,
+    aws_profile::load_aws_profile, crypto::DataKey, prepare_storage_backend,
+};
 pub use crate::config::{StorageProvider, storage_config::StorageConfig};
 use crate::error::{Error, Result};
+use crate::jobs::CancelToken;
+use crate::journal::{Change, JournalEntry};
 use opendal::Operator;
+use opendal::layers::HttpClientLayer;
+use opendal::layers::observe::MetricsLayer;
+use opendal::raw::HttpClient;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
 
+pub mod connection;
 pub mod constants;
+mod failover;
+#[cfg(feature = "http")]
+mod http_index;
+mod limiter;
+pub mod metrics;
+#[cfg(feature = "fuse")]
+mod mount;
 mod operations;
+pub mod output;
+pub mod recording;
+mod rest;
+pub mod retry;
+mod sts;
+mod timing;
+mod trace;
 mod utils;
+pub use self::limiter::{ConcurrencyLimit, RateLimit};
+#[cfg(feature = "fuse")]
+pub use self::mount::MountOptions;
+pub use self::operations::chmod::parse_mode;
+#[cfg(feature = "hdfs")]
+pub use self::operations::hdfs::parse_owner;
+pub use self::operations::tagging::{MAX_TAG_KEY_LEN, MAX_TAG_VALUE_LEN};
+pub use self::operations::trash::batch_name as trash_batch_name;
+pub use self::operations::{
+    ByteRange, CannedAcl, CatOptions, Comparison, CorsConfig, DiffSource, DirDiffOptions,
+    DownloadOptions, EmptyTrashOptions, FindAction, FollowOptions, GcOptions, GrepOptions,
+    HealthReport, InventoryFormat, LatestBy, LifecycleConfig, ListOptions, ListSort, MetadataMode,
+    ObjectMeta, Replacement, ReportOutput, SymlinkPolicy, SyncPlan, TreeFormat, TreeOptions,
+    UploadOptions, UsageGroup, UsageLine, UsageOptions, UsageSort, VerifySummary, local_files,
+};
+pub use self::rest::escape_xml;
+pub use self::timing::RequestTimer;
 pub use self::utils::OutputFormat;
+pub use self::utils::clobber::Clobber;
+pub use self::utils::codec::Compression;
+pub use self::utils::filter::PathFilter;
+pub use self::utils::format::{EntryFields, Template};
+pub use self::utils::paging::PageSize;
+pub use self::utils::path::{is_remote_directory, normalize_under_root, transfer_target};
+pub use self::utils::report::{MultiResult, TransferReport};
+pub use self::utils::size::{format_size, parse_size};
 
+use self::connection::ConnectionSettings;
+#[cfg(feature = "http")]
+use self::http_index::HttpIndexLayer;
+use self::limiter::{ConcurrencyLimitLayer, RateLimitLayer};
+use self::metrics::{RequestCountLayer, TransferCounters};
+use self::operations::acl::RestAclManager;
+use self::operations::append::{OpenDalAppender, concat_sources};
+use self::operations::apply::OpenDalApplier;
+use self::operations::bucket_config::{BucketSetting, RestBucketConfigurer};
 use self::operations::cat::OpenDalFileReader;
+use self::operations::chmod::LocalModeChanger;
+use self::operations::compose::{GcsBucket, OpenDalComposer, ServerCompose};
 use self::operations::copy::OpenDalCopier;
 use self::operations::delete::OpenDalDeleter;
-use self::operations::diff::OpenDalDiffer;
+use self::operations::diff::{OpenDalDiffer, OpenDalDirDiffer};
 use self::operations::download::OpenDalDownloader;
 use self::operations::find::OpenDalFinder;
-use self::operations::grep::OpenDalGreper;
+use self::operations::gc::OpenDalGarbageCollector;
+use self::operations::grep::{Matcher, OpenDalGreper};
+use self::operations::hdfs::WebHdfsExtension;
 use self::operations::head::OpenDalHeadReader;
+use self::operations::health::{Diagnosis, OpenDalHealthChecker};
+use self::operations::index::OpenDalIndexer;
+use self::operations::inventory::OpenDalInventory;
+use self::operations::latest::OpenDalLatestResolver;
+use self::operations::lifecycle::RestLifecycleManager;
+use self::operations::link::OpenDalLinker;
 use self::operations::list::OpenDalLister;
 use self::operations::mkdir::OpenDalMkdirer;
+use self::operations::multipart::RestMultipartManager;
 use self::operations::mv::OpenDalMover;
+use self::operations::preserve;
+use self::operations::rename::OpenDalRenamer;
+use self::operations::storage_class::{RestClassChanger, provider_class};
+use self::operations::tagging::RestTagger;
 use self::operations::tail::OpenDalTailReader;
-use self::operations::touch::OpenDalToucher;
+use self::operations::touch::{OpenDalToucher, set_local_mtime};
+use self::operations::trash::OpenDalTrasher;
 use self::operations::tree::OpenDalTreer;
+use self::operations::undo::OpenDalUndoer;
 use self::operations::upload::OpenDalUploader;
 use self::operations::usage::OpenDalUsageCalculator;
+use self::operations::verify::OpenDalVerifier;
+use self::operations::versions::OpenDalVersioner;
 use self::operations::{
-    Cater, Copier, Deleter, Differ, Downloader, Greper, Header, Lister, Mkdirer, Mover, Stater,
-    Tailer, Toucher, Treer, Uploader, UsageCalculator,
+    AclManager, Appender, Applier, BucketConfigurer, Cater, ClassChanger, Composer, Copier,
+    Deleter, Differ, DirDiffer, Downloader, GarbageCollector, Greper, Header, HealthChecker,
+    Indexer, InventoryExporter, LatestResolver, LifecycleManager, Linker, Lister, Mkdirer,
+    ModeChanger, Mover, MultipartManager, ProviderExtension, Renamer, Stater, Tagger, Tailer,
+    Toucher, Trasher, Treer, Undoer, Uploader, UsageCalculator, Verifier, Versioner,
 };
+use self::output::StatOutput;
+use self::rest::RestBucket;
+use self::retry::RetryPolicy;
+use self::sts::SecurityTokenLayer;
+use self::timing::TimingLayer;
+use self::trace::{TraceLayer, trace_http_client};
+use self::utils::paging::{list_entries_after, report_continuation};
+use self::utils::path::get_root_relative_path;
 use crate::storage::utils::error::IntoStorifyError;
 use crate::wrap_err;
 use futures::stream::{StreamExt, TryStreamExt};
@@ -37,14 +127,214 @@ use futures::stream::{StreamExt, TryStreamExt};
 pub struct StorageClient {
     operator: Operator,
     provider: StorageProvider,
+    backend: ProviderBackend,
+    /// Off while recording or replaying, since REST lookups bypass the operator's client
+    rest_lookups: bool,
+    limit: Option<ConcurrencyLimit>,
+    /// Kept to pace replacement HTTP clients as well
+    rate_limit: Option<RateLimit>,
+    retry: RetryPolicy,
+    connection: ConnectionSettings,
+    /// Kept to count the requests of replacement HTTP clients as well
+    requests: RequestCountLayer,
+    page_size: PageSize,
+    /// Key for client-side encryption, validated when the config was prepared
+    data_key: Option<String>,
+    /// Cancellation of the job this client works for; checked by the transfer loops
+    cancel: CancelToken,
 }
 
 impl StorageClient {
-    pub async fn new(mut config: StorageConfig) -> Result<Self> {
+    pub async fn new(config: StorageConfig) -> Result<Self> {
+        Self::with_counters(config, TransferCounters::default()).await
+    }
+
+    /// Build a client that reports transferred bytes, requests, retries and failed
+    /// operations into `counters`.
+    ///
+    /// Requests are retried, paced and timed out as the config's `retries`,
+    /// `retry_backoff_ms`, `rate_limit`, `timeout_secs`, `connect_timeout_secs` and
+    /// `pool_size` say; the metrics only count operations that failed after their retries.
+    pub async fn with_counters(
+        mut config: StorageConfig,
+        counters: TransferCounters,
+    ) -> Result<Self> {
+        let provider = config.provider;
+        let retry = RetryPolicy::from_config(&config);
+        let rate_limit = config.rate_limit.map(RateLimit::new);
+        let connection = ConnectionSettings::from_config(&config);
+        let data_key = config.data_key.clone();
+        let mut backend = prepare_storage_backend(&mut config)?;
+        // Without credential discovery OpenDAL cannot read a region from the AWS config;
+        // ask the bucket, as the AWS CLI does for `--no-sign-request`.
+        if let ProviderBackend::S3 {
+            bucket,
+            region: region @ None,
+            endpoint,
+            unsigned: true,
+            ..
+        } = &mut backend
+        {
+            let endpoint = endpoint
+                .as_deref()
+                .unwrap_or(constants::DEFAULT_S3_ENDPOINT);
+            let detected = opendal::services::S3::detect_region(endpoint, bucket).await;
+            tracing::debug!("detected region {detected:?} for unsigned bucket {bucket}");
+            *region = Some(detected.unwrap_or_else(|| constants::DEFAULT_S3_REGION.to_string()));
+        }
+        let mut operator = if config.fallback_endpoints.is_empty() {
+            Self::build_operator(provider, &backend)?
+        } else {
+            Self::build_failover_operator(config).await?
+        };
+        if let Some(client) = connection.http_client()? {
+            operator = operator.layer(HttpClientLayer::new(client));
+        }
+        // After any client replacement, which would drop the re-signing.
+        if let Some(layer) = SecurityTokenLayer::for_backend(&backend) {
+            operator = operator.layer(layer);
+        }
+        // Inside the retries, so a timed out attempt is retried like any transient error.
+        if let Some(layer) = connection.timeout_layer() {
+            operator = operator.layer(layer);
+        }
+        if let Some(layer) = retry.layer(&counters) {
+            operator = operator.layer(layer);
+        }
+        if let Some(rate_limit) = &rate_limit {
+            operator = operator.layer(RateLimitLayer::new(rate_limit.clone()));
+        }
+        // Outside the retries, so every attempt is logged in the span of its operation.
+        let requests = RequestCountLayer::new(counters.clone());
+        let operator = operator
+            .layer(requests.clone())
+            .layer(MetricsLayer::new(counters.clone()))
+            .layer(TraceLayer);
+        Ok(Self {
+            operator,
+            provider,
+            backend,
+            rest_lookups: true,
+            limit: None,
+            rate_limit,
+            retry,
+            connection,
+            requests,
+            page_size: PageSize::Auto,
+            data_key,
+            cancel: CancelToken::default(),
+        })
+    }
+
+    /// Send the operator's HTTP requests through `client`, e.g. to record or replay them.
+    pub fn with_http_client(mut self, client: HttpClient) -> Self {
+        self.operator = self.operator.layer(HttpClientLayer::new(trace_http_client(
+            self.requests.http_client(client),
+        )));
+        if let Some(layer) = SecurityTokenLayer::for_backend(&self.backend) {
+            self.operator = self.operator.layer(layer);
+        }
+        if let Some(rate_limit) = &self.rate_limit {
+            self.operator = self.operator.layer(RateLimitLayer::new(rate_limit.clone()));
+        }
+        self.rest_lookups = false;
+        self
+    }
+
+    /// Log the latency of every HTTP request into `timer`. Install it before
+    /// [`Self::with_concurrency_limit`] so waiting for a permit is not counted.
+    pub fn with_request_timer(mut self, timer: RequestTimer) -> Self {
+        self.operator = self.operator.layer(TimingLayer::new(timer));
+        self
+    }
+
+    /// Share `limit` with the other clients of this invocation. Install it after
+    /// [`Self::with_http_client`] so recorded or replayed requests are limited too.
+    pub fn with_concurrency_limit(mut self, limit: ConcurrencyLimit) -> Self {
+        self.operator = self
+            .operator
+            .layer(ConcurrencyLimitLayer::new(limit.clone()));
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Stop uploads, downloads, copies and moves once `token` is cancelled.
+    pub fn with_cancel_token(mut self, token: CancelToken) -> Self {
+        self.cancel = token;
+        self
+    }
+
+    /// Request `page_size` entries per listing page (`ls`, `du`, `find`, `index`).
+    pub fn with_page_size(mut self, page_size: PageSize) -> Self {
+        self.page_size = page_size;
+        self
+    }
+
+    /// `--start-after` skips every key sorting before it, which is only a continuation on
+    /// providers that list in key order.
+    fn ensure_resumable(&self, start_after: Option<&str>) -> Result<()> {
+        let ordered = !matches!(
+            self.provider,
+            StorageProvider::Fs | StorageProvider::Hdfs | StorageProvider::Http
+        );
+        if start_after.is_some() && !ordered {
+            return Err(Error::InvalidArgument {
+                message: format!(
+                    "--start-after is not supported for {}: it does not list keys in order",
+                    self.provider.as_str()
+                ),
+            });
+        }
+        Ok(())
+    }
+
+    /// Width of a task pipeline that asked for `requested` concurrent tasks.
+    pub(crate) fn concurrency(&self, requested: usize) -> usize {
+        self.limit
+            .as_ref()
+            .map_or(requested, |limit| limit.cap(requested))
+    }
+
+    /// Build an operator against the first endpoint that is reachable.
+    ///
+    /// Only connection failures move on to the next endpoint; auth or bucket errors mean the
+    /// endpoint answered and are left for the actual command to report.
+    async fn build_failover_operator(config: StorageConfig) -> Result<Operator> {
         let provider = config.provider;
-        let backend = prepare_storage_backend(&mut config)?;
-        let operator = Self::build_operator(provider, &backend)?;
-        Ok(Self { operator, provider })
+        let candidates = failover::candidates(&config);
+        let build = |endpoint: Option<String>| -> Result<Operator> {
+            let mut attempt = config.clone();
+            attempt.endpoint = endpoint;
+            let backend = prepare_storage_backend(&mut attempt)?;
+            Self::build_operator(provider, &backend)
+        };
+
+        if let Some(endpoint) = failover::remembered(&candidates) {
+            return build(endpoint);
+        }
+        for endpoint in &candidates {
+            let operator = build(endpoint.clone())?;
+            let report = OpenDalHealthChecker::new(operator.clone())
+                .health_check()
+                .await;
+            if report.diagnosis == Some(Diagnosis::EndpointUnreachable) {
+                tracing::warn!(
+                    "endpoint {} unreachable, trying next",
+                    failover::label(endpoint)
+                );
+                continue;
+            }
+            tracing::debug!("using endpoint {}", failover::label(endpoint));
+            failover::remember(&candidates, endpoint.clone());
+            return Ok(operator);
+        }
+        Err(Error::EndpointsUnreachable {
+            endpoints: candidates
+                .iter()
+                .map(|endpoint| failover::label(endpoint).to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+        })
     }
 
     #[cfg(test)]
@@ -59,6 +349,31 @@ impl StorageClient {
         &self.operator
     }
 
+    fn apply_compat_preset(
+        mut builder: opendal::services::S3,
+        preset: CompatPreset,
+    ) -> opendal::services::S3 {
+        if preset.virtual_host_style() {
+            builder = builder.enable_virtual_host_style();
+        }
+        if !preset.supports_stat_with_override() {
+            builder = builder.disable_stat_with_override();
+        }
+        if !preset.supports_write_with_if_match() {
+            builder = builder.disable_write_with_if_match();
+        }
+        if !preset.supports_list_objects_v2() {
+            builder = builder.disable_list_objects_v2();
+        }
+        if let Some(algorithm) = preset.checksum_algorithm() {
+            builder = builder.checksum_algorithm(algorithm);
+        }
+        if let Some(limit) = preset.delete_batch_limit() {
+            builder = builder.delete_max_size(limit);
+        }
+        builder
+    }
+
     #[allow(unused_variables)]
     fn build_operator(provider: StorageProvider, backend: &ProviderBackend) -> Result<Operator> {
         match backend {
@@ -66,10 +381,14 @@ impl StorageClient {
                 bucket,
                 access_key,
                 secret_key,
+                security_token,
                 endpoint,
                 anonymous,
+                encryption,
             } => {
-                let mut builder = opendal::services::Oss::default().bucket(bucket);
+                let mut builder = opendal::services::Oss::default()
+                    .bucket(bucket)
+                    .enable_versioning(true);
                 if *anonymous {
                     builder = builder.allow_anonymous();
                 }
@@ -82,17 +401,34 @@ impl StorageClient {
                 if let Some(endpoint) = endpoint.as_deref() {
                     builder = builder.endpoint(endpoint);
                 }
+                if let Some(encryption) = encryption {
+                    builder = builder.server_side_encryption(encryption.header_value(provider));
+                    if let Some(key_id) = encryption.kms_key_id.as_deref() {
+                        builder = builder.server_side_encryption_key_id(key_id);
+                    }
+                }
                 Ok(Operator::new(builder)?.finish())
             }
             ProviderBackend::S3 {
                 bucket,
                 access_key,
                 secret_key,
+                session_token,
                 region,
                 endpoint,
                 anonymous,
+                unsigned,
+                role_arn,
+                aws_profile,
+                use_instance_metadata,
+                compat,
+                storage_class,
+                encryption,
+                request_payer,
             } => {
-                let mut builder = opendal::services::S3::default().bucket(bucket);
+                let mut builder = opendal::services::S3::default()
+                    .bucket(bucket)
+                    .enable_versioning(compat.is_none_or(|preset| preset.supports_versioning()));
                 if *anonymous {
                     builder = builder.allow_anonymous();
                 }
@@ -102,12 +438,75 @@ impl StorageClient {
                 if let Some(secret_access_key) = secret_key.as_deref() {
                     builder = builder.secret_access_key(secret_access_key);
                 }
+                if let Some(session_token) = session_token.as_deref() {
+                    builder = builder.session_token(session_token);
+                }
                 if let Some(region) = region.as_deref() {
                     builder = builder.region(region);
                 }
                 if let Some(endpoint) = endpoint.as_deref() {
                     builder = builder.endpoint(endpoint);
                 }
+                if let Some(profile_name) = aws_profile.as_deref() {
+                    let profile = load_aws_profile(profile_name)?;
+                    builder = builder
+                        .access_key_id(&profile.access_key_id)
+                        .secret_access_key(&profile.secret_access_key)
+                        .disable_ec2_metadata();
+                    if let Some(session_token) = profile.session_token.as_deref() {
+                        builder = builder.session_token(session_token);
+                    }
+                    if region.is_none()
+                        && let Some(profile_region) = profile.region.as_deref()
+                    {
+                        builder = builder.region(profile_region);
+                    }
+                }
+                if *use_instance_metadata {
+                    // Skip env/profile discovery so credentials come from IMDS only.
+                    builder = builder.disable_config_load();
+                }
+                if *unsigned {
+                    builder = builder.disable_config_load().disable_ec2_metadata();
+                }
+                if let Some(role_arn) = role_arn.as_deref() {
+                    builder = builder.role_arn(role_arn);
+                }
+                if let Some(preset) = compat {
+                    builder = Self::apply_compat_preset(builder, *preset);
+                }
+                if let Some(class) = storage_class.as_deref() {
+                    builder = builder.default_storage_class(&provider_class(provider, class));
+                }
+                if *request_payer {
+                    builder = builder.enable_request_payer();
+                }
+                builder = match encryption {
+                    Some(ServerSideEncryption {
+                        algorithm: SseAlgorithm::Aes256,
+                        ..
+                    }) => builder.server_side_encryption_with_s3_key(),
+                    Some(ServerSideEncryption {
+                        algorithm: SseAlgorithm::Kms,
+                        kms_key_id: Some(key_id),
+                    }) => builder.server_side_encryption_with_customer_managed_kms_key(key_id),
+                    Some(ServerSideEncryption {
+                        algorithm: SseAlgorithm::Kms,
+                        kms_key_id: None,
+                    }) => builder.server_side_encryption_with_aws_managed_kms_key(),
+                    None => builder,
+                };
+                tracing::debug!(
+                    "S3 builder config: bucket={}, region={:?}, role_arn={:?}, aws_profile={:?}, instance_metadata={}, compat={:?}, storage_class={:?}, encryption={:?}",
+                    bucket,
+                    region,
+                    role_arn,
+                    aws_profile,
+                    use_instance_metadata,
+                    compat,
+                    storage_class,
+                    encryption,
+                );
                 Ok(Operator::new(builder)?.finish())
             }
             ProviderBackend::Cos {
@@ -121,7 +520,7 @@ impl StorageClient {
                 if let Some(endpoint) = endpoint.as_deref() {
                     builder = builder.endpoint(endpoint);
                 }
-                log::debug!(
+                tracing::debug!(
                     "COS builder config: bucket={}, endpoint={:?}",
                     bucket,
                     endpoint,
@@ -153,60 +552,197 @@ impl StorageClient {
                 container,
                 account_name,
                 account_key,
+                sas_token,
+                connection_string,
                 endpoint,
             } => {
-                let mut builder = opendal::services::Azblob::default().container(container);
+                let mut builder = match connection_string.as_deref() {
+                    Some(conn) => opendal::services::Azblob::from_connection_string(conn)?,
+                    None => opendal::services::Azblob::default(),
+                }
+                .container(container);
                 if let Some(account_name) = account_name.as_deref() {
                     builder = builder.account_name(account_name);
                 }
                 if let Some(account_key) = account_key.as_deref() {
                     builder = builder.account_key(account_key);
                 }
+                if let Some(sas_token) = sas_token.as_deref() {
+                    builder = builder.sas_token(sas_token);
+                }
+                if let Some(endpoint) = endpoint.as_deref() {
+                    builder = builder.endpoint(endpoint);
+                }
+                Ok(Operator::new(builder)?.finish())
+            }
+            ProviderBackend::Gcs {
+                bucket,
+                credential_path,
+                endpoint,
+                anonymous,
+            } => {
+                let mut builder = opendal::services::Gcs::default().bucket(bucket);
+                if *anonymous {
+                    builder = builder
+                        .allow_anonymous()
+                        .disable_config_load()
+                        .disable_vm_metadata();
+                }
+                if let Some(credential_path) = credential_path.as_deref() {
+                    builder = builder.credential_path(credential_path);
+                }
                 if let Some(endpoint) = endpoint.as_deref() {
                     builder = builder.endpoint(endpoint);
                 }
                 Ok(Operator::new(builder)?.finish())
             }
+            ProviderBackend::Memory { name } => {
+                #[cfg(feature = "memory")]
+                {
+                    use std::collections::HashMap;
+                    use std::sync::{LazyLock, Mutex, PoisonError};
+
+                    // Every client of the process sees the same store per name, so
+                    // multi-step pipelines (apply jobs, cross-bucket copies) can be rehearsed.
+                    static STORES: LazyLock<Mutex<HashMap<String, Operator>>> =
+                        LazyLock::new(Default::default);
+                    let mut stores = STORES.lock().unwrap_or_else(PoisonError::into_inner);
+                    if let Some(operator) = stores.get(name) {
+                        return Ok(operator.clone());
+                    }
+                    let operator = Operator::new(opendal::services::Memory::default())?.finish();
+                    stores.insert(name.clone(), operator.clone());
+                    Ok(operator)
+                }
+
+                #[cfg(not(feature = "memory"))]
+                {
+                    let _ = name;
+                    Err(Error::UnsupportedProvider {
+                        provider: format!("{} (feature disabled)", provider.as_str()),
+                    })
+                }
+            }
+            ProviderBackend::Http {
+                endpoint,
+                root,
+                username,
+                password,
+                manifest,
+            } => {
+                #[cfg(feature = "http")]
+                {
+                    let mut builder = opendal::services::Http::default().endpoint(endpoint);
+                    if let Some(root) = root.as_deref() {
+                        builder = builder.root(root);
+                    }
+                    if let Some(username) = username.as_deref() {
+                        builder = builder.username(username);
+                    }
+                    if let Some(password) = password.as_deref() {
+                        builder = builder.password(password);
+                    }
+                    let endpoint_path = endpoint
+                        .parse::<http::Uri>()
+                        .map(|uri| uri.path().to_string())
+                        .unwrap_or_default();
+                    let base_path = format!("{endpoint_path}/{}", root.as_deref().unwrap_or(""));
+                    tracing::debug!(
+                        "HTTP builder config: endpoint={}, root={:?}, manifest={:?}",
+                        endpoint,
+                        root,
+                        manifest,
+                    );
+                    Ok(Operator::new(builder)?
+                        .finish()
+                        .layer(HttpIndexLayer::new(&base_path, manifest.clone())))
+                }
+
+                #[cfg(not(feature = "http"))]
+                {
+                    let _ = (endpoint, root, username, password, manifest);
+                    Err(Error::UnsupportedProvider {
+                        provider: format!("{} (feature disabled)", provider.as_str()),
+                    })
+                }
+            }
         }
     }
 
-    pub async fn list_directory(&self, path: &str, long: bool, recursive: bool) -> Result<()> {
-        log::debug!(
-            "list_directory provider={:?} path={} long={} recursive={}",
+    /// Probe capabilities and connectivity without touching any object.
+    pub async fn health_check(&self) -> HealthReport {
+        tracing::debug!("health_check provider={:?}", self.provider);
+        let checker = OpenDalHealthChecker::new(self.operator.clone());
+        checker.health_check().await
+    }
+
+    pub async fn list_directory(&self, path: &str, options: ListOptions) -> Result<()> {
+        tracing::debug!(
+            "list_directory provider={:?} path={} options={:?}",
             self.provider,
             path,
-            long,
-            recursive
+            options
         );
-        let lister = OpenDalLister::new(self.operator.clone());
+        let mut lister = OpenDalLister::new(self.operator.clone())
+            .with_stat_concurrency(self.concurrency(constants::DEFAULT_STAT_CONCURRENCY))
+            .with_page_size(self.page_size);
+        if let Some(classes) = self.class_changer() {
+            lister = lister.with_storage_classes(classes);
+        }
         wrap_err!(
-            lister.list(path, long, recursive).await,
+            lister.list(path, options).await,
             ListDirectoryFailed {
                 path: path.to_string()
             }
         )
     }
 
-    pub async fn print_tree(
-        &self,
-        path: &str,
-        depth: Option<usize>,
-        dirs_only: bool,
-    ) -> Result<()> {
+    pub async fn print_tree(&self, path: &str, options: TreeOptions) -> Result<()> {
         let treer = OpenDalTreer::new(self.operator.clone());
-        treer.tree(path, depth, dirs_only).await
+        treer.tree(path, options).await
     }
 
-    pub async fn download_files(&self, remote_path: &str, local_path: &str) -> Result<()> {
-        log::debug!(
-            "download_files provider={:?} remote_path={} local_path={}",
+    pub async fn download_files(
+        &self,
+        remote_path: &str,
+        local_path: &str,
+        version: Option<&str>,
+        options: &DownloadOptions,
+    ) -> Result<TransferReport> {
+        tracing::debug!(
+            "download_files provider={:?} remote_path={} local_path={} version={:?} options={:?}",
             self.provider,
             remote_path,
-            local_path
+            local_path,
+            version,
+            options
         );
-        let downloader = OpenDalDownloader::new(self.operator.clone());
+        let options = DownloadOptions {
+            concurrency: self.concurrency(options.concurrency),
+            ..options.clone()
+        };
+        let mut downloader =
+            OpenDalDownloader::new(self.operator.clone()).with_cancel_token(self.cancel.clone());
+        if options.decrypt {
+            let key = wrap_err!(
+                self.data_key("get --decrypt"),
+                DownloadFailed {
+                    remote_path: remote_path.to_string(),
+                    local_path: local_path.to_string()
+                }
+            )?;
+            downloader = downloader.with_data_key(key);
+        }
+        let result = match version {
+            Some(version) => {
+                downloader
+                    .download_version(remote_path, local_path, version)
+                    .await
+            }
+            None => downloader.download(remote_path, local_path, &options).await,
+        };
         wrap_err!(
-            downloader.download(remote_path, local_path).await,
+            result,
             DownloadFailed {
                 remote_path: remote_path.to_string(),
                 local_path: local_path.to_string()
@@ -214,16 +750,17 @@ impl StorageClient {
         )
     }
 
-    pub async fn disk_usage(&self, path: &str, summary: bool) -> Result<()> {
-        log::debug!(
-            "disk_usage provider={:?} path={} summary={}",
+    pub async fn disk_usage(&self, path: &str, options: UsageOptions) -> Result<()> {
+        tracing::debug!(
+            "disk_usage provider={:?} path={} options={:?}",
             self.provider,
             path,
-            summary
+            options
         );
-        let calculator = OpenDalUsageCalculator::new(self.operator.clone());
+        let calculator =
+            OpenDalUsageCalculator::new(self.operator.clone()).with_page_size(self.page_size);
         wrap_err!(
-            calculator.calculate_usage(path, summary).await,
+            calculator.calculate_usage(path, options).await,
             DiskUsageFailed {
                 path: path.to_string()
             }
@@ -235,17 +772,57 @@ impl StorageClient {
         local_path: &str,
         remote_path: &str,
         is_recursive: bool,
-    ) -> Result<()> {
-        log::debug!(
-            "upload_files provider={:?} local_path={} remote_path={} recursive={}",
+        options: &UploadOptions,
+    ) -> Result<TransferReport> {
+        tracing::debug!(
+            "upload_files provider={:?} local_path={} remote_path={} recursive={} options={:?}",
             self.provider,
             local_path,
             remote_path,
-            is_recursive
+            is_recursive,
+            options
         );
-        let uploader = OpenDalUploader::new(self.operator.clone());
+        let options = UploadOptions {
+            concurrency: self.concurrency(options.concurrency),
+            ..options.clone()
+        };
+        let mut uploader =
+            OpenDalUploader::new(self.operator.clone()).with_cancel_token(self.cancel.clone());
+        if let ProviderBackend::S3 {
+            encryption: Some(encryption),
+            ..
+        }
+        | ProviderBackend::Oss {
+            encryption: Some(encryption),
+            ..
+        } = &self.backend
+        {
+            uploader = uploader.with_encryption(encryption.describe(self.provider));
+        }
+        if let Some(replication) = options.replication {
+            let extension = wrap_err!(
+                self.extension("--replication"),
+                UploadFailed {
+                    local_path: local_path.to_string(),
+                    remote_path: remote_path.to_string()
+                }
+            )?;
+            uploader = uploader.with_replication(extension, replication);
+        }
+        if options.encrypt {
+            let key = wrap_err!(
+                self.data_key("put --encrypt"),
+                UploadFailed {
+                    local_path: local_path.to_string(),
+                    remote_path: remote_path.to_string()
+                }
+            )?;
+            uploader = uploader.with_data_key(key);
+        }
         wrap_err!(
-            uploader.upload(local_path, remote_path, is_recursive).await,
+            uploader
+                .upload(local_path, remote_path, is_recursive, &options)
+                .await,
             UploadFailed {
                 local_path: local_path.to_string(),
                 remote_path: remote_path.to_string()
@@ -253,16 +830,31 @@ impl StorageClient {
         )
     }
 
-    pub async fn delete_files(&self, paths: &[String], recursive: bool) -> Result<()> {
-        log::debug!(
-            "delete_files provider={:?} paths_count={} recursive={}",
+    pub async fn delete_files(
+        &self,
+        paths: &[String],
+        recursive: bool,
+        version: Option<&str>,
+        allow_root: bool,
+    ) -> Result<()> {
+        tracing::debug!(
+            "delete_files provider={:?} paths_count={} recursive={} version={:?} allow_root={}",
             self.provider,
             paths.len(),
-            recursive
+            recursive,
+            version,
+            allow_root
         );
-        let deleter = OpenDalDeleter::new(self.operator.clone());
+        let deleter = OpenDalDeleter::new(self.operator.clone()).with_allow_root(allow_root);
+        let result = match (version, paths) {
+            (Some(version), [path]) if !recursive => deleter.delete_version(path, version).await,
+            (Some(_), _) => Err(Error::InvalidArgument {
+                message: "--version-id requires exactly one path and no -R".to_string(),
+            }),
+            (None, _) => deleter.delete(paths, recursive).await,
+        };
         wrap_err!(
-            deleter.delete(paths, recursive).await,
+            result,
             DeleteFailed {
                 // summarize inputs to avoid huge error strings
                 paths: paths.iter().take(5).cloned().collect::<Vec<_>>().join(","),
@@ -271,253 +863,1202 @@ impl StorageClient {
         )
     }
 
-    pub async fn copy_files(&self, src_path: &str, dest_path: &str) -> Result<()> {
-        log::debug!(
-            "copy_files provider={:?} src_path={} dest_path={}",
+    /// Move `paths` into trash batch `batch` instead of deleting them (`rm --trash`).
+    pub async fn trash_files(&self, paths: &[String], batch: &str, recursive: bool) -> Result<()> {
+        tracing::debug!(
+            "trash_files provider={:?} paths_count={} batch={} recursive={}",
             self.provider,
-            src_path,
-            dest_path
+            paths.len(),
+            batch,
+            recursive
         );
-        let copier = OpenDalCopier::new(self.operator.clone());
+        let trasher = OpenDalTrasher::new(self.operator.clone());
         wrap_err!(
-            copier.copy(src_path, dest_path).await,
-            CopyFailed {
-                src_path: src_path.to_string(),
-                dest_path: dest_path.to_string()
+            trasher.trash(paths, batch, recursive).await,
+            DeleteFailed {
+                paths: paths.iter().take(5).cloned().collect::<Vec<_>>().join(","),
+                recursive: recursive
             }
         )
     }
 
-    pub async fn move_files(&self, src_path: &str, dest_path: &str) -> Result<()> {
-        log::debug!(
-            "move_files provider={:?} src_path={} dest_path={}",
-            self.provider,
-            src_path,
-            dest_path
-        );
-        let mover = OpenDalMover::new(self.operator.clone());
+    pub async fn list_trash(&self) -> Result<()> {
+        tracing::debug!("list_trash provider={:?}", self.provider);
+        let trasher = OpenDalTrasher::new(self.operator.clone());
         wrap_err!(
-            mover.mover(src_path, dest_path).await,
-            MoveFailed {
-                src_path: src_path.to_string(),
-                dest_path: dest_path.to_string()
+            trasher.list_trash().await,
+            TrashFailed {
+                action: "list".to_string()
             }
         )
     }
 
-    pub async fn create_directory(&self, path: &str, parents: bool) -> Result<()> {
-        log::debug!(
-            "create_directory provider={:?} path={} parents={}",
+    /// Move trashed `paths` back, from `batch` or from the newest batch holding each one.
+    pub async fn restore_from_trash(
+        &self,
+        paths: &[String],
+        batch: Option<&str>,
+        force: bool,
+    ) -> Result<()> {
+        tracing::debug!(
+            "restore_from_trash provider={:?} paths_count={} batch={:?} force={}",
             self.provider,
-            path,
-            parents
+            paths.len(),
+            batch,
+            force
         );
-        let mkdirer = OpenDalMkdirer::new(self.operator.clone());
+        let trasher = OpenDalTrasher::new(self.operator.clone());
         wrap_err!(
-            mkdirer.mkdir(path, parents).await,
-            DirectoryCreationFailed {
-                path: path.to_string()
+            trasher.restore(paths, batch, force).await,
+            TrashFailed {
+                action: "restore from".to_string()
             }
         )
     }
 
-    pub async fn cat_file(&self, path: &str, force: bool, size_limit_mb: u64) -> Result<()> {
-        log::debug!(
-            "cat_file provider={:?} path={},force={},size_limit_mb={}",
+    pub async fn empty_trash(&self, options: EmptyTrashOptions) -> Result<()> {
+        tracing::debug!(
+            "empty_trash provider={:?} options={:?}",
             self.provider,
-            path,
-            force,
-            size_limit_mb
+            options
         );
-        let reader = OpenDalFileReader::new(self.operator.clone());
+        let trasher = OpenDalTrasher::new(self.operator.clone());
         wrap_err!(
-            reader.cat(path, force, size_limit_mb).await,
-            CatFailed {
-                path: path.to_string()
+            trasher.empty(options).await,
+            TrashFailed {
+                action: "empty".to_string()
             }
         )
     }
 
-    pub async fn head_file(
+    /// Record what deleting or overwriting `paths` would lose, copying small objects
+    /// into `backup_dir` so `storify undo` can put them back.
+    pub async fn snapshot_objects(
         &self,
-        path: &str,
-        lines: Option<usize>,
-        bytes: Option<usize>,
-    ) -> Result<()> {
-        log::debug!(
-            "head_file provider={:?} path={} lines={:?} bytes={:?}",
+        paths: &[String],
+        recursive: bool,
+        backup_dir: &std::path::Path,
+    ) -> Result<Change> {
+        tracing::debug!(
+            "snapshot_objects provider={:?} paths_count={} recursive={} backup_dir={}",
             self.provider,
-            path,
-            lines,
-            bytes
+            paths.len(),
+            recursive,
+            backup_dir.display()
         );
-        let reader = OpenDalHeadReader::new(self.operator.clone());
-        wrap_err!(
-            reader.head(path, lines, bytes).await,
-            HeadFailed {
-                path: path.to_string()
-            }
-        )
+        OpenDalUndoer::new(self.operator.clone())
+            .snapshot(paths, recursive, backup_dir)
+            .await
     }
 
-    pub async fn head_files(
-        &self,
-        paths: &[String],
-        lines: Option<usize>,
-        bytes: Option<usize>,
-        quiet: bool,
-        verbose: bool,
-    ) -> Result<()> {
-        log::debug!(
-            "head_files provider={:?} paths_count={} lines={:?} bytes={:?} quiet={} verbose={}",
+    /// Revert a journaled operation.
+    pub async fn undo(&self, entry: &JournalEntry) -> Result<()> {
+        tracing::debug!(
+            "undo provider={:?} id={} change={:?}",
             self.provider,
-            paths.len(),
-            lines,
-            bytes,
-            quiet,
-            verbose
+            entry.id,
+            entry.change
         );
-        let reader = OpenDalHeadReader::new(self.operator.clone());
+        let undoer = OpenDalUndoer::new(self.operator.clone());
         wrap_err!(
-            reader.head_many(paths, lines, bytes, quiet, verbose).await,
-            HeadFailed {
-                path: paths.iter().take(5).cloned().collect::<Vec<_>>().join(",")
+            undoer.undo(&entry.change).await,
+            UndoFailed {
+                command: entry.command.clone()
             }
         )
     }
 
-    pub async fn tail_file(
+    /// Storage this client addresses (`scheme://name/root`), so journaled operations are
+    /// only undone against the storage they changed.
+    pub fn location(&self) -> String {
+        let info = self.operator.info();
+        format!("{}://{}{}", info.scheme(), info.name(), info.root())
+    }
+
+    pub async fn copy_files(
         &self,
-        path: &str,
-        lines: Option<usize>,
-        bytes: Option<usize>,
+        src_path: &str,
+        dest_path: &str,
+        clobber: &Clobber,
     ) -> Result<()> {
-        log::debug!(
-            "tail_file provider={:?} path={} lines={:?} bytes={:?}",
+        tracing::debug!(
+            "copy_files provider={:?} src_path={} dest_path={} clobber={:?}",
             self.provider,
-            path,
-            lines,
-            bytes
+            src_path,
+            dest_path,
+            clobber
         );
-        let reader = OpenDalTailReader::new(self.operator.clone());
+        let copier = OpenDalCopier::new(self.operator.clone())
+            .with_clobber(clobber.clone())
+            .with_cancel_token(self.cancel.clone());
         wrap_err!(
-            reader.tail(path, lines, bytes).await,
-            TailFailed {
-                path: path.to_string()
+            copier.copy(src_path, dest_path).await,
+            CopyFailed {
+                src_path: src_path.to_string(),
+                dest_path: dest_path.to_string()
             }
         )
     }
 
-    pub async fn tail_files(
+    /// Move `src_path` to `dest_path`; returns whether every file was moved, which is not
+    /// the case when `clobber` kept some destinations. Files that cannot be renamed are
+    /// copied and, with `verify`, checked against their source before it is deleted.
+    pub async fn move_files(
         &self,
-        paths: &[String],
-        lines: Option<usize>,
-        bytes: Option<usize>,
-        quiet: bool,
-        verbose: bool,
-    ) -> Result<()> {
-        log::debug!(
-            "tail_files provider={:?} paths_count={} lines={:?} bytes={:?} quiet={} verbose={}",
+        src_path: &str,
+        dest_path: &str,
+        clobber: &Clobber,
+        verify: bool,
+    ) -> Result<bool> {
+        tracing::debug!(
+            "move_files provider={:?} src_path={} dest_path={} clobber={:?} verify={}",
             self.provider,
-            paths.len(),
-            lines,
-            bytes,
-            quiet,
-            verbose
+            src_path,
+            dest_path,
+            clobber,
+            verify
         );
-        let reader = OpenDalTailReader::new(self.operator.clone());
+        let mover = OpenDalMover::new(self.operator.clone())
+            .with_clobber(clobber.clone())
+            .with_verify(verify)
+            .with_cancel_token(self.cancel.clone());
         wrap_err!(
-            reader.tail_many(paths, lines, bytes, quiet, verbose).await,
-            TailFailed {
-                path: paths.iter().take(5).cloned().collect::<Vec<_>>().join(",")
+            mover
+                .mover(src_path, dest_path)
+                .await
+                .map(|()| mover.skipped() == 0),
+            MoveFailed {
+                src_path: src_path.to_string(),
+                dest_path: dest_path.to_string()
             }
         )
     }
 
-    pub async fn stat_metadata(&self, path: &str, format: OutputFormat) -> Result<()> {
-        log::debug!(
-            "stat_metadata provider={:?} path={} format={:?}",
+    /// Rename every file matching `pattern` by applying `replacement` to its name, or with
+    /// `dry_run` only list the renames. Existing destinations are kept and their sources
+    /// skipped; each rename is a server-side rename where the backend supports one.
+    pub async fn rename_matching(
+        &self,
+        pattern: &str,
+        replacement: &Replacement,
+        dry_run: bool,
+    ) -> Result<()> {
+        tracing::debug!(
+            "rename_matching provider={:?} pattern={} replacement={:?} dry_run={}",
             self.provider,
-            path,
-            format
+            pattern,
+            replacement,
+            dry_run
         );
-        let stater = self::operations::stat::OpenDalStater::new(self.operator.clone());
-        let meta = stater.stat(path).await?;
+        let renamer = OpenDalRenamer::new(self.operator.clone());
+        let plan = renamer.plan_renames(pattern, replacement).await?;
+        if plan.is_empty() {
+            println!("No files matching '{pattern}' to rename");
+            return Ok(());
+        }
+        if dry_run {
+            for (from, to) in &plan {
+                println!("Would rename: {from} → {to}");
+            }
+            println!("{} file(s) would be renamed", plan.len());
+            return Ok(());
+        }
+
+        // Renames are independent, so one failure does not stop the rest.
+        let mover = OpenDalMover::new(self.operator.clone()).with_clobber(Clobber::Skip);
+        let mut results = MultiResult::new(plan.len());
+        let mut failed = 0;
+        for (from, to) in &plan {
+            let result = mover.mover(from, to).await.map_err(|e| Error::MoveFailed {
+                src_path: from.clone(),
+                dest_path: to.clone(),
+                source: Box::new(e),
+            });
+            if !results.record(from, result) {
+                failed += 1;
+            }
+        }
+        println!(
+            "Renamed {} of {} file(s)",
+            plan.len() - failed - mover.skipped(),
+            plan.len()
+        );
+        results.into_result(|failed_paths, total| Error::PartialFailure {
+            failed_paths,
+            total,
+        })
+    }
+
+    /// Run the steps of a job spec in order, stopping at the first failing step.
+    ///
+    /// With `dry_run` only the plan of each step is printed.
+    pub async fn apply_job(&self, spec: &JobSpec, dry_run: bool) -> Result<()> {
+        tracing::debug!(
+            "apply_job provider={:?} steps={} dry_run={}",
+            self.provider,
+            spec.steps.len(),
+            dry_run
+        );
+        let applier = OpenDalApplier::new(self.operator.clone());
+        for step in &spec.steps {
+            let step = &JobStep {
+                concurrency: self.concurrency(step.concurrency),
+                ..step.clone()
+            };
+            let result = async {
+                let plan = applier.plan(step).await?;
+                println!(
+                    "==> {} ({}): {} file(s) / {} to transfer, {} skipped, {} to delete",
+                    step.name,
+                    step.action.as_str(),
+                    plan.transfers.len(),
+                    format_size(plan.bytes()),
+                    plan.skipped,
+                    plan.deletions.len()
+                );
+                if dry_run {
+                    for transfer in &plan.transfers {
+                        println!(
+                            "would {}: {} → {}",
+                            step.action.as_str(),
+                            transfer.source,
+                            transfer.destination
+                        );
+                    }
+                    for path in &plan.deletions {
+                        println!("would delete: {path}");
+                    }
+                    return Ok(());
+                }
+                applier.execute(step, &plan).await
+            }
+            .await;
+            wrap_err!(
+                result,
+                ApplyStepFailed {
+                    step: step.name.clone()
+                }
+            )?;
+        }
+        Ok(())
+    }
+
+    pub async fn create_directory(
+        &self,
+        path: &str,
+        parents: bool,
+        mode: Option<u32>,
+    ) -> Result<()> {
+        tracing::debug!(
+            "create_directory provider={:?} path={} parents={} mode={:?}",
+            self.provider,
+            path,
+            parents,
+            mode
+        );
+        let mkdirer = OpenDalMkdirer::new(self.operator.clone());
+        let result: Result<()> = async {
+            mkdirer.mkdir(path, parents).await?;
+            if let Some(mode) = mode
+                && let Some(changer) = self.mode_changer("--mode")
+            {
+                changer.chmod(path, mode, false).await?;
+            }
+            Ok(())
+        }
+        .await;
+        wrap_err!(
+            result,
+            DirectoryCreationFailed {
+                path: path.to_string()
+            }
+        )
+    }
+
+    /// Set the permission bits of `path` (and everything below it when `recursive`).
+    /// Only the fs backend has modes; elsewhere this warns and changes nothing.
+    pub async fn change_mode(&self, path: &str, mode: u32, recursive: bool) -> Result<()> {
+        tracing::debug!(
+            "change_mode provider={:?} path={} mode={:o} recursive={}",
+            self.provider,
+            path,
+            mode,
+            recursive
+        );
+        let Some(changer) = self.mode_changer("chmod") else {
+            return Ok(());
+        };
+        let changed = wrap_err!(
+            changer.chmod(path, mode, recursive).await,
+            ChmodFailed {
+                path: path.to_string()
+            }
+        )?;
+        println!("Changed mode of {changed} path(s) to {mode:o}");
+        Ok(())
+    }
+
+    /// Mode changes for `chmod` and `mkdir --mode`; `None`, after a warning, on backends
+    /// without POSIX modes. OpenDAL's HDFS service does not expose permissions either.
+    fn mode_changer(&self, command: &str) -> Option<LocalModeChanger> {
+        match &self.backend {
+            ProviderBackend::Fs { root } => Some(
+                LocalModeChanger::new(self.operator.clone(), root).with_page_size(self.page_size),
+            ),
+            _ => {
+                eprintln!(
+                    "Warning: the '{}' backend has no file modes; {command} changed nothing",
+                    self.provider.as_str()
+                );
+                None
+            }
+        }
+    }
+
+    /// Change the owner and/or group of `path` (and everything below it when `recursive`).
+    #[cfg(feature = "hdfs")]
+    pub async fn change_owner(
+        &self,
+        path: &str,
+        owner: Option<&str>,
+        group: Option<&str>,
+        recursive: bool,
+    ) -> Result<()> {
+        tracing::debug!(
+            "change_owner provider={:?} path={} owner={:?} group={:?} recursive={}",
+            self.provider,
+            path,
+            owner,
+            group,
+            recursive
+        );
+        let result = async {
+            let extension = self.extension("chown")?;
+            let mut paths = vec![path.to_string()];
+            if recursive && self.operator.stat(path).await?.is_dir() {
+                let dir = format!("{}/", path.trim_end_matches('/'));
+                let mut entries =
+                    self::utils::paging::list_entries(&self.operator, &dir, true, self.page_size)
+                        .await?;
+                while let Some(entry) = entries.try_next().await.map_err(|e| e.into_error())? {
+                    // Listings of filesystem-like backends include the directory itself.
+                    if entry.path() != dir {
+                        paths.push(entry.path().to_string());
+                    }
+                }
+            }
+            for path in &paths {
+                extension.chown(path, owner, group).await?;
+            }
+            Ok::<_, Error>(paths.len())
+        }
+        .await;
+        let changed = wrap_err!(
+            result,
+            ChownFailed {
+                path: path.to_string()
+            }
+        )?;
+        println!("Changed owner of {changed} path(s)");
+        Ok(())
+    }
+
+    /// HDFS features outside OpenDAL (replication, ownership) over WebHDFS; `feature`
+    /// names the flag or command in the error on other providers.
+    /// The client-side encryption key, which `feature` cannot work without.
+    fn data_key(&self, feature: &str) -> Result<DataKey> {
+        match self.data_key.as_deref() {
+            Some(key) => DataKey::decode(key),
+            None => Err(Error::ObjectEncryption {
+                message: format!(
+                    "{feature} needs a data key: set one with `storify config edit <profile> \
+                     --generate-data-key` or STORAGE_DATA_KEY"
+                ),
+            }),
+        }
+    }
+
+    /// The client-side encryption key if one is configured, for reads that decrypt the
+    /// encrypted objects they come across.
+    fn optional_data_key(&self) -> Option<DataKey> {
+        self.data_key
+            .as_deref()
+            .and_then(|key| DataKey::decode(key).ok())
+    }
+
+    fn extension(&self, feature: &str) -> Result<WebHdfsExtension> {
+        WebHdfsExtension::from_backend(
+            self.provider,
+            &self.backend,
+            feature,
+            self.retry,
+            &self.connection,
+        )
+    }
+
+    /// Resolve a `--latest` selector to the newest object under `prefix`.
+    pub async fn resolve_latest(&self, prefix: &str, by: LatestBy) -> Result<String> {
+        tracing::debug!(
+            "resolve_latest provider={:?} prefix={} by={:?}",
+            self.provider,
+            prefix,
+            by
+        );
+        let resolver = OpenDalLatestResolver::new(self.operator.clone());
+        let path = resolver.resolve_latest(prefix, by).await?;
+        tracing::debug!("resolve_latest {} -> {}", prefix, path);
+        Ok(path)
+    }
+
+    /// Create `alias` as a link object pointing at `target`.
+    pub async fn create_link(&self, target: &str, alias: &str) -> Result<()> {
+        tracing::debug!(
+            "create_link provider={:?} target={} alias={}",
+            self.provider,
+            target,
+            alias
+        );
+        let linker = OpenDalLinker::new(self.operator.clone());
+        wrap_err!(
+            linker.link(target, alias).await,
+            LinkFailed {
+                alias: alias.to_string(),
+                target: target.to_string()
+            }
+        )
+    }
+
+    /// Follow link objects from `path` to the key they finally point at.
+    pub async fn resolve_links(&self, path: &str) -> Result<String> {
+        let linker = OpenDalLinker::new(self.operator.clone());
+        let resolved = linker.resolve_links(path).await?;
+        tracing::debug!("resolve_links {} -> {}", path, resolved);
+        Ok(resolved)
+    }
+
+    pub async fn cat_files(&self, paths: &[String], options: &CatOptions) -> Result<()> {
+        tracing::debug!(
+            "cat_files provider={:?} paths={:?} options={:?}",
+            self.provider,
+            paths,
+            options
+        );
+        let reader =
+            OpenDalFileReader::new(self.operator.clone()).with_data_key(self.optional_data_key());
+        wrap_err!(
+            reader.cat(paths, options).await,
+            CatFailed {
+                path: paths.join(", ")
+            }
+        )
+    }
+
+    pub async fn head_file(
+        &self,
+        path: &str,
+        lines: Option<usize>,
+        bytes: Option<usize>,
+    ) -> Result<()> {
+        tracing::debug!(
+            "head_file provider={:?} path={} lines={:?} bytes={:?}",
+            self.provider,
+            path,
+            lines,
+            bytes
+        );
+        let reader =
+            OpenDalHeadReader::new(self.operator.clone()).with_data_key(self.optional_data_key());
+        wrap_err!(
+            reader.head(path, lines, bytes).await,
+            HeadFailed {
+                path: path.to_string()
+            }
+        )
+    }
+
+    pub async fn head_files(
+        &self,
+        paths: &[String],
+        lines: Option<usize>,
+        bytes: Option<usize>,
+        quiet: bool,
+        verbose: bool,
+    ) -> Result<()> {
+        tracing::debug!(
+            "head_files provider={:?} paths_count={} lines={:?} bytes={:?} quiet={} verbose={}",
+            self.provider,
+            paths.len(),
+            lines,
+            bytes,
+            quiet,
+            verbose
+        );
+        let reader =
+            OpenDalHeadReader::new(self.operator.clone()).with_data_key(self.optional_data_key());
+        wrap_err!(
+            reader.head_many(paths, lines, bytes, quiet, verbose).await,
+            HeadFailed {
+                path: paths.iter().take(5).cloned().collect::<Vec<_>>().join(",")
+            }
+        )
+    }
+
+    pub async fn tail_file(
+        &self,
+        path: &str,
+        lines: Option<usize>,
+        bytes: Option<usize>,
+    ) -> Result<()> {
+        tracing::debug!(
+            "tail_file provider={:?} path={} lines={:?} bytes={:?}",
+            self.provider,
+            path,
+            lines,
+            bytes
+        );
+        let reader = OpenDalTailReader::new(self.operator.clone());
+        wrap_err!(
+            reader.tail(path, lines, bytes).await,
+            TailFailed {
+                path: path.to_string()
+            }
+        )
+    }
+
+    pub async fn tail_files(
+        &self,
+        paths: &[String],
+        lines: Option<usize>,
+        bytes: Option<usize>,
+        quiet: bool,
+        verbose: bool,
+    ) -> Result<()> {
+        tracing::debug!(
+            "tail_files provider={:?} paths_count={} lines={:?} bytes={:?} quiet={} verbose={}",
+            self.provider,
+            paths.len(),
+            lines,
+            bytes,
+            quiet,
+            verbose
+        );
+        let reader = OpenDalTailReader::new(self.operator.clone());
+        wrap_err!(
+            reader.tail_many(paths, lines, bytes, quiet, verbose).await,
+            TailFailed {
+                path: paths.iter().take(5).cloned().collect::<Vec<_>>().join(",")
+            }
+        )
+    }
+
+    pub async fn stat_metadata(
+        &self,
+        path: &str,
+        version: Option<&str>,
+        format: OutputFormat,
+        shallow: bool,
+        template: Option<&Template>,
+    ) -> Result<()> {
+        tracing::debug!(
+            "stat_metadata provider={:?} path={} version={:?} format={:?} shallow={} template={}",
+            self.provider,
+            path,
+            version,
+            format,
+            shallow,
+            template.is_some()
+        );
+        let stater = self::operations::stat::OpenDalStater::new(self.operator.clone());
+        let mut meta = stater.stat(path, version).await?;
+        if meta.entry_type == "file"
+            && version.is_none()
+            && let Some(classes) = self.class_changer()
+        {
+            meta.storage_class = classes
+                .object_class(path)
+                .await
+                .inspect_err(|e| tracing::debug!("storage class lookup failed for {path}: {e}"))
+                .ok();
+        }
+        if self.provider == StorageProvider::Hdfs && version.is_none() {
+            let status = async { self.extension("stat")?.status(path).await }.await;
+            match status {
+                Ok(status) => {
+                    meta.owner = Some(status.owner);
+                    meta.group = Some(status.group);
+                    meta.replication = (meta.entry_type == "file").then_some(status.replication);
+                }
+                Err(e) => tracing::debug!("WebHDFS status lookup failed for {path}: {e}"),
+            }
+        }
+        if meta.entry_type == "dir"
+            && version.is_none()
+            && template.is_none_or(|t| t.uses_any(ObjectMeta::DIRECTORY_FIELDS))
+        {
+            let calculator =
+                OpenDalUsageCalculator::new(self.operator.clone()).with_page_size(self.page_size);
+            meta.directory = Some(calculator.summarize(path, shallow).await?);
+        }
+        if let Some(template) = template {
+            println!("{}", template.render(&meta));
+            return Ok(());
+        }
+
+        match format {
+            OutputFormat::Human => {
+                println!("path={}", meta.path);
+                println!("type={}", meta.entry_type);
+                println!("size={}", meta.size);
+                if let Some(t) = meta.last_modified {
+                    println!("last_modified={}", t);
+                }
+                if let Some(etag) = meta.etag {
+                    println!("etag=\"{}\"", etag);
+                }
+                if let Some(ct) = meta.content_type {
+                    println!("content_type={}", ct);
+                }
+                if let Some(version) = meta.version {
+                    println!("version_id={}", version);
+                }
+                if let Some(class) = meta.storage_class {
+                    println!("storage_class={}", class);
+                }
+                if let Some(target) = meta.link_target {
+                    println!("link_target={}", target);
+                }
+                if let Some(owner) = &meta.owner {
+                    println!("owner={}", owner);
+                }
+                if let Some(group) = &meta.group {
+                    println!("group={}", group);
+                }
+                if let Some(replication) = meta.replication {
+                    println!("replication={}", replication);
+                }
+                if let Some(summary) = &meta.directory {
+                    println!("{summary}");
+                }
+            }
+            OutputFormat::Raw => {
+                println!("path={}", meta.path);
+                println!("type={}", meta.entry_type);
+                println!("size={}", meta.size);
+                if let Some(t) = meta.last_modified {
+                    println!("last_modified={}", t);
+                }
+                if let Some(etag) = meta.etag {
+                    println!("etag=\"{}\"", etag);
+                }
+                if let Some(ct) = meta.content_type {
+                    println!("content_type={}", ct);
+                }
+                if let Some(version) = meta.version {
+                    println!("version_id={}", version);
+                }
+                if let Some(class) = meta.storage_class {
+                    println!("storage_class={}", class);
+                }
+                if let Some(target) = meta.link_target {
+                    println!("link_target={}", target);
+                }
+                if let Some(owner) = &meta.owner {
+                    println!("owner={}", owner);
+                }
+                if let Some(group) = &meta.group {
+                    println!("group={}", group);
+                }
+                if let Some(replication) = meta.replication {
+                    println!("replication={}", replication);
+                }
+                if let Some(summary) = &meta.directory {
+                    println!("{summary}");
+                }
+            }
+            OutputFormat::Json => {
+                let json = StatOutput::from(meta);
+                println!("{}", serde_json::to_string(&json)?);
+            }
+        }
+
+        Ok(())
+    }
+
+    pub async fn index_prefix(
+        &self,
+        path: &str,
+        output: &std::path::Path,
+        force: bool,
+    ) -> Result<()> {
+        tracing::debug!(
+            "index_prefix provider={:?} path={} output={}",
+            self.provider,
+            path,
+            output.display()
+        );
+        let indexer = OpenDalIndexer::new(self.operator.clone()).with_page_size(self.page_size);
+        wrap_err!(
+            indexer.index(path, output, force).await,
+            IndexFailed {
+                path: path.to_string()
+            }
+        )
+    }
+
+    pub async fn export_inventory(
+        &self,
+        path: &str,
+        output: &std::path::Path,
+        format: InventoryFormat,
+        force: bool,
+    ) -> Result<usize> {
+        tracing::debug!(
+            "export_inventory provider={:?} path={} output={} format={:?}",
+            self.provider,
+            path,
+            output.display(),
+            format
+        );
+        let mut inventory =
+            OpenDalInventory::new(self.operator.clone()).with_page_size(self.page_size);
+        if let Some(classes) = self.class_changer() {
+            inventory = inventory.with_storage_classes(classes);
+        }
+        wrap_err!(
+            inventory.export(path, output, format, force).await,
+            InventoryFailed {
+                path: path.to_string()
+            }
+        )
+    }
+
+    /// Serve the directory `path` read-only on the local `mountpoint` until unmounted.
+    #[cfg(feature = "fuse")]
+    pub async fn mount(
+        &self,
+        path: &str,
+        mountpoint: &std::path::Path,
+        options: &MountOptions,
+    ) -> Result<()> {
+        tracing::debug!(
+            "mount provider={:?} path={} mountpoint={} options={:?}",
+            self.provider,
+            path,
+            mountpoint.display(),
+            options
+        );
+        let result = async {
+            if utils::path::is_remote_file(&self.operator, path).await {
+                return Err(Error::InvalidArgument {
+                    message: format!("'{path}' is a file; mount a directory"),
+                });
+            }
+            let root = path.trim_start_matches('/');
+            let root = if root.is_empty() {
+                String::new()
+            } else {
+                utils::path::ensure_trailing_slash(root)
+            };
+            mount::mount(self.operator.clone(), root, mountpoint, options).await
+        }
+        .await;
+        wrap_err!(
+            result,
+            MountFailed {
+                path: path.to_string()
+            }
+        )
+    }
+
+    /// Serve the remote over HTTP until interrupted.
+    pub async fn serve(&self, options: &crate::server::ServeOptions) -> Result<()> {
+        tracing::debug!("serve provider={:?} options={:?}", self.provider, options);
+        wrap_err!(
+            crate::server::serve(self.operator.clone(), options).await,
+            ServeFailed {
+                addr: options.addr.to_string()
+            }
+        )
+    }
+
+    pub async fn list_versions(&self, path: &str) -> Result<()> {
+        tracing::debug!("list_versions provider={:?} path={}", self.provider, path);
+        let versioner = OpenDalVersioner::new(self.operator.clone());
+        wrap_err!(
+            versioner.list_versions(path).await,
+            VersionsFailed {
+                path: path.to_string()
+            }
+        )
+    }
+
+    pub async fn restore_version(&self, path: &str, version_id: &str) -> Result<()> {
+        tracing::debug!(
+            "restore_version provider={:?} path={} version_id={}",
+            self.provider,
+            path,
+            version_id
+        );
+        let versioner = OpenDalVersioner::new(self.operator.clone());
+        wrap_err!(
+            versioner.restore(path, version_id).await,
+            RestoreFailed {
+                path: path.to_string(),
+                version_id: version_id.to_string()
+            }
+        )
+    }
+
+    /// Storage class lookups for `stat` and `ls -L`; `None` on providers (or credential
+    /// setups) without REST access, and for recorded sessions.
+    fn class_changer(&self) -> Option<RestClassChanger> {
+        if !self.rest_lookups
+            || !matches!(
+                self.provider,
+                StorageProvider::S3 | StorageProvider::Oss | StorageProvider::Cos
+            )
+        {
+            return None;
+        }
+        RestClassChanger::from_backend(self.provider, &self.backend, self.retry, &self.connection)
+            .inspect_err(|e| tracing::debug!("storage classes unavailable: {e}"))
+            .ok()
+    }
+
+    pub async fn change_storage_class(&self, path: &str, class: &str) -> Result<()> {
+        tracing::debug!(
+            "change_storage_class provider={:?} path={} class={}",
+            self.provider,
+            path,
+            class
+        );
+        let result = async {
+            RestClassChanger::from_backend(
+                self.provider,
+                &self.backend,
+                self.retry,
+                &self.connection,
+            )?
+            .change_class(path, class)
+            .await
+        }
+        .await;
+        wrap_err!(
+            result,
+            StorageClassFailed {
+                path: path.to_string()
+            }
+        )
+    }
+
+    /// Remove stale scratch objects and abort incomplete multipart uploads under `path`.
+    pub async fn collect_garbage(&self, path: &str, options: GcOptions) -> Result<()> {
+        tracing::debug!(
+            "collect_garbage provider={:?} path={} options={:?}",
+            self.provider,
+            path,
+            options
+        );
+        let mut collector = OpenDalGarbageCollector::new(self.operator.clone());
+        if self.rest_lookups
+            && matches!(
+                self.provider,
+                StorageProvider::S3 | StorageProvider::Oss | StorageProvider::Cos
+            )
+        {
+            match RestBucket::from_backend(
+                self.provider,
+                &self.backend,
+                "aborting uploads",
+                self.retry,
+                &self.connection,
+            ) {
+                Ok(bucket) => collector = collector.with_uploads(bucket),
+                Err(e) => tracing::warn!("skipping incomplete uploads: {e}"),
+            }
+        }
+        wrap_err!(
+            collector.collect(path, options).await,
+            GcFailed {
+                path: path.to_string()
+            }
+        )
+    }
+
+    fn multipart_manager(&self) -> Result<RestMultipartManager> {
+        RestMultipartManager::from_backend(
+            self.provider,
+            &self.backend,
+            self.retry,
+            &self.connection,
+        )
+    }
+
+    pub async fn list_multipart_uploads(&self, prefix: &str) -> Result<()> {
+        tracing::debug!(
+            "list_multipart_uploads provider={:?} prefix={}",
+            self.provider,
+            prefix
+        );
+        let result = async { self.multipart_manager()?.list_uploads(prefix).await }.await;
+        wrap_err!(
+            result,
+            MultipartFailed {
+                action: "list".to_string()
+            }
+        )
+    }
+
+    pub async fn abort_multipart_uploads(
+        &self,
+        prefix: &str,
+        older_than: Option<Duration>,
+        dry_run: bool,
+    ) -> Result<()> {
+        tracing::debug!(
+            "abort_multipart_uploads provider={:?} prefix={} older_than={:?} dry_run={}",
+            self.provider,
+            prefix,
+            older_than,
+            dry_run
+        );
+        let result = async {
+            self.multipart_manager()?
+                .abort_uploads(prefix, older_than, dry_run)
+                .await
+        }
+        .await;
+        wrap_err!(
+            result,
+            MultipartFailed {
+                action: "abort".to_string()
+            }
+        )
+    }
+
+    fn tagger(&self) -> Result<RestTagger> {
+        RestTagger::from_backend(self.provider, &self.backend, self.retry, &self.connection)
+    }
+
+    pub async fn get_tags(&self, path: &str) -> Result<()> {
+        tracing::debug!("get_tags provider={:?} path={}", self.provider, path);
+        let result = async { self.tagger()?.get_tags(path).await }.await;
+        wrap_err!(
+            result,
+            TaggingFailed {
+                path: path.to_string()
+            }
+        )
+    }
+
+    pub async fn set_tags(&self, path: &str, tags: &[(String, String)]) -> Result<()> {
+        tracing::debug!(
+            "set_tags provider={:?} path={} tags={:?}",
+            self.provider,
+            path,
+            tags
+        );
+        let result = async { self.tagger()?.set_tags(path, tags).await }.await;
+        wrap_err!(
+            result,
+            TaggingFailed {
+                path: path.to_string()
+            }
+        )
+    }
+
+    pub async fn remove_tags(&self, path: &str, keys: &[String]) -> Result<()> {
+        tracing::debug!(
+            "remove_tags provider={:?} path={} keys={:?}",
+            self.provider,
+            path,
+            keys
+        );
+        let result = async { self.tagger()?.remove_tags(path, keys).await }.await;
+        wrap_err!(
+            result,
+            TaggingFailed {
+                path: path.to_string()
+            }
+        )
+    }
+
+    fn acl_manager(&self) -> Result<RestAclManager> {
+        RestAclManager::from_backend(self.provider, &self.backend, self.retry, &self.connection)
+    }
+
+    pub async fn get_acl(&self, path: &str) -> Result<()> {
+        tracing::debug!("get_acl provider={:?} path={}", self.provider, path);
+        let result = async { self.acl_manager()?.get_acl(path).await }.await;
+        wrap_err!(
+            result,
+            AclFailed {
+                action: "read".to_string(),
+                path: path.to_string()
+            }
+        )
+    }
 
-        match format {
-            OutputFormat::Human => {
-                println!("path={}", meta.path);
-                println!("type={}", meta.entry_type);
-                println!("size={}", meta.size);
-                if let Some(t) = meta.last_modified {
-                    println!("last_modified={}", t);
-                }
-                if let Some(etag) = meta.etag {
-                    println!("etag=\"{}\"", etag);
-                }
-                if let Some(ct) = meta.content_type {
-                    println!("content_type={}", ct);
-                }
+    pub async fn set_acl(&self, path: &str, acl: CannedAcl) -> Result<()> {
+        tracing::debug!(
+            "set_acl provider={:?} path={} acl={}",
+            self.provider,
+            path,
+            acl.as_str()
+        );
+        let result = async { self.acl_manager()?.set_acl(path, acl).await }.await;
+        wrap_err!(
+            result,
+            AclFailed {
+                action: "set".to_string(),
+                path: path.to_string()
             }
-            OutputFormat::Raw => {
-                println!("path={}", meta.path);
-                println!("type={}", meta.entry_type);
-                println!("size={}", meta.size);
-                if let Some(t) = meta.last_modified {
-                    println!("last_modified={}", t);
-                }
-                if let Some(etag) = meta.etag {
-                    println!("etag=\"{}\"", etag);
-                }
-                if let Some(ct) = meta.content_type {
-                    println!("content_type={}", ct);
-                }
+        )
+    }
+
+    fn lifecycle_manager(&self) -> Result<RestLifecycleManager> {
+        RestLifecycleManager::from_backend(
+            self.provider,
+            &self.backend,
+            self.retry,
+            &self.connection,
+        )
+    }
+
+    pub async fn get_lifecycle(&self, json: bool) -> Result<()> {
+        tracing::debug!("get_lifecycle provider={:?} json={}", self.provider, json);
+        let result = async { self.lifecycle_manager()?.get_lifecycle(json).await }.await;
+        wrap_err!(
+            result,
+            LifecycleFailed {
+                action: "read".to_string()
             }
-            OutputFormat::Json => {
-                #[derive(serde::Serialize)]
-                struct JsonMeta<'a> {
-                    path: &'a str,
-                    entry_type: &'a str,
-                    size: u64,
-                    last_modified: Option<String>,
-                    etag: Option<String>,
-                    content_type: Option<String>,
-                }
-                let json = JsonMeta {
-                    path: &meta.path,
-                    entry_type: &meta.entry_type,
-                    size: meta.size,
-                    last_modified: meta.last_modified,
-                    etag: meta.etag,
-                    content_type: meta.content_type,
-                };
-                println!("{}", serde_json::to_string(&json)?);
+        )
+    }
+
+    pub async fn set_lifecycle(&self, config: &LifecycleConfig) -> Result<()> {
+        tracing::debug!(
+            "set_lifecycle provider={:?} rules={}",
+            self.provider,
+            config.rules.len()
+        );
+        let result = async { self.lifecycle_manager()?.set_lifecycle(config).await }.await;
+        wrap_err!(
+            result,
+            LifecycleFailed {
+                action: "set".to_string()
+            }
+        )
+    }
+
+    /// Remove the lifecycle rules with the given ids, or every rule when `ids` is empty.
+    pub async fn remove_lifecycle(&self, ids: &[String]) -> Result<()> {
+        tracing::debug!(
+            "remove_lifecycle provider={:?} ids={:?}",
+            self.provider,
+            ids
+        );
+        let result = async { self.lifecycle_manager()?.remove_lifecycle(ids).await }.await;
+        wrap_err!(
+            result,
+            LifecycleFailed {
+                action: "remove".to_string()
+            }
+        )
+    }
+
+    fn bucket_configurer(&self, setting: BucketSetting) -> Result<RestBucketConfigurer> {
+        RestBucketConfigurer::from_backend(
+            self.provider,
+            &self.backend,
+            setting,
+            self.retry,
+            &self.connection,
+        )
+    }
+
+    pub async fn get_bucket_policy(&self) -> Result<()> {
+        tracing::debug!("get_bucket_policy provider={:?}", self.provider);
+        let result = async {
+            self.bucket_configurer(BucketSetting::Policy)?
+                .get_policy()
+                .await
+        }
+        .await;
+        wrap_err!(
+            result,
+            BucketConfigFailed {
+                action: "read bucket policy".to_string()
             }
+        )
+    }
+
+    pub async fn set_bucket_policy(&self, policy: &str) -> Result<()> {
+        tracing::debug!(
+            "set_bucket_policy provider={:?} bytes={}",
+            self.provider,
+            policy.len()
+        );
+        let result = async {
+            self.bucket_configurer(BucketSetting::Policy)?
+                .set_policy(policy)
+                .await
         }
+        .await;
+        wrap_err!(
+            result,
+            BucketConfigFailed {
+                action: "set bucket policy".to_string()
+            }
+        )
+    }
 
-        Ok(())
+    pub async fn get_cors(&self, json: bool) -> Result<()> {
+        tracing::debug!("get_cors provider={:?} json={}", self.provider, json);
+        let result = async {
+            self.bucket_configurer(BucketSetting::Cors)?
+                .get_cors(json)
+                .await
+        }
+        .await;
+        wrap_err!(
+            result,
+            BucketConfigFailed {
+                action: "read CORS rules".to_string()
+            }
+        )
     }
 
-    pub async fn grep_file(
-        &self,
-        path: &str,
-        pattern: &str,
-        ignore_case: bool,
-        line_number: bool,
-    ) -> Result<()> {
-        log::debug!(
-            "grep_file provider={:?} path={} pattern={} ignore_case={} line_number={}",
+    pub async fn set_cors(&self, config: &CorsConfig) -> Result<()> {
+        tracing::debug!(
+            "set_cors provider={:?} rules={}",
+            self.provider,
+            config.rules.len()
+        );
+        let result = async {
+            self.bucket_configurer(BucketSetting::Cors)?
+                .set_cors(config)
+                .await
+        }
+        .await;
+        wrap_err!(
+            result,
+            BucketConfigFailed {
+                action: "set CORS rules".to_string()
+            }
+        )
+    }
+
+    async fn grep_file(&self, path: &str, matcher: &Matcher, options: &GrepOptions) -> Result<()> {
+        tracing::debug!(
+            "grep_file provider={:?} path={} options={:?}",
             self.provider,
             path,
-            pattern,
-            ignore_case,
-            line_number
+            options
         );
         let greper = OpenDalGreper::new(self.operator.clone());
         wrap_err!(
-            greper
-                .grep(path, pattern, ignore_case, line_number, false)
-                .await,
+            greper.grep(path, matcher, options, false).await,
             GrepFailed {
                 path: path.to_string()
             }
@@ -528,28 +2069,26 @@ impl StorageClient {
         &self,
         path: &str,
         pattern: &str,
-        ignore_case: bool,
-        line_number: bool,
+        options: &GrepOptions,
         recursive: bool,
     ) -> Result<()> {
-        log::debug!(
-            "grep_path provider={:?} path={} pattern={} ignore_case={} line_number={} recursive={}",
+        tracing::debug!(
+            "grep_path provider={:?} path={} pattern={} options={:?} recursive={}",
             self.provider,
             path,
             pattern,
-            ignore_case,
-            line_number,
+            options,
             recursive
         );
+        let matcher = Matcher::new(pattern, options.ignore_case, options.regex)?;
+        self.ensure_resumable(options.start_after.as_deref())?;
 
         // When recursive is requested, avoid failing on NotFound for virtual prefixes (S3/OSS).
         if recursive {
             match self.operator.stat(path).await {
                 Ok(meta) => {
                     if meta.mode().is_file() {
-                        return self
-                            .grep_file(path, pattern, ignore_case, line_number)
-                            .await;
+                        return self.grep_file(path, &matcher, options).await;
                     }
                     // If it's a directory or other type, fall through to recursive listing.
                 }
@@ -564,31 +2103,7 @@ impl StorageClient {
                 }
             }
 
-            let lister = wrap_err!(
-                self.operator.lister_with(path).recursive(true).await,
-                ListDirectoryFailed {
-                    path: path.to_string()
-                }
-            )?;
-
-            futures::pin_mut!(lister);
-            while let Some(entry) =
-                lister
-                    .try_next()
-                    .await
-                    .map_err(|e| Error::ListDirectoryFailed {
-                        path: path.to_string(),
-                        source: Box::new(e.into_error()),
-                    })?
-            {
-                if entry.metadata().mode().is_file() {
-                    let greper = OpenDalGreper::new(self.operator.clone());
-                    greper
-                        .grep(entry.path(), pattern, ignore_case, line_number, true)
-                        .await?;
-                }
-            }
-            return Ok(());
+            return self.grep_recursive(path, &matcher, options).await;
         }
 
         // Non-recursive: require a real file; directories must use -R.
@@ -606,9 +2121,7 @@ impl StorageClient {
         })?;
 
         if meta.mode().is_file() {
-            return self
-                .grep_file(path, pattern, ignore_case, line_number)
-                .await;
+            return self.grep_file(path, &matcher, options).await;
         }
         if meta.mode().is_dir() {
             return Err(Error::InvalidArgument {
@@ -621,8 +2134,86 @@ impl StorageClient {
         })
     }
 
+    /// Search every file under `prefix` that passes `options.filter`, `options.concurrency`
+    /// files at a time. Each file's output is buffered and printed whole, in listing order.
+    async fn grep_recursive(
+        &self,
+        prefix: &str,
+        matcher: &Matcher,
+        options: &GrepOptions,
+    ) -> Result<()> {
+        let list_failed = |e: opendal::Error| Error::ListDirectoryFailed {
+            path: prefix.to_string(),
+            source: Box::new(e.into_error()),
+        };
+        let entries = list_entries_after(
+            &self.operator,
+            prefix,
+            true,
+            self.page_size,
+            options.start_after.as_deref(),
+        )
+        .await
+        .map_err(list_failed)?;
+        let greper = OpenDalGreper::new(self.operator.clone());
+        let greper = &greper;
+        let mut outputs = entries
+            .try_filter(|entry| {
+                let selected = entry.metadata().mode().is_file()
+                    && options
+                        .filter
+                        .matches(&get_root_relative_path(entry.path(), prefix));
+                futures::future::ready(selected)
+            })
+            .map(|entry| async move {
+                let entry = entry.map_err(list_failed)?;
+                let mut output = Vec::new();
+                greper
+                    .search(entry.path(), matcher, options, true, &mut output)
+                    .await?;
+                Ok::<_, Error>((entry, output))
+            })
+            .buffered(self.concurrency(options.concurrency.max(1)));
+
+        let stdout = std::io::stdout();
+        let mut printed = 0;
+        while let Some(result) = outputs.next().await {
+            let (entry, output) = result?;
+            std::io::Write::write_all(&mut stdout.lock(), &output)?;
+            printed += memchr::memchr_iter(b'\n', &output).count();
+            if options.max_results.is_some_and(|max| printed >= max) {
+                // Files already in flight are dropped; the next run starts after this one.
+                report_continuation(entry.path());
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn follow_grep(
+        &self,
+        path: &str,
+        pattern: &str,
+        options: &FollowOptions,
+    ) -> Result<()> {
+        tracing::debug!(
+            "follow_grep provider={:?} path={} pattern={} options={:?}",
+            self.provider,
+            path,
+            pattern,
+            options
+        );
+        let greper = OpenDalGreper::new(self.operator.clone());
+        wrap_err!(
+            greper.follow(path, pattern, options).await,
+            GrepFailed {
+                path: path.to_string()
+            }
+        )
+    }
+
     pub async fn find_paths(&self, args: &crate::cli::storage::FindArgs) -> Result<()> {
-        log::debug!(
+        tracing::debug!(
             "find_paths provider={:?} path={} name={:?} regex_present={} type={:?}",
             self.provider,
             args.path,
@@ -631,6 +2222,8 @@ impl StorageClient {
             args.r#type,
         );
 
+        self.ensure_resumable(args.start_after.as_deref())?;
+
         // Prepare filters
         let name_glob = if let Some(pattern) = &args.name {
             let g = globset::Glob::new(pattern).map_err(|e| Error::InvalidArgument {
@@ -661,12 +2254,26 @@ impl StorageClient {
             }
         };
 
-        let finder = OpenDalFinder::new(self.operator.clone());
+        let finder = OpenDalFinder::new(self.operator.clone()).with_page_size(self.page_size);
         let opts = self::operations::find::FindOptions {
             path: args.path.clone(),
             name_glob,
             regex,
             type_filter,
+            size: args.size,
+            mtime_days: args.mtime,
+            min_depth: args.min_depth,
+            max_depth: args.max_depth,
+            action: match &args.exec {
+                Some(command) => FindAction::Exec(command.clone()),
+                None if args.delete => FindAction::Delete,
+                None => match &args.format {
+                    Some(format) => FindAction::Format(format.clone()),
+                    None => FindAction::Print,
+                },
+            },
+            max_results: args.max_results,
+            start_after: args.start_after.clone(),
         };
 
         self::operations::find::Finder::find(&finder, &opts)
@@ -680,63 +2287,178 @@ impl StorageClient {
             })
     }
 
-    pub async fn diff_files(
+    /// Whether `left` and `right` are both directories (true) or both files (false);
+    /// a directory cannot be diffed against a file.
+    pub async fn both_directories(&self, left: &str, right: &str) -> Result<bool> {
+        let left_dir = is_remote_directory(&self.operator, left).await;
+        let right_dir = is_remote_directory(&self.operator, right).await;
+        match (left_dir, right_dir) {
+            (true, true) => Ok(true),
+            (false, false) => Ok(false),
+            (true, false) | (false, true) => Err(Error::InvalidArgument {
+                message: format!("cannot diff a directory against a file: '{left}' and '{right}'"),
+            }),
+        }
+    }
+
+    pub async fn diff_directories(
         &self,
         left: &str,
         right: &str,
-        context: usize,
-        ignore_space: bool,
-        size_limit_mb: u64,
-        force: bool,
+        options: DirDiffOptions,
     ) -> Result<()> {
-        // Validate both paths are files
-        let left_meta = self.operator.stat(left).await.map_err(|e| {
-            if e.kind() == opendal::ErrorKind::NotFound {
-                Error::PathNotFound {
-                    path: std::path::PathBuf::from(left),
-                }
-            } else {
-                Error::InvalidArgument {
-                    message: format!("Failed to stat '{}': {}", left, e),
-                }
+        tracing::debug!(
+            "diff_directories provider={:?} left={} right={} content={}",
+            self.provider,
+            left,
+            right,
+            options.content
+        );
+        let differ = OpenDalDirDiffer::new(self.operator.clone()).with_page_size(self.page_size);
+        wrap_err!(
+            differ.diff_dirs(left, right, options).await,
+            DiffFailed {
+                src_path: left.to_string(),
+                dest_path: right.to_string()
             }
-        })?;
-        let right_meta = self.operator.stat(right).await.map_err(|e| {
-            if e.kind() == opendal::ErrorKind::NotFound {
-                Error::PathNotFound {
-                    path: std::path::PathBuf::from(right),
+        )?;
+        Ok(())
+    }
+
+    /// Check the files below `local_dir` against the objects under `remote_prefix` by MD5.
+    pub async fn verify_directory(
+        &self,
+        local_dir: &std::path::Path,
+        remote_prefix: &str,
+    ) -> Result<VerifySummary> {
+        tracing::debug!(
+            "verify_directory provider={:?} local={} remote={}",
+            self.provider,
+            local_dir.display(),
+            remote_prefix
+        );
+        let verifier = OpenDalVerifier::new(self.operator.clone()).with_page_size(self.page_size);
+        wrap_err!(
+            verifier.verify(local_dir, remote_prefix).await,
+            VerifyFailed {
+                local_path: local_dir.display().to_string(),
+                remote_path: remote_prefix.to_string()
+            }
+        )
+    }
+
+    /// Files to upload and objects to delete for a one-way sync of `local_dir` to
+    /// `remote_prefix`.
+    pub async fn sync_plan(
+        &self,
+        local_dir: &std::path::Path,
+        remote_prefix: &str,
+    ) -> Result<SyncPlan> {
+        tracing::debug!(
+            "sync_plan provider={:?} local={} remote={}",
+            self.provider,
+            local_dir.display(),
+            remote_prefix
+        );
+        let verifier = OpenDalVerifier::new(self.operator.clone()).with_page_size(self.page_size);
+        wrap_err!(
+            verifier.sync_plan(local_dir, remote_prefix).await,
+            VerifyFailed {
+                local_path: local_dir.display().to_string(),
+                remote_path: remote_prefix.to_string()
+            }
+        )
+    }
+
+    /// Size and etag of one side of a file diff; directories are rejected.
+    async fn diff_source_meta(&self, source: &DiffSource) -> Result<(u64, Option<String>)> {
+        let not_file = || Error::InvalidArgument {
+            message: format!(
+                "diff of '{}' needs a file; use two directories to diff prefixes",
+                source.label()
+            ),
+        };
+        match source {
+            DiffSource::Remote(path) => {
+                let meta = self.operator.stat(path).await.map_err(|e| {
+                    if e.kind() == opendal::ErrorKind::NotFound {
+                        Error::PathNotFound {
+                            path: std::path::PathBuf::from(path),
+                        }
+                    } else {
+                        Error::InvalidArgument {
+                            message: format!("Failed to stat '{}': {}", path, e),
+                        }
+                    }
+                })?;
+                if !meta.mode().is_file() {
+                    return Err(not_file());
                 }
-            } else {
-                Error::InvalidArgument {
-                    message: format!("Failed to stat '{}': {}", right, e),
+                Ok((meta.content_length(), meta.etag().map(str::to_string)))
+            }
+            DiffSource::Local(path) => {
+                let meta = tokio::fs::metadata(path).await.map_err(|e| {
+                    if e.kind() == std::io::ErrorKind::NotFound {
+                        Error::PathNotFound { path: path.clone() }
+                    } else {
+                        e.into()
+                    }
+                })?;
+                if !meta.is_file() {
+                    return Err(not_file());
                 }
+                Ok((meta.len(), None))
             }
-        })?;
-
-        if !left_meta.mode().is_file() || !right_meta.mode().is_file() {
-            return Err(Error::InvalidArgument {
-                message: "diff only supports files; directories are not supported".to_string(),
-            });
         }
+    }
+
+    pub async fn diff_files(
+        &self,
+        left: &DiffSource,
+        right: &DiffSource,
+        context: usize,
+        ignore_space: bool,
+        size_limit_mb: u64,
+        force: bool,
+    ) -> Result<()> {
+        tracing::debug!(
+            "diff_files provider={:?} left={:?} right={:?}",
+            self.provider,
+            left,
+            right
+        );
+        // Validate both paths are files
+        let (left_size, left_etag) = self.diff_source_meta(left).await?;
+        let (right_size, right_etag) = self.diff_source_meta(right).await?;
 
         // Short-circuit: identical paths (after existence/type validation)
         if left == right {
             return Ok(());
         }
 
-        // Short-circuit when ETag and size match (content-identical for many providers)
-        if left_meta.content_length() == right_meta.content_length() {
-            let le = left_meta.etag();
-            let re = right_meta.etag();
-            match (le, re) {
-                (Some(le), Some(re)) if le == re => return Ok(()),
-                _ => {}
+        let differ = OpenDalDiffer::new(self.operator.clone());
+        if left_size == right_size {
+            // Short-circuit when ETag and size match (content-identical for many providers)
+            if let (Some(le), Some(re)) = (&left_etag, &right_etag)
+                && le == re
+            {
+                return Ok(());
+            }
+            // Otherwise stream both sides; equal files need no size check or full read.
+            let same = wrap_err!(
+                differ.same_content(left, right, left_size).await,
+                DiffFailed {
+                    src_path: left.label(),
+                    dest_path: right.label()
+                }
+            )?;
+            if same {
+                return Ok(());
             }
         }
 
         // Size check (sum of both files)
-        let total_mb =
-            (left_meta.content_length() + right_meta.content_length()).div_ceil(1024 * 1024);
+        let total_mb = (left_size + right_size).div_ceil(1024 * 1024);
         if size_limit_mb > 0 && total_mb > size_limit_mb && !force {
             return Err(Error::InvalidArgument {
                 message: format!(
@@ -746,49 +2468,209 @@ impl StorageClient {
             });
         }
 
-        let differ = OpenDalDiffer::new(self.operator.clone());
         wrap_err!(
             differ.diff(left, right, context, ignore_space).await,
             DiffFailed {
-                src_path: left.to_string(),
-                dest_path: right.to_string()
+                src_path: left.label(),
+                dest_path: right.label()
             }
         )
     }
 
+    /// Append local files, or standard input for `-`, to the remote file `path` in order,
+    /// with `separator` between consecutive sources.
+    pub async fn append_files(
+        &self,
+        sources: &[String],
+        path: &str,
+        separator: &[u8],
+    ) -> Result<()> {
+        tracing::debug!(
+            "append_files provider={:?} sources={:?} path={} separator={:?}",
+            self.provider,
+            sources,
+            path,
+            separator
+        );
+        let appender = OpenDalAppender::new(self.operator.clone());
+        let result = match concat_sources(sources, separator).await {
+            Ok(reader) => appender.append(path, reader).await,
+            Err(e) => Err(e),
+        };
+        let (appended, strategy) = wrap_err!(
+            result,
+            AppendFailed {
+                path: path.to_string()
+            }
+        )?;
+        let source = match sources {
+            [source] if source == "-" => "stdin".to_string(),
+            [source] => source.clone(),
+            _ => format!("{} sources", sources.len()),
+        };
+        println!("✅ Append: {source} → {path} ({appended} bytes, {strategy})");
+        Ok(())
+    }
+
+    /// Concatenate the objects `sources` into `dest`, server-side where the provider
+    /// supports it.
+    pub async fn concat_objects(&self, dest: &str, sources: &[String]) -> Result<()> {
+        tracing::debug!(
+            "concat_objects provider={:?} dest={} sources={:?}",
+            self.provider,
+            dest,
+            sources
+        );
+        let mut composer = OpenDalComposer::new(self.operator.clone());
+        if let Some(server) = self.server_compose() {
+            composer = composer.with_server(server);
+        }
+        let (size, method) = wrap_err!(
+            composer.compose(dest, sources).await,
+            ConcatFailed {
+                path: dest.to_string()
+            }
+        )?;
+        println!(
+            "✅ Concat: {} object(s) → {dest} ({size} bytes, {method})",
+            sources.len()
+        );
+        Ok(())
+    }
+
+    /// Server-side merge API of this backend; `None`, falling back to streaming, when it
+    /// has none or the REST client cannot be set up (e.g. without static credentials).
+    fn server_compose(&self) -> Option<ServerCompose> {
+        let server = match self.provider {
+            StorageProvider::S3 | StorageProvider::Oss | StorageProvider::Cos => {
+                RestBucket::from_backend(
+                    self.provider,
+                    &self.backend,
+                    "server-side concat",
+                    self.retry,
+                    &self.connection,
+                )
+                .map(|bucket| Some(ServerCompose::PartCopy(bucket)))
+            }
+            StorageProvider::Gcs => {
+                GcsBucket::from_backend(&self.backend, self.retry, &self.connection)
+                    .map(|bucket| bucket.map(ServerCompose::Gcs))
+            }
+            _ => Ok(None),
+        };
+        server.unwrap_or_else(|e| {
+            tracing::debug!("concat falls back to streaming: {e}");
+            None
+        })
+    }
+
+    /// Create or truncate files; with `mtime`, also set their modification time: the
+    /// real one on fs and HDFS, the `--preserve` user metadata on object stores.
     pub async fn touch_files(
         &self,
         paths: &[String],
         no_create: bool,
         truncate: bool,
         parents: bool,
+        mtime: Option<SystemTime>,
     ) -> Result<()> {
-        log::debug!(
-            "touch_files provider={:?} paths_count={} no_create={} truncate={} parents={}",
+        tracing::debug!(
+            "touch_files provider={:?} paths_count={} no_create={} truncate={} parents={} mtime={:?}",
             self.provider,
             paths.len(),
             no_create,
             truncate,
-            parents
+            parents,
+            mtime
         );
+        let extension = match (&self.backend, mtime) {
+            (ProviderBackend::Hdfs { .. }, Some(_)) => Some(self.extension("touch -d")?),
+            (ProviderBackend::Fs { .. }, _) | (_, None) => None,
+            (_, Some(_)) => {
+                preserve::ensure_supported(&self.operator, "touch -d")?;
+                None
+            }
+        };
+        let extension = extension.as_ref();
 
-        let concurrency: usize = 8;
-        futures::stream::iter(paths.iter().cloned())
+        let concurrency = self.concurrency(8);
+        let outcomes: Vec<(&String, Result<()>)> = futures::stream::iter(paths)
             .map(|p| {
                 let op = self.operator.clone();
                 async move {
-                    let toucher = OpenDalToucher::new(op);
-                    toucher
-                        .touch(&p, no_create, truncate, parents)
-                        .await
-                        .map_err(|e| Error::TouchFailed {
-                            path: p.clone(),
-                            source: Box::new(e),
-                        })
+                    let toucher = OpenDalToucher::new(op).with_mtime(mtime);
+                    let result = match toucher.touch(p, no_create, truncate, parents).await {
+                        Ok(true) if let Some(mtime) = mtime => match (&self.backend, extension) {
+                            (ProviderBackend::Fs { root }, _) => {
+                                set_local_mtime(Path::new(root), p, mtime)
+                            }
+                            (_, Some(extension)) => extension.set_mtime(p, mtime).await,
+                            _ => Ok(()),
+                        },
+                        result => result.map(|_| ()),
+                    };
+                    (p, result)
                 }
             })
-            .buffer_unordered(concurrency)
-            .try_for_each(|_| async { Ok(()) })
+            .buffered(concurrency)
+            .collect()
+            .await;
+
+        // Every path is touched even when some fail; one path keeps its own error.
+        let mut results = MultiResult::new(paths.len());
+        for (path, result) in outcomes {
+            let result = result.map_err(|e| Error::TouchFailed {
+                path: path.clone(),
+                source: Box::new(e),
+            });
+            results.record(path, result);
+        }
+        results.into_result(|failed_paths, total| Error::PartialFailure {
+            failed_paths,
+            total,
+        })
+    }
+
+    /// Modification time of `path` for `touch --reference`: the time preserved in its
+    /// user metadata if any, else the backend's last-modified time.
+    pub async fn reference_mtime(&self, path: &str) -> Result<SystemTime> {
+        tracing::debug!("reference_mtime provider={:?} path={}", self.provider, path);
+        let meta = wrap_err!(
+            self.operator.stat(path).await,
+            TouchFailed {
+                path: path.to_string()
+            }
+        )?;
+        meta.user_metadata()
+            .and_then(|metadata| metadata.get(preserve::MTIME_METADATA_KEY))
+            .and_then(|value| preserve::parse_mtime(value))
+            .or_else(|| meta.last_modified().map(SystemTime::from))
+            .ok_or_else(|| Error::InvalidArgument {
+                message: format!("'{path}' has no modification time to copy"),
+            })
+    }
+}
+
+#[cfg(all(test, feature = "memory"))]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn memory_stores_are_shared_by_name() {
+        let writer = StorageClient::new(StorageConfig::memory("shared"))
+            .await
+            .unwrap();
+        writer.operator().write("a.txt", "hi").await.unwrap();
+
+        let reader = StorageClient::new(StorageConfig::memory("shared"))
+            .await
+            .unwrap();
+        let content = reader.operator().read("a.txt").await.unwrap();
+        assert_eq!(content.to_vec(), b"hi");
+
+        let other = StorageClient::new(StorageConfig::memory("other"))
             .await
+            .unwrap();
+        assert!(!other.operator().exists("a.txt").await.unwrap());
     }
 }
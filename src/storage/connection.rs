@@ -0,0 +1,124 @@
+//! Request timeouts, HTTP connection pool settings and extra request headers.
+//!
+//! Nothing is limited by default, which keeps large transfers on slow links working; CI
+//! and flaky networks can bound how long a stalled request may hang instead.
+
+use crate::config::StorageConfig;
+use crate::error::{Error, Result};
+use opendal::layers::TimeoutLayer;
+use opendal::raw::HttpClient;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use std::time::Duration;
+
+/// Timeouts, pool size and headers of one client; unset fields keep the library defaults.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConnectionSettings {
+    /// Longest a single request, or a single read or write of a transfer, may take
+    pub timeout: Option<Duration>,
+    /// Longest establishing a connection may take
+    pub connect_timeout: Option<Duration>,
+    /// Idle connections kept open per host; 0 closes them after every request
+    pub pool_size: Option<usize>,
+    /// Sent with every request, unsigned; S3 may refuse unsigned `x-amz-*` headers
+    pub headers: Vec<(String, String)>,
+}
+
+impl ConnectionSettings {
+    pub fn from_config(config: &StorageConfig) -> Self {
+        Self {
+            timeout: config.timeout_secs.map(Duration::from_secs),
+            connect_timeout: config.connect_timeout_secs.map(Duration::from_secs),
+            pool_size: config.pool_size,
+            headers: config.headers.clone(),
+        }
+    }
+
+    /// Layer timing out the operator's operations. A transfer is not bounded as a whole,
+    /// only each of its reads and writes, so large objects still finish.
+    pub fn timeout_layer(&self) -> Option<TimeoutLayer> {
+        self.timeout.map(|timeout| {
+            TimeoutLayer::new()
+                .with_timeout(timeout)
+                .with_io_timeout(timeout)
+        })
+    }
+
+    /// HTTP client for the operator, or `None` when the default one fits.
+    pub fn http_client(&self) -> Result<Option<HttpClient>> {
+        if self.connect_timeout.is_none() && self.pool_size.is_none() && self.headers.is_empty() {
+            return Ok(None);
+        }
+        let client = self.client_builder()?.build().map_err(client_error)?;
+        Ok(Some(HttpClient::with(client)))
+    }
+
+    /// Client for requests storify signs itself. Their bodies are small, so the timeout
+    /// covers the whole request.
+    pub fn rest_client(&self) -> Result<reqwest::Client> {
+        let mut builder = self
+            .client_builder()?
+            .user_agent(concat!("storify/", env!("CARGO_PKG_VERSION")));
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        builder.build().map_err(client_error)
+    }
+
+    fn client_builder(&self) -> Result<reqwest::ClientBuilder> {
+        let mut builder = reqwest::Client::builder();
+        if !self.headers.is_empty() {
+            let mut headers = HeaderMap::new();
+            for (name, value) in &self.headers {
+                let invalid = || Error::InvalidArgument {
+                    message: format!("invalid header '{name}: {value}'"),
+                };
+                headers.append(
+                    HeaderName::from_bytes(name.as_bytes()).map_err(|_| invalid())?,
+                    HeaderValue::from_str(value).map_err(|_| invalid())?,
+                );
+            }
+            builder = builder.default_headers(headers);
+        }
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+        if let Some(size) = self.pool_size {
+            builder = builder.pool_max_idle_per_host(size);
+        }
+        Ok(builder)
+    }
+}
+
+fn client_error(err: reqwest::Error) -> Error {
+    Error::ProviderRequest {
+        message: format!("failed to build HTTP client: {err}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_leave_client_and_operator_alone() {
+        let mut config = StorageConfig::memory("connection");
+        let settings = ConnectionSettings::from_config(&config);
+        assert!(settings.timeout_layer().is_none());
+        assert!(settings.http_client().unwrap().is_none());
+
+        config.connect_timeout_secs = Some(5);
+        config.pool_size = Some(0);
+        let settings = ConnectionSettings::from_config(&config);
+        assert_eq!(settings.connect_timeout, Some(Duration::from_secs(5)));
+        assert!(settings.http_client().unwrap().is_some());
+
+        let mut config = StorageConfig::memory("connection");
+        config.headers = vec![("X-Api-Key".to_string(), "secret".to_string())];
+        assert!(
+            ConnectionSettings::from_config(&config)
+                .http_client()
+                .unwrap()
+                .is_some()
+        );
+    }
+}
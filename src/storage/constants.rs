@@ -2,6 +2,28 @@
 pub const DEFAULT_BUFFER_SIZE: usize = 8192;
 pub const DEFAULT_CHUNK_SIZE: usize = 1024 * 1024;
 
+// Chunked single-file downloads: concurrent range requests and bytes per request
+pub const DEFAULT_DOWNLOAD_CONCURRENCY: usize = 4;
+pub const DEFAULT_DOWNLOAD_PART_SIZE: u64 = 8 * 1024 * 1024;
+
+// Files uploaded in parallel by recursive `put`
+pub const DEFAULT_UPLOAD_CONCURRENCY: usize = 4;
+
+// Files searched in parallel by recursive `grep`
+pub const DEFAULT_GREP_CONCURRENCY: usize = 8;
+
+// Maximum in-flight stat calls when enriching listings
+pub const DEFAULT_STAT_CONCURRENCY: usize = 16;
+
+// Retries of transient request failures: attempts after the first, the initial delay
+// (doubled per retry) and the longest delay between two attempts
+pub const DEFAULT_RETRIES: usize = 3;
+pub const DEFAULT_RETRY_BACKOFF_MS: u64 = 200;
+pub const MAX_RETRY_BACKOFF: std::time::Duration = std::time::Duration::from_secs(30);
+
+// Prefix `rm --trash` moves objects under, one `<timestamp>/` batch per run
+pub const TRASH_PREFIX: &str = ".storify-trash/";
+
 // Progress related constants
 // Controls how often progress is printed (in multiples of buffer size)
 pub const PROGRESS_UPDATE_INTERVAL: u64 = 100;
@@ -10,3 +32,8 @@ pub const PROGRESS_UPDATE_INTERVAL: u64 = 100;
 pub const DEFAULT_FS_ROOT: &str = "./storage";
 pub const DEFAULT_HDFS_ROOT: &str = "/";
 pub const DEFAULT_COS_ENDPOINT: &str = "https://cos.myqcloud.com";
+// Unsigned (`--anonymous`) S3 buckets without an endpoint or region are looked up on AWS
+pub const DEFAULT_S3_ENDPOINT: &str = "https://s3.amazonaws.com";
+pub const DEFAULT_S3_REGION: &str = "us-east-1";
+// Public gateway `ipfs://<cid>/` paths are read through unless IPFS_GATEWAY is set
+pub const DEFAULT_IPFS_GATEWAY: &str = "https://ipfs.io";
@@ -0,0 +1,45 @@
+//! Endpoint failover for profiles that list several endpoints.
+//!
+//! Candidates are probed in order and the first endpoint that answers is remembered for the
+//! rest of the process, so later clients built from the same configuration skip the probes.
+
+use crate::config::StorageConfig;
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+
+/// Ordered endpoint candidates; `None` stands for the provider default endpoint.
+pub type Candidates = Vec<Option<String>>;
+
+static SELECTED: LazyLock<Mutex<HashMap<Candidates, Option<String>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Primary endpoint followed by the configured fallbacks, without duplicates.
+pub fn candidates(config: &StorageConfig) -> Candidates {
+    let mut candidates = vec![config.endpoint.clone()];
+    for endpoint in &config.fallback_endpoints {
+        let endpoint = Some(endpoint.clone());
+        if !candidates.contains(&endpoint) {
+            candidates.push(endpoint);
+        }
+    }
+    candidates
+}
+
+/// Endpoint that answered earlier in this process for the same candidate list.
+pub fn remembered(candidates: &Candidates) -> Option<Option<String>> {
+    SELECTED
+        .lock()
+        .ok()
+        .and_then(|selected| selected.get(candidates).cloned())
+}
+
+pub fn remember(candidates: &Candidates, endpoint: Option<String>) {
+    if let Ok(mut selected) = SELECTED.lock() {
+        selected.insert(candidates.clone(), endpoint);
+    }
+}
+
+/// Human-readable label for a candidate.
+pub fn label(endpoint: &Option<String>) -> &str {
+    endpoint.as_deref().unwrap_or("<provider default>")
+}
@@ -0,0 +1,305 @@
+//! Listing support for the read-only `http` provider.
+//!
+//! OpenDAL's http service can only stat and read, so this layer answers list calls itself:
+//! either from a manifest file (one key per line, relative to the root) or by fetching the
+//! directory URL and collecting the links of its HTML index page, as served by nginx/Apache
+//! autoindex, `python -m http.server` or IPFS gateways.
+
+use futures::future;
+use opendal::raw::oio::{self, Read};
+use opendal::raw::{
+    Access, Layer, LayeredAccess, OpList, OpRead, OpStat, RpList, RpRead, RpStat, RpWrite,
+};
+use opendal::raw::{AccessorInfo, OpWrite, RpDelete};
+use opendal::{EntryMode, Error, ErrorKind, Metadata, Result};
+use regex::Regex;
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+use std::sync::{Arc, LazyLock};
+use tokio::sync::OnceCell;
+
+static HREF: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?i)href\s*=\s*["']([^"'#?]*)"#).expect("valid href regex"));
+
+/// Adds listing to an http operator.
+#[derive(Clone)]
+pub struct HttpIndexLayer {
+    base_path: String,
+    manifest: Option<String>,
+}
+
+impl HttpIndexLayer {
+    /// `base_path` is the URL path of the operator root (endpoint path plus root), used to
+    /// resolve absolute links such as `/ipfs/<cid>/file`.
+    pub fn new(base_path: &str, manifest: Option<String>) -> Self {
+        let trimmed = base_path.trim_matches('/');
+        let base_path = if trimmed.is_empty() {
+            "/".to_string()
+        } else {
+            format!("/{trimmed}/")
+        };
+        Self {
+            base_path,
+            manifest,
+        }
+    }
+}
+
+impl<A: Access> Layer<A> for HttpIndexLayer {
+    type LayeredAccess = HttpIndexAccessor<A>;
+
+    fn layer(&self, inner: A) -> Self::LayeredAccess {
+        let info = inner.info();
+        info.update_full_capability(|mut cap| {
+            cap.list = true;
+            cap.list_with_recursive = true;
+            cap
+        });
+        HttpIndexAccessor {
+            inner,
+            base_path: self.base_path.clone(),
+            manifest: self.manifest.clone(),
+            manifest_keys: Arc::new(OnceCell::new()),
+        }
+    }
+}
+
+pub struct HttpIndexAccessor<A> {
+    inner: A,
+    base_path: String,
+    manifest: Option<String>,
+    manifest_keys: Arc<OnceCell<Vec<String>>>,
+}
+
+impl<A> fmt::Debug for HttpIndexAccessor<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HttpIndexAccessor")
+            .field("base_path", &self.base_path)
+            .field("manifest", &self.manifest)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<A: Access> HttpIndexAccessor<A> {
+    async fn fetch_text(&self, path: &str) -> Result<String> {
+        let (_, mut reader) = self.inner.read(path, OpRead::new()).await?;
+        let buffer = reader.read_all().await?;
+        Ok(String::from_utf8_lossy(&buffer.to_vec()).into_owned())
+    }
+
+    async fn manifest_entries(
+        &self,
+        manifest: &str,
+        dir: &str,
+        recursive: bool,
+    ) -> Result<Vec<oio::Entry>> {
+        let keys = self
+            .manifest_keys
+            .get_or_try_init(|| async {
+                Ok::<_, Error>(parse_manifest(&self.fetch_text(manifest).await?))
+            })
+            .await?;
+        Ok(manifest_children(keys, dir, recursive))
+    }
+
+    /// Walk index pages breadth-first; only the first level unless `recursive`.
+    async fn index_entries(&self, dir: &str, recursive: bool) -> Result<Vec<oio::Entry>> {
+        let mut entries = Vec::new();
+        let mut pending = VecDeque::from([dir.to_string()]);
+        while let Some(current) = pending.pop_front() {
+            let page = self.fetch_text(&current).await?;
+            let dir_abs = format!("{}{current}", self.base_path);
+            for name in index_links(&page, &dir_abs) {
+                let path = format!("{current}{name}");
+                if name.ends_with('/') {
+                    if recursive {
+                        pending.push_back(path.clone());
+                    }
+                    entries.push(oio::Entry::new(&path, Metadata::new(EntryMode::DIR)));
+                } else {
+                    entries.push(oio::Entry::new(&path, Metadata::new(EntryMode::FILE)));
+                }
+            }
+        }
+        Ok(entries)
+    }
+}
+
+impl<A: Access> LayeredAccess for HttpIndexAccessor<A> {
+    type Inner = A;
+    type Reader = A::Reader;
+    type Writer = A::Writer;
+    type Lister = HttpIndexLister;
+    type Deleter = A::Deleter;
+
+    fn inner(&self) -> &Self::Inner {
+        &self.inner
+    }
+
+    fn info(&self) -> Arc<AccessorInfo> {
+        self.inner.info()
+    }
+
+    async fn read(&self, path: &str, args: OpRead) -> Result<(RpRead, Self::Reader)> {
+        self.inner.read(path, args).await
+    }
+
+    async fn write(&self, path: &str, args: OpWrite) -> Result<(RpWrite, Self::Writer)> {
+        self.inner.write(path, args).await
+    }
+
+    async fn stat(&self, path: &str, args: OpStat) -> Result<RpStat> {
+        self.inner.stat(path, args).await
+    }
+
+    async fn delete(&self) -> Result<(RpDelete, Self::Deleter)> {
+        self.inner.delete().await
+    }
+
+    async fn list(&self, path: &str, args: OpList) -> Result<(RpList, Self::Lister)> {
+        let dir = if path == "/" { "" } else { path };
+        if !dir.is_empty() && !dir.ends_with('/') {
+            return Err(Error::new(
+                ErrorKind::NotADirectory,
+                "http listing needs a directory path ending with '/'",
+            ));
+        }
+        let entries = match self.manifest.as_deref() {
+            Some(manifest) => {
+                self.manifest_entries(manifest, dir, args.recursive())
+                    .await?
+            }
+            None => self.index_entries(dir, args.recursive()).await?,
+        };
+        Ok((RpList::default(), HttpIndexLister(entries.into())))
+    }
+}
+
+/// Entries resolved up front by [`HttpIndexAccessor::list`].
+pub struct HttpIndexLister(VecDeque<oio::Entry>);
+
+impl oio::List for HttpIndexLister {
+    async fn next(&mut self) -> Result<Option<oio::Entry>> {
+        future::ready(Ok(self.0.pop_front())).await
+    }
+}
+
+/// Keys of a manifest: one per line, blank lines and `#` comments skipped.
+fn parse_manifest(text: &str) -> Vec<String> {
+    text.lines()
+        .map(|line| line.trim().trim_start_matches('/'))
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Manifest keys below `dir`; without `recursive`, deeper keys collapse into their
+/// first-level directory.
+fn manifest_children(keys: &[String], dir: &str, recursive: bool) -> Vec<oio::Entry> {
+    let mut seen_dirs = HashSet::new();
+    let mut entries = Vec::new();
+    for key in keys {
+        let Some(rest) = key.strip_prefix(dir).filter(|rest| !rest.is_empty()) else {
+            continue;
+        };
+        match rest.split_once('/') {
+            Some((child, _)) if !recursive => {
+                let path = format!("{dir}{child}/");
+                if seen_dirs.insert(path.clone()) {
+                    entries.push(oio::Entry::new(&path, Metadata::new(EntryMode::DIR)));
+                }
+            }
+            _ => entries.push(oio::Entry::new(key, Metadata::new(EntryMode::FILE))),
+        }
+    }
+    entries
+}
+
+/// Names (directories with a trailing '/') linked from an index page of `dir_abs`.
+///
+/// Links leaving the directory (parents, other hosts, sort/query links) are ignored.
+fn index_links(page: &str, dir_abs: &str) -> Vec<String> {
+    let mut seen = HashSet::new();
+    let mut names = Vec::new();
+    for capture in HREF.captures_iter(page) {
+        let href = &capture[1];
+        if href.contains("://") || href.starts_with("mailto:") {
+            continue;
+        }
+        let relative = match href.strip_prefix('/') {
+            Some(_) => match href.strip_prefix(dir_abs) {
+                Some(rest) => rest,
+                None => continue,
+            },
+            None => href.strip_prefix("./").unwrap_or(href),
+        };
+        let name = percent_decode(relative);
+        let single_segment = name.trim_end_matches('/');
+        if single_segment.is_empty()
+            || single_segment.starts_with("..")
+            || single_segment.contains('/')
+        {
+            continue;
+        }
+        if seen.insert(name.clone()) {
+            names.push(name);
+        }
+    }
+    names
+}
+
+/// Decode `%XX` escapes; malformed escapes are kept as they are.
+fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && let Some(hex) = input.get(i + 1..i + 3)
+            && let Ok(byte) = u8::from_str_radix(hex, 16)
+        {
+            out.push(byte);
+            i += 3;
+            continue;
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn index_links_keep_children_only() {
+        let page = r#"
+            <a href="../">Parent</a>
+            <a href="?C=M;O=A">Sort</a>
+            <a href="data%202024.csv">data 2024.csv</a>
+            <a href="images/">images/</a>
+            <a href="/ipfs/cid/docs/readme.md">readme.md</a>
+            <a href="/elsewhere/x">x</a>
+            <a href="https://example.com/">home</a>
+            <a href='images/'>again</a>
+        "#;
+        assert_eq!(
+            index_links(page, "/ipfs/cid/docs/"),
+            vec!["data 2024.csv", "images/", "readme.md"]
+        );
+    }
+
+    #[test]
+    fn manifest_children_collapse_directories() {
+        let keys = parse_manifest("# dataset\n/a/1.csv\na/b/2.csv\n\nc.txt\n");
+        let paths = |entries: Vec<oio::Entry>| -> Vec<String> {
+            entries.iter().map(|e| e.path().to_string()).collect()
+        };
+        assert_eq!(paths(manifest_children(&keys, "", false)), ["a/", "c.txt"]);
+        assert_eq!(
+            paths(manifest_children(&keys, "a/", true)),
+            ["a/1.csv", "a/b/2.csv"]
+        );
+    }
+}
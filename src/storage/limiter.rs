@@ -0,0 +1,175 @@
+//! Concurrency budget shared by every client of one invocation.
+//!
+//! Two things are bounded by the same `--max-concurrency` value:
+//! - storage HTTP requests in flight, through a permit held from sending a request until its
+//!   response headers arrive (bodies stream without one, so a copy that reads while it
+//!   writes cannot deadlock on its own permits);
+//! - the width of each task pipeline (upload files, download parts, `ls -L` stats, job
+//!   steps, touches), which also bounds open files and connections on local providers.
+//!
+//! A [`RateLimit`] additionally spaces the requests of one client evenly in time
+//! (`--rate-limit`), e.g. to stay below a provider's per-second request quota.
+
+use opendal::Buffer;
+use opendal::raw::{Access, HttpBody, HttpClient, HttpFetch, Layer};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Semaphore;
+use tokio::time::Instant;
+
+/// Permits for one invocation; clones share the same budget.
+#[derive(Debug, Clone)]
+pub struct ConcurrencyLimit {
+    permits: Arc<Semaphore>,
+    max: usize,
+}
+
+impl ConcurrencyLimit {
+    pub fn new(max: usize) -> Self {
+        let max = max.max(1);
+        Self {
+            permits: Arc::new(Semaphore::new(max)),
+            max,
+        }
+    }
+
+    /// Width for a pipeline that asked for `requested` concurrent tasks.
+    pub fn cap(&self, requested: usize) -> usize {
+        requested.clamp(1, self.max)
+    }
+}
+
+/// Routes an operator's HTTP requests through the budget of a [`ConcurrencyLimit`].
+#[derive(Clone)]
+pub struct ConcurrencyLimitLayer {
+    limit: ConcurrencyLimit,
+}
+
+impl ConcurrencyLimitLayer {
+    pub fn new(limit: ConcurrencyLimit) -> Self {
+        Self { limit }
+    }
+}
+
+impl<A: Access> Layer<A> for ConcurrencyLimitLayer {
+    type LayeredAccess = A;
+
+    fn layer(&self, inner: A) -> Self::LayeredAccess {
+        inner.info().update_http_client(|client| {
+            HttpClient::with(LimitedFetcher {
+                inner: client,
+                permits: self.limit.permits.clone(),
+            })
+        });
+        inner
+    }
+}
+
+struct LimitedFetcher {
+    inner: HttpClient,
+    permits: Arc<Semaphore>,
+}
+
+impl HttpFetch for LimitedFetcher {
+    async fn fetch(&self, req: http::Request<Buffer>) -> opendal::Result<http::Response<HttpBody>> {
+        let _permit = self
+            .permits
+            .acquire()
+            .await
+            .expect("concurrency semaphore is never closed");
+        self.inner.fetch(req).await
+    }
+}
+
+/// Requests per second allowed to one client; clones share the same schedule.
+#[derive(Debug, Clone)]
+pub struct RateLimit {
+    interval: Duration,
+    /// Earliest time the next request may be sent
+    next: Arc<Mutex<Option<Instant>>>,
+}
+
+impl RateLimit {
+    pub fn new(per_second: u32) -> Self {
+        Self {
+            interval: Duration::from_secs(1) / per_second.max(1),
+            next: Arc::default(),
+        }
+    }
+
+    /// Reserve the next free slot and return when it starts.
+    fn reserve(&self, now: Instant) -> Instant {
+        let mut next = self.next.lock().unwrap_or_else(|e| e.into_inner());
+        let slot = next.map_or(now, |next| next.max(now));
+        *next = Some(slot + self.interval);
+        slot
+    }
+
+    async fn wait(&self) {
+        tokio::time::sleep_until(self.reserve(Instant::now())).await;
+    }
+}
+
+/// Holds an operator's HTTP requests back until their [`RateLimit`] slot.
+#[derive(Clone)]
+pub struct RateLimitLayer {
+    limit: RateLimit,
+}
+
+impl RateLimitLayer {
+    pub fn new(limit: RateLimit) -> Self {
+        Self { limit }
+    }
+}
+
+impl<A: Access> Layer<A> for RateLimitLayer {
+    type LayeredAccess = A;
+
+    fn layer(&self, inner: A) -> Self::LayeredAccess {
+        inner.info().update_http_client(|client| {
+            HttpClient::with(PacedFetcher {
+                inner: client,
+                limit: self.limit.clone(),
+            })
+        });
+        inner
+    }
+}
+
+struct PacedFetcher {
+    inner: HttpClient,
+    limit: RateLimit,
+}
+
+impl HttpFetch for PacedFetcher {
+    async fn fetch(&self, req: http::Request<Buffer>) -> opendal::Result<http::Response<HttpBody>> {
+        self.limit.wait().await;
+        self.inner.fetch(req).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cap_stays_within_budget() {
+        let limit = ConcurrencyLimit::new(4);
+        assert_eq!(limit.cap(16), 4);
+        assert_eq!(limit.cap(2), 2);
+        assert_eq!(limit.cap(0), 1);
+        assert_eq!(ConcurrencyLimit::new(0).cap(8), 1);
+    }
+
+    #[test]
+    fn rate_limit_spaces_requests() {
+        let limit = RateLimit::new(4);
+        let start = Instant::now();
+        assert_eq!(limit.reserve(start), start);
+        assert_eq!(limit.reserve(start), start + Duration::from_millis(250));
+        assert_eq!(limit.reserve(start), start + Duration::from_millis(500));
+        // An idle client does not bank slots for a later burst.
+        let later = start + Duration::from_secs(5);
+        assert_eq!(limit.reserve(later), later);
+    }
+}
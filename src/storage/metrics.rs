@@ -0,0 +1,287 @@
+//! Per-run transfer counters, the `--stats` summary and their export in the Prometheus
+//! textfile format.
+//!
+//! Counters are fed by OpenDAL's metrics and retry layers and by a wrapper around the HTTP
+//! client, so every operation is measured without the operations themselves knowing about it. The textfile is meant for node_exporter's textfile
+//! collector: it is rewritten atomically after each run and only holds gauges describing
+//! that run.
+
+use crate::error::Result;
+use crate::scratch::StagedFile;
+use crate::storage::utils::size::format_size;
+use opendal::layers::RetryInterceptor;
+use opendal::layers::observe::{MetricLabels, MetricValue, MetricsIntercept};
+use opendal::raw::{Access, HttpBody, HttpClient, HttpFetch, Layer};
+use opendal::{Buffer, Error};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Bytes moved, requests sent and operations failed through the clients of one run.
+#[derive(Debug, Clone, Default)]
+pub struct TransferCounters {
+    inner: Arc<Counters>,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    read_bytes: AtomicU64,
+    written_bytes: AtomicU64,
+    operation_errors: AtomicU64,
+    requests: AtomicU64,
+    retries: AtomicU64,
+}
+
+impl TransferCounters {
+    pub fn read_bytes(&self) -> u64 {
+        self.inner.read_bytes.load(Ordering::Relaxed)
+    }
+
+    pub fn written_bytes(&self) -> u64 {
+        self.inner.written_bytes.load(Ordering::Relaxed)
+    }
+
+    pub fn operation_errors(&self) -> u64 {
+        self.inner.operation_errors.load(Ordering::Relaxed)
+    }
+
+    /// HTTP requests sent, retries included
+    pub fn requests(&self) -> u64 {
+        self.inner.requests.load(Ordering::Relaxed)
+    }
+
+    /// Operations repeated after a transient failure
+    pub fn retries(&self) -> u64 {
+        self.inner.retries.load(Ordering::Relaxed)
+    }
+
+    /// One-line summary printed by `--stats`.
+    pub fn summary(&self, elapsed: Duration) -> String {
+        format!(
+            "stats: read {}, written {}, {} request(s), {} retr{}, {:.2}s elapsed",
+            format_size(self.read_bytes()),
+            format_size(self.written_bytes()),
+            self.requests(),
+            self.retries(),
+            if self.retries() == 1 { "y" } else { "ies" },
+            elapsed.as_secs_f64()
+        )
+    }
+}
+
+impl MetricsIntercept for TransferCounters {
+    fn observe(&self, labels: MetricLabels, value: MetricValue) {
+        match value {
+            MetricValue::OperationBytes(bytes) if labels.operation == "read" => {
+                self.inner.read_bytes.fetch_add(bytes, Ordering::Relaxed);
+            }
+            MetricValue::OperationBytes(bytes) if labels.operation == "write" => {
+                self.inner.written_bytes.fetch_add(bytes, Ordering::Relaxed);
+            }
+            MetricValue::OperationErrorsTotal => {
+                self.inner.operation_errors.fetch_add(1, Ordering::Relaxed);
+            }
+            _ => {}
+        }
+    }
+}
+
+impl RetryInterceptor for TransferCounters {
+    fn intercept(&self, err: &Error, dur: Duration) {
+        self.inner.retries.fetch_add(1, Ordering::Relaxed);
+        tracing::warn!("will retry after {:.3}s because: {err}", dur.as_secs_f64());
+    }
+}
+
+/// Counts every HTTP request an operator sends into its [`TransferCounters`].
+#[derive(Clone)]
+pub struct RequestCountLayer {
+    counters: TransferCounters,
+}
+
+impl RequestCountLayer {
+    pub fn new(counters: TransferCounters) -> Self {
+        Self { counters }
+    }
+
+    /// Count the requests of `client`; installed again whenever the client is replaced.
+    pub fn http_client(&self, client: HttpClient) -> HttpClient {
+        HttpClient::with(CountedFetcher {
+            inner: client,
+            counters: self.counters.clone(),
+        })
+    }
+}
+
+impl<A: Access> Layer<A> for RequestCountLayer {
+    type LayeredAccess = A;
+
+    fn layer(&self, inner: A) -> Self::LayeredAccess {
+        inner
+            .info()
+            .update_http_client(|client| self.http_client(client));
+        inner
+    }
+}
+
+struct CountedFetcher {
+    inner: HttpClient,
+    counters: TransferCounters,
+}
+
+impl HttpFetch for CountedFetcher {
+    async fn fetch(&self, req: http::Request<Buffer>) -> opendal::Result<http::Response<HttpBody>> {
+        self.counters.inner.requests.fetch_add(1, Ordering::Relaxed);
+        self.inner.fetch(req).await
+    }
+}
+
+/// Outcome of one command run, as exported by `--metrics-textfile`.
+#[derive(Debug, Clone)]
+pub struct RunMetrics {
+    /// Distinguishes runs sharing a collector directory (the textfile stem)
+    pub task: String,
+    pub command: String,
+    pub success: bool,
+    pub duration: Duration,
+    pub read_bytes: u64,
+    pub written_bytes: u64,
+    pub operation_errors: u64,
+    pub finished_at: SystemTime,
+}
+
+impl RunMetrics {
+    /// Render the run in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let labels = format!(
+            "task=\"{}\",command=\"{}\"",
+            escape_label(&self.task),
+            escape_label(&self.command)
+        );
+        let finished_at = self
+            .finished_at
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs_f64();
+        let gauges = [
+            (
+                "storify_run_success",
+                "Whether the last run succeeded (1) or failed (0)",
+                u64::from(self.success) as f64,
+            ),
+            (
+                "storify_run_timestamp_seconds",
+                "Unix time the last run finished",
+                finished_at,
+            ),
+            (
+                "storify_run_duration_seconds",
+                "Wall-clock duration of the last run",
+                self.duration.as_secs_f64(),
+            ),
+            (
+                "storify_run_read_bytes",
+                "Bytes read from storage during the last run",
+                self.read_bytes as f64,
+            ),
+            (
+                "storify_run_written_bytes",
+                "Bytes written to storage during the last run",
+                self.written_bytes as f64,
+            ),
+            (
+                "storify_run_operation_errors",
+                "Storage operations that failed during the last run, including skipped objects",
+                self.operation_errors as f64,
+            ),
+        ];
+
+        let mut out = String::new();
+        for (name, help, value) in gauges {
+            let _ = writeln!(out, "# HELP {name} {help}");
+            let _ = writeln!(out, "# TYPE {name} gauge");
+            let _ = writeln!(out, "{name}{{{labels}}} {value}");
+        }
+        out
+    }
+
+    /// Replace `path` with the rendered metrics via a temp file and rename, so the
+    /// collector never reads a half-written file.
+    pub fn write_textfile(&self, path: &Path) -> Result<()> {
+        // node_exporter only reads `*.prom`, so the staging file is ignored until renamed.
+        let staged = StagedFile::new(path);
+        fs::write(staged.path(), self.render())?;
+        staged.persist(path)
+    }
+}
+
+/// Label value derived from a textfile path (`/var/lib/node_exporter/backup.prom` → `backup`).
+pub fn task_from_path(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "storify".to_string())
+}
+
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_and_writes_textfile() {
+        let metrics = RunMetrics {
+            task: "nightly \"backup\"".to_string(),
+            command: "put".to_string(),
+            success: false,
+            duration: Duration::from_millis(1500),
+            read_bytes: 0,
+            written_bytes: 2048,
+            operation_errors: 3,
+            finished_at: UNIX_EPOCH + Duration::from_secs(1_700_000_000),
+        };
+        let text = metrics.render();
+        let labels = r#"{task="nightly \"backup\"",command="put"}"#;
+        assert!(text.contains("# TYPE storify_run_success gauge"));
+        assert!(text.contains(&format!("storify_run_success{labels} 0\n")));
+        assert!(text.contains(&format!("storify_run_duration_seconds{labels} 1.5\n")));
+        assert!(text.contains(&format!("storify_run_written_bytes{labels} 2048\n")));
+        assert!(text.contains(&format!("storify_run_operation_errors{labels} 3\n")));
+        assert!(text.contains(&format!(
+            "storify_run_timestamp_seconds{labels} 1700000000\n"
+        )));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("storify.prom");
+        metrics.write_textfile(&path).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), text);
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+        assert_eq!(task_from_path(&path), "storify");
+    }
+
+    #[test]
+    fn summary_reports_counters() {
+        let counters = TransferCounters::default();
+        let labels = MetricLabels {
+            operation: "read",
+            ..Default::default()
+        };
+        counters.observe(labels, MetricValue::OperationBytes(3 * 1024 * 1024));
+        counters.intercept(
+            &Error::new(opendal::ErrorKind::Unexpected, "503"),
+            Duration::ZERO,
+        );
+        assert_eq!(
+            counters.summary(Duration::from_millis(1500)),
+            "stats: read 3.0M, written 0B, 0 request(s), 1 retry, 1.50s elapsed"
+        );
+    }
+}
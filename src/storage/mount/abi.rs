@@ -0,0 +1,397 @@
+//! The part of the FUSE kernel protocol (`<linux/fuse.h>`) a read-only filesystem needs.
+//!
+//! Requests read from `/dev/fuse` are decoded into [`Request`]s; replies are encoded into
+//! the native-endian layouts the kernel expects. Layouts follow protocol 7.31.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub const KERNEL_VERSION: u32 = 7;
+pub const KERNEL_MINOR_VERSION: u32 = 31;
+
+/// Node id of the mount root.
+pub const ROOT_ID: u64 = 1;
+
+/// Largest READ the kernel is allowed to send.
+pub const MAX_READ: u32 = 128 * 1024;
+
+/// Room for one request: the largest payload plus headers.
+pub const BUFFER_SIZE: usize = MAX_READ as usize + 4096;
+
+const IN_HEADER_LEN: usize = 40;
+const OUT_HEADER_LEN: usize = 16;
+
+/// `FUSE_ASYNC_READ`: reads of a file may be sent concurrently.
+const ASYNC_READ: u32 = 1 << 0;
+
+const S_IFDIR: u32 = 0o040000;
+const S_IFREG: u32 = 0o100000;
+
+/// Decoded request; opcodes a read-only filesystem does not implement end up in `Other`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Operation {
+    Init {
+        major: u32,
+        minor: u32,
+        max_readahead: u32,
+        flags: u32,
+    },
+    Destroy,
+    /// `name` is `None` when it is not UTF-8, which no object key can match
+    Lookup {
+        name: Option<String>,
+    },
+    Forget {
+        nlookup: u64,
+    },
+    BatchForget {
+        nodes: Vec<(u64, u64)>,
+    },
+    GetAttr,
+    Open {
+        flags: u32,
+    },
+    Read {
+        offset: u64,
+        size: u32,
+    },
+    Flush,
+    Release,
+    OpenDir,
+    ReadDir {
+        fh: u64,
+        offset: u64,
+        size: u32,
+    },
+    ReleaseDir {
+        fh: u64,
+    },
+    StatFs,
+    Interrupt,
+    /// Requests that would change the filesystem
+    Write,
+    Other(u32),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Request {
+    pub unique: u64,
+    pub nodeid: u64,
+    pub operation: Operation,
+}
+
+impl Request {
+    /// Decode one request as read from `/dev/fuse`; `None` when it is truncated.
+    pub fn parse(buf: &[u8]) -> Option<Self> {
+        let mut header = Fields::new(buf);
+        let len = header.u32()? as usize;
+        let opcode = header.u32()?;
+        let unique = header.u64()?;
+        let nodeid = header.u64()?;
+        let mut body = Fields::new(buf.get(IN_HEADER_LEN..len.min(buf.len()))?);
+        let operation = match opcode {
+            1 => Operation::Lookup { name: body.name()? },
+            2 => Operation::Forget {
+                nlookup: body.u64()?,
+            },
+            3 => Operation::GetAttr,
+            14 => Operation::Open { flags: body.u32()? },
+            15 => {
+                let _fh = body.u64()?;
+                Operation::Read {
+                    offset: body.u64()?,
+                    size: body.u32()?,
+                }
+            }
+            17 => Operation::StatFs,
+            18 => Operation::Release,
+            25 => Operation::Flush,
+            26 => Operation::Init {
+                major: body.u32()?,
+                minor: body.u32()?,
+                max_readahead: body.u32()?,
+                flags: body.u32()?,
+            },
+            27 => Operation::OpenDir,
+            28 => Operation::ReadDir {
+                fh: body.u64()?,
+                offset: body.u64()?,
+                size: body.u32()?,
+            },
+            29 => Operation::ReleaseDir { fh: body.u64()? },
+            36 => Operation::Interrupt,
+            38 => Operation::Destroy,
+            42 => {
+                let count = body.u32()?;
+                let _dummy = body.u32()?;
+                let nodes = (0..count)
+                    .map(|_| Some((body.u64()?, body.u64()?)))
+                    .collect::<Option<Vec<_>>>()?;
+                Operation::BatchForget { nodes }
+            }
+            // SETATTR, SYMLINK, MKNOD, MKDIR, UNLINK, RMDIR, RENAME, LINK, WRITE, SETXATTR,
+            // REMOVEXATTR, CREATE, FALLOCATE, RENAME2, COPY_FILE_RANGE
+            4 | 6 | 8..=13 | 16 | 21 | 24 | 35 | 43 | 45 | 47 => Operation::Write,
+            other => Operation::Other(other),
+        };
+        Some(Self {
+            unique,
+            nodeid,
+            operation,
+        })
+    }
+}
+
+/// Sequential reader of native-endian fields.
+struct Fields<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Fields<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        Self { buf }
+    }
+
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let (taken, rest) = self.buf.split_at_checked(len)?;
+        self.buf = rest;
+        Some(taken)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_ne_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_ne_bytes(self.take(8)?.try_into().ok()?))
+    }
+
+    /// NUL-terminated name.
+    fn name(&mut self) -> Option<Option<String>> {
+        let end = self.buf.iter().position(|&byte| byte == 0)?;
+        let name = self.take(end)?;
+        Some(String::from_utf8(name.to_vec()).ok())
+    }
+}
+
+/// Attributes of a file or directory, as reported to the kernel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Attr {
+    pub ino: u64,
+    pub size: u64,
+    pub mtime: SystemTime,
+    pub is_dir: bool,
+    pub uid: u32,
+    pub gid: u32,
+}
+
+impl Attr {
+    fn mode(&self) -> u32 {
+        if self.is_dir {
+            S_IFDIR | 0o555
+        } else {
+            S_IFREG | 0o444
+        }
+    }
+
+    fn encode(&self, out: &mut Vec<u8>) {
+        let mtime = self
+            .mtime
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or(Duration::ZERO);
+        out.extend_from_slice(&self.ino.to_ne_bytes());
+        out.extend_from_slice(&self.size.to_ne_bytes());
+        out.extend_from_slice(&self.size.div_ceil(512).to_ne_bytes());
+        // atime, mtime and ctime are all the object's modification time.
+        for _ in 0..3 {
+            out.extend_from_slice(&mtime.as_secs().to_ne_bytes());
+        }
+        for _ in 0..3 {
+            out.extend_from_slice(&mtime.subsec_nanos().to_ne_bytes());
+        }
+        let nlink: u32 = if self.is_dir { 2 } else { 1 };
+        for field in [self.mode(), nlink, self.uid, self.gid, 0, 4096, 0] {
+            out.extend_from_slice(&field.to_ne_bytes());
+        }
+    }
+}
+
+/// Reply carrying `error` (a positive errno) and no payload.
+pub fn error(unique: u64, errno: i32) -> Vec<u8> {
+    reply(unique, -errno, &[])
+}
+
+/// Successful reply with `payload`.
+pub fn ok(unique: u64, payload: &[u8]) -> Vec<u8> {
+    reply(unique, 0, payload)
+}
+
+fn reply(unique: u64, error: i32, payload: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(OUT_HEADER_LEN + payload.len());
+    out.extend_from_slice(&((OUT_HEADER_LEN + payload.len()) as u32).to_ne_bytes());
+    out.extend_from_slice(&error.to_ne_bytes());
+    out.extend_from_slice(&unique.to_ne_bytes());
+    out.extend_from_slice(payload);
+    out
+}
+
+/// `fuse_init_out`, trimmed to the layout kernels older than 7.23 expect.
+pub fn init_out(kernel_minor: u32, max_readahead: u32, kernel_flags: u32) -> Vec<u8> {
+    let mut out = Vec::with_capacity(64);
+    for field in [
+        KERNEL_VERSION,
+        KERNEL_MINOR_VERSION,
+        max_readahead,
+        kernel_flags & ASYNC_READ,
+    ] {
+        out.extend_from_slice(&field.to_ne_bytes());
+    }
+    // max_background, congestion_threshold
+    out.extend_from_slice(&16u16.to_ne_bytes());
+    out.extend_from_slice(&12u16.to_ne_bytes());
+    // max_write, time_gran
+    out.extend_from_slice(&MAX_READ.to_ne_bytes());
+    out.extend_from_slice(&1u32.to_ne_bytes());
+    out.resize(if kernel_minor < 23 { 24 } else { 64 }, 0);
+    out
+}
+
+/// `fuse_entry_out`; `attr` is `None` for a negative entry the kernel caches for `ttl`.
+pub fn entry_out(attr: Option<&Attr>, ttl: Duration) -> Vec<u8> {
+    let mut out = Vec::with_capacity(128);
+    out.extend_from_slice(&attr.map_or(0, |attr| attr.ino).to_ne_bytes());
+    out.extend_from_slice(&0u64.to_ne_bytes());
+    encode_ttl(&mut out, ttl);
+    match attr {
+        Some(attr) => attr.encode(&mut out),
+        None => out.resize(128, 0),
+    }
+    out
+}
+
+/// `fuse_attr_out`.
+pub fn attr_out(attr: &Attr, ttl: Duration) -> Vec<u8> {
+    let mut out = Vec::with_capacity(104);
+    out.extend_from_slice(&ttl.as_secs().to_ne_bytes());
+    out.extend_from_slice(&ttl.subsec_nanos().to_ne_bytes());
+    out.extend_from_slice(&0u32.to_ne_bytes());
+    attr.encode(&mut out);
+    out
+}
+
+/// Entry and attribute validity: both seconds, then both nanoseconds.
+fn encode_ttl(out: &mut Vec<u8>, ttl: Duration) {
+    out.extend_from_slice(&ttl.as_secs().to_ne_bytes());
+    out.extend_from_slice(&ttl.as_secs().to_ne_bytes());
+    out.extend_from_slice(&ttl.subsec_nanos().to_ne_bytes());
+    out.extend_from_slice(&ttl.subsec_nanos().to_ne_bytes());
+}
+
+/// `fuse_open_out`.
+pub fn open_out(fh: u64) -> Vec<u8> {
+    let mut out = fh.to_ne_bytes().to_vec();
+    out.resize(16, 0);
+    out
+}
+
+/// `fuse_statfs_out` of a filesystem without capacity figures.
+pub fn statfs_out() -> Vec<u8> {
+    let mut out = vec![0; 40];
+    // bsize, namelen (the S3 key limit), frsize
+    for field in [4096u32, 1024, 4096] {
+        out.extend_from_slice(&field.to_ne_bytes());
+    }
+    out.resize(80, 0);
+    out
+}
+
+/// Append a `fuse_dirent` to `out` unless it would grow past `limit` bytes.
+/// `next` is the offset a READDIR continuing after this entry asks for; the entry type is
+/// the `DT_*` value matching its mode.
+pub fn push_dirent(
+    out: &mut Vec<u8>,
+    limit: usize,
+    ino: u64,
+    is_dir: bool,
+    next: u64,
+    name: &str,
+) -> bool {
+    let len = (24 + name.len()).next_multiple_of(8);
+    if out.len() + len > limit {
+        return false;
+    }
+    let start = out.len();
+    let kind = if is_dir { S_IFDIR } else { S_IFREG } >> 12;
+    out.extend_from_slice(&ino.to_ne_bytes());
+    out.extend_from_slice(&next.to_ne_bytes());
+    out.extend_from_slice(&(name.len() as u32).to_ne_bytes());
+    out.extend_from_slice(&kind.to_ne_bytes());
+    out.extend_from_slice(name.as_bytes());
+    out.resize(start + len, 0);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(opcode: u32, body: &[u8]) -> Vec<u8> {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&((IN_HEADER_LEN + body.len()) as u32).to_ne_bytes());
+        buf.extend_from_slice(&opcode.to_ne_bytes());
+        buf.extend_from_slice(&7u64.to_ne_bytes());
+        buf.extend_from_slice(&ROOT_ID.to_ne_bytes());
+        buf.resize(IN_HEADER_LEN, 0);
+        buf.extend_from_slice(body);
+        buf
+    }
+
+    #[test]
+    fn requests_decode_and_replies_match_kernel_layouts() {
+        let lookup = Request::parse(&request(1, b"logs\0")).unwrap();
+        assert_eq!(lookup.unique, 7);
+        assert_eq!(lookup.nodeid, ROOT_ID);
+        assert_eq!(
+            lookup.operation,
+            Operation::Lookup {
+                name: Some("logs".to_string())
+            }
+        );
+
+        let mut read = Vec::new();
+        read.extend_from_slice(&3u64.to_ne_bytes());
+        read.extend_from_slice(&4096u64.to_ne_bytes());
+        read.extend_from_slice(&512u32.to_ne_bytes());
+        assert_eq!(
+            Request::parse(&request(15, &read)).unwrap().operation,
+            Operation::Read {
+                offset: 4096,
+                size: 512
+            }
+        );
+        assert_eq!(Request::parse(&request(15, &read[..10])), None);
+        assert_eq!(
+            Request::parse(&request(9, b"new\0")).unwrap().operation,
+            Operation::Write
+        );
+
+        let attr = Attr {
+            ino: 2,
+            size: 1000,
+            mtime: UNIX_EPOCH,
+            is_dir: false,
+            uid: 0,
+            gid: 0,
+        };
+        assert_eq!(entry_out(Some(&attr), Duration::from_secs(1)).len(), 128);
+        assert_eq!(entry_out(None, Duration::from_secs(1)).len(), 128);
+        assert_eq!(attr_out(&attr, Duration::from_secs(1)).len(), 104);
+        assert_eq!(init_out(31, 0, 0).len(), 64);
+        assert_eq!(statfs_out().len(), 80);
+        assert_eq!(ok(7, &open_out(0)).len(), 32);
+
+        let mut dirents = Vec::new();
+        assert!(push_dirent(&mut dirents, 48, 2, false, 1, "a.txt"));
+        assert_eq!(dirents.len(), 32);
+        assert!(!push_dirent(&mut dirents, 48, 3, false, 2, "b.txt"));
+    }
+}
@@ -0,0 +1,228 @@
+//! Mounting, unmounting, and the `/dev/fuse` connection of a mount.
+//!
+//! root mounts directly with mount(2); other users go through the setuid `fusermount3`
+//! (or `fusermount`) helper, which passes the opened `/dev/fuse` descriptor back over a
+//! socket, as libfuse does.
+
+use std::ffi::CString;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const FUSERMOUNT: [&str; 2] = ["fusermount3", "fusermount"];
+
+/// How the mount was made, and so how to undo it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Mounter {
+    Syscall,
+    Helper(&'static str),
+}
+
+/// An established mount and the connection the kernel sends its requests on.
+#[derive(Debug)]
+pub struct Channel {
+    device: File,
+    mountpoint: PathBuf,
+    mounter: Mounter,
+}
+
+impl Channel {
+    /// Mount a read-only FUSE filesystem on `mountpoint`.
+    pub fn mount(mountpoint: &Path, allow_other: bool) -> io::Result<Self> {
+        let mountpoint = mountpoint.canonicalize().map_err(|e| {
+            io::Error::new(
+                e.kind(),
+                format!("mount point {}: {e}", mountpoint.display()),
+            )
+        })?;
+        if !mountpoint.is_dir() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotADirectory,
+                format!("{} is not a directory", mountpoint.display()),
+            ));
+        }
+        let (device, mounter) = match mount_syscall(&mountpoint, allow_other) {
+            Ok(device) => (device, Mounter::Syscall),
+            Err(e) if e.raw_os_error() == Some(libc::EPERM) => {
+                mount_helper(&mountpoint, allow_other)?
+            }
+            Err(e) => return Err(e),
+        };
+        Ok(Self {
+            device,
+            mountpoint,
+            mounter,
+        })
+    }
+
+    pub fn mountpoint(&self) -> &Path {
+        &self.mountpoint
+    }
+
+    /// Read the next request into `buf`; `None` once the filesystem has been unmounted.
+    pub fn receive(&self, buf: &mut [u8]) -> io::Result<Option<usize>> {
+        loop {
+            match (&self.device).read(buf) {
+                Ok(len) => return Ok(Some(len)),
+                Err(e) => match e.raw_os_error() {
+                    // The request was interrupted before it was read, or no request is ready.
+                    Some(libc::ENOENT | libc::EINTR | libc::EAGAIN) => continue,
+                    Some(libc::ENODEV) => return Ok(None),
+                    _ => return Err(e),
+                },
+            }
+        }
+    }
+
+    /// Send one reply; the kernel takes each reply as a single write.
+    pub fn send(&self, reply: &[u8]) -> io::Result<()> {
+        let written = (&self.device).write(reply)?;
+        if written != reply.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::WriteZero,
+                "short write to /dev/fuse",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Detach the mount; the kernel ends the connection once no file on it is open.
+    pub fn unmount(&self) -> io::Result<()> {
+        match self.mounter {
+            Mounter::Syscall => {
+                let target = c_path(&self.mountpoint)?;
+                // SAFETY: `target` is a valid NUL-terminated path.
+                if unsafe { libc::umount2(target.as_ptr(), libc::MNT_DETACH) } != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(())
+            }
+            Mounter::Helper(helper) => {
+                let status = Command::new(helper)
+                    .args(["-u", "-z", "--"])
+                    .arg(&self.mountpoint)
+                    .status()?;
+                if !status.success() {
+                    return Err(io::Error::other(format!("{helper} -u failed ({status})")));
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+fn mount_syscall(mountpoint: &Path, allow_other: bool) -> io::Result<File> {
+    let device = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/fuse")?;
+    // SAFETY: getuid and getgid cannot fail.
+    let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+    let mut data = format!(
+        "fd={},rootmode=40000,user_id={uid},group_id={gid},default_permissions",
+        device.as_raw_fd()
+    );
+    if allow_other {
+        data.push_str(",allow_other");
+    }
+    let target = c_path(mountpoint)?;
+    let data = CString::new(data).map_err(io::Error::other)?;
+    // SAFETY: all pointers are valid NUL-terminated strings for the duration of the call.
+    let result = unsafe {
+        libc::mount(
+            c"storify".as_ptr(),
+            target.as_ptr(),
+            c"fuse.storify".as_ptr(),
+            libc::MS_NOSUID | libc::MS_NODEV | libc::MS_RDONLY,
+            data.as_ptr().cast(),
+        )
+    };
+    if result != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(device)
+}
+
+fn mount_helper(mountpoint: &Path, allow_other: bool) -> io::Result<(File, Mounter)> {
+    let mut options =
+        "ro,nosuid,nodev,default_permissions,fsname=storify,subtype=storify".to_string();
+    if allow_other {
+        options.push_str(",allow_other");
+    }
+    let mut last_error = None;
+    for helper in FUSERMOUNT {
+        let mut fds = [0; 2];
+        // SAFETY: `fds` has room for the two descriptors socketpair returns.
+        if unsafe { libc::socketpair(libc::AF_UNIX, libc::SOCK_STREAM, 0, fds.as_mut_ptr()) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: socketpair succeeded, so both descriptors are open and owned by nobody else.
+        let (ours, theirs) =
+            unsafe { (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) };
+        let status = Command::new(helper)
+            .arg("-o")
+            .arg(&options)
+            .arg("--")
+            .arg(mountpoint)
+            .env("_FUSE_COMMFD", theirs.as_raw_fd().to_string())
+            .status();
+        drop(theirs);
+        match status {
+            Ok(status) if status.success() => {
+                return Ok((File::from(receive_fd(&ours)?), Mounter::Helper(helper)));
+            }
+            Ok(status) => {
+                last_error = Some(io::Error::other(format!("{helper} failed ({status})")));
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(last_error.unwrap_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            "mounting needs root or fusermount3 (from the fuse3 package)",
+        )
+    }))
+}
+
+/// The descriptor fusermount sends as `SCM_RIGHTS` ancillary data.
+fn receive_fd(socket: &OwnedFd) -> io::Result<OwnedFd> {
+    let mut byte = [0u8; 1];
+    let mut iov = libc::iovec {
+        iov_base: byte.as_mut_ptr().cast(),
+        iov_len: byte.len(),
+    };
+    let mut control = [0u8; 64];
+    // SAFETY: msghdr is plain data; zeroed is a valid empty header.
+    let mut message: libc::msghdr = unsafe { std::mem::zeroed() };
+    message.msg_iov = &mut iov;
+    message.msg_iovlen = 1;
+    message.msg_control = control.as_mut_ptr().cast();
+    message.msg_controllen = control.len() as _;
+    // SAFETY: `message` points at buffers that outlive the call.
+    if unsafe { libc::recvmsg(socket.as_raw_fd(), &mut message, 0) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: the control buffer was filled in by recvmsg and is still alive.
+    unsafe {
+        let header = libc::CMSG_FIRSTHDR(&message);
+        if header.is_null()
+            || (*header).cmsg_level != libc::SOL_SOCKET
+            || (*header).cmsg_type != libc::SCM_RIGHTS
+        {
+            return Err(io::Error::other(
+                "fusermount did not pass a /dev/fuse descriptor",
+            ));
+        }
+        let fd = std::ptr::read_unaligned(libc::CMSG_DATA(header).cast::<libc::c_int>());
+        Ok(OwnedFd::from_raw_fd(fd))
+    }
+}
+
+fn c_path(path: &Path) -> io::Result<CString> {
+    CString::new(path.as_os_str().as_bytes()).map_err(io::Error::other)
+}
@@ -0,0 +1,383 @@
+//! Answers FUSE requests from a remote prefix, keeping the attributes it has seen for the
+//! attribute TTL so repeated `stat`s and lookups do not each cost a request.
+
+use futures::TryStreamExt;
+use opendal::{ErrorKind, Metadata, Operator};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+use super::abi::{self, Attr, Operation, ROOT_ID, Request};
+use crate::storage::utils::path::is_remote_directory;
+
+/// What is known about a file or directory.
+#[derive(Debug, Clone, Copy)]
+struct Meta {
+    is_dir: bool,
+    size: u64,
+    mtime: Option<SystemTime>,
+}
+
+impl Meta {
+    fn dir() -> Self {
+        Self {
+            is_dir: true,
+            size: 0,
+            mtime: None,
+        }
+    }
+
+    fn file(meta: &Metadata) -> Self {
+        Self {
+            is_dir: false,
+            size: meta.content_length(),
+            mtime: meta.last_modified().map(SystemTime::from),
+        }
+    }
+}
+
+struct Node {
+    /// Path under the operator root; directories end with `/`
+    path: String,
+    /// `None` until looked up when a listing did not carry sizes and times
+    meta: Option<Meta>,
+    fetched: Instant,
+    /// Kernel references; the node is dropped when they are all forgotten
+    lookups: u64,
+}
+
+#[derive(Default)]
+struct State {
+    nodes: HashMap<u64, Node>,
+    by_path: HashMap<String, u64>,
+    next_ino: u64,
+    /// Listings of open directories, by handle
+    dirs: HashMap<u64, Vec<(String, u64, bool)>>,
+    next_fh: u64,
+}
+
+impl State {
+    /// Node id of `path`, creating the node when it is new; `meta` refreshes what is known.
+    fn intern(&mut self, path: String, meta: Option<Meta>) -> u64 {
+        if let Some(&ino) = self.by_path.get(&path) {
+            if let (Some(node), Some(meta)) = (self.nodes.get_mut(&ino), meta) {
+                node.meta = Some(meta);
+                node.fetched = Instant::now();
+            }
+            return ino;
+        }
+        let ino = self.next_ino;
+        self.next_ino += 1;
+        self.by_path.insert(path.clone(), ino);
+        self.nodes.insert(
+            ino,
+            Node {
+                path,
+                meta,
+                fetched: Instant::now(),
+                lookups: 0,
+            },
+        );
+        ino
+    }
+
+    fn forget(&mut self, ino: u64, nlookup: u64) {
+        if ino == ROOT_ID {
+            return;
+        }
+        if let Some(node) = self.nodes.get_mut(&ino) {
+            node.lookups = node.lookups.saturating_sub(nlookup);
+            if node.lookups == 0 {
+                let path = node.path.clone();
+                self.nodes.remove(&ino);
+                self.by_path.remove(&path);
+            }
+        }
+    }
+}
+
+/// Read-only view of everything under a prefix.
+pub struct RemoteFs {
+    operator: Operator,
+    ttl: Duration,
+    uid: u32,
+    gid: u32,
+    /// Modification time reported for directories, which object stores do not keep
+    mounted_at: SystemTime,
+    state: Mutex<State>,
+}
+
+type Reply = std::result::Result<Vec<u8>, i32>;
+
+impl RemoteFs {
+    /// Serve `root` (empty for the whole bucket, otherwise ending with `/`).
+    pub fn new(operator: Operator, root: String, ttl: Duration) -> Self {
+        let mut state = State {
+            next_ino: ROOT_ID,
+            next_fh: 1,
+            ..State::default()
+        };
+        state.intern(root, Some(Meta::dir()));
+        // SAFETY: getuid and getgid cannot fail.
+        let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+        Self {
+            operator,
+            ttl,
+            uid,
+            gid,
+            mounted_at: SystemTime::now(),
+            state: Mutex::new(state),
+        }
+    }
+
+    /// Reply to `request`, or `None` for requests the kernel expects no reply to.
+    pub async fn handle(&self, request: Request) -> Option<Vec<u8>> {
+        let Request {
+            unique,
+            nodeid: ino,
+            operation,
+        } = request;
+        let reply = match operation {
+            Operation::Init {
+                major,
+                minor,
+                max_readahead,
+                flags,
+            } => {
+                if major != abi::KERNEL_VERSION {
+                    tracing::error!("unsupported FUSE protocol {major}.{minor}");
+                    Err(libc::EPROTO)
+                } else {
+                    Ok(abi::init_out(minor, max_readahead, flags))
+                }
+            }
+            Operation::Forget { nlookup } => {
+                self.state().forget(ino, nlookup);
+                return None;
+            }
+            Operation::BatchForget { nodes } => {
+                let mut state = self.state();
+                for (ino, nlookup) in nodes {
+                    state.forget(ino, nlookup);
+                }
+                return None;
+            }
+            Operation::Interrupt => return None,
+            Operation::Lookup { name } => self.lookup(ino, name.as_deref()).await,
+            Operation::GetAttr => self
+                .attr(ino)
+                .await
+                .map(|attr| abi::attr_out(&attr, self.ttl)),
+            Operation::Open { flags } => self.open(ino, flags),
+            Operation::Read { offset, size } => self.read(ino, offset, size).await,
+            Operation::OpenDir => self.open_dir(ino).await,
+            Operation::ReadDir { fh, offset, size } => self.read_dir(fh, offset, size),
+            Operation::ReleaseDir { fh } => {
+                self.state().dirs.remove(&fh);
+                Ok(Vec::new())
+            }
+            Operation::Release | Operation::Flush | Operation::Destroy => Ok(Vec::new()),
+            Operation::StatFs => Ok(abi::statfs_out()),
+            Operation::Write => Err(libc::EROFS),
+            Operation::Other(opcode) => {
+                tracing::trace!("unsupported FUSE opcode {opcode}");
+                Err(libc::ENOSYS)
+            }
+        };
+        Some(match reply {
+            Ok(payload) => abi::ok(unique, &payload),
+            Err(errno) => abi::error(unique, errno),
+        })
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn path_of(&self, ino: u64) -> std::result::Result<String, i32> {
+        self.state()
+            .nodes
+            .get(&ino)
+            .map(|node| node.path.clone())
+            .ok_or(libc::ENOENT)
+    }
+
+    fn to_attr(&self, ino: u64, meta: Meta) -> Attr {
+        Attr {
+            ino,
+            size: meta.size,
+            mtime: meta.mtime.unwrap_or(self.mounted_at),
+            is_dir: meta.is_dir,
+            uid: self.uid,
+            gid: self.gid,
+        }
+    }
+
+    /// Cached metadata of `path` if it is still fresh.
+    fn cached(&self, path: &str) -> Option<(u64, Meta)> {
+        let state = self.state();
+        let ino = *state.by_path.get(path)?;
+        let node = state.nodes.get(&ino)?;
+        let meta = node.meta.filter(|_| node.fetched.elapsed() < self.ttl)?;
+        Some((ino, meta))
+    }
+
+    /// Find `path` as a file, else as a directory.
+    async fn resolve(&self, path: &str) -> std::result::Result<Option<(String, Meta)>, i32> {
+        let dir_path = format!("{path}/");
+        match self.operator.stat(path).await {
+            Ok(meta) if meta.mode().is_file() => {
+                return Ok(Some((path.to_string(), Meta::file(&meta))));
+            }
+            Ok(meta) if meta.mode().is_dir() => return Ok(Some((dir_path, Meta::dir()))),
+            Ok(_) => {}
+            Err(e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => return Err(errno(&e)),
+        }
+        Ok(is_remote_directory(&self.operator, &dir_path)
+            .await
+            .then_some((dir_path, Meta::dir())))
+    }
+
+    async fn lookup(&self, parent: u64, name: Option<&str>) -> Reply {
+        let parent_path = self.path_of(parent)?;
+        let Some(name) = name.filter(|name| !name.is_empty() && *name != "." && *name != "..")
+        else {
+            return Err(libc::ENOENT);
+        };
+        let path = format!("{parent_path}{name}");
+        let found = match self
+            .cached(&path)
+            .or_else(|| self.cached(&format!("{path}/")))
+        {
+            Some((ino, meta)) => Some((ino, meta)),
+            None => self.resolve(&path).await?.map(|(path, meta)| {
+                let ino = self.state().intern(path, Some(meta));
+                (ino, meta)
+            }),
+        };
+        let Some((ino, meta)) = found else {
+            // A negative entry: the kernel remembers the name is missing for the TTL.
+            return Ok(abi::entry_out(None, self.ttl));
+        };
+        if let Some(node) = self.state().nodes.get_mut(&ino) {
+            node.lookups += 1;
+        }
+        Ok(abi::entry_out(Some(&self.to_attr(ino, meta)), self.ttl))
+    }
+
+    async fn attr(&self, ino: u64) -> std::result::Result<Attr, i32> {
+        let path = self.path_of(ino)?;
+        if let Some((_, meta)) = self.cached(&path) {
+            return Ok(self.to_attr(ino, meta));
+        }
+        let meta = if path.is_empty() || path.ends_with('/') {
+            Meta::dir()
+        } else {
+            match self.operator.stat(&path).await {
+                Ok(meta) => Meta::file(&meta),
+                Err(e) => return Err(errno(&e)),
+            }
+        };
+        self.state().intern(path, Some(meta));
+        Ok(self.to_attr(ino, meta))
+    }
+
+    fn open(&self, ino: u64, flags: u32) -> Reply {
+        if flags as i32 & libc::O_ACCMODE != libc::O_RDONLY {
+            return Err(libc::EROFS);
+        }
+        if self.path_of(ino)?.ends_with('/') {
+            return Err(libc::EISDIR);
+        }
+        Ok(abi::open_out(0))
+    }
+
+    async fn read(&self, ino: u64, offset: u64, size: u32) -> Reply {
+        let size = match self.attr(ino).await?.size.checked_sub(offset) {
+            Some(left) if left > 0 => left.min(u64::from(size)),
+            _ => return Ok(Vec::new()),
+        };
+        let path = self.path_of(ino)?;
+        self.operator
+            .read_with(&path)
+            .range(offset..offset + size)
+            .await
+            .map(|buffer| buffer.to_vec())
+            .map_err(|e| errno(&e))
+    }
+
+    async fn open_dir(&self, ino: u64) -> Reply {
+        let path = self.path_of(ino)?;
+        if !path.is_empty() && !path.ends_with('/') {
+            return Err(libc::ENOTDIR);
+        }
+        let entries: Vec<opendal::Entry> = self
+            .operator
+            .lister(&path)
+            .await
+            .map_err(|e| errno(&e))?
+            .try_collect()
+            .await
+            .map_err(|e| errno(&e))?;
+
+        let mut state = self.state();
+        let mut listing = vec![(".".to_string(), ino, true), ("..".to_string(), ino, true)];
+        for entry in entries {
+            let Some(name) = entry
+                .path()
+                .strip_prefix(path.as_str())
+                .map(|name| name.trim_end_matches('/'))
+                .filter(|name| !name.is_empty() && !name.contains('/'))
+            else {
+                continue;
+            };
+            let listed = entry.metadata();
+            let is_dir = listed.mode().is_dir();
+            // Listings of object stores carry sizes and times; others leave them to stat.
+            let meta = if is_dir {
+                Some(Meta::dir())
+            } else {
+                listed.last_modified().is_some().then(|| Meta::file(listed))
+            };
+            let child = state.intern(entry.path().to_string(), meta);
+            listing.push((name.to_string(), child, is_dir));
+        }
+        let fh = state.next_fh;
+        state.next_fh += 1;
+        state.dirs.insert(fh, listing);
+        Ok(abi::open_out(fh))
+    }
+
+    fn read_dir(&self, fh: u64, offset: u64, size: u32) -> Reply {
+        let state = self.state();
+        let listing = state.dirs.get(&fh).ok_or(libc::EBADF)?;
+        let mut out = Vec::new();
+        for (index, (name, ino, is_dir)) in listing.iter().enumerate().skip(offset as usize) {
+            if !abi::push_dirent(
+                &mut out,
+                size as usize,
+                *ino,
+                *is_dir,
+                index as u64 + 1,
+                name,
+            ) {
+                break;
+            }
+        }
+        Ok(out)
+    }
+}
+
+fn errno(err: &opendal::Error) -> i32 {
+    match err.kind() {
+        ErrorKind::NotFound => libc::ENOENT,
+        ErrorKind::PermissionDenied => libc::EACCES,
+        ErrorKind::IsADirectory => libc::EISDIR,
+        ErrorKind::NotADirectory => libc::ENOTDIR,
+        _ => {
+            tracing::warn!("remote request failed: {err}");
+            libc::EIO
+        }
+    }
+}
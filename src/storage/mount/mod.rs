@@ -0,0 +1,88 @@
+//! `storify mount`: a remote prefix as a read-only FUSE filesystem (Linux, `fuse` feature).
+//!
+//! The kernel protocol is spoken directly over `/dev/fuse` ([`abi`], [`channel`]) and every
+//! request is answered through the same OpenDAL operator the other commands use ([`fs`]),
+//! so retries, rate limits and `--stats` apply to mounted reads too.
+
+#[cfg(not(target_os = "linux"))]
+compile_error!("the `fuse` feature is only supported on Linux");
+
+mod abi;
+mod channel;
+mod fs;
+
+use crate::error::Result;
+use opendal::Operator;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::runtime::Handle;
+use tokio::task;
+
+use self::abi::{BUFFER_SIZE, Operation, Request};
+use self::channel::Channel;
+use self::fs::RemoteFs;
+
+#[derive(Debug, Clone)]
+pub struct MountOptions {
+    /// How long attributes and lookups (including misses) are cached
+    pub attr_ttl: Duration,
+    /// Let users other than the one mounting access the filesystem
+    pub allow_other: bool,
+}
+
+/// Serve `root` on `mountpoint` until it is unmounted or Ctrl-C is pressed.
+pub async fn mount(
+    operator: Operator,
+    root: String,
+    mountpoint: &Path,
+    options: &MountOptions,
+) -> Result<()> {
+    let channel = Arc::new(Channel::mount(mountpoint, options.allow_other)?);
+    let fs = Arc::new(RemoteFs::new(operator, root, options.attr_ttl));
+    println!(
+        "Mounted read-only at {}; press Ctrl-C or run `umount {}` to unmount",
+        channel.mountpoint().display(),
+        channel.mountpoint().display()
+    );
+
+    let handle = Handle::current();
+    let reader = {
+        let channel = Arc::clone(&channel);
+        task::spawn_blocking(move || -> std::io::Result<()> {
+            let mut buffer = vec![0u8; BUFFER_SIZE];
+            while let Some(len) = channel.receive(&mut buffer)? {
+                let Some(request) = Request::parse(&buffer[..len]) else {
+                    tracing::warn!("ignoring malformed FUSE request ({len} bytes)");
+                    continue;
+                };
+                let destroy = request.operation == Operation::Destroy;
+                let (fs, channel) = (Arc::clone(&fs), Arc::clone(&channel));
+                handle.spawn(async move {
+                    if let Some(reply) = fs.handle(request).await
+                        && let Err(e) = channel.send(&reply)
+                    {
+                        // The request was interrupted, or the filesystem is going away.
+                        tracing::debug!("FUSE reply not delivered: {e}");
+                    }
+                });
+                if destroy {
+                    break;
+                }
+            }
+            Ok(())
+        })
+    };
+
+    tokio::pin!(reader);
+    tokio::select! {
+        result = &mut reader => result.map_err(std::io::Error::other)??,
+        _ = tokio::signal::ctrl_c() => {
+            channel.unmount()?;
+            // The kernel ends the connection once the last open file is closed.
+            reader.await.map_err(std::io::Error::other)??;
+        }
+    }
+    println!("Unmounted {}", channel.mountpoint().display());
+    Ok(())
+}
@@ -0,0 +1,307 @@
+use crate::error::{Error, Result};
+use crate::storage::constants::{DEFAULT_BUFFER_SIZE, DEFAULT_CHUNK_SIZE};
+use futures::TryStreamExt;
+use opendal::{Buffer, ErrorKind, Metadata, Operator, Writer};
+use std::fmt;
+use std::io::Cursor;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// How an append reached the object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AppendStrategy {
+    /// The backend appended in place (OSS appendable objects, Azure append blobs, fs, HDFS).
+    Native,
+    /// The object was read and written back with the data added.
+    Rewrite,
+}
+
+impl fmt::Display for AppendStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            AppendStrategy::Native => "native append",
+            AppendStrategy::Rewrite => "rewritten",
+        })
+    }
+}
+
+/// Trait for appending data to the end of a file in storage
+pub trait Appender {
+    /// Append everything `reader` yields to the file at `path`, creating it when missing.
+    ///
+    /// Returns the bytes appended and how they were appended.
+    async fn append(
+        &self,
+        path: &str,
+        reader: impl AsyncRead + Unpin,
+    ) -> Result<(u64, AppendStrategy)>;
+}
+
+/// Implementation of Appender that appends in place when the backend can, and rewrites
+/// the object otherwise.
+pub struct OpenDalAppender {
+    operator: Operator,
+    native: bool,
+}
+
+impl OpenDalAppender {
+    pub fn new(operator: Operator) -> Self {
+        let native = operator.info().full_capability().write_can_append;
+        Self { operator, native }
+    }
+
+    /// Append `chunk` in a request of its own, which fails with `ConditionNotMatch` when
+    /// the existing object cannot be appended to (an OSS normal object, an Azure block blob).
+    async fn append_chunk(&self, path: &str, chunk: &[u8]) -> opendal::Result<()> {
+        self.operator
+            .write_with(path, chunk.to_vec())
+            .append(true)
+            .await?;
+        Ok(())
+    }
+
+    /// Write the existing content of `path` followed by `first` and the rest of `reader`.
+    ///
+    /// The write is conditional on the ETag the object had when read, where supported,
+    /// so a concurrent writer makes the append fail instead of being overwritten.
+    async fn rewrite(
+        &self,
+        path: &str,
+        existing: Option<&Metadata>,
+        first: &[u8],
+        reader: impl AsyncRead + Unpin,
+    ) -> Result<u64> {
+        let mut writer = self.operator.writer_with(path);
+        if let Some(meta) = existing {
+            if let Some(etag) = meta.etag()
+                && self.operator.info().full_capability().write_with_if_match
+            {
+                writer = writer.if_match(etag);
+            }
+            if let Some(content_type) = meta.content_type() {
+                writer = writer.content_type(content_type);
+            }
+            if let Some(metadata) = meta.user_metadata() {
+                writer = writer.user_metadata(metadata.clone());
+            }
+        }
+        let content = match existing {
+            Some(_) => Some(
+                self.operator
+                    .reader(path)
+                    .await?
+                    .into_bytes_stream(..)
+                    .await?,
+            ),
+            None => None,
+        };
+        let mut writer = writer.await?;
+        if let Some(mut content) = content {
+            while let Some(bytes) = content.try_next().await? {
+                writer.write(bytes).await?;
+            }
+        }
+        writer.write(first.to_vec()).await?;
+        let appended = first.len() as u64 + copy(reader, &mut writer).await?;
+        writer.close().await.map_err(|e| match e.kind() {
+            ErrorKind::ConditionNotMatch => Error::InvalidArgument {
+                message: format!("'{path}' changed while appending to it; nothing was written"),
+            },
+            _ => e.into(),
+        })?;
+        Ok(appended)
+    }
+}
+
+impl Appender for OpenDalAppender {
+    async fn append(
+        &self,
+        path: &str,
+        mut reader: impl AsyncRead + Unpin,
+    ) -> Result<(u64, AppendStrategy)> {
+        if path.is_empty() || path.ends_with('/') {
+            return Err(Error::InvalidArgument {
+                message: format!("append needs a file path, not a directory: '{path}'"),
+            });
+        }
+        let existing = match self.operator.stat(path).await {
+            Ok(meta) if meta.mode().is_dir() => {
+                return Err(Error::InvalidArgument {
+                    message: format!("'{path}' is a directory"),
+                });
+            }
+            Ok(meta) => Some(meta),
+            Err(e) if e.kind() == ErrorKind::NotFound => None,
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut buffer = vec![0u8; DEFAULT_CHUNK_SIZE];
+        let len = read_chunk(&mut reader, &mut buffer).await?;
+        let first = &buffer[..len];
+        if first.is_empty() {
+            if existing.is_none() {
+                self.operator.write(path, Buffer::new()).await?;
+            }
+            let strategy = if self.native {
+                AppendStrategy::Native
+            } else {
+                AppendStrategy::Rewrite
+            };
+            return Ok((0, strategy));
+        }
+
+        if !self.native {
+            let appended = self.rewrite(path, existing.as_ref(), first, reader).await?;
+            return Ok((appended, AppendStrategy::Rewrite));
+        }
+        // The first chunk tells whether this object takes appends; until it is written
+        // the input can still go through a rewrite.
+        match self.append_chunk(path, first).await {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::ConditionNotMatch && existing.is_some() => {
+                tracing::debug!("append {path}: not appendable in place ({e}); rewriting");
+                let appended = self.rewrite(path, existing.as_ref(), first, reader).await?;
+                return Ok((appended, AppendStrategy::Rewrite));
+            }
+            Err(e) => return Err(e.into()),
+        }
+        let mut appended = len as u64;
+        loop {
+            let len = read_chunk(&mut reader, &mut buffer).await?;
+            if len == 0 {
+                return Ok((appended, AppendStrategy::Native));
+            }
+            self.append_chunk(path, &buffer[..len]).await?;
+            appended += len as u64;
+        }
+    }
+}
+
+/// Read `sources` (local files, `-` for standard input) one after another with
+/// `separator` between them. Files are opened up front so a missing one fails before
+/// anything is appended, and each is streamed rather than read into memory.
+pub async fn concat_sources(
+    sources: &[String],
+    separator: &[u8],
+) -> Result<Box<dyn AsyncRead + Unpin + Send>> {
+    if sources.iter().filter(|source| *source == "-").count() > 1 {
+        return Err(Error::InvalidArgument {
+            message: "standard input ('-') can only be appended once".to_string(),
+        });
+    }
+    let mut reader: Option<Box<dyn AsyncRead + Unpin + Send>> = None;
+    for source in sources {
+        let next: Box<dyn AsyncRead + Unpin + Send> = match source.as_str() {
+            "-" => Box::new(tokio::io::stdin()),
+            local => Box::new(tokio::fs::File::open(local).await.map_err(|e| {
+                Error::InvalidArgument {
+                    message: format!("cannot open '{local}': {e}"),
+                }
+            })?),
+        };
+        reader = Some(match reader {
+            None => next,
+            Some(reader) => Box::new(reader.chain(Cursor::new(separator.to_vec())).chain(next)),
+        });
+    }
+    Ok(reader.unwrap_or_else(|| Box::new(tokio::io::empty())))
+}
+
+/// Fill `buffer` from `reader` as far as the input allows; returns the bytes read.
+async fn read_chunk(reader: &mut (impl AsyncRead + Unpin), buffer: &mut [u8]) -> Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        let read = reader.read(&mut buffer[filled..]).await?;
+        if read == 0 {
+            break;
+        }
+        filled += read;
+    }
+    Ok(filled)
+}
+
+/// Copy the rest of `reader` into `writer`; returns the bytes copied.
+async fn copy(mut reader: impl AsyncRead + Unpin, writer: &mut Writer) -> Result<u64> {
+    let mut buffer = vec![0u8; DEFAULT_BUFFER_SIZE];
+    let mut total = 0u64;
+    loop {
+        let read = reader.read(&mut buffer).await?;
+        if read == 0 {
+            return Ok(total);
+        }
+        writer.write(buffer[..read].to_vec()).await?;
+        total += read as u64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn append(appender: &OpenDalAppender, path: &str, data: &[u8]) -> AppendStrategy {
+        let (appended, strategy) = appender.append(path, data).await.unwrap();
+        assert_eq!(appended, data.len() as u64);
+        strategy
+    }
+
+    #[tokio::test]
+    async fn appends_natively_or_by_rewriting() {
+        let dir = tempfile::tempdir().unwrap();
+        let fs = Operator::new(opendal::services::Fs::default().root(dir.path().to_str().unwrap()))
+            .unwrap()
+            .finish();
+        let appender = OpenDalAppender::new(fs.clone());
+        assert_eq!(
+            append(&appender, "log.txt", b"one\n").await,
+            AppendStrategy::Native
+        );
+        append(&appender, "log.txt", b"two\n").await;
+        assert_eq!(fs.read("log.txt").await.unwrap().to_vec(), b"one\ntwo\n");
+
+        let memory = Operator::new(opendal::services::Memory::default())
+            .unwrap()
+            .finish();
+        memory.write("log.txt", b"one\n".to_vec()).await.unwrap();
+        let appender = OpenDalAppender::new(memory.clone());
+        assert_eq!(
+            append(&appender, "log.txt", b"two\n").await,
+            AppendStrategy::Rewrite
+        );
+        assert_eq!(
+            append(&appender, "empty", b"").await,
+            AppendStrategy::Rewrite
+        );
+        assert_eq!(
+            memory.read("log.txt").await.unwrap().to_vec(),
+            b"one\ntwo\n"
+        );
+        assert!(memory.exists("empty").await.unwrap());
+        assert!(appender.append("dir/", &b"x"[..]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn concatenates_sources_with_a_separator() {
+        let dir = tempfile::tempdir().unwrap();
+        let shard = |name: &str, content: &str| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, content).unwrap();
+            path.to_string_lossy().into_owned()
+        };
+        let sources = [shard("a.csv", "1,2"), shard("b.csv", "3,4")];
+        let mut merged = String::new();
+        concat_sources(&sources, b"\n")
+            .await
+            .unwrap()
+            .read_to_string(&mut merged)
+            .await
+            .unwrap();
+        assert_eq!(merged, "1,2\n3,4");
+
+        let missing = dir.path().join("missing").to_string_lossy().into_owned();
+        assert!(concat_sources(&[missing], b"").await.is_err());
+        assert!(
+            concat_sources(&["-".into(), "-".into()], b"")
+                .await
+                .is_err()
+        );
+    }
+}
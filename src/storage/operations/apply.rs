@@ -0,0 +1,345 @@
+use crate::apply::{JobStep, StepAction};
+use crate::error::{Error, Result};
+use crate::storage::constants::DEFAULT_CHUNK_SIZE;
+use crate::storage::utils::path::{basename, build_remote_path, ensure_trailing_slash};
+use futures::stream::{self, StreamExt, TryStreamExt};
+use opendal::{EntryMode, ErrorKind, Operator};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+/// A file selected under a step's source or destination.
+#[derive(Debug, Clone)]
+struct SelectedFile {
+    /// Path relative to the root it was listed under
+    relative: String,
+    /// Full remote key or local path
+    path: String,
+    size: u64,
+}
+
+/// One file a step will transfer.
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedTransfer {
+    pub source: String,
+    pub destination: String,
+    pub size: u64,
+}
+
+/// Everything a step would do, computed before anything changes.
+#[derive(Debug, Clone, Default)]
+pub struct StepPlan {
+    pub transfers: Vec<PlannedTransfer>,
+    /// Remote keys, or local paths for `download`
+    pub deletions: Vec<String>,
+    /// Files left alone because the destination already matches
+    pub skipped: usize,
+}
+
+impl StepPlan {
+    pub fn bytes(&self) -> u64 {
+        self.transfers.iter().map(|t| t.size).sum()
+    }
+}
+
+#[derive(Serialize)]
+struct Manifest<'a> {
+    step: &'a str,
+    action: &'a str,
+    generated_at: String,
+    files: &'a [PlannedTransfer],
+    deleted: &'a [String],
+}
+
+/// Trait for running the steps of a `storify apply` job.
+pub trait Applier {
+    /// Work out the transfers and deletions of `step` without changing anything.
+    async fn plan(&self, step: &JobStep) -> Result<StepPlan>;
+
+    /// Carry out a plan produced by [`Applier::plan`] and write the step's manifest.
+    async fn execute(&self, step: &JobStep, plan: &StepPlan) -> Result<()>;
+}
+
+/// Implementation of Applier for OpenDAL Operator.
+pub struct OpenDalApplier {
+    operator: Operator,
+}
+
+impl OpenDalApplier {
+    pub fn new(operator: Operator) -> Self {
+        Self { operator }
+    }
+
+    /// Files under a remote file or prefix that pass `step.filter`.
+    async fn select_remote(&self, root: &str, step: &JobStep) -> Result<Vec<SelectedFile>> {
+        if !root.ends_with('/') {
+            match self.operator.stat(root).await {
+                Ok(meta) if meta.mode() == EntryMode::FILE => {
+                    let relative = basename(root);
+                    return Ok(step
+                        .filter
+                        .matches(&relative)
+                        .then(|| SelectedFile {
+                            relative,
+                            path: root.to_string(),
+                            size: meta.content_length(),
+                        })
+                        .into_iter()
+                        .collect());
+                }
+                Ok(_) => {}
+                Err(e) if e.kind() == ErrorKind::NotFound => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        let prefix = ensure_trailing_slash(root.trim_start_matches('/'));
+        let prefix = if prefix == "/" { String::new() } else { prefix };
+        let mut lister = match self.operator.lister_with(&prefix).recursive(true).await {
+            Ok(lister) => lister,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut selected = Vec::new();
+        while let Some(entry) = lister.try_next().await? {
+            if entry.metadata().mode() != EntryMode::FILE {
+                continue;
+            }
+            let path = entry.path().to_string();
+            let relative = path
+                .trim_start_matches('/')
+                .strip_prefix(&prefix)
+                .unwrap_or(&path)
+                .to_string();
+            if !step.filter.matches(&relative) {
+                continue;
+            }
+            // Listings of some backends omit sizes.
+            let size = if entry.metadata().last_modified().is_none() {
+                self.operator.stat(&path).await?.content_length()
+            } else {
+                entry.metadata().content_length()
+            };
+            selected.push(SelectedFile {
+                relative,
+                path,
+                size,
+            });
+        }
+        Ok(selected)
+    }
+
+    /// Size of an existing remote object, `None` when absent.
+    async fn remote_size(&self, key: &str) -> Result<Option<u64>> {
+        match self.operator.stat(key).await {
+            Ok(meta) => Ok(Some(meta.content_length())),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn upload(&self, from: &str, to: &str) -> Result<()> {
+        let mut file = fs::File::open(from).await?;
+        let mut writer = self.operator.writer(to).await?;
+        let mut buffer = vec![0u8; DEFAULT_CHUNK_SIZE];
+        loop {
+            let read = file.read(&mut buffer).await?;
+            if read == 0 {
+                break;
+            }
+            writer.write(buffer[..read].to_vec()).await?;
+        }
+        writer.close().await?;
+        Ok(())
+    }
+
+    async fn download(&self, from: &str, to: &str) -> Result<()> {
+        if let Some(parent) = Path::new(to).parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        let reader = self
+            .operator
+            .reader_with(from)
+            .chunk(DEFAULT_CHUNK_SIZE)
+            .await?;
+        let mut bytes = reader.into_bytes_stream(..).await?;
+        let mut file = fs::File::create(to).await?;
+        while let Some(chunk) = bytes.try_next().await? {
+            file.write_all(&chunk).await?;
+        }
+        file.flush().await?;
+        Ok(())
+    }
+
+    async fn transfer(&self, action: StepAction, transfer: &PlannedTransfer) -> Result<()> {
+        match action {
+            StepAction::Upload => self.upload(&transfer.source, &transfer.destination).await,
+            StepAction::Download => self.download(&transfer.source, &transfer.destination).await,
+            StepAction::Copy => Ok(self
+                .operator
+                .copy(&transfer.source, &transfer.destination)
+                .await?),
+            StepAction::Delete => Ok(()),
+        }
+    }
+
+    async fn write_manifest(&self, step: &JobStep, plan: &StepPlan, path: &Path) -> Result<()> {
+        let manifest = Manifest {
+            step: &step.name,
+            action: step.action.as_str(),
+            generated_at: chrono::Utc::now().to_rfc3339(),
+            files: &plan.transfers,
+            deleted: &plan.deletions,
+        };
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent).await?;
+        }
+        fs::write(path, serde_json::to_vec_pretty(&manifest)?).await?;
+        Ok(())
+    }
+}
+
+/// Files under a local file or directory that pass `step.filter`.
+fn select_local(root: &Path, step: &JobStep) -> Result<Vec<SelectedFile>> {
+    let mut selected = Vec::new();
+    if root.is_file() {
+        let relative = root
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        if step.filter.matches(&relative) {
+            selected.push(SelectedFile {
+                relative,
+                path: root.to_string_lossy().into_owned(),
+                size: root.metadata()?.len(),
+            });
+        }
+        return Ok(selected);
+    }
+    if !root.is_dir() {
+        return Ok(selected);
+    }
+
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                pending.push(path);
+                continue;
+            }
+            let relative = path
+                .strip_prefix(root)
+                .unwrap_or(&path)
+                .to_string_lossy()
+                .replace('\\', "/");
+            if file_type.is_file() && step.filter.matches(&relative) {
+                selected.push(SelectedFile {
+                    relative,
+                    size: entry.metadata()?.len(),
+                    path: path.to_string_lossy().into_owned(),
+                });
+            }
+        }
+    }
+    selected.sort_by(|a, b| a.relative.cmp(&b.relative));
+    Ok(selected)
+}
+
+fn local_size(path: &Path) -> Option<u64> {
+    std::fs::metadata(path)
+        .ok()
+        .filter(|meta| meta.is_file())
+        .map(|meta| meta.len())
+}
+
+impl Applier for OpenDalApplier {
+    async fn plan(&self, step: &JobStep) -> Result<StepPlan> {
+        let mut plan = StepPlan::default();
+        let source = match step.action {
+            StepAction::Upload => select_local(Path::new(&step.source), step)?,
+            _ => self.select_remote(&step.source, step).await?,
+        };
+        let Some(destination) = step.destination.as_deref() else {
+            plan.deletions = source.into_iter().map(|file| file.path).collect();
+            return Ok(plan);
+        };
+
+        for file in &source {
+            let target = match step.action {
+                StepAction::Download => Path::new(destination)
+                    .join(&file.relative)
+                    .to_string_lossy()
+                    .into_owned(),
+                _ => build_remote_path(destination, &file.relative),
+            };
+            if step.skip_existing {
+                let existing = match step.action {
+                    StepAction::Download => local_size(Path::new(&target)),
+                    _ => self.remote_size(&target).await?,
+                };
+                if existing == Some(file.size) {
+                    plan.skipped += 1;
+                    continue;
+                }
+            }
+            plan.transfers.push(PlannedTransfer {
+                source: file.path.clone(),
+                destination: target,
+                size: file.size,
+            });
+        }
+
+        if step.prune {
+            let keep: HashSet<&str> = source.iter().map(|file| file.relative.as_str()).collect();
+            let existing = match step.action {
+                StepAction::Download => select_local(Path::new(destination), step)?,
+                _ => {
+                    self.select_remote(&ensure_trailing_slash(destination), step)
+                        .await?
+                }
+            };
+            plan.deletions = existing
+                .into_iter()
+                .filter(|file| !keep.contains(file.relative.as_str()))
+                .map(|file| file.path)
+                .collect();
+        }
+        Ok(plan)
+    }
+
+    async fn execute(&self, step: &JobStep, plan: &StepPlan) -> Result<()> {
+        stream::iter(&plan.transfers)
+            .map(|transfer| async move {
+                self.transfer(step.action, transfer).await?;
+                println!(
+                    "{}: {} → {}",
+                    step.action.as_str(),
+                    transfer.source,
+                    transfer.destination
+                );
+                Ok::<_, Error>(())
+            })
+            .buffer_unordered(step.concurrency)
+            .try_collect::<Vec<_>>()
+            .await?;
+
+        for path in &plan.deletions {
+            if step.action == StepAction::Download {
+                fs::remove_file(PathBuf::from(path)).await?;
+            } else {
+                self.operator.delete(path).await?;
+            }
+            println!("delete: {path}");
+        }
+
+        if let Some(manifest) = &step.manifest {
+            self.write_manifest(step, plan, manifest).await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,422 @@
+use crate::config::{ProviderBackend, StorageProvider};
+use crate::error::{Error, Result};
+use crate::storage::connection::ConnectionSettings;
+use crate::storage::rest::{RestBucket, content_md5, element, elements, escape_xml, unescape_xml};
+use crate::storage::retry::RetryPolicy;
+use regex::Regex;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::sync::LazyLock;
+
+/// CORS rule limit shared by S3, OSS and COS.
+pub const MAX_CORS_RULES: usize = 100;
+
+const CORS_METHODS: [&str; 5] = ["GET", "PUT", "POST", "DELETE", "HEAD"];
+
+static CORS_RULE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?s)<CORSRule>(.*?)</CORSRule>").expect("valid CORS regex"));
+
+/// Bucket-level settings managed by `storify bucket`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BucketSetting {
+    /// Access policy document (JSON, in the provider's policy language)
+    Policy,
+    /// Cross-origin rules for browsers
+    Cors,
+}
+
+impl BucketSetting {
+    /// Name used in messages.
+    pub fn name(&self) -> &'static str {
+        match self {
+            BucketSetting::Policy => "bucket policies",
+            BucketSetting::Cors => "bucket CORS rules",
+        }
+    }
+
+    fn subresource(&self) -> &'static str {
+        match self {
+            BucketSetting::Policy => "policy",
+            BucketSetting::Cors => "cors",
+        }
+    }
+
+    /// Error codes of providers that have nothing configured.
+    fn is_unset(&self, err: &Error) -> bool {
+        let Error::ProviderRequest { message } = err else {
+            return false;
+        };
+        match self {
+            BucketSetting::Policy => {
+                message.contains("NoSuchBucketPolicy") || message.contains("NoSuchPolicy")
+            }
+            BucketSetting::Cors => message.contains("NoSuchCORSConfiguration"),
+        }
+    }
+}
+
+/// CORS rules of a bucket, as read from a rule file or printed by `bucket cors get --json`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CorsConfig {
+    #[serde(default)]
+    pub rules: Vec<CorsRule>,
+}
+
+/// Which origins may send which requests.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CorsRule {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Origins such as `https://app.example.com`, or `*`
+    pub allowed_origins: Vec<String>,
+    /// Any of GET, PUT, POST, DELETE and HEAD
+    pub allowed_methods: Vec<String>,
+    /// Request headers browsers may send (`*` for any)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub allowed_headers: Vec<String>,
+    /// Response headers scripts may read, e.g. `ETag`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub expose_headers: Vec<String>,
+    /// How long browsers may cache the preflight response
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_age_secs: Option<u32>,
+}
+
+impl CorsConfig {
+    /// Read and validate a JSON or TOML rule file (chosen by extension, JSON otherwise).
+    pub fn load(path: &Path) -> Result<Self> {
+        let invalid = |message: String| Error::InvalidCorsRules {
+            path: path.to_path_buf(),
+            message,
+        };
+        let extension = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_ascii_lowercase());
+        let text = fs::read_to_string(path)?;
+        let config: Self = match extension.as_deref() {
+            Some("yaml" | "yml") => {
+                return Err(invalid(
+                    "YAML rule files are not supported; write the rules as JSON or TOML"
+                        .to_string(),
+                ));
+            }
+            Some("toml") => toml::from_str(&text).map_err(|e| invalid(e.message().to_string()))?,
+            _ => serde_json::from_str(&text).map_err(|e| invalid(e.to_string()))?,
+        };
+        config.validate().map_err(invalid)?;
+        Ok(config)
+    }
+
+    /// Describe the first problem providers would reject the rules for.
+    pub fn validate(&self) -> std::result::Result<(), String> {
+        if self.rules.is_empty() {
+            return Err("no rules defined".to_string());
+        }
+        if self.rules.len() > MAX_CORS_RULES {
+            return Err(format!(
+                "{} rules defined; buckets take at most {MAX_CORS_RULES}",
+                self.rules.len()
+            ));
+        }
+        for (index, rule) in self.rules.iter().enumerate() {
+            let name = rule.id.clone().unwrap_or_else(|| format!("#{}", index + 1));
+            if rule.allowed_origins.is_empty() {
+                return Err(format!("rule {name} has no allowed_origins"));
+            }
+            if rule.allowed_methods.is_empty() {
+                return Err(format!("rule {name} has no allowed_methods"));
+            }
+            if let Some(method) = rule
+                .allowed_methods
+                .iter()
+                .find(|method| !CORS_METHODS.contains(&method.to_ascii_uppercase().as_str()))
+            {
+                return Err(format!(
+                    "rule {name}: unsupported method '{method}' (expected {})",
+                    CORS_METHODS.join(", ")
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl CorsRule {
+    /// One-line summary for `bucket cors get`.
+    fn describe(&self) -> String {
+        let mut line = format!(
+            "{}  {}",
+            self.allowed_origins.join(","),
+            self.allowed_methods.join(",")
+        );
+        if !self.allowed_headers.is_empty() {
+            line.push_str(&format!("  headers={}", self.allowed_headers.join(",")));
+        }
+        if !self.expose_headers.is_empty() {
+            line.push_str(&format!("  expose={}", self.expose_headers.join(",")));
+        }
+        if let Some(max_age) = self.max_age_secs {
+            line.push_str(&format!("  max-age={max_age}s"));
+        }
+        match &self.id {
+            Some(id) => format!("{id}  {line}"),
+            None => line,
+        }
+    }
+}
+
+/// Trait for reading and replacing bucket-level configuration.
+pub trait BucketConfigurer {
+    /// Print the bucket policy as indented JSON.
+    async fn get_policy(&self) -> Result<()>;
+
+    /// Replace the bucket policy.
+    ///
+    /// # Arguments
+    /// * `policy` - Policy document; must be a JSON object
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or detailed error information
+    async fn set_policy(&self, policy: &str) -> Result<()>;
+
+    /// Print the bucket's CORS rules, one per line or as a JSON rule file.
+    ///
+    /// # Arguments
+    /// * `json` - Print the rules in the format `set_cors` reads
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or detailed error information
+    async fn get_cors(&self, json: bool) -> Result<()>;
+
+    /// Replace every CORS rule of the bucket.
+    async fn set_cors(&self, config: &CorsConfig) -> Result<()>;
+}
+
+/// Implementation of BucketConfigurer using the `?policy` and `?cors` sub-resources of the
+/// provider's REST API, which OpenDAL does not expose.
+pub struct RestBucketConfigurer {
+    bucket: RestBucket,
+}
+
+impl RestBucketConfigurer {
+    /// Create a configurer for `setting` on the configured bucket. Only S3, OSS and COS with
+    /// static credentials (or an AWS profile) are supported, and S3-compatible services
+    /// only where their compat preset says the setting exists.
+    pub fn from_backend(
+        provider: StorageProvider,
+        backend: &ProviderBackend,
+        setting: BucketSetting,
+        retry: RetryPolicy,
+        connection: &ConnectionSettings,
+    ) -> Result<Self> {
+        if let ProviderBackend::S3 {
+            compat: Some(preset),
+            ..
+        } = backend
+        {
+            let supported = match setting {
+                BucketSetting::Policy => preset.supports_bucket_policy(),
+                BucketSetting::Cors => preset.supports_cors(),
+            };
+            if !supported {
+                return Err(Error::InvalidArgument {
+                    message: format!(
+                        "{} are not available on {} buckets",
+                        setting.name(),
+                        preset.as_str()
+                    ),
+                });
+            }
+        }
+        Ok(Self {
+            bucket: RestBucket::from_backend(provider, backend, setting.name(), retry, connection)?,
+        })
+    }
+
+    /// Body of the setting, or `None` when the bucket has none.
+    async fn fetch(&self, setting: BucketSetting) -> Result<Option<String>> {
+        match self
+            .bucket
+            .send(Method::GET, "", Some(setting.subresource()), &[], None)
+            .await
+        {
+            Ok(response) => Ok(Some(response.body)),
+            Err(e) if setting.is_unset(&e) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn store(&self, setting: BucketSetting, body: String, content_type: &str) -> Result<()> {
+        let headers = [
+            content_md5(&body),
+            ("Content-Type".to_string(), content_type.to_string()),
+        ];
+        self.bucket
+            .send(
+                Method::PUT,
+                "",
+                Some(setting.subresource()),
+                &headers,
+                Some(body),
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+impl BucketConfigurer for RestBucketConfigurer {
+    async fn get_policy(&self) -> Result<()> {
+        let Some(policy) = self.fetch(BucketSetting::Policy).await? else {
+            println!("No bucket policy");
+            return Ok(());
+        };
+        match serde_json::from_str::<serde_json::Value>(&policy) {
+            Ok(value) => println!("{}", serde_json::to_string_pretty(&value)?),
+            Err(_) => println!("{}", policy.trim_end()),
+        }
+        Ok(())
+    }
+
+    async fn set_policy(&self, policy: &str) -> Result<()> {
+        let value: serde_json::Value =
+            serde_json::from_str(policy).map_err(|e| Error::InvalidArgument {
+                message: format!("the policy is not valid JSON: {e}"),
+            })?;
+        if !value.is_object() {
+            return Err(Error::InvalidArgument {
+                message: "the policy must be a JSON object".to_string(),
+            });
+        }
+        self.store(
+            BucketSetting::Policy,
+            policy.trim().to_string(),
+            "application/json",
+        )
+        .await?;
+        println!("Set bucket policy");
+        Ok(())
+    }
+
+    async fn get_cors(&self, json: bool) -> Result<()> {
+        let rules = self
+            .fetch(BucketSetting::Cors)
+            .await?
+            .map(|xml| parse_cors(&xml))
+            .unwrap_or_default();
+        if json {
+            let config = CorsConfig { rules };
+            println!("{}", serde_json::to_string_pretty(&config)?);
+        } else if rules.is_empty() {
+            println!("No CORS rules");
+        } else {
+            for rule in &rules {
+                println!("{}", rule.describe());
+            }
+        }
+        Ok(())
+    }
+
+    async fn set_cors(&self, config: &CorsConfig) -> Result<()> {
+        let xml = cors_xml(self.bucket.provider(), config);
+        self.store(BucketSetting::Cors, xml, "application/xml")
+            .await?;
+        println!("Set {} CORS rule(s)", config.rules.len());
+        Ok(())
+    }
+}
+
+/// `CORSConfiguration` document; OSS has no rule ids.
+fn cors_xml(provider: StorageProvider, config: &CorsConfig) -> String {
+    let mut xml = String::from("<CORSConfiguration>");
+    for rule in &config.rules {
+        xml.push_str("<CORSRule>");
+        if let Some(id) = rule
+            .id
+            .as_deref()
+            .filter(|_| provider != StorageProvider::Oss)
+        {
+            xml.push_str(&format!("<ID>{}</ID>", escape_xml(id)));
+        }
+        let lists = [
+            ("AllowedOrigin", &rule.allowed_origins),
+            ("AllowedMethod", &rule.allowed_methods),
+            ("AllowedHeader", &rule.allowed_headers),
+            ("ExposeHeader", &rule.expose_headers),
+        ];
+        for (name, values) in lists {
+            for value in values {
+                let value = if name == "AllowedMethod" {
+                    value.to_ascii_uppercase()
+                } else {
+                    value.clone()
+                };
+                xml.push_str(&format!("<{name}>{}</{name}>", escape_xml(&value)));
+            }
+        }
+        if let Some(max_age) = rule.max_age_secs {
+            xml.push_str(&format!("<MaxAgeSeconds>{max_age}</MaxAgeSeconds>"));
+        }
+        xml.push_str("</CORSRule>");
+    }
+    xml.push_str("</CORSConfiguration>");
+    xml
+}
+
+fn parse_cors(xml: &str) -> Vec<CorsRule> {
+    CORS_RULE
+        .captures_iter(xml)
+        .map(|capture| {
+            let body = &capture[1];
+            let list = |name: &str| {
+                elements(body, name)
+                    .into_iter()
+                    .map(unescape_xml)
+                    .collect::<Vec<_>>()
+            };
+            CorsRule {
+                id: element(body, "ID").map(unescape_xml),
+                allowed_origins: list("AllowedOrigin"),
+                allowed_methods: list("AllowedMethod"),
+                allowed_headers: list("AllowedHeader"),
+                expose_headers: list("ExposeHeader"),
+                max_age_secs: element(body, "MaxAgeSeconds")
+                    .and_then(|age| age.trim().parse().ok()),
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cors_rules_roundtrip_and_validate() {
+        let config: CorsConfig = serde_json::from_str(
+            r#"{"rules": [{"id": "web", "allowed_origins": ["https://app.example.com"],
+                "allowed_methods": ["get", "PUT"], "allowed_headers": ["*"],
+                "expose_headers": ["ETag"], "max_age_secs": 600}]}"#,
+        )
+        .unwrap();
+        assert_eq!(config.validate(), Ok(()));
+
+        let parsed = parse_cors(&cors_xml(StorageProvider::S3, &config));
+        let mut expected = config.rules[0].clone();
+        expected.allowed_methods[0] = "GET".to_string();
+        assert_eq!(parsed, vec![expected.clone()]);
+
+        expected.id = None;
+        assert_eq!(
+            parse_cors(&cors_xml(StorageProvider::Oss, &config)),
+            vec![expected]
+        );
+
+        let mut invalid = config.clone();
+        invalid.rules[0].allowed_methods.push("PATCH".to_string());
+        assert!(invalid.validate().unwrap_err().contains("PATCH"));
+    }
+}
@@ -1,48 +1,142 @@
+use crate::config::crypto::DataKey;
 use crate::error::{Error, Result};
-use crate::storage::constants::DEFAULT_CHUNK_SIZE;
+use crate::scratch::StagedFile;
+use crate::storage::constants::{DEFAULT_BUFFER_SIZE, DEFAULT_CHUNK_SIZE};
+use crate::storage::utils::cipher;
+use crate::storage::utils::codec::Compression;
+use futures::{StreamExt, TryStreamExt};
 use opendal::Operator;
+use std::fs::File;
 use std::io::IsTerminal;
-use std::io::{self, Write};
+use std::io::{self, BufWriter, Write};
+use std::ops::Range;
 use std::path::PathBuf;
+use std::str::FromStr;
+use tokio::io::AsyncReadExt;
+
+/// Bytes of an object to print, as given to `cat --range`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteRange {
+    /// `START-END` (inclusive) or `START-` (to the end of the object)
+    From { start: u64, end: Option<u64> },
+    /// `-N`: the last N bytes
+    Suffix(u64),
+}
+
+impl ByteRange {
+    /// Half-open range within an object of `size` bytes; the end is clamped to the size.
+    pub fn resolve(&self, size: u64) -> Result<Range<u64>> {
+        match *self {
+            ByteRange::From { start, end } => {
+                if start >= size && size > 0 {
+                    return Err(Error::InvalidArgument {
+                        message: format!(
+                            "range starts at byte {start} but the object has {size} bytes"
+                        ),
+                    });
+                }
+                let end = end.map_or(size, |end| end.saturating_add(1).min(size));
+                Ok(start.min(end)..end)
+            }
+            ByteRange::Suffix(len) => Ok(size.saturating_sub(len)..size),
+        }
+    }
+}
+
+impl FromStr for ByteRange {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || Error::InvalidArgument {
+            message: format!("invalid range '{s}' (expected START-END, START- or -LENGTH)"),
+        };
+        let (start, end) = s.trim().split_once('-').ok_or_else(invalid)?;
+        let parse = |n: &str| n.trim().parse::<u64>().map_err(|_| invalid());
+        match (start.trim().is_empty(), end.trim().is_empty()) {
+            (true, true) => Err(invalid()),
+            (true, false) => Ok(ByteRange::Suffix(parse(end)?)),
+            (false, true) => Ok(ByteRange::From {
+                start: parse(start)?,
+                end: None,
+            }),
+            (false, false) => {
+                let (start, end) = (parse(start)?, parse(end)?);
+                if end < start {
+                    return Err(invalid());
+                }
+                Ok(ByteRange::From {
+                    start,
+                    end: Some(end),
+                })
+            }
+        }
+    }
+}
+
+/// Settings for `storify cat`.
+#[derive(Debug, Clone, Default)]
+pub struct CatOptions {
+    /// Whether to bypass size-limit confirmation
+    pub force: bool,
+    /// Maximum bytes (in MB) printed per object before asking for confirmation; `0` disables the check.
+    /// Only applies when stdout is a terminal.
+    pub size_limit_mb: u64,
+    /// Only print these bytes of each object
+    pub range: Option<ByteRange>,
+    /// Write to this file instead of stdout (no size limit applies)
+    pub output: Option<PathBuf>,
+    /// Print gzip and zstd objects as stored instead of decompressing them
+    pub raw: bool,
+}
 
 /// Trait for displaying file contents in object storage.
 pub trait Cater {
-    /// Display file contents with optional large-file protection.
+    /// Stream the contents of one or more files, one after another.
     ///
     /// # Arguments
-    /// * `path` - File path to display
-    /// * `force` - Whether to bypass size-limit confirmation
-    /// * `size_limit_mb` - Maximum file size (in MB) before asking for confirmation; `0` disables the check
+    /// * `paths` - File paths to display, in order
+    /// * `options` - Byte range, size guard and output target
     ///
     /// # Returns
     /// * `Result<()>` - Success or detailed error information
-    async fn cat(&self, path: &str, force: bool, size_limit_mb: u64) -> Result<()>;
+    async fn cat(&self, paths: &[String], options: &CatOptions) -> Result<()>;
 }
 
 /// Implementation of Cater for OpenDAL Operator.
 pub struct OpenDalFileReader {
     operator: Operator,
+    /// Key for printing objects written by `put --encrypt`
+    data_key: Option<DataKey>,
 }
 
 impl OpenDalFileReader {
     pub fn new(operator: Operator) -> Self {
-        Self { operator }
+        Self {
+            operator,
+            data_key: None,
+        }
     }
 
-    /// Read and display file content.
+    /// Decrypt encrypted objects with `key`; without one they are refused.
+    pub fn with_data_key(mut self, key: Option<DataKey>) -> Self {
+        self.data_key = key;
+        self
+    }
+
+    /// Stream the selected bytes of `path` into `out`.
     ///
     /// # Arguments
     /// * `path` - File path to display
-    /// * `force` - Whether to bypass size-limit confirmation
-    /// * `size_limit_mb` - Maximum file size (in MB) before asking for confirmation; `0` disables the check
+    /// * `options` - Byte range and size guard
+    /// * `out` - Destination; the size guard only applies to stdout
     ///
     /// # Returns
     /// * `Result<()>` - Success or detailed error information
     pub async fn read_and_display(
         &self,
         path: &str,
-        force: bool,
-        size_limit_mb: u64,
+        options: &CatOptions,
+        out: &mut dyn Write,
     ) -> Result<()> {
         // Get file metadata
         let metadata = self.operator.stat(path).await.map_err(|e| {
@@ -54,47 +148,88 @@ impl OpenDalFileReader {
                 self.map_to_cat_failed(path, e)
             }
         })?;
+        let file_size = metadata.content_length();
+        let range = match options.range {
+            Some(range) => range.resolve(file_size)?,
+            None => 0..file_size,
+        };
 
-        // Check size limit
-        if size_limit_mb > 0 {
-            let size = metadata.content_length();
-            let file_size_mb = size.div_ceil(1024 * 1024);
-            if file_size_mb > size_limit_mb
-                && !force
-                && !self.confirm_large_file(file_size_mb, size_limit_mb).await?
+        // Check size limit against what will actually be printed
+        if options.size_limit_mb > 0 && options.output.is_none() {
+            let size_mb = (range.end - range.start).div_ceil(1024 * 1024);
+            if size_mb > options.size_limit_mb
+                && !options.force
+                && !self
+                    .confirm_large_file(size_mb, options.size_limit_mb)
+                    .await?
             {
                 return Ok(());
             }
         }
+        if range.is_empty() {
+            return Ok(());
+        }
 
-        // Stream read and display
-        let file_size = metadata.content_length();
-        let stdout = io::stdout();
-        let mut handle = stdout.lock();
-
-        let mut offset: u64 = 0;
-        while offset < file_size {
-            let chunk_size = std::cmp::min(DEFAULT_CHUNK_SIZE as u64, file_size - offset);
-            let data = self
-                .operator
-                .read_with(path)
-                .range(offset..offset + chunk_size)
-                .await
-                .map_err(|e| self.map_to_cat_failed(path, e))?;
-
-            if data.is_empty() {
-                break;
+        // Encrypted objects are decrypted whole; a byte range of one is meaningless.
+        let data_key = if options.raw {
+            None
+        } else if cipher::is_encrypted_object(&self.operator, path, file_size).await? {
+            if options.range.is_some() {
+                return Err(Error::InvalidArgument {
+                    message: format!("'{path}' is encrypted; --range only works with --raw on it"),
+                });
             }
+            Some(
+                self.data_key
+                    .as_ref()
+                    .ok_or_else(|| cipher::missing_key(path))?,
+            )
+        } else {
+            None
+        };
 
-            let bytes = data.to_vec();
-            handle.write_all(&bytes).map_err(|e| Error::CatFailed {
-                path: path.to_string(),
-                source: Box::new(e.into()),
-            })?;
-            offset += bytes.len() as u64;
+        // One ranged request, consumed chunk by chunk
+        let reader = self
+            .operator
+            .reader_with(path)
+            .chunk(DEFAULT_CHUNK_SIZE)
+            .await
+            .map_err(|e| self.map_to_cat_failed(path, e))?;
+        let bytes = reader
+            .into_bytes_stream(range)
+            .await
+            .map_err(|e| self.map_to_cat_failed(path, e))?;
+        let mut chunks = match data_key {
+            Some(key) => cipher::decrypt_stream(bytes, key).boxed(),
+            None => bytes.map_ok(|chunk| chunk.to_vec()).boxed(),
+        };
+        let failed = |e: io::Error| Error::CatFailed {
+            path: path.to_string(),
+            source: Box::new(e.into()),
+        };
+        // A byte range selects stored bytes, which a decoder cannot start from.
+        let compression = if options.raw || options.range.is_some() {
+            None
+        } else {
+            Compression::detect(path, metadata.content_encoding())
+        };
+        if let Some(compression) = compression {
+            let mut decoded = compression.decompress(chunks)?;
+            let mut buffer = vec![0u8; DEFAULT_BUFFER_SIZE];
+            loop {
+                let read = decoded.read(&mut buffer).await.map_err(failed)?;
+                if read == 0 {
+                    break;
+                }
+                out.write_all(&buffer[..read]).map_err(failed)?;
+            }
+        } else {
+            while let Some(chunk) = chunks.try_next().await.map_err(failed)? {
+                out.write_all(&chunk).map_err(failed)?;
+            }
         }
 
-        handle.flush().map_err(|e| Error::CatFailed {
+        out.flush().map_err(|e| Error::CatFailed {
             path: path.to_string(),
             source: Box::new(e.into()),
         })
@@ -142,7 +277,125 @@ impl OpenDalFileReader {
 }
 
 impl Cater for OpenDalFileReader {
-    async fn cat(&self, path: &str, force: bool, size_limit_mb: u64) -> Result<()> {
-        self.read_and_display(path, force, size_limit_mb).await
+    async fn cat(&self, paths: &[String], options: &CatOptions) -> Result<()> {
+        let Some(target) = options.output.as_deref() else {
+            let stdout = io::stdout();
+            // Piped output is streamed whole: the guard only protects the terminal.
+            let options = CatOptions {
+                size_limit_mb: if stdout.is_terminal() {
+                    options.size_limit_mb
+                } else {
+                    0
+                },
+                ..options.clone()
+            };
+            let mut out = PipeWriter::new(stdout.lock());
+            for path in paths {
+                self.read_and_display(path, &options, &mut out).await?;
+                if out.closed {
+                    break;
+                }
+            }
+            return Ok(());
+        };
+
+        // Staged so an interrupted cat never leaves a truncated file under the real name.
+        let staged = StagedFile::new(target);
+        let mut file = BufWriter::new(File::create(staged.path())?);
+        for path in paths {
+            self.read_and_display(path, options, &mut file).await?;
+        }
+        file.into_inner()
+            .map_err(|e| Error::from(e.into_error()))?
+            .sync_all()?;
+        staged.persist(target)
+    }
+}
+
+/// Stdout that stops quietly once the reading end of a pipe goes away
+/// (`storify cat big.log | head`), instead of failing with a broken pipe.
+struct PipeWriter<W> {
+    inner: W,
+    closed: bool,
+}
+
+impl<W: Write> PipeWriter<W> {
+    fn new(inner: W) -> Self {
+        Self {
+            inner,
+            closed: false,
+        }
+    }
+
+    fn check<T>(&mut self, result: io::Result<T>, closed: T) -> io::Result<T> {
+        match result {
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => {
+                self.closed = true;
+                Ok(closed)
+            }
+            other => other,
+        }
+    }
+}
+
+impl<W: Write> Write for PipeWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.closed {
+            return Ok(buf.len());
+        }
+        let result = self.inner.write(buf);
+        self.check(result, buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.closed {
+            return Ok(());
+        }
+        let result = self.inner.flush();
+        self.check(result, ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranges_parse_and_clamp_to_the_object() {
+        let range: ByteRange = "100-199".parse().unwrap();
+        assert_eq!(range.resolve(1000).unwrap(), 100..200);
+        assert_eq!(range.resolve(150).unwrap(), 100..150);
+        assert!(range.resolve(100).is_err());
+
+        let open: ByteRange = "900-".parse().unwrap();
+        assert_eq!(open.resolve(1000).unwrap(), 900..1000);
+
+        let suffix: ByteRange = "-64".parse().unwrap();
+        assert_eq!(suffix.resolve(1000).unwrap(), 936..1000);
+        assert_eq!(suffix.resolve(10).unwrap(), 0..10);
+
+        assert!("-".parse::<ByteRange>().is_err());
+        assert!("20-10".parse::<ByteRange>().is_err());
+        assert!("a-b".parse::<ByteRange>().is_err());
+    }
+
+    struct ClosedPipe;
+
+    impl Write for ClosedPipe {
+        fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+            Err(io::ErrorKind::BrokenPipe.into())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Err(io::ErrorKind::BrokenPipe.into())
+        }
+    }
+
+    #[test]
+    fn closed_pipe_ends_output_without_error() {
+        let mut out = PipeWriter::new(ClosedPipe);
+        out.write_all(b"hello").unwrap();
+        out.flush().unwrap();
+        assert!(out.closed);
     }
 }
@@ -0,0 +1,160 @@
+//! POSIX permission bits for `chmod` and `mkdir --mode` on the fs backend.
+
+use crate::error::{Error, Result};
+use crate::storage::utils::error::IntoStorifyError;
+use crate::storage::utils::paging::{PageSize, list_entries};
+use futures::stream::TryStreamExt;
+use opendal::Operator;
+use std::path::{Path, PathBuf};
+
+/// Parse an octal mode such as `750` or `0640`.
+pub fn parse_mode(value: &str) -> Result<u32> {
+    u32::from_str_radix(value, 8)
+        .ok()
+        .filter(|mode| *mode <= 0o7777)
+        .ok_or_else(|| Error::InvalidArgument {
+            message: format!("invalid mode '{value}': expected octal digits such as 750 or 0640"),
+        })
+}
+
+/// Trait for changing the permission bits of files and directories.
+pub trait ModeChanger {
+    /// Set the permission bits of `path`, and of everything below it when `recursive`.
+    ///
+    /// # Returns
+    /// * `Result<usize>` - Number of paths changed
+    async fn chmod(&self, path: &str, mode: u32, recursive: bool) -> Result<usize>;
+}
+
+/// Implementation of ModeChanger for the fs backend, applying modes to the files under
+/// its root directly; the OpenDAL operator only lists them.
+pub struct LocalModeChanger {
+    operator: Operator,
+    root: PathBuf,
+    page_size: PageSize,
+}
+
+impl LocalModeChanger {
+    pub fn new(operator: Operator, root: impl Into<PathBuf>) -> Self {
+        Self {
+            operator,
+            root: root.into(),
+            page_size: PageSize::Auto,
+        }
+    }
+
+    /// Page size of the listing requests.
+    pub fn with_page_size(mut self, page_size: PageSize) -> Self {
+        self.page_size = page_size;
+        self
+    }
+
+    fn local_path(&self, path: &str) -> PathBuf {
+        self.root.join(path.trim_start_matches('/'))
+    }
+}
+
+impl ModeChanger for LocalModeChanger {
+    async fn chmod(&self, path: &str, mode: u32, recursive: bool) -> Result<usize> {
+        let local = self.local_path(path);
+        let meta = std::fs::symlink_metadata(&local)?;
+        if !recursive || !meta.is_dir() {
+            set_mode(&local, mode)?;
+            return Ok(1);
+        }
+
+        let dir = format!("{}/", path.trim_end_matches('/'));
+        let mut changed = 0;
+        // Directories go last, deepest first, so a mode without the execute bit cannot
+        // lock the listing out of them halfway through.
+        let mut dirs = vec![local];
+        let mut entries = list_entries(&self.operator, &dir, true, self.page_size).await?;
+        while let Some(entry) = entries.try_next().await.map_err(|e| e.into_error())? {
+            let entry_path = self.local_path(entry.path());
+            if entry.metadata().mode().is_dir() {
+                dirs.push(entry_path);
+            } else {
+                set_mode(&entry_path, mode)?;
+                changed += 1;
+            }
+        }
+        dirs.sort_by(|a, b| {
+            (b.components().count())
+                .cmp(&a.components().count())
+                .then_with(|| a.cmp(b))
+        });
+        dirs.dedup();
+        for dir in dirs {
+            set_mode(&dir, mode)?;
+            changed += 1;
+        }
+        Ok(changed)
+    }
+}
+
+/// Apply `mode` to `path`, leaving symlinks (whose modes are ignored) alone.
+pub fn set_mode(path: &Path, mode: u32) -> Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if !std::fs::symlink_metadata(path)?.file_type().is_symlink() {
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))?;
+        }
+        Ok(())
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = mode;
+        Err(Error::InvalidArgument {
+            message: format!(
+                "cannot set permission bits of {} on this platform",
+                path.display()
+            ),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn modes_are_octal() {
+        assert_eq!(parse_mode("750").unwrap(), 0o750);
+        assert_eq!(parse_mode("0640").unwrap(), 0o640);
+        assert_eq!(parse_mode("4755").unwrap(), 0o4755);
+        for bad in ["", "8", "rwx", "17777"] {
+            assert!(parse_mode(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn changes_modes_recursively_under_the_root() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("d/sub")).unwrap();
+        std::fs::write(dir.path().join("d/a"), "1").unwrap();
+        std::fs::write(dir.path().join("d/sub/b"), "1").unwrap();
+        let operator =
+            Operator::new(opendal::services::Fs::default().root(dir.path().to_str().unwrap()))
+                .unwrap()
+                .finish();
+        let changer = LocalModeChanger::new(operator, dir.path());
+        let mode = |path: &str| {
+            std::fs::metadata(dir.path().join(path))
+                .unwrap()
+                .permissions()
+                .mode()
+                & 0o7777
+        };
+
+        assert_eq!(changer.chmod("d/a", 0o600, false).await.unwrap(), 1);
+        assert_eq!(mode("d/a"), 0o600);
+        assert_eq!(changer.chmod("d", 0o750, true).await.unwrap(), 4);
+        for path in ["d", "d/a", "d/sub", "d/sub/b"] {
+            assert_eq!(mode(path), 0o750, "{path}");
+        }
+    }
+}
@@ -0,0 +1,515 @@
+//! `storify concat`: merge objects into a new one server-side where the provider can
+//! (S3-style multipart part copies, GCS compose) and by streaming through storify
+//! otherwise.
+
+use crate::config::ProviderBackend;
+use crate::error::{Error, Result};
+use crate::storage::connection::ConnectionSettings;
+use crate::storage::rest::{
+    RestBucket, SendError, element, encode_query_value, object_key, unescape_xml,
+};
+use crate::storage::retry::RetryPolicy;
+use futures::TryStreamExt;
+use opendal::Operator;
+use reqsign::{GoogleCredentialLoader, GoogleSigner, GoogleTokenLoader};
+use reqwest::Method;
+use std::fmt;
+use std::ops::Range;
+
+/// Smallest part S3 accepts in a multipart upload, except for the last one.
+const MIN_PART_SIZE: u64 = 5 * 1024 * 1024;
+/// Largest range one part copy may take.
+const MAX_PART_SIZE: u64 = 5 * 1024 * 1024 * 1024;
+/// Most parts of one multipart upload.
+const MAX_PARTS: usize = 10_000;
+/// Most source objects of one GCS compose request.
+const MAX_COMPOSE_SOURCES: usize = 32;
+const GCS_SCOPE: &str = "https://www.googleapis.com/auth/devstorage.read_write";
+const DEFAULT_GCS_ENDPOINT: &str = "https://storage.googleapis.com";
+
+/// How a concatenated object was built.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ComposeMethod {
+    /// Multipart upload whose parts were copied from the sources by the server
+    PartCopy,
+    /// GCS compose requests
+    GcsCompose,
+    /// Sources read and written back through storify
+    Stream,
+}
+
+impl fmt::Display for ComposeMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ComposeMethod::PartCopy => "server-side part copy",
+            ComposeMethod::GcsCompose => "GCS compose",
+            ComposeMethod::Stream => "streamed",
+        })
+    }
+}
+
+/// Provider API that merges objects without downloading them.
+pub enum ServerCompose {
+    PartCopy(RestBucket),
+    Gcs(GcsBucket),
+}
+
+/// Trait for concatenating objects in storage
+pub trait Composer {
+    /// Write the objects `sources`, in order, into `dest`, replacing it if it exists.
+    ///
+    /// Returns the size of `dest` and how it was built.
+    async fn compose(&self, dest: &str, sources: &[String]) -> Result<(u64, ComposeMethod)>;
+}
+
+/// Implementation of Composer that uses the provider's server-side API when one is set
+/// and the sources fit its limits, and streams otherwise.
+pub struct OpenDalComposer {
+    operator: Operator,
+    server: Option<ServerCompose>,
+}
+
+struct Source {
+    key: String,
+    size: u64,
+}
+
+impl OpenDalComposer {
+    pub fn new(operator: Operator) -> Self {
+        Self {
+            operator,
+            server: None,
+        }
+    }
+
+    pub fn with_server(mut self, server: ServerCompose) -> Self {
+        self.server = Some(server);
+        self
+    }
+
+    async fn stream(
+        &self,
+        dest: &str,
+        sources: &[Source],
+        content_type: Option<&str>,
+    ) -> Result<()> {
+        let mut writer = self.operator.writer_with(dest);
+        if let Some(content_type) = content_type {
+            writer = writer.content_type(content_type);
+        }
+        let mut writer = writer.await?;
+        for source in sources {
+            let mut content = self
+                .operator
+                .reader(&source.key)
+                .await?
+                .into_bytes_stream(..)
+                .await?;
+            while let Some(bytes) = content.try_next().await? {
+                writer.write(bytes).await?;
+            }
+        }
+        writer.close().await?;
+        Ok(())
+    }
+
+    async fn part_copy(
+        &self,
+        bucket: &RestBucket,
+        dest: &str,
+        parts: &[(&str, Range<u64>)],
+        content_type: Option<&str>,
+    ) -> Result<()> {
+        // Some servers insist on a length even for the empty creation request.
+        let mut headers = vec![("Content-Length".to_string(), "0".to_string())];
+        if let Some(content_type) = content_type {
+            headers.push(("Content-Type".to_string(), content_type.to_string()));
+        }
+        let created = bucket
+            .send(Method::POST, dest, Some("uploads"), &headers, None)
+            .await?;
+        let upload_id = element(&created.body, "UploadId")
+            .map(unescape_xml)
+            .ok_or_else(|| Error::ProviderRequest {
+                message: format!("no UploadId in the response to creating an upload of '{dest}'"),
+            })?;
+        let result = copy_parts(bucket, dest, &upload_id, parts).await;
+        if result.is_err() {
+            let query = format!("uploadId={}", encode_query_value(&upload_id));
+            if let Err(e) = bucket
+                .send(Method::DELETE, dest, Some(&query), &[], None)
+                .await
+            {
+                tracing::warn!("failed to abort the upload of {dest}: {e}");
+            }
+        }
+        result
+    }
+}
+
+impl Composer for OpenDalComposer {
+    async fn compose(&self, dest: &str, sources: &[String]) -> Result<(u64, ComposeMethod)> {
+        let dest = object_key(dest, "concat destinations")?;
+        if sources.is_empty() {
+            return Err(Error::InvalidArgument {
+                message: "concat needs at least one source".to_string(),
+            });
+        }
+        let mut stated = Vec::with_capacity(sources.len());
+        let mut content_type = None;
+        for source in sources {
+            let key = object_key(source, "concat sources")?;
+            if key == dest {
+                return Err(Error::InvalidArgument {
+                    message: format!("'{source}' is both a source and the destination"),
+                });
+            }
+            let meta = self.operator.stat(key).await?;
+            if meta.mode().is_dir() {
+                return Err(Error::InvalidArgument {
+                    message: format!("'{source}' is a directory"),
+                });
+            }
+            if content_type.is_none() {
+                content_type = meta.content_type().map(str::to_string);
+            }
+            stated.push(Source {
+                key: key.to_string(),
+                size: meta.content_length(),
+            });
+        }
+        let total = stated.iter().map(|source| source.size).sum();
+
+        let method = match &self.server {
+            Some(ServerCompose::PartCopy(bucket)) => match copy_plan(&stated) {
+                Some(parts) => {
+                    self.part_copy(bucket, dest, &parts, content_type.as_deref())
+                        .await?;
+                    ComposeMethod::PartCopy
+                }
+                None => {
+                    tracing::debug!("concat {dest}: sources below the part size; streaming");
+                    self.stream(dest, &stated, content_type.as_deref()).await?;
+                    ComposeMethod::Stream
+                }
+            },
+            Some(ServerCompose::Gcs(bucket)) => {
+                let keys: Vec<&str> = stated.iter().map(|source| source.key.as_str()).collect();
+                bucket.compose(dest, &keys, content_type.as_deref()).await?;
+                ComposeMethod::GcsCompose
+            }
+            None => {
+                self.stream(dest, &stated, content_type.as_deref()).await?;
+                ComposeMethod::Stream
+            }
+        };
+        Ok((total, method))
+    }
+}
+
+/// Byte ranges of the sources to copy as parts, or `None` when the multipart limits
+/// rule a part copy out: every part but the last must be at least 5 MiB.
+fn copy_plan(sources: &[Source]) -> Option<Vec<(&str, Range<u64>)>> {
+    let non_empty: Vec<&Source> = sources.iter().filter(|source| source.size > 0).collect();
+    let (last, rest) = non_empty.split_last()?;
+    if rest.iter().any(|source| source.size < MIN_PART_SIZE) {
+        return None;
+    }
+    let mut parts = Vec::new();
+    for source in rest.iter().chain([last]) {
+        // Even pieces, so the tail of a large source does not fall below the minimum.
+        let pieces = source.size.div_ceil(MAX_PART_SIZE);
+        let piece = source.size.div_ceil(pieces);
+        let mut start = 0;
+        while start < source.size {
+            let end = (start + piece).min(source.size);
+            parts.push((source.key.as_str(), start..end));
+            start = end;
+        }
+    }
+    (parts.len() <= MAX_PARTS).then_some(parts)
+}
+
+/// Copy `parts` into the upload `upload_id` and complete it.
+async fn copy_parts(
+    bucket: &RestBucket,
+    dest: &str,
+    upload_id: &str,
+    parts: &[(&str, Range<u64>)],
+) -> Result<()> {
+    let prefix = bucket.header_prefix();
+    let mut completed = String::from("<CompleteMultipartUpload>");
+    for (index, (key, range)) in parts.iter().enumerate() {
+        let number = index + 1;
+        let query = format!(
+            "partNumber={number}&uploadId={}",
+            encode_query_value(upload_id)
+        );
+        let headers = [
+            (format!("{prefix}copy-source"), bucket.copy_source(key)),
+            (
+                format!("{prefix}copy-source-range"),
+                format!("bytes={}-{}", range.start, range.end - 1),
+            ),
+        ];
+        let response = bucket
+            .send(Method::PUT, dest, Some(&query), &headers, None)
+            .await?;
+        let etag = element(&response.body, "ETag").ok_or_else(|| Error::ProviderRequest {
+            message: format!("no ETag in the response to copying part {number} from '{key}'"),
+        })?;
+        completed.push_str(&format!(
+            "<Part><PartNumber>{number}</PartNumber><ETag>{etag}</ETag></Part>"
+        ));
+    }
+    completed.push_str("</CompleteMultipartUpload>");
+    let query = format!("uploadId={}", encode_query_value(upload_id));
+    let response = bucket
+        .send(Method::POST, dest, Some(&query), &[], Some(completed))
+        .await?;
+    // Completion can fail after the 200 status line has been sent.
+    if response.body.contains("<Error>") {
+        return Err(Error::ProviderRequest {
+            message: [
+                element(&response.body, "Code"),
+                element(&response.body, "Message"),
+            ]
+            .into_iter()
+            .flatten()
+            .map(unescape_xml)
+            .collect::<Vec<_>>()
+            .join(": "),
+        });
+    }
+    Ok(())
+}
+
+/// A GCS bucket addressed through the JSON API, for compose requests.
+pub struct GcsBucket {
+    client: reqwest::Client,
+    signer: GoogleSigner,
+    tokens: Option<Box<GoogleTokenLoader>>,
+    bucket: String,
+    base_url: String,
+    retry: RetryPolicy,
+}
+
+impl GcsBucket {
+    /// Create the client for a GCS backend with the credentials OpenDAL would use; `None`
+    /// for other providers.
+    pub fn from_backend(
+        backend: &ProviderBackend,
+        retry: RetryPolicy,
+        connection: &ConnectionSettings,
+    ) -> Result<Option<Self>> {
+        let ProviderBackend::Gcs {
+            bucket,
+            credential_path,
+            endpoint,
+            anonymous,
+        } = backend
+        else {
+            return Ok(None);
+        };
+        let client = connection.rest_client()?;
+        let tokens = if *anonymous {
+            None
+        } else {
+            let mut loader = GoogleCredentialLoader::default();
+            if let Some(path) = credential_path {
+                loader = loader.with_path(path);
+            }
+            let credential = loader.load().map_err(|err| Error::ProviderRequest {
+                message: format!("failed to load GCS credentials: {err}"),
+            })?;
+            let tokens = GoogleTokenLoader::new(GCS_SCOPE, client.clone());
+            Some(Box::new(match credential {
+                Some(credential) => tokens.with_credentials(credential),
+                None => tokens,
+            }))
+        };
+        Ok(Some(Self {
+            client,
+            signer: GoogleSigner::new("storage"),
+            tokens,
+            bucket: bucket.clone(),
+            base_url: endpoint
+                .as_deref()
+                .unwrap_or(DEFAULT_GCS_ENDPOINT)
+                .trim_end_matches('/')
+                .to_string(),
+            retry,
+        }))
+    }
+
+    /// Compose `sources` into `dest`, 32 at a time: after the first request, `dest`
+    /// itself is the first source of the next one.
+    async fn compose(
+        &self,
+        dest: &str,
+        sources: &[&str],
+        content_type: Option<&str>,
+    ) -> Result<()> {
+        let (first, rest) = sources.split_at(sources.len().min(MAX_COMPOSE_SOURCES));
+        self.compose_once(dest, first, content_type).await?;
+        for batch in rest.chunks(MAX_COMPOSE_SOURCES - 1) {
+            let sources: Vec<&str> = std::iter::once(dest).chain(batch.iter().copied()).collect();
+            self.compose_once(dest, &sources, content_type).await?;
+        }
+        Ok(())
+    }
+
+    async fn compose_once(
+        &self,
+        dest: &str,
+        sources: &[&str],
+        content_type: Option<&str>,
+    ) -> Result<()> {
+        let url = format!(
+            "{}/storage/v1/b/{}/o/{}/compose",
+            self.base_url,
+            encode_query_value(&self.bucket),
+            encode_query_value(dest)
+        );
+        let body = compose_body(sources, content_type);
+        let what = format!("POST {url}");
+        self.retry
+            .run(
+                &what,
+                |err| matches!(err, SendError::Transient(_)),
+                || self.send_once(&url, &body),
+            )
+            .await
+            .map_err(SendError::into_inner)
+    }
+
+    async fn send_once(&self, url: &str, body: &str) -> std::result::Result<(), SendError> {
+        let transport = |err: reqwest::Error| {
+            let error = Error::ProviderRequest {
+                message: err.to_string(),
+            };
+            if err.is_connect() || err.is_timeout() {
+                SendError::Transient(error)
+            } else {
+                SendError::Fatal(error)
+            }
+        };
+        let mut request = self
+            .client
+            .post(url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body.to_string())
+            .build()
+            .map_err(transport)?;
+        if let Some(tokens) = &self.tokens {
+            let token = tokens
+                .load()
+                .await
+                .map_err(|err| {
+                    SendError::Fatal(Error::ProviderRequest {
+                        message: format!("failed to load a GCS token: {err}"),
+                    })
+                })?
+                .ok_or_else(|| {
+                    SendError::Fatal(Error::ProviderRequest {
+                        message: "no GCS credentials found".to_string(),
+                    })
+                })?;
+            self.signer.sign(&mut request, &token).map_err(|err| {
+                SendError::Fatal(Error::ProviderRequest {
+                    message: format!("failed to sign request: {err}"),
+                })
+            })?;
+        }
+        let response = self.client.execute(request).await.map_err(transport)?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(());
+        }
+        let body = response.text().await.map_err(transport)?;
+        let message = serde_json::from_str::<serde_json::Value>(&body)
+            .ok()
+            .and_then(|value| value["error"]["message"].as_str().map(str::to_string));
+        let err = Error::ProviderRequest {
+            message: match message {
+                Some(message) => format!("{status} ({message})"),
+                None => format!("{status}"),
+            },
+        };
+        if status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            Err(SendError::Transient(err))
+        } else {
+            Err(SendError::Fatal(err))
+        }
+    }
+}
+
+fn compose_body(sources: &[&str], content_type: Option<&str>) -> String {
+    let mut body = serde_json::json!({
+        "sourceObjects": sources
+            .iter()
+            .map(|name| serde_json::json!({ "name": name }))
+            .collect::<Vec<_>>(),
+    });
+    if let Some(content_type) = content_type {
+        body["destination"] = serde_json::json!({ "contentType": content_type });
+    }
+    body.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source(key: &str, size: u64) -> Source {
+        Source {
+            key: key.to_string(),
+            size,
+        }
+    }
+
+    #[test]
+    fn part_copies_follow_multipart_limits() {
+        let mib = 1024 * 1024;
+        let sources = [source("a", 6 * mib), source("empty", 0), source("b", 1)];
+        let plan = copy_plan(&sources).unwrap();
+        assert_eq!(plan, vec![("a", 0..6 * mib), ("b", 0..1)]);
+        assert!(copy_plan(&[source("a", mib), source("b", 6 * mib)]).is_none());
+        assert!(copy_plan(&[source("empty", 0)]).is_none());
+
+        // 5 GiB + 1 byte splits into two even halves rather than 5 GiB and a 1-byte tail.
+        let sources = [source("big", MAX_PART_SIZE + 1), source("b", 1)];
+        let plan = copy_plan(&sources).unwrap();
+        assert_eq!(plan.len(), 3);
+        assert_eq!(plan[0].1, 0..MAX_PART_SIZE / 2 + 1);
+        assert_eq!(plan[1].1, MAX_PART_SIZE / 2 + 1..MAX_PART_SIZE + 1);
+
+        assert_eq!(
+            compose_body(&["a", "b"], Some("text/csv")),
+            r#"{"destination":{"contentType":"text/csv"},"sourceObjects":[{"name":"a"},{"name":"b"}]}"#
+        );
+    }
+
+    #[tokio::test]
+    async fn streams_sources_into_a_new_object() {
+        let operator = Operator::new(opendal::services::Memory::default())
+            .unwrap()
+            .finish();
+        operator.write("a", b"1,2\n".to_vec()).await.unwrap();
+        operator.write("b", b"3,4\n".to_vec()).await.unwrap();
+        let composer = OpenDalComposer::new(operator.clone());
+        let sources = ["a".to_string(), "b".to_string()];
+        assert_eq!(
+            composer.compose("all", &sources).await.unwrap(),
+            (8, ComposeMethod::Stream)
+        );
+        assert_eq!(operator.read("all").await.unwrap().to_vec(), b"1,2\n3,4\n");
+        assert!(composer.compose("a", &sources).await.is_err());
+        assert!(
+            composer
+                .compose("all", &["missing".to_string()])
+                .await
+                .is_err()
+        );
+    }
+}
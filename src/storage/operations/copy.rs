@@ -1,7 +1,13 @@
-use crate::error::{InvalidPathSnafu, Result};
+use crate::error::{
+    DestinationInsideSourceSnafu, DestinationIsDirectorySnafu, DestinationIsFileSnafu,
+    InvalidPathSnafu, Result,
+};
+use crate::jobs::CancelToken;
 use crate::storage::constants::DEFAULT_CHUNK_SIZE;
+use crate::storage::utils::clobber::Clobber;
 use crate::storage::utils::path::{
-    basename, build_remote_path, ensure_trailing_slash, get_root_relative_path,
+    build_remote_path, ensure_trailing_slash, get_root_relative_path, is_remote_directory,
+    is_remote_file, is_within, names_directory, transfer_target,
 };
 use crate::storage::utils::progress::ConsoleProgressReporter;
 use async_recursion::async_recursion;
@@ -25,29 +31,31 @@ pub trait Copier {
 /// Implementation of Copier for OpenDAL Operator.
 pub struct OpenDalCopier {
     operator: Operator,
+    clobber: Clobber,
+    /// Checked between chunks and files
+    cancel: CancelToken,
 }
 
 impl OpenDalCopier {
     /// Create a new copier with the given OpenDAL operator.
     pub fn new(operator: Operator) -> Self {
-        Self { operator }
+        Self {
+            operator,
+            clobber: Clobber::default(),
+            cancel: CancelToken::default(),
+        }
     }
 
-    /// Hybrid directory detection for object storage: stat first; if not available, probe prefix.
-    async fn is_directory(&self, path: &str) -> bool {
-        match self.operator.stat(path).await.ok().map(|m| m.mode()) {
-            Some(EntryMode::DIR) => true,
-            Some(_) => false,
-            None => {
-                let probe = ensure_trailing_slash(path);
-                self.operator
-                    .list_with(&probe)
-                    .limit(1)
-                    .await
-                    .map(|entries| !entries.is_empty())
-                    .unwrap_or(false)
-            }
-        }
+    /// Set what happens to destination objects that already exist.
+    pub fn with_clobber(mut self, clobber: Clobber) -> Self {
+        self.clobber = clobber;
+        self
+    }
+
+    /// Stop the copy once `token` is cancelled.
+    pub fn with_cancel_token(mut self, token: CancelToken) -> Self {
+        self.cancel = token;
+        self
     }
 
     /// Ensure a remote directory exists (appends trailing '/').
@@ -64,6 +72,7 @@ impl OpenDalCopier {
 
         let mut stream = lister;
         while let Some(entry) = stream.try_next().await? {
+            self.cancel.check()?;
             let meta = entry.metadata();
             let entry_path = entry.path();
 
@@ -79,7 +88,11 @@ impl OpenDalCopier {
 
             if meta.mode() == EntryMode::DIR {
                 self.ensure_directory(&new_dest_path).await?;
-            } else {
+            } else if self
+                .clobber
+                .allows(&self.operator, entry_path, &new_dest_path)
+                .await?
+            {
                 self.stream_copy(entry_path, &new_dest_path).await?;
             }
         }
@@ -88,7 +101,7 @@ impl OpenDalCopier {
     }
 
     /// Stream copy a single file with progress reporting.
-    async fn stream_copy(&self, src_path: &str, dest_path: &str) -> opendal::Result<()> {
+    async fn stream_copy(&self, src_path: &str, dest_path: &str) -> Result<()> {
         let metadata = self.operator.stat(src_path).await?;
         let file_size = metadata.content_length();
 
@@ -103,6 +116,11 @@ impl OpenDalCopier {
         );
 
         loop {
+            if let Err(e) = self.cancel.check() {
+                // Drop the partial object rather than leave it under the destination name.
+                let _ = writer.abort().await;
+                return Err(e);
+            }
             let chunk_size = std::cmp::min(DEFAULT_CHUNK_SIZE as u64, file_size - offset);
 
             let data = self
@@ -132,7 +150,7 @@ impl OpenDalCopier {
 impl Copier for OpenDalCopier {
     async fn copy(&self, src_path: &str, dest_path: &str) -> Result<()> {
         let src_stat = self.operator.stat(src_path).await.ok();
-        let src_is_dir = self.is_directory(src_path).await;
+        let src_is_dir = is_remote_directory(&self.operator, src_path).await;
         ensure!(
             src_stat.is_some() || src_is_dir,
             InvalidPathSnafu {
@@ -140,37 +158,39 @@ impl Copier for OpenDalCopier {
             }
         );
 
+        let target = transfer_target(src_path, dest_path);
         if src_is_dir {
-            let target_root = if self.is_directory(dest_path).await {
-                let base_name = basename(src_path);
-                let target_root = build_remote_path(dest_path, &base_name);
-                self.ensure_directory(&target_root).await?;
-                target_root
-            } else {
-                self.ensure_directory(dest_path).await?;
-                dest_path.to_string()
-            };
-
-            self.copy_file_recursive(src_path, &target_root).await?;
+            ensure!(
+                names_directory(dest_path) || !is_remote_file(&self.operator, dest_path).await,
+                DestinationIsFileSnafu {
+                    path: dest_path.to_string()
+                }
+            );
+            ensure!(
+                !is_within(&target, src_path),
+                DestinationInsideSourceSnafu {
+                    src: src_path.to_string(),
+                    dest: target.clone()
+                }
+            );
+            self.ensure_directory(&target).await?;
+            self.copy_file_recursive(&ensure_trailing_slash(src_path), &target)
+                .await?;
             Ok(())
         } else {
-            let dest_is_dir_hint = dest_path.ends_with('/');
-            let dest_is_dir = self.is_directory(dest_path).await;
-
-            if dest_is_dir_hint && !dest_is_dir {
-                return Err(crate::error::Error::InvalidPath {
-                    path: dest_path.to_string(),
-                });
+            ensure!(
+                names_directory(dest_path) || !is_remote_directory(&self.operator, dest_path).await,
+                DestinationIsDirectorySnafu {
+                    path: dest_path.to_string()
+                }
+            );
+            if self
+                .clobber
+                .allows(&self.operator, src_path, &target)
+                .await?
+            {
+                self.stream_copy(src_path, &target).await?;
             }
-
-            let final_dest = if dest_is_dir_hint || dest_is_dir {
-                let file_name = basename(src_path);
-                build_remote_path(dest_path, &file_name)
-            } else {
-                dest_path.to_string()
-            };
-
-            self.stream_copy(src_path, &final_dest).await?;
             Ok(())
         }
     }
@@ -1,6 +1,11 @@
 // Delete operation trait and implementation
-use crate::error::{DirectoryDeletionNotRecursiveSnafu, PartialDeletionSnafu, Result};
-use opendal::Operator;
+use crate::error::{
+    DirectoryDeletionNotRecursiveSnafu, PartialDeletionSnafu, PathNotFoundSnafu, Result,
+    RootDeletionRefusedSnafu,
+};
+use crate::storage::utils::path::is_root_path;
+use crate::storage::utils::report::MultiResult;
+use opendal::{Operator, Scheme};
 
 /// Trait for deleting files and directories from storage.
 /// Provides a clean interface for delete operations with proper error handling.
@@ -19,12 +24,50 @@ pub trait Deleter {
 /// Implementation of Deleter for OpenDAL Operator.
 pub struct OpenDalDeleter {
     operator: Operator,
+    /// Let the root of an fs backend be deleted
+    allow_root: bool,
 }
 
 impl OpenDalDeleter {
     /// Create a new deleter with the given OpenDAL operator.
     pub fn new(operator: Operator) -> Self {
-        Self { operator }
+        Self {
+            operator,
+            allow_root: false,
+        }
+    }
+
+    /// Allow deleting the root directory of an fs backend.
+    pub fn with_allow_root(mut self, allow_root: bool) -> Self {
+        self.allow_root = allow_root;
+        self
+    }
+
+    /// Refuse to wipe the root of a local filesystem backend unless explicitly allowed.
+    fn ensure_not_root(&self, path: &str) -> Result<()> {
+        if !self.allow_root && self.operator.info().scheme() == Scheme::Fs && is_root_path(path) {
+            return RootDeletionRefusedSnafu { path }.fail();
+        }
+        Ok(())
+    }
+
+    /// Permanently delete a single version of an object.
+    pub async fn delete_version(&self, path: &str, version: &str) -> Result<()> {
+        super::versions::ensure_versioning(&self.operator)?;
+        self.operator.delete_with(path).version(version).await?;
+        println!("Deleted: {path} (version {version})");
+        Ok(())
+    }
+
+    async fn delete_one(&self, path: &str, recursive: bool) -> Result<()> {
+        if !self.path_exists(path).await? {
+            return PathNotFoundSnafu { path }.fail();
+        }
+        if self.is_directory(path).await? && !recursive {
+            return DirectoryDeletionNotRecursiveSnafu { path }.fail();
+        }
+        self.operator.remove_all(path).await?;
+        Ok(())
     }
 
     /// Check if a path exists in storage.
@@ -46,32 +89,16 @@ impl OpenDalDeleter {
 
 impl Deleter for OpenDalDeleter {
     async fn delete(&self, paths: &[String], recursive: bool) -> Result<()> {
-        let mut failed_paths = Vec::new();
-
         for path in paths {
-            if !self.path_exists(path).await? {
-                eprintln!("Path not found: {path}");
-                failed_paths.push(path.clone());
-                continue;
-            }
-
-            if self.is_directory(path).await? && !recursive {
-                return DirectoryDeletionNotRecursiveSnafu { path: path.clone() }.fail();
-            }
-
-            match self.operator.remove_all(path).await {
-                Ok(_) => println!("Deleted: {path}"),
-                Err(e) => {
-                    eprintln!("Failed to delete {path}: {e}");
-                    failed_paths.push(path.clone());
-                }
-            }
+            self.ensure_not_root(path)?;
         }
-
-        if !failed_paths.is_empty() {
-            return PartialDeletionSnafu { failed_paths }.fail();
+        let mut results = MultiResult::new(paths.len());
+        for path in paths {
+            let result = self.delete_one(path, recursive).await;
+            if results.record(path, result) {
+                println!("Deleted: {path}");
+            }
         }
-
-        Ok(())
+        results.into_result(|failed_paths, _| PartialDeletionSnafu { failed_paths }.build())
     }
 }
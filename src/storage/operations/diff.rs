@@ -1,13 +1,82 @@
 use crate::error::{Error, Result};
+use crate::storage::utils::paging::{PageSize, list_entries};
+use crate::storage::utils::path::ensure_trailing_slash;
+use futures::stream::TryStreamExt;
 use opendal::Operator;
 use similar::TextDiff;
+use std::collections::{BTreeMap, BTreeSet};
 use std::path::PathBuf;
+use tokio::io::AsyncReadExt;
+
+/// Bytes read from each side per step of a content comparison.
+const CONTENT_CHUNK_SIZE: u64 = 4 * 1024 * 1024;
+
+/// One side of a file diff: an object in storage or a file on the local disk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffSource {
+    Remote(String),
+    Local(PathBuf),
+}
+
+impl DiffSource {
+    /// Name shown in the diff header and in errors.
+    pub fn label(&self) -> String {
+        match self {
+            DiffSource::Remote(path) => path.clone(),
+            DiffSource::Local(path) => path.display().to_string(),
+        }
+    }
+}
 
 /// Trait for diffing two files and printing a unified diff
 pub trait Differ {
     /// Diff two files and print the unified diff to stdout
-    async fn diff(&self, left: &str, right: &str, context: usize, ignore_space: bool)
-    -> Result<()>;
+    async fn diff(
+        &self,
+        left: &DiffSource,
+        right: &DiffSource,
+        context: usize,
+        ignore_space: bool,
+    ) -> Result<()>;
+
+    /// Whether two files of `size` bytes hold the same data, streamed chunk by chunk so
+    /// neither is loaded whole.
+    async fn same_content(&self, left: &DiffSource, right: &DiffSource, size: u64) -> Result<bool>;
+}
+
+/// Sequential chunk reader over either kind of source.
+enum ChunkReader<'a> {
+    Remote {
+        operator: &'a Operator,
+        path: &'a str,
+        offset: u64,
+    },
+    Local(tokio::fs::File),
+}
+
+impl ChunkReader<'_> {
+    /// Read exactly `len` bytes, the caller knowing that many remain.
+    async fn next_chunk(&mut self, len: u64) -> Result<Vec<u8>> {
+        match self {
+            ChunkReader::Remote {
+                operator,
+                path,
+                offset,
+            } => {
+                let chunk = operator
+                    .read_with(path)
+                    .range(*offset..*offset + len)
+                    .await?;
+                *offset += len;
+                Ok(chunk.to_vec())
+            }
+            ChunkReader::Local(file) => {
+                let mut chunk = vec![0; len as usize];
+                file.read_exact(&mut chunk).await?;
+                Ok(chunk)
+            }
+        }
+    }
 }
 
 pub struct OpenDalDiffer {
@@ -19,23 +88,50 @@ impl OpenDalDiffer {
         Self { operator }
     }
 
-    async fn read_text(&self, path: &str, ignore_space: bool) -> Result<String> {
-        let data = self.operator.read(path).await.map_err(|e| {
-            if e.kind() == opendal::ErrorKind::NotFound {
-                Error::PathNotFound {
-                    path: PathBuf::from(path),
-                }
-            } else {
-                Error::DiffFailed {
-                    src_path: path.to_string(),
-                    dest_path: path.to_string(),
-                    source: Box::new(e.into()),
-                }
+    async fn read_bytes(&self, source: &DiffSource) -> Result<Vec<u8>> {
+        match source {
+            DiffSource::Remote(path) => {
+                let data = self.operator.read(path).await.map_err(|e| {
+                    if e.kind() == opendal::ErrorKind::NotFound {
+                        Error::PathNotFound {
+                            path: PathBuf::from(path),
+                        }
+                    } else {
+                        Error::DiffFailed {
+                            src_path: path.to_string(),
+                            dest_path: path.to_string(),
+                            source: Box::new(e.into()),
+                        }
+                    }
+                })?;
+                Ok(data.to_vec())
             }
-        })?;
+            DiffSource::Local(path) => tokio::fs::read(path).await.map_err(|e| {
+                if e.kind() == std::io::ErrorKind::NotFound {
+                    Error::PathNotFound { path: path.clone() }
+                } else {
+                    e.into()
+                }
+            }),
+        }
+    }
 
-        let mut s = String::from_utf8(data.to_vec()).map_err(|_| Error::InvalidArgument {
-            message: format!("Non-UTF8 or binary file not supported: {}", path),
+    async fn chunk_reader<'a>(&'a self, source: &'a DiffSource) -> Result<ChunkReader<'a>> {
+        Ok(match source {
+            DiffSource::Remote(path) => ChunkReader::Remote {
+                operator: &self.operator,
+                path,
+                offset: 0,
+            },
+            DiffSource::Local(path) => ChunkReader::Local(tokio::fs::File::open(path).await?),
+        })
+    }
+
+    async fn read_text(&self, source: &DiffSource, ignore_space: bool) -> Result<String> {
+        let data = self.read_bytes(source).await?;
+
+        let mut s = String::from_utf8(data).map_err(|_| Error::InvalidArgument {
+            message: format!("Non-UTF8 or binary file not supported: {}", source.label()),
         })?;
 
         if ignore_space {
@@ -60,8 +156,8 @@ impl OpenDalDiffer {
 impl Differ for OpenDalDiffer {
     async fn diff(
         &self,
-        left: &str,
-        right: &str,
+        left: &DiffSource,
+        right: &DiffSource,
         context: usize,
         ignore_space: bool,
     ) -> Result<()> {
@@ -73,7 +169,7 @@ impl Differ for OpenDalDiffer {
         let unified = diff
             .unified_diff()
             .context_radius(context)
-            .header(left, right)
+            .header(&left.label(), &right.label())
             .to_string();
 
         // If no differences, print nothing
@@ -84,4 +180,228 @@ impl Differ for OpenDalDiffer {
         println!("{}", unified);
         Ok(())
     }
+
+    async fn same_content(&self, left: &DiffSource, right: &DiffSource, size: u64) -> Result<bool> {
+        let mut left_reader = self.chunk_reader(left).await?;
+        let mut right_reader = self.chunk_reader(right).await?;
+        let mut offset = 0;
+        while offset < size {
+            let len = CONTENT_CHUNK_SIZE.min(size - offset);
+            if left_reader.next_chunk(len).await? != right_reader.next_chunk(len).await? {
+                return Ok(false);
+            }
+            offset += len;
+        }
+        Ok(true)
+    }
+}
+
+/// Options for comparing two directory prefixes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DirDiffOptions {
+    /// Compare the bytes of objects whose size matches but whose etags cannot prove
+    /// them equal
+    pub content: bool,
+}
+
+/// Counts printed in the summary line of a directory diff.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DirDiffSummary {
+    pub added: usize,
+    pub removed: usize,
+    pub changed: usize,
+    pub unchanged: usize,
+}
+
+/// Trait for comparing the objects under two directory prefixes.
+pub trait DirDiffer {
+    /// Print the objects added, removed and changed from `left` to `right`, then a summary.
+    async fn diff_dirs(
+        &self,
+        left: &str,
+        right: &str,
+        options: DirDiffOptions,
+    ) -> Result<DirDiffSummary>;
+}
+
+/// Size and etag of a listed object.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Listed {
+    size: u64,
+    etag: Option<String>,
+}
+
+/// Outcome of comparing one relative path on both sides.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Change {
+    Added,
+    Removed,
+    /// Differs; the reason is printed after the path
+    Changed(String),
+    Unchanged,
+    /// Same size with etags that cannot tell; decided by reading both objects
+    CompareContent,
+}
+
+/// Decide what listing metadata says about a path present on one or both sides.
+///
+/// Objects of equal size are unchanged when their etags match. Differing etags mean a
+/// change unless `--content` asks to check the bytes (multipart uploads get different
+/// etags for the same data); without etags only sizes are compared.
+fn classify(left: Option<&Listed>, right: Option<&Listed>, content: bool) -> Change {
+    match (left, right) {
+        (None, None) | (None, Some(_)) => Change::Added,
+        (Some(_), None) => Change::Removed,
+        (Some(l), Some(r)) if l.size != r.size => {
+            Change::Changed(format!("size {} → {}", l.size, r.size))
+        }
+        (Some(l), Some(r)) => match (&l.etag, &r.etag) {
+            (Some(le), Some(re)) if le == re => Change::Unchanged,
+            _ if content => Change::CompareContent,
+            (Some(_), Some(_)) => Change::Changed("etag".to_string()),
+            _ => Change::Unchanged,
+        },
+    }
+}
+
+pub struct OpenDalDirDiffer {
+    operator: Operator,
+    page_size: PageSize,
+}
+
+impl OpenDalDirDiffer {
+    pub fn new(operator: Operator) -> Self {
+        Self {
+            operator,
+            page_size: PageSize::Auto,
+        }
+    }
+
+    /// Page size of the listing requests.
+    pub fn with_page_size(mut self, page_size: PageSize) -> Self {
+        self.page_size = page_size;
+        self
+    }
+
+    /// Objects under `prefix`, keyed by their path relative to it.
+    async fn list_objects(&self, prefix: &str) -> Result<BTreeMap<String, Listed>> {
+        let prefix = ensure_trailing_slash(prefix);
+        let mut entries = list_entries(&self.operator, &prefix, true, self.page_size).await?;
+        let mut objects = BTreeMap::new();
+        while let Some(entry) = entries.try_next().await? {
+            if entry.metadata().mode().is_dir() {
+                continue;
+            }
+            let Some(rel) = entry.path().strip_prefix(&prefix) else {
+                continue;
+            };
+            // Listings without metadata (fs) carry no sizes; stat those entries.
+            let meta = match entry.metadata().last_modified() {
+                Some(_) => entry.metadata().clone(),
+                None => self.operator.stat(entry.path()).await?,
+            };
+            objects.insert(
+                rel.to_string(),
+                Listed {
+                    size: meta.content_length(),
+                    etag: meta.etag().map(str::to_string),
+                },
+            );
+        }
+        Ok(objects)
+    }
+}
+
+impl DirDiffer for OpenDalDirDiffer {
+    async fn diff_dirs(
+        &self,
+        left: &str,
+        right: &str,
+        options: DirDiffOptions,
+    ) -> Result<DirDiffSummary> {
+        let left_objects = self.list_objects(left).await?;
+        let right_objects = self.list_objects(right).await?;
+        let paths: BTreeSet<&String> = left_objects.keys().chain(right_objects.keys()).collect();
+
+        let mut summary = DirDiffSummary::default();
+        for rel in paths {
+            let l = left_objects.get(rel);
+            let r = right_objects.get(rel);
+            let change = match classify(l, r, options.content) {
+                Change::CompareContent => {
+                    let size = l.map_or(0, |l| l.size);
+                    let left_path = format!("{}{rel}", ensure_trailing_slash(left));
+                    let right_path = format!("{}{rel}", ensure_trailing_slash(right));
+                    let differ = OpenDalDiffer::new(self.operator.clone());
+                    let left_source = DiffSource::Remote(left_path);
+                    let right_source = DiffSource::Remote(right_path);
+                    if differ
+                        .same_content(&left_source, &right_source, size)
+                        .await?
+                    {
+                        Change::Unchanged
+                    } else {
+                        Change::Changed("content".to_string())
+                    }
+                }
+                change => change,
+            };
+            match change {
+                Change::Added => {
+                    summary.added += 1;
+                    println!("+ {rel}");
+                }
+                Change::Removed => {
+                    summary.removed += 1;
+                    println!("- {rel}");
+                }
+                Change::Changed(reason) => {
+                    summary.changed += 1;
+                    println!("M {rel} ({reason})");
+                }
+                Change::Unchanged | Change::CompareContent => summary.unchanged += 1,
+            }
+        }
+        println!(
+            "{} added, {} removed, {} changed, {} unchanged",
+            summary.added, summary.removed, summary.changed, summary.unchanged
+        );
+        Ok(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn listed(size: u64, etag: Option<&str>) -> Listed {
+        Listed {
+            size,
+            etag: etag.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn classify_uses_size_then_etag() {
+        let a = listed(3, Some("x"));
+        assert_eq!(classify(None, Some(&a), false), Change::Added);
+        assert_eq!(classify(Some(&a), None, false), Change::Removed);
+        assert_eq!(
+            classify(Some(&a), Some(&listed(4, Some("x"))), true),
+            Change::Changed("size 3 → 4".to_string())
+        );
+        assert_eq!(classify(Some(&a), Some(&a), true), Change::Unchanged);
+        let b = listed(3, Some("y"));
+        assert_eq!(
+            classify(Some(&a), Some(&b), false),
+            Change::Changed("etag".to_string())
+        );
+        assert_eq!(classify(Some(&a), Some(&b), true), Change::CompareContent);
+        let bare = listed(3, None);
+        assert_eq!(classify(Some(&bare), Some(&bare), false), Change::Unchanged);
+        assert_eq!(
+            classify(Some(&bare), Some(&bare), true),
+            Change::CompareContent
+        );
+    }
 }
@@ -1,9 +1,101 @@
+use crate::config::crypto::DataKey;
 use crate::error::{Error, Result};
-use crate::storage::utils::path::get_root_relative_path;
-use futures::stream::TryStreamExt;
+use crate::jobs::CancelToken;
+use crate::scratch::StagedFile;
+use crate::storage::constants::DEFAULT_CHUNK_SIZE;
+use crate::storage::utils::archive::{self, TarEntry};
+use crate::storage::utils::cipher;
+use crate::storage::utils::codec::Compression;
+use crate::storage::utils::filter::PathFilter;
+use crate::storage::utils::path::{
+    ensure_trailing_slash, get_root_relative_path, is_remote_file, names_directory, transfer_target,
+};
+use crate::storage::utils::report::TransferReport;
+use crate::storage::utils::size::format_size;
+use crate::storage::utils::validate::{local_path_warnings, report_warnings};
+use chrono::{DateTime, Utc};
+use futures::future;
+use futures::stream::{self, StreamExt, TryStreamExt};
 use opendal::{EntryMode, Operator};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::io::SeekFrom;
 use std::path::{Path, PathBuf};
+use std::time::{Instant, UNIX_EPOCH};
 use tokio::fs;
+use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+
+use super::preserve;
+
+/// Settings for `storify get`.
+#[derive(Debug, Clone)]
+pub struct DownloadOptions {
+    /// Fail before transferring if the destination lacks free space
+    pub check_space: bool,
+    /// Concurrent range requests per file
+    pub concurrency: usize,
+    /// Bytes fetched by each range request
+    pub part_size: u64,
+    /// Continue from an interrupted download's `.part` file if the object is unchanged
+    pub resume: bool,
+    /// Files to download, by path relative to the downloaded directory
+    pub filter: PathFilter,
+    /// Only download files modified after this time
+    pub newer_than: Option<DateTime<Utc>>,
+    /// Restore modification times and permissions stored by `put --preserve`
+    pub preserve: bool,
+    /// Recreate symlinks uploaded by `put --preserve-links` instead of writing their markers
+    pub preserve_links: bool,
+    /// Print each file's size and a summary of the transfer
+    pub verbose: bool,
+    /// Print what would be downloaded without downloading anything
+    pub dry_run: bool,
+    /// Decompress gzip and zstd objects, dropping their extension from the local name
+    pub decompress: bool,
+    /// Decrypt objects written by `put --encrypt` with the configured data key
+    pub decrypt: bool,
+    /// Write the files into one tar archive at the local path instead of one by one
+    pub archive: bool,
+}
+
+/// Progress of a chunked download, kept beside its `.part` file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct PartState {
+    /// Identity of the remote object when the download started
+    etag: Option<String>,
+    last_modified: Option<String>,
+    size: u64,
+    part_size: u64,
+    /// Indices of the parts already written to the `.part` file
+    done: BTreeSet<u64>,
+}
+
+impl PartState {
+    fn parts(&self) -> u64 {
+        self.size.div_ceil(self.part_size)
+    }
+
+    /// Whether parts saved in `self` can be reused for a download described by `current`:
+    /// same object version and the same part layout.
+    fn resumes(&self, current: &PartState) -> bool {
+        let same_object = match (&self.etag, &current.etag) {
+            (Some(saved), Some(current)) => saved == current,
+            _ => self.last_modified == current.last_modified,
+        };
+        same_object && self.size == current.size && self.part_size == current.part_size
+    }
+
+    async fn load(path: &Path) -> Option<Self> {
+        let data = fs::read(path).await.ok()?;
+        serde_json::from_slice(&data).ok()
+    }
+
+    async fn save(&self, path: &Path) -> Result<()> {
+        let staged = StagedFile::new(path);
+        fs::write(staged.path(), serde_json::to_vec(self)?).await?;
+        staged.persist(path)
+    }
+}
 
 /// Trait for downloading files and directories from storage.
 pub trait Downloader {
@@ -12,51 +104,721 @@ pub trait Downloader {
     /// # Arguments
     /// * `remote_path` - Source path in storage (file or directory)
     /// * `local_path` - Destination path on local filesystem
+    /// * `options` - Free-space check and chunked download settings
     ///
     /// # Returns
-    /// * `Result<()>` - Success or detailed error information
-    async fn download(&self, remote_path: &str, local_path: &str) -> Result<()>;
+    /// * `Result<TransferReport>` - Totals of the download; files of a directory that fail
+    ///   are counted there instead of aborting the rest
+    async fn download(
+        &self,
+        remote_path: &str,
+        local_path: &str,
+        options: &DownloadOptions,
+    ) -> Result<TransferReport>;
 }
 
 /// Implementation of Downloader for OpenDAL Operator.
 pub struct OpenDalDownloader {
     operator: Operator,
+    /// Key that objects written by `put --encrypt` are decrypted with
+    data_key: Option<DataKey>,
+    /// Checked between chunks, parts and files
+    cancel: CancelToken,
 }
 
 impl OpenDalDownloader {
     /// Create a new downloader with the given OpenDAL operator.
     pub fn new(operator: Operator) -> Self {
-        Self { operator }
+        Self {
+            operator,
+            data_key: None,
+            cancel: CancelToken::default(),
+        }
+    }
+
+    /// Decrypt the encrypted objects of the download with `key`.
+    pub fn with_data_key(mut self, key: DataKey) -> Self {
+        self.data_key = Some(key);
+        self
+    }
+
+    /// Stop the download once `token` is cancelled.
+    pub fn with_cancel_token(mut self, token: CancelToken) -> Self {
+        self.cancel = token;
+        self
+    }
+
+    /// Download a specific version of a single object to `local_path`.
+    ///
+    /// Mirrors the layout of [`Downloader::download`]: the object is written under its
+    /// base name when `local_path` is spelled as a directory, and to `local_path` otherwise.
+    pub async fn download_version(
+        &self,
+        remote_path: &str,
+        local_path: &str,
+        version: &str,
+    ) -> Result<TransferReport> {
+        let started = Instant::now();
+        super::versions::ensure_versioning(&self.operator)?;
+
+        let meta = self
+            .operator
+            .stat_with(remote_path)
+            .version(version)
+            .await
+            .map_err(|e| {
+                if e.kind() == opendal::ErrorKind::NotFound {
+                    Error::PathNotFound {
+                        path: PathBuf::from(remote_path),
+                    }
+                } else {
+                    e.into()
+                }
+            })?;
+        if !meta.mode().is_file() {
+            return Err(Error::InvalidArgument {
+                message: "--version-id only applies to files".to_string(),
+            });
+        }
+
+        let local_file_path = local_file_target(remote_path, local_path)?;
+        if let Some(file_name) = local_file_path.file_name() {
+            report_warnings(&local_path_warnings(
+                [file_name.to_string_lossy().as_ref()].into_iter(),
+            ));
+        }
+        if let Some(parent) = local_file_path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        let data = self
+            .operator
+            .read_with(remote_path)
+            .version(version)
+            .await?;
+        let staged = StagedFile::new(&local_file_path);
+        fs::write(staged.path(), data.to_vec()).await?;
+        staged.persist(&local_file_path)?;
+        println!(
+            "Downloaded: {remote_path} (version {version}) → {}",
+            local_file_path.display()
+        );
+        let mut report = TransferReport::default();
+        report.add_file(data.len() as u64);
+        report.elapsed = started.elapsed();
+        Ok(report)
+    }
+}
+
+impl OpenDalDownloader {
+    /// Size of a listed file, stat-ing entries listed without metadata.
+    async fn entry_size(&self, entry: &opendal::Entry) -> Result<u64> {
+        let meta = entry.metadata();
+        Ok(if meta.last_modified().is_none() {
+            self.operator.stat(entry.path()).await?.content_length()
+        } else {
+            meta.content_length()
+        })
+    }
+
+    /// Whether a listed entry passes `--include`/`--exclude`/`--newer-than`. Directories are
+    /// left out: the ones holding selected files are created on the way.
+    async fn selected(
+        &self,
+        entry: &opendal::Entry,
+        relative_path: &str,
+        options: &DownloadOptions,
+    ) -> Result<bool> {
+        if entry.metadata().mode().is_dir() || !options.filter.matches(relative_path) {
+            return Ok(false);
+        }
+        let Some(newer_than) = options.newer_than else {
+            return Ok(true);
+        };
+        let modified = match entry.metadata().last_modified() {
+            Some(modified) => Some(modified),
+            None => self.operator.stat(entry.path()).await?.last_modified(),
+        };
+        Ok(modified.is_some_and(|modified| modified > newer_than))
+    }
+
+    /// Print the files a download would transfer, for `--dry-run`.
+    async fn print_plan(
+        &self,
+        planned: &[(opendal::Entry, String)],
+        local_root: &Path,
+    ) -> Result<()> {
+        let (mut count, mut total) = (0usize, 0u64);
+        for (entry, relative_path) in planned {
+            if entry.metadata().mode().is_dir() {
+                continue;
+            }
+            let size = self.entry_size(entry).await?;
+            println!(
+                "Would download: {} → {} ({size} bytes)",
+                entry.path(),
+                local_root.join(relative_path).display()
+            );
+            count += 1;
+            total += size;
+        }
+        println!("Would download {count} file(s), {}", format_size(total));
+        Ok(())
+    }
+
+    /// Total bytes of the planned files.
+    async fn planned_size(&self, planned: &[(opendal::Entry, String)]) -> Result<u64> {
+        let mut total = 0u64;
+        for (entry, _) in planned {
+            if !entry.metadata().mode().is_dir() {
+                total += self.entry_size(entry).await?;
+            }
+        }
+        Ok(total)
+    }
+
+    /// Download one object to `local_file_path`.
+    ///
+    /// Objects that fit in one part are streamed into a staging file. Larger ones are
+    /// fetched in ranged parts into `<name>.part`, with progress recorded in
+    /// `<name>.part.json` so `--resume` can continue an interrupted download. Either way
+    /// the final name only ever holds a complete file.
+    async fn download_file(
+        &self,
+        remote_path: &str,
+        local_file_path: &Path,
+        size: u64,
+        compression: Option<Compression>,
+        options: &DownloadOptions,
+    ) -> Result<()> {
+        if options.preserve_links && self.restore_symlink(remote_path, local_file_path).await? {
+            return Ok(());
+        }
+        let data_key = match &self.data_key {
+            Some(key) if cipher::is_encrypted_object(&self.operator, remote_path, size).await? => {
+                Some(key)
+            }
+            _ => None,
+        };
+        if data_key.is_some() || compression.is_some() {
+            // Decoding is sequential, so the object is read in one stream.
+            let staged = StagedFile::new(local_file_path);
+            self.download_decoded(remote_path, staged.path(), data_key, compression)
+                .await?;
+            staged.persist(local_file_path)?;
+            return self
+                .restore_metadata(remote_path, local_file_path, options)
+                .await;
+        }
+        if size <= options.part_size.max(1) {
+            let staged = StagedFile::new(local_file_path);
+            self.download_sequential(remote_path, staged.path()).await?;
+            staged.persist(local_file_path)?;
+            return self
+                .restore_metadata(remote_path, local_file_path, options)
+                .await;
+        }
+
+        let (part_path, state_path) = part_paths(local_file_path);
+        let result = self
+            .fetch_parts(remote_path, &part_path, &state_path, size, options)
+            .await;
+        if result.is_err() && fs::try_exists(&state_path).await.unwrap_or(false) {
+            tracing::warn!(
+                "Partial download kept at {}; rerun with --resume to continue",
+                part_path.display()
+            );
+        }
+        result?;
+        fs::rename(&part_path, local_file_path).await?;
+        let _ = fs::remove_file(&state_path).await;
+        self.restore_metadata(remote_path, local_file_path, options)
+            .await
+    }
+
+    /// Recreate `remote_path` as a symlink if it is a `put --preserve-links` marker;
+    /// returns whether it was one.
+    async fn restore_symlink(&self, remote_path: &str, local_file_path: &Path) -> Result<bool> {
+        let meta = self.operator.stat(remote_path).await?;
+        let Some(target) = meta
+            .user_metadata()
+            .and_then(|metadata| metadata.get(preserve::SYMLINK_METADATA_KEY))
+        else {
+            return Ok(false);
+        };
+        preserve::restore_symlink(local_file_path, target)?;
+        Ok(true)
+    }
+
+    /// With `--preserve`, apply the modification time and permissions stored on the object.
+    async fn restore_metadata(
+        &self,
+        remote_path: &str,
+        local_file_path: &Path,
+        options: &DownloadOptions,
+    ) -> Result<()> {
+        if !options.preserve {
+            return Ok(());
+        }
+        // Listings do not carry user metadata, so ask for it.
+        let meta = self.operator.stat(remote_path).await?;
+        match meta.user_metadata() {
+            Some(metadata) => preserve::restore(local_file_path, metadata),
+            None => Ok(()),
+        }
+    }
+
+    /// Fetch one object into `part_path` in ranged parts, `options.concurrency` at a time,
+    /// saving the finished parts to `state_path` as they land.
+    async fn fetch_parts(
+        &self,
+        remote_path: &str,
+        part_path: &Path,
+        state_path: &Path,
+        size: u64,
+        options: &DownloadOptions,
+    ) -> Result<()> {
+        let part_size = options.part_size.max(1);
+        let meta = self.operator.stat(remote_path).await?;
+        let mut state = PartState {
+            etag: meta.etag().map(str::to_string),
+            last_modified: meta.last_modified().map(|time| time.to_rfc3339()),
+            size,
+            part_size,
+            done: BTreeSet::new(),
+        };
+
+        if options.resume {
+            match PartState::load(state_path).await {
+                Some(saved) if !saved.resumes(&state) => tracing::warn!(
+                    "{remote_path} changed since the interrupted download; starting over"
+                ),
+                Some(_) if part_file_len(part_path).await != Some(size) => tracing::warn!(
+                    "{} is missing or truncated; starting over",
+                    part_path.display()
+                ),
+                Some(saved) if !saved.done.is_empty() => {
+                    state.done = saved.done;
+                    println!(
+                        "Resuming {remote_path}: {} of {} parts already downloaded",
+                        state.done.len(),
+                        state.parts()
+                    );
+                }
+                _ => {}
+            }
+        }
+        if state.done.is_empty() {
+            let file = fs::File::create(part_path).await?;
+            file.set_len(size).await?;
+            drop(file);
+            state.save(state_path).await?;
+        }
+
+        // The first part doubles as a probe: backends that reject or ignore ranges
+        // make us fall back to a single stream.
+        if !state.done.contains(&0) {
+            let first = self
+                .operator
+                .read_with(remote_path)
+                .range(0..part_size)
+                .await;
+            let first = match first {
+                Ok(data) if data.len() as u64 == part_size => data,
+                Ok(data) => {
+                    tracing::warn!(
+                        "Range read of {remote_path} returned {} bytes instead of {part_size}; downloading sequentially",
+                        data.len()
+                    );
+                    let _ = fs::remove_file(state_path).await;
+                    return self.download_sequential(remote_path, part_path).await;
+                }
+                Err(e) if e.kind() == opendal::ErrorKind::Unsupported => {
+                    tracing::warn!(
+                        "Range reads unsupported for {remote_path}; downloading sequentially"
+                    );
+                    let _ = fs::remove_file(state_path).await;
+                    return self.download_sequential(remote_path, part_path).await;
+                }
+                Err(e) => return Err(e.into()),
+            };
+            write_at(part_path, 0, &first.to_vec()).await?;
+            state.done.insert(0);
+            state.save(state_path).await?;
+        }
+
+        let pending: Vec<u64> = (1..state.parts())
+            .filter(|part| !state.done.contains(part))
+            .collect();
+        let mut fetched = stream::iter(pending)
+            .map(|part| async move {
+                self.cancel.check()?;
+                let start = part * part_size;
+                let end = std::cmp::min(start + part_size, size);
+                let expected = end - start;
+                let data = self
+                    .operator
+                    .read_with(remote_path)
+                    .range(start..end)
+                    .await?;
+                if data.len() as u64 != expected {
+                    return Err(Error::InvalidArgument {
+                        message: format!(
+                            "short range read of {remote_path} at offset {start}: got {} of {expected} bytes",
+                            data.len()
+                        ),
+                    });
+                }
+                write_at(part_path, start, &data.to_vec()).await?;
+                Ok(part)
+            })
+            .buffer_unordered(options.concurrency.max(1));
+        while let Some(part) = fetched.try_next().await? {
+            state.done.insert(part);
+            state.save(state_path).await?;
+        }
+        Ok(())
+    }
+
+    /// Format `remote_path` is stored in, from its extension or else its content encoding.
+    async fn compression_of(&self, remote_path: &str) -> Result<Option<Compression>> {
+        if let Some(compression) = Compression::from_extension(remote_path) {
+            return Ok(Some(compression));
+        }
+        let meta = self.operator.stat(remote_path).await?;
+        Ok(Compression::detect(remote_path, meta.content_encoding()))
+    }
+
+    /// Stream an object to disk through decryption with `data_key` and then
+    /// `compression`'s decoder.
+    async fn download_decoded(
+        &self,
+        remote_path: &str,
+        local_file_path: &Path,
+        data_key: Option<&DataKey>,
+        compression: Option<Compression>,
+    ) -> Result<()> {
+        let reader = self
+            .operator
+            .reader_with(remote_path)
+            .chunk(DEFAULT_CHUNK_SIZE)
+            .await?;
+        let bytes = reader.into_bytes_stream(..).await?;
+        let chunks = match data_key {
+            Some(key) => cipher::decrypt_stream(bytes, key).boxed(),
+            None => bytes.map_ok(|chunk| chunk.to_vec()).boxed(),
+        };
+        let cancel = self.cancel.clone();
+        let mut chunks = chunks
+            .and_then(move |chunk| {
+                future::ready(
+                    cancel
+                        .check()
+                        .map(|()| chunk)
+                        .map_err(std::io::Error::other),
+                )
+            })
+            .boxed();
+        let mut file = fs::File::create(local_file_path).await?;
+        match compression {
+            Some(compression) => {
+                let mut decoded = compression.decompress(chunks)?;
+                tokio::io::copy(&mut decoded, &mut file).await?;
+            }
+            None => {
+                while let Some(chunk) = chunks.try_next().await? {
+                    file.write_all(&chunk).await?;
+                }
+            }
+        }
+        file.flush().await?;
+        Ok(())
+    }
+
+    /// Write the planned files into the tar archive `archive_path`, compressed as its
+    /// name says. The archive is staged and only appears once it is complete.
+    async fn download_archive(
+        &self,
+        planned: Vec<(opendal::Entry, String)>,
+        archive_path: &str,
+        report: &mut TransferReport,
+        options: &DownloadOptions,
+    ) -> Result<()> {
+        let compression = archive::compression_for(archive_path)?;
+        let target = Path::new(archive_path);
+        let staged = StagedFile::new(target);
+        let mut file = fs::File::create(staged.path()).await?;
+        let (tar_in, mut tar_out) = tokio::io::duplex(DEFAULT_CHUNK_SIZE);
+        let sink = async move {
+            match compression {
+                Some(compression) => {
+                    let mut encoded = compression.compress(tar_out)?;
+                    tokio::io::copy(&mut encoded, &mut file).await?;
+                }
+                None => {
+                    tokio::io::copy(&mut tar_out, &mut file).await?;
+                }
+            }
+            file.flush().await?;
+            Ok(())
+        };
+        tokio::try_join!(
+            self.write_archive(planned, tar_in, archive_path, report, options),
+            sink
+        )?;
+        staged.persist(target)
+    }
+
+    /// Write each planned file as a tar entry into `out`, then the end-of-archive marker.
+    async fn write_archive(
+        &self,
+        planned: Vec<(opendal::Entry, String)>,
+        mut out: tokio::io::DuplexStream,
+        archive_path: &str,
+        report: &mut TransferReport,
+        options: &DownloadOptions,
+    ) -> Result<()> {
+        for (entry, relative_path) in planned {
+            if entry.metadata().mode().is_dir() {
+                continue;
+            }
+            let remote_file_path = entry.path();
+            let size = self.entry_size(&entry).await?;
+            let (mode, mtime) = self.archive_times(&entry, options).await?;
+            let header = TarEntry {
+                path: relative_path.clone(),
+                size,
+                mode,
+                mtime,
+            };
+            out.write_all(&header.header()).await?;
+
+            let reader = self
+                .operator
+                .reader_with(remote_file_path)
+                .chunk(DEFAULT_CHUNK_SIZE)
+                .await?;
+            let mut bytes = reader.into_bytes_stream(..).await?;
+            let mut written = 0u64;
+            while let Some(chunk) = bytes.try_next().await? {
+                written += chunk.len() as u64;
+                if written > size {
+                    break;
+                }
+                out.write_all(&chunk).await?;
+            }
+            // The header already promised `size` bytes.
+            if written != size {
+                return Err(Error::InvalidArgument {
+                    message: format!("{remote_file_path} changed while it was being archived"),
+                });
+            }
+            out.write_all(&vec![0u8; archive::padding(size)]).await?;
+
+            if options.verbose {
+                println!(
+                    "Archived: {remote_file_path} → {archive_path}:{relative_path} ({size} bytes)"
+                );
+            } else {
+                println!("Archived: {remote_file_path} → {archive_path}:{relative_path}");
+            }
+            report.add_file(size);
+        }
+        out.write_all(&archive::END_OF_ARCHIVE).await?;
+        out.shutdown().await?;
+        Ok(())
+    }
+
+    /// Permission bits and modification time (Unix seconds) of an archived file: those
+    /// stored by `put --preserve` with `--preserve`, else 0644 and the object's time.
+    async fn archive_times(
+        &self,
+        entry: &opendal::Entry,
+        options: &DownloadOptions,
+    ) -> Result<(u32, u64)> {
+        let mut mode = 0o644;
+        let mut modified = entry.metadata().last_modified();
+        if options.preserve || modified.is_none() {
+            let meta = self.operator.stat(entry.path()).await?;
+            modified = meta.last_modified();
+            if options.preserve
+                && let Some(metadata) = meta.user_metadata()
+            {
+                if let Some(stored) = metadata
+                    .get(preserve::MODE_METADATA_KEY)
+                    .and_then(|value| u32::from_str_radix(value, 8).ok())
+                {
+                    mode = stored;
+                }
+                if let Some(stored) = metadata
+                    .get(preserve::MTIME_METADATA_KEY)
+                    .and_then(|value| preserve::parse_mtime(value))
+                    .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                {
+                    return Ok((mode, stored.as_secs()));
+                }
+            }
+        }
+        let mtime = modified.map_or(0, |time| time.timestamp().max(0) as u64);
+        Ok((mode, mtime))
+    }
+
+    /// Stream an object to disk without buffering it whole in memory.
+    async fn download_sequential(&self, remote_path: &str, local_file_path: &Path) -> Result<()> {
+        let reader = self
+            .operator
+            .reader_with(remote_path)
+            .chunk(DEFAULT_CHUNK_SIZE)
+            .await?;
+        let mut bytes = reader.into_bytes_stream(..).await?;
+        let mut file = fs::File::create(local_file_path).await?;
+        while let Some(chunk) = bytes.try_next().await? {
+            self.cancel.check()?;
+            file.write_all(&chunk).await?;
+        }
+        file.flush().await?;
+        Ok(())
+    }
+}
+
+/// Local path a single remote file is downloaded to, refusing to replace a directory.
+fn local_file_target(remote_path: &str, local_path: &str) -> Result<PathBuf> {
+    if !names_directory(local_path) && Path::new(local_path).is_dir() {
+        return Err(Error::DestinationIsDirectory {
+            path: local_path.to_string(),
+        });
+    }
+    Ok(PathBuf::from(transfer_target(remote_path, local_path)))
+}
+
+/// Local directory the entries of a remote directory are downloaded into.
+fn local_directory_target(remote_path: &str, local_path: &str) -> Result<PathBuf> {
+    if !names_directory(local_path) && Path::new(local_path).is_file() {
+        return Err(Error::DestinationIsFile {
+            path: local_path.to_string(),
+        });
+    }
+    Ok(PathBuf::from(transfer_target(remote_path, local_path)))
+}
+
+/// The `.part` file and its state file for a download to `target`.
+fn part_paths(target: &Path) -> (PathBuf, PathBuf) {
+    let name = target
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    (
+        target.with_file_name(format!("{name}.part")),
+        target.with_file_name(format!("{name}.part.json")),
+    )
+}
+
+async fn part_file_len(path: &Path) -> Option<u64> {
+    fs::metadata(path).await.ok().map(|meta| meta.len())
+}
+
+/// Write `data` into an existing file at `offset`.
+async fn write_at(path: &Path, offset: u64, data: &[u8]) -> Result<()> {
+    let mut file = fs::OpenOptions::new().write(true).open(path).await?;
+    file.seek(SeekFrom::Start(offset)).await?;
+    file.write_all(data).await?;
+    file.flush().await?;
+    // On disk before the part is recorded as done, so a crash cannot resume over a hole.
+    file.sync_data().await?;
+    Ok(())
+}
+
+/// Fail early when the filesystem holding `local_path` cannot fit `required` bytes.
+fn ensure_free_space(local_path: &Path, required: u64) -> Result<()> {
+    // The destination may not exist yet; measure the closest existing ancestor.
+    let probe = local_path
+        .ancestors()
+        .find(|p| p.exists())
+        .unwrap_or_else(|| Path::new("."));
+    let available = fs4::available_space(probe)?;
+    if required > available {
+        return Err(Error::InsufficientSpace {
+            path: local_path.to_path_buf(),
+            required,
+            available,
+        });
     }
+    Ok(())
 }
 
 impl Downloader for OpenDalDownloader {
-    async fn download(&self, remote_path: &str, local_path: &str) -> Result<()> {
+    async fn download(
+        &self,
+        remote_path: &str,
+        local_path: &str,
+        options: &DownloadOptions,
+    ) -> Result<TransferReport> {
+        let started = Instant::now();
         if !self.operator.exists(remote_path).await? {
             return Err(Error::PathNotFound {
                 path: PathBuf::from(remote_path),
             });
         }
 
+        // A single file is planned under its target name in the target's directory.
+        let (local_root, file_name, list_path) = if options.archive {
+            archive::compression_for(local_path)?;
+            if Path::new(local_path).is_dir() {
+                return Err(Error::DestinationIsDirectory {
+                    path: local_path.to_string(),
+                });
+            }
+            let root = PathBuf::from(local_path);
+            if is_remote_file(&self.operator, remote_path).await {
+                let file_name = Path::new(remote_path)
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string());
+                (root, file_name, remote_path.to_string())
+            } else {
+                (root, None, ensure_trailing_slash(remote_path))
+            }
+        } else if is_remote_file(&self.operator, remote_path).await {
+            let target = local_file_target(remote_path, local_path)?;
+            let file_name = target
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string());
+            let parent = target.parent().map(Path::to_path_buf).unwrap_or_default();
+            (parent, file_name, remote_path.to_string())
+        } else {
+            let root = local_directory_target(remote_path, local_path)?;
+            (root, None, ensure_trailing_slash(remote_path))
+        };
+
         let lister = self
             .operator
-            .lister_with(remote_path)
+            .lister_with(&list_path)
             .recursive(true)
             .await?;
 
+        // Plan all local targets first so path problems surface before any transfer starts.
+        let mut planned = Vec::new();
+        let filtered = !options.filter.is_empty() || options.newer_than.is_some();
+        let mut skipped = 0usize;
         let mut stream = lister;
         while let Some(entry) = stream.try_next().await? {
-            let meta = entry.metadata();
             let remote_file_path = entry.path();
+            // Only the file itself (not `a.log.1` next to `a.log`), and not the listed directory
+            let is_listed_path =
+                remote_file_path.trim_start_matches('/') == list_path.trim_start_matches('/');
+            if file_name.is_some() != is_listed_path {
+                continue;
+            }
             // Skip malformed keys that contain double slashes which may be normalized differently at read time
             if remote_file_path.contains("//") {
-                log::warn!(
+                tracing::warn!(
                     "Skip malformed remote key containing double slashes: {}",
                     remote_file_path
                 );
                 continue;
             }
-            let mut relative_path = get_root_relative_path(remote_file_path, remote_path);
+            let mut relative_path = match &file_name {
+                Some(name) => name.clone(),
+                None => get_root_relative_path(remote_file_path, &list_path),
+            };
             if relative_path.is_empty() {
                 // Fallback: use base name
                 relative_path = Path::new(remote_file_path)
@@ -64,7 +826,63 @@ impl Downloader for OpenDalDownloader {
                     .map(|s| s.to_string_lossy().to_string())
                     .unwrap_or_default();
             }
-            let local_file_path = Path::new(local_path).join(relative_path);
+            if filtered && !self.selected(&entry, &relative_path, options).await? {
+                skipped += 1;
+                continue;
+            }
+            planned.push((entry, relative_path));
+        }
+        if skipped > 0 && planned.is_empty() {
+            println!("No files under {remote_path} matched the filters");
+            return Ok(TransferReport {
+                directory: file_name.is_none(),
+                skipped,
+                elapsed: started.elapsed(),
+                ..Default::default()
+            });
+        }
+        report_warnings(&local_path_warnings(
+            planned.iter().map(|(_, rel)| rel.as_str()),
+        ));
+
+        if options.check_space {
+            let required = self.planned_size(&planned).await?;
+            let space_root = match options.archive {
+                true => local_root.parent().unwrap_or(Path::new(".")),
+                false => &local_root,
+            };
+            ensure_free_space(space_root, required)?;
+        }
+        if options.dry_run {
+            self.print_plan(&planned, &local_root).await?;
+            return Ok(TransferReport::default());
+        }
+
+        let mut report = TransferReport {
+            directory: file_name.is_none(),
+            skipped,
+            ..Default::default()
+        };
+        if options.archive {
+            self.download_archive(planned, local_path, &mut report, options)
+                .await?;
+            report.elapsed = started.elapsed();
+            return Ok(report);
+        }
+        for (entry, relative_path) in planned {
+            self.cancel.check()?;
+            let meta = entry.metadata();
+            let remote_file_path = entry.path();
+            let mut local_file_path = local_root.join(relative_path);
+            let compression = if options.decompress && meta.mode() != EntryMode::DIR {
+                self.compression_of(remote_file_path).await?
+            } else {
+                None
+            };
+            if let Some(compression) = compression {
+                let name = local_file_path.to_string_lossy().into_owned();
+                local_file_path = PathBuf::from(compression.strip_extension(&name));
+            }
 
             if meta.mode() == EntryMode::DIR {
                 fs::create_dir_all(&local_file_path).await?;
@@ -72,29 +890,98 @@ impl Downloader for OpenDalDownloader {
                 if let Some(parent) = local_file_path.parent() {
                     fs::create_dir_all(parent).await?;
                 }
-                match self.operator.read(remote_file_path).await {
-                    Ok(data) => {
-                        fs::write(&local_file_path, data.to_vec()).await?;
-                        println!(
-                            "Downloaded: {remote_file_path} → {}",
-                            local_file_path.display()
-                        );
-                    }
-                    Err(e) => {
-                        // Gracefully skip objects that cannot be found due to key normalization issues
-                        if e.kind() == opendal::ErrorKind::NotFound {
-                            log::warn!(
-                                "Skip not found at read (likely normalized key): {}",
-                                remote_file_path
+                let result = match self.entry_size(&entry).await {
+                    Ok(size) => self
+                        .download_file(
+                            remote_file_path,
+                            &local_file_path,
+                            size,
+                            compression,
+                            options,
+                        )
+                        .await
+                        .map(|()| size),
+                    Err(e) => Err(e),
+                };
+                match result {
+                    Ok(size) => {
+                        if options.verbose {
+                            println!(
+                                "Downloaded: {remote_file_path} → {} ({size} bytes)",
+                                local_file_path.display()
+                            );
+                        } else {
+                            println!(
+                                "Downloaded: {remote_file_path} → {}",
+                                local_file_path.display()
                             );
-                            continue;
                         }
-                        return Err(e.into());
+                        report.add_file(size);
+                    }
+                    // Gracefully skip objects that cannot be found due to key normalization issues
+                    Err(Error::OpenDal { source })
+                        if source.kind() == opendal::ErrorKind::NotFound =>
+                    {
+                        tracing::warn!(
+                            "Skip not found at read (likely normalized key): {}",
+                            remote_file_path
+                        );
+                        let _ = fs::remove_file(&local_file_path).await;
+                        report.skipped += 1;
+                    }
+                    // Keep going through a directory; a single file fails outright.
+                    Err(e) if report.directory => {
+                        eprintln!(
+                            "Failed: {remote_file_path} → {}: {e}",
+                            local_file_path.display()
+                        );
+                        report.failed += 1;
                     }
+                    Err(e) => return Err(e),
                 }
             }
         }
 
-        Ok(())
+        report.elapsed = started.elapsed();
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(etag: Option<&str>, last_modified: Option<&str>) -> PartState {
+        PartState {
+            etag: etag.map(str::to_string),
+            last_modified: last_modified.map(str::to_string),
+            size: 20,
+            part_size: 8,
+            done: BTreeSet::from([0, 2]),
+        }
+    }
+
+    #[test]
+    fn part_state_resumes_only_the_same_object_and_layout() {
+        let saved = state(Some("\"abc\""), Some("2024-01-01T00:00:00+00:00"));
+        assert_eq!(saved.parts(), 3);
+        assert!(saved.resumes(&state(Some("\"abc\""), None)));
+        assert!(!saved.resumes(&state(Some("\"def\""), Some("2024-01-01T00:00:00+00:00"))));
+        // Without an etag on either side the modification time decides.
+        let saved = state(None, Some("2024-01-01T00:00:00+00:00"));
+        assert!(saved.resumes(&state(None, Some("2024-01-01T00:00:00+00:00"))));
+        assert!(!saved.resumes(&state(None, Some("2024-01-02T00:00:00+00:00"))));
+        let resized = PartState {
+            part_size: 16,
+            ..state(None, Some("2024-01-01T00:00:00+00:00"))
+        };
+        assert!(!saved.resumes(&resized));
+    }
+
+    #[test]
+    fn part_files_sit_beside_the_target() {
+        let (part, state) = part_paths(Path::new("out/big.bin"));
+        assert_eq!(part, Path::new("out/big.bin.part"));
+        assert_eq!(state, Path::new("out/big.bin.part.json"));
     }
 }
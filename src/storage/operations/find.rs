@@ -1,9 +1,15 @@
-use crate::error::{Error, Result};
+use crate::config::env_export::shell_quote;
+use crate::error::{Error, ExecFailedSnafu, PartialDeletionSnafu, Result};
 use crate::storage::utils::error::IntoStorifyError;
+use crate::storage::utils::format::{EntryFields, Template};
+use crate::storage::utils::paging::{PageSize, list_entries_after, report_continuation};
+use crate::storage::utils::path::is_root_path;
+use chrono::Utc;
 use futures::stream::TryStreamExt;
 use globset::GlobMatcher;
-use opendal::{EntryMode, Operator};
+use opendal::{EntryMode, Metadata, Operator};
 use regex::Regex;
+use std::borrow::Cow;
 
 pub trait Finder {
     async fn find(&self, opts: &FindOptions) -> Result<()>;
@@ -11,11 +17,21 @@ pub trait Finder {
 
 pub struct OpenDalFinder {
     operator: Operator,
+    page_size: PageSize,
 }
 
 impl OpenDalFinder {
     pub fn new(operator: Operator) -> Self {
-        Self { operator }
+        Self {
+            operator,
+            page_size: PageSize::Auto,
+        }
+    }
+
+    /// Page size of the listing requests.
+    pub fn with_page_size(mut self, page_size: PageSize) -> Self {
+        self.page_size = page_size;
+        self
     }
 }
 
@@ -26,24 +42,259 @@ pub enum EntryTypeFilter {
     Other,
 }
 
+/// A numeric test as written for `find`: `+N` (more than N), `-N` (less than N) or `N`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Above(u64),
+    Below(u64),
+    Exactly(u64),
+}
+
+impl Comparison {
+    /// Parse `[+|-]VALUE`, reading VALUE with `parse_value`.
+    pub fn parse(
+        input: &str,
+        flag: &str,
+        expected: &str,
+        parse_value: impl Fn(&str) -> Option<u64>,
+    ) -> Result<Self> {
+        let s = input.trim();
+        let invalid = || Error::InvalidArgument {
+            message: format!("invalid {flag} '{s}' (expected {expected})"),
+        };
+        let (make, value): (fn(u64) -> Self, &str) = match s.as_bytes().first() {
+            Some(b'+') => (Self::Above, &s[1..]),
+            Some(b'-') => (Self::Below, &s[1..]),
+            _ => (Self::Exactly, s),
+        };
+        parse_value(value).map(make).ok_or_else(invalid)
+    }
+
+    pub fn matches(&self, value: u64) -> bool {
+        match *self {
+            Comparison::Above(n) => value > n,
+            Comparison::Below(n) => value < n,
+            Comparison::Exactly(n) => value == n,
+        }
+    }
+}
+
+/// What `find` does with each matching path.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum FindAction {
+    #[default]
+    Print,
+    /// Delete the path; directories go after everything below them
+    Delete,
+    /// Run a shell command, with `{}` replaced by the quoted path (appended when absent)
+    Exec(String),
+    /// Print the path and its metadata through a `--format` template
+    Format(Template),
+}
+
 pub struct FindOptions {
     pub path: String,
     pub name_glob: Option<GlobMatcher>,
     pub regex: Option<Regex>,
     pub type_filter: Option<EntryTypeFilter>,
+    /// Size in bytes
+    pub size: Option<Comparison>,
+    /// Age in whole days since the last modification
+    pub mtime_days: Option<Comparison>,
+    /// Depth below `path`, whose direct children are at depth 1
+    pub min_depth: Option<usize>,
+    pub max_depth: Option<usize>,
+    pub action: FindAction,
+    /// Stop after printing this many paths
+    pub max_results: Option<usize>,
+    /// Only scan keys sorting after this one (continues a `max_results` run)
+    pub start_after: Option<String>,
+}
+
+impl OpenDalFinder {
+    /// Metadata for the size and mtime tests, stat-ing entries listed without it.
+    async fn full_metadata<'a>(
+        &self,
+        path: &str,
+        listed: &'a Metadata,
+    ) -> Result<Cow<'a, Metadata>> {
+        // Filesystem-like backends list names only.
+        if listed.last_modified().is_some() {
+            return Ok(Cow::Borrowed(listed));
+        }
+        Ok(Cow::Owned(self.operator.stat(path).await?))
+    }
+
+    /// Metadata handed to the action, complete when a `--format` template prints it.
+    async fn action_metadata<'a>(
+        &self,
+        path: &str,
+        listed: &'a Metadata,
+        action: &FindAction,
+    ) -> Result<Cow<'a, Metadata>> {
+        match action {
+            FindAction::Format(format)
+                if !listed.mode().is_dir() && format.uses_any(EntryFields::METADATA) =>
+            {
+                self.full_metadata(path, listed).await
+            }
+            _ => Ok(Cow::Borrowed(listed)),
+        }
+    }
+
+    /// Whether `path` passes every test; metadata is only fetched when a test needs it.
+    async fn matches(&self, path: &str, listed: &Metadata, opts: &FindOptions) -> Result<bool> {
+        if !is_match(path, listed, opts) {
+            return Ok(false);
+        }
+        if opts.size.is_none() && opts.mtime_days.is_none() {
+            return Ok(true);
+        }
+        let meta = self.full_metadata(path, listed).await?;
+        if let Some(size) = opts.size
+            && !size.matches(meta.content_length())
+        {
+            return Ok(false);
+        }
+        if let Some(mtime) = opts.mtime_days {
+            let Some(modified) = meta.last_modified() else {
+                return Ok(false);
+            };
+            let age_days = (Utc::now() - modified).num_days().max(0) as u64;
+            if !mtime.matches(age_days) {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// Carries out `--delete` and `--exec`, collecting failures for the end of the scan.
+#[derive(Default)]
+struct Actions {
+    /// Directories to delete once the files below them are gone, with their depth
+    dirs: Vec<(String, usize)>,
+    failed_deletes: Vec<String>,
+    failed_execs: usize,
+}
+
+impl Actions {
+    async fn run(
+        &mut self,
+        operator: &Operator,
+        action: &FindAction,
+        path: &str,
+        meta: &Metadata,
+        depth: usize,
+    ) -> Result<()> {
+        match action {
+            FindAction::Print => println!("{path}"),
+            FindAction::Format(format) => println!(
+                "{}",
+                format.render(&EntryFields {
+                    path,
+                    is_dir: meta.mode() == EntryMode::DIR,
+                    size: meta.content_length(),
+                    last_modified: meta.last_modified(),
+                    storage_class: None,
+                    depth: Some(depth),
+                })
+            ),
+            FindAction::Delete if is_root_path(path) => {}
+            FindAction::Delete if meta.mode() == EntryMode::DIR => {
+                self.dirs.push((path.to_string(), depth));
+            }
+            FindAction::Delete => self.delete(operator, path).await,
+            FindAction::Exec(template) => {
+                let quoted = shell_quote(path);
+                let command = if template.contains("{}") {
+                    template.replace("{}", &quoted)
+                } else {
+                    format!("{template} {quoted}")
+                };
+                let status = shell(&command).status().await?;
+                if !status.success() {
+                    eprintln!("--exec failed for {path}: {status}");
+                    self.failed_execs += 1;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn delete(&mut self, operator: &Operator, path: &str) {
+        match operator.delete(path).await {
+            Ok(()) => println!("Deleted: {path}"),
+            Err(e) => {
+                eprintln!("Failed to delete {path}: {e}");
+                self.failed_deletes.push(path.to_string());
+            }
+        }
+    }
+
+    /// Delete the deferred directories deepest first, then report failures.
+    async fn finish(mut self, operator: &Operator) -> Result<()> {
+        let mut dirs = std::mem::take(&mut self.dirs);
+        dirs.sort_by_key(|(_, depth)| std::cmp::Reverse(*depth));
+        for (dir, _) in dirs {
+            self.delete(operator, &dir).await;
+        }
+        if !self.failed_deletes.is_empty() {
+            return PartialDeletionSnafu {
+                failed_paths: self.failed_deletes,
+            }
+            .fail();
+        }
+        if self.failed_execs > 0 {
+            return ExecFailedSnafu {
+                failed: self.failed_execs,
+            }
+            .fail();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+fn shell(command: &str) -> tokio::process::Command {
+    let mut cmd = tokio::process::Command::new("sh");
+    cmd.arg("-c").arg(command);
+    cmd
+}
+
+#[cfg(not(unix))]
+fn shell(command: &str) -> tokio::process::Command {
+    let mut cmd = tokio::process::Command::new("cmd");
+    cmd.arg("/C").arg(command);
+    cmd
+}
+
+/// Number of path segments `path` lies below `root`; `root` itself is at depth 0.
+fn depth_below(path: &str, root: &str) -> usize {
+    let path = path.trim_matches('/');
+    let root = root.trim_matches('/');
+    let relative = if root.is_empty() {
+        path
+    } else {
+        path.strip_prefix(root).unwrap_or(path)
+    };
+    relative.split('/').filter(|s| !s.is_empty()).count()
 }
 
 impl Finder for OpenDalFinder {
     async fn find(&self, opts: &FindOptions) -> Result<()> {
+        let mut actions = Actions::default();
         // First, try stat to determine if it's a file; if not found, map to PathNotFound
         match self.operator.stat(&opts.path).await {
             Ok(meta) => {
                 if meta.mode() == EntryMode::FILE {
                     let path = opts.path.as_str();
-                    if is_match(path, &meta, opts) {
-                        println!("{}", path);
+                    if opts.min_depth.unwrap_or(0) == 0 && self.matches(path, &meta, opts).await? {
+                        actions
+                            .run(&self.operator, &opts.action, path, &meta, 0)
+                            .await?;
                     }
-                    return Ok(());
+                    return actions.finish(&self.operator).await;
                 }
             }
             Err(e) => {
@@ -56,35 +307,50 @@ impl Finder for OpenDalFinder {
             }
         }
 
-        let lister = self
-            .operator
-            .lister_with(&opts.path)
-            .recursive(true)
-            .await
-            .map_err(|e| Error::FindFailed {
-                path: opts.path.clone(),
-                source: Box::new(IntoStorifyError::into_error(e)),
-            })?;
+        // Nothing below the direct children is wanted, so one level of listing is enough.
+        let recursive = opts.max_depth.is_none_or(|max| max > 1);
+        let lister = list_entries_after(
+            &self.operator,
+            &opts.path,
+            recursive,
+            self.page_size,
+            opts.start_after.as_deref(),
+        )
+        .await
+        .map_err(|e| Error::FindFailed {
+            path: opts.path.clone(),
+            source: Box::new(IntoStorifyError::into_error(e)),
+        })?;
 
         futures::pin_mut!(lister);
-        let no_filters =
-            opts.type_filter.is_none() && opts.name_glob.is_none() && opts.regex.is_none();
+        let mut printed = 0;
         while let Some(entry) = lister.try_next().await.map_err(|e| Error::FindFailed {
             path: opts.path.clone(),
             source: Box::new(IntoStorifyError::into_error(e.into_error())),
         })? {
-            if no_filters {
-                println!("{}", entry.path());
+            let path = entry.path();
+            let depth = depth_below(path, &opts.path);
+            if opts.min_depth.is_some_and(|min| depth < min)
+                || opts.max_depth.is_some_and(|max| depth > max)
+            {
                 continue;
             }
-
-            let meta = entry.metadata();
-            let path = entry.path();
-            if is_match(path, meta, opts) {
-                println!("{}", path);
+            if !self.matches(path, entry.metadata(), opts).await? {
+                continue;
+            }
+            let meta = self
+                .action_metadata(path, entry.metadata(), &opts.action)
+                .await?;
+            actions
+                .run(&self.operator, &opts.action, path, &meta, depth)
+                .await?;
+            printed += 1;
+            if opts.max_results.is_some_and(|max| printed >= max) {
+                report_continuation(path);
+                break;
             }
         }
-        Ok(())
+        actions.finish(&self.operator).await
     }
 }
 
@@ -114,3 +380,31 @@ fn is_match(path: &str, meta: &opendal::Metadata, opts: &FindOptions) -> bool {
 
     true
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::parse_size;
+
+    #[test]
+    fn comparisons_parse_signs_and_units() {
+        let size = |s| Comparison::parse(s, "--size", "", parse_size);
+        assert_eq!(size("+10M").unwrap(), Comparison::Above(10 << 20));
+        assert_eq!(size("-1k").unwrap(), Comparison::Below(1024));
+        assert_eq!(size("4096").unwrap(), Comparison::Exactly(4096));
+        assert!(size("+").is_err());
+        assert!(size("ten").is_err());
+
+        assert!(Comparison::Above(7).matches(8));
+        assert!(!Comparison::Above(7).matches(7));
+        assert!(Comparison::Below(1).matches(0));
+    }
+
+    #[test]
+    fn depth_counts_segments_below_the_root() {
+        assert_eq!(depth_below("logs/", "logs/"), 0);
+        assert_eq!(depth_below("logs/a.log", "logs/"), 1);
+        assert_eq!(depth_below("logs/2024/01/", "logs"), 2);
+        assert_eq!(depth_below("a/b", ""), 2);
+    }
+}
@@ -0,0 +1,133 @@
+use crate::error::Result;
+use crate::scratch::scratch_prefix;
+use crate::storage::rest::RestBucket;
+use chrono::{DateTime, Utc};
+use futures::stream::TryStreamExt;
+use opendal::Operator;
+use std::time::Duration;
+
+use super::multipart::{PendingUpload, abort_upload, pending_uploads};
+
+/// Settings for `storify gc`.
+#[derive(Debug, Clone, Copy)]
+pub struct GcOptions {
+    /// Only remove leftovers at least this old
+    pub older_than: Duration,
+    /// Print what would be removed without removing it
+    pub dry_run: bool,
+}
+
+/// Trait for removing what interrupted runs left in a bucket.
+pub trait GarbageCollector {
+    /// Remove stale objects under the scratch prefix and abort stale multipart uploads.
+    ///
+    /// # Arguments
+    /// * `path` - Prefix whose incomplete uploads are aborted (the whole bucket when empty)
+    /// * `options` - Age threshold and dry-run switch
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or detailed error information
+    async fn collect(&self, path: &str, options: GcOptions) -> Result<()>;
+}
+
+/// Implementation of GarbageCollector for OpenDAL Operator, with incomplete multipart
+/// uploads found through the provider's REST API when one is available.
+pub struct OpenDalGarbageCollector {
+    operator: Operator,
+    uploads: Option<RestBucket>,
+}
+
+impl OpenDalGarbageCollector {
+    /// Create a new collector with the given OpenDAL operator.
+    pub fn new(operator: Operator) -> Self {
+        Self {
+            operator,
+            uploads: None,
+        }
+    }
+
+    /// Also abort incomplete multipart uploads of `bucket`.
+    pub fn with_uploads(mut self, bucket: RestBucket) -> Self {
+        self.uploads = Some(bucket);
+        self
+    }
+
+    async fn sweep_scratch(&self, cutoff: DateTime<Utc>, dry_run: bool) -> Result<usize> {
+        let prefix = scratch_prefix();
+        let lister = match self.operator.lister_with(&prefix).recursive(true).await {
+            Ok(lister) => lister,
+            Err(e) if e.kind() == opendal::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e.into()),
+        };
+        let entries: Vec<opendal::Entry> = lister.try_collect().await?;
+        let mut removed = 0;
+        for entry in entries {
+            if entry.metadata().mode().is_dir() {
+                continue;
+            }
+            let modified = match entry.metadata().last_modified() {
+                Some(modified) => Some(modified),
+                None => self.operator.stat(entry.path()).await?.last_modified(),
+            };
+            if modified.is_some_and(|modified| modified > cutoff) {
+                continue;
+            }
+            if dry_run {
+                println!("Would remove scratch object {}", entry.path());
+            } else {
+                self.operator.delete(entry.path()).await?;
+                println!("Removed scratch object {}", entry.path());
+            }
+            removed += 1;
+        }
+        Ok(removed)
+    }
+
+    async fn abort_uploads(
+        &self,
+        bucket: &RestBucket,
+        path: &str,
+        cutoff: DateTime<Utc>,
+        dry_run: bool,
+    ) -> Result<usize> {
+        let stale: Vec<PendingUpload> = pending_uploads(bucket, path)
+            .await?
+            .into_iter()
+            .filter(|upload| upload.started_before(cutoff))
+            .collect();
+        for upload in &stale {
+            if dry_run {
+                println!(
+                    "Would abort upload of {} (started {})",
+                    upload.key,
+                    upload.started()
+                );
+            } else {
+                abort_upload(bucket, upload).await?;
+            }
+        }
+        Ok(stale.len())
+    }
+}
+
+impl GarbageCollector for OpenDalGarbageCollector {
+    async fn collect(&self, path: &str, options: GcOptions) -> Result<()> {
+        let cutoff = Utc::now()
+            - chrono::Duration::from_std(options.older_than).unwrap_or(chrono::Duration::MAX);
+        let removed = self.sweep_scratch(cutoff, options.dry_run).await?;
+        let aborted = match &self.uploads {
+            Some(bucket) => {
+                self.abort_uploads(bucket, path, cutoff, options.dry_run)
+                    .await?
+            }
+            None => 0,
+        };
+        let verb = if options.dry_run {
+            "Would remove"
+        } else {
+            "Removed"
+        };
+        println!("{verb} {removed} scratch object(s) and {aborted} incomplete upload(s)");
+        Ok(())
+    }
+}
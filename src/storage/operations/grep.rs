@@ -1,33 +1,239 @@
 use crate::error::{Error, Result};
-use memchr::memchr_iter;
-use opendal::Operator;
+use crate::storage::operations::latest::list_files;
+use crate::storage::utils::filter::PathFilter;
+use chrono::{DateTime, Utc};
+use memchr::{memchr, memchr_iter};
+use opendal::{Metadata, Operator};
+use regex::{Regex, RegexBuilder};
+use std::collections::VecDeque;
 use std::io::BufWriter;
 use std::io::{self, Write};
+use std::time::Duration;
 
-struct GrepOptions<'a, W: Write> {
+const CHUNK_SIZE: u64 = 64 * 1024;
+
+/// Settings for `storify grep`.
+#[derive(Debug, Clone, Default)]
+pub struct GrepOptions {
+    pub ignore_case: bool,
+    pub line_number: bool,
+    /// Treat the pattern as a regular expression instead of a literal substring
+    pub regex: bool,
+    /// Select the lines that do not match
+    pub invert_match: bool,
+    /// Print the number of selected lines per file instead of the lines
+    pub count: bool,
+    /// Print only the paths of files with at least one selected line
+    pub files_with_matches: bool,
+    /// Stop reading a file after this many selected lines
+    pub max_count: Option<usize>,
+    /// Files of a recursive search to read, by path relative to the searched prefix
+    pub filter: PathFilter,
+    /// Files of a recursive search read at the same time
+    pub concurrency: usize,
+    /// Lines printed after each selected line (`-A`)
+    pub after_context: usize,
+    /// Lines printed before each selected line (`-B`)
+    pub before_context: usize,
+    /// Search files that look binary instead of skipping them
+    pub text: bool,
+    /// Stop a recursive search after the file whose output reaches this many lines
+    pub max_results: Option<usize>,
+    /// Only search keys sorting after this one (continues a `max_results` run)
+    pub start_after: Option<String>,
+}
+
+/// Line matcher, compiled once per command and shared by every searched file.
+pub enum Matcher {
+    /// Literal substring, pre-lowercased when matching case-insensitively
+    Substring {
+        needle: String,
+        ignore_case: bool,
+    },
+    Regex(Regex),
+}
+
+impl Matcher {
+    pub fn new(pattern: &str, ignore_case: bool, regex: bool) -> Result<Self> {
+        if regex {
+            return RegexBuilder::new(pattern)
+                .case_insensitive(ignore_case)
+                .build()
+                .map(Matcher::Regex)
+                .map_err(|e| Error::InvalidArgument {
+                    message: format!("invalid regex '{pattern}': {e}"),
+                });
+        }
+        let needle = if ignore_case {
+            pattern.to_lowercase()
+        } else {
+            pattern.to_string()
+        };
+        Ok(Matcher::Substring {
+            needle,
+            ignore_case,
+        })
+    }
+
+    fn is_match(&self, line: &str) -> bool {
+        match self {
+            Matcher::Regex(regex) => regex.is_match(line),
+            Matcher::Substring {
+                needle,
+                ignore_case: false,
+            } => line.contains(needle.as_str()),
+            // Optimize ASCII fast-path for case-insensitive checks without allocations
+            Matcher::Substring {
+                needle,
+                ignore_case: true,
+            } => {
+                if line.is_ascii() && needle.is_ascii() {
+                    // needle is pre-lowercased; compare haystack in-place without allocations.
+                    Self::ascii_contains_case_insensitive(line.as_bytes(), needle.as_bytes())
+                } else {
+                    line.to_lowercase().contains(needle.as_str())
+                }
+            }
+        }
+    }
+
+    #[inline]
+    fn ascii_contains_case_insensitive(haystack: &[u8], needle: &[u8]) -> bool {
+        if needle.is_empty() {
+            return true;
+        }
+        if needle.len() > haystack.len() {
+            return false;
+        }
+        let nlen = needle.len();
+        for i in 0..=haystack.len() - nlen {
+            let mut j = 0;
+            while j < nlen {
+                if haystack[i + j].to_ascii_lowercase() != needle[j] {
+                    break;
+                }
+                j += 1;
+            }
+            if j == nlen {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+/// Per-file state of a search: selection rules, output target and selected line count.
+struct LineSink<'a, W: Write> {
     path: &'a str,
-    needle: &'a str,
-    ignore_case: bool,
+    matcher: &'a Matcher,
+    invert_match: bool,
     line_number: bool,
     with_filename: bool,
+    count: bool,
+    files_with_matches: bool,
+    max_count: Option<usize>,
+    /// Lines selected so far in this file
+    selected: usize,
+    after_context: usize,
+    before_context: usize,
+    /// Most recent unprinted lines, kept for `-B`
+    before: VecDeque<(usize, String)>,
+    /// Context lines still to print after the last selected line
+    after_remaining: usize,
+    /// Number of the last printed line, to separate non-adjacent context groups
+    last_printed: Option<usize>,
     handle: &'a mut W,
     out_buf: String,
 }
 
+impl<'a, W: Write> LineSink<'a, W> {
+    fn new(path: &'a str, matcher: &'a Matcher, handle: &'a mut W) -> Self {
+        Self {
+            path,
+            matcher,
+            invert_match: false,
+            line_number: false,
+            with_filename: false,
+            count: false,
+            files_with_matches: false,
+            max_count: None,
+            selected: 0,
+            after_context: 0,
+            before_context: 0,
+            before: VecDeque::new(),
+            after_remaining: 0,
+            last_printed: None,
+            handle,
+            out_buf: String::with_capacity(256),
+        }
+    }
+
+    fn max_reached(&self) -> bool {
+        self.max_count.is_some_and(|max| self.selected >= max)
+    }
+
+    /// Whether the rest of the file can be skipped.
+    fn done(&self) -> bool {
+        (self.max_reached() && self.after_remaining == 0)
+            || (self.files_with_matches && self.selected > 0)
+    }
+
+    fn prints_lines(&self) -> bool {
+        !self.count && !self.files_with_matches
+    }
+}
+
+/// Settings for `grep --follow`.
+#[derive(Debug, Clone)]
+pub struct FollowOptions {
+    pub ignore_case: bool,
+    pub line_number: bool,
+    /// Treat the pattern as a regular expression instead of a literal substring
+    pub regex: bool,
+    /// Select the lines that do not match
+    pub invert_match: bool,
+    /// Delay between polls of the followed object
+    pub interval: Duration,
+    /// Also scan content written at or after this time instead of starting at the end
+    pub since: Option<DateTime<Utc>>,
+}
+
+/// Read position within the object currently being followed.
+struct FollowCursor {
+    path: String,
+    offset: u64,
+    line_no: usize,
+    leftover: Vec<u8>,
+}
+
+impl FollowCursor {
+    fn new(path: String, offset: u64) -> Self {
+        Self {
+            path,
+            offset,
+            line_no: 0,
+            leftover: Vec::new(),
+        }
+    }
+}
+
 /// Trait for searching patterns in files.
 pub trait Greper {
-    /// Search for lines matching pattern in file and print matches.
+    /// Search for lines selected by `matcher` in file and print them, their count or the
+    /// file path as `options` asks.
     ///
-    /// - `ignore_case`: case-insensitive when true
-    /// - `line_number`: prefix output lines with 1-based line numbers when true
+    /// - `with_filename`: prefix output lines with the file path when true
     async fn grep(
         &self,
         path: &str,
-        pattern: &str,
-        ignore_case: bool,
-        line_number: bool,
+        matcher: &Matcher,
+        options: &GrepOptions,
         with_filename: bool,
     ) -> Result<()>;
+
+    /// Poll an object, or the newest object under a `/`-terminated prefix, and print newly
+    /// appended lines matching `pattern` until interrupted.
+    async fn follow(&self, path: &str, pattern: &str, options: &FollowOptions) -> Result<()>;
 }
 
 /// OpenDAL-based grep implementation.
@@ -43,10 +249,25 @@ impl OpenDalGreper {
     pub async fn search_and_print(
         &self,
         path: &str,
-        pattern: &str,
-        ignore_case: bool,
-        line_number: bool,
+        matcher: &Matcher,
+        options: &GrepOptions,
+        with_filename: bool,
+    ) -> Result<()> {
+        let stdout = io::stdout();
+        let mut handle = BufWriter::new(stdout.lock());
+        self.search(path, matcher, options, with_filename, &mut handle)
+            .await
+    }
+
+    /// Search one object and write its output to `handle`; used with an in-memory buffer
+    /// so files searched in parallel print as whole groups.
+    pub async fn search<W: Write>(
+        &self,
+        path: &str,
+        matcher: &Matcher,
+        options: &GrepOptions,
         with_filename: bool,
+        handle: &mut W,
     ) -> Result<()> {
         // Ensure target exists; map NotFound to PathNotFound
         let meta = self.operator.stat(path).await.map_err(|e| {
@@ -60,18 +281,7 @@ impl OpenDalGreper {
         })?;
         let file_size = meta.content_length();
 
-        // Prepare search parameters
-        let needle = if ignore_case {
-            pattern.to_lowercase()
-        } else {
-            pattern.to_string()
-        };
-
         // Stream-read the object by ranged reads
-        const CHUNK_SIZE: u64 = 64 * 1024;
-        let stdout = io::stdout();
-        let mut handle = BufWriter::new(stdout.lock());
-
         let mut next_offset: u64 = 0;
         let mut line_no: usize = 0;
         let mut leftover: Vec<u8> = Vec::new();
@@ -79,14 +289,16 @@ impl OpenDalGreper {
         // If file_size == 0, the object may be empty or provider doesn't expose size.
         // We still attempt ranged reads in fixed chunks until EOF.
         let known_size = file_size > 0;
-        let mut opts = GrepOptions {
-            path,
-            needle: &needle,
-            ignore_case,
-            line_number,
+        let mut opts = LineSink {
+            invert_match: options.invert_match,
+            line_number: options.line_number,
             with_filename,
-            handle: &mut handle,
-            out_buf: String::with_capacity(256),
+            count: options.count,
+            files_with_matches: options.files_with_matches,
+            max_count: options.max_count,
+            after_context: options.after_context,
+            before_context: options.before_context,
+            ..LineSink::new(path, matcher, handle)
         };
 
         // Tracks EOF for unknown-size reads when we observe a short read
@@ -143,47 +355,21 @@ impl OpenDalGreper {
             if chunk.is_empty() {
                 break;
             }
-
-            // Concatenate leftover with current chunk
-            // Build a combined buffer without extra allocations when possible
-            let mut buf = leftover;
-            if buf.capacity() < buf.len() + chunk.len() {
-                // Ensure we don't reallocate repeatedly in tight loops
-                buf.reserve(chunk.len());
+            // Like GNU grep, a NUL byte in the first chunk marks the file as binary.
+            if line_no == 0 && leftover.is_empty() && !options.text && memchr(0, &chunk).is_some() {
+                tracing::debug!("skipping binary file {path}");
+                break;
             }
-            buf.extend_from_slice(&chunk);
-            // Reset leftover for reuse
-            let combined = buf;
-
-            // Split by '\n'; keep last partial line in leftover (memchr for speed)
-            let mut start: usize = 0;
-            for i in memchr_iter(b'\n', &combined) {
-                let mut line_bytes = &combined[start..i];
-                if let Some(&b'\r') = line_bytes.last() {
-                    line_bytes = &line_bytes[..line_bytes.len() - 1];
-                }
 
-                line_no += 1;
-                self.process_line(&mut opts, line_no, line_bytes)?;
+            self.process_chunk(&mut opts, &mut line_no, &mut leftover, &chunk)?;
 
-                start = i + 1;
-            }
-
-            // Save remaining partial line to leftover (reuse allocation)
-            leftover = if start < combined.len() {
-                let mut combined = combined; // take ownership
-                combined.split_off(start)
-            } else {
-                Vec::new()
-            };
-
-            if reached_eof {
+            if reached_eof || opts.done() {
                 break;
             }
         }
 
         // Process leftover as the final line (no trailing newline)
-        if !leftover.is_empty() {
+        if !leftover.is_empty() && !opts.done() {
             let mut line_bytes = leftover.as_slice();
             if let Some(&b'\r') = line_bytes.last() {
                 line_bytes = &line_bytes[..line_bytes.len() - 1];
@@ -192,65 +378,275 @@ impl OpenDalGreper {
             self.process_line(&mut opts, line_no, line_bytes)?;
         }
 
-        self.flush_handle(path, &mut handle)
+        self.print_summary(&mut opts)?;
+        self.flush_handle(path, opts.handle)
     }
 
-    fn process_line<W: Write>(
-        &self,
-        opts: &mut GrepOptions<W>,
-        line_no: usize,
-        line_bytes: &[u8],
-    ) -> Result<()> {
-        let line = String::from_utf8_lossy(line_bytes);
-        // Optimize ASCII fast-path for case-insensitive checks without allocations
-        let matched = if opts.ignore_case {
-            if line.is_ascii() && opts.needle.is_ascii() {
-                // opts.needle is pre-lowercased; compare haystack in-place without allocations.
-                Self::ascii_contains_case_insensitive(line.as_bytes(), opts.needle.as_bytes())
+    /// Print the per-file line for `-c` and `-l`.
+    fn print_summary<W: Write>(&self, opts: &mut LineSink<W>) -> Result<()> {
+        let line = if opts.files_with_matches {
+            if opts.selected == 0 {
+                return Ok(());
+            }
+            format!("{}\n", opts.path)
+        } else if opts.count {
+            if opts.with_filename {
+                format!("{}:{}\n", opts.path, opts.selected)
             } else {
-                line.to_lowercase().contains(opts.needle)
+                format!("{}\n", opts.selected)
             }
         } else {
-            line.contains(opts.needle)
+            return Ok(());
         };
+        self.write_all_handle(opts.path, &mut opts.handle, line.as_bytes())
+    }
 
-        if matched {
-            use std::fmt::Write as _;
-            opts.out_buf.clear();
-            if opts.with_filename {
-                let _ = write!(&mut opts.out_buf, "{}:", opts.path);
+    pub async fn follow_and_print(
+        &self,
+        path: &str,
+        pattern: &str,
+        options: &FollowOptions,
+    ) -> Result<()> {
+        let matcher = Matcher::new(pattern, options.ignore_case, options.regex)?;
+        let prefix_mode = path.ends_with('/');
+        let stdout = io::stdout();
+        let mut handle = BufWriter::new(stdout.lock());
+
+        let mut cursor = if prefix_mode {
+            self.start_prefix(path, &matcher, options, &mut handle)
+                .await?
+        } else {
+            let meta = self
+                .stat_followed(path)
+                .await?
+                .ok_or_else(|| Error::PathNotFound {
+                    path: std::path::PathBuf::from(path),
+                })?;
+            let offset = match options.since {
+                Some(since) if meta.last_modified().is_none_or(|t| t >= since) => 0,
+                _ => meta.content_length(),
+            };
+            Some(FollowCursor::new(path.to_string(), offset))
+        };
+
+        loop {
+            if let Some(current) = cursor.as_mut()
+                && let Some(meta) = self.stat_followed(&current.path).await?
+            {
+                self.drain(current, &meta, &matcher, options, prefix_mode, &mut handle)
+                    .await?;
             }
-            if opts.line_number {
-                let _ = write!(&mut opts.out_buf, "{}:", line_no);
+
+            if prefix_mode
+                && let Some((newest, meta)) = self.newest_under(path).await?
+                && cursor.as_ref().is_none_or(|c| c.path != newest)
+            {
+                // Rotation: the previous object was drained above, start the new one from 0.
+                let mut next = FollowCursor::new(newest, 0);
+                self.drain(
+                    &mut next,
+                    &meta,
+                    &matcher,
+                    options,
+                    prefix_mode,
+                    &mut handle,
+                )
+                .await?;
+                cursor = Some(next);
+            }
+
+            tokio::select! {
+                _ = tokio::time::sleep(options.interval) => {}
+                _ = tokio::signal::ctrl_c() => return Ok(()),
             }
-            let _ = writeln!(&mut opts.out_buf, "{}", line);
-            self.write_all_handle(opts.path, &mut opts.handle, opts.out_buf.as_bytes())?;
         }
-        Ok(())
     }
 
-    #[inline]
-    fn ascii_contains_case_insensitive(haystack: &[u8], needle: &[u8]) -> bool {
-        if needle.is_empty() {
-            return true;
+    /// Pick the starting object under `prefix`; with `since`, first scan every object
+    /// modified at or after it (oldest first).
+    async fn start_prefix<W: Write>(
+        &self,
+        prefix: &str,
+        matcher: &Matcher,
+        options: &FollowOptions,
+        handle: &mut W,
+    ) -> Result<Option<FollowCursor>> {
+        let mut objects = self.objects_under(prefix).await?;
+        objects.sort_by_key(|(_, meta)| meta.last_modified());
+
+        let Some(since) = options.since else {
+            return Ok(objects
+                .pop()
+                .map(|(path, meta)| FollowCursor::new(path, meta.content_length())));
+        };
+
+        let mut cursor = None;
+        for (path, meta) in objects {
+            if meta.last_modified().is_some_and(|t| t < since) {
+                continue;
+            }
+            let mut next = FollowCursor::new(path, 0);
+            self.drain(&mut next, &meta, matcher, options, true, handle)
+                .await?;
+            cursor = Some(next);
         }
-        if needle.len() > haystack.len() {
-            return false;
+        Ok(cursor)
+    }
+
+    /// Print matching complete lines between the cursor and the object's current size.
+    async fn drain<W: Write>(
+        &self,
+        cursor: &mut FollowCursor,
+        meta: &Metadata,
+        matcher: &Matcher,
+        options: &FollowOptions,
+        with_filename: bool,
+        handle: &mut W,
+    ) -> Result<()> {
+        let size = meta.content_length();
+        if size < cursor.offset {
+            // Truncated or replaced with shorter content: start over like `tail -F`.
+            *cursor = FollowCursor::new(std::mem::take(&mut cursor.path), 0);
         }
-        let nlen = needle.len();
-        for i in 0..=haystack.len() - nlen {
-            let mut j = 0;
-            while j < nlen {
-                if haystack[i + j].to_ascii_lowercase() != needle[j] {
-                    break;
+
+        let path = cursor.path.clone();
+        let mut opts = LineSink {
+            invert_match: options.invert_match,
+            line_number: options.line_number,
+            with_filename,
+            ..LineSink::new(&path, matcher, handle)
+        };
+        while cursor.offset < size {
+            let end = std::cmp::min(cursor.offset + CHUNK_SIZE, size);
+            let data = self
+                .operator
+                .read_with(&path)
+                .range(cursor.offset..end)
+                .await
+                .map_err(|e| self.map_to_grep_failed(&path, e))?;
+            cursor.offset = end;
+            self.process_chunk(
+                &mut opts,
+                &mut cursor.line_no,
+                &mut cursor.leftover,
+                &data.to_vec(),
+            )?;
+        }
+        self.flush_handle(&path, opts.handle)
+    }
+
+    /// Stat a followed object; a missing object (e.g. mid-rotation) is not an error.
+    async fn stat_followed(&self, path: &str) -> Result<Option<Metadata>> {
+        match self.operator.stat(path).await {
+            Ok(meta) => Ok(Some(meta)),
+            Err(e) if e.kind() == opendal::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(self.map_to_grep_failed(path, e)),
+        }
+    }
+
+    async fn newest_under(&self, prefix: &str) -> Result<Option<(String, Metadata)>> {
+        Ok(self
+            .objects_under(prefix)
+            .await?
+            .into_iter()
+            .max_by_key(|(_, meta)| meta.last_modified()))
+    }
+
+    async fn objects_under(&self, prefix: &str) -> Result<Vec<(String, Metadata)>> {
+        list_files(&self.operator, prefix, true)
+            .await
+            .map_err(|e| self.map_to_grep_failed(prefix, e))
+    }
+
+    /// Split `chunk` (prefixed by any partial line from the previous chunk) into lines and
+    /// process each complete one; the trailing partial line is kept in `leftover`.
+    fn process_chunk<W: Write>(
+        &self,
+        opts: &mut LineSink<W>,
+        line_no: &mut usize,
+        leftover: &mut Vec<u8>,
+        chunk: &[u8],
+    ) -> Result<()> {
+        let mut combined = std::mem::take(leftover);
+        combined.extend_from_slice(chunk);
+
+        // Split by '\n'; keep last partial line in leftover (memchr for speed)
+        let mut start: usize = 0;
+        for i in memchr_iter(b'\n', &combined) {
+            if opts.done() {
+                return Ok(());
+            }
+            let mut line_bytes = &combined[start..i];
+            if let Some(&b'\r') = line_bytes.last() {
+                line_bytes = &line_bytes[..line_bytes.len() - 1];
+            }
+
+            *line_no += 1;
+            self.process_line(opts, *line_no, line_bytes)?;
+
+            start = i + 1;
+        }
+
+        if start < combined.len() {
+            *leftover = combined.split_off(start);
+        }
+        Ok(())
+    }
+
+    fn process_line<W: Write>(
+        &self,
+        opts: &mut LineSink<W>,
+        line_no: usize,
+        line_bytes: &[u8],
+    ) -> Result<()> {
+        let line = String::from_utf8_lossy(line_bytes);
+        // Past --max-count, lines only complete the trailing context of the last match.
+        let selected = !opts.max_reached() && opts.matcher.is_match(&line) != opts.invert_match;
+        if selected {
+            opts.selected += 1;
+            if opts.prints_lines() {
+                while let Some((before_no, before_line)) = opts.before.pop_front() {
+                    self.print_line(opts, before_no, &before_line, '-')?;
                 }
-                j += 1;
+                self.print_line(opts, line_no, &line, ':')?;
+                opts.after_remaining = opts.after_context;
             }
-            if j == nlen {
-                return true;
+        } else if opts.after_remaining > 0 {
+            opts.after_remaining -= 1;
+            self.print_line(opts, line_no, &line, '-')?;
+        } else if opts.before_context > 0 {
+            if opts.before.len() == opts.before_context {
+                opts.before.pop_front();
             }
+            opts.before.push_back((line_no, line.into_owned()));
         }
-        false
+        Ok(())
+    }
+
+    /// Print a selected line (`separator` `:`) or a context line (`-`), preceded by `--`
+    /// when it does not follow the previously printed line.
+    fn print_line<W: Write>(
+        &self,
+        opts: &mut LineSink<W>,
+        line_no: usize,
+        line: &str,
+        separator: char,
+    ) -> Result<()> {
+        use std::fmt::Write as _;
+        opts.out_buf.clear();
+        let context = opts.before_context > 0 || opts.after_context > 0;
+        if context && opts.last_printed.is_some_and(|last| line_no > last + 1) {
+            opts.out_buf.push_str("--\n");
+        }
+        opts.last_printed = Some(line_no);
+        if opts.with_filename {
+            let _ = write!(&mut opts.out_buf, "{}{separator}", opts.path);
+        }
+        if opts.line_number {
+            let _ = write!(&mut opts.out_buf, "{line_no}{separator}");
+        }
+        let _ = writeln!(&mut opts.out_buf, "{}", line);
+        self.write_all_handle(opts.path, &mut opts.handle, opts.out_buf.as_bytes())
     }
 
     fn map_to_grep_failed(&self, path: &str, err: opendal::Error) -> Error {
@@ -284,12 +680,57 @@ impl Greper for OpenDalGreper {
     async fn grep(
         &self,
         path: &str,
-        pattern: &str,
-        ignore_case: bool,
-        line_number: bool,
+        matcher: &Matcher,
+        options: &GrepOptions,
         with_filename: bool,
     ) -> Result<()> {
-        self.search_and_print(path, pattern, ignore_case, line_number, with_filename)
+        self.search_and_print(path, matcher, options, with_filename)
             .await
     }
+
+    async fn follow(&self, path: &str, pattern: &str, options: &FollowOptions) -> Result<()> {
+        self.follow_and_print(path, pattern, options).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matchers_cover_substrings_and_regexes() {
+        let literal = Matcher::new("a.c", true, false).unwrap();
+        assert!(literal.is_match("xA.Cx"));
+        assert!(!literal.is_match("abc"));
+
+        let regex = Matcher::new(r"^err(or)?\s+\d+", true, true).unwrap();
+        assert!(regex.is_match("ERROR 42"));
+        assert!(regex.is_match("err 7"));
+        assert!(!regex.is_match("an error 42"));
+
+        assert!(Matcher::new("(", false, true).is_err());
+    }
+
+    #[test]
+    fn context_lines_are_grouped_and_separated() {
+        let greper = OpenDalGreper::new(Operator::via_iter(opendal::Scheme::Memory, []).unwrap());
+        let matcher = Matcher::new("hit", false, false).unwrap();
+        let mut out = Vec::new();
+        let mut sink = LineSink {
+            line_number: true,
+            before_context: 1,
+            after_context: 1,
+            ..LineSink::new("f", &matcher, &mut out)
+        };
+        let mut line_no = 0;
+        let mut leftover = Vec::new();
+        let text = b"a\nhit\nb\nc\nd\nhit\nhit\ne\n";
+        greper
+            .process_chunk(&mut sink, &mut line_no, &mut leftover, text)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "1-a\n2:hit\n3-b\n--\n5-d\n6:hit\n7:hit\n8-e\n"
+        );
+    }
 }
@@ -0,0 +1,310 @@
+//! HDFS features OpenDAL does not expose (replication factor, ownership, modification
+//! times), through the name node's WebHDFS REST API.
+
+use crate::config::{ProviderBackend, StorageProvider};
+use crate::error::{Error, Result};
+use crate::storage::connection::ConnectionSettings;
+use crate::storage::rest::{SendError, encode_query_value};
+use crate::storage::retry::RetryPolicy;
+use reqwest::Method;
+use serde::Deserialize;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Environment variable overriding the WebHDFS address derived from the name node.
+pub const WEBHDFS_ENDPOINT_ENV: &str = "STORIFY_WEBHDFS_ENDPOINT";
+/// HTTP port of the name node since Hadoop 3 (50070 before).
+const DEFAULT_WEBHDFS_PORT: u16 = 9870;
+
+/// Owner, group and replication factor of an HDFS path.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct HdfsStatus {
+    pub owner: String,
+    pub group: String,
+    /// 0 for directories
+    pub replication: u16,
+}
+
+/// Trait for provider features outside OpenDAL's common API. Only HDFS implements it.
+pub trait ProviderExtension {
+    /// Set the replication factor of the file `path`.
+    async fn set_replication(&self, path: &str, replication: u16) -> Result<()>;
+
+    /// Change the owner and/or group of `path`; `None` keeps the current one.
+    #[cfg(feature = "hdfs")]
+    async fn chown(&self, path: &str, owner: Option<&str>, group: Option<&str>) -> Result<()>;
+
+    /// Owner, group and replication factor of `path`.
+    async fn status(&self, path: &str) -> Result<HdfsStatus>;
+
+    /// Set the modification time of `path`.
+    async fn set_mtime(&self, path: &str, mtime: SystemTime) -> Result<()>;
+}
+
+/// Implementation of ProviderExtension over WebHDFS, with simple authentication as
+/// `HADOOP_USER_NAME` when set.
+pub struct WebHdfsExtension {
+    client: reqwest::Client,
+    base_url: String,
+    root: String,
+    user: Option<String>,
+    retry: RetryPolicy,
+}
+
+impl WebHdfsExtension {
+    /// Create the extension for an HDFS backend; `feature` names the flag or command in
+    /// errors for other providers.
+    pub fn from_backend(
+        provider: StorageProvider,
+        backend: &ProviderBackend,
+        feature: &str,
+        retry: RetryPolicy,
+        connection: &ConnectionSettings,
+    ) -> Result<Self> {
+        let ProviderBackend::Hdfs { root, name_node } = backend else {
+            return Err(Error::InvalidArgument {
+                message: format!(
+                    "{feature} needs the hdfs provider (got '{}')",
+                    provider.as_str()
+                ),
+            });
+        };
+        let endpoint = std::env::var(WEBHDFS_ENDPOINT_ENV)
+            .ok()
+            .filter(|endpoint| !endpoint.is_empty())
+            .or_else(|| webhdfs_endpoint(name_node))
+            .ok_or_else(|| Error::InvalidArgument {
+                message: format!(
+                    "cannot tell the WebHDFS address from name node '{name_node}'; set {WEBHDFS_ENDPOINT_ENV}"
+                ),
+            })?;
+        Ok(Self {
+            client: connection.rest_client()?,
+            base_url: format!("{}/webhdfs/v1", endpoint.trim_end_matches('/')),
+            root: root.clone(),
+            user: std::env::var("HADOOP_USER_NAME").ok(),
+            retry,
+        })
+    }
+
+    fn url(&self, path: &str, op: &str, params: &[(&str, &str)]) -> String {
+        let path = absolute_path(&self.root, path);
+        let mut url = format!("{}{}?op={op}", self.base_url, encode_path(&path));
+        let user = self.user.as_deref().map(|user| ("user.name", user));
+        for (name, value) in params.iter().copied().chain(user) {
+            url.push_str(&format!("&{name}={}", encode_query_value(value)));
+        }
+        url
+    }
+
+    /// Send one WebHDFS call, retrying connection failures and 5xx responses.
+    async fn send(&self, method: Method, url: &str) -> Result<serde_json::Value> {
+        let what = format!("{method} {url}");
+        self.retry
+            .run(
+                &what,
+                |err| matches!(err, SendError::Transient(_)),
+                || self.send_once(method.clone(), url),
+            )
+            .await
+            .map_err(SendError::into_inner)
+    }
+
+    async fn send_once(
+        &self,
+        method: Method,
+        url: &str,
+    ) -> std::result::Result<serde_json::Value, SendError> {
+        let transport = |err: reqwest::Error| {
+            let error = Error::ProviderRequest {
+                message: err.to_string(),
+            };
+            if err.is_connect() || err.is_timeout() {
+                SendError::Transient(error)
+            } else {
+                SendError::Fatal(error)
+            }
+        };
+        let response = self
+            .client
+            .request(method, url)
+            .send()
+            .await
+            .map_err(transport)?;
+        let status = response.status();
+        let body = response.text().await.map_err(transport)?;
+        if status.is_success() {
+            return Ok(serde_json::from_str(&body).unwrap_or(serde_json::Value::Null));
+        }
+        let err = Error::ProviderRequest {
+            message: match remote_exception(&body) {
+                Some(message) => format!("{status} ({message})"),
+                None => format!("{status}"),
+            },
+        };
+        if status.is_server_error() {
+            Err(SendError::Transient(err))
+        } else {
+            Err(SendError::Fatal(err))
+        }
+    }
+}
+
+impl ProviderExtension for WebHdfsExtension {
+    async fn set_replication(&self, path: &str, replication: u16) -> Result<()> {
+        let url = self.url(
+            path,
+            "SETREPLICATION",
+            &[("replication", &replication.to_string())],
+        );
+        let response = self.send(Method::PUT, &url).await?;
+        // HDFS answers false for directories, which have no replication factor.
+        if response["boolean"] == serde_json::Value::Bool(false) {
+            return Err(Error::InvalidArgument {
+                message: format!("cannot set the replication of '{path}'; is it a directory?"),
+            });
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "hdfs")]
+    async fn chown(&self, path: &str, owner: Option<&str>, group: Option<&str>) -> Result<()> {
+        let params: Vec<(&str, &str)> = [("owner", owner), ("group", group)]
+            .into_iter()
+            .filter_map(|(name, value)| value.map(|value| (name, value)))
+            .collect();
+        let url = self.url(path, "SETOWNER", &params);
+        self.send(Method::PUT, &url).await?;
+        Ok(())
+    }
+
+    async fn status(&self, path: &str) -> Result<HdfsStatus> {
+        let url = self.url(path, "GETFILESTATUS", &[]);
+        let response = self.send(Method::GET, &url).await?;
+        Ok(serde_json::from_value(response["FileStatus"].clone())?)
+    }
+
+    async fn set_mtime(&self, path: &str, mtime: SystemTime) -> Result<()> {
+        // WebHDFS takes milliseconds since the epoch; -1 leaves the access time alone.
+        let millis = mtime
+            .duration_since(UNIX_EPOCH)
+            .map(|since_epoch| since_epoch.as_millis())
+            .unwrap_or_default();
+        let url = self.url(
+            path,
+            "SETTIMES",
+            &[
+                ("modificationtime", &millis.to_string()),
+                ("accesstime", "-1"),
+            ],
+        );
+        self.send(Method::PUT, &url).await?;
+        Ok(())
+    }
+}
+
+/// WebHDFS address for a name node such as `hdfs://nn:8020`: the same host on the
+/// default HTTP port. `None` for `default` and other names without a host.
+pub fn webhdfs_endpoint(name_node: &str) -> Option<String> {
+    let authority = name_node.strip_prefix("hdfs://")?;
+    let host = authority.split(['/', ':']).next()?;
+    (!host.is_empty()).then(|| format!("http://{host}:{DEFAULT_WEBHDFS_PORT}"))
+}
+
+/// Parse `OWNER`, `OWNER:GROUP` or `:GROUP` as `chown` takes them.
+#[cfg(any(feature = "hdfs", test))]
+pub fn parse_owner(spec: &str) -> Result<(Option<String>, Option<String>)> {
+    let (owner, group) = match spec.split_once(':') {
+        Some((owner, group)) => (owner, Some(group)),
+        None => (spec, None),
+    };
+    let owner = Some(owner).filter(|owner| !owner.is_empty());
+    let group = group.filter(|group| !group.is_empty());
+    if owner.is_none() && group.is_none() {
+        return Err(Error::InvalidArgument {
+            message: format!("invalid owner '{spec}': expected OWNER, OWNER:GROUP or :GROUP"),
+        });
+    }
+    Ok((owner.map(str::to_string), group.map(str::to_string)))
+}
+
+/// `path` below the backend root, as an absolute HDFS path.
+fn absolute_path(root: &str, path: &str) -> String {
+    let root = root.trim_matches('/');
+    let path = path.trim_matches('/');
+    match (root.is_empty(), path.is_empty()) {
+        (true, _) => format!("/{path}"),
+        (false, true) => format!("/{root}"),
+        (false, false) => format!("/{root}/{path}"),
+    }
+}
+
+fn encode_path(path: &str) -> String {
+    path.split('/')
+        .map(encode_query_value)
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Message of a WebHDFS `RemoteException` error body.
+fn remote_exception(body: &str) -> Option<String> {
+    let value: serde_json::Value = serde_json::from_str(body).ok()?;
+    value["RemoteException"]["message"]
+        .as_str()
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_webhdfs_requests() {
+        assert_eq!(
+            webhdfs_endpoint("hdfs://nn.example:8020").as_deref(),
+            Some("http://nn.example:9870")
+        );
+        assert_eq!(webhdfs_endpoint("default"), None);
+
+        let extension = WebHdfsExtension {
+            client: reqwest::Client::new(),
+            base_url: "http://nn:9870/webhdfs/v1".to_string(),
+            root: "/data/".to_string(),
+            user: Some("hdfs".to_string()),
+            retry: RetryPolicy::default(),
+        };
+        assert_eq!(
+            extension.url("logs/a b.txt", "SETREPLICATION", &[("replication", "2")]),
+            "http://nn:9870/webhdfs/v1/data/logs/a%20b.txt?op=SETREPLICATION&replication=2&user.name=hdfs"
+        );
+        assert_eq!(absolute_path("/", "/x/"), "/x");
+
+        let status: HdfsStatus = serde_json::from_str(
+            r#"{"owner":"etl","group":"supergroup","replication":3,"type":"FILE","length":1}"#,
+        )
+        .unwrap();
+        assert_eq!(status.replication, 3);
+        assert_eq!(
+            remote_exception(r#"{"RemoteException":{"message":"File does not exist: /x"}}"#)
+                .as_deref(),
+            Some("File does not exist: /x")
+        );
+    }
+
+    #[test]
+    fn owners_take_an_optional_group() {
+        let owned = |owner: &str, group: &str| {
+            (
+                Some(owner.to_string()).filter(|o| !o.is_empty()),
+                Some(group.to_string()).filter(|g| !g.is_empty()),
+            )
+        };
+        assert_eq!(parse_owner("etl").unwrap(), owned("etl", ""));
+        assert_eq!(
+            parse_owner("etl:analysts").unwrap(),
+            owned("etl", "analysts")
+        );
+        assert_eq!(parse_owner(":analysts").unwrap(), owned("", "analysts"));
+        assert!(parse_owner(":").is_err());
+        assert!(parse_owner("").is_err());
+    }
+}
@@ -1,4 +1,8 @@
+use crate::config::crypto::DataKey;
 use crate::error::{Error, Result};
+use crate::storage::utils::cipher;
+use crate::storage::utils::report::MultiResult;
+use futures::TryStreamExt;
 use opendal::Operator;
 use std::io::{self, Write};
 use std::path::PathBuf;
@@ -20,11 +24,22 @@ pub trait Header {
 /// Implementation of Header for OpenDAL Operator.
 pub struct OpenDalHeadReader {
     operator: Operator,
+    /// Key for showing objects written by `put --encrypt`
+    data_key: Option<DataKey>,
 }
 
 impl OpenDalHeadReader {
     pub fn new(operator: Operator) -> Self {
-        Self { operator }
+        Self {
+            operator,
+            data_key: None,
+        }
+    }
+
+    /// Decrypt encrypted objects with `key`; without one they are refused.
+    pub fn with_data_key(mut self, key: Option<DataKey>) -> Self {
+        self.data_key = key;
+        self
     }
 
     /// Read and display the beginning of file content.
@@ -67,6 +82,14 @@ impl OpenDalHeadReader {
             }
         };
 
+        if cipher::is_encrypted_object(&self.operator, path, file_size).await? {
+            let key = self
+                .data_key
+                .as_ref()
+                .ok_or_else(|| cipher::missing_key(path))?;
+            return self.head_decrypted(path, mode, key).await;
+        }
+
         // Read and display based on mode
         match mode {
             HeadMode::Lines(line_count) => {
@@ -106,6 +129,7 @@ impl OpenDalHeadReader {
         };
 
         let total = paths.len();
+        let mut results = MultiResult::new(total);
         for (idx, p) in paths.iter().enumerate() {
             let show_header = should_show_header(total);
             if show_header {
@@ -115,12 +139,14 @@ impl OpenDalHeadReader {
                 println!("==> {} <==", p);
             }
 
-            if let Err(e) = self.read_and_display_head(p, lines, bytes).await {
-                eprintln!("{}", e);
-            }
+            let result = self.read_and_display_head(p, lines, bytes).await;
+            results.record(p, result);
         }
 
-        Ok(())
+        results.into_result(|failed_paths, total| Error::PartialFailure {
+            failed_paths,
+            total,
+        })
     }
 
     /// Read and display file content by lines using ranged, chunked reads.
@@ -212,6 +238,58 @@ impl OpenDalHeadReader {
         self.flush_handle(path, &mut handle)
     }
 
+    /// Display the beginning of an encrypted object, decrypting only as far as needed.
+    async fn head_decrypted(&self, path: &str, mode: HeadMode, key: &DataKey) -> Result<()> {
+        let mut remaining = match mode {
+            HeadMode::Lines(count) | HeadMode::Bytes(count) => count,
+        };
+        if remaining == 0 {
+            return Ok(());
+        }
+
+        let reader = self
+            .operator
+            .reader(path)
+            .await
+            .map_err(|e| self.map_to_head_failed(path, e))?;
+        let bytes = reader
+            .into_bytes_stream(..)
+            .await
+            .map_err(|e| self.map_to_head_failed(path, e))?;
+        let mut chunks = Box::pin(cipher::decrypt_stream(bytes, key));
+
+        let stdout = io::stdout();
+        let mut handle = stdout.lock();
+        while let Some(chunk) = chunks
+            .try_next()
+            .await
+            .map_err(|e| self.map_io_to_head_failed(path, e))?
+        {
+            let end = match mode {
+                HeadMode::Bytes(_) => chunk.len().min(remaining),
+                HeadMode::Lines(_) => {
+                    let mut end = chunk.len();
+                    for (i, _) in chunk.iter().enumerate().filter(|(_, b)| **b == b'\n') {
+                        remaining -= 1;
+                        if remaining == 0 {
+                            end = i + 1;
+                            break;
+                        }
+                    }
+                    end
+                }
+            };
+            self.write_all_handle(path, &mut handle, &chunk[..end])?;
+            if let HeadMode::Bytes(_) = mode {
+                remaining -= end;
+            }
+            if remaining == 0 {
+                break;
+            }
+        }
+        self.flush_handle(path, &mut handle)
+    }
+
     /// Map OpenDAL error to HeadFailed error.
     fn map_to_head_failed(&self, path: &str, err: opendal::Error) -> Error {
         Error::HeadFailed {
@@ -0,0 +1,158 @@
+use futures::stream::TryStreamExt;
+use opendal::{ErrorKind, Operator};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Likely cause of a failed connectivity probe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Diagnosis {
+    /// Credentials were rejected or lack permission on the bucket.
+    AuthFailure,
+    /// The endpoint could not be reached (DNS, TLS, connection refused, timeout).
+    EndpointUnreachable,
+    /// The endpoint answered but the bucket/container/root does not exist.
+    BucketMissing,
+    /// The backend is missing a capability storify relies on.
+    MissingCapability,
+    /// Any other backend error.
+    Other,
+}
+
+impl fmt::Display for Diagnosis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            Diagnosis::AuthFailure => "authentication failed (check credentials and permissions)",
+            Diagnosis::EndpointUnreachable => "endpoint unreachable (check endpoint, network, TLS)",
+            Diagnosis::BucketMissing => "bucket or root path not found",
+            Diagnosis::MissingCapability => "backend lacks required capabilities",
+            Diagnosis::Other => "unexpected backend error",
+        };
+        f.write_str(text)
+    }
+}
+
+/// Outcome of a single probe step.
+#[derive(Debug, Clone)]
+pub struct HealthCheck {
+    pub name: &'static str,
+    pub elapsed: Duration,
+    pub error: Option<String>,
+}
+
+impl HealthCheck {
+    pub fn passed(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Result of [`HealthChecker::health_check`].
+#[derive(Debug, Clone)]
+pub struct HealthReport {
+    pub scheme: String,
+    pub name: String,
+    pub root: String,
+    pub checks: Vec<HealthCheck>,
+    pub diagnosis: Option<Diagnosis>,
+}
+
+/// Trait for probing backend connectivity.
+pub trait HealthChecker {
+    /// Run a capability check followed by a lightweight list/stat against the root.
+    async fn health_check(&self) -> HealthReport;
+}
+
+/// OpenDAL-based connectivity probe.
+pub struct OpenDalHealthChecker {
+    operator: Operator,
+}
+
+impl OpenDalHealthChecker {
+    pub fn new(operator: Operator) -> Self {
+        Self { operator }
+    }
+
+    fn check_capabilities(&self) -> HealthCheck {
+        let started = Instant::now();
+        let capability = self.operator.info().full_capability();
+        let missing: Vec<&str> = [
+            ("read", capability.read),
+            ("stat", capability.stat),
+            ("list", capability.list),
+        ]
+        .into_iter()
+        .filter(|(_, supported)| !supported)
+        .map(|(name, _)| name)
+        .collect();
+        HealthCheck {
+            name: "capabilities",
+            elapsed: started.elapsed(),
+            error: (!missing.is_empty()).then(|| format!("missing {}", missing.join(", "))),
+        }
+    }
+}
+
+impl HealthChecker for OpenDalHealthChecker {
+    async fn health_check(&self) -> HealthReport {
+        let info = self.operator.info();
+        let mut report = HealthReport {
+            scheme: info.scheme().to_string(),
+            name: info.name().to_string(),
+            root: info.root().to_string(),
+            checks: Vec::new(),
+            diagnosis: None,
+        };
+
+        let capabilities = self.check_capabilities();
+        let capabilities_ok = capabilities.passed();
+        report.checks.push(capabilities);
+        if !capabilities_ok {
+            report.diagnosis = Some(Diagnosis::MissingCapability);
+            return report;
+        }
+
+        // Listing forces a round-trip on every backend, unlike stat on the root which some
+        // services answer locally.
+        let started = Instant::now();
+        let listed = async {
+            let mut lister = self.operator.lister_with("/").limit(1).await?;
+            lister.try_next().await
+        }
+        .await;
+        report.checks.push(HealthCheck {
+            name: "list /",
+            elapsed: started.elapsed(),
+            error: listed.as_ref().err().map(ToString::to_string),
+        });
+        if let Err(err) = listed {
+            report.diagnosis = Some(diagnose(&err));
+            return report;
+        }
+
+        let started = Instant::now();
+        let stat = self.operator.stat("/").await;
+        report.checks.push(HealthCheck {
+            name: "stat /",
+            elapsed: started.elapsed(),
+            error: stat.as_ref().err().map(ToString::to_string),
+        });
+        if let Err(err) = stat {
+            report.diagnosis = Some(diagnose(&err));
+        }
+        report
+    }
+}
+
+/// Map an OpenDAL error onto the user-facing diagnosis.
+pub fn diagnose(err: &opendal::Error) -> Diagnosis {
+    match err.kind() {
+        ErrorKind::PermissionDenied => Diagnosis::AuthFailure,
+        // S3-compatible services report `NoSuchBucket` as an invalid configuration.
+        ErrorKind::NotFound | ErrorKind::ConfigInvalid => Diagnosis::BucketMissing,
+        ErrorKind::Unexpected
+            if err.is_temporary() || err.to_string().contains("send http request") =>
+        {
+            Diagnosis::EndpointUnreachable
+        }
+        _ => Diagnosis::Other,
+    }
+}
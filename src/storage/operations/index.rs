@@ -0,0 +1,75 @@
+use crate::error::Result;
+use crate::index::{IndexWriter, IndexedObject};
+use crate::storage::utils::error::IntoStorifyError;
+use crate::storage::utils::paging::{PageSize, list_entries};
+use futures::stream::TryStreamExt;
+use opendal::Operator;
+use std::path::Path;
+
+/// Trait for exporting a listing snapshot into a local SQLite index.
+pub trait Indexer {
+    /// Recursively list a prefix and record every object in a new index database.
+    ///
+    /// # Arguments
+    /// * `path` - Prefix to index
+    /// * `output` - Local SQLite file to create
+    /// * `force` - Replace the output file when it already exists
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or detailed error information
+    async fn index(&self, path: &str, output: &Path, force: bool) -> Result<()>;
+}
+
+/// Implementation of Indexer for OpenDAL Operator.
+pub struct OpenDalIndexer {
+    operator: Operator,
+    page_size: PageSize,
+}
+
+impl OpenDalIndexer {
+    /// Create a new indexer with the given OpenDAL operator.
+    pub fn new(operator: Operator) -> Self {
+        Self {
+            operator,
+            page_size: PageSize::Auto,
+        }
+    }
+
+    /// Page size of the listing requests.
+    pub fn with_page_size(mut self, page_size: PageSize) -> Self {
+        self.page_size = page_size;
+        self
+    }
+}
+
+impl Indexer for OpenDalIndexer {
+    async fn index(&self, path: &str, output: &Path, force: bool) -> Result<()> {
+        let mut writer = IndexWriter::create(output, path, force)?;
+
+        let mut lister = list_entries(&self.operator, path, true, self.page_size).await?;
+        while let Some(entry) = lister.try_next().await.map_err(|e| e.into_error())? {
+            if entry.metadata().mode().is_dir() {
+                continue;
+            }
+            // Filesystem-like backends list names only; stat to fill in size and mtime.
+            let meta = if entry.metadata().last_modified().is_none() {
+                self.operator.stat(entry.path()).await?
+            } else {
+                entry.metadata().clone()
+            };
+            writer.insert(&IndexedObject {
+                path: entry.path().to_string(),
+                size: meta.content_length(),
+                last_modified: meta.last_modified().map(|t| t.timestamp()),
+                etag: meta.etag().map(str::to_string),
+            })?;
+        }
+
+        let count = writer.finish()?;
+        println!(
+            "Indexed {count} objects under '{path}' into {}",
+            output.display()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,295 @@
+//! `storify inventory`: stream a prefix listing into a local CSV or JSON Lines manifest.
+
+use crate::error::{Error, Result};
+use crate::scratch::StagedFile;
+use crate::storage::operations::storage_class::RestClassChanger;
+use crate::storage::utils::error::IntoStorifyError;
+use crate::storage::utils::format::csv_field;
+use crate::storage::utils::paging::{PageSize, list_entries};
+use crate::storage::utils::path::ensure_trailing_slash;
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use futures::stream::TryStreamExt;
+use opendal::Operator;
+use serde::Serialize;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// Objects between progress lines on stderr.
+const PROGRESS_INTERVAL: usize = 100_000;
+
+/// File format of an inventory manifest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum InventoryFormat {
+    /// Comma-separated values with a header row
+    Csv,
+    /// One JSON object per line
+    Jsonl,
+}
+
+impl InventoryFormat {
+    /// Format implied by the extension of `output`.
+    pub fn from_path(output: &Path) -> Result<Self> {
+        let extension = output
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(str::to_ascii_lowercase);
+        match extension.as_deref() {
+            Some("csv") => Ok(Self::Csv),
+            Some("jsonl" | "ndjson") => Ok(Self::Jsonl),
+            Some("parquet") => Err(Error::InvalidArgument {
+                message: "Parquet manifests are not supported by this build; write .csv or .jsonl"
+                    .to_string(),
+            }),
+            _ => Err(Error::InvalidArgument {
+                message: format!(
+                    "cannot tell the manifest format of '{}'; use a .csv or .jsonl file or pass --format",
+                    output.display()
+                ),
+            }),
+        }
+    }
+}
+
+/// One object of the manifest.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InventoryRecord {
+    pub key: String,
+    pub size: u64,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    /// Empty on providers without storage classes
+    pub storage_class: Option<String>,
+}
+
+impl InventoryRecord {
+    fn new(
+        key: String,
+        size: u64,
+        etag: Option<&str>,
+        last_modified: Option<DateTime<Utc>>,
+        storage_class: Option<String>,
+    ) -> Self {
+        Self {
+            key,
+            size,
+            etag: etag.map(|etag| etag.trim_start_matches("W/").trim_matches('"').to_string()),
+            last_modified: last_modified.map(|t| t.to_rfc3339()),
+            storage_class,
+        }
+    }
+}
+
+/// Writes records to a staged file that only replaces the output once complete, so an
+/// interrupted export never leaves a truncated manifest behind.
+struct ManifestWriter {
+    staged: StagedFile,
+    out: BufWriter<File>,
+    format: InventoryFormat,
+    count: usize,
+}
+
+impl ManifestWriter {
+    fn create(output: &Path, format: InventoryFormat, force: bool) -> Result<Self> {
+        if output.exists() && !force {
+            return Err(Error::InvalidArgument {
+                message: format!(
+                    "manifest '{}' already exists; use --force to replace it",
+                    output.display()
+                ),
+            });
+        }
+        let staged = StagedFile::new(output);
+        let mut out = BufWriter::new(File::create(staged.path())?);
+        if format == InventoryFormat::Csv {
+            writeln!(out, "key,size,etag,last_modified,storage_class")?;
+        }
+        Ok(Self {
+            staged,
+            out,
+            format,
+            count: 0,
+        })
+    }
+
+    fn write(&mut self, record: &InventoryRecord) -> Result<()> {
+        match self.format {
+            InventoryFormat::Csv => writeln!(
+                self.out,
+                "{},{},{},{},{}",
+                csv_field(&record.key),
+                record.size,
+                csv_field(record.etag.as_deref().unwrap_or("")),
+                record.last_modified.as_deref().unwrap_or(""),
+                csv_field(record.storage_class.as_deref().unwrap_or(""))
+            )?,
+            InventoryFormat::Jsonl => {
+                serde_json::to_writer(&mut self.out, record)?;
+                self.out.write_all(b"\n")?;
+            }
+        }
+        self.count += 1;
+        if self.count.is_multiple_of(PROGRESS_INTERVAL) {
+            eprintln!("{} objects listed...", self.count);
+        }
+        Ok(())
+    }
+
+    fn finish(mut self, output: &Path) -> Result<usize> {
+        self.out.flush()?;
+        drop(self.out);
+        self.staged.persist(output)?;
+        Ok(self.count)
+    }
+}
+
+/// Trait for exporting a listing of every object below a prefix into a local manifest.
+pub trait InventoryExporter {
+    /// Recursively list `path` and write one record per object to `output`.
+    ///
+    /// Records are written as they are listed, so memory stays flat however many objects
+    /// there are; the output only appears once the listing completes.
+    ///
+    /// # Returns
+    /// * `Result<usize>` - Number of objects written
+    async fn export(
+        &self,
+        path: &str,
+        output: &Path,
+        format: InventoryFormat,
+        force: bool,
+    ) -> Result<usize>;
+}
+
+/// Implementation of InventoryExporter for OpenDAL Operator.
+pub struct OpenDalInventory {
+    operator: Operator,
+    page_size: PageSize,
+    classes: Option<RestClassChanger>,
+}
+
+impl OpenDalInventory {
+    pub fn new(operator: Operator) -> Self {
+        Self {
+            operator,
+            page_size: PageSize::Auto,
+            classes: None,
+        }
+    }
+
+    /// Page size of the listing requests.
+    pub fn with_page_size(mut self, page_size: PageSize) -> Self {
+        self.page_size = page_size;
+        self
+    }
+
+    /// List through the provider's REST API, which returns storage classes with the keys.
+    pub fn with_storage_classes(mut self, classes: RestClassChanger) -> Self {
+        self.classes = Some(classes);
+        self
+    }
+
+    async fn export_listed(&self, path: &str, writer: &mut ManifestWriter) -> Result<()> {
+        let mut lister = list_entries(&self.operator, path, true, self.page_size).await?;
+        while let Some(entry) = lister.try_next().await.map_err(|e| e.into_error())? {
+            if entry.metadata().mode().is_dir() {
+                continue;
+            }
+            // Filesystem-like backends list names only; stat to fill in size and mtime.
+            let meta = if entry.metadata().last_modified().is_none() {
+                self.operator.stat(entry.path()).await?
+            } else {
+                entry.metadata().clone()
+            };
+            writer.write(&InventoryRecord::new(
+                entry.path().to_string(),
+                meta.content_length(),
+                meta.etag(),
+                meta.last_modified(),
+                None,
+            ))?;
+        }
+        Ok(())
+    }
+}
+
+impl InventoryExporter for OpenDalInventory {
+    async fn export(
+        &self,
+        path: &str,
+        output: &Path,
+        format: InventoryFormat,
+        force: bool,
+    ) -> Result<usize> {
+        let prefix = ensure_trailing_slash(path);
+        let mut writer = ManifestWriter::create(output, format, force)?;
+        match &self.classes {
+            Some(classes) => {
+                classes
+                    .for_each_page(&prefix, true, |objects| {
+                        for object in objects.into_iter().filter(|o| !o.key.ends_with('/')) {
+                            writer.write(&InventoryRecord::new(
+                                object.key,
+                                object.size,
+                                object.etag.as_deref(),
+                                object.last_modified,
+                                Some(object.storage_class),
+                            ))?;
+                        }
+                        Ok(true)
+                    })
+                    .await?
+            }
+            None => self.export_listed(&prefix, &mut writer).await?,
+        }
+        let count = writer.finish(output)?;
+        println!(
+            "Wrote {count} objects under '{path}' to {}",
+            output.display()
+        );
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manifests_follow_the_output_extension() {
+        let dir = tempfile::tempdir().unwrap();
+        let record = InventoryRecord::new(
+            "logs/a,b.txt".to_string(),
+            3,
+            Some("\"900150983cd24fb0d6963f7d28e17f72\""),
+            DateTime::from_timestamp(0, 0),
+            Some("STANDARD".to_string()),
+        );
+
+        let csv = dir.path().join("manifest.csv");
+        let format = InventoryFormat::from_path(&csv).unwrap();
+        let mut writer = ManifestWriter::create(&csv, format, false).unwrap();
+        writer.write(&record).unwrap();
+        assert_eq!(writer.finish(&csv).unwrap(), 1);
+        assert_eq!(
+            std::fs::read_to_string(&csv).unwrap(),
+            "key,size,etag,last_modified,storage_class\n\
+             \"logs/a,b.txt\",3,900150983cd24fb0d6963f7d28e17f72,1970-01-01T00:00:00+00:00,STANDARD\n"
+        );
+        assert!(ManifestWriter::create(&csv, format, false).is_err());
+
+        let jsonl = dir.path().join("manifest.JSONL");
+        let format = InventoryFormat::from_path(&jsonl).unwrap();
+        let mut writer = ManifestWriter::create(&jsonl, format, false).unwrap();
+        writer.write(&record).unwrap();
+        writer.finish(&jsonl).unwrap();
+        let line: serde_json::Value =
+            serde_json::from_str(std::fs::read_to_string(&jsonl).unwrap().trim_end()).unwrap();
+        assert_eq!(line["key"], "logs/a,b.txt");
+        assert_eq!(line["etag"], "900150983cd24fb0d6963f7d28e17f72");
+
+        assert!(InventoryFormat::from_path(Path::new("m.parquet")).is_err());
+        assert!(InventoryFormat::from_path(Path::new("manifest")).is_err());
+    }
+}
@@ -0,0 +1,94 @@
+use crate::error::{Error, Result};
+use clap::ValueEnum;
+use futures::stream::TryStreamExt;
+use opendal::{Metadata, Operator};
+
+/// Ordering used to pick the newest object under a prefix.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum LatestBy {
+    /// Most recent modification time
+    #[default]
+    Mtime,
+    /// Lexicographically greatest path (date-stamped names)
+    Name,
+}
+
+/// Trait for resolving `--latest` selectors.
+pub trait LatestResolver {
+    /// Resolve the newest file below `prefix` (searched recursively).
+    async fn resolve_latest(&self, prefix: &str, by: LatestBy) -> Result<String>;
+}
+
+/// OpenDAL-based latest-object resolver.
+pub struct OpenDalLatestResolver {
+    operator: Operator,
+}
+
+impl OpenDalLatestResolver {
+    pub fn new(operator: Operator) -> Self {
+        Self { operator }
+    }
+}
+
+impl LatestResolver for OpenDalLatestResolver {
+    async fn resolve_latest(&self, prefix: &str, by: LatestBy) -> Result<String> {
+        let prefix = if prefix.ends_with('/') {
+            prefix.to_string()
+        } else {
+            format!("{prefix}/")
+        };
+        let files = list_files(&self.operator, &prefix, by == LatestBy::Mtime)
+            .await
+            .map_err(|e| Error::ListDirectoryFailed {
+                path: prefix.clone(),
+                source: Box::new(e.into()),
+            })?;
+
+        let newest = match by {
+            LatestBy::Mtime => files.into_iter().max_by(|(a_path, a), (b_path, b)| {
+                a.last_modified()
+                    .cmp(&b.last_modified())
+                    .then_with(|| a_path.cmp(b_path))
+            }),
+            LatestBy::Name => files.into_iter().max_by(|(a, _), (b, _)| a.cmp(b)),
+        };
+        newest
+            .map(|(path, _)| path)
+            .ok_or(Error::NoObjectsUnderPrefix { prefix })
+    }
+}
+
+/// Files below `prefix` with their metadata.
+///
+/// With `need_mtime`, entries listed without a modification time (e.g. filesystem backends)
+/// are stat'ed; objects that vanish between list and stat are skipped.
+pub async fn list_files(
+    operator: &Operator,
+    prefix: &str,
+    need_mtime: bool,
+) -> opendal::Result<Vec<(String, Metadata)>> {
+    let entries: Vec<_> = operator
+        .lister_with(prefix)
+        .recursive(true)
+        .await?
+        .try_collect()
+        .await?;
+
+    let mut files = Vec::new();
+    for entry in entries {
+        if !entry.metadata().mode().is_file() {
+            continue;
+        }
+        let meta = if need_mtime && entry.metadata().last_modified().is_none() {
+            match operator.stat(entry.path()).await {
+                Ok(meta) => meta,
+                Err(e) if e.kind() == opendal::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e),
+            }
+        } else {
+            entry.metadata().clone()
+        };
+        files.push((entry.path().to_string(), meta));
+    }
+    Ok(files)
+}
@@ -0,0 +1,109 @@
+use crate::error::{Error, Result};
+use opendal::{ErrorKind, Operator};
+
+/// User metadata key holding the target of a link object. Azure only accepts
+/// identifier-like metadata names, so it carries no separators.
+pub const LINK_METADATA_KEY: &str = "storifylink";
+
+/// Links followed before giving up, so a cycle cannot loop forever.
+const MAX_LINK_HOPS: usize = 8;
+
+/// Trait for pointer objects that stand in for another key, like symbolic links.
+pub trait Linker {
+    /// Create or replace `alias` as a link to `target` (a key, or a prefix ending in '/').
+    async fn link(&self, target: &str, alias: &str) -> Result<()>;
+
+    /// Target of `path` if it is a link object, without following further links.
+    async fn read_link(&self, path: &str) -> Result<Option<String>>;
+
+    /// Follow links from `path` until it names something that is not a link.
+    async fn resolve_links(&self, path: &str) -> Result<String>;
+}
+
+/// OpenDAL-based link implementation; the target is stored in the object's user metadata
+/// and, for readability, as its content.
+pub struct OpenDalLinker {
+    operator: Operator,
+}
+
+impl OpenDalLinker {
+    pub fn new(operator: Operator) -> Self {
+        Self { operator }
+    }
+
+    /// Fail early with a readable error when the backend cannot store user metadata.
+    fn ensure_user_metadata(&self) -> Result<()> {
+        if self
+            .operator
+            .info()
+            .full_capability()
+            .write_with_user_metadata
+        {
+            Ok(())
+        } else {
+            Err(Error::InvalidArgument {
+                message: format!(
+                    "links need object metadata, which the '{}' backend does not support",
+                    self.operator.info().scheme()
+                ),
+            })
+        }
+    }
+}
+
+impl Linker for OpenDalLinker {
+    async fn link(&self, target: &str, alias: &str) -> Result<()> {
+        self.ensure_user_metadata()?;
+        if alias.is_empty() || alias.ends_with('/') {
+            return Err(Error::InvalidArgument {
+                message: format!("a link needs an object key, not a directory: '{alias}'"),
+            });
+        }
+        if target.trim_start_matches('/') == alias.trim_start_matches('/') {
+            return Err(Error::InvalidArgument {
+                message: format!("'{alias}' cannot link to itself"),
+            });
+        }
+        if !self.operator.exists(target).await? {
+            eprintln!("warning: link target '{target}' does not exist (yet)");
+        }
+
+        self.operator
+            .write_with(alias, target.as_bytes().to_vec())
+            .content_type("text/plain")
+            .user_metadata([(LINK_METADATA_KEY.to_string(), target.to_string())])
+            .await?;
+        println!("Linked: {alias} → {target}");
+        Ok(())
+    }
+
+    async fn read_link(&self, path: &str) -> Result<Option<String>> {
+        if path.ends_with('/') {
+            return Ok(None);
+        }
+        match self.operator.stat(path).await {
+            Ok(meta) => Ok(meta
+                .user_metadata()
+                .and_then(|metadata| metadata.get(LINK_METADATA_KEY))
+                .cloned()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn resolve_links(&self, path: &str) -> Result<String> {
+        let mut current = path.to_string();
+        for _ in 0..MAX_LINK_HOPS {
+            match self.read_link(&current).await? {
+                Some(target) => {
+                    tracing::debug!("link {current} -> {target}");
+                    current = target;
+                }
+                None => return Ok(current),
+            }
+        }
+        Err(Error::InvalidArgument {
+            message: format!("too many levels of links resolving '{path}'"),
+        })
+    }
+}
@@ -1,84 +1,356 @@
 use crate::error::Result;
+use crate::storage::constants::DEFAULT_STAT_CONCURRENCY;
 use crate::storage::utils::error::IntoStorifyError;
+use crate::storage::utils::format::{EntryFields, Template};
+use crate::storage::utils::paging::{PageSize, list_entries_parallel};
 use crate::wrap_err;
-use futures::stream::TryStreamExt;
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use futures::future;
+use futures::stream::{StreamExt, TryStreamExt};
 use opendal::Operator;
+use std::collections::HashMap;
 use std::fmt;
 
+use super::storage_class::RestClassChanger;
+use super::verify::content_md5;
+
+/// How much metadata `ls -L` gathers for each entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MetadataMode {
+    /// Use listing metadata, and stat only entries that came back without it
+    #[default]
+    Auto,
+    /// Never stat; print whatever the listing returned
+    Fast,
+    /// Stat every file, with bounded concurrency
+    Full,
+}
+
+/// Sort key for `ls --sort`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ListSort {
+    /// Lexicographic path order
+    Name,
+    /// Largest first
+    Size,
+    /// Most recently modified first
+    Mtime,
+}
+
+/// Options controlling what `ls` prints and in which order.
+#[derive(Debug, Clone, Default)]
+pub struct ListOptions {
+    /// Show detailed information
+    pub long: bool,
+    /// List recursively
+    pub recursive: bool,
+    /// How to enrich entries in long format
+    pub metadata: MetadataMode,
+    /// Sort entries; listing order when unset
+    pub sort: Option<ListSort>,
+    /// Reverse the output order
+    pub reverse: bool,
+    /// Stop after this many entries
+    pub max_keys: Option<usize>,
+    /// Only show directories (common prefixes)
+    pub prefix_only: bool,
+    /// Print each entry through this template instead of the short or long format
+    pub format: Option<Template>,
+    /// Show the MD5 (or etag) of files in long format, stat-ing files listed without one
+    pub checksum: bool,
+    /// Top-level directories listed at once by recursive listings
+    pub jobs: usize,
+}
+
+impl ListOptions {
+    /// Entries have to be collected before printing when they are reordered.
+    fn buffers(&self) -> bool {
+        self.sort.is_some() || self.reverse
+    }
+
+    /// Whether printed entries show sizes and times, which may need stat calls.
+    fn shows_metadata(&self) -> bool {
+        self.long
+            || self
+                .format
+                .as_ref()
+                .is_some_and(|format| format.uses_any(EntryFields::METADATA))
+    }
+
+    /// Whether printed entries show storage classes.
+    fn shows_classes(&self) -> bool {
+        match &self.format {
+            Some(format) => format.uses_any(&["storage_class"]),
+            None => self.long,
+        }
+    }
+
+    fn print(&self, info: &FileInfo) {
+        match &self.format {
+            Some(format) => println!("{}", format.render(&info.fields())),
+            None if self.long => println!("{info}"),
+            None => println!("{}", info.path),
+        }
+    }
+}
+
 /// Trait for listing directory contents in object storage.
 pub trait Lister {
     /// List contents of a directory in object storage.
     ///
     /// # Arguments
     /// * `path` - Directory path to list
-    /// * `long` - Whether to show detailed information
-    /// * `recursive` - Whether to list recursively
+    /// * `options` - Format, ordering and paging of the output
     ///
     /// # Returns
     /// * `Result<()>` - Success or detailed error information
-    async fn list(&self, path: &str, long: bool, recursive: bool) -> Result<()>;
+    async fn list(&self, path: &str, options: ListOptions) -> Result<()>;
 }
 
 /// Implementation of Lister for OpenDAL Operator.
 pub struct OpenDalLister {
     operator: Operator,
+    classes: Option<RestClassChanger>,
+    stat_concurrency: usize,
+    page_size: PageSize,
 }
 
 impl OpenDalLister {
     /// Create a new lister with the given OpenDAL operator.
     pub fn new(operator: Operator) -> Self {
-        Self { operator }
+        Self {
+            operator,
+            classes: None,
+            stat_concurrency: DEFAULT_STAT_CONCURRENCY,
+            page_size: PageSize::Auto,
+        }
+    }
+
+    /// Page size of the listing requests.
+    pub fn with_page_size(mut self, page_size: PageSize) -> Self {
+        self.page_size = page_size;
+        self
+    }
+
+    /// Limit the stat calls `ls -L` runs at the same time.
+    pub fn with_stat_concurrency(mut self, concurrency: usize) -> Self {
+        self.stat_concurrency = concurrency.max(1);
+        self
     }
 
-    /// Print a single entry with optional detailed formatting.
-    fn print_entry(&self, entry: &opendal::Entry, long: bool) {
-        if long {
-            let file_info = FileInfo::from_entry(entry);
-            println!("{file_info}");
+    /// Show storage classes in long format, looked up through the provider's REST API.
+    pub fn with_storage_classes(mut self, classes: RestClassChanger) -> Self {
+        self.classes = Some(classes);
+        self
+    }
+
+    /// Storage classes for the long listing of `path`; `None` when not available.
+    async fn storage_classes(
+        &self,
+        path: &str,
+        options: &ListOptions,
+    ) -> Option<HashMap<String, String>> {
+        let changer = self.classes.as_ref().filter(|_| options.shows_classes())?;
+        let limit = if options.buffers() {
+            None
         } else {
-            println!("{}", entry.path());
+            options.max_keys
+        };
+        match changer.list_classes(path, options.recursive, limit).await {
+            Ok(classes) => Some(classes),
+            Err(e) => {
+                tracing::debug!("storage class listing failed for {path}: {e}");
+                None
+            }
         }
     }
+
+    /// Build detailed file information, issuing a stat call when the mode asks for it.
+    async fn file_info(
+        &self,
+        entry: opendal::Entry,
+        mode: MetadataMode,
+        checksum: bool,
+        classes: Option<&HashMap<String, String>>,
+    ) -> FileInfo {
+        let listed = entry.metadata();
+        let needs_stat = !listed.mode().is_dir()
+            && match mode {
+                MetadataMode::Fast => false,
+                MetadataMode::Auto => listed.last_modified().is_none(),
+                MetadataMode::Full => true,
+            };
+        let needs_checksum = checksum
+            && !listed.mode().is_dir()
+            && listed.etag().is_none()
+            && listed.content_md5().is_none();
+        let stat = if needs_stat || needs_checksum {
+            self.operator
+                .stat(entry.path())
+                .await
+                .inspect_err(|e| tracing::debug!("stat failed for {}: {e}", entry.path()))
+                .ok()
+        } else {
+            None
+        };
+        let meta = stat.as_ref().unwrap_or(listed);
+        let mut info = FileInfo::new(entry.path(), meta);
+        if checksum {
+            info.checksum = Some(
+                content_md5(meta)
+                    .or_else(|| meta.etag().map(|etag| etag.trim_matches('"').to_string()))
+                    .unwrap_or_else(|| "-".to_string()),
+            );
+        }
+        if let Some(classes) = classes {
+            info.storage_class = Some(
+                classes
+                    .get(entry.path())
+                    .cloned()
+                    .unwrap_or_else(|| "-".to_string()),
+            );
+        }
+        info
+    }
 }
 
 impl Lister for OpenDalLister {
-    async fn list(&self, path: &str, long: bool, recursive: bool) -> Result<()> {
+    async fn list(&self, path: &str, options: ListOptions) -> Result<()> {
         let lister = wrap_err!(
-            self.operator.lister_with(path).recursive(recursive).await,
+            list_entries_parallel(
+                &self.operator,
+                path,
+                options.recursive,
+                self.page_size,
+                options.jobs
+            )
+            .await,
             ListDirectoryFailed {
                 path: path.to_string()
             }
         )?;
 
-        lister
+        let classes = self.storage_classes(path, &options).await;
+        let classes = classes.as_ref();
+        let prefix_only = options.prefix_only;
+        let entries = lister
             .map_err(|e| crate::error::Error::ListDirectoryFailed {
                 path: path.to_string(),
                 source: Box::new(e.into_error()),
             })
-            .try_for_each(|entry| async move {
-                self.print_entry(&entry, long);
+            .try_filter(move |entry| {
+                future::ready(!prefix_only || entry.metadata().mode().is_dir())
+            });
+
+        if options.buffers() {
+            // Sizes and times only matter when printed or sorted on.
+            let needs_metadata = options.shows_metadata()
+                || matches!(options.sort, Some(ListSort::Size | ListSort::Mtime));
+            let mode = if needs_metadata {
+                options.metadata
+            } else {
+                MetadataMode::Fast
+            };
+            let checksum = options.long && options.checksum;
+            let mut infos: Vec<FileInfo> = entries
+                .map_ok(
+                    |entry| async move { Ok(self.file_info(entry, mode, checksum, classes).await) },
+                )
+                .try_buffered(self.stat_concurrency)
+                .try_collect()
+                .await?;
+            sort_entries(&mut infos, options.sort, options.reverse);
+            infos.truncate(options.max_keys.unwrap_or(usize::MAX));
+            for info in &infos {
+                options.print(info);
+            }
+            return Ok(());
+        }
+
+        // Stop listing once enough keys were printed.
+        let entries = entries.take(options.max_keys.unwrap_or(usize::MAX));
+        if !options.long && options.format.is_none() {
+            return entries
+                .try_for_each(|entry| async move {
+                    println!("{}", entry.path());
+                    Ok(())
+                })
+                .await;
+        }
+
+        // `buffered` keeps listing order while stats run concurrently.
+        let metadata = if options.shows_metadata() {
+            options.metadata
+        } else {
+            MetadataMode::Fast
+        };
+        let checksum = options.long && options.checksum;
+        let options = &options;
+        entries
+            .map_ok(
+                |entry| async move { Ok(self.file_info(entry, metadata, checksum, classes).await) },
+            )
+            .try_buffered(self.stat_concurrency)
+            .try_for_each(|file_info| async move {
+                options.print(&file_info);
                 Ok(())
             })
             .await
     }
 }
 
+/// Order entries by `sort` (ties by name), then flip the result for `reverse`.
+fn sort_entries(infos: &mut [FileInfo], sort: Option<ListSort>, reverse: bool) {
+    match sort {
+        Some(ListSort::Name) => infos.sort_by(|a, b| a.path.cmp(&b.path)),
+        Some(ListSort::Size) => {
+            infos.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.path.cmp(&b.path)))
+        }
+        Some(ListSort::Mtime) => infos.sort_by(|a, b| {
+            b.modified
+                .cmp(&a.modified)
+                .then_with(|| a.path.cmp(&b.path))
+        }),
+        None => {}
+    }
+    if reverse {
+        infos.reverse();
+    }
+}
+
 /// File information for detailed listing output.
 struct FileInfo {
     path: String,
     size: u64,
-    modified: Option<String>,
+    modified: Option<DateTime<Utc>>,
     is_dir: bool,
+    /// Shown as an extra column when the provider reports storage classes
+    storage_class: Option<String>,
+    /// Shown as an extra column with `--checksum`
+    checksum: Option<String>,
 }
 
 impl FileInfo {
-    fn from_entry(entry: &opendal::Entry) -> Self {
-        let meta = entry.metadata();
+    fn new(path: &str, meta: &opendal::Metadata) -> Self {
         Self {
-            path: entry.path().to_string(),
+            path: path.to_string(),
             size: meta.content_length(),
-            modified: meta.last_modified().map(|t| t.to_rfc3339()),
+            modified: meta.last_modified(),
             is_dir: meta.mode().is_dir(),
+            storage_class: None,
+            checksum: None,
+        }
+    }
+
+    fn fields(&self) -> EntryFields<'_> {
+        EntryFields {
+            path: &self.path,
+            is_dir: self.is_dir,
+            size: self.size,
+            last_modified: self.modified,
+            storage_class: self.storage_class.as_deref(),
+            depth: None,
         }
     }
 }
@@ -91,7 +363,53 @@ impl fmt::Display for FileInfo {
         } else {
             crate::storage::utils::size::format_size(self.size)
         };
-        let modified = self.modified.as_deref().unwrap_or("Unknown");
-        write!(f, "{file_type:<6} {size_str:>10} {modified} {}", self.path)
+        let modified = self
+            .modified
+            .map(|t| t.to_rfc3339())
+            .unwrap_or_else(|| "Unknown".to_string());
+        write!(f, "{file_type:<6} {size_str:>10} ")?;
+        if let Some(checksum) = &self.checksum {
+            let checksum = if self.is_dir { "-" } else { checksum };
+            write!(f, "{checksum:<32} ")?;
+        }
+        if let Some(class) = &self.storage_class {
+            write!(f, "{class:<12} ")?;
+        }
+        write!(f, "{modified} {}", self.path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(path: &str, size: u64, modified: Option<i64>) -> FileInfo {
+        FileInfo {
+            path: path.to_string(),
+            size,
+            modified: modified.and_then(|secs| DateTime::from_timestamp(secs, 0)),
+            is_dir: false,
+            storage_class: None,
+            checksum: None,
+        }
+    }
+
+    fn paths(infos: &[FileInfo]) -> Vec<&str> {
+        infos.iter().map(|info| info.path.as_str()).collect()
+    }
+
+    #[test]
+    fn sort_entries_by_key_and_reverse() {
+        let mut infos = vec![
+            info("b", 10, Some(300)),
+            info("a", 10, None),
+            info("c", 99, Some(100)),
+        ];
+        sort_entries(&mut infos, Some(ListSort::Size), false);
+        assert_eq!(paths(&infos), ["c", "a", "b"]);
+        sort_entries(&mut infos, Some(ListSort::Mtime), false);
+        assert_eq!(paths(&infos), ["b", "c", "a"]);
+        sort_entries(&mut infos, Some(ListSort::Name), true);
+        assert_eq!(paths(&infos), ["c", "b", "a"]);
     }
 }
@@ -1,36 +1,82 @@
 // Storage operation traits and implementations
+pub mod acl;
+pub mod append;
+pub mod apply;
+pub mod bucket_config;
 pub mod cat;
+pub mod chmod;
+pub mod compose;
 pub mod copy;
 pub mod delete;
 pub mod diff;
 pub mod download;
 pub mod find;
+pub mod gc;
 pub mod grep;
+pub mod hdfs;
 pub mod head;
+pub mod health;
+pub mod index;
+pub mod inventory;
+pub mod latest;
+pub mod lifecycle;
+pub mod link;
 pub mod list;
 pub mod mkdir;
+pub mod multipart;
 pub mod mv;
+pub mod preserve;
+pub mod rename;
 pub mod stat;
+pub mod storage_class;
+pub mod tagging;
 pub mod tail;
 pub mod touch;
+pub mod trash;
 pub mod tree;
+pub mod undo;
 pub mod upload;
 pub mod usage;
+pub mod verify;
+pub mod versions;
 
 // Re-export all operation traits - all are now implemented
-pub use cat::Cater;
+pub use acl::{AclManager, CannedAcl};
+pub use append::Appender;
+pub use apply::Applier;
+pub use bucket_config::{BucketConfigurer, CorsConfig};
+pub use cat::{ByteRange, CatOptions, Cater};
+pub use chmod::ModeChanger;
+pub use compose::Composer;
 pub use copy::Copier;
 pub use delete::Deleter;
-pub use diff::Differ;
-pub use download::Downloader;
-pub use grep::Greper;
+pub use diff::{DiffSource, Differ, DirDiffOptions, DirDiffer};
+pub use download::{DownloadOptions, Downloader};
+pub use find::{Comparison, FindAction};
+pub use gc::{GarbageCollector, GcOptions};
+pub use grep::{FollowOptions, GrepOptions, Greper};
+pub use hdfs::ProviderExtension;
 pub use head::Header;
-pub use list::Lister;
+pub use health::{HealthChecker, HealthReport};
+pub use index::Indexer;
+pub use inventory::{InventoryExporter, InventoryFormat};
+pub use latest::{LatestBy, LatestResolver};
+pub use lifecycle::{LifecycleConfig, LifecycleManager};
+pub use link::Linker;
+pub use list::{ListOptions, ListSort, Lister, MetadataMode};
 pub use mkdir::Mkdirer;
+pub use multipart::MultipartManager;
 pub use mv::Mover;
-pub use stat::Stater;
+pub use rename::{Renamer, Replacement};
+pub use stat::{ObjectMeta, Stater};
+pub use storage_class::ClassChanger;
+pub use tagging::Tagger;
 pub use tail::Tailer;
 pub use touch::Toucher;
-pub use tree::Treer;
-pub use upload::Uploader;
-pub use usage::UsageCalculator;
+pub use trash::{EmptyTrashOptions, Trasher};
+pub use tree::{TreeFormat, TreeOptions, Treer};
+pub use undo::Undoer;
+pub use upload::{SymlinkPolicy, UploadOptions, Uploader, local_files};
+pub use usage::{ReportOutput, UsageCalculator, UsageGroup, UsageLine, UsageOptions, UsageSort};
+pub use verify::{SyncPlan, Verifier, VerifySummary};
+pub use versions::Versioner;
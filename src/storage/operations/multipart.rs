@@ -0,0 +1,259 @@
+use crate::config::{ProviderBackend, StorageProvider};
+use crate::error::Result;
+use crate::storage::connection::ConnectionSettings;
+use crate::storage::rest::{RestBucket, element, encode_query_value, unescape_xml};
+use crate::storage::retry::RetryPolicy;
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use reqwest::Method;
+use std::sync::LazyLock;
+use std::time::Duration;
+
+static UPLOAD: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?s)<Upload>(.*?)</Upload>").expect("valid upload regex"));
+
+/// Trait for inspecting and aborting incomplete multipart uploads, whose parts are billed
+/// until the upload is completed or aborted.
+pub trait MultipartManager {
+    /// Print the incomplete uploads under a prefix, oldest first.
+    ///
+    /// # Arguments
+    /// * `prefix` - Key prefix (the whole bucket when empty)
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or detailed error information
+    async fn list_uploads(&self, prefix: &str) -> Result<()>;
+
+    /// Abort the incomplete uploads under a prefix.
+    ///
+    /// # Arguments
+    /// * `prefix` - Key prefix (the whole bucket when empty)
+    /// * `older_than` - Only abort uploads started at least this long ago
+    /// * `dry_run` - Print what would be aborted without aborting it
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or detailed error information
+    async fn abort_uploads(
+        &self,
+        prefix: &str,
+        older_than: Option<Duration>,
+        dry_run: bool,
+    ) -> Result<()>;
+}
+
+/// Implementation of MultipartManager using the `?uploads` sub-resource of the provider's
+/// REST API, which OpenDAL does not expose.
+pub struct RestMultipartManager {
+    bucket: RestBucket,
+}
+
+impl RestMultipartManager {
+    /// Create a manager for the configured bucket; only S3, OSS and COS with static
+    /// credentials (or an AWS profile) are supported.
+    pub fn from_backend(
+        provider: StorageProvider,
+        backend: &ProviderBackend,
+        retry: RetryPolicy,
+        connection: &ConnectionSettings,
+    ) -> Result<Self> {
+        Ok(Self {
+            bucket: RestBucket::from_backend(
+                provider,
+                backend,
+                "managing multipart uploads",
+                retry,
+                connection,
+            )?,
+        })
+    }
+}
+
+impl MultipartManager for RestMultipartManager {
+    async fn list_uploads(&self, prefix: &str) -> Result<()> {
+        let mut uploads = pending_uploads(&self.bucket, prefix).await?;
+        uploads.sort_by(|a, b| a.initiated.cmp(&b.initiated).then(a.key.cmp(&b.key)));
+        for upload in &uploads {
+            println!(
+                "{:<25}  {}  {}",
+                upload.started(),
+                upload.key,
+                upload.upload_id
+            );
+        }
+        println!("{} incomplete upload(s)", uploads.len());
+        Ok(())
+    }
+
+    async fn abort_uploads(
+        &self,
+        prefix: &str,
+        older_than: Option<Duration>,
+        dry_run: bool,
+    ) -> Result<()> {
+        let cutoff = older_than.map(|age| {
+            Utc::now() - chrono::Duration::from_std(age).unwrap_or(chrono::Duration::MAX)
+        });
+        let uploads: Vec<PendingUpload> = pending_uploads(&self.bucket, prefix)
+            .await?
+            .into_iter()
+            .filter(|upload| cutoff.is_none_or(|cutoff| upload.started_before(cutoff)))
+            .collect();
+        for upload in &uploads {
+            if dry_run {
+                println!(
+                    "Would abort upload of {} (started {})",
+                    upload.key,
+                    upload.started()
+                );
+            } else {
+                abort_upload(&self.bucket, upload).await?;
+            }
+        }
+        let verb = if dry_run { "Would abort" } else { "Aborted" };
+        println!("{verb} {} incomplete upload(s)", uploads.len());
+        Ok(())
+    }
+}
+
+/// An incomplete multipart upload.
+#[derive(Debug, PartialEq)]
+pub(crate) struct PendingUpload {
+    pub key: String,
+    pub upload_id: String,
+    pub initiated: Option<DateTime<Utc>>,
+}
+
+impl PendingUpload {
+    /// When the upload was started, for display.
+    pub fn started(&self) -> String {
+        self.initiated
+            .map(|at| at.to_rfc3339())
+            .unwrap_or_else(|| "Unknown".to_string())
+    }
+
+    /// Whether the upload started no later than `cutoff`; uploads of unknown age count.
+    pub fn started_before(&self, cutoff: DateTime<Utc>) -> bool {
+        self.initiated.is_none_or(|at| at <= cutoff)
+    }
+}
+
+/// Every incomplete upload of `bucket` under `prefix`, following the listing's pages.
+pub(crate) async fn pending_uploads(
+    bucket: &RestBucket,
+    prefix: &str,
+) -> Result<Vec<PendingUpload>> {
+    let prefix = prefix.trim_start_matches('/');
+    let mut uploads = Vec::new();
+    let (mut key_marker, mut upload_marker) = (String::new(), String::new());
+    loop {
+        let mut query = format!("uploads&prefix={}", encode_query_value(prefix));
+        if !key_marker.is_empty() {
+            query.push_str(&format!(
+                "&key-marker={}&upload-id-marker={}",
+                encode_query_value(&key_marker),
+                encode_query_value(&upload_marker)
+            ));
+        }
+        let response = bucket
+            .send(Method::GET, "", Some(&query), &[], None)
+            .await?;
+        let page = parse_uploads(&response.body);
+        uploads.extend(page.uploads);
+        match page.next {
+            Some(next) if page.truncated && next != (key_marker.clone(), upload_marker) => {
+                (key_marker, upload_marker) = next;
+            }
+            _ => break,
+        }
+    }
+    Ok(uploads)
+}
+
+/// Abort `upload`, freeing the parts stored for it.
+pub(crate) async fn abort_upload(bucket: &RestBucket, upload: &PendingUpload) -> Result<()> {
+    let query = format!("uploadId={}", encode_query_value(&upload.upload_id));
+    bucket
+        .send(Method::DELETE, &upload.key, Some(&query), &[], None)
+        .await?;
+    println!(
+        "Aborted upload of {} (started {})",
+        upload.key,
+        upload.started()
+    );
+    Ok(())
+}
+
+/// One page of a ListMultipartUploads response.
+struct UploadsPage {
+    uploads: Vec<PendingUpload>,
+    truncated: bool,
+    /// Key and upload id markers of the next page
+    next: Option<(String, String)>,
+}
+
+fn parse_uploads(xml: &str) -> UploadsPage {
+    let uploads = UPLOAD
+        .captures_iter(xml)
+        .filter_map(|capture| {
+            let upload = capture.get(1)?.as_str();
+            Some(PendingUpload {
+                key: unescape_xml(element(upload, "Key")?),
+                upload_id: unescape_xml(element(upload, "UploadId")?),
+                initiated: element(upload, "Initiated")
+                    .and_then(|at| DateTime::parse_from_rfc3339(at).ok())
+                    .map(|at| at.with_timezone(&Utc)),
+            })
+        })
+        .collect();
+    let next = element(xml, "NextKeyMarker")
+        .filter(|marker| !marker.is_empty())
+        .map(|key| {
+            (
+                unescape_xml(key),
+                element(xml, "NextUploadIdMarker")
+                    .map(unescape_xml)
+                    .unwrap_or_default(),
+            )
+        });
+    UploadsPage {
+        uploads,
+        truncated: element(xml, "IsTruncated") == Some("true"),
+        next,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_uploads_reads_pending_uploads() {
+        let xml = "<ListMultipartUploadsResult><IsTruncated>true</IsTruncated>\
+            <NextKeyMarker>b&amp;c.bin</NextKeyMarker><NextUploadIdMarker>id2</NextUploadIdMarker>\
+            <Upload><Key>a.bin</Key><UploadId>id1</UploadId><Initiated>2024-05-01T10:00:00.000Z</Initiated></Upload>\
+            <Upload><Key>b&amp;c.bin</Key><UploadId>id2</UploadId></Upload></ListMultipartUploadsResult>";
+        let page = parse_uploads(xml);
+        assert_eq!(
+            page.uploads,
+            vec![
+                PendingUpload {
+                    key: "a.bin".to_string(),
+                    upload_id: "id1".to_string(),
+                    initiated: DateTime::from_timestamp(1_714_557_600, 0),
+                },
+                PendingUpload {
+                    key: "b&c.bin".to_string(),
+                    upload_id: "id2".to_string(),
+                    initiated: None,
+                },
+            ]
+        );
+        assert!(page.truncated);
+        assert_eq!(page.next, Some(("b&c.bin".to_string(), "id2".to_string())));
+
+        let cutoff = DateTime::from_timestamp(1_714_557_600, 0).unwrap();
+        assert!(page.uploads[0].started_before(cutoff));
+        assert!(!page.uploads[0].started_before(cutoff - chrono::Duration::seconds(1)));
+        assert!(page.uploads[1].started_before(cutoff));
+    }
+}
@@ -1,13 +1,23 @@
-use crate::error::{InvalidPathSnafu, Result};
+use crate::error::{
+    DestinationInsideSourceSnafu, DestinationIsDirectorySnafu, DestinationIsFileSnafu, Error,
+    InvalidPathSnafu, Result,
+};
+use crate::jobs::CancelToken;
 use crate::storage::constants::DEFAULT_CHUNK_SIZE;
+use crate::storage::utils::clobber::Clobber;
 use crate::storage::utils::path::{
-    basename, build_remote_path, ensure_trailing_slash, get_root_relative_path,
+    build_remote_path, ensure_trailing_slash, get_root_relative_path, is_remote_directory,
+    is_remote_file, is_within, names_directory, transfer_target,
 };
 use crate::storage::utils::progress::ConsoleProgressReporter;
 use async_recursion::async_recursion;
 use futures::stream::TryStreamExt;
+use md5::{Digest, Md5};
 use opendal::{EntryMode, Operator};
 use snafu::ensure;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use super::verify::content_md5;
 
 /// Trait for moving files and directories within storage.
 pub trait Mover {
@@ -24,33 +34,68 @@ pub trait Mover {
 }
 
 /// Implementation of Mover for OpenDAL Operator.
+///
+/// Each file is renamed where the backend can rename within itself. Otherwise it is copied,
+/// the copy is verified against what was read (size, then MD5), and only then is the source
+/// deleted, so a failed or corrupt copy never costs the original.
 pub struct OpenDalMover {
     operator: Operator,
+    clobber: Clobber,
+    /// Compare each copy with its source before deleting the source
+    verify: bool,
+    /// Files left in place because their destination was kept
+    skipped: AtomicUsize,
+    /// Checked between chunks and files
+    cancel: CancelToken,
 }
 
 impl OpenDalMover {
-    /// Create a new copier with the given OpenDAL operator.
+    /// Create a new mover within the given OpenDAL operator.
     pub fn new(operator: Operator) -> Self {
-        Self { operator }
-    }
-
-    /// Hybrid directory detection for object storage: stat first; if not available, probe prefix.
-    async fn is_directory(&self, path: &str) -> bool {
-        match self.operator.stat(path).await.ok().map(|m| m.mode()) {
-            Some(EntryMode::DIR) => true,
-            Some(_) => false,
-            None => {
-                let probe = ensure_trailing_slash(path);
-                self.operator
-                    .list_with(&probe)
-                    .limit(1)
-                    .await
-                    .map(|entries| !entries.is_empty())
-                    .unwrap_or(false)
-            }
+        Self {
+            operator,
+            clobber: Clobber::default(),
+            verify: true,
+            skipped: AtomicUsize::new(0),
+            cancel: CancelToken::default(),
         }
     }
 
+    /// Stop the move once `token` is cancelled.
+    pub fn with_cancel_token(mut self, token: CancelToken) -> Self {
+        self.cancel = token;
+        self
+    }
+
+    /// Set what happens to destination objects that already exist.
+    pub fn with_clobber(mut self, clobber: Clobber) -> Self {
+        self.clobber = clobber;
+        self
+    }
+
+    /// Whether copies are checked against their source before the source is deleted.
+    pub fn with_verify(mut self, verify: bool) -> Self {
+        self.verify = verify;
+        self
+    }
+
+    /// Files the last moves left in place because their destination exists.
+    pub fn skipped(&self) -> usize {
+        self.skipped.load(Ordering::Relaxed)
+    }
+
+    /// Whether `src_path` may replace `dest_path`, counting it as skipped when not.
+    async fn allows(&self, src_path: &str, dest_path: &str) -> Result<bool> {
+        let allowed = self
+            .clobber
+            .allows(&self.operator, src_path, dest_path)
+            .await?;
+        if !allowed {
+            self.skipped.fetch_add(1, Ordering::Relaxed);
+        }
+        Ok(allowed)
+    }
+
     /// Ensure a remote directory exists (appends trailing '/').
     async fn ensure_directory(&self, dir_path: &str) -> Result<()> {
         let to_create = ensure_trailing_slash(dir_path);
@@ -65,6 +110,7 @@ impl OpenDalMover {
 
         let mut stream = lister;
         while let Some(entry) = stream.try_next().await? {
+            self.cancel.check()?;
             let meta = entry.metadata();
             let entry_path = entry.path();
 
@@ -80,21 +126,39 @@ impl OpenDalMover {
 
             if meta.mode() == EntryMode::DIR {
                 self.ensure_directory(&new_dest_path).await?;
-            } else {
-                self.stream_move(entry_path, &new_dest_path).await?;
-                self.operator.delete(entry_path).await?;
+            } else if self.allows(entry_path, &new_dest_path).await? {
+                self.move_file(entry_path, &new_dest_path).await?;
             }
         }
 
         Ok(())
     }
 
-    /// Stream move a single file with progress reporting.
-    async fn stream_move(&self, src_path: &str, dest_path: &str) -> opendal::Result<()> {
+    /// Move one file: rename it, or copy, verify and delete it.
+    async fn move_file(&self, src_path: &str, dest_path: &str) -> Result<()> {
+        if self.operator.info().full_capability().rename {
+            self.operator.rename(src_path, dest_path).await?;
+            println!("✅ Moved: {src_path} → {dest_path} (renamed)");
+            return Ok(());
+        }
+
+        let (total_bytes, md5) = self.stream_copy(src_path, dest_path).await?;
+        if self.verify {
+            self.verify_copy(dest_path, total_bytes, &md5).await?;
+        }
+        self.operator.delete(src_path).await?;
+        println!("\n✅ Moved: {src_path} → {dest_path} ({total_bytes} bytes)");
+        Ok(())
+    }
+
+    /// Stream a file to the destination with progress reporting, returning the bytes
+    /// written and their MD5 in lowercase hex.
+    async fn stream_copy(&self, src_path: &str, dest_path: &str) -> Result<(u64, String)> {
         let metadata = self.operator.stat(src_path).await?;
         let file_size = metadata.content_length();
 
         let mut writer = self.operator.writer(dest_path).await?;
+        let mut hasher = Md5::new();
         let mut total_bytes = 0u64;
         let mut offset = 0u64;
 
@@ -108,6 +172,11 @@ impl OpenDalMover {
             if offset >= file_size {
                 break;
             }
+            if let Err(e) = self.cancel.check() {
+                // The source is only deleted after a complete copy, so drop the partial one.
+                let _ = writer.abort().await;
+                return Err(e);
+            }
 
             let chunk_size = std::cmp::min(DEFAULT_CHUNK_SIZE as u64, file_size - offset);
 
@@ -121,6 +190,9 @@ impl OpenDalMover {
                 break;
             }
 
+            for chunk in data.clone() {
+                hasher.update(&chunk);
+            }
             writer.write(data).await?;
             total_bytes += data_len as u64;
             offset += data_len as u64;
@@ -129,16 +201,60 @@ impl OpenDalMover {
         }
 
         writer.close().await?;
-        println!("\n✅ Moved: {src_path} → {dest_path} ({total_bytes} bytes)");
+        Ok((total_bytes, format!("{:x}", hasher.finalize())))
+    }
 
-        Ok(())
+    /// Check the copy at `dest_path` against the bytes read from its source: by size, then
+    /// by the MD5 the destination reports, or by reading the copy back when it reports none.
+    /// A copy that differs is removed; the source is left alone.
+    async fn verify_copy(&self, dest_path: &str, size: u64, md5: &str) -> Result<()> {
+        let meta = self.operator.stat(dest_path).await?;
+        let problem = if meta.content_length() != size {
+            Some(format!("size {} instead of {size}", meta.content_length()))
+        } else {
+            let copied = match content_md5(&meta) {
+                Some(copied) => copied,
+                None => self.object_md5(dest_path, size).await?,
+            };
+            (copied != md5).then(|| format!("md5 {copied} instead of {md5}"))
+        };
+        let Some(problem) = problem else {
+            return Ok(());
+        };
+        let _ = self.operator.delete(dest_path).await;
+        Err(Error::CopyMismatch {
+            path: dest_path.to_string(),
+            detail: problem,
+        })
+    }
+
+    /// MD5 of an object, read in chunks.
+    async fn object_md5(&self, path: &str, size: u64) -> Result<String> {
+        let mut hasher = Md5::new();
+        let mut offset = 0u64;
+        while offset < size {
+            let chunk_size = std::cmp::min(DEFAULT_CHUNK_SIZE as u64, size - offset);
+            let data = self
+                .operator
+                .read_with(path)
+                .range(offset..offset + chunk_size)
+                .await?;
+            if data.is_empty() {
+                break;
+            }
+            offset += data.len() as u64;
+            for chunk in data {
+                hasher.update(&chunk);
+            }
+        }
+        Ok(format!("{:x}", hasher.finalize()))
     }
 }
 
 impl Mover for OpenDalMover {
     async fn mover(&self, src_path: &str, dest_path: &str) -> Result<()> {
         let src_stat = self.operator.stat(src_path).await.ok();
-        let src_is_dir = self.is_directory(src_path).await;
+        let src_is_dir = is_remote_directory(&self.operator, src_path).await;
         ensure!(
             src_stat.is_some() || src_is_dir,
             InvalidPathSnafu {
@@ -146,38 +262,82 @@ impl Mover for OpenDalMover {
             }
         );
 
+        let target = transfer_target(src_path, dest_path);
         if src_is_dir {
-            let target_root = if self.is_directory(dest_path).await {
-                let base_name = basename(src_path);
-                let target_root = build_remote_path(dest_path, &base_name);
-                self.ensure_directory(&target_root).await?;
-                target_root
-            } else {
-                self.ensure_directory(dest_path).await?;
-                dest_path.to_string()
-            };
-
-            self.move_file_recursive(src_path, &target_root).await?;
+            ensure!(
+                names_directory(dest_path) || !is_remote_file(&self.operator, dest_path).await,
+                DestinationIsFileSnafu {
+                    path: dest_path.to_string()
+                }
+            );
+            ensure!(
+                !is_within(&target, src_path),
+                DestinationInsideSourceSnafu {
+                    src: src_path.to_string(),
+                    dest: target.clone()
+                }
+            );
+            self.ensure_directory(&target).await?;
+            self.move_file_recursive(&ensure_trailing_slash(src_path), &target)
+                .await?;
             Ok(())
         } else {
-            let dest_is_dir_hint = dest_path.ends_with('/');
-            let mut dest_is_dir = self.is_directory(dest_path).await;
-
-            if dest_is_dir_hint && !dest_is_dir {
-                self.operator.create_dir(dest_path).await?;
-                dest_is_dir = true;
+            ensure!(
+                names_directory(dest_path) || !is_remote_directory(&self.operator, dest_path).await,
+                DestinationIsDirectorySnafu {
+                    path: dest_path.to_string()
+                }
+            );
+            // A skipped file stays where it is.
+            if self.allows(src_path, &target).await? {
+                self.move_file(src_path, &target).await?;
             }
-
-            let final_dest = if dest_is_dir_hint || dest_is_dir {
-                let file_name = basename(src_path);
-                build_remote_path(dest_path, &file_name)
-            } else {
-                dest_path.to_string()
-            };
-
-            self.stream_move(src_path, &final_dest).await?;
-            self.operator.delete(src_path).await?;
             Ok(())
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opendal::services::Memory;
+
+    fn memory() -> Operator {
+        Operator::new(Memory::default()).unwrap().finish()
+    }
+
+    #[tokio::test]
+    async fn moves_by_copy_verify_and_delete() {
+        // The memory service cannot rename, so every file takes the copy path.
+        let operator = memory();
+        assert!(!operator.info().full_capability().rename);
+        operator.write("dir/a.txt", "alpha").await.unwrap();
+        operator.write("dir/sub/b.txt", "beta").await.unwrap();
+
+        let mover = OpenDalMover::new(operator.clone());
+        mover.mover("dir/", "moved/").await.unwrap();
+
+        let read = |path: &'static str| {
+            let operator = operator.clone();
+            async move { operator.read(path).await.unwrap().to_vec() }
+        };
+        assert_eq!(read("moved/a.txt").await, b"alpha");
+        assert_eq!(read("moved/sub/b.txt").await, b"beta");
+        assert!(!operator.exists("dir/a.txt").await.unwrap());
+        assert!(!operator.exists("dir/sub/b.txt").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn keeps_the_source_when_the_copy_differs() {
+        let operator = memory();
+        operator.write("dest", "different").await.unwrap();
+        let mover = OpenDalMover::new(operator.clone());
+
+        let err = mover
+            .verify_copy("dest", 9, "00000000000000000000000000000000")
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::CopyMismatch { .. }), "{err}");
+        assert!(!operator.exists("dest").await.unwrap());
+    }
+}
@@ -0,0 +1,158 @@
+//! `--preserve` for `put`/`get`: local modification times and permission bits carried as
+//! object user metadata.
+
+use crate::error::{Error, Result};
+use crate::scratch::StagedFile;
+use opendal::Operator;
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// User metadata key holding the file's modification time as `<secs>.<nanos>` since the
+/// Unix epoch. Like the link key, it is identifier-like so Azure accepts it.
+pub const MTIME_METADATA_KEY: &str = "storifymtime";
+/// User metadata key holding the file's permission bits in octal (Unix only).
+pub const MODE_METADATA_KEY: &str = "storifymode";
+/// User metadata key marking an object that stands for a local symlink; the value is the
+/// link target as read from the filesystem. Unlike `storify ln` links, it is not a key.
+pub const SYMLINK_METADATA_KEY: &str = "storifysymlink";
+
+/// Fail early with a readable error when the backend cannot store user metadata.
+pub fn ensure_supported(operator: &Operator, flag: &str) -> Result<()> {
+    if operator.info().full_capability().write_with_user_metadata {
+        Ok(())
+    } else {
+        Err(Error::InvalidArgument {
+            message: format!(
+                "{flag} needs object metadata, which the '{}' backend does not support",
+                operator.info().scheme()
+            ),
+        })
+    }
+}
+
+/// User metadata describing the local file at `path`.
+pub fn file_metadata(path: &Path) -> Result<Vec<(String, String)>> {
+    let meta = std::fs::metadata(path)?;
+    let mut metadata = Vec::new();
+    if let Some(mtime) = mtime_value(meta.modified()?) {
+        metadata.push((MTIME_METADATA_KEY.to_string(), mtime));
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        metadata.push((
+            MODE_METADATA_KEY.to_string(),
+            format!("{:o}", meta.permissions().mode() & 0o7777),
+        ));
+    }
+    Ok(metadata)
+}
+
+/// Apply preserved metadata to the downloaded file at `path`. Objects uploaded without
+/// `--preserve` carry none and are left as downloaded.
+pub fn restore(path: &Path, metadata: &HashMap<String, String>) -> Result<()> {
+    if let Some(mtime) = metadata
+        .get(MTIME_METADATA_KEY)
+        .and_then(|v| parse_mtime(v))
+    {
+        std::fs::File::options()
+            .write(true)
+            .open(path)?
+            .set_modified(mtime)?;
+    }
+    #[cfg(unix)]
+    if let Some(mode) = metadata
+        .get(MODE_METADATA_KEY)
+        .and_then(|v| u32::from_str_radix(v, 8).ok())
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode & 0o7777))?;
+    }
+    Ok(())
+}
+
+/// Replace whatever is at `path` with a symlink to `target`.
+pub fn restore_symlink(path: &Path, target: &str) -> Result<()> {
+    #[cfg(unix)]
+    {
+        let staged = StagedFile::new(path);
+        std::os::unix::fs::symlink(target, staged.path())?;
+        staged.persist(path)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = target;
+        Err(Error::InvalidArgument {
+            message: format!(
+                "cannot recreate {} as a symlink on this platform",
+                path.display()
+            ),
+        })
+    }
+}
+
+/// `time` as stored under [`MTIME_METADATA_KEY`]; `None` before the Unix epoch.
+pub fn mtime_value(time: SystemTime) -> Option<String> {
+    let since_epoch = time.duration_since(UNIX_EPOCH).ok()?;
+    Some(format!(
+        "{}.{:09}",
+        since_epoch.as_secs(),
+        since_epoch.subsec_nanos()
+    ))
+}
+
+/// Parse a value stored under [`MTIME_METADATA_KEY`].
+pub fn parse_mtime(value: &str) -> Option<SystemTime> {
+    let (secs, nanos) = value.split_once('.').unwrap_or((value, "0"));
+    let nanos: u32 = format!("{nanos:0<9}").get(..9)?.parse().ok()?;
+    UNIX_EPOCH.checked_add(Duration::new(secs.parse().ok()?, nanos))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metadata_round_trips_through_a_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("source");
+        std::fs::write(&source, b"x").unwrap();
+        let mtime = UNIX_EPOCH + Duration::new(1_700_000_000, 123_456_789);
+        std::fs::File::options()
+            .write(true)
+            .open(&source)
+            .unwrap()
+            .set_modified(mtime)
+            .unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&source, std::fs::Permissions::from_mode(0o750)).unwrap();
+        }
+
+        let metadata: HashMap<_, _> = file_metadata(&source).unwrap().into_iter().collect();
+        assert_eq!(metadata[MTIME_METADATA_KEY], "1700000000.123456789");
+
+        let target = dir.path().join("target");
+        std::fs::write(&target, b"x").unwrap();
+        restore(&target, &metadata).unwrap();
+        let restored = std::fs::metadata(&target).unwrap();
+        assert_eq!(restored.modified().unwrap(), mtime);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(restored.permissions().mode() & 0o7777, 0o750);
+        }
+
+        assert_eq!(
+            parse_mtime("12"),
+            Some(UNIX_EPOCH + Duration::from_secs(12))
+        );
+        assert_eq!(
+            parse_mtime("12.5"),
+            Some(UNIX_EPOCH + Duration::from_millis(12_500))
+        );
+        assert_eq!(parse_mtime("soon"), None);
+    }
+}
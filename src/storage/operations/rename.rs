@@ -0,0 +1,142 @@
+use crate::error::{Error, Result};
+use crate::storage::utils::glob;
+use crate::storage::utils::path::basename;
+use opendal::Operator;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Text substitution applied to file names by `rename --replace FROM:TO`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replacement {
+    from: String,
+    to: String,
+}
+
+impl FromStr for Replacement {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.split_once(':') {
+            _ if s.contains('/') => {
+                Err("names may not contain '/'; use mv to change directories".into())
+            }
+            Some((from, to)) if !from.is_empty() => Ok(Self {
+                from: from.to_string(),
+                to: to.to_string(),
+            }),
+            _ => Err(format!("expected FROM:TO with a non-empty FROM, got '{s}'")),
+        }
+    }
+}
+
+impl Replacement {
+    /// `path` with every occurrence of FROM in its file name replaced, or `None` when the
+    /// name does not contain FROM or would end up empty.
+    pub fn apply(&self, path: &str) -> Option<String> {
+        let name = basename(path);
+        if !name.contains(&self.from) {
+            return None;
+        }
+        let renamed = name.replace(&self.from, &self.to);
+        if renamed.is_empty() || renamed == name {
+            return None;
+        }
+        let dir = &path[..path.len() - name.len()];
+        Some(format!("{dir}{renamed}"))
+    }
+}
+
+/// Trait for planning bulk renames of the files a glob matches.
+pub trait Renamer {
+    /// `(from, to)` for every file matching `pattern` whose name `replacement` changes,
+    /// in key order. Fails when two files would get the same name.
+    async fn plan_renames(
+        &self,
+        pattern: &str,
+        replacement: &Replacement,
+    ) -> Result<Vec<(String, String)>>;
+}
+
+/// OpenDAL-based rename planner; the renames themselves are done by the Mover.
+pub struct OpenDalRenamer {
+    operator: Operator,
+}
+
+impl OpenDalRenamer {
+    pub fn new(operator: Operator) -> Self {
+        Self { operator }
+    }
+}
+
+impl Renamer for OpenDalRenamer {
+    async fn plan_renames(
+        &self,
+        pattern: &str,
+        replacement: &Replacement,
+    ) -> Result<Vec<(String, String)>> {
+        let keys = glob::expand(&self.operator, pattern).await?;
+        let plan: Vec<(String, String)> = keys
+            .into_iter()
+            .filter_map(|key| replacement.apply(&key).map(|to| (key, to)))
+            .collect();
+
+        let mut targets: HashMap<&str, &str> = HashMap::new();
+        for (from, to) in &plan {
+            if let Some(other) = targets.insert(to, from) {
+                return Err(Error::InvalidArgument {
+                    message: format!("'{other}' and '{from}' would both be renamed to '{to}'"),
+                });
+            }
+        }
+        Ok(plan)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opendal::services::Memory;
+
+    #[test]
+    fn replaces_within_the_file_name_only() {
+        let ext: Replacement = ".txt:.log".parse().unwrap();
+        assert_eq!(
+            ext.apply("logs.txt/a.txt").as_deref(),
+            Some("logs.txt/a.log")
+        );
+        assert_eq!(ext.apply("logs/a.csv"), None);
+        assert!("-old:".parse::<Replacement>().is_ok());
+        assert!(":x".parse::<Replacement>().is_err());
+        assert!("a:b/c".parse::<Replacement>().is_err());
+    }
+
+    #[tokio::test]
+    async fn refuses_plans_that_merge_files() {
+        let operator = Operator::new(Memory::default()).unwrap().finish();
+        for key in [
+            "logs/a-1.txt",
+            "logs/a-2.txt",
+            "logs/b.csv",
+            "tmp/a.txt",
+            "tmp/aa.txt",
+        ] {
+            operator.write(key, "x").await.unwrap();
+        }
+        let renamer = OpenDalRenamer::new(operator);
+        let replacement = |s: &str| s.parse::<Replacement>().unwrap();
+
+        let plan = renamer
+            .plan_renames("logs/*", &replacement(".txt:.log"))
+            .await
+            .unwrap();
+        assert_eq!(
+            plan,
+            [
+                ("logs/a-1.txt".to_string(), "logs/a-1.log".to_string()),
+                ("logs/a-2.txt".to_string(), "logs/a-2.log".to_string()),
+            ]
+        );
+        let merged = renamer.plan_renames("tmp/*", &replacement("a:")).await;
+        assert!(matches!(merged, Err(Error::InvalidArgument { .. })));
+    }
+}
@@ -1,4 +1,7 @@
+use super::usage::DirectorySummary;
 use crate::error::Result;
+use crate::storage::utils::format::Fields;
+use crate::storage::utils::size::format_size;
 use opendal::{EntryMode, Operator};
 
 /// Object metadata used by `stat` command output.
@@ -9,6 +12,11 @@ use opendal::{EntryMode, Operator};
 /// - `last_modified`: RFC3339 string if available
 /// - `etag`: Backend provided entity tag if available
 /// - `content_type`: MIME type if available
+/// - `version`: Version id when the backend exposes object versioning
+/// - `storage_class`: Storage class on providers that report one (S3, OSS, COS)
+/// - `link_target`: Key the object points at when it is a link created by `storify ln`
+/// - `owner`, `group`, `replication`: HDFS ownership and replication factor
+/// - `directory`: Totals of the entries below a directory, filled in by the caller
 #[derive(Debug, Clone)]
 pub struct ObjectMeta {
     pub path: String,
@@ -17,6 +25,75 @@ pub struct ObjectMeta {
     pub last_modified: Option<String>,
     pub etag: Option<String>,
     pub content_type: Option<String>,
+    pub version: Option<String>,
+    pub storage_class: Option<String>,
+    pub link_target: Option<String>,
+    pub owner: Option<String>,
+    pub group: Option<String>,
+    pub replication: Option<u16>,
+    pub directory: Option<DirectorySummary>,
+}
+
+impl ObjectMeta {
+    /// Template fields only known for directories, which walk the prefix to fill them in.
+    pub const DIRECTORY_FIELDS: &'static [&'static str] = &[
+        "files",
+        "directories",
+        "total_size",
+        "newest_modified",
+        "oldest_modified",
+        "max_depth",
+    ];
+}
+
+impl Fields for ObjectMeta {
+    const NAMES: &'static [&'static str] = &[
+        "path",
+        "type",
+        "size",
+        "human_size",
+        "last_modified",
+        "etag",
+        "content_type",
+        "version_id",
+        "storage_class",
+        "link_target",
+        "owner",
+        "group",
+        "replication",
+        "files",
+        "directories",
+        "total_size",
+        "newest_modified",
+        "oldest_modified",
+        "max_depth",
+    ];
+
+    fn field(&self, name: &str) -> Option<String> {
+        let directory = self.directory.as_ref();
+        match name {
+            "path" => Some(self.path.clone()),
+            "type" => Some(self.entry_type.clone()),
+            "size" => Some(self.size.to_string()),
+            "human_size" => Some(format_size(self.size)),
+            "last_modified" => self.last_modified.clone(),
+            "etag" => self.etag.clone(),
+            "content_type" => self.content_type.clone(),
+            "version_id" => self.version.clone(),
+            "storage_class" => self.storage_class.clone(),
+            "link_target" => self.link_target.clone(),
+            "owner" => self.owner.clone(),
+            "group" => self.group.clone(),
+            "replication" => self.replication.map(|r| r.to_string()),
+            "files" => directory.map(|d| d.files.to_string()),
+            "directories" => directory.map(|d| d.directories.to_string()),
+            "total_size" => directory.map(|d| d.bytes.to_string()),
+            "newest_modified" => directory.and_then(|d| d.newest).map(|t| t.to_string()),
+            "oldest_modified" => directory.and_then(|d| d.oldest).map(|t| t.to_string()),
+            "max_depth" => directory.map(|d| d.depth.to_string()),
+            _ => None,
+        }
+    }
 }
 
 /// Trait for fetching object metadata from storage.
@@ -28,10 +105,11 @@ pub trait Stater {
     ///
     /// # Arguments
     /// * `path` - Object path to query. Accepts any type implementing `AsRef<str>`.
+    /// * `version` - Optional version id to query instead of the current version
     ///
     /// # Returns
     /// * `Result<ObjectMeta>` - Collected metadata for the provided path
-    async fn stat<P: AsRef<str>>(&self, path: P) -> Result<ObjectMeta>;
+    async fn stat<P: AsRef<str>>(&self, path: P, version: Option<&str>) -> Result<ObjectMeta>;
 }
 
 /// Implementation of `Stater` for OpenDAL `Operator`.
@@ -47,8 +125,17 @@ impl Stater for OpenDalStater {
     }
 
     /// Fetch object metadata via OpenDAL's `stat` API, and normalize fields to printable types.
-    async fn stat<P: AsRef<str>>(&self, path: P) -> Result<ObjectMeta> {
-        let meta = self.operator.stat(path.as_ref()).await?;
+    async fn stat<P: AsRef<str>>(&self, path: P, version: Option<&str>) -> Result<ObjectMeta> {
+        let meta = match version {
+            Some(version) => {
+                super::versions::ensure_versioning(&self.operator)?;
+                self.operator
+                    .stat_with(path.as_ref())
+                    .version(version)
+                    .await?
+            }
+            None => self.operator.stat(path.as_ref()).await?,
+        };
 
         let entry_type = match meta.mode() {
             EntryMode::FILE => "file".to_string(),
@@ -59,6 +146,11 @@ impl Stater for OpenDalStater {
         let last_modified = meta.last_modified().map(|t| t.to_string());
         let etag = meta.etag().map(|s| s.to_string());
         let content_type = meta.content_type().map(|s| s.to_string());
+        let version = meta.version().map(|s| s.to_string());
+        let link_target = meta
+            .user_metadata()
+            .and_then(|metadata| metadata.get(super::link::LINK_METADATA_KEY))
+            .cloned();
 
         Ok(ObjectMeta {
             path: path.as_ref().to_owned(),
@@ -67,6 +159,13 @@ impl Stater for OpenDalStater {
             last_modified,
             etag,
             content_type,
+            version,
+            storage_class: None,
+            link_target,
+            owner: None,
+            group: None,
+            replication: None,
+            directory: None,
         })
     }
 }
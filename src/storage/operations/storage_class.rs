@@ -0,0 +1,292 @@
+use crate::config::{ProviderBackend, StorageProvider};
+use crate::error::{Error, Result};
+use crate::storage::connection::ConnectionSettings;
+use crate::storage::rest::{RestBucket, element, encode_query_value, object_key, unescape_xml};
+use crate::storage::retry::RetryPolicy;
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use reqwest::Method;
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+/// Page size of the listings used by `ls -L` and `inventory`.
+const LIST_PAGE_SIZE: usize = 1000;
+
+static CONTENTS: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?s)<Contents>(.*?)</Contents>").expect("valid contents regex"));
+
+/// Trait for moving objects between storage classes.
+pub trait ClassChanger {
+    /// Rewrite an object in place with another storage class.
+    ///
+    /// # Arguments
+    /// * `path` - Object key
+    /// * `class` - Target class, either the provider's name or an alias (`IA`, `ARCHIVE`)
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or detailed error information
+    async fn change_class(&self, path: &str, class: &str) -> Result<()>;
+}
+
+/// Implementation of ClassChanger using a server-side copy of the object onto itself,
+/// which is how S3, OSS and COS transition objects outside of lifecycle rules.
+pub struct RestClassChanger {
+    bucket: RestBucket,
+}
+
+impl RestClassChanger {
+    /// Create a class changer for the configured bucket; only S3, OSS and COS with static
+    /// credentials (or an AWS profile) are supported.
+    pub fn from_backend(
+        provider: StorageProvider,
+        backend: &ProviderBackend,
+        retry: RetryPolicy,
+        connection: &ConnectionSettings,
+    ) -> Result<Self> {
+        Ok(Self {
+            bucket: RestBucket::from_backend(
+                provider,
+                backend,
+                "storage classes",
+                retry,
+                connection,
+            )?,
+        })
+    }
+
+    fn class_header(&self) -> String {
+        format!("{}storage-class", self.bucket.header_prefix())
+    }
+
+    /// Storage class of a single object. S3 and COS leave the header out for STANDARD.
+    pub async fn object_class(&self, path: &str) -> Result<String> {
+        let key = object_key(path, "storage classes")?;
+        let response = self.bucket.send(Method::HEAD, key, None, &[], None).await?;
+        Ok(response
+            .headers
+            .get(self.class_header())
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("STANDARD")
+            .to_string())
+    }
+
+    /// Storage classes of the objects listed below `prefix`, keyed by path. Stops after
+    /// `limit` objects when set.
+    pub async fn list_classes(
+        &self,
+        prefix: &str,
+        recursive: bool,
+        limit: Option<usize>,
+    ) -> Result<HashMap<String, String>> {
+        let mut classes = HashMap::new();
+        self.for_each_page(prefix, recursive, |objects| {
+            classes.extend(
+                objects
+                    .into_iter()
+                    .map(|object| (object.key, object.storage_class)),
+            );
+            Ok(limit.is_none_or(|limit| classes.len() < limit))
+        })
+        .await?;
+        Ok(classes)
+    }
+
+    /// List the objects below `prefix` one page at a time, handing each page to `visit`
+    /// until the listing ends or `visit` returns `false`.
+    pub async fn for_each_page(
+        &self,
+        prefix: &str,
+        recursive: bool,
+        mut visit: impl FnMut(Vec<ListedObject>) -> Result<bool>,
+    ) -> Result<()> {
+        let prefix = prefix.trim_start_matches('/');
+        let mut marker = String::new();
+        loop {
+            let mut query = format!(
+                "max-keys={LIST_PAGE_SIZE}&prefix={}",
+                encode_query_value(prefix)
+            );
+            if !recursive {
+                query.push_str("&delimiter=%2F");
+            }
+            if !marker.is_empty() {
+                query.push_str(&format!("&marker={}", encode_query_value(&marker)));
+            }
+            let response = self
+                .bucket
+                .send(Method::GET, "", Some(&query), &[], None)
+                .await?;
+            let page = parse_listing(&response.body);
+            let last_key = page.objects.last().map(|object| object.key.clone());
+            if !visit(page.objects)? || !page.truncated {
+                return Ok(());
+            }
+            // Without a delimiter S3 leaves NextMarker out; continue after the last key.
+            match page.next_marker.or(last_key) {
+                Some(next) if next != marker => marker = next,
+                _ => return Ok(()),
+            }
+        }
+    }
+}
+
+impl ClassChanger for RestClassChanger {
+    async fn change_class(&self, path: &str, class: &str) -> Result<()> {
+        let key = object_key(path, "storage classes")?;
+        let class = provider_class(self.bucket.provider(), class);
+        let prefix = self.bucket.header_prefix();
+        let directive = match self.bucket.provider() {
+            StorageProvider::Cos => "Copy",
+            _ => "COPY",
+        };
+        let headers = [
+            (format!("{prefix}copy-source"), self.bucket.copy_source(key)),
+            (self.class_header(), class.clone()),
+            (format!("{prefix}metadata-directive"), directive.to_string()),
+        ];
+        let response = self
+            .bucket
+            .send(Method::PUT, key, None, &headers, None)
+            .await?;
+        // S3 may report a failed copy inside a 200 response.
+        if response.body.contains("<Error>") {
+            return Err(Error::ProviderRequest {
+                message: [
+                    element(&response.body, "Code"),
+                    element(&response.body, "Message"),
+                ]
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>()
+                .join(": "),
+            });
+        }
+        println!("Changed storage class of {path} to {class}");
+        Ok(())
+    }
+}
+
+/// Provider spelling of a storage class; `IA`, `ARCHIVE` and `GLACIER` work everywhere.
+pub fn provider_class(provider: StorageProvider, class: &str) -> String {
+    let upper = class.trim().to_ascii_uppercase().replace('-', "_");
+    match provider {
+        StorageProvider::Oss => match upper.as_str() {
+            "STANDARD" => "Standard".to_string(),
+            "IA" | "STANDARD_IA" => "IA".to_string(),
+            "ARCHIVE" | "GLACIER" => "Archive".to_string(),
+            "COLDARCHIVE" | "COLD_ARCHIVE" => "ColdArchive".to_string(),
+            "DEEPCOLDARCHIVE" | "DEEP_COLD_ARCHIVE" => "DeepColdArchive".to_string(),
+            _ => class.trim().to_string(),
+        },
+        StorageProvider::Cos => match upper.as_str() {
+            "IA" => "STANDARD_IA".to_string(),
+            "GLACIER" => "ARCHIVE".to_string(),
+            _ => upper,
+        },
+        _ => match upper.as_str() {
+            "IA" => "STANDARD_IA".to_string(),
+            "ARCHIVE" => "GLACIER".to_string(),
+            _ => upper,
+        },
+    }
+}
+
+/// An object as returned by ListObjects.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListedObject {
+    pub key: String,
+    pub size: u64,
+    /// Entity tag without the surrounding quotes
+    pub etag: Option<String>,
+    pub last_modified: Option<DateTime<Utc>>,
+    pub storage_class: String,
+}
+
+/// One page of a ListObjects response.
+struct ListingPage {
+    objects: Vec<ListedObject>,
+    truncated: bool,
+    next_marker: Option<String>,
+}
+
+fn parse_listing(xml: &str) -> ListingPage {
+    let objects = CONTENTS
+        .captures_iter(xml)
+        .filter_map(|capture| {
+            let contents = capture.get(1)?.as_str();
+            Some(ListedObject {
+                key: unescape_xml(element(contents, "Key")?),
+                size: element(contents, "Size")
+                    .and_then(|size| size.parse().ok())
+                    .unwrap_or(0),
+                etag: element(contents, "ETag")
+                    .map(|etag| unescape_xml(etag).trim_matches('"').to_string()),
+                last_modified: element(contents, "LastModified")
+                    .and_then(|time| DateTime::parse_from_rfc3339(time).ok())
+                    .map(|time| time.with_timezone(&Utc)),
+                storage_class: element(contents, "StorageClass")
+                    .unwrap_or("STANDARD")
+                    .to_string(),
+            })
+        })
+        .collect();
+    ListingPage {
+        objects,
+        truncated: element(xml, "IsTruncated") == Some("true"),
+        next_marker: element(xml, "NextMarker")
+            .filter(|marker| !marker.is_empty())
+            .map(unescape_xml),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn class_aliases_follow_provider_names() {
+        assert_eq!(provider_class(StorageProvider::S3, "ia"), "STANDARD_IA");
+        assert_eq!(provider_class(StorageProvider::S3, "archive"), "GLACIER");
+        assert_eq!(
+            provider_class(StorageProvider::S3, "deep_archive"),
+            "DEEP_ARCHIVE"
+        );
+        assert_eq!(provider_class(StorageProvider::Oss, "GLACIER"), "Archive");
+        assert_eq!(provider_class(StorageProvider::Oss, "ia"), "IA");
+        assert_eq!(provider_class(StorageProvider::Cos, "GLACIER"), "ARCHIVE");
+        assert_eq!(provider_class(StorageProvider::Cos, "IA"), "STANDARD_IA");
+    }
+
+    #[test]
+    fn listing_pages_carry_objects_and_markers() {
+        let xml = "<ListBucketResult><IsTruncated>true</IsTruncated>\
+            <NextMarker>logs/b&amp;c.txt</NextMarker>\
+            <Contents><Key>logs/a.txt</Key><LastModified>2024-05-01T10:00:00.000Z</LastModified>\
+            <ETag>&quot;900150983cd24fb0d6963f7d28e17f72&quot;</ETag><Size>3</Size>\
+            <StorageClass>GLACIER</StorageClass></Contents>\
+            <Contents><Key>logs/b&amp;c.txt</Key><Size>1</Size></Contents>\
+            <CommonPrefixes><Prefix>logs/old/</Prefix></CommonPrefixes></ListBucketResult>";
+        let page = parse_listing(xml);
+        assert_eq!(
+            page.objects,
+            vec![
+                ListedObject {
+                    key: "logs/a.txt".to_string(),
+                    size: 3,
+                    etag: Some("900150983cd24fb0d6963f7d28e17f72".to_string()),
+                    last_modified: DateTime::from_timestamp(1_714_557_600, 0),
+                    storage_class: "GLACIER".to_string(),
+                },
+                ListedObject {
+                    key: "logs/b&c.txt".to_string(),
+                    size: 1,
+                    etag: None,
+                    last_modified: None,
+                    storage_class: "STANDARD".to_string(),
+                },
+            ]
+        );
+        assert!(page.truncated);
+        assert_eq!(page.next_marker.as_deref(), Some("logs/b&c.txt"));
+    }
+}
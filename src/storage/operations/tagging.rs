@@ -0,0 +1,186 @@
+use crate::config::{ProviderBackend, StorageProvider};
+use crate::error::{Error, Result};
+use crate::storage::connection::ConnectionSettings;
+use crate::storage::rest::{RestBucket, escape_xml, object_key, unescape_xml};
+use crate::storage::retry::RetryPolicy;
+use regex::Regex;
+use reqwest::Method;
+use std::sync::LazyLock;
+
+/// Tag limit shared by S3, OSS and COS.
+pub const MAX_TAGS: usize = 10;
+pub const MAX_TAG_KEY_LEN: usize = 128;
+pub const MAX_TAG_VALUE_LEN: usize = 256;
+
+static TAG: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?s)<Tag>\s*<Key>(.*?)</Key>\s*(?:<Value>(.*?)</Value>|<Value\s*/>)\s*</Tag>")
+        .expect("valid tag regex")
+});
+
+/// Trait for reading and changing object tags.
+pub trait Tagger {
+    /// Print the tags of an object as `key=value` lines.
+    ///
+    /// # Arguments
+    /// * `path` - Object key
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or detailed error information
+    async fn get_tags(&self, path: &str) -> Result<()>;
+
+    /// Add or overwrite tags, keeping the object's other tags.
+    ///
+    /// # Arguments
+    /// * `path` - Object key
+    /// * `tags` - Key/value pairs to set
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or detailed error information
+    async fn set_tags(&self, path: &str, tags: &[(String, String)]) -> Result<()>;
+
+    /// Remove tags from an object.
+    ///
+    /// # Arguments
+    /// * `path` - Object key
+    /// * `keys` - Tag keys to remove; every tag when empty
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or detailed error information
+    async fn remove_tags(&self, path: &str, keys: &[String]) -> Result<()>;
+}
+
+/// Implementation of Tagger using the `?tagging` sub-resource of the provider's REST API,
+/// which OpenDAL does not expose.
+pub struct RestTagger {
+    bucket: RestBucket,
+}
+
+impl RestTagger {
+    /// Create a tagger for the configured bucket; only S3, OSS and COS with static
+    /// credentials (or an AWS profile) support tagging.
+    pub fn from_backend(
+        provider: StorageProvider,
+        backend: &ProviderBackend,
+        retry: RetryPolicy,
+        connection: &ConnectionSettings,
+    ) -> Result<Self> {
+        Ok(Self {
+            bucket: RestBucket::from_backend(
+                provider,
+                backend,
+                "object tagging",
+                retry,
+                connection,
+            )?,
+        })
+    }
+
+    async fn fetch(&self, path: &str) -> Result<Vec<(String, String)>> {
+        let body = self.send(Method::GET, path, None).await?;
+        Ok(parse_tagging(&body))
+    }
+
+    async fn store(&self, path: &str, tags: &[(String, String)]) -> Result<()> {
+        if tags.is_empty() {
+            self.send(Method::DELETE, path, None).await?;
+        } else {
+            self.send(Method::PUT, path, Some(tagging_xml(tags)))
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn send(&self, method: Method, path: &str, body: Option<String>) -> Result<String> {
+        let key = object_key(path, "tags")?;
+        let response = self
+            .bucket
+            .send(method, key, Some("tagging"), &[], body)
+            .await?;
+        Ok(response.body)
+    }
+}
+
+impl Tagger for RestTagger {
+    async fn get_tags(&self, path: &str) -> Result<()> {
+        for (key, value) in self.fetch(path).await? {
+            println!("{key}={value}");
+        }
+        Ok(())
+    }
+
+    async fn set_tags(&self, path: &str, tags: &[(String, String)]) -> Result<()> {
+        let mut merged = self.fetch(path).await?;
+        for (key, value) in tags {
+            match merged.iter_mut().find(|(existing, _)| existing == key) {
+                Some(entry) => entry.1 = value.clone(),
+                None => merged.push((key.clone(), value.clone())),
+            }
+        }
+        if merged.len() > MAX_TAGS {
+            return Err(Error::InvalidArgument {
+                message: format!(
+                    "objects can carry at most {MAX_TAGS} tags ({} after this change)",
+                    merged.len()
+                ),
+            });
+        }
+        self.store(path, &merged).await?;
+        println!("Tagged {path} ({} tag(s))", merged.len());
+        Ok(())
+    }
+
+    async fn remove_tags(&self, path: &str, keys: &[String]) -> Result<()> {
+        let remaining = if keys.is_empty() {
+            Vec::new()
+        } else {
+            let mut tags = self.fetch(path).await?;
+            tags.retain(|(key, _)| !keys.contains(key));
+            tags
+        };
+        self.store(path, &remaining).await?;
+        println!("Removed tags from {path} ({} left)", remaining.len());
+        Ok(())
+    }
+}
+
+fn tagging_xml(tags: &[(String, String)]) -> String {
+    let mut xml = String::from("<Tagging><TagSet>");
+    for (key, value) in tags {
+        xml.push_str(&format!(
+            "<Tag><Key>{}</Key><Value>{}</Value></Tag>",
+            escape_xml(key),
+            escape_xml(value)
+        ));
+    }
+    xml.push_str("</TagSet></Tagging>");
+    xml
+}
+
+fn parse_tagging(xml: &str) -> Vec<(String, String)> {
+    TAG.captures_iter(xml)
+        .map(|capture| {
+            let value = capture.get(2).map_or("", |value| value.as_str());
+            (unescape_xml(&capture[1]), unescape_xml(value))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tagging_xml_roundtrips() {
+        let tags = vec![
+            ("team".to_string(), "data & ml".to_string()),
+            ("empty".to_string(), String::new()),
+        ];
+        assert_eq!(parse_tagging(&tagging_xml(&tags)), tags);
+
+        let response = "<Tagging>\n  <TagSet>\n    <Tag>\n      <Key>env</Key>\n      <Value/>\n    </Tag>\n  </TagSet>\n</Tagging>";
+        assert_eq!(
+            parse_tagging(response),
+            vec![("env".to_string(), String::new())]
+        );
+    }
+}
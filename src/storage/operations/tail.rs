@@ -1,4 +1,5 @@
 use crate::error::{Error, Result};
+use crate::storage::utils::report::MultiResult;
 use opendal::Operator;
 use std::io::{self, Write};
 use std::path::PathBuf;
@@ -96,6 +97,7 @@ impl OpenDalTailReader {
         };
 
         let total = paths.len();
+        let mut results = MultiResult::new(total);
         for (idx, p) in paths.iter().enumerate() {
             let show_header = should_show_header(total);
             if show_header {
@@ -105,12 +107,14 @@ impl OpenDalTailReader {
                 println!("==> {} <==", p);
             }
 
-            if let Err(e) = self.read_and_display_tail(p, lines, bytes).await {
-                eprintln!("{}", e);
-            }
+            let result = self.read_and_display_tail(p, lines, bytes).await;
+            results.record(p, result);
         }
 
-        Ok(())
+        results.into_result(|failed_paths, total| Error::PartialFailure {
+            failed_paths,
+            total,
+        })
     }
 
     async fn tail_by_bytes(&self, path: &str, bytes: usize, file_size: u64) -> Result<()> {
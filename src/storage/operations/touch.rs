@@ -1,7 +1,11 @@
 use crate::error::{Error, Result};
 use crate::storage::operations::Mkdirer;
 use crate::storage::operations::mkdir::OpenDalMkdirer;
+use crate::storage::operations::preserve;
 use opendal::{ErrorKind, Operator};
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::SystemTime;
 
 /// Trait for touching files in storage (create or truncate)
 pub trait Toucher {
@@ -10,17 +14,73 @@ pub trait Toucher {
     /// - When `no_create` is true and the path does not exist, this is a no-op.
     /// - When `truncate` is true and the path exists as a file, it will be truncated to 0 bytes.
     /// - When `parents` is true, try to create parent directories when needed.
-    async fn touch(&self, path: &str, no_create: bool, truncate: bool, parents: bool)
-    -> Result<()>;
+    ///
+    /// Returns whether the file exists afterwards.
+    async fn touch(
+        &self,
+        path: &str,
+        no_create: bool,
+        truncate: bool,
+        parents: bool,
+    ) -> Result<bool>;
 }
 
 pub struct OpenDalToucher {
     operator: Operator,
+    mtime: Option<SystemTime>,
 }
 
 impl OpenDalToucher {
     pub fn new(operator: Operator) -> Self {
-        Self { operator }
+        Self {
+            operator,
+            mtime: None,
+        }
+    }
+
+    /// Stamp touched files with `mtime` in the `--preserve` user metadata, on backends
+    /// that store it. Existing objects are rewritten with their content and metadata.
+    pub fn with_mtime(mut self, mtime: Option<SystemTime>) -> Self {
+        self.mtime = mtime;
+        self
+    }
+
+    /// User metadata to write: `existing` plus the stamped mtime, or `None` when there
+    /// is nothing to stamp.
+    fn stamped_metadata(
+        &self,
+        existing: Option<&HashMap<String, String>>,
+    ) -> Option<HashMap<String, String>> {
+        if !self
+            .operator
+            .info()
+            .full_capability()
+            .write_with_user_metadata
+        {
+            return None;
+        }
+        let mtime = preserve::mtime_value(self.mtime?)?;
+        let mut metadata = existing.cloned().unwrap_or_default();
+        metadata.insert(preserve::MTIME_METADATA_KEY.to_string(), mtime);
+        Some(metadata)
+    }
+
+    async fn write(
+        &self,
+        path: &str,
+        content: opendal::Buffer,
+        content_type: Option<&str>,
+        metadata: Option<HashMap<String, String>>,
+    ) -> Result<()> {
+        let mut write = self.operator.write_with(path, content);
+        if let Some(content_type) = content_type {
+            write = write.content_type(content_type);
+        }
+        if let Some(metadata) = metadata {
+            write = write.user_metadata(metadata);
+        }
+        write.await?;
+        Ok(())
     }
 
     fn parent_dir_of(path: &str) -> Option<String> {
@@ -45,7 +105,7 @@ impl Toucher for OpenDalToucher {
         no_create: bool,
         truncate: bool,
         parents: bool,
-    ) -> Result<()> {
+    ) -> Result<bool> {
         if path.ends_with('/') {
             return Err(Error::InvalidArgument {
                 message: "touch does not support directories; use mkdir".to_string(),
@@ -60,18 +120,27 @@ impl Toucher for OpenDalToucher {
                     });
                 }
 
+                let metadata = self.stamped_metadata(meta.user_metadata());
                 if truncate {
-                    let mut writer = self.operator.writer(path).await?;
-                    writer.close().await?;
+                    self.write(path, opendal::Buffer::new(), meta.content_type(), metadata)
+                        .await?;
                     println!("Truncated: {}", path);
+                } else if let Some(metadata) = metadata {
+                    // Object stores cannot change metadata in place.
+                    let content = self.operator.read(path).await?;
+                    self.write(path, content, meta.content_type(), Some(metadata))
+                        .await?;
+                    println!("Touched: {}", path);
+                } else if self.mtime.is_some() {
+                    println!("Touched: {}", path);
                 }
                 // else: exists and not truncating -> no-op
-                Ok(())
+                Ok(true)
             }
             Err(e) if e.kind() == ErrorKind::NotFound => {
                 if no_create {
                     // no-op when file is missing
-                    return Ok(());
+                    return Ok(false);
                 }
 
                 if parents
@@ -81,12 +150,48 @@ impl Toucher for OpenDalToucher {
                     let mkdirer = OpenDalMkdirer::new(self.operator.clone());
                     Mkdirer::mkdir(&mkdirer, &parent, true).await?;
                 }
-                let mut writer = self.operator.writer(path).await?;
-                writer.close().await?;
+                let metadata = self.stamped_metadata(None);
+                self.write(path, opendal::Buffer::new(), None, metadata)
+                    .await?;
                 println!("Created: {}", path);
-                Ok(())
+                Ok(true)
             }
             Err(e) => Err(e.into()),
         }
     }
 }
+
+/// Set the modification time of `path` below the local `root`, for the fs backend.
+pub fn set_local_mtime(root: &Path, path: &str, mtime: SystemTime) -> Result<()> {
+    std::fs::File::options()
+        .write(true)
+        .open(root.join(path.trim_start_matches('/')))?
+        .set_modified(mtime)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[tokio::test]
+    async fn touch_reports_whether_the_file_exists() {
+        let dir = tempfile::tempdir().unwrap();
+        let operator =
+            Operator::new(opendal::services::Fs::default().root(dir.path().to_str().unwrap()))
+                .unwrap()
+                .finish();
+        let mtime = UNIX_EPOCH + Duration::from_secs(1_704_067_200);
+        let toucher = OpenDalToucher::new(operator).with_mtime(Some(mtime));
+
+        assert!(!toucher.touch("missing", true, false, false).await.unwrap());
+        assert!(toucher.touch("a/marker", false, false, true).await.unwrap());
+        set_local_mtime(dir.path(), "/a/marker", mtime).unwrap();
+        let modified = std::fs::metadata(dir.path().join("a/marker"))
+            .unwrap()
+            .modified()
+            .unwrap();
+        assert_eq!(modified, mtime);
+    }
+}
@@ -0,0 +1,299 @@
+//! Soft deletion for `rm --trash` and `storify trash`.
+//!
+//! Each `rm --trash` run moves its paths under `.storify-trash/<batch>/`, keeping their
+//! original keys below the batch, so a batch can be listed, restored or emptied as a whole.
+//! Batch names are UTC timestamps and sort chronologically.
+
+use crate::error::{
+    DirectoryDeletionNotRecursiveSnafu, Error, PartialDeletionSnafu, PartialRestoreSnafu,
+    PathNotFoundSnafu, Result,
+};
+use crate::storage::constants::TRASH_PREFIX;
+use crate::storage::utils::path::{is_remote_directory, is_remote_file, is_root_path, is_within};
+use crate::storage::utils::report::MultiResult;
+use crate::storage::utils::size::format_size;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use futures::stream::TryStreamExt;
+use opendal::{ErrorKind, Operator};
+use std::time::Duration;
+
+use super::mv::{Mover, OpenDalMover};
+
+const BATCH_FORMAT: &str = "%Y%m%dT%H%M%S%.3fZ";
+
+/// Settings for `storify trash empty`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EmptyTrashOptions {
+    /// Only remove batches at least this old
+    pub older_than: Duration,
+    /// Print what would be removed without removing it
+    pub dry_run: bool,
+}
+
+/// Trait for moving paths into the trash and back.
+pub trait Trasher {
+    /// Move paths into a trash batch instead of deleting them.
+    ///
+    /// # Arguments
+    /// * `paths` - Paths to move
+    /// * `batch` - Batch to move them into (see [`batch_name`])
+    /// * `recursive` - Whether directories may be moved
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or detailed error information
+    async fn trash(&self, paths: &[String], batch: &str, recursive: bool) -> Result<()>;
+
+    /// Print every trashed object with its batch, size and original path.
+    async fn list_trash(&self) -> Result<()>;
+
+    /// Move trashed paths back to their original location.
+    ///
+    /// # Arguments
+    /// * `paths` - Original paths to restore
+    /// * `batch` - Batch to restore from (the newest batch holding each path when `None`)
+    /// * `force` - Overwrite paths that exist again
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or detailed error information
+    async fn restore(&self, paths: &[String], batch: Option<&str>, force: bool) -> Result<()>;
+
+    /// Permanently delete trash batches.
+    async fn empty(&self, options: EmptyTrashOptions) -> Result<()>;
+}
+
+/// Implementation of Trasher for OpenDAL Operator.
+pub struct OpenDalTrasher {
+    operator: Operator,
+}
+
+impl OpenDalTrasher {
+    /// Create a new trasher with the given OpenDAL operator.
+    pub fn new(operator: Operator) -> Self {
+        Self { operator }
+    }
+
+    /// Batch names in the trash, newest first.
+    async fn batches(&self) -> Result<Vec<String>> {
+        let entries: Vec<opendal::Entry> = match self.operator.list(TRASH_PREFIX).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut batches: Vec<String> = entries
+            .iter()
+            .filter(|entry| {
+                entry.metadata().mode().is_dir()
+                    && entry.path().trim_start_matches('/') != TRASH_PREFIX
+            })
+            .map(|entry| entry.name().trim_end_matches('/').to_string())
+            .collect();
+        batches.sort_unstable_by(|a, b| b.cmp(a));
+        Ok(batches)
+    }
+
+    async fn exists(&self, path: &str) -> bool {
+        is_remote_file(&self.operator, path).await
+            || is_remote_directory(&self.operator, path).await
+    }
+}
+
+impl OpenDalTrasher {
+    async fn trash_one(&self, path: &str, dest: &str, recursive: bool) -> Result<()> {
+        let is_dir = is_remote_directory(&self.operator, path).await;
+        if !is_dir && !is_remote_file(&self.operator, path).await {
+            return PathNotFoundSnafu { path }.fail();
+        }
+        if is_dir && !recursive {
+            return DirectoryDeletionNotRecursiveSnafu { path }.fail();
+        }
+        OpenDalMover::new(self.operator.clone())
+            .mover(path, dest)
+            .await?;
+        // Moving leaves the emptied directory behind on backends with real ones.
+        if is_dir {
+            self.operator.remove_all(path).await?;
+        }
+        Ok(())
+    }
+}
+
+impl Trasher for OpenDalTrasher {
+    async fn trash(&self, paths: &[String], batch: &str, recursive: bool) -> Result<()> {
+        for path in paths {
+            if is_root_path(path) || is_within(path, TRASH_PREFIX) {
+                return Err(Error::InvalidArgument {
+                    message: format!(
+                        "cannot move '{path}' into the trash; use `storify trash empty` to delete trashed objects"
+                    ),
+                });
+            }
+        }
+        let mut results = MultiResult::new(paths.len());
+        for path in paths {
+            let dest = trash_path(batch, path);
+            let result = self.trash_one(path, &dest, recursive).await;
+            if results.record(path, result) {
+                println!("Trashed: {path} → {dest}");
+            }
+        }
+        results.into_result(|failed_paths, _| PartialDeletionSnafu { failed_paths }.build())
+    }
+
+    async fn list_trash(&self) -> Result<()> {
+        let lister = match self
+            .operator
+            .lister_with(TRASH_PREFIX)
+            .recursive(true)
+            .await
+        {
+            Ok(lister) => lister,
+            Err(e) if e.kind() == ErrorKind::NotFound => {
+                println!("Trash is empty");
+                return Ok(());
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let entries: Vec<opendal::Entry> = lister.try_collect().await?;
+        let mut trashed: Vec<(String, String, u64)> = Vec::new();
+        for entry in entries {
+            if entry.metadata().mode().is_dir() {
+                continue;
+            }
+            let Some((batch, path)) = split_trash_path(entry.path()) else {
+                continue;
+            };
+            let size = match entry.metadata().content_length() {
+                0 => self.operator.stat(entry.path()).await?.content_length(),
+                size => size,
+            };
+            trashed.push((batch.to_string(), path.to_string(), size));
+        }
+        if trashed.is_empty() {
+            println!("Trash is empty");
+            return Ok(());
+        }
+        trashed.sort();
+        for (batch, path, size) in trashed {
+            println!("{batch}  {:>8}  {path}", format_size(size));
+        }
+        Ok(())
+    }
+
+    async fn restore(&self, paths: &[String], batch: Option<&str>, force: bool) -> Result<()> {
+        let batches = match batch {
+            Some(batch) => vec![batch.to_string()],
+            None => self.batches().await?,
+        };
+        let mover = OpenDalMover::new(self.operator.clone());
+        let mut failed_paths = Vec::new();
+
+        for path in paths {
+            let mut source = None;
+            for batch in &batches {
+                let trashed = trash_path(batch, path);
+                if self.exists(&trashed).await {
+                    source = Some((batch, trashed));
+                    break;
+                }
+            }
+            let Some((batch, trashed)) = source else {
+                eprintln!("Not in the trash: {path}");
+                failed_paths.push(path.clone());
+                continue;
+            };
+            let target = path.trim_end_matches('/');
+            if !force && self.exists(target).await {
+                eprintln!("Not restoring {path}: it exists (use --force to overwrite)");
+                failed_paths.push(path.clone());
+                continue;
+            }
+            match mover.mover(&trashed, target).await {
+                Ok(()) => println!("Restored: {path} (from {batch})"),
+                Err(e) => {
+                    eprintln!("Failed to restore {path}: {e}");
+                    failed_paths.push(path.clone());
+                }
+            }
+        }
+
+        if !failed_paths.is_empty() {
+            return PartialRestoreSnafu { failed_paths }.fail();
+        }
+        Ok(())
+    }
+
+    async fn empty(&self, options: EmptyTrashOptions) -> Result<()> {
+        let older_than =
+            chrono::Duration::from_std(options.older_than).unwrap_or(chrono::Duration::MAX);
+        let cutoff = Utc::now()
+            .checked_sub_signed(older_than)
+            .unwrap_or(DateTime::<Utc>::MIN_UTC);
+        let mut removed = 0;
+        for batch in self.batches().await? {
+            // Batches not named by storify are left alone rather than guessed at.
+            if batch_time(&batch).is_none_or(|trashed_at| trashed_at > cutoff) {
+                continue;
+            }
+            let prefix = trash_path(&batch, "");
+            if options.dry_run {
+                println!("Would remove trash batch {batch}");
+            } else {
+                self.operator.remove_all(&prefix).await?;
+                println!("Removed trash batch {batch}");
+            }
+            removed += 1;
+        }
+        if removed == 0 {
+            println!("Nothing to remove from the trash");
+        }
+        Ok(())
+    }
+}
+
+/// Batch name for a trash run started at `now`.
+pub fn batch_name(now: DateTime<Utc>) -> String {
+    now.format(BATCH_FORMAT).to_string()
+}
+
+/// When a batch was trashed, or `None` for names storify did not create.
+fn batch_time(batch: &str) -> Option<DateTime<Utc>> {
+    NaiveDateTime::parse_from_str(batch, BATCH_FORMAT)
+        .ok()
+        .map(|time| time.and_utc())
+}
+
+/// Key of `path` inside `batch`; an empty `path` names the batch itself (with a trailing '/').
+pub fn trash_path(batch: &str, path: &str) -> String {
+    let path = path.trim_matches('/');
+    if path.is_empty() {
+        format!("{TRASH_PREFIX}{batch}/")
+    } else {
+        format!("{TRASH_PREFIX}{batch}/{path}")
+    }
+}
+
+/// Batch and original path of a key in the trash.
+fn split_trash_path(key: &str) -> Option<(&str, &str)> {
+    let rest = key.trim_start_matches('/').strip_prefix(TRASH_PREFIX)?;
+    rest.split_once('/').filter(|(_, path)| !path.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn batch_names_round_trip_and_map_paths() {
+        let now = Utc.with_ymd_and_hms(2026, 10, 15, 22, 25, 0).unwrap();
+        let batch = batch_name(now);
+        assert_eq!(batch, "20261015T222500.000Z");
+        assert_eq!(batch_time(&batch), Some(now));
+        assert_eq!(batch_time("manual"), None);
+
+        let key = trash_path(&batch, "/logs/app/");
+        assert_eq!(key, ".storify-trash/20261015T222500.000Z/logs/app");
+        assert_eq!(split_trash_path(&key), Some((batch.as_str(), "logs/app")));
+        assert_eq!(split_trash_path("docs/a.txt"), None);
+    }
+}
@@ -1,52 +1,291 @@
 use crate::error::Result;
+use crate::storage::output::{TreeEntry, TreeReport};
+use crate::storage::rest::escape_xml;
 use crate::storage::utils::error::IntoStorifyError;
+use crate::storage::utils::paging::{PageSize, list_entries_parallel};
+use crate::storage::utils::size::format_size;
 use crate::wrap_err;
+use clap::ValueEnum;
 use futures::stream::TryStreamExt;
-use opendal::Operator;
-use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use opendal::{Entry, Operator};
+use std::collections::{BTreeMap, VecDeque};
+
+/// Output format of `tree`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum TreeFormat {
+    /// Indented text with box-drawing connectors
+    #[default]
+    Text,
+    /// Nested JSON in the layout of `tree -J`
+    Json,
+    /// Standalone HTML page with nested lists
+    Html,
+}
+
+/// Options controlling what `tree` shows and how it is rendered.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TreeOptions {
+    /// Some(n) to limit depth; None or Some(0) means unlimited
+    pub max_depth: Option<usize>,
+    /// Show directories only
+    pub dirs_only: bool,
+    /// Show file sizes and cumulative directory sizes
+    pub show_size: bool,
+    /// Output format
+    pub format: TreeFormat,
+    /// Top-level directories listed at once without a depth limit
+    pub jobs: usize,
+}
 
 /// Trait for printing a directory tree in object storage.
 pub trait Treer {
     /// Print the directory structure as a tree.
     ///
     /// - `path`: root path to show
-    /// - `max_depth`: Some(n) to limit depth; None or Some(0) means unlimited
-    /// - `dirs_only`: show directories only
-    async fn tree(&self, path: &str, max_depth: Option<usize>, dirs_only: bool) -> Result<()>;
+    /// - `options`: depth limit, filters, sizes and output format
+    async fn tree(&self, path: &str, options: TreeOptions) -> Result<()>;
 }
 
 pub struct OpenDalTreer {
     operator: Operator,
 }
 
+/// Directory contents collected from the listing, keyed by name.
+#[derive(Debug, Default)]
+struct DirNode {
+    dirs: BTreeMap<String, DirNode>,
+    files: BTreeMap<String, u64>,
+}
+
+impl DirNode {
+    /// Node of the directory `chain` (like "a/b") below this one, created as needed.
+    fn dir_mut(&mut self, chain: &str) -> &mut DirNode {
+        let mut node = self;
+        for seg in chain.split('/').filter(|seg| !seg.is_empty()) {
+            node = node.dirs.entry(seg.to_string()).or_default();
+        }
+        node
+    }
+
+    /// Record a listed entry given its path relative to the tree root.
+    fn insert(&mut self, rel: &str, is_dir: bool, size: u64) {
+        if is_dir {
+            self.dir_mut(rel.trim_matches('/'));
+        } else {
+            match rel.rsplit_once('/') {
+                Some((parent, name)) => {
+                    self.dir_mut(parent).files.insert(name.to_string(), size);
+                }
+                None => {
+                    self.files.insert(rel.to_string(), size);
+                }
+            }
+        }
+    }
+
+    /// Size of every file below this directory, including deeper levels than shown.
+    fn total_size(&self) -> u64 {
+        self.files.values().sum::<u64>() + self.dirs.values().map(DirNode::total_size).sum::<u64>()
+    }
+}
+
+/// Number of directories and files shown, excluding the root.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct TreeCounts {
+    directories: usize,
+    files: usize,
+}
+
+impl TreeCounts {
+    fn summary(&self, total_size: Option<u64>) -> String {
+        let directories = if self.directories == 1 {
+            "directory"
+        } else {
+            "directories"
+        };
+        let files = if self.files == 1 { "file" } else { "files" };
+        let mut line = format!("{} {directories}, {} {files}", self.directories, self.files);
+        if let Some(size) = total_size {
+            line.push_str(&format!(", {} total", format_size(size)));
+        }
+        line
+    }
+}
+
+/// Build the entries below `node`, `depth` levels under the root.
+fn build_entries(
+    node: &DirNode,
+    depth: usize,
+    options: &TreeOptions,
+    counts: &mut TreeCounts,
+) -> Vec<TreeEntry> {
+    let limit = match options.max_depth {
+        Some(0) | None => usize::MAX,
+        Some(n) => n,
+    };
+    if depth >= limit {
+        return Vec::new();
+    }
+    let mut entries = Vec::new();
+    for (name, child) in &node.dirs {
+        counts.directories += 1;
+        entries.push(TreeEntry::Directory {
+            name: name.clone(),
+            size: options.show_size.then(|| child.total_size()),
+            contents: build_entries(child, depth + 1, options, counts),
+        });
+    }
+    if !options.dirs_only {
+        for (name, size) in &node.files {
+            counts.files += 1;
+            entries.push(TreeEntry::File {
+                name: name.clone(),
+                size: options.show_size.then_some(*size),
+            });
+        }
+    }
+    entries
+}
+
+/// Label of a directory or file line, with the size in brackets when shown.
+fn label(name: &str, is_dir: bool, size: Option<u64>) -> String {
+    let slash = if is_dir { "/" } else { "" };
+    match size {
+        Some(size) => format!("[{:>6}]  {name}{slash}", format_size(size)),
+        None => format!("{name}{slash}"),
+    }
+}
+
+fn render_text(entries: &[TreeEntry], prefix_flags: &mut Vec<bool>, out: &mut String) {
+    let total = entries.len();
+    for (idx, entry) in entries.iter().enumerate() {
+        let is_last = idx + 1 == total;
+        for more in prefix_flags.iter() {
+            out.push_str(if *more { "│   " } else { "    " });
+        }
+        out.push_str(if is_last { "└── " } else { "├── " });
+        match entry {
+            TreeEntry::Directory {
+                name,
+                size,
+                contents,
+            } => {
+                out.push_str(&label(name, true, *size));
+                out.push('\n');
+                // Whether more siblings follow decides if a vertical bar continues.
+                prefix_flags.push(!is_last);
+                render_text(contents, prefix_flags, out);
+                prefix_flags.pop();
+            }
+            TreeEntry::File { name, size } => {
+                out.push_str(&label(name, false, *size));
+                out.push('\n');
+            }
+        }
+    }
+}
+
+fn render_html(entries: &[TreeEntry], out: &mut String) {
+    if entries.is_empty() {
+        return;
+    }
+    out.push_str("<ul>\n");
+    for entry in entries {
+        match entry {
+            TreeEntry::Directory {
+                name,
+                size,
+                contents,
+            } => {
+                out.push_str(&format!(
+                    "<li class=\"dir\">{}",
+                    escape_xml(&label(name, true, *size))
+                ));
+                if !contents.is_empty() {
+                    out.push('\n');
+                    render_html(contents, out);
+                }
+                out.push_str("</li>\n");
+            }
+            TreeEntry::File { name, size } => {
+                out.push_str(&format!(
+                    "<li class=\"file\">{}</li>\n",
+                    escape_xml(&label(name, false, *size))
+                ));
+            }
+        }
+    }
+    out.push_str("</ul>\n");
+}
+
+/// Render the whole tree, root label and summary included.
+fn render(root_label: &str, root: &DirNode, options: &TreeOptions) -> Result<String> {
+    let mut counts = TreeCounts::default();
+    let entries = build_entries(root, 0, options, &mut counts);
+    let total_size = options.show_size.then(|| root.total_size());
+    let mut out = String::new();
+    match options.format {
+        TreeFormat::Text => {
+            out.push_str(&label(root_label.trim_end_matches('/'), true, total_size));
+            out.push('\n');
+            render_text(&entries, &mut Vec::new(), &mut out);
+            out.push('\n');
+            out.push_str(&counts.summary(total_size));
+            out.push('\n');
+        }
+        TreeFormat::Json => {
+            let root = TreeEntry::Directory {
+                name: root_label.to_string(),
+                size: total_size,
+                contents: entries,
+            };
+            let report = TreeReport::new(counts.directories, counts.files);
+            out.push_str(&serde_json::to_string(&(root, report))?);
+            out.push('\n');
+        }
+        TreeFormat::Html => {
+            let title = escape_xml(root_label);
+            out.push_str(&format!(
+                "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n\
+                 <style>ul {{ list-style: none; }} li.dir {{ font-weight: bold; }} \
+                 li.file {{ font-weight: normal; }}</style>\n</head>\n<body>\n<h1>{}</h1>\n",
+                escape_xml(&label(root_label.trim_end_matches('/'), true, total_size))
+            ));
+            render_html(&entries, &mut out);
+            out.push_str(&format!(
+                "<p>{}</p>\n</body>\n</html>\n",
+                escape_xml(&counts.summary(total_size))
+            ));
+        }
+    }
+    Ok(out)
+}
+
 impl OpenDalTreer {
     pub fn new(operator: Operator) -> Self {
         Self { operator }
     }
 
-    fn print_branch(prefix_flags: &[bool], name: &str, is_dir: bool, is_last: bool) {
-        let mut line = String::with_capacity(prefix_flags.len() * 4 + 4 + name.len() + 1);
-        for more in prefix_flags {
-            if *more {
-                line.push_str("│   ");
-            } else {
-                line.push_str("    ");
-            }
+    /// Size of a listed file; stats it when sizes are shown and the listing came back
+    /// without metadata (as on fs).
+    async fn entry_size(&self, entry: &Entry, show_size: bool) -> u64 {
+        let meta = entry.metadata();
+        if !show_size || meta.mode().is_dir() || meta.last_modified().is_some() {
+            return meta.content_length();
         }
-        line.push_str(if is_last { "└── " } else { "├── " });
-        line.push_str(name);
-        if is_dir {
-            line.push('/');
+        match self.operator.stat(entry.path()).await {
+            Ok(stat) => stat.content_length(),
+            Err(e) => {
+                tracing::debug!("stat failed for {}: {e}", entry.path());
+                meta.content_length()
+            }
         }
-        println!("{}", line);
     }
 }
 
 impl Treer for OpenDalTreer {
-    async fn tree(&self, path: &str, max_depth: Option<usize>, dirs_only: bool) -> Result<()> {
-        // Build children map directly during traversal
-        let mut dir_children: BTreeMap<String, (BTreeSet<String>, BTreeSet<String>)> =
-            BTreeMap::new();
+    async fn tree(&self, path: &str, options: TreeOptions) -> Result<()> {
+        let mut root_node = DirNode::default();
 
         // Ensure root path normalized without leading './'
         let root = path.trim_start_matches("./");
@@ -55,38 +294,29 @@ impl Treer for OpenDalTreer {
         } else {
             format!("{}/", root)
         };
-
-        // Helper to register directory chain like "a/b" so that "" -> a, "a" -> b
-        fn add_dir_chain(
-            dir_children: &mut BTreeMap<String, (BTreeSet<String>, BTreeSet<String>)>,
-            chain: &str,
-        ) {
-            if chain.is_empty() {
-                return;
-            }
-            let mut parent_path: String = String::new();
-            for seg in chain.split('/') {
-                let (dirs_set, _files_set) = dir_children.entry(parent_path.clone()).or_default();
-                dirs_set.insert(seg.to_string());
-                if parent_path.is_empty() {
-                    parent_path.push_str(seg);
-                } else {
-                    parent_path.push('/');
-                    parent_path.push_str(seg);
-                }
+        let relative = |p: &str| -> String {
+            if let Some(rel) = p.strip_prefix(&root_prefix) {
+                rel.to_string()
+            } else if p == root {
+                String::new()
+            } else {
+                p.to_string()
             }
-        }
+        };
 
-        // Determine traversal strategy based on depth limit
-        let traversal_limit = match max_depth {
+        // Determine traversal strategy based on depth limit; cumulative sizes need
+        // every file below the shown levels, so --size always lists everything.
+        let traversal_limit = match options.max_depth {
             Some(0) | None => usize::MAX,
+            _ if options.show_size => usize::MAX,
             Some(n) => n,
         };
 
         if traversal_limit == usize::MAX {
             // Unlimited depth: keep using a single recursive lister (fast path)
             let lister = wrap_err!(
-                self.operator.lister_with(path).recursive(true).await,
+                list_entries_parallel(&self.operator, path, true, PageSize::Auto, options.jobs)
+                    .await,
                 ListDirectoryFailed {
                     path: path.to_string()
                 }
@@ -98,36 +328,12 @@ impl Treer for OpenDalTreer {
             });
 
             while let Some(entry) = stream.try_next().await? {
-                let p = entry.path();
-                let rel = if p.starts_with(&root_prefix) {
-                    &p[root_prefix.len()..]
-                } else if p == root {
-                    ""
-                } else {
-                    p
-                };
-
+                let rel = relative(entry.path());
                 if rel.is_empty() {
                     continue;
                 }
-
-                let meta = entry.metadata();
-                if meta.mode().is_dir() {
-                    // normalize directory path without leading/trailing slashes
-                    let d = rel.trim_matches('/').to_string();
-                    add_dir_chain(&mut dir_children, &d);
-                } else if !dirs_only {
-                    // treat file only when dirs_only is false
-                    if let Some((parent, name)) = rel.rsplit_once('/') {
-                        let parent_norm = parent.trim_matches('/');
-                        add_dir_chain(&mut dir_children, parent_norm);
-                        let (_ds, fs) = dir_children.entry(parent_norm.to_string()).or_default();
-                        fs.insert(name.to_string());
-                    } else {
-                        let (_ds, fs) = dir_children.entry(String::new()).or_default();
-                        fs.insert(rel.to_string());
-                    }
-                }
+                let size = self.entry_size(&entry, options.show_size).await;
+                root_node.insert(&rel, entry.metadata().mode().is_dir(), size);
             }
         } else {
             // Limited depth: BFS by levels, only list down to traversal_limit
@@ -149,102 +355,80 @@ impl Treer for OpenDalTreer {
                 });
 
                 while let Some(entry) = stream.try_next().await? {
-                    let p = entry.path();
-                    let rel = if p.starts_with(&root_prefix) {
-                        &p[root_prefix.len()..]
-                    } else if p == root {
-                        ""
-                    } else {
-                        p
-                    };
-
+                    let rel = relative(entry.path());
                     if rel.is_empty() {
                         continue;
                     }
-
-                    let meta = entry.metadata();
-                    if meta.mode().is_dir() {
-                        let d = rel.trim_matches('/').to_string();
-                        add_dir_chain(&mut dir_children, &d);
-                        // Only traverse deeper if within depth limit
-                        if depth + 1 < traversal_limit {
-                            // entry.path() should be the absolute directory path
-                            queue.push_back((p.to_string(), depth + 1));
-                        }
-                    } else if !dirs_only {
-                        if let Some((parent, name)) = rel.rsplit_once('/') {
-                            let parent_norm = parent.trim_matches('/');
-                            add_dir_chain(&mut dir_children, parent_norm);
-                            let (_ds, fs) =
-                                dir_children.entry(parent_norm.to_string()).or_default();
-                            fs.insert(name.to_string());
-                        } else {
-                            let (_ds, fs) = dir_children.entry(String::new()).or_default();
-                            fs.insert(rel.to_string());
-                        }
+                    let is_dir = entry.metadata().mode().is_dir();
+                    let size = self.entry_size(&entry, options.show_size).await;
+                    root_node.insert(&rel, is_dir, size);
+                    // Only traverse deeper if within depth limit
+                    if is_dir && depth + 1 < traversal_limit {
+                        // entry.path() should be the absolute directory path
+                        queue.push_back((entry.path().to_string(), depth + 1));
                     }
                 }
             }
         }
 
-        // DFS print with Unicode connectors
-        let limit = match max_depth {
-            Some(0) | None => usize::MAX,
-            Some(n) => n,
-        };
         // print root label and ensure directories end with a trailing slash
         let root_label: String = if root.is_empty() || root == "/" {
             "/".to_string()
         } else {
             format!("{}/", root.trim_end_matches('/'))
         };
-        println!("{}", root_label);
-
-        fn dfs(
-            cwd: &str,
-            prefix_flags: &mut Vec<bool>,
-            limit: usize,
-            dirs_only: bool,
-            dir_children: &BTreeMap<String, (BTreeSet<String>, BTreeSet<String>)>,
-        ) {
-            // depth equals current prefix length; stop if reached limit
-            if prefix_flags.len() >= limit {
-                return;
-            }
+        print!("{}", render(&root_label, &root_node, &options)?);
+        Ok(())
+    }
+}
 
-            if let Some((dir_set, file_set)) = dir_children.get(cwd) {
-                // build ordered children list: dirs then files
-                let mut items: Vec<(String, bool)> = Vec::new();
-                for d in dir_set.iter() {
-                    items.push((d.clone(), true));
-                }
-                if !dirs_only {
-                    for f in file_set.iter() {
-                        items.push((f.clone(), false));
-                    }
-                }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-                let total = items.len();
-                for (idx, (name, is_dir)) in items.into_iter().enumerate() {
-                    let is_last = idx + 1 == total;
-                    OpenDalTreer::print_branch(prefix_flags, &name, is_dir, is_last);
-                    if is_dir {
-                        let next = if cwd.is_empty() {
-                            name.clone()
-                        } else {
-                            format!("{}/{}", cwd, name)
-                        };
-                        // push whether there are more siblings at this level to draw vertical bars
-                        prefix_flags.push(!is_last);
-                        dfs(&next, prefix_flags, limit, dirs_only, dir_children);
-                        prefix_flags.pop();
-                    }
-                }
-            }
-        }
+    fn sample() -> DirNode {
+        let mut root = DirNode::default();
+        root.insert("a/", true, 0);
+        root.insert("a/b/c.txt", false, 2048);
+        root.insert("a/d.txt", false, 1024);
+        root.insert("top.txt", false, 10);
+        root
+    }
 
-        let mut prefix_flags: Vec<bool> = Vec::new();
-        dfs("", &mut prefix_flags, limit, dirs_only, &dir_children);
-        Ok(())
+    #[test]
+    fn text_shows_cumulative_sizes_and_summary() {
+        let options = TreeOptions {
+            show_size: true,
+            max_depth: Some(1),
+            ..Default::default()
+        };
+        let out = render("data/", &sample(), &options).unwrap();
+        assert_eq!(
+            out,
+            "[  3.0K]  data/\n├── [  3.0K]  a/\n└── [   10B]  top.txt\n\n\
+             1 directory, 1 file, 3.0K total\n"
+        );
+    }
+
+    #[test]
+    fn json_nests_entries_and_reports_counts() {
+        let options = TreeOptions {
+            dirs_only: true,
+            format: TreeFormat::Json,
+            ..Default::default()
+        };
+        let out = render("data/", &sample(), &options).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!([
+                {"type": "directory", "name": "data/", "contents": [
+                    {"type": "directory", "name": "a", "contents": [
+                        {"type": "directory", "name": "b", "contents": []}
+                    ]}
+                ]},
+                {"type": "report", "schema_version": 1, "directories": 2, "files": 0}
+            ])
+        );
     }
 }
@@ -0,0 +1,292 @@
+//! Snapshots taken before destructive commands, and reverting them for `storify undo`.
+//!
+//! Before `rm`, `touch -t` or an overwriting `put`, the objects about to be lost are
+//! copied to a local backup directory (see [`crate::journal`]); undoing puts those copies
+//! back. Moves and trashed paths need no copies: they are moved back into place.
+
+use crate::error::{Error, PartialRestoreSnafu, Result};
+use crate::journal::{
+    Change, MAX_BACKUP_OBJECTS, MAX_BACKUP_SIZE, MAX_BACKUP_TOTAL, Prior, PriorObject, backup_path,
+};
+use crate::storage::utils::path::{ensure_trailing_slash, is_remote_directory, is_remote_file};
+use futures::stream::TryStreamExt;
+use opendal::{ErrorKind, Operator};
+use std::path::Path;
+
+use super::mv::{Mover, OpenDalMover};
+use super::trash::{OpenDalTrasher, Trasher, trash_path};
+
+/// Trait for recording and reverting destructive changes.
+pub trait Undoer {
+    /// Record the current state of `paths` (with everything under directories when
+    /// `recursive`), copying small objects into `backup_dir`.
+    ///
+    /// # Arguments
+    /// * `paths` - Paths about to be deleted or overwritten
+    /// * `recursive` - Whether directories are affected as a whole
+    /// * `backup_dir` - Local directory receiving the copies
+    ///
+    /// # Returns
+    /// * `Result<Change>` - The state of every affected object, or [`Change::NotUndoable`]
+    ///   once they outgrow the per-command object or byte limit
+    async fn snapshot(
+        &self,
+        paths: &[String],
+        recursive: bool,
+        backup_dir: &Path,
+    ) -> Result<Change>;
+
+    /// Revert a recorded change.
+    async fn undo(&self, change: &Change) -> Result<()>;
+}
+
+/// Implementation of Undoer for OpenDAL Operator.
+pub struct OpenDalUndoer {
+    operator: Operator,
+}
+
+impl OpenDalUndoer {
+    /// Create a new undoer with the given OpenDAL operator.
+    pub fn new(operator: Operator) -> Self {
+        Self { operator }
+    }
+
+    /// Record the state of `path` in `objects`, copying it into `backup_dir` when it fits
+    /// the remaining `budget`. `size` comes from a listing when it had one, saving a stat.
+    ///
+    /// Returns why the snapshot has to stop, once the objects or bytes are over the limits.
+    async fn keep(
+        &self,
+        path: &str,
+        size: Option<u64>,
+        backup_dir: &Path,
+        objects: &mut Vec<PriorObject>,
+        budget: &mut u64,
+    ) -> Result<Option<String>> {
+        if objects.len() >= MAX_BACKUP_OBJECTS {
+            return Ok(Some(format!(
+                "it affected more than {MAX_BACKUP_OBJECTS} objects"
+            )));
+        }
+        let size = match size {
+            Some(size) => size,
+            None => match self.operator.stat(path).await {
+                Ok(meta) => meta.content_length(),
+                Err(e) if e.kind() == ErrorKind::NotFound => {
+                    objects.push(PriorObject {
+                        path: path.to_string(),
+                        prior: Prior::Missing,
+                    });
+                    return Ok(None);
+                }
+                Err(e) => return Err(e.into()),
+            },
+        };
+        let prior = if size > MAX_BACKUP_SIZE {
+            Prior::NotKept { size }
+        } else if size > *budget {
+            return Ok(Some(format!(
+                "its objects are over the {} MiB kept per command",
+                MAX_BACKUP_TOTAL / (1024 * 1024)
+            )));
+        } else {
+            let backup = backup_path(backup_dir, objects.len());
+            let content = self.operator.read(path).await?;
+            tokio::fs::write(&backup, content.to_vec()).await?;
+            *budget -= size;
+            Prior::Saved { backup }
+        };
+        objects.push(PriorObject {
+            path: path.to_string(),
+            prior,
+        });
+        Ok(None)
+    }
+
+    /// Whether `path` holds a file, or a directory with files in it.
+    async fn occupied(&self, path: &str) -> Result<bool> {
+        if is_remote_file(&self.operator, path).await {
+            return Ok(true);
+        }
+        let entries: Vec<opendal::Entry> = match self
+            .operator
+            .lister_with(&ensure_trailing_slash(path))
+            .recursive(true)
+            .await
+        {
+            Ok(lister) => lister.try_collect().await?,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(false),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(entries
+            .iter()
+            .any(|entry| !entry.metadata().mode().is_dir()))
+    }
+
+    async fn undo_move(&self, from: &str, to: &str) -> Result<()> {
+        if self.occupied(from).await? {
+            eprintln!("Not moving {to} back: {from} exists");
+            return PartialRestoreSnafu {
+                failed_paths: vec![from.to_string()],
+            }
+            .fail();
+        }
+        let is_dir = is_remote_directory(&self.operator, to).await;
+        OpenDalMover::new(self.operator.clone())
+            .mover(to, from)
+            .await?;
+        // Moving leaves the emptied directory behind on backends with real ones.
+        if is_dir {
+            self.operator.remove_all(to).await?;
+        }
+        println!("Moved back: {to} → {from}");
+        Ok(())
+    }
+
+    async fn undo_trash(&self, batch: &str, paths: &[String]) -> Result<()> {
+        let mut trashed = Vec::new();
+        for path in paths {
+            if self.occupied(&trash_path(batch, path)).await? {
+                trashed.push(path.clone());
+            }
+        }
+        if trashed.is_empty() {
+            println!("Nothing from trash batch {batch} is left to restore");
+            return Ok(());
+        }
+        OpenDalTrasher::new(self.operator.clone())
+            .restore(&trashed, Some(batch), false)
+            .await
+    }
+
+    async fn undo_replace(&self, objects: &[PriorObject]) -> Result<()> {
+        let mut failed_paths = Vec::new();
+        for object in objects {
+            let path = &object.path;
+            let result = match &object.prior {
+                Prior::Missing => self
+                    .operator
+                    .delete(path)
+                    .await
+                    .map(|_| println!("Removed: {path}")),
+                Prior::Saved { backup } => match tokio::fs::read(backup).await {
+                    Ok(content) => self
+                        .operator
+                        .write(path, content)
+                        .await
+                        .map(|_| println!("Restored: {path}")),
+                    Err(e) => {
+                        eprintln!("Failed to restore {path}: {e}");
+                        failed_paths.push(path.clone());
+                        continue;
+                    }
+                },
+                Prior::NotKept { size } => {
+                    eprintln!("Cannot restore {path}: no copy was kept ({size} bytes)");
+                    failed_paths.push(path.clone());
+                    continue;
+                }
+            };
+            if let Err(e) = result {
+                eprintln!("Failed to restore {path}: {e}");
+                failed_paths.push(path.clone());
+            }
+        }
+
+        if !failed_paths.is_empty() {
+            return PartialRestoreSnafu { failed_paths }.fail();
+        }
+        Ok(())
+    }
+}
+
+impl Undoer for OpenDalUndoer {
+    async fn snapshot(
+        &self,
+        paths: &[String],
+        recursive: bool,
+        backup_dir: &Path,
+    ) -> Result<Change> {
+        let mut objects = Vec::new();
+        let mut budget = MAX_BACKUP_TOTAL;
+        for path in paths {
+            let stopped = if is_remote_directory(&self.operator, path).await {
+                if !recursive {
+                    continue;
+                }
+                let mut lister = self
+                    .operator
+                    .lister_with(&ensure_trailing_slash(path))
+                    .recursive(true)
+                    .await?;
+                let mut stopped = None;
+                while stopped.is_none()
+                    && let Some(entry) = lister.try_next().await?
+                {
+                    let meta = entry.metadata();
+                    if meta.mode().is_dir() {
+                        continue;
+                    }
+                    // Listings without modification times may not carry sizes either.
+                    let size = meta.last_modified().map(|_| meta.content_length());
+                    stopped = self
+                        .keep(entry.path(), size, backup_dir, &mut objects, &mut budget)
+                        .await?;
+                }
+                stopped
+            } else {
+                self.keep(path, None, backup_dir, &mut objects, &mut budget)
+                    .await?
+            };
+            if let Some(reason) = stopped {
+                return Ok(Change::NotUndoable { reason });
+            }
+        }
+        Ok(Change::Replaced { objects })
+    }
+
+    async fn undo(&self, change: &Change) -> Result<()> {
+        match change {
+            Change::Trashed { batch, paths } => self.undo_trash(batch, paths).await,
+            Change::Moved { from, to } => self.undo_move(from, to).await,
+            Change::Replaced { objects } => self.undo_replace(objects).await,
+            Change::NotUndoable { reason } => Err(Error::InvalidArgument {
+                message: format!("the operation cannot be undone: {reason}"),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opendal::services::Memory;
+
+    #[tokio::test]
+    async fn snapshot_stops_at_the_object_limit() {
+        let operator = Operator::new(Memory::default()).unwrap().finish();
+        operator.write("logs/a.txt", "a").await.unwrap();
+        operator.write("logs/b.txt", "b").await.unwrap();
+        let backups = tempfile::tempdir().unwrap();
+        let undoer = OpenDalUndoer::new(operator.clone());
+
+        let paths = ["logs/".to_string(), "missing.txt".to_string()];
+        let Change::Replaced { objects } =
+            undoer.snapshot(&paths, true, backups.path()).await.unwrap()
+        else {
+            panic!("small deletes are undoable");
+        };
+        assert_eq!(objects.len(), 3);
+        assert!(matches!(objects[0].prior, Prior::Saved { .. }));
+        assert_eq!(objects[2].prior, Prior::Missing);
+
+        for n in 0..MAX_BACKUP_OBJECTS {
+            operator.write(&format!("logs/{n}"), "x").await.unwrap();
+        }
+        let change = undoer
+            .snapshot(&paths[..1], true, backups.path())
+            .await
+            .unwrap();
+        assert!(matches!(change, Change::NotUndoable { .. }));
+    }
+}
@@ -1,14 +1,191 @@
-use crate::error::{DirectoryUploadNotRecursiveSnafu, PathNotFoundSnafu, Result};
+use crate::config::crypto::DataKey;
+use crate::error::{
+    DestinationIsDirectorySnafu, DestinationIsFileSnafu, DirectoryUploadNotRecursiveSnafu, Error,
+    PathNotFoundSnafu, Result,
+};
+use crate::jobs::CancelToken;
 use crate::storage::constants::{DEFAULT_BUFFER_SIZE, PROGRESS_UPDATE_INTERVAL};
-use crate::storage::utils::path::build_remote_path;
+use crate::storage::utils::archive::{self, EntryKind, TarReader};
+use crate::storage::utils::cipher::Sealer;
+use crate::storage::utils::clobber::Clobber;
+use crate::storage::utils::codec::Compression;
+use crate::storage::utils::filter::PathFilter;
+use crate::storage::utils::ignore::{IGNORE_FILE_NAME, IgnoreRules};
+use crate::storage::utils::path::{
+    build_remote_path, ensure_trailing_slash, is_remote_directory, is_remote_file, names_directory,
+    transfer_target,
+};
 use crate::storage::utils::progress::ConsoleProgressReporter;
-use async_recursion::async_recursion;
+use crate::storage::utils::report::TransferReport;
+use crate::storage::utils::size::format_size;
+use crate::storage::utils::throttle::{FileThrottle, Throttle};
+use crate::storage::utils::validate::{remote_key_warnings, report_warnings};
+use futures::stream::{self, StreamExt};
 use opendal::Operator;
 use snafu::ensure;
-use std::ffi::OsStr;
-use std::path::Path;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
 use tokio::fs;
-use tokio::io::{AsyncReadExt, BufReader};
+use tokio::io::{AsyncRead, AsyncReadExt, BufReader};
+
+use super::hdfs::{ProviderExtension, WebHdfsExtension};
+use super::preserve;
+
+/// Local path that makes `put` read standard input.
+pub const STDIN_PATH: &str = "-";
+
+/// Settings for `storify put`.
+#[derive(Debug, Clone)]
+pub struct UploadOptions {
+    /// Files uploaded at the same time during recursive uploads
+    pub concurrency: usize,
+    /// Bandwidth cap for each file, in bytes per second
+    pub per_file_limit: Option<u64>,
+    /// Bandwidth cap shared by all files of the command, in bytes per second
+    pub total_limit: Option<u64>,
+    /// Report the server-side encryption applied to each uploaded object and a summary
+    pub verbose: bool,
+    /// Print what would be uploaded without uploading anything
+    pub dry_run: bool,
+    /// Files of a recursive upload to transfer, by path relative to the uploaded directory
+    pub filter: PathFilter,
+    /// Store each file's modification time and permissions as object metadata
+    pub preserve: bool,
+    /// Treatment of symlinks found inside uploaded directories
+    pub symlinks: SymlinkPolicy,
+    /// HDFS replication factor of the uploaded files
+    pub replication: Option<u16>,
+    /// Compress each file while uploading it, adding the format's extension to its key
+    pub compression: Option<Compression>,
+    /// Encrypt each file with the configured data key before it leaves the machine
+    pub encrypt: bool,
+    /// Upload the files of a tar archive one by one instead of the archive itself
+    pub extract: bool,
+    /// Treatment of objects that already exist at the destination
+    pub clobber: Clobber,
+}
+
+/// How `put` treats symbolic links it finds on the local side.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SymlinkPolicy {
+    /// Upload what links point to; linked directories are walked, skipping loops
+    #[default]
+    Follow,
+    /// Leave links out of the upload
+    Skip,
+    /// Upload each link as a small marker object holding its target
+    Preserve,
+}
+
+/// Walk of a local directory collecting the files a recursive upload transfers with their
+/// remote keys. Excluded and ignored directories are not descended into.
+struct DirWalk<'a> {
+    filter: &'a PathFilter,
+    ignore: Option<&'a IgnoreRules>,
+    symlinks: SymlinkPolicy,
+    /// Canonical directories being walked, so a followed link back up cannot loop
+    ancestors: HashSet<PathBuf>,
+    files: Vec<(PathBuf, String)>,
+    /// Files left out by the filters or the ignore file
+    skipped: usize,
+    /// Links left out by `SymlinkPolicy::Skip`
+    skipped_links: usize,
+}
+
+impl DirWalk<'_> {
+    fn walk(&mut self, local_dir: &Path, relative_dir: &str, remote_path: &str) {
+        let canonical = local_dir.canonicalize().ok();
+        if let Some(canonical) = &canonical
+            && !self.ancestors.insert(canonical.clone())
+        {
+            eprintln!(
+                "Warning: skipping {}: symlink loop back to a parent directory",
+                local_dir.display()
+            );
+            return;
+        }
+        self.walk_entries(local_dir, relative_dir, remote_path);
+        if let Some(canonical) = canonical {
+            self.ancestors.remove(&canonical);
+        }
+    }
+
+    fn walk_entries(&mut self, local_dir: &Path, relative_dir: &str, remote_path: &str) {
+        let Ok(entries) = std::fs::read_dir(local_dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let local_file_path = entry.path();
+            let file_name = entry.file_name().to_string_lossy().into_owned();
+            let relative_path = if relative_dir.is_empty() {
+                file_name.clone()
+            } else {
+                format!("{relative_dir}/{file_name}")
+            };
+            let is_link = entry.file_type().is_ok_and(|kind| kind.is_symlink());
+            let is_dir = match (is_link, self.symlinks) {
+                (true, SymlinkPolicy::Skip) => {
+                    self.skipped_links += 1;
+                    continue;
+                }
+                (true, SymlinkPolicy::Preserve) => false,
+                (true, SymlinkPolicy::Follow) if !local_file_path.exists() => {
+                    eprintln!(
+                        "Warning: skipping broken symlink {}",
+                        local_file_path.display()
+                    );
+                    self.skipped += 1;
+                    continue;
+                }
+                _ => local_file_path.is_dir(),
+            };
+            let ignored = self
+                .ignore
+                .is_some_and(|rules| rules.is_ignored(&relative_path, is_dir));
+            let new_remote_path = build_remote_path(remote_path, &file_name);
+            if is_dir {
+                if ignored || self.filter.excludes(&relative_path) {
+                    tracing::debug!("Skip excluded directory {}", local_file_path.display());
+                    continue;
+                }
+                self.walk(&local_file_path, &relative_path, &new_remote_path);
+            } else if relative_path == IGNORE_FILE_NAME {
+                continue;
+            } else if ignored || !self.filter.matches(&relative_path) {
+                self.skipped += 1;
+            } else {
+                self.files.push((local_file_path, new_remote_path));
+            }
+        }
+    }
+}
+
+/// Files below `local_dir` that `put -R` uploads without filters (symlinks followed,
+/// `.storifyignore` applied), with their paths relative to it.
+pub fn local_files(local_dir: &Path) -> Result<Vec<(PathBuf, String)>> {
+    let filter = PathFilter::default();
+    let ignore = IgnoreRules::load(local_dir)?;
+    let mut walk = DirWalk {
+        filter: &filter,
+        ignore: ignore.as_ref(),
+        symlinks: SymlinkPolicy::Follow,
+        ancestors: HashSet::new(),
+        files: Vec::new(),
+        skipped: 0,
+        skipped_links: 0,
+    };
+    walk.walk(local_dir, "", "");
+    Ok(walk.files)
+}
+
+/// How much a compressed upload shrank the file, for the upload summary line.
+fn compressed_note(compression: Option<Compression>, original: u64) -> String {
+    match compression {
+        Some(compression) => format!(", {} from {original}", compression.content_encoding()),
+        None => String::new(),
+    }
+}
 
 /// Trait for uploading files and directories to storage.
 pub trait Uploader {
@@ -18,80 +195,554 @@ pub trait Uploader {
     /// * `local_path` - Source path on local filesystem (file or directory)
     /// * `remote_path` - Destination path in storage
     /// * `recursive` - Whether to upload directories recursively
+    /// * `options` - Concurrency and bandwidth limits
     ///
     /// # Returns
-    /// * `Result<()>` - Success or detailed error information
-    async fn upload(&self, local_path: &str, remote_path: &str, recursive: bool) -> Result<()>;
+    /// * `Result<TransferReport>` - Totals of the upload; files of a directory that fail are
+    ///   counted there instead of aborting the rest
+    async fn upload(
+        &self,
+        local_path: &str,
+        remote_path: &str,
+        recursive: bool,
+        options: &UploadOptions,
+    ) -> Result<TransferReport>;
 }
 
 /// Implementation of Uploader for OpenDAL Operator.
 pub struct OpenDalUploader {
     operator: Operator,
+    /// Server-side encryption the operator writes with, as shown in verbose output
+    encryption: Option<String>,
+    /// Replication factor set on each file once written
+    replication: Option<(WebHdfsExtension, u16)>,
+    /// Client-side encryption key every file is sealed with
+    data_key: Option<DataKey>,
+    /// Checked between chunks and before each file of a directory
+    cancel: CancelToken,
 }
 
 impl OpenDalUploader {
     /// Create a new uploader with the given OpenDAL operator.
     pub fn new(operator: Operator) -> Self {
-        Self { operator }
+        Self {
+            operator,
+            encryption: None,
+            replication: None,
+            data_key: None,
+            cancel: CancelToken::default(),
+        }
     }
 
-    /// Upload a single file with streaming progress.
-    async fn upload_file_streaming(&self, local_path: &Path, remote_path: &str) -> Result<()> {
+    /// Describe the server-side encryption configured on the operator.
+    pub fn with_encryption(mut self, encryption: String) -> Self {
+        self.encryption = Some(encryption);
+        self
+    }
+
+    /// Set the HDFS replication factor of every uploaded file.
+    pub fn with_replication(mut self, extension: WebHdfsExtension, replication: u16) -> Self {
+        self.replication = Some((extension, replication));
+        self
+    }
+
+    /// Encrypt every uploaded file with `key`.
+    pub fn with_data_key(mut self, key: DataKey) -> Self {
+        self.data_key = Some(key);
+        self
+    }
+
+    /// Stop the upload once `token` is cancelled.
+    pub fn with_cancel_token(mut self, token: CancelToken) -> Self {
+        self.cancel = token;
+        self
+    }
+
+    /// Apply the requested replication factor to a freshly written file.
+    async fn replicate(&self, remote_path: &str) -> Result<()> {
+        if let Some((extension, replication)) = &self.replication {
+            extension.set_replication(remote_path, *replication).await?;
+        }
+        Ok(())
+    }
+
+    /// Upload a single file, printing percentage progress when `show_progress` is set.
+    async fn upload_file_streaming(
+        &self,
+        local_path: &Path,
+        remote_path: &str,
+        throttle: FileThrottle,
+        show_progress: bool,
+        options: &UploadOptions,
+    ) -> Result<u64> {
+        let metadata = if options.preserve {
+            preserve::file_metadata(local_path)?
+        } else {
+            Vec::new()
+        };
         let file = fs::File::open(local_path).await?;
         let file_size = file.metadata().await?.len();
-        let mut reader = BufReader::new(file);
-        let mut buffer = vec![0u8; DEFAULT_BUFFER_SIZE];
-        let mut total_bytes = 0u64;
-        let mut writer = self.operator.writer(remote_path).await?;
-
-        let step_bytes = DEFAULT_BUFFER_SIZE as u64 * PROGRESS_UPDATE_INTERVAL;
         let reporter = ConsoleProgressReporter::new(
             format!("Uploading {}", local_path.display()),
-            Some(file_size),
-            step_bytes,
+            show_progress.then_some(file_size),
+            DEFAULT_BUFFER_SIZE as u64 * PROGRESS_UPDATE_INTERVAL,
+        );
+        let total_bytes = match options.compression {
+            // Progress then counts the compressed bytes sent, against the file's size.
+            Some(compression) => {
+                self.write_stream(
+                    compression.compress(BufReader::new(file))?,
+                    remote_path,
+                    metadata,
+                    Some(compression),
+                    throttle,
+                    &reporter,
+                )
+                .await?
+            }
+            None => {
+                self.write_stream(
+                    BufReader::new(file),
+                    remote_path,
+                    metadata,
+                    None,
+                    throttle,
+                    &reporter,
+                )
+                .await?
+            }
+        };
+        self.replicate(remote_path).await?;
+        if show_progress {
+            println!();
+        }
+        println!(
+            "✅ Upload: {} → {remote_path} ({total_bytes} bytes{})",
+            local_path.display(),
+            compressed_note(options.compression, file_size),
         );
+        self.report_encryption(options.verbose);
+        Ok(total_bytes)
+    }
 
-        loop {
-            let bytes_read = reader.read(&mut buffer).await?;
-            if bytes_read == 0 {
-                break;
+    /// Upload standard input to `remote_path` as it arrives, without knowing its size.
+    async fn upload_stdin(
+        &self,
+        remote_path: &str,
+        throttle: FileThrottle,
+        options: &UploadOptions,
+    ) -> Result<u64> {
+        if remote_path.is_empty() || remote_path.ends_with('/') {
+            return Err(Error::InvalidArgument {
+                message: format!(
+                    "uploading from stdin needs an object key, not a directory: '{remote_path}'"
+                ),
+            });
+        }
+        let reporter = ConsoleProgressReporter::new("Uploading stdin", None, 1);
+        let total_bytes = match options.compression {
+            Some(compression) => {
+                self.write_stream(
+                    compression.compress(tokio::io::stdin())?,
+                    remote_path,
+                    Vec::new(),
+                    Some(compression),
+                    throttle,
+                    &reporter,
+                )
+                .await?
+            }
+            None => {
+                self.write_stream(
+                    tokio::io::stdin(),
+                    remote_path,
+                    Vec::new(),
+                    None,
+                    throttle,
+                    &reporter,
+                )
+                .await?
             }
-            writer.write(buffer[..bytes_read].to_vec()).await?;
-            total_bytes += bytes_read as u64;
-            reporter.maybe_report(total_bytes);
+        };
+        self.replicate(remote_path).await?;
+        println!("✅ Upload: stdin → {remote_path} ({total_bytes} bytes)");
+        self.report_encryption(options.verbose);
+        Ok(total_bytes)
+    }
+
+    /// Copy `reader` into a new object chunk by chunk, with `metadata` as its user
+    /// metadata and the content encoding of `compression` where the backend stores one;
+    /// with a data key the content is encrypted on the way. Returns the bytes read.
+    async fn write_stream(
+        &self,
+        mut reader: impl AsyncRead + Unpin,
+        remote_path: &str,
+        metadata: Vec<(String, String)>,
+        compression: Option<Compression>,
+        throttle: FileThrottle,
+        reporter: &ConsoleProgressReporter,
+    ) -> Result<u64> {
+        let mut buffer = vec![0u8; DEFAULT_BUFFER_SIZE];
+        let mut total_bytes = 0u64;
+        let mut sealer = self.data_key.as_ref().map(Sealer::new);
+        let mut writer = self.operator.writer_with(remote_path);
+        if let Some(compression) = compression
+            && self.data_key.is_none()
+            && self
+                .operator
+                .info()
+                .full_capability()
+                .write_with_content_encoding
+        {
+            writer = writer.content_encoding(compression.content_encoding());
+        }
+        let mut writer = if metadata.is_empty() {
+            writer.await?
+        } else {
+            writer.user_metadata(metadata).await?
+        };
+
+        let copied: Result<()> = async {
+            loop {
+                self.cancel.check()?;
+                let bytes_read = reader.read(&mut buffer).await?;
+                if bytes_read == 0 {
+                    break;
+                }
+                throttle.acquire(bytes_read).await;
+                let chunk = &buffer[..bytes_read];
+                match sealer.as_mut() {
+                    Some(sealer) => writer.write(sealer.update(chunk)?).await?,
+                    None => writer.write(chunk.to_vec()).await?,
+                }
+                total_bytes += bytes_read as u64;
+                reporter.maybe_report(total_bytes);
+            }
+            if let Some(sealer) = sealer {
+                writer.write(sealer.finish()?).await?;
+            }
+            Ok(())
+        }
+        .await;
+        // A failed read or a cancelled job must not leave a shortened object behind. Backends that cannot
+        // abort (fs without an atomic write dir) have already written part of it.
+        if let Err(e) = copied {
+            if writer.abort().await.is_err() {
+                let _ = self.operator.delete(remote_path).await;
+            }
+            return Err(e);
         }
         writer.close().await?;
+        Ok(total_bytes)
+    }
+
+    /// Upload the symlink at `local_path` as a marker object holding its target, which
+    /// `get --preserve-links` turns back into a symlink.
+    async fn upload_link(&self, local_path: &Path, remote_path: &str) -> Result<u64> {
+        let target = fs::read_link(local_path)
+            .await?
+            .to_string_lossy()
+            .into_owned();
+        self.operator
+            .write_with(remote_path, target.clone().into_bytes())
+            .content_type("text/plain")
+            .user_metadata([(preserve::SYMLINK_METADATA_KEY.to_string(), target.clone())])
+            .await?;
         println!(
-            "\n✅ Upload: {} → {remote_path} ({total_bytes} bytes)",
-            local_path.display(),
+            "✅ Link: {} → {remote_path} (→ {target})",
+            local_path.display()
         );
-        Ok(())
+        Ok(target.len() as u64)
     }
 
-    /// Upload a directory recursively.
-    #[async_recursion]
-    async fn upload_recursive(&self, local_path: &str, remote_path: &str) -> Result<()> {
-        let mut entries = fs::read_dir(local_path).await?;
-        while let Some(entry) = entries.next_entry().await? {
-            let local_file_path = entry.path();
-            let file_name = local_file_path.file_name().unwrap_or_default();
-            let file_name_str = file_name.to_string_lossy();
-            let new_remote_path = build_remote_path(remote_path, &file_name_str);
+    /// Upload each file of the tar archive at `local_path` (`-` for stdin) below
+    /// `remote_path`, reading the archive once and never unpacking it to disk.
+    async fn upload_archive(
+        &self,
+        local_path: &str,
+        remote_path: &str,
+        options: &UploadOptions,
+    ) -> Result<TransferReport> {
+        let compression = match local_path {
+            STDIN_PATH => None,
+            local => archive::compression_for(local)?,
+        };
+        ensure!(
+            names_directory(remote_path) || !is_remote_file(&self.operator, remote_path).await,
+            DestinationIsFileSnafu {
+                path: remote_path.to_string()
+            }
+        );
+        if options.preserve {
+            preserve::ensure_supported(&self.operator, "--preserve")?;
+        }
+        let input: Box<dyn AsyncRead + Unpin + Send> = match local_path {
+            STDIN_PATH => Box::new(tokio::io::stdin()),
+            local => {
+                let path = Path::new(local);
+                ensure!(
+                    path.is_file(),
+                    PathNotFoundSnafu {
+                        path: path.to_path_buf()
+                    }
+                );
+                Box::new(fs::File::open(path).await?)
+            }
+        };
+        let input: Box<dyn AsyncRead + Unpin + Send> = match compression {
+            Some(compression) => Box::new(compression.decompress_reader(input)?),
+            None => input,
+        };
+        let remote_root = match remote_path {
+            "" => String::new(),
+            path => ensure_trailing_slash(path),
+        };
 
-            if local_file_path.is_dir() {
-                self.upload_recursive(&local_file_path.to_string_lossy(), &new_remote_path)
-                    .await?;
-            } else {
-                self.upload_file_streaming(&local_file_path, &new_remote_path)
-                    .await?;
+        let throttle = Throttle::new(options.per_file_limit, options.total_limit);
+        let reporter = ConsoleProgressReporter::new(String::new(), None, 1);
+        let mut archive = TarReader::new(BufReader::new(input));
+        let mut report = TransferReport {
+            directory: true,
+            ..Default::default()
+        };
+        let (mut planned_files, mut planned_bytes) = (0usize, 0u64);
+        while let Some(entry) = archive.next_entry().await? {
+            if entry.kind != EntryKind::File {
+                if entry.kind == EntryKind::Other {
+                    report.skipped += 1;
+                }
+                continue;
+            }
+            let Some(relative_path) = archive::safe_entry_path(&entry.path) else {
+                eprintln!("Skipping unsafe archive path: {}", entry.path);
+                report.skipped += 1;
+                continue;
+            };
+            let remote_file_path = format!("{remote_root}{relative_path}");
+            if options.dry_run {
+                println!(
+                    "Would upload: {local_path}:{} → {remote_file_path} ({} bytes)",
+                    entry.path, entry.size
+                );
+                planned_files += 1;
+                planned_bytes += entry.size;
+                continue;
+            }
+            let source = format!("{local_path}:{}", entry.path);
+            // The reader skips the body of an entry that is not read.
+            if !options
+                .clobber
+                .allows(&self.operator, &source, &remote_file_path)
+                .await?
+            {
+                report.skipped += 1;
+                continue;
+            }
+            let mut metadata = Vec::new();
+            if options.preserve {
+                metadata.push((
+                    preserve::MTIME_METADATA_KEY.to_string(),
+                    entry.mtime.to_string(),
+                ));
+                metadata.push((
+                    preserve::MODE_METADATA_KEY.to_string(),
+                    format!("{:o}", entry.mode & 0o7777),
+                ));
+            }
+            let written = self
+                .write_stream(
+                    archive.body(entry.size),
+                    &remote_file_path,
+                    metadata,
+                    None,
+                    throttle.for_file(),
+                    &reporter,
+                )
+                .await?;
+            self.replicate(&remote_file_path).await?;
+            println!(
+                "✅ Upload: {local_path}:{} → {remote_file_path} ({written} bytes)",
+                entry.path
+            );
+            self.report_encryption(options.verbose);
+            report.add_file(written);
+        }
+        if options.dry_run {
+            println!(
+                "Would upload {planned_files} file(s), {}",
+                format_size(planned_bytes)
+            );
+            return Ok(TransferReport::default());
+        }
+        Ok(report)
+    }
+
+    /// Print the files `upload_recursive` would transfer, for `--dry-run`.
+    fn print_plan(files: &[(PathBuf, String)], symlinks: SymlinkPolicy) -> Result<()> {
+        let mut total = 0u64;
+        for (local_file_path, remote_file_path) in files {
+            let is_link = std::fs::symlink_metadata(local_file_path)?
+                .file_type()
+                .is_symlink();
+            if is_link && symlinks == SymlinkPolicy::Preserve {
+                let target = std::fs::read_link(local_file_path)?;
+                println!(
+                    "Would link: {} → {remote_file_path} (→ {})",
+                    local_file_path.display(),
+                    target.display()
+                );
+                continue;
             }
+            let size = std::fs::metadata(local_file_path)?.len();
+            total += size;
+            println!(
+                "Would upload: {} → {remote_file_path} ({size} bytes)",
+                local_file_path.display()
+            );
         }
+        println!(
+            "Would upload {} file(s), {}",
+            files.len(),
+            format_size(total)
+        );
         Ok(())
     }
+
+    fn report_encryption(&self, verbose: bool) {
+        if verbose {
+            println!(
+                "   encryption: {}",
+                self.encryption.as_deref().unwrap_or("bucket default")
+            );
+        }
+    }
+
+    /// Upload one file of a recursive upload, or a link marker for a preserved link;
+    /// `None` when an existing object is kept.
+    async fn upload_entry(
+        &self,
+        local_path: &Path,
+        remote_path: &str,
+        throttle: FileThrottle,
+        show_progress: bool,
+        options: &UploadOptions,
+    ) -> Result<Option<u64>> {
+        self.cancel.check()?;
+        let source = local_path.to_string_lossy();
+        if !options
+            .clobber
+            .allows(&self.operator, &source, remote_path)
+            .await?
+        {
+            return Ok(None);
+        }
+        let is_link = fs::symlink_metadata(local_path)
+            .await
+            .is_ok_and(|meta| meta.file_type().is_symlink());
+        let bytes = if is_link && options.symlinks == SymlinkPolicy::Preserve {
+            self.upload_link(local_path, remote_path).await?
+        } else {
+            self.upload_file_streaming(local_path, remote_path, throttle, show_progress, options)
+                .await?
+        };
+        Ok(Some(bytes))
+    }
+
+    /// Upload a directory recursively, `options.concurrency` files at a time. A file that
+    /// fails is reported and counted in `report`; the others are still uploaded.
+    async fn upload_recursive(
+        &self,
+        files: Vec<(PathBuf, String)>,
+        options: &UploadOptions,
+        report: &mut TransferReport,
+    ) {
+        let throttle = Throttle::new(options.per_file_limit, options.total_limit);
+        let concurrency = options.concurrency.max(1);
+        let show_progress = concurrency == 1;
+        let mut results = stream::iter(files)
+            .map(|(local_file_path, remote_file_path)| {
+                let file_throttle = throttle.for_file();
+                async move {
+                    let result = self
+                        .upload_entry(
+                            &local_file_path,
+                            &remote_file_path,
+                            file_throttle,
+                            show_progress,
+                            options,
+                        )
+                        .await;
+                    (local_file_path, remote_file_path, result)
+                }
+            })
+            .buffer_unordered(concurrency);
+        while let Some((local_file_path, remote_file_path, result)) = results.next().await {
+            match result {
+                Ok(Some(bytes)) => report.add_file(bytes),
+                Ok(None) => report.skipped += 1,
+                // The job reports its cancellation once, not once per file it stopped.
+                Err(_) if self.cancel.is_cancelled() => {}
+                Err(e) => {
+                    eprintln!(
+                        "❌ Failed: {} → {remote_file_path}: {e}",
+                        local_file_path.display()
+                    );
+                    report.failed += 1;
+                }
+            }
+        }
+    }
 }
 
 impl Uploader for OpenDalUploader {
-    async fn upload(&self, local_path: &str, remote_path: &str, recursive: bool) -> Result<()> {
+    async fn upload(
+        &self,
+        local_path: &str,
+        remote_path: &str,
+        recursive: bool,
+        options: &UploadOptions,
+    ) -> Result<TransferReport> {
+        let started = Instant::now();
+        let mut report = TransferReport::default();
+        if options.extract {
+            let mut report = self
+                .upload_archive(local_path, remote_path, options)
+                .await?;
+            report.elapsed = started.elapsed();
+            return Ok(report);
+        }
+        if local_path == STDIN_PATH {
+            if options.dry_run {
+                println!("Would upload: stdin → {remote_path}");
+                return Ok(report);
+            }
+            let remote_path = match options.compression {
+                Some(compression) => compression.add_extension(remote_path),
+                None => remote_path.to_string(),
+            };
+            if !options
+                .clobber
+                .allows(&self.operator, "stdin", &remote_path)
+                .await?
+            {
+                report.skipped += 1;
+                return Ok(report);
+            }
+            let throttle = Throttle::new(options.per_file_limit, options.total_limit);
+            let bytes = self
+                .upload_stdin(&remote_path, throttle.for_file(), options)
+                .await?;
+            report.add_file(bytes);
+            report.elapsed = started.elapsed();
+            return Ok(report);
+        }
+
+        if options.preserve {
+            preserve::ensure_supported(&self.operator, "--preserve")?;
+        }
+        if options.symlinks == SymlinkPolicy::Preserve {
+            preserve::ensure_supported(&self.operator, "--preserve-links")?;
+        }
         let path = Path::new(local_path);
         ensure!(
             path.exists(),
@@ -101,19 +752,106 @@ impl Uploader for OpenDalUploader {
         );
 
         if path.is_file() {
-            let file_name = path.file_name().unwrap_or(OsStr::new(local_path));
-            let file_name_str = file_name.to_string_lossy();
-            let remote_file_path = build_remote_path(remote_path, &file_name_str);
-            self.upload_file_streaming(Path::new(local_path), &remote_file_path)
+            ensure!(
+                names_directory(remote_path)
+                    || !is_remote_directory(&self.operator, remote_path).await,
+                DestinationIsDirectorySnafu {
+                    path: remote_path.to_string()
+                }
+            );
+            let mut remote_file_path = transfer_target(local_path, remote_path);
+            if let Some(compression) = options.compression {
+                remote_file_path = compression.add_extension(&remote_file_path);
+            }
+            report_warnings(&remote_key_warnings(
+                self.operator.info().scheme(),
+                std::slice::from_ref(&remote_file_path),
+            ));
+            if options.dry_run {
+                Self::print_plan(
+                    &[(path.to_path_buf(), remote_file_path)],
+                    SymlinkPolicy::Follow,
+                )?;
+                return Ok(report);
+            }
+            if !options
+                .clobber
+                .allows(&self.operator, local_path, &remote_file_path)
+                .await?
+            {
+                report.skipped += 1;
+                report.elapsed = started.elapsed();
+                return Ok(report);
+            }
+            let throttle = Throttle::new(options.per_file_limit, options.total_limit);
+            let bytes = self
+                .upload_file_streaming(
+                    Path::new(local_path),
+                    &remote_file_path,
+                    throttle.for_file(),
+                    true,
+                    options,
+                )
                 .await?;
+            report.add_file(bytes);
         } else if path.is_dir() {
             if recursive {
-                self.upload_recursive(local_path, remote_path).await?;
+                ensure!(
+                    names_directory(remote_path)
+                        || !is_remote_file(&self.operator, remote_path).await,
+                    DestinationIsFileSnafu {
+                        path: remote_path.to_string()
+                    }
+                );
+                let remote_root = transfer_target(local_path, remote_path);
+                let ignore = IgnoreRules::load(path)?;
+                let mut walk = DirWalk {
+                    filter: &options.filter,
+                    ignore: ignore.as_ref(),
+                    symlinks: options.symlinks,
+                    ancestors: HashSet::new(),
+                    files: Vec::new(),
+                    skipped: 0,
+                    skipped_links: 0,
+                };
+                walk.walk(path, "", &remote_root);
+                if walk.skipped > 0 {
+                    println!(
+                        "Skipping {} file(s) excluded by filters or {IGNORE_FILE_NAME}",
+                        walk.skipped
+                    );
+                }
+                if walk.skipped_links > 0 {
+                    println!(
+                        "Skipping {} symlink(s) (--no-follow-symlinks)",
+                        walk.skipped_links
+                    );
+                }
+                let mut files = walk.files;
+                if let Some(compression) = options.compression {
+                    for (local_file_path, key) in &mut files {
+                        // Link markers are stored as they are.
+                        let is_link = local_file_path.is_symlink();
+                        if !(is_link && options.symlinks == SymlinkPolicy::Preserve) {
+                            *key = compression.add_extension(key);
+                        }
+                    }
+                }
+                let keys: Vec<String> = files.iter().map(|(_, key)| key.clone()).collect();
+                report_warnings(&remote_key_warnings(self.operator.info().scheme(), &keys));
+                if options.dry_run {
+                    Self::print_plan(&files, options.symlinks)?;
+                    return Ok(report);
+                }
+                report.directory = true;
+                report.skipped = walk.skipped + walk.skipped_links;
+                self.upload_recursive(files, options, &mut report).await;
             } else {
                 return DirectoryUploadNotRecursiveSnafu.fail();
             }
         }
 
-        Ok(())
+        report.elapsed = started.elapsed();
+        Ok(report)
     }
 }
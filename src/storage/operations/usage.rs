@@ -1,6 +1,170 @@
 use crate::error::Result;
+use crate::storage::utils::format::{Fields, Template, csv_field};
+use crate::storage::utils::paging::{PageSize, list_entries_parallel};
+use crate::storage::utils::size::format_size;
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
 use futures::stream::TryStreamExt;
-use opendal::Operator;
+use opendal::{EntryMode, Operator};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Ordering of `du` output lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum UsageSort {
+    /// Largest first
+    Size,
+    /// Lexicographic path order
+    Name,
+}
+
+/// What `du --report` totals objects by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum UsageGroup {
+    /// First directory below the path, e.g. one row per team folder
+    TopLevel,
+    /// File extension, lowercased
+    Extension,
+}
+
+/// How `du --report` prints its rows.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ReportOutput {
+    /// Aligned columns (size, objects, percent, group) followed by a total line
+    #[default]
+    Table,
+    /// `prefix,objects,bytes,percent` with a header row
+    Csv,
+}
+
+/// Options controlling how disk usage is aggregated and printed.
+#[derive(Debug, Clone, Default)]
+pub struct UsageOptions {
+    /// Print only the total for the path
+    pub summary: bool,
+    /// Print sizes as 1.5K/2.0M instead of bytes
+    pub human_readable: bool,
+    /// Print directory totals down to this depth instead of every file
+    pub max_depth: Option<usize>,
+    /// Sort output lines; listing order when unset
+    pub sort: Option<UsageSort>,
+    /// Print each line through this template instead of `SIZE PATH`
+    pub format: Option<Template>,
+    /// Print per-group object counts, sizes and shares instead of per-path lines
+    pub report: Option<UsageGroup>,
+    /// Layout of the report
+    pub output: ReportOutput,
+    /// Top-level directories listed at once
+    pub jobs: usize,
+}
+
+/// One row of `du --report`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct GroupTotal {
+    key: String,
+    objects: usize,
+    bytes: u64,
+}
+
+/// One line of `du` output: a file, a directory total or the grand total.
+#[derive(Debug, Clone, Copy)]
+pub struct UsageLine<'a> {
+    pub path: &'a str,
+    pub size: u64,
+}
+
+impl Fields for UsageLine<'_> {
+    const NAMES: &'static [&'static str] = &["path", "size", "human_size"];
+
+    fn field(&self, name: &str) -> Option<String> {
+        match name {
+            "path" => Some(self.path.to_string()),
+            "size" => Some(self.size.to_string()),
+            "human_size" => Some(format_size(self.size)),
+            _ => None,
+        }
+    }
+}
+
+/// A file or directory found below the root of a `du` or `stat` walk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UsageEntry {
+    pub path: String,
+    pub size: u64,
+    pub is_dir: bool,
+    pub last_modified: Option<DateTime<Utc>>,
+}
+
+/// Totals that `stat` prints for a directory.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DirectorySummary {
+    /// Files below the directory
+    pub files: usize,
+    /// Subdirectories below the directory
+    pub directories: usize,
+    /// Summed size of the files
+    pub bytes: u64,
+    /// Latest modification time among the files
+    pub newest: Option<DateTime<Utc>>,
+    /// Earliest modification time among the files
+    pub oldest: Option<DateTime<Utc>>,
+    /// Level of the most deeply nested entry; direct children are level 1
+    pub depth: usize,
+    /// Only direct children were counted (`--shallow`)
+    pub shallow: bool,
+}
+
+impl DirectorySummary {
+    /// Aggregate the entries listed below `root`.
+    pub fn from_entries(root: &str, entries: &[UsageEntry], shallow: bool) -> Self {
+        let base = directory_base(root);
+        let mut summary = Self {
+            shallow,
+            ..Default::default()
+        };
+        for entry in entries {
+            let Some(relative) = entry.path.strip_prefix(&base) else {
+                continue;
+            };
+            let relative = relative.trim_end_matches('/');
+            if relative.is_empty() {
+                continue;
+            }
+            summary.depth = summary.depth.max(relative.split('/').count());
+            if entry.is_dir {
+                summary.directories += 1;
+                continue;
+            }
+            summary.files += 1;
+            summary.bytes += entry.size;
+            if let Some(modified) = entry.last_modified {
+                summary.newest = summary.newest.max(Some(modified));
+                summary.oldest = Some(summary.oldest.map_or(modified, |t| t.min(modified)));
+            }
+        }
+        summary
+    }
+}
+
+/// `key=value` lines appended to `stat` output.
+impl fmt::Display for DirectorySummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "files={}", self.files)?;
+        writeln!(f, "directories={}", self.directories)?;
+        writeln!(f, "total_size={}", self.bytes)?;
+        if let Some(t) = self.newest {
+            writeln!(f, "newest_modified={t}")?;
+        }
+        if let Some(t) = self.oldest {
+            writeln!(f, "oldest_modified={t}")?;
+        }
+        write!(f, "max_depth={}", self.depth)?;
+        if self.shallow {
+            write!(f, "\nshallow=true")?;
+        }
+        Ok(())
+    }
+}
 
 /// Trait for calculating disk usage in storage.
 pub trait UsageCalculator {
@@ -8,49 +172,328 @@ pub trait UsageCalculator {
     ///
     /// # Arguments
     /// * `path` - Path to calculate usage for
-    /// * `summary` - Whether to show summary only or detailed listing
+    /// * `options` - Summary, depth, sorting and size formatting
     ///
     /// # Returns
     /// * `Result<()>` - Success or detailed error information
-    async fn calculate_usage(&self, path: &str, summary: bool) -> Result<()>;
+    async fn calculate_usage(&self, path: &str, options: UsageOptions) -> Result<()>;
 }
 
 /// Implementation of UsageCalculator for OpenDAL Operator.
 pub struct OpenDalUsageCalculator {
     operator: Operator,
+    page_size: PageSize,
 }
 
 impl OpenDalUsageCalculator {
     /// Create a new usage calculator with the given OpenDAL operator.
     pub fn new(operator: Operator) -> Self {
-        Self { operator }
+        Self {
+            operator,
+            page_size: PageSize::Auto,
+        }
+    }
+
+    /// Page size of the listing requests.
+    pub fn with_page_size(mut self, page_size: PageSize) -> Self {
+        self.page_size = page_size;
+        self
+    }
+
+    /// Totals of the directory `path`, walking all levels unless `shallow`.
+    pub async fn summarize(&self, path: &str, shallow: bool) -> Result<DirectorySummary> {
+        let entries = self.entries(path, !shallow, 1).await?;
+        Ok(DirectorySummary::from_entries(path, &entries, shallow))
+    }
+
+    /// List `path` with sizes and modification times, stat-ing files listed without them.
+    async fn entries(&self, path: &str, recursive: bool, jobs: usize) -> Result<Vec<UsageEntry>> {
+        let mut lister =
+            list_entries_parallel(&self.operator, path, recursive, self.page_size, jobs).await?;
+        let mut entries = Vec::new();
+        while let Some(entry) = lister.try_next().await? {
+            let is_dir = entry.metadata().mode() == EntryMode::DIR;
+            let meta = if !is_dir && entry.metadata().last_modified().is_none() {
+                self.operator.stat(entry.path()).await?
+            } else {
+                entry.metadata().clone()
+            };
+            entries.push(UsageEntry {
+                path: entry.path().to_string(),
+                size: meta.content_length(),
+                is_dir,
+                last_modified: meta.last_modified(),
+            });
+        }
+        Ok(entries)
     }
 }
 
 impl UsageCalculator for OpenDalUsageCalculator {
-    async fn calculate_usage(&self, path: &str, summary: bool) -> Result<()> {
-        let lister = self.operator.lister_with(path).recursive(true).await?;
-        let (total_size, total_files) = lister
-            .try_fold((0, 0), |(size, count), entry| async move {
-                let meta = entry.metadata();
-                if !summary {
-                    println!(
-                        "{} {}",
-                        crate::storage::utils::size::format_size(meta.content_length()),
-                        entry.path()
-                    );
-                }
-                Ok((size + meta.content_length(), count + 1))
-            })
-            .await?;
+    async fn calculate_usage(&self, path: &str, options: UsageOptions) -> Result<()> {
+        let print = |line_path: &str, size: u64| match &options.format {
+            Some(format) => println!(
+                "{}",
+                format.render(&UsageLine {
+                    path: line_path,
+                    size
+                })
+            ),
+            None if options.human_readable => println!("{} {line_path}", format_size(size)),
+            None => println!("{size} {line_path}"),
+        };
+
+        let entries = self.entries(path, true, options.jobs).await?;
+        let total_size: u64 = entries.iter().map(|entry| entry.size).sum();
+        let total_files = entries.len();
 
-        if summary {
-            println!(
-                "{} {path}",
-                crate::storage::utils::size::format_size(total_size)
-            );
-            println!("Total files: {total_files}");
+        if let Some(group) = options.report {
+            let mut rows = group_totals(path, &entries, group);
+            match options.sort {
+                Some(UsageSort::Name) => rows.sort_by(|a, b| a.key.cmp(&b.key)),
+                _ => rows.sort_by(|a, b| b.bytes.cmp(&a.bytes).then(a.key.cmp(&b.key))),
+            }
+            print_report(&rows, &options);
+            return Ok(());
+        }
+
+        if options.summary {
+            print(path, total_size);
+            if options.format.is_none() {
+                println!("Total files: {total_files}");
+            }
+            return Ok(());
+        }
+
+        let mut lines: Vec<(String, u64)> = match options.max_depth {
+            Some(depth) => directory_totals(path, &entries, depth)
+                .into_iter()
+                .collect(),
+            None => entries
+                .into_iter()
+                .map(|entry| (entry.path, entry.size))
+                .collect(),
+        };
+        match options.sort {
+            Some(UsageSort::Size) => lines.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0))),
+            Some(UsageSort::Name) => lines.sort_by(|a, b| a.0.cmp(&b.0)),
+            None => {}
+        }
+        for (line_path, size) in &lines {
+            print(line_path, *size);
+        }
+        if options.max_depth.is_some() {
+            print(path, total_size);
         }
         Ok(())
     }
 }
+
+/// Count and sum the files below `root` per group. Files directly under `root` form the
+/// top-level group named after `root` itself.
+fn group_totals(root: &str, entries: &[UsageEntry], group: UsageGroup) -> Vec<GroupTotal> {
+    let base = directory_base(root);
+    let mut totals: BTreeMap<String, (usize, u64)> = BTreeMap::new();
+    for entry in entries.iter().filter(|entry| !entry.is_dir) {
+        let Some(relative) = entry.path.strip_prefix(&base) else {
+            continue;
+        };
+        let key = match group {
+            UsageGroup::TopLevel => match relative.split_once('/') {
+                Some((dir, _)) => format!("{base}{dir}/"),
+                None => base.clone(),
+            },
+            UsageGroup::Extension => {
+                let name = relative.rsplit('/').next().unwrap_or(relative);
+                match name.rsplit_once('.') {
+                    Some((stem, ext)) if !stem.is_empty() && !ext.is_empty() => {
+                        format!(".{}", ext.to_lowercase())
+                    }
+                    _ => "(none)".to_string(),
+                }
+            }
+        };
+        let total = totals.entry(key).or_default();
+        total.0 += 1;
+        total.1 += entry.size;
+    }
+    totals
+        .into_iter()
+        .map(|(key, (objects, bytes))| GroupTotal {
+            key,
+            objects,
+            bytes,
+        })
+        .collect()
+}
+
+/// Share of `bytes` in `total`, in percent with one decimal.
+fn percent(bytes: u64, total: u64) -> String {
+    if total == 0 {
+        "0.0".to_string()
+    } else {
+        format!("{:.1}", bytes as f64 * 100.0 / total as f64)
+    }
+}
+
+fn print_report(rows: &[GroupTotal], options: &UsageOptions) {
+    let objects: usize = rows.iter().map(|row| row.objects).sum();
+    let bytes: u64 = rows.iter().map(|row| row.bytes).sum();
+    match options.output {
+        ReportOutput::Csv => {
+            println!("prefix,objects,bytes,percent");
+            for row in rows {
+                println!(
+                    "{},{},{},{}",
+                    csv_field(&row.key),
+                    row.objects,
+                    row.bytes,
+                    percent(row.bytes, bytes)
+                );
+            }
+        }
+        ReportOutput::Table => {
+            let size = |bytes: u64| {
+                if options.human_readable {
+                    format_size(bytes)
+                } else {
+                    bytes.to_string()
+                }
+            };
+            for row in rows {
+                println!(
+                    "{:>12} {:>8} {:>6}% {}",
+                    size(row.bytes),
+                    row.objects,
+                    percent(row.bytes, bytes),
+                    row.key
+                );
+            }
+            println!("{:>12} {objects:>8} {:>6}% total", size(bytes), "100.0");
+        }
+    }
+}
+
+/// Sum file sizes into their ancestor directories below `root`, down to `max_depth` levels.
+fn directory_totals(root: &str, entries: &[UsageEntry], max_depth: usize) -> BTreeMap<String, u64> {
+    let base = directory_base(root);
+    let mut totals = BTreeMap::new();
+    for entry in entries {
+        let Some(relative) = entry.path.strip_prefix(&base) else {
+            continue;
+        };
+        let mut dirs: Vec<&str> = relative.split('/').collect();
+        if !entry.is_dir {
+            // The last component is the file name itself.
+            dirs.pop();
+        }
+        let dirs: Vec<&str> = dirs.into_iter().filter(|dir| !dir.is_empty()).collect();
+        let mut prefix = base.clone();
+        for dir in dirs.iter().take(max_depth) {
+            prefix.push_str(dir);
+            prefix.push('/');
+            *totals.entry(prefix.clone()).or_insert(0) += entry.size;
+        }
+    }
+    totals
+}
+
+/// `root` with the trailing slash that prefixes the paths listed below it.
+fn directory_base(root: &str) -> String {
+    if root.is_empty() || root.ends_with('/') {
+        root.to_string()
+    } else {
+        format!("{root}/")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str, size: u64, modified: Option<i64>) -> UsageEntry {
+        UsageEntry {
+            path: path.to_string(),
+            size,
+            is_dir: path.ends_with('/'),
+            last_modified: modified.and_then(|secs| DateTime::from_timestamp(secs, 0)),
+        }
+    }
+
+    fn sample_entries() -> Vec<UsageEntry> {
+        vec![
+            entry("logs/", 0, None),
+            entry("logs/a/", 0, None),
+            entry("logs/a/1.log", 10, Some(300)),
+            entry("logs/a/b/2.log", 5, Some(100)),
+            entry("logs/c/3.log", 7, None),
+            entry("logs/top.log", 100, Some(200)),
+        ]
+    }
+
+    #[test]
+    fn directory_totals_stop_at_max_depth() {
+        let entries = sample_entries();
+
+        let totals = directory_totals("logs", &entries, 1);
+        assert_eq!(
+            totals.into_iter().collect::<Vec<_>>(),
+            vec![("logs/a/".to_string(), 15), ("logs/c/".to_string(), 7)]
+        );
+
+        let totals = directory_totals("logs/", &entries, 2);
+        assert_eq!(totals.get("logs/a/b/"), Some(&5));
+        assert_eq!(totals.get("logs/a/"), Some(&15));
+        assert!(directory_totals("logs/", &entries, 0).is_empty());
+    }
+
+    #[test]
+    fn summary_counts_sizes_times_and_depth() {
+        let summary = DirectorySummary::from_entries("logs", &sample_entries(), false);
+        assert_eq!(
+            summary,
+            DirectorySummary {
+                files: 4,
+                directories: 1,
+                bytes: 122,
+                newest: DateTime::from_timestamp(300, 0),
+                oldest: DateTime::from_timestamp(100, 0),
+                depth: 3,
+                shallow: false,
+            }
+        );
+        assert_eq!(
+            DirectorySummary::from_entries("logs/", &[], true),
+            DirectorySummary {
+                shallow: true,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn group_totals_by_top_level_and_extension() {
+        let entries = sample_entries();
+        let rows = group_totals("logs", &entries, UsageGroup::TopLevel);
+        let row = |key: &str, objects, bytes| GroupTotal {
+            key: key.to_string(),
+            objects,
+            bytes,
+        };
+        assert_eq!(
+            rows,
+            vec![
+                row("logs/", 1, 100),
+                row("logs/a/", 2, 15),
+                row("logs/c/", 1, 7)
+            ]
+        );
+        assert_eq!(
+            group_totals("logs/", &entries, UsageGroup::Extension),
+            vec![row(".log", 4, 122)]
+        );
+        assert_eq!(percent(15, 122), "12.3");
+        assert_eq!(percent(0, 0), "0.0");
+        assert_eq!(csv_field("team,a/"), "\"team,a/\"");
+    }
+}
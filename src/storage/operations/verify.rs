@@ -0,0 +1,318 @@
+//! `storify verify`: check a local directory against a remote prefix by comparing MD5
+//! hashes with the checksums the store keeps, without downloading anything.
+
+use crate::error::{Error, Result};
+use crate::storage::constants::{DEFAULT_CHUNK_SIZE, DEFAULT_STAT_CONCURRENCY};
+use crate::storage::utils::paging::{PageSize, list_entries};
+use crate::storage::utils::path::{ensure_trailing_slash, is_root_path};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64_ENGINE;
+use futures::stream::{self, StreamExt, TryStreamExt};
+use md5::{Digest, Md5};
+use opendal::{Metadata, Operator};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use tokio::io::AsyncReadExt;
+
+/// MD5 of an object's content in lowercase hex, when the store reports one.
+///
+/// S3-compatible stores use the MD5 as the etag of single-part uploads; multipart etags
+/// (`<hex>-<parts>`) and opaque etags carry none. A Content-MD5 header is base64.
+pub fn content_md5(meta: &Metadata) -> Option<String> {
+    meta.content_md5()
+        .and_then(|md5| hex_md5(md5).or_else(|| base64_md5(md5)))
+        .or_else(|| meta.etag().and_then(hex_md5))
+}
+
+fn hex_md5(value: &str) -> Option<String> {
+    let value = value.trim_start_matches("W/").trim_matches('"');
+    (value.len() == 32 && value.bytes().all(|b| b.is_ascii_hexdigit()))
+        .then(|| value.to_ascii_lowercase())
+}
+
+fn base64_md5(value: &str) -> Option<String> {
+    let bytes = BASE64_ENGINE.decode(value.trim()).ok()?;
+    (bytes.len() == 16).then(|| bytes.iter().map(|b| format!("{b:02x}")).collect())
+}
+
+/// MD5 of a local file in lowercase hex, read in chunks.
+async fn file_md5(path: &Path) -> Result<String> {
+    let mut file = tokio::fs::File::open(path).await?;
+    let mut hasher = Md5::new();
+    let mut buf = vec![0; DEFAULT_CHUNK_SIZE];
+    loop {
+        let n = file.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Counts printed at the end of `verify`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct VerifySummary {
+    /// Same size and MD5 on both sides
+    pub verified: usize,
+    /// Size or MD5 differs
+    pub mismatched: usize,
+    /// No object for the local file
+    pub missing: usize,
+    /// Same size, but the store keeps no MD5 to compare (multipart uploads, fs)
+    pub unchecked: usize,
+}
+
+impl VerifySummary {
+    /// Fail when any local file is missing or differs remotely.
+    pub fn ensure_verified(&self) -> Result<()> {
+        let failed = self.mismatched + self.missing;
+        if failed == 0 {
+            Ok(())
+        } else {
+            Err(Error::VerificationFailed {
+                failed,
+                total: failed + self.verified + self.unchecked,
+            })
+        }
+    }
+}
+
+impl fmt::Display for VerifySummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} verified, {} mismatched, {} missing, {} without checksum",
+            self.verified, self.mismatched, self.missing, self.unchecked
+        )
+    }
+}
+
+/// Outcome for one local file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Check {
+    Verified,
+    Mismatched(String),
+    Missing,
+    Unchecked,
+}
+
+/// Trait for checking local files against the objects they were uploaded to.
+pub trait Verifier {
+    /// Compare every file below `local_dir` with the object at the same relative path
+    /// under `remote_prefix`, print the ones that do not match, then a summary.
+    ///
+    /// Files are found as `put -R` finds them: `.storifyignore` applies and symlinks are
+    /// followed. Objects without a local file are not reported.
+    async fn verify(&self, local_dir: &Path, remote_prefix: &str) -> Result<VerifySummary>;
+}
+
+pub struct OpenDalVerifier {
+    operator: Operator,
+    page_size: PageSize,
+}
+
+impl OpenDalVerifier {
+    pub fn new(operator: Operator) -> Self {
+        Self {
+            operator,
+            page_size: PageSize::Auto,
+        }
+    }
+
+    /// Page size of the listing requests.
+    pub fn with_page_size(mut self, page_size: PageSize) -> Self {
+        self.page_size = page_size;
+        self
+    }
+
+    /// Metadata of the objects under `prefix`, keyed by their path relative to it.
+    async fn list_objects(&self, prefix: &str) -> Result<HashMap<String, Metadata>> {
+        let mut entries = list_entries(&self.operator, prefix, true, self.page_size).await?;
+        let mut objects = HashMap::new();
+        while let Some(entry) = entries.try_next().await? {
+            if entry.metadata().mode().is_dir() {
+                continue;
+            }
+            let base = if is_root_path(prefix) { "" } else { prefix };
+            let Some(rel) = entry.path().strip_prefix(base) else {
+                continue;
+            };
+            // Listings without metadata (fs) carry no sizes; stat those entries.
+            let meta = match entry.metadata().last_modified() {
+                Some(_) => entry.metadata().clone(),
+                None => self.operator.stat(entry.path()).await?,
+            };
+            objects.insert(rel.to_string(), meta);
+        }
+        Ok(objects)
+    }
+}
+
+/// What a one-way sync of a local directory to a prefix has to do.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncPlan {
+    /// Local files missing remotely or differing from their object, with relative paths
+    pub stale: Vec<(PathBuf, String)>,
+    /// Files whose object matches
+    pub unchanged: usize,
+    /// Relative paths of objects with no local file
+    pub orphaned: Vec<String>,
+}
+
+impl OpenDalVerifier {
+    /// Compare `local_dir` with `remote_prefix` by the checks of [`Verifier::verify`].
+    /// Files that can only be matched by size count as unchanged; objects whose file is
+    /// present but left out by `.storifyignore` are not orphaned.
+    pub async fn sync_plan(&self, local_dir: &Path, remote_prefix: &str) -> Result<SyncPlan> {
+        if !local_dir.is_dir() {
+            return Err(Error::InvalidArgument {
+                message: format!("{} is not a directory", local_dir.display()),
+            });
+        }
+        let prefix = ensure_trailing_slash(remote_prefix);
+        let objects = self.list_objects(&prefix).await?;
+        let files = super::upload::local_files(local_dir)?;
+        let objects_ref = &objects;
+        let checked: Vec<_> = stream::iter(files)
+            .map(|(local, rel)| async move {
+                let result = check(&local, objects_ref.get(&rel)).await;
+                (local, rel, result)
+            })
+            .buffered(DEFAULT_STAT_CONCURRENCY)
+            .collect()
+            .await;
+
+        let mut plan = SyncPlan::default();
+        for (local, rel, result) in checked {
+            match result? {
+                Check::Missing | Check::Mismatched(_) => plan.stale.push((local, rel)),
+                Check::Verified | Check::Unchecked => plan.unchanged += 1,
+            }
+        }
+        plan.orphaned = objects
+            .into_keys()
+            .filter(|rel| !local_dir.join(rel).exists())
+            .collect();
+        plan.stale.sort_by(|a, b| a.1.cmp(&b.1));
+        plan.orphaned.sort();
+        Ok(plan)
+    }
+}
+
+/// Compare one local file with its object, hashing only when there is an MD5 to match.
+async fn check(local: &Path, remote: Option<&Metadata>) -> Result<Check> {
+    let Some(remote) = remote else {
+        return Ok(Check::Missing);
+    };
+    let size = tokio::fs::metadata(local).await?.len();
+    if size != remote.content_length() {
+        return Ok(Check::Mismatched(format!(
+            "size {size} → {}",
+            remote.content_length()
+        )));
+    }
+    let Some(expected) = content_md5(remote) else {
+        return Ok(Check::Unchecked);
+    };
+    let actual = file_md5(local).await?;
+    Ok(if actual == expected {
+        Check::Verified
+    } else {
+        Check::Mismatched(format!("md5 {actual} → {expected}"))
+    })
+}
+
+impl Verifier for OpenDalVerifier {
+    async fn verify(&self, local_dir: &Path, remote_prefix: &str) -> Result<VerifySummary> {
+        if !local_dir.is_dir() {
+            return Err(Error::InvalidArgument {
+                message: format!("{} is not a directory", local_dir.display()),
+            });
+        }
+        let prefix = ensure_trailing_slash(remote_prefix);
+        let objects = self.list_objects(&prefix).await?;
+        let mut files = super::upload::local_files(local_dir)?;
+        files.sort_by(|a, b| a.1.cmp(&b.1));
+
+        let objects = &objects;
+        let mut checks = stream::iter(files)
+            .map(|(local, rel)| async move {
+                let result = check(&local, objects.get(&rel)).await;
+                (rel, result)
+            })
+            .buffered(DEFAULT_STAT_CONCURRENCY);
+
+        let mut summary = VerifySummary::default();
+        while let Some((rel, result)) = checks.next().await {
+            match result? {
+                Check::Verified => summary.verified += 1,
+                Check::Mismatched(reason) => {
+                    summary.mismatched += 1;
+                    println!("M {rel} ({reason})");
+                }
+                Check::Missing => {
+                    summary.missing += 1;
+                    println!("- {rel} (missing remotely)");
+                }
+                Check::Unchecked => {
+                    summary.unchecked += 1;
+                    println!("? {rel} (no MD5 checksum; size matches)");
+                }
+            }
+        }
+        println!("{summary}");
+        Ok(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn content_md5_reads_hex_etags_and_base64_headers() {
+        let md5 = "900150983cd24fb0d6963f7d28e17f72";
+        let meta = Metadata::default().with_etag(format!("\"{}\"", md5.to_uppercase()));
+        assert_eq!(content_md5(&meta).as_deref(), Some(md5));
+
+        let meta = Metadata::default().with_content_md5("kAFQmDzST7DWlj99KOF/cg==".to_string());
+        assert_eq!(content_md5(&meta).as_deref(), Some(md5));
+
+        let multipart = Metadata::default().with_etag(format!("\"{md5}-3\""));
+        assert_eq!(content_md5(&multipart), None);
+        assert_eq!(content_md5(&Metadata::default()), None);
+    }
+
+    #[tokio::test]
+    async fn checks_size_then_md5() {
+        let dir = tempfile::tempdir().unwrap();
+        let local = dir.path().join("abc");
+        std::fs::write(&local, b"abc").unwrap();
+        let object = |etag: &str| {
+            Metadata::new(opendal::EntryMode::FILE)
+                .with_content_length(3)
+                .with_etag(etag.to_string())
+        };
+
+        let good = object("\"900150983cd24fb0d6963f7d28e17f72\"");
+        assert_eq!(check(&local, Some(&good)).await.unwrap(), Check::Verified);
+        let bad = object("\"00000000000000000000000000000000\"");
+        assert!(matches!(
+            check(&local, Some(&bad)).await.unwrap(),
+            Check::Mismatched(_)
+        ));
+        let opaque = object("\"abc-2\"");
+        assert_eq!(
+            check(&local, Some(&opaque)).await.unwrap(),
+            Check::Unchecked
+        );
+        let short = object("x").with_content_length(2);
+        assert_eq!(
+            check(&local, Some(&short)).await.unwrap(),
+            Check::Mismatched("size 3 → 2".to_string())
+        );
+        assert_eq!(check(&local, None).await.unwrap(), Check::Missing);
+    }
+}
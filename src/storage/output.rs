@@ -0,0 +1,313 @@
+//! Versioned JSON output contracts.
+//!
+//! Every JSON document storify prints is serialized from a struct in this module and
+//! carries a `schema_version`. The version of a command is bumped when one of its fields
+//! is removed, renamed or changes type; adding a field keeps it. `storify schema <command>`
+//! prints the JSON Schema of the current version so tools can validate what they parse.
+
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::{Value, json};
+
+use super::operations::stat::ObjectMeta;
+use super::operations::usage::DirectorySummary;
+
+/// Version of the `stat --json` object.
+pub const STAT_SCHEMA_VERSION: u32 = 1;
+/// Version of the `tree --format json` document.
+pub const TREE_SCHEMA_VERSION: u32 = 1;
+
+const SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// Commands with JSON output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SchemaCommand {
+    /// `storify stat --json`
+    Stat,
+    /// `storify tree --format json`
+    Tree,
+}
+
+impl SchemaCommand {
+    /// JSON Schema (draft 2020-12) of the command's output.
+    pub fn schema(self) -> Value {
+        match self {
+            SchemaCommand::Stat => stat_schema(),
+            SchemaCommand::Tree => tree_schema(),
+        }
+    }
+}
+
+/// Object printed by `stat --json`.
+#[derive(Debug, Serialize)]
+pub struct StatOutput {
+    pub schema_version: u32,
+    pub path: String,
+    pub entry_type: String,
+    pub size: u64,
+    pub last_modified: Option<String>,
+    pub etag: Option<String>,
+    pub content_type: Option<String>,
+    pub version_id: Option<String>,
+    pub storage_class: Option<String>,
+    pub link_target: Option<String>,
+    pub owner: Option<String>,
+    pub group: Option<String>,
+    pub replication: Option<u16>,
+    pub directory: Option<DirectoryOutput>,
+}
+
+/// Totals nested in `stat --json` output for directories.
+#[derive(Debug, Serialize)]
+pub struct DirectoryOutput {
+    pub files: usize,
+    pub directories: usize,
+    pub total_size: u64,
+    pub newest_modified: Option<String>,
+    pub oldest_modified: Option<String>,
+    pub max_depth: usize,
+    pub shallow: bool,
+}
+
+impl From<DirectorySummary> for DirectoryOutput {
+    fn from(summary: DirectorySummary) -> Self {
+        Self {
+            files: summary.files,
+            directories: summary.directories,
+            total_size: summary.bytes,
+            newest_modified: summary.newest.map(|t| t.to_string()),
+            oldest_modified: summary.oldest.map(|t| t.to_string()),
+            max_depth: summary.depth,
+            shallow: summary.shallow,
+        }
+    }
+}
+
+impl From<ObjectMeta> for StatOutput {
+    fn from(meta: ObjectMeta) -> Self {
+        Self {
+            schema_version: STAT_SCHEMA_VERSION,
+            path: meta.path,
+            entry_type: meta.entry_type,
+            size: meta.size,
+            last_modified: meta.last_modified,
+            etag: meta.etag,
+            content_type: meta.content_type,
+            version_id: meta.version,
+            storage_class: meta.storage_class,
+            link_target: meta.link_target,
+            owner: meta.owner,
+            group: meta.group,
+            replication: meta.replication,
+            directory: meta.directory.map(DirectoryOutput::from),
+        }
+    }
+}
+
+/// Entry of `tree --format json`, nested in the layout of `tree -J`. Sizes are only
+/// present with `--size`.
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum TreeEntry {
+    Directory {
+        name: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        size: Option<u64>,
+        contents: Vec<TreeEntry>,
+    },
+    File {
+        name: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        size: Option<u64>,
+    },
+}
+
+/// Trailing element of `tree --format json`, after the root directory.
+#[derive(Debug, Serialize)]
+pub struct TreeReport {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    pub schema_version: u32,
+    pub directories: usize,
+    pub files: usize,
+}
+
+impl TreeReport {
+    pub fn new(directories: usize, files: usize) -> Self {
+        Self {
+            kind: "report",
+            schema_version: TREE_SCHEMA_VERSION,
+            directories,
+            files,
+        }
+    }
+}
+
+fn nullable_string() -> Value {
+    json!({ "type": ["string", "null"] })
+}
+
+fn stat_schema() -> Value {
+    json!({
+        "$schema": SCHEMA_DIALECT,
+        "title": "storify stat --json",
+        "type": "object",
+        "properties": {
+            "schema_version": { "const": STAT_SCHEMA_VERSION },
+            "path": { "type": "string" },
+            "entry_type": { "enum": ["file", "dir", "other"] },
+            "size": { "type": "integer", "minimum": 0 },
+            "last_modified": nullable_string(),
+            "etag": nullable_string(),
+            "content_type": nullable_string(),
+            "version_id": nullable_string(),
+            "storage_class": nullable_string(),
+            "link_target": nullable_string(),
+            "owner": nullable_string(),
+            "group": nullable_string(),
+            "replication": { "type": ["integer", "null"], "minimum": 0 },
+            "directory": {
+                "type": ["object", "null"],
+                "properties": {
+                    "files": { "type": "integer", "minimum": 0 },
+                    "directories": { "type": "integer", "minimum": 0 },
+                    "total_size": { "type": "integer", "minimum": 0 },
+                    "newest_modified": nullable_string(),
+                    "oldest_modified": nullable_string(),
+                    "max_depth": { "type": "integer", "minimum": 0 },
+                    "shallow": { "type": "boolean" }
+                },
+                "required": [
+                    "files", "directories", "total_size", "newest_modified",
+                    "oldest_modified", "max_depth", "shallow"
+                ]
+            },
+        },
+        "required": [
+            "schema_version", "path", "entry_type", "size", "last_modified", "etag",
+            "content_type", "version_id", "storage_class", "link_target", "owner", "group",
+            "replication", "directory"
+        ],
+    })
+}
+
+fn tree_schema() -> Value {
+    json!({
+        "$schema": SCHEMA_DIALECT,
+        "title": "storify tree --format json",
+        "type": "array",
+        "prefixItems": [
+            { "$ref": "#/$defs/directory" },
+            { "$ref": "#/$defs/report" }
+        ],
+        "items": false,
+        "minItems": 2,
+        "$defs": {
+            "entry": {
+                "oneOf": [
+                    { "$ref": "#/$defs/directory" },
+                    { "$ref": "#/$defs/file" }
+                ]
+            },
+            "directory": {
+                "type": "object",
+                "properties": {
+                    "type": { "const": "directory" },
+                    "name": { "type": "string" },
+                    "size": { "type": "integer", "minimum": 0 },
+                    "contents": { "type": "array", "items": { "$ref": "#/$defs/entry" } }
+                },
+                "required": ["type", "name", "contents"]
+            },
+            "file": {
+                "type": "object",
+                "properties": {
+                    "type": { "const": "file" },
+                    "name": { "type": "string" },
+                    "size": { "type": "integer", "minimum": 0 }
+                },
+                "required": ["type", "name"]
+            },
+            "report": {
+                "type": "object",
+                "properties": {
+                    "type": { "const": "report" },
+                    "schema_version": { "const": TREE_SCHEMA_VERSION },
+                    "directories": { "type": "integer", "minimum": 0 },
+                    "files": { "type": "integer", "minimum": 0 }
+                },
+                "required": ["type", "schema_version", "directories", "files"]
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Check that `value` has exactly the keys the object schema declares and requires.
+    fn assert_matches_object(schema: &Value, value: &Value) {
+        let properties = schema["properties"].as_object().unwrap();
+        let object = value.as_object().unwrap();
+        for key in object.keys() {
+            assert!(properties.contains_key(key), "undeclared field {key}");
+        }
+        for key in schema["required"].as_array().unwrap() {
+            assert!(object.contains_key(key.as_str().unwrap()), "missing {key}");
+        }
+    }
+
+    #[test]
+    fn stat_output_matches_schema() {
+        let output = StatOutput::from(ObjectMeta {
+            path: "a.txt".to_string(),
+            entry_type: "file".to_string(),
+            size: 3,
+            last_modified: None,
+            etag: Some("e".to_string()),
+            content_type: None,
+            version: None,
+            storage_class: None,
+            link_target: None,
+            owner: None,
+            group: None,
+            replication: Some(3),
+            directory: None,
+        });
+        let value = serde_json::to_value(&output).unwrap();
+        let schema = SchemaCommand::Stat.schema();
+        assert_matches_object(&schema, &value);
+        assert_eq!(
+            value["schema_version"],
+            schema["properties"]["schema_version"]["const"]
+        );
+
+        let directory = serde_json::to_value(DirectoryOutput::from(DirectorySummary {
+            files: 2,
+            bytes: 5,
+            ..Default::default()
+        }))
+        .unwrap();
+        assert_matches_object(&schema["properties"]["directory"], &directory);
+    }
+
+    #[test]
+    fn tree_output_matches_schema() {
+        let root = TreeEntry::Directory {
+            name: "data/".to_string(),
+            size: Some(3),
+            contents: vec![TreeEntry::File {
+                name: "a.txt".to_string(),
+                size: Some(3),
+            }],
+        };
+        let value = serde_json::to_value((&root, TreeReport::new(0, 1))).unwrap();
+        let schema = SchemaCommand::Tree.schema();
+        let defs = &schema["$defs"];
+        assert_matches_object(&defs["directory"], &value[0]);
+        assert_matches_object(&defs["file"], &value[0]["contents"][0]);
+        assert_matches_object(&defs["report"], &value[1]);
+        assert_eq!(value[1]["schema_version"], TREE_SCHEMA_VERSION);
+    }
+}
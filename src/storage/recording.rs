@@ -0,0 +1,479 @@
+//! Recording of backend HTTP traffic (`--record`) and its replay (`--replay`).
+//!
+//! A recording is a JSON Lines file. Every client opened during a run starts with a
+//! `session` line naming the provider, bucket and endpoint, followed by one `exchange` line
+//! per HTTP request it sent. Credentials are redacted from headers and query strings before
+//! anything is written, so a recording can be attached to a bug report; object keys and
+//! contents are kept as they are.
+//!
+//! Replay builds a client from the first session with placeholder credentials and answers
+//! each request with the first unused exchange of the same method and path, so concurrent
+//! requests may arrive in a different order than they were recorded.
+
+use crate::config::{StorageConfig, StorageProvider};
+use crate::error::{Error, Result};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64_ENGINE;
+use futures::stream;
+use http::header::{HeaderName, HeaderValue};
+use http::{HeaderMap, Request, Response, Uri};
+use opendal::Buffer;
+use opendal::raw::{HttpBody, HttpClient, HttpFetch};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+const REDACTED: &str = "REDACTED";
+
+/// Headers that carry credentials or signatures (compared lowercase).
+const SENSITIVE_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+    "x-amz-security-token",
+    "x-amz-server-side-encryption-customer-key",
+    "x-amz-copy-source-server-side-encryption-customer-key",
+    "x-oss-security-token",
+    "x-cos-security-token",
+    "x-ms-copy-source-authorization",
+    "x-goog-encryption-key",
+];
+
+/// Query parameters of presigned or SAS URLs that carry credentials (compared lowercase).
+const SENSITIVE_QUERY_PARAMS: &[&str] = &[
+    "x-amz-signature",
+    "x-amz-credential",
+    "x-amz-security-token",
+    "x-oss-signature",
+    "x-oss-credential",
+    "x-oss-security-token",
+    "x-goog-signature",
+    "x-goog-credential",
+    "signature",
+    "awsaccesskeyid",
+    "ossaccesskeyid",
+    "security-token",
+    "q-signature",
+    "q-ak",
+    "sig",
+];
+
+/// Text request bodies up to this size (batch deletes, multipart completion) are kept.
+const MAX_RECORDED_REQUEST_BODY: usize = 16 * 1024;
+
+/// Placeholder credentials for replay; the account key must be valid base64 for Azure.
+const REPLAY_ACCESS_KEY: &str = "storify-replay";
+const REPLAY_SECRET_KEY: &str = "c3RvcmlmeS1yZXBsYXk=";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+enum Entry {
+    Session(Session),
+    Exchange(Exchange),
+}
+
+/// Non-secret description of the client that produced the following exchanges.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Session {
+    storify: String,
+    command: String,
+    provider: String,
+    bucket: String,
+    endpoint: Option<String>,
+    region: Option<String>,
+    /// Azure storage account, part of every request URL anyway
+    account: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Exchange {
+    method: String,
+    uri: String,
+    request_headers: BTreeMap<String, String>,
+    request_size: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    request_body: Option<String>,
+    status: u16,
+    response_headers: BTreeMap<String, String>,
+    /// Response body when it is UTF-8
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    body: Option<String>,
+    /// Response body otherwise
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    body_base64: Option<String>,
+}
+
+impl Exchange {
+    fn response_body(&self) -> std::result::Result<Vec<u8>, String> {
+        match (&self.body, &self.body_base64) {
+            (Some(text), _) => Ok(text.clone().into_bytes()),
+            (None, Some(encoded)) => BASE64_ENGINE
+                .decode(encoded)
+                .map_err(|e| format!("invalid body_base64: {e}")),
+            (None, None) => Ok(Vec::new()),
+        }
+    }
+}
+
+/// Forwards requests to the default HTTP client and appends each exchange to a recording.
+#[derive(Clone)]
+pub struct Recorder {
+    inner: HttpClient,
+    path: PathBuf,
+    file: Arc<Mutex<File>>,
+}
+
+impl Recorder {
+    /// Create (or truncate) the recording at `path`.
+    pub fn create(path: &Path) -> Result<Self> {
+        Ok(Self {
+            inner: HttpClient::default(),
+            path: path.to_path_buf(),
+            file: Arc::new(Mutex::new(File::create(path)?)),
+        })
+    }
+
+    /// Start a session for a client built from `config`; its exchanges follow.
+    pub fn begin(&self, config: &StorageConfig, command: &str) -> Result<()> {
+        if matches!(config.provider, StorageProvider::Fs | StorageProvider::Hdfs) {
+            return Err(Error::InvalidArgument {
+                message: format!(
+                    "--record captures HTTP traffic, which the {} provider does not use",
+                    config.provider.as_str()
+                ),
+            });
+        }
+        self.append(&Entry::Session(Session {
+            storify: env!("CARGO_PKG_VERSION").to_string(),
+            command: command.to_string(),
+            provider: config.provider.as_str().to_string(),
+            bucket: config.bucket.clone(),
+            endpoint: config.endpoint.clone(),
+            region: config.region.clone(),
+            account: (config.provider == StorageProvider::Azblob)
+                .then(|| config.access_key_id.clone())
+                .flatten(),
+        }))
+    }
+
+    /// HTTP client to install on the operators whose traffic is recorded.
+    pub fn http_client(&self) -> HttpClient {
+        HttpClient::with(self.clone())
+    }
+
+    fn append(&self, entry: &Entry) -> Result<()> {
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        file.write_all(&line)?;
+        Ok(())
+    }
+}
+
+impl HttpFetch for Recorder {
+    async fn fetch(&self, req: Request<Buffer>) -> opendal::Result<Response<HttpBody>> {
+        let method = req.method().to_string();
+        let uri = redact_uri(req.uri());
+        let request_headers = redact_headers(req.headers());
+        let request_size = req.body().len();
+        let request_body = (request_size <= MAX_RECORDED_REQUEST_BODY)
+            .then(|| String::from_utf8(req.body().to_vec()).ok())
+            .flatten()
+            .filter(|text| !text.is_empty());
+
+        let (parts, mut body) = self.inner.fetch(req).await?.into_parts();
+        // The whole response is buffered so it can be written out; recording is for
+        // debugging, not for large transfers.
+        let buffer = body.to_buffer().await?;
+        let bytes = buffer.to_vec();
+        let (text, encoded) = match String::from_utf8(bytes) {
+            Ok(text) => (Some(text).filter(|t| !t.is_empty()), None),
+            Err(e) => (None, Some(BASE64_ENGINE.encode(e.as_bytes()))),
+        };
+        let exchange = Exchange {
+            method,
+            uri,
+            request_headers,
+            request_size,
+            request_body,
+            status: parts.status.as_u16(),
+            response_headers: redact_headers(&parts.headers),
+            body: text,
+            body_base64: encoded,
+        };
+        // A broken recording must not fail the command being debugged.
+        if let Err(err) = self.append(&Entry::Exchange(exchange)) {
+            tracing::warn!("failed to record to {}: {err}", self.path.display());
+        }
+        Ok(Response::from_parts(parts, buffered_body(buffer)))
+    }
+}
+
+/// Answers requests from a recording instead of the network.
+#[derive(Clone)]
+pub struct Replayer {
+    session: Session,
+    /// Exchanges in recorded order; answered ones are taken out
+    exchanges: Arc<Mutex<Vec<Option<Exchange>>>>,
+}
+
+impl Replayer {
+    /// Load and check the recording at `path`.
+    pub fn open(path: &Path) -> Result<Self> {
+        let invalid = |message: String| Error::InvalidRecording {
+            path: path.to_path_buf(),
+            message,
+        };
+        let text = fs::read_to_string(path)?;
+        let mut session = None;
+        let mut exchanges = Vec::new();
+        for (index, line) in text.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry: Entry = serde_json::from_str(line)
+                .map_err(|e| invalid(format!("line {}: {e}", index + 1)))?;
+            match entry {
+                Entry::Session(found) => {
+                    session.get_or_insert(found);
+                }
+                Entry::Exchange(exchange) => {
+                    exchange
+                        .response_body()
+                        .map_err(|e| invalid(format!("line {}: {e}", index + 1)))?;
+                    exchanges.push(Some(exchange));
+                }
+            }
+        }
+        let session = session.ok_or_else(|| invalid("no session recorded".to_string()))?;
+        Ok(Self {
+            session,
+            exchanges: Arc::new(Mutex::new(exchanges)),
+        })
+    }
+
+    /// Configuration of the recorded client, with placeholder credentials.
+    pub fn storage_config(&self) -> Result<StorageConfig> {
+        let provider: StorageProvider = self.session.provider.parse()?;
+        if matches!(
+            provider,
+            StorageProvider::Fs
+                | StorageProvider::Hdfs
+                | StorageProvider::Gcs
+                | StorageProvider::Memory
+        ) {
+            // GCS fetches its OAuth token outside the operator's HTTP client.
+            return Err(Error::InvalidArgument {
+                message: format!("{} recordings cannot be replayed", provider.as_str()),
+            });
+        }
+        Ok(StorageConfig {
+            provider,
+            bucket: self.session.bucket.clone(),
+            access_key_id: Some(
+                self.session
+                    .account
+                    .clone()
+                    .unwrap_or_else(|| REPLAY_ACCESS_KEY.to_string()),
+            ),
+            access_key_secret: Some(REPLAY_SECRET_KEY.to_string()),
+            endpoint: self.session.endpoint.clone(),
+            region: self.session.region.clone(),
+            ..StorageConfig::default()
+        })
+    }
+
+    /// HTTP client to install on the operators that replay the recording.
+    pub fn http_client(&self) -> HttpClient {
+        HttpClient::with(self.clone())
+    }
+
+    fn take(&self, method: &str, target: &str) -> Option<Exchange> {
+        let mut exchanges = self.exchanges.lock().unwrap_or_else(|e| e.into_inner());
+        exchanges
+            .iter_mut()
+            .find(|slot| {
+                slot.as_ref().is_some_and(|exchange| {
+                    exchange.method == method && request_target(&exchange.uri) == target
+                })
+            })
+            .and_then(Option::take)
+    }
+}
+
+impl HttpFetch for Replayer {
+    async fn fetch(&self, req: Request<Buffer>) -> opendal::Result<Response<HttpBody>> {
+        let method = req.method().as_str();
+        let target = request_target(&redact_uri(req.uri()));
+        let unexpected =
+            |message: String| opendal::Error::new(opendal::ErrorKind::Unexpected, message);
+        let exchange = self.take(method, &target).ok_or_else(|| {
+            unexpected(format!("no recorded response left for {method} {target}"))
+        })?;
+        tracing::debug!("replay {method} {target} -> {}", exchange.status);
+
+        let body = exchange.response_body().map_err(unexpected)?;
+        let mut response = Response::builder()
+            .status(exchange.status)
+            .extension(req.uri().clone());
+        if let Some(headers) = response.headers_mut() {
+            for (name, value) in &exchange.response_headers {
+                if let (Ok(name), Ok(value)) = (
+                    HeaderName::from_bytes(name.as_bytes()),
+                    HeaderValue::from_str(value),
+                ) {
+                    headers.insert(name, value);
+                }
+            }
+        }
+        response
+            .body(buffered_body(Buffer::from(body)))
+            .map_err(|e| unexpected(format!("invalid recorded response: {e}")))
+    }
+}
+
+fn buffered_body(buffer: Buffer) -> HttpBody {
+    let size = buffer.len() as u64;
+    HttpBody::new(stream::iter([Ok(buffer)]), Some(size))
+}
+
+fn redact_headers(headers: &HeaderMap) -> BTreeMap<String, String> {
+    let mut redacted = BTreeMap::new();
+    for (name, value) in headers {
+        let value = if SENSITIVE_HEADERS.contains(&name.as_str()) {
+            REDACTED.to_string()
+        } else {
+            String::from_utf8_lossy(value.as_bytes()).into_owned()
+        };
+        redacted
+            .entry(name.as_str().to_string())
+            .and_modify(|existing: &mut String| {
+                existing.push_str(", ");
+                existing.push_str(&value);
+            })
+            .or_insert(value);
+    }
+    redacted
+}
+
+pub(super) fn redact_uri(uri: &Uri) -> String {
+    let text = uri.to_string();
+    let Some((base, query)) = text.split_once('?') else {
+        return text;
+    };
+    let query = query
+        .split('&')
+        .map(|pair| match pair.split_once('=') {
+            Some((key, _))
+                if SENSITIVE_QUERY_PARAMS.contains(&key.to_ascii_lowercase().as_str()) =>
+            {
+                format!("{key}={REDACTED}")
+            }
+            _ => pair.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("&");
+    format!("{base}?{query}")
+}
+
+/// Path and query of a recorded URI, which is what replay matches on.
+pub(super) fn request_target(uri: &str) -> String {
+    uri.parse::<Uri>()
+        .ok()
+        .and_then(|uri| uri.path_and_query().map(|pq| pq.to_string()))
+        .unwrap_or_else(|| uri.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacts_credentials() {
+        let uri: Uri = "https://bucket.s3.amazonaws.com/a%20b.txt?X-Amz-Credential=AKIA%2F1&x-id=GetObject&X-Amz-Signature=abc"
+            .parse()
+            .unwrap();
+        let redacted = redact_uri(&uri);
+        assert_eq!(
+            redacted,
+            "https://bucket.s3.amazonaws.com/a%20b.txt?X-Amz-Credential=REDACTED&x-id=GetObject&X-Amz-Signature=REDACTED"
+        );
+        assert_eq!(
+            request_target(&redacted),
+            "/a%20b.txt?X-Amz-Credential=REDACTED&x-id=GetObject&X-Amz-Signature=REDACTED"
+        );
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "authorization",
+            HeaderValue::from_static("AWS4-HMAC-SHA256 secret"),
+        );
+        headers.insert("x-amz-security-token", HeaderValue::from_static("token"));
+        headers.insert("content-type", HeaderValue::from_static("text/plain"));
+        let redacted = redact_headers(&headers);
+        assert_eq!(redacted["authorization"], REDACTED);
+        assert_eq!(redacted["x-amz-security-token"], REDACTED);
+        assert_eq!(redacted["content-type"], "text/plain");
+    }
+
+    #[tokio::test]
+    async fn replays_recorded_exchanges() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.jsonl");
+        let recorder = Recorder::create(&path).unwrap();
+        let config = StorageConfig {
+            provider: StorageProvider::S3,
+            bucket: "demo".to_string(),
+            access_key_id: Some("AKIASECRET".to_string()),
+            access_key_secret: Some("topsecret".to_string()),
+            region: Some("us-east-1".to_string()),
+            ..StorageConfig::default()
+        };
+        recorder.begin(&config, "cat").unwrap();
+        let exchange = |status: u16, body: &[u8]| {
+            Entry::Exchange(Exchange {
+                method: "GET".to_string(),
+                uri: "https://demo.s3.amazonaws.com/key.bin".to_string(),
+                request_headers: BTreeMap::new(),
+                request_size: 0,
+                request_body: None,
+                status,
+                response_headers: BTreeMap::from([(
+                    "content-length".to_string(),
+                    body.len().to_string(),
+                )]),
+                body: None,
+                body_base64: Some(BASE64_ENGINE.encode(body)),
+            })
+        };
+        recorder.append(&exchange(503, b"")).unwrap();
+        recorder
+            .append(&exchange(200, &[0, 159, 146, 150]))
+            .unwrap();
+        let recorded = fs::read_to_string(&path).unwrap();
+        assert!(!recorded.contains("AKIASECRET") && !recorded.contains("topsecret"));
+
+        let replayer = Replayer::open(&path).unwrap();
+        let replay_config = replayer.storage_config().unwrap();
+        assert_eq!(replay_config.bucket, "demo");
+        assert_eq!(replay_config.region.as_deref(), Some("us-east-1"));
+
+        let request = || {
+            Request::get("https://demo.s3.amazonaws.com/key.bin")
+                .body(Buffer::new())
+                .unwrap()
+        };
+        let first = replayer.fetch(request()).await.unwrap();
+        assert_eq!(first.status(), 503);
+        let (_, mut body) = replayer.fetch(request()).await.unwrap().into_parts();
+        assert_eq!(
+            body.to_buffer().await.unwrap().to_vec(),
+            vec![0, 159, 146, 150]
+        );
+        assert!(replayer.fetch(request()).await.is_err());
+    }
+}
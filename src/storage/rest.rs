@@ -0,0 +1,490 @@
+//! Signed requests against the S3, OSS and COS REST APIs.
+//!
+//! Used for the object features OpenDAL does not expose (tagging, storage classes). Only
+//! static credentials are supported; role assumption and instance metadata stay with
+//! OpenDAL's credential loaders.
+
+use crate::config::{ProviderBackend, StorageProvider, aws_profile::load_aws_profile};
+use crate::error::{Error, Result};
+use crate::storage::connection::ConnectionSettings;
+use crate::storage::retry::RetryPolicy;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64_ENGINE;
+use md5::{Digest, Md5};
+use reqsign::{
+    AliyunCredential, AliyunOssSigner, AwsCredential, AwsV4Signer, TencentCosCredential,
+    TencentCosSigner,
+};
+use reqwest::Method;
+use reqwest::header::HeaderMap;
+
+/// Request signing for the providers with a REST fallback.
+enum Signer {
+    S3(AwsV4Signer, AwsCredential),
+    Oss(AliyunOssSigner, AliyunCredential),
+    Cos(TencentCosSigner, TencentCosCredential),
+}
+
+impl Signer {
+    fn sign(&self, request: &mut reqwest::Request) -> Result<()> {
+        match self {
+            Signer::S3(signer, credential) => signer.sign(request, credential),
+            Signer::Oss(signer, credential) => signer.sign(request, credential),
+            Signer::Cos(signer, credential) => signer.sign(request, credential),
+        }
+        .map_err(|err| Error::ProviderRequest {
+            message: format!("failed to sign request: {err}"),
+        })
+    }
+}
+
+/// Failed attempt of a [`RestBucket::send`] call.
+pub(crate) enum SendError {
+    /// Connection failures, timeouts, throttling and 5xx responses, which are retried
+    Transient(Error),
+    Fatal(Error),
+}
+
+impl SendError {
+    pub(crate) fn into_inner(self) -> Error {
+        match self {
+            SendError::Transient(err) | SendError::Fatal(err) => err,
+        }
+    }
+}
+
+impl std::fmt::Display for SendError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SendError::Transient(err) | SendError::Fatal(err) => err.fmt(f),
+        }
+    }
+}
+
+/// Successful response of a [`RestBucket::send`] call.
+pub struct RestResponse {
+    pub headers: HeaderMap,
+    pub body: String,
+}
+
+/// A bucket addressed through the provider's REST API with signed requests.
+pub struct RestBucket {
+    client: reqwest::Client,
+    signer: Signer,
+    provider: StorageProvider,
+    bucket: String,
+    /// Bucket URL that percent-encoded keys are appended to
+    bucket_url: String,
+    /// Headers added to, and signed with, every request (e.g. the S3 request payer)
+    signed_headers: Vec<(String, String)>,
+    retry: RetryPolicy,
+}
+
+impl RestBucket {
+    /// Resolve the bucket URL and credentials of `backend`; `feature` names the calling
+    /// command in error messages (e.g. "object tagging").
+    pub fn from_backend(
+        provider: StorageProvider,
+        backend: &ProviderBackend,
+        feature: &str,
+        retry: RetryPolicy,
+        connection: &ConnectionSettings,
+    ) -> Result<Self> {
+        let mut signed_headers = Vec::new();
+        let (signer, bucket, bucket_url) = match backend {
+            ProviderBackend::S3 {
+                bucket,
+                access_key,
+                secret_key,
+                session_token,
+                region,
+                endpoint,
+                aws_profile,
+                role_arn,
+                use_instance_metadata,
+                compat,
+                request_payer,
+                ..
+            } => {
+                if role_arn.is_some() || *use_instance_metadata {
+                    return Err(static_credentials_required(provider, feature));
+                }
+                if *request_payer {
+                    signed_headers
+                        .push(("x-amz-request-payer".to_string(), "requester".to_string()));
+                }
+                let (credential, profile_region) = match (access_key, secret_key, aws_profile) {
+                    (Some(access_key), Some(secret_key), _) => (
+                        AwsCredential {
+                            access_key_id: access_key.clone(),
+                            secret_access_key: secret_key.clone(),
+                            session_token: session_token.clone(),
+                            expires_in: None,
+                        },
+                        None,
+                    ),
+                    (_, _, Some(name)) => {
+                        let profile = load_aws_profile(name)?;
+                        (
+                            AwsCredential {
+                                access_key_id: profile.access_key_id,
+                                secret_access_key: profile.secret_access_key,
+                                session_token: profile.session_token,
+                                expires_in: None,
+                            },
+                            profile.region,
+                        )
+                    }
+                    _ => return Err(static_credentials_required(provider, feature)),
+                };
+                let region = region
+                    .clone()
+                    .or(profile_region)
+                    .unwrap_or_else(|| "us-east-1".to_string());
+                let bucket_url = match endpoint.as_deref() {
+                    Some(endpoint) if compat.is_some_and(|preset| preset.virtual_host_style()) => {
+                        virtual_host_url(endpoint, bucket)
+                    }
+                    Some(endpoint) => format!("{}/{bucket}", with_scheme(endpoint)),
+                    None => format!("https://{bucket}.s3.{region}.amazonaws.com"),
+                };
+                (
+                    Signer::S3(AwsV4Signer::new("s3", &region), credential),
+                    bucket,
+                    bucket_url,
+                )
+            }
+            ProviderBackend::Oss {
+                bucket,
+                access_key: Some(access_key),
+                secret_key: Some(secret_key),
+                security_token,
+                endpoint,
+                ..
+            } => {
+                let endpoint = required_endpoint(provider, endpoint.as_deref(), feature)?;
+                let credential = AliyunCredential {
+                    access_key_id: access_key.clone(),
+                    access_key_secret: secret_key.clone(),
+                    security_token: security_token.clone(),
+                    expires_in: None,
+                };
+                (
+                    Signer::Oss(AliyunOssSigner::new(bucket), credential),
+                    bucket,
+                    virtual_host_url(endpoint, bucket),
+                )
+            }
+            ProviderBackend::Oss { .. } => {
+                return Err(static_credentials_required(provider, feature));
+            }
+            ProviderBackend::Cos {
+                bucket,
+                secret_id,
+                secret_key,
+                endpoint,
+            } => {
+                let endpoint = required_endpoint(provider, endpoint.as_deref(), feature)?;
+                let credential = TencentCosCredential {
+                    secret_id: secret_id.clone(),
+                    secret_key: secret_key.clone(),
+                    security_token: None,
+                    expires_in: None,
+                };
+                (
+                    Signer::Cos(TencentCosSigner::new(), credential),
+                    bucket,
+                    virtual_host_url(endpoint, bucket),
+                )
+            }
+            _ => {
+                return Err(Error::InvalidArgument {
+                    message: format!(
+                        "{feature} needs the s3, oss or cos provider (got '{}')",
+                        provider.as_str()
+                    ),
+                });
+            }
+        };
+        Ok(Self {
+            client: connection.rest_client()?,
+            signer,
+            provider,
+            bucket: bucket.clone(),
+            bucket_url,
+            signed_headers,
+            retry,
+        })
+    }
+
+    pub fn provider(&self) -> StorageProvider {
+        self.provider
+    }
+
+    /// Vendor prefix of the provider's request and response headers.
+    pub fn header_prefix(&self) -> &'static str {
+        match self.provider {
+            StorageProvider::Oss => "x-oss-",
+            StorageProvider::Cos => "x-cos-",
+            _ => "x-amz-",
+        }
+    }
+
+    /// Value of the `<prefix>copy-source` header that copies `key` within this bucket.
+    pub fn copy_source(&self, key: &str) -> String {
+        match self.provider {
+            // COS wants the bucket host rather than the bucket name.
+            StorageProvider::Cos => {
+                let host = self
+                    .bucket_url
+                    .split_once("://")
+                    .map_or(self.bucket_url.as_str(), |(_, host)| host);
+                format!("{host}/{}", encode_key(key))
+            }
+            _ => format!("/{}/{}", self.bucket, encode_key(key)),
+        }
+    }
+
+    /// Send a signed request for `key` (the bucket itself when empty) with an optional
+    /// query string, which must already be encoded. Transient failures are retried.
+    pub async fn send(
+        &self,
+        method: Method,
+        key: &str,
+        query: Option<&str>,
+        headers: &[(String, String)],
+        body: Option<String>,
+    ) -> Result<RestResponse> {
+        let mut url = self.bucket_url.clone();
+        url.push('/');
+        url.push_str(&encode_key(key));
+        if let Some(query) = query {
+            url.push('?');
+            url.push_str(query);
+        }
+        let what = format!("{method} {url}");
+        self.retry
+            .run(
+                &what,
+                |err| matches!(err, SendError::Transient(_)),
+                || self.send_once(method.clone(), &url, headers, body.clone()),
+            )
+            .await
+            .map_err(SendError::into_inner)
+    }
+
+    /// One signed attempt of [`Self::send`]; signing again per attempt keeps the request
+    /// timestamp fresh.
+    async fn send_once(
+        &self,
+        method: Method,
+        url: &str,
+        headers: &[(String, String)],
+        body: Option<String>,
+    ) -> std::result::Result<RestResponse, SendError> {
+        let transport = |err: reqwest::Error| {
+            if err.is_connect() || err.is_timeout() || err.is_request() || err.is_body() {
+                SendError::Transient(request_error(err))
+            } else {
+                SendError::Fatal(request_error(err))
+            }
+        };
+        let mut builder = self.client.request(method, url);
+        for (name, value) in self.signed_headers.iter().chain(headers) {
+            builder = builder.header(name, value);
+        }
+        if let Some(body) = body {
+            // XML unless the caller names another type (bucket policies are JSON).
+            if !headers
+                .iter()
+                .any(|(name, _)| name.eq_ignore_ascii_case("content-type"))
+            {
+                builder = builder.header(reqwest::header::CONTENT_TYPE, "application/xml");
+            }
+            builder = builder.body(body);
+        }
+        let mut request = builder
+            .build()
+            .map_err(|err| SendError::Fatal(request_error(err)))?;
+        self.signer.sign(&mut request).map_err(SendError::Fatal)?;
+        let response = self.client.execute(request).await.map_err(transport)?;
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = response.text().await.map_err(transport)?;
+        if status.is_success() {
+            return Ok(RestResponse { headers, body });
+        }
+        let detail = [element(&body, "Code"), element(&body, "Message")]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(": ");
+        let err = Error::ProviderRequest {
+            message: if detail.is_empty() {
+                format!("{status}")
+            } else {
+                format!("{status} ({detail})")
+            },
+        };
+        if status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            Err(SendError::Transient(err))
+        } else {
+            Err(SendError::Fatal(err))
+        }
+    }
+}
+
+/// Key of a single object; directories (and the bucket root) are rejected.
+pub fn object_key<'a>(path: &'a str, what: &str) -> Result<&'a str> {
+    let key = path.trim_start_matches('/');
+    if key.is_empty() || key.ends_with('/') {
+        return Err(Error::InvalidArgument {
+            message: format!("{what} apply to objects, not directories: '{path}'"),
+        });
+    }
+    Ok(key)
+}
+
+fn required_endpoint<'a>(
+    provider: StorageProvider,
+    endpoint: Option<&'a str>,
+    feature: &str,
+) -> Result<&'a str> {
+    endpoint.ok_or_else(|| Error::MissingConfigField {
+        provider: provider.as_str().to_string(),
+        field: format!("endpoint (required for {feature})"),
+    })
+}
+
+fn static_credentials_required(provider: StorageProvider, feature: &str) -> Error {
+    Error::InvalidArgument {
+        message: format!(
+            "{feature} on '{}' needs static access keys{}",
+            provider.as_str(),
+            if provider == StorageProvider::S3 {
+                " or an AWS profile"
+            } else {
+                ""
+            }
+        ),
+    }
+}
+
+fn request_error(err: reqwest::Error) -> Error {
+    Error::ProviderRequest {
+        message: err.to_string(),
+    }
+}
+
+fn with_scheme(endpoint: &str) -> String {
+    let endpoint = endpoint.trim_end_matches('/');
+    if endpoint.contains("://") {
+        endpoint.to_string()
+    } else {
+        format!("https://{endpoint}")
+    }
+}
+
+/// `https://oss-cn-hangzhou.aliyuncs.com` -> `https://<bucket>.oss-cn-hangzhou.aliyuncs.com`
+fn virtual_host_url(endpoint: &str, bucket: &str) -> String {
+    let endpoint = with_scheme(endpoint);
+    let (scheme, host) = endpoint
+        .split_once("://")
+        .expect("with_scheme adds a scheme");
+    format!("{scheme}://{bucket}.{host}")
+}
+
+/// Percent-encode a key for use in a URL path, keeping '/' separators.
+fn encode_key(key: &str) -> String {
+    encode(key, true)
+}
+
+/// Percent-encode a query parameter value.
+pub fn encode_query_value(value: &str) -> String {
+    encode(value, false)
+}
+
+fn encode(input: &str, keep_slash: bool) -> String {
+    let mut encoded = String::with_capacity(input.len());
+    for byte in input.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            b'/' if keep_slash => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
+
+/// Text of the first `<name>` element of an XML document, still escaped.
+pub fn element<'a>(xml: &'a str, name: &str) -> Option<&'a str> {
+    let start = xml.find(&format!("<{name}>"))? + name.len() + 2;
+    let end = start + xml[start..].find(&format!("</{name}>"))?;
+    Some(&xml[start..end])
+}
+
+/// Text of every `<name>` element of an XML document, in order and still escaped.
+pub fn elements<'a>(xml: &'a str, name: &str) -> Vec<&'a str> {
+    let (open, close) = (format!("<{name}>"), format!("</{name}>"));
+    let mut found = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find(&open) {
+        rest = &rest[start + open.len()..];
+        let Some(end) = rest.find(&close) else {
+            break;
+        };
+        found.push(&rest[..end]);
+        rest = &rest[end + close.len()..];
+    }
+    found
+}
+
+/// `Content-MD5` header of a request body, which S3 and COS require on bucket
+/// configuration changes.
+pub fn content_md5(body: &str) -> (String, String) {
+    (
+        "Content-MD5".to_string(),
+        BASE64_ENGINE.encode(Md5::digest(body.as_bytes())),
+    )
+}
+
+pub fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+pub fn unescape_xml(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn object_urls_are_encoded() {
+        assert_eq!(encode_key("logs/a b+c.txt"), "logs/a%20b%2Bc.txt");
+        assert_eq!(encode_query_value("logs/a b"), "logs%2Fa%20b");
+        assert_eq!(
+            virtual_host_url("oss-cn-hangzhou.aliyuncs.com/", "bucket"),
+            "https://bucket.oss-cn-hangzhou.aliyuncs.com"
+        );
+        assert_eq!(
+            element("<Error><Code>NoSuchKey</Code></Error>", "Code"),
+            Some("NoSuchKey")
+        );
+        assert_eq!(
+            elements("<R><M>GET</M><M>PUT</M><O>*</O></R>", "M"),
+            vec!["GET", "PUT"]
+        );
+    }
+}
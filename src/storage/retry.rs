@@ -0,0 +1,125 @@
+//! Retries of storage requests that failed transiently.
+//!
+//! Requests that go through the operator are retried by OpenDAL's `RetryLayer`, which
+//! only repeats errors OpenDAL marks as temporary (timeouts, connection resets, 5xx and
+//! 429 responses). Requests storify sends itself (tagging, storage classes, aborting
+//! uploads) are retried by [`RetryPolicy::run`] with the same attempts and delays.
+
+use crate::config::StorageConfig;
+use crate::storage::constants::{DEFAULT_RETRIES, DEFAULT_RETRY_BACKOFF_MS, MAX_RETRY_BACKOFF};
+use crate::storage::metrics::TransferCounters;
+use opendal::layers::RetryLayer;
+use std::future::Future;
+use std::time::Duration;
+
+/// How often and how patiently a failed request is repeated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Attempts after the first one; 0 disables retries
+    pub retries: usize,
+    /// Delay before the first retry, doubled for each further one
+    pub backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            retries: DEFAULT_RETRIES,
+            backoff: Duration::from_millis(DEFAULT_RETRY_BACKOFF_MS),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Policy of `config`, falling back to the defaults for unset fields.
+    pub fn from_config(config: &StorageConfig) -> Self {
+        let default = Self::default();
+        Self {
+            retries: config.retries.unwrap_or(default.retries),
+            backoff: config
+                .retry_backoff_ms
+                .map_or(default.backoff, Duration::from_millis),
+        }
+    }
+
+    /// Layer retrying the operator's requests, or `None` when retries are disabled; each
+    /// retry is counted into `counters`.
+    pub fn layer(&self, counters: &TransferCounters) -> Option<RetryLayer<TransferCounters>> {
+        (self.retries > 0).then(|| {
+            RetryLayer::new()
+                .with_notify(counters.clone())
+                .with_max_times(self.retries)
+                .with_min_delay(self.backoff)
+                .with_max_delay(MAX_RETRY_BACKOFF.max(self.backoff))
+                .with_factor(2.0)
+                .with_jitter()
+        })
+    }
+
+    /// Delay before retry number `retry` (0 for the first retry).
+    pub fn delay(&self, retry: usize) -> Duration {
+        let factor = 1u32.checked_shl(retry as u32).unwrap_or(u32::MAX);
+        self.backoff
+            .saturating_mul(factor)
+            .min(MAX_RETRY_BACKOFF.max(self.backoff))
+    }
+
+    /// Run `attempt` until it succeeds, fails with an error `is_transient` rejects, or the
+    /// retries are used up; `what` names the request in log messages.
+    pub async fn run<T, E, F, Fut>(
+        &self,
+        what: &str,
+        is_transient: impl Fn(&E) -> bool,
+        mut attempt: F,
+    ) -> std::result::Result<T, E>
+    where
+        E: std::fmt::Display,
+        F: FnMut() -> Fut,
+        Fut: Future<Output = std::result::Result<T, E>>,
+    {
+        let mut retry = 0;
+        loop {
+            match attempt().await {
+                Err(err) if retry < self.retries && is_transient(&err) => {
+                    let delay = self.delay(retry);
+                    tracing::warn!(
+                        "{what} failed ({err}); retry {} of {} in {delay:?}",
+                        retry + 1,
+                        self.retries
+                    );
+                    tokio::time::sleep(delay).await;
+                    retry += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delays_double_up_to_the_cap() {
+        let policy = RetryPolicy {
+            retries: 20,
+            backoff: Duration::from_millis(100),
+        };
+        assert_eq!(policy.delay(0), Duration::from_millis(100));
+        assert_eq!(policy.delay(3), Duration::from_millis(800));
+        assert_eq!(policy.delay(19), MAX_RETRY_BACKOFF);
+        assert_eq!(policy.delay(64), MAX_RETRY_BACKOFF);
+    }
+
+    #[test]
+    fn config_fields_override_defaults() {
+        let mut config = StorageConfig::memory("retry");
+        assert_eq!(RetryPolicy::from_config(&config), RetryPolicy::default());
+        config.retries = Some(0);
+        config.retry_backoff_ms = Some(50);
+        let policy = RetryPolicy::from_config(&config);
+        assert_eq!(policy.backoff, Duration::from_millis(50));
+        assert!(policy.layer(&TransferCounters::default()).is_none());
+    }
+}
@@ -0,0 +1,186 @@
+//! Per-request latency for `ls --timing` and `stat --timing`.
+//!
+//! Every HTTP request an operator sends is timed from the moment it is handed to the client:
+//! time to first byte ends when the response headers arrive, total time when the body has
+//! been read. Bodies are buffered to take the second measurement, which is fine for the
+//! listings and metadata calls this is meant for but not for transfers.
+
+use futures::stream;
+use opendal::Buffer;
+use opendal::raw::{Access, HttpBody, HttpClient, HttpFetch, Layer};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::recording::{redact_uri, request_target};
+
+/// Latency of one HTTP request.
+#[derive(Debug, Clone)]
+pub struct RequestTiming {
+    pub method: String,
+    /// Path and query, with credentials redacted
+    pub target: String,
+    pub status: Option<u16>,
+    pub ttfb: Duration,
+    pub total: Duration,
+}
+
+/// Collects the timings of every client of one invocation; clones share the same log.
+#[derive(Debug, Clone)]
+pub struct RequestTimer {
+    started: Instant,
+    timings: Arc<Mutex<Vec<RequestTiming>>>,
+}
+
+impl Default for RequestTimer {
+    fn default() -> Self {
+        Self {
+            started: Instant::now(),
+            timings: Arc::default(),
+        }
+    }
+}
+
+impl RequestTimer {
+    fn push(&self, timing: RequestTiming) {
+        self.timings
+            .lock()
+            .expect("timing log lock poisoned")
+            .push(timing);
+    }
+
+    /// Timings recorded so far, in completion order.
+    fn timings(&self) -> Vec<RequestTiming> {
+        self.timings
+            .lock()
+            .expect("timing log lock poisoned")
+            .clone()
+    }
+
+    /// Print one line per request and a summary to stderr, keeping stdout parseable.
+    pub fn report(&self) {
+        let timings = self.timings();
+        let wall = self.started.elapsed();
+        if timings.is_empty() {
+            eprintln!(
+                "timing: no HTTP requests were sent, wall {}",
+                format_latency(wall)
+            );
+            return;
+        }
+        for timing in &timings {
+            let status = timing
+                .status
+                .map_or_else(|| "ERR".to_string(), |status| status.to_string());
+            eprintln!(
+                "timing: {:<6} {status:<3} ttfb={:>9} total={:>9} {}",
+                timing.method,
+                format_latency(timing.ttfb),
+                format_latency(timing.total),
+                timing.target
+            );
+        }
+        let slowest = timings
+            .iter()
+            .map(|timing| timing.total)
+            .max()
+            .unwrap_or_default();
+        eprintln!(
+            "timing: {} request(s), slowest {}, wall {}",
+            timings.len(),
+            format_latency(slowest),
+            format_latency(wall)
+        );
+    }
+}
+
+/// Routes an operator's HTTP requests through a [`RequestTimer`].
+#[derive(Clone)]
+pub struct TimingLayer {
+    timer: RequestTimer,
+}
+
+impl TimingLayer {
+    pub fn new(timer: RequestTimer) -> Self {
+        Self { timer }
+    }
+}
+
+impl<A: Access> Layer<A> for TimingLayer {
+    type LayeredAccess = A;
+
+    fn layer(&self, inner: A) -> Self::LayeredAccess {
+        inner.info().update_http_client(|client| {
+            HttpClient::with(TimedFetcher {
+                inner: client,
+                timer: self.timer.clone(),
+            })
+        });
+        inner
+    }
+}
+
+struct TimedFetcher {
+    inner: HttpClient,
+    timer: RequestTimer,
+}
+
+impl HttpFetch for TimedFetcher {
+    async fn fetch(&self, req: http::Request<Buffer>) -> opendal::Result<http::Response<HttpBody>> {
+        let method = req.method().to_string();
+        let target = request_target(&redact_uri(req.uri()));
+        let started = Instant::now();
+        let response = self.inner.fetch(req).await;
+        let ttfb = started.elapsed();
+        let (parts, mut body) = match response {
+            Ok(response) => response.into_parts(),
+            Err(err) => {
+                self.timer.push(RequestTiming {
+                    method,
+                    target,
+                    status: None,
+                    ttfb,
+                    total: ttfb,
+                });
+                return Err(err);
+            }
+        };
+        let buffer = body.to_buffer().await;
+        self.timer.push(RequestTiming {
+            method,
+            target,
+            status: Some(parts.status.as_u16()),
+            ttfb,
+            total: started.elapsed(),
+        });
+        let buffer = buffer?;
+        let size = buffer.len() as u64;
+        Ok(http::Response::from_parts(
+            parts,
+            HttpBody::new(stream::iter([Ok(buffer)]), Some(size)),
+        ))
+    }
+}
+
+/// `850µs`, `12.3ms` or `1.20s`.
+fn format_latency(duration: Duration) -> String {
+    let micros = duration.as_micros();
+    if micros < 1_000 {
+        format!("{micros}µs")
+    } else if micros < 1_000_000 {
+        format!("{:.1}ms", micros as f64 / 1_000.0)
+    } else {
+        format!("{:.2}s", duration.as_secs_f64())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn latencies_pick_a_readable_unit() {
+        assert_eq!(format_latency(Duration::from_micros(850)), "850µs");
+        assert_eq!(format_latency(Duration::from_micros(12_340)), "12.3ms");
+        assert_eq!(format_latency(Duration::from_millis(1_204)), "1.20s");
+    }
+}
@@ -0,0 +1,237 @@
+//! Tracing spans for storage operations and events for their HTTP requests.
+//!
+//! Every operation an operator runs (stat, read, write, list, delete, copy, ...) gets an
+//! `info` span named after it with the paths involved; readers, writers and listers keep
+//! the span of the call that created them, so the requests of a long transfer are still
+//! attributed to it. Each HTTP request then logs one `debug` event in that span, and
+//! failures (transport errors, 429 and 5xx responses, failed operations) are raised to
+//! `info` so `-v` is enough to see them.
+
+use opendal::raw::oio;
+use opendal::raw::{
+    Access, HttpBody, HttpClient, HttpFetch, Layer, LayeredAccess, OpCopy, OpCreateDir, OpDelete,
+    OpList, OpPresign, OpRead, OpRename, OpStat, OpWrite, RpCopy, RpCreateDir, RpDelete, RpList,
+    RpPresign, RpRead, RpRename, RpStat, RpWrite,
+};
+use opendal::{Buffer, ErrorKind, Metadata, Result};
+use std::time::Instant;
+use tracing::{Instrument, Span};
+
+use super::recording::{redact_uri, request_target};
+
+/// Installs operation spans on an operator and request events on its HTTP client.
+#[derive(Clone, Default)]
+pub struct TraceLayer;
+
+impl<A: Access> Layer<A> for TraceLayer {
+    type LayeredAccess = TraceAccessor<A>;
+
+    fn layer(&self, inner: A) -> Self::LayeredAccess {
+        inner.info().update_http_client(trace_http_client);
+        TraceAccessor { inner }
+    }
+}
+
+/// Log the requests of `client`; installed again whenever the client is replaced.
+pub fn trace_http_client(client: HttpClient) -> HttpClient {
+    HttpClient::with(TracedFetcher { inner: client })
+}
+
+struct TracedFetcher {
+    inner: HttpClient,
+}
+
+impl HttpFetch for TracedFetcher {
+    async fn fetch(&self, req: http::Request<Buffer>) -> Result<http::Response<HttpBody>> {
+        let method = req.method().to_string();
+        let uri = request_target(&redact_uri(req.uri()));
+        let started = Instant::now();
+        let response = self.inner.fetch(req).await;
+        let elapsed_ms = started.elapsed().as_millis() as u64;
+        match &response {
+            Ok(response) => {
+                let status = response.status();
+                if status.is_server_error() || status == http::StatusCode::TOO_MANY_REQUESTS {
+                    tracing::info!(
+                        %method, %uri, status = status.as_u16(), elapsed_ms,
+                        "request failed"
+                    );
+                } else {
+                    tracing::debug!(
+                        %method, %uri, status = status.as_u16(), elapsed_ms,
+                        "request"
+                    );
+                }
+            }
+            Err(err) => {
+                tracing::info!(%method, %uri, elapsed_ms, error = %err, "request failed");
+            }
+        }
+        response
+    }
+}
+
+/// Log a failed operation in its span; missing objects are expected often enough (exists
+/// checks, stat before write) to stay at `debug`.
+fn traced<T>(result: Result<T>) -> Result<T> {
+    if let Err(err) = &result {
+        if err.kind() == ErrorKind::NotFound {
+            tracing::debug!(error = %err, "operation failed");
+        } else {
+            tracing::info!(error = %err, "operation failed");
+        }
+    }
+    result
+}
+
+#[derive(Debug)]
+pub struct TraceAccessor<A> {
+    inner: A,
+}
+
+impl<A: Access> LayeredAccess for TraceAccessor<A> {
+    type Inner = A;
+    type Reader = Traced<A::Reader>;
+    type Writer = Traced<A::Writer>;
+    type Lister = Traced<A::Lister>;
+    type Deleter = Traced<A::Deleter>;
+
+    fn inner(&self) -> &Self::Inner {
+        &self.inner
+    }
+
+    async fn create_dir(&self, path: &str, args: OpCreateDir) -> Result<RpCreateDir> {
+        let span = tracing::info_span!("create_dir", path);
+        async { traced(self.inner.create_dir(path, args).await) }
+            .instrument(span)
+            .await
+    }
+
+    async fn read(&self, path: &str, args: OpRead) -> Result<(RpRead, Self::Reader)> {
+        let span = tracing::info_span!("read", path);
+        let (rp, reader) = async { traced(self.inner.read(path, args).await) }
+            .instrument(span.clone())
+            .await?;
+        Ok((rp, Traced::new(span, reader)))
+    }
+
+    async fn write(&self, path: &str, args: OpWrite) -> Result<(RpWrite, Self::Writer)> {
+        let span = tracing::info_span!("write", path);
+        let (rp, writer) = async { traced(self.inner.write(path, args).await) }
+            .instrument(span.clone())
+            .await?;
+        Ok((rp, Traced::new(span, writer)))
+    }
+
+    async fn copy(&self, from: &str, to: &str, args: OpCopy) -> Result<RpCopy> {
+        let span = tracing::info_span!("copy", from, to);
+        async { traced(self.inner.copy(from, to, args).await) }
+            .instrument(span)
+            .await
+    }
+
+    async fn rename(&self, from: &str, to: &str, args: OpRename) -> Result<RpRename> {
+        let span = tracing::info_span!("rename", from, to);
+        async { traced(self.inner.rename(from, to, args).await) }
+            .instrument(span)
+            .await
+    }
+
+    async fn stat(&self, path: &str, args: OpStat) -> Result<RpStat> {
+        let span = tracing::info_span!("stat", path);
+        async { traced(self.inner.stat(path, args).await) }
+            .instrument(span)
+            .await
+    }
+
+    async fn delete(&self) -> Result<(RpDelete, Self::Deleter)> {
+        let span = tracing::info_span!("delete");
+        let (rp, deleter) = async { traced(self.inner.delete().await) }
+            .instrument(span.clone())
+            .await?;
+        Ok((rp, Traced::new(span, deleter)))
+    }
+
+    async fn list(&self, path: &str, args: OpList) -> Result<(RpList, Self::Lister)> {
+        let span = tracing::info_span!("list", path);
+        let (rp, lister) = async { traced(self.inner.list(path, args).await) }
+            .instrument(span.clone())
+            .await?;
+        Ok((rp, Traced::new(span, lister)))
+    }
+
+    async fn presign(&self, path: &str, args: OpPresign) -> Result<RpPresign> {
+        let span = tracing::info_span!("presign", path);
+        async { traced(self.inner.presign(path, args).await) }
+            .instrument(span)
+            .await
+    }
+}
+
+/// Reader, writer, lister or deleter that runs in the span of the operation creating it.
+pub struct Traced<R> {
+    span: Span,
+    inner: R,
+}
+
+impl<R> Traced<R> {
+    fn new(span: Span, inner: R) -> Self {
+        Self { span, inner }
+    }
+}
+
+impl<R: oio::Read> oio::Read for Traced<R> {
+    async fn read(&mut self) -> Result<Buffer> {
+        let span = self.span.clone();
+        async { traced(self.inner.read().await) }
+            .instrument(span)
+            .await
+    }
+}
+
+impl<R: oio::Write> oio::Write for Traced<R> {
+    async fn write(&mut self, bs: Buffer) -> Result<()> {
+        let span = self.span.clone();
+        async { traced(self.inner.write(bs).await) }
+            .instrument(span)
+            .await
+    }
+
+    async fn abort(&mut self) -> Result<()> {
+        let span = self.span.clone();
+        async { traced(self.inner.abort().await) }
+            .instrument(span)
+            .await
+    }
+
+    async fn close(&mut self) -> Result<Metadata> {
+        let span = self.span.clone();
+        async { traced(self.inner.close().await) }
+            .instrument(span)
+            .await
+    }
+}
+
+impl<R: oio::List> oio::List for Traced<R> {
+    async fn next(&mut self) -> Result<Option<oio::Entry>> {
+        let span = self.span.clone();
+        async { traced(self.inner.next().await) }
+            .instrument(span)
+            .await
+    }
+}
+
+impl<R: oio::Delete> oio::Delete for Traced<R> {
+    fn delete(&mut self, path: &str, args: OpDelete) -> Result<()> {
+        let _entered = self.span.enter();
+        tracing::debug!(path, "queued for deletion");
+        self.inner.delete(path, args)
+    }
+
+    async fn flush(&mut self) -> Result<usize> {
+        let span = self.span.clone();
+        async { traced(self.inner.flush().await) }
+            .instrument(span)
+            .await
+    }
+}
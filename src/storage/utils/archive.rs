@@ -0,0 +1,493 @@
+//! Streaming tar archives for `get --archive` and `put --extract`.
+//!
+//! Entries are written and read one after another, so a prefix of any size becomes one
+//! archive (or an archive becomes objects) without staging its files on disk. Names longer
+//! than the ustar header allows, and files of 8 GiB or more, get a PAX extended header.
+
+use crate::error::{Error, Result};
+use crate::storage::utils::codec::Compression;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll, ready};
+use tokio::io::{AsyncRead, AsyncReadExt, ReadBuf, Take};
+
+const BLOCK_SIZE: usize = 512;
+/// Two zero blocks close an archive.
+pub const END_OF_ARCHIVE: [u8; 2 * BLOCK_SIZE] = [0; 2 * BLOCK_SIZE];
+/// Largest size an 11-digit octal header field holds.
+const MAX_OCTAL_SIZE: u64 = 0o77777777777;
+const NAME_LEN: usize = 100;
+
+/// Compression of the archive named `path`, from its extension.
+pub fn compression_for(path: &str) -> Result<Option<Compression>> {
+    let name = path.to_ascii_lowercase();
+    if name.ends_with(".tar") {
+        Ok(None)
+    } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        Ok(Some(Compression::Gzip))
+    } else if name.ends_with(".tar.zst") || name.ends_with(".tzst") {
+        Ok(Some(Compression::Zstd))
+    } else if name.ends_with(".zip") {
+        Err(Error::InvalidArgument {
+            message: format!(
+                "'{path}': zip archives are not supported; use .tar, .tar.gz or .tar.zst"
+            ),
+        })
+    } else {
+        Err(Error::InvalidArgument {
+            message: format!(
+                "'{path}' is not an archive name: use .tar, .tar.gz (.tgz) or .tar.zst (.tzst)"
+            ),
+        })
+    }
+}
+
+/// Bytes of zero padding after an entry body of `size` bytes.
+pub fn padding(size: u64) -> usize {
+    (BLOCK_SIZE - (size % BLOCK_SIZE as u64) as usize) % BLOCK_SIZE
+}
+
+/// A regular file to add to an archive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TarEntry {
+    /// Path inside the archive, with `/` separators
+    pub path: String,
+    pub size: u64,
+    /// Permission bits
+    pub mode: u32,
+    /// Modification time, in seconds since the Unix epoch
+    pub mtime: u64,
+}
+
+impl TarEntry {
+    /// Header blocks preceding the entry's body.
+    pub fn header(&self) -> Vec<u8> {
+        let mut records = String::new();
+        if self.path.len() > NAME_LEN {
+            records.push_str(&pax_record("path", &self.path));
+        }
+        if self.size > MAX_OCTAL_SIZE {
+            records.push_str(&pax_record("size", &self.size.to_string()));
+        }
+
+        let mut out = Vec::with_capacity(3 * BLOCK_SIZE);
+        if !records.is_empty() {
+            let pax_name = format!("PaxHeaders/{}", truncate_name(&self.path, NAME_LEN - 11));
+            out.extend(ustar_block(
+                &pax_name,
+                records.len() as u64,
+                0o644,
+                self.mtime,
+                b'x',
+            ));
+            out.extend_from_slice(records.as_bytes());
+            out.resize(out.len() + padding(records.len() as u64), 0);
+        }
+        let name = truncate_name(&self.path, NAME_LEN);
+        let size = self.size.min(MAX_OCTAL_SIZE);
+        out.extend(ustar_block(name, size, self.mode, self.mtime, b'0'));
+        out
+    }
+}
+
+/// `name` cut to at most `max` bytes on a character boundary.
+fn truncate_name(name: &str, max: usize) -> &str {
+    let mut end = name.len().min(max);
+    while !name.is_char_boundary(end) {
+        end -= 1;
+    }
+    &name[..end]
+}
+
+/// A PAX record, `"<length> <key>=<value>\n"`, where the length counts itself.
+fn pax_record(key: &str, value: &str) -> String {
+    let body = format!(" {key}={value}\n");
+    let mut len = body.len() + 1;
+    while len.to_string().len() + body.len() != len {
+        len = len.to_string().len() + body.len();
+    }
+    format!("{len}{body}")
+}
+
+fn ustar_block(name: &str, size: u64, mode: u32, mtime: u64, kind: u8) -> [u8; BLOCK_SIZE] {
+    let mut block = [0u8; BLOCK_SIZE];
+    block[..name.len()].copy_from_slice(name.as_bytes());
+    write_octal(&mut block[100..108], u64::from(mode & 0o7777));
+    write_octal(&mut block[108..116], 0);
+    write_octal(&mut block[116..124], 0);
+    write_octal(&mut block[124..136], size);
+    write_octal(&mut block[136..148], mtime.min(MAX_OCTAL_SIZE));
+    block[156] = kind;
+    block[257..263].copy_from_slice(b"ustar\0");
+    block[263..265].copy_from_slice(b"00");
+    block[148..156].fill(b' ');
+    let checksum: u32 = block.iter().map(|b| u32::from(*b)).sum();
+    block[148..155].copy_from_slice(format!("{checksum:06o}\0").as_bytes());
+    block
+}
+
+/// Zero-padded octal digits followed by a NUL, filling `field`.
+fn write_octal(field: &mut [u8], value: u64) {
+    let digits = field.len() - 1;
+    field[..digits].copy_from_slice(format!("{value:0digits$o}").as_bytes());
+    field[digits] = 0;
+}
+
+/// What an archive entry holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    File,
+    Directory,
+    /// Links, devices and other entries that have no object equivalent
+    Other,
+}
+
+/// An entry read from an archive; its body follows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntryHeader {
+    pub path: String,
+    pub size: u64,
+    pub kind: EntryKind,
+    pub mode: u32,
+    pub mtime: u64,
+}
+
+/// Reads the entries of a tar stream in order.
+pub struct TarReader<R> {
+    inner: R,
+    /// Unread bytes of the current entry: its body unless taken, then its padding
+    skip: u64,
+}
+
+impl<R: AsyncRead + Unpin> TarReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self { inner, skip: 0 }
+    }
+
+    /// The next entry, or `None` at the end of the archive. Unread parts of the previous
+    /// entry are skipped.
+    pub async fn next_entry(&mut self) -> Result<Option<EntryHeader>> {
+        let skip = std::mem::take(&mut self.skip);
+        self.discard(skip).await?;
+
+        let mut long_path = None;
+        let mut long_size = None;
+        loop {
+            let mut block = [0u8; BLOCK_SIZE];
+            if !self.read_block(&mut block).await? {
+                return Ok(None);
+            }
+            if block.iter().all(|b| *b == 0) {
+                return Ok(None);
+            }
+            verify_checksum(&block)?;
+            let size = parse_number(&block[124..136])?;
+            match block[156] {
+                b'x' | b'L' => {
+                    let data = self.read_data(size).await?;
+                    if block[156] == b'L' {
+                        long_path = Some(field_str(&data));
+                    } else {
+                        for (key, value) in parse_pax(&data)? {
+                            match key.as_str() {
+                                "path" => long_path = Some(value),
+                                "size" => {
+                                    long_size = Some(value.parse().map_err(|_| corrupt())?);
+                                }
+                                _ => {}
+                            }
+                        }
+                    }
+                }
+                b'g' => {
+                    self.discard(size + padding(size) as u64).await?;
+                }
+                kind => {
+                    let size = long_size.unwrap_or(size);
+                    let path = long_path.unwrap_or_else(|| {
+                        let name = field_str(&block[..100]);
+                        let prefix = field_str(&block[345..500]);
+                        if &block[257..262] == b"ustar" && !prefix.is_empty() {
+                            format!("{prefix}/{name}")
+                        } else {
+                            name
+                        }
+                    });
+                    let kind = match kind {
+                        b'0' | 0 | b'7' => EntryKind::File,
+                        b'5' => EntryKind::Directory,
+                        _ => EntryKind::Other,
+                    };
+                    // Only files carry a body; sizes of links and the like are ignored.
+                    let body = if kind == EntryKind::File { size } else { 0 };
+                    self.skip = body + padding(body) as u64;
+                    return Ok(Some(EntryHeader {
+                        path,
+                        size: body,
+                        kind,
+                        mode: parse_number(&block[100..108]).unwrap_or(0o644) as u32,
+                        mtime: parse_number(&block[136..148]).unwrap_or(0),
+                    }));
+                }
+            }
+        }
+    }
+
+    /// The body of the entry just returned by [`TarReader::next_entry`]; read it to the end
+    /// before asking for the next entry.
+    pub fn body(&mut self, size: u64) -> EntryBody<'_, R> {
+        self.skip = padding(size) as u64;
+        EntryBody {
+            inner: (&mut self.inner).take(size),
+        }
+    }
+
+    /// Fill `block`, or return false at a clean end of input.
+    async fn read_block(&mut self, block: &mut [u8; BLOCK_SIZE]) -> Result<bool> {
+        let mut filled = 0;
+        while filled < BLOCK_SIZE {
+            let read = self.inner.read(&mut block[filled..]).await?;
+            if read == 0 {
+                return if filled == 0 {
+                    Ok(false)
+                } else {
+                    Err(truncated())
+                };
+            }
+            filled += read;
+        }
+        Ok(true)
+    }
+
+    async fn read_data(&mut self, size: u64) -> Result<Vec<u8>> {
+        // Extended headers hold names and numbers; refuse to buffer anything bigger.
+        if size > 1024 * 1024 {
+            return Err(corrupt());
+        }
+        let mut data = vec![0u8; size as usize];
+        self.inner
+            .read_exact(&mut data)
+            .await
+            .map_err(|_| truncated())?;
+        self.discard(padding(size) as u64).await?;
+        Ok(data)
+    }
+
+    async fn discard(&mut self, len: u64) -> Result<()> {
+        let copied =
+            tokio::io::copy(&mut (&mut self.inner).take(len), &mut tokio::io::sink()).await?;
+        if copied < len {
+            return Err(truncated());
+        }
+        Ok(())
+    }
+}
+
+/// Body of an archive entry. Fails, rather than ending early, when the archive is cut
+/// short inside it, so a truncated archive never yields a shorter file.
+pub struct EntryBody<'a, R> {
+    inner: Take<&'a mut R>,
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for EntryBody<'_, R> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let inner = &mut self.get_mut().inner;
+        let before = buf.filled().len();
+        ready!(Pin::new(&mut *inner).poll_read(cx, buf))?;
+        if buf.filled().len() == before && buf.remaining() > 0 && inner.limit() > 0 {
+            return Poll::Ready(Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                truncated().to_string(),
+            )));
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+fn corrupt() -> Error {
+    Error::InvalidArgument {
+        message: "not a tar archive, or a corrupt one".to_string(),
+    }
+}
+
+fn truncated() -> Error {
+    Error::InvalidArgument {
+        message: "tar archive is truncated".to_string(),
+    }
+}
+
+fn verify_checksum(block: &[u8; BLOCK_SIZE]) -> Result<()> {
+    let stored = parse_number(&block[148..156])?;
+    let sum: u64 = block
+        .iter()
+        .enumerate()
+        .map(|(i, b)| {
+            if (148..156).contains(&i) {
+                u64::from(b' ')
+            } else {
+                u64::from(*b)
+            }
+        })
+        .sum();
+    if stored == sum {
+        Ok(())
+    } else {
+        Err(corrupt())
+    }
+}
+
+/// A numeric header field: octal text, or GNU base-256 when the high bit is set.
+fn parse_number(field: &[u8]) -> Result<u64> {
+    if field.first().is_some_and(|b| b & 0x80 != 0) {
+        let mut value = u64::from(field[0] & 0x7f);
+        for b in &field[1..] {
+            value = value.checked_mul(256).ok_or_else(corrupt)? + u64::from(*b);
+        }
+        return Ok(value);
+    }
+    let text = std::str::from_utf8(field).map_err(|_| corrupt())?;
+    let text = text.trim_matches(|c: char| c == '\0' || c == ' ');
+    if text.is_empty() {
+        return Ok(0);
+    }
+    u64::from_str_radix(text, 8).map_err(|_| corrupt())
+}
+
+/// A NUL-terminated text field.
+fn field_str(field: &[u8]) -> String {
+    let end = field.iter().position(|b| *b == 0).unwrap_or(field.len());
+    String::from_utf8_lossy(&field[..end]).into_owned()
+}
+
+fn parse_pax(data: &[u8]) -> Result<Vec<(String, String)>> {
+    let mut records = Vec::new();
+    let mut rest = data;
+    while !rest.is_empty() {
+        let space = rest.iter().position(|b| *b == b' ').ok_or_else(corrupt)?;
+        let len: usize = std::str::from_utf8(&rest[..space])
+            .ok()
+            .and_then(|len| len.parse().ok())
+            .filter(|len| *len > space && *len <= rest.len())
+            .ok_or_else(corrupt)?;
+        let record = String::from_utf8_lossy(&rest[space + 1..len - 1]).into_owned();
+        if let Some((key, value)) = record.split_once('=') {
+            records.push((key.to_string(), value.to_string()));
+        }
+        rest = &rest[len..];
+    }
+    Ok(records)
+}
+
+/// `path` from an archive as a relative key, or `None` when it is absolute or climbs
+/// out with `..`.
+pub fn safe_entry_path(path: &str) -> Option<String> {
+    if path.starts_with('/') {
+        return None;
+    }
+    let mut parts = Vec::new();
+    for part in path.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => return None,
+            part => parts.push(part),
+        }
+    }
+    (!parts.is_empty()).then(|| parts.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn archive(entries: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut out = Vec::new();
+        for (path, body) in entries {
+            let entry = TarEntry {
+                path: path.to_string(),
+                size: body.len() as u64,
+                mode: 0o640,
+                mtime: 1_700_000_000,
+            };
+            out.extend(entry.header());
+            out.extend_from_slice(body);
+            out.resize(out.len() + padding(body.len() as u64), 0);
+        }
+        out.extend_from_slice(&END_OF_ARCHIVE);
+        out
+    }
+
+    #[tokio::test]
+    async fn round_trips_entries_with_long_names() {
+        let long = format!("{}/file.txt", "d".repeat(150));
+        let data = archive(&[("a.txt", b"hello"), (&long, b""), ("b/c", &[7u8; 1000])]).await;
+        assert_eq!(data.len() % BLOCK_SIZE, 0);
+
+        let mut reader = TarReader::new(data.as_slice());
+        let first = reader.next_entry().await.unwrap().unwrap();
+        assert_eq!(
+            first,
+            EntryHeader {
+                path: "a.txt".to_string(),
+                size: 5,
+                kind: EntryKind::File,
+                mode: 0o640,
+                mtime: 1_700_000_000,
+            }
+        );
+        let mut body = Vec::new();
+        reader
+            .body(first.size)
+            .read_to_end(&mut body)
+            .await
+            .unwrap();
+        assert_eq!(body, b"hello");
+        // The long name survives; the last body is skipped without being read.
+        assert_eq!(reader.next_entry().await.unwrap().unwrap().path, long);
+        assert_eq!(reader.next_entry().await.unwrap().unwrap().size, 1000);
+        assert!(reader.next_entry().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn rejects_corrupt_and_truncated_archives() {
+        let data = archive(&[("a.txt", &[1u8; 600])]).await;
+        let mut corrupt = data.clone();
+        corrupt[10] ^= 1;
+        assert!(
+            TarReader::new(corrupt.as_slice())
+                .next_entry()
+                .await
+                .is_err()
+        );
+
+        let mut reader = TarReader::new(&data[..BLOCK_SIZE + 100]);
+        reader.next_entry().await.unwrap();
+        assert!(reader.next_entry().await.is_err());
+
+        let mut reader = TarReader::new(&data[..BLOCK_SIZE + 100]);
+        let entry = reader.next_entry().await.unwrap().unwrap();
+        let mut body = Vec::new();
+        let read = reader.body(entry.size).read_to_end(&mut body).await;
+        assert_eq!(read.unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    #[test]
+    fn names_archives_and_entries() {
+        assert_eq!(compression_for("a.tar").unwrap(), None);
+        assert_eq!(compression_for("a.TGZ").unwrap(), Some(Compression::Gzip));
+        assert_eq!(
+            compression_for("a.tar.zst").unwrap(),
+            Some(Compression::Zstd)
+        );
+        assert!(compression_for("a.zip").is_err());
+        assert!(compression_for("a.txt").is_err());
+        assert_eq!(safe_entry_path("./x//y/"), Some("x/y".to_string()));
+        assert_eq!(safe_entry_path("/etc/passwd"), None);
+        assert_eq!(safe_entry_path("a/../../b"), None);
+        assert_eq!(pax_record("path", "x"), "9 path=x\n");
+        assert_eq!(pax_record("path", &"x".repeat(95)).len(), 105);
+    }
+}
@@ -0,0 +1,329 @@
+//! Client-side encryption of object contents.
+//!
+//! Objects written by `put --encrypt` start with a header — the magic bytes, the chunk size
+//! and a random nonce prefix — followed by the content in ChaCha20Poly1305-sealed chunks.
+//! Each chunk's nonce is the prefix, the chunk number and a flag marking the final chunk,
+//! so chunks cannot be reordered and a truncated object fails to decrypt instead of
+//! yielding a shorter file. The header is authenticated with every chunk.
+
+use crate::config::crypto::DataKey;
+use crate::error::{Error, Result};
+use chacha20poly1305::aead::{Aead, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Nonce};
+use futures::{Stream, StreamExt};
+use opendal::Operator;
+use rand::Rng;
+use std::io;
+
+/// Leading bytes of an encrypted object.
+const MAGIC: &[u8; 8] = b"STFYENC\x01";
+const PREFIX_LEN: usize = 7;
+/// Magic, chunk size and nonce prefix.
+pub const HEADER_LEN: usize = MAGIC.len() + 4 + PREFIX_LEN;
+const TAG_LEN: usize = 16;
+/// Plaintext bytes per sealed chunk.
+const CHUNK_SIZE: usize = 64 * 1024;
+/// Largest chunk size accepted from a header, to bound memory on corrupt input.
+const MAX_CHUNK_SIZE: usize = 16 * 1024 * 1024;
+
+/// Whether `bytes` (the start of an object) carry the encryption header.
+pub fn is_encrypted(bytes: &[u8]) -> bool {
+    bytes.starts_with(MAGIC)
+}
+
+/// Whether the object at `path`, of `size` bytes, was written by `put --encrypt`.
+pub async fn is_encrypted_object(operator: &Operator, path: &str, size: u64) -> Result<bool> {
+    if size < HEADER_LEN as u64 {
+        return Ok(false);
+    }
+    let head = operator.read_with(path).range(0..HEADER_LEN as u64).await?;
+    Ok(is_encrypted(&head.to_vec()))
+}
+
+/// Error for reading an encrypted object without a data key.
+pub fn missing_key(path: &str) -> Error {
+    Error::ObjectEncryption {
+        message: format!(
+            "'{path}' is encrypted; set the profile's data_key (or STORAGE_DATA_KEY) to read it"
+        ),
+    }
+}
+
+fn nonce(prefix: &[u8], counter: u32, last: bool) -> Nonce {
+    let mut nonce = [0u8; 12];
+    nonce[..PREFIX_LEN].copy_from_slice(prefix);
+    nonce[PREFIX_LEN..PREFIX_LEN + 4].copy_from_slice(&counter.to_be_bytes());
+    nonce[11] = u8::from(last);
+    *Nonce::from_slice(&nonce)
+}
+
+fn next_counter(counter: &mut u32) -> Result<u32> {
+    let current = *counter;
+    *counter = counter
+        .checked_add(1)
+        .ok_or_else(|| Error::ObjectEncryption {
+            message: "object too large to encrypt".to_string(),
+        })?;
+    Ok(current)
+}
+
+/// Encrypts a stream fed in pieces of any size.
+pub struct Sealer {
+    cipher: ChaCha20Poly1305,
+    header: [u8; HEADER_LEN],
+    header_written: bool,
+    counter: u32,
+    pending: Vec<u8>,
+}
+
+impl Sealer {
+    pub fn new(key: &DataKey) -> Self {
+        let mut header = [0u8; HEADER_LEN];
+        header[..MAGIC.len()].copy_from_slice(MAGIC);
+        header[MAGIC.len()..MAGIC.len() + 4].copy_from_slice(&(CHUNK_SIZE as u32).to_be_bytes());
+        let prefix: [u8; PREFIX_LEN] = rand::rng().random();
+        header[MAGIC.len() + 4..].copy_from_slice(&prefix);
+        Self {
+            cipher: key.cipher(),
+            header,
+            header_written: false,
+            counter: 0,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Encrypt `data`, returning what is ready to be written. The last chunk is held back
+    /// until [`Sealer::finish`].
+    pub fn update(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        let mut out = self.take_header();
+        self.pending.extend_from_slice(data);
+        let mut start = 0;
+        while self.pending.len() - start > CHUNK_SIZE {
+            let end = start + CHUNK_SIZE;
+            let sealed = self.seal(start..end, false)?;
+            out.extend_from_slice(&sealed);
+            start = end;
+        }
+        self.pending.drain(..start);
+        Ok(out)
+    }
+
+    /// Encrypt the final chunk; an empty input still produces a header and final chunk.
+    pub fn finish(mut self) -> Result<Vec<u8>> {
+        let mut out = self.take_header();
+        let len = self.pending.len();
+        out.extend_from_slice(&self.seal(0..len, true)?);
+        Ok(out)
+    }
+
+    fn take_header(&mut self) -> Vec<u8> {
+        if std::mem::replace(&mut self.header_written, true) {
+            Vec::new()
+        } else {
+            self.header.to_vec()
+        }
+    }
+
+    fn seal(&mut self, range: std::ops::Range<usize>, last: bool) -> Result<Vec<u8>> {
+        let counter = next_counter(&mut self.counter)?;
+        let nonce = nonce(&self.header[MAGIC.len() + 4..], counter, last);
+        self.cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: &self.pending[range],
+                    aad: &self.header,
+                },
+            )
+            .map_err(|e| Error::ObjectEncryption {
+                message: format!("encryption failed: {e}"),
+            })
+    }
+}
+
+/// Decrypts a stream written by [`Sealer`], fed in pieces of any size.
+pub struct Opener {
+    cipher: ChaCha20Poly1305,
+    header: Option<[u8; HEADER_LEN]>,
+    chunk_size: usize,
+    counter: u32,
+    pending: Vec<u8>,
+}
+
+impl Opener {
+    pub fn new(key: &DataKey) -> Self {
+        Self {
+            cipher: key.cipher(),
+            header: None,
+            chunk_size: 0,
+            counter: 0,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Decrypt `data`, returning the plaintext of every chunk known not to be the last.
+    pub fn update(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        self.pending.extend_from_slice(data);
+        if self.header.is_none() {
+            if self.pending.len() < HEADER_LEN {
+                return Ok(Vec::new());
+            }
+            self.read_header()?;
+        }
+        let sealed_len = self.chunk_size + TAG_LEN;
+        let mut out = Vec::new();
+        let mut start = 0;
+        while self.pending.len() - start > sealed_len {
+            let end = start + sealed_len;
+            out.extend_from_slice(&self.open(start..end, false)?);
+            start = end;
+        }
+        self.pending.drain(..start);
+        Ok(out)
+    }
+
+    /// Decrypt the final chunk, failing when the object was cut short.
+    pub fn finish(mut self) -> Result<Vec<u8>> {
+        if self.header.is_none() {
+            if self.pending.len() < HEADER_LEN {
+                return Err(Error::ObjectEncryption {
+                    message: "object is not encrypted by storify or is truncated".to_string(),
+                });
+            }
+            self.read_header()?;
+        }
+        let len = self.pending.len();
+        self.open(0..len, true)
+    }
+
+    fn read_header(&mut self) -> Result<()> {
+        if !is_encrypted(&self.pending) {
+            return Err(Error::ObjectEncryption {
+                message: "object is not encrypted by storify".to_string(),
+            });
+        }
+        let mut header = [0u8; HEADER_LEN];
+        header.copy_from_slice(&self.pending[..HEADER_LEN]);
+        let size_bytes: [u8; 4] = header[MAGIC.len()..MAGIC.len() + 4]
+            .try_into()
+            .expect("four bytes");
+        let chunk_size = u32::from_be_bytes(size_bytes) as usize;
+        if chunk_size == 0 || chunk_size > MAX_CHUNK_SIZE {
+            return Err(Error::ObjectEncryption {
+                message: format!("invalid chunk size {chunk_size} in encryption header"),
+            });
+        }
+        self.pending.drain(..HEADER_LEN);
+        self.header = Some(header);
+        self.chunk_size = chunk_size;
+        Ok(())
+    }
+
+    fn open(&mut self, range: std::ops::Range<usize>, last: bool) -> Result<Vec<u8>> {
+        let header = self.header.expect("header read before chunks");
+        let counter = next_counter(&mut self.counter)?;
+        let nonce = nonce(&header[MAGIC.len() + 4..], counter, last);
+        self.cipher
+            .decrypt(
+                &nonce,
+                Payload {
+                    msg: &self.pending[range],
+                    aad: &header,
+                },
+            )
+            .map_err(|_| Error::ObjectEncryption {
+                message: "decryption failed: wrong data key, or the object is truncated or \
+                          was modified"
+                    .to_string(),
+            })
+    }
+}
+
+/// Decrypt an object's byte stream as it arrives.
+pub fn decrypt_stream<B, S>(
+    input: S,
+    key: &DataKey,
+) -> impl Stream<Item = io::Result<Vec<u8>>> + use<B, S>
+where
+    B: AsRef<[u8]>,
+    S: Stream<Item = io::Result<B>> + Unpin,
+{
+    let to_io = |e: Error| io::Error::new(io::ErrorKind::InvalidData, e.to_string());
+    futures::stream::unfold(Some((input, Opener::new(key))), move |state| async move {
+        let (mut input, mut opener) = state?;
+        loop {
+            match input.next().await {
+                Some(Ok(chunk)) => match opener.update(chunk.as_ref()) {
+                    Ok(plain) if plain.is_empty() => continue,
+                    Ok(plain) => return Some((Ok(plain), Some((input, opener)))),
+                    Err(e) => return Some((Err(to_io(e)), None)),
+                },
+                Some(Err(e)) => return Some((Err(e), None)),
+                None => return Some((opener.finish().map_err(to_io), None)),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seal(key: &DataKey, data: &[u8], piece: usize) -> Vec<u8> {
+        let mut sealer = Sealer::new(key);
+        let mut out = Vec::new();
+        for chunk in data.chunks(piece.max(1)) {
+            out.extend(sealer.update(chunk).unwrap());
+        }
+        out.extend(sealer.finish().unwrap());
+        out
+    }
+
+    fn open(key: &DataKey, sealed: &[u8], piece: usize) -> Result<Vec<u8>> {
+        let mut opener = Opener::new(key);
+        let mut out = Vec::new();
+        for chunk in sealed.chunks(piece.max(1)) {
+            out.extend(opener.update(chunk)?);
+        }
+        out.extend(opener.finish()?);
+        Ok(out)
+    }
+
+    #[test]
+    fn round_trips_in_any_piece_size() {
+        let key = DataKey::generate();
+        let data: Vec<u8> = (0..CHUNK_SIZE * 2 + 123).map(|i| i as u8).collect();
+        for size in [0, 10, CHUNK_SIZE, CHUNK_SIZE * 2, data.len()] {
+            let sealed = seal(&key, &data[..size], 7000);
+            assert!(is_encrypted(&sealed));
+            assert_eq!(open(&key, &sealed, 999).unwrap(), &data[..size]);
+            assert_eq!(open(&key, &sealed, sealed.len()).unwrap(), &data[..size]);
+        }
+    }
+
+    #[test]
+    fn rejects_wrong_keys_truncation_and_tampering() {
+        let key = DataKey::generate();
+        let data = vec![7u8; CHUNK_SIZE + 10];
+        let sealed = seal(&key, &data, 4096);
+        assert!(open(&DataKey::generate(), &sealed, 4096).is_err());
+        // Dropping the final chunk leaves a complete but non-final chunk at the end.
+        assert!(open(&key, &sealed[..HEADER_LEN + CHUNK_SIZE + TAG_LEN], 4096).is_err());
+        let mut tampered = sealed.clone();
+        tampered[HEADER_LEN + 5] ^= 1;
+        assert!(open(&key, &tampered, 4096).is_err());
+        assert!(open(&key, b"plain text", 4096).is_err());
+    }
+
+    #[tokio::test]
+    async fn decrypts_a_byte_stream() {
+        let key = DataKey::generate();
+        let sealed = seal(&key, b"hello, world", 3);
+        let pieces: Vec<io::Result<Vec<u8>>> = sealed.chunks(5).map(|c| Ok(c.to_vec())).collect();
+        let plain: Vec<Vec<u8>> = decrypt_stream(futures::stream::iter(pieces), &key)
+            .map(|chunk| chunk.unwrap())
+            .collect()
+            .await;
+        assert_eq!(plain.concat(), b"hello, world");
+    }
+}
@@ -0,0 +1,82 @@
+// What cp, mv and put do with destination objects that already exist
+use crate::error::Result;
+use opendal::{ErrorKind, Operator};
+use std::fmt;
+use std::sync::Arc;
+
+/// Asks whether the existing object at the given path may be replaced.
+pub type ConfirmOverwrite = Arc<dyn Fn(&str) -> bool + Send + Sync>;
+
+/// Treatment of destination objects that already exist.
+#[derive(Clone, Default)]
+pub enum Clobber {
+    /// Replace them (`--force`, and the default)
+    #[default]
+    Overwrite,
+    /// Leave them alone and skip their sources (`--no-clobber`)
+    Skip,
+    /// Ask before replacing each one (`--interactive`)
+    Ask(ConfirmOverwrite),
+}
+
+impl fmt::Debug for Clobber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Clobber::Overwrite => f.write_str("Overwrite"),
+            Clobber::Skip => f.write_str("Skip"),
+            Clobber::Ask(_) => f.write_str("Ask"),
+        }
+    }
+}
+
+impl Clobber {
+    /// Whether `dest` may be written. Existence is only checked when it matters, and a
+    /// source that may not be written is reported as skipped.
+    pub async fn allows(&self, operator: &Operator, source: &str, dest: &str) -> Result<bool> {
+        if matches!(self, Clobber::Overwrite) {
+            return Ok(true);
+        }
+        let exists = match operator.stat(dest).await {
+            Ok(meta) => !meta.is_dir(),
+            Err(e) if e.kind() == ErrorKind::NotFound => false,
+            Err(e) => return Err(e.into()),
+        };
+        let allowed = match self {
+            _ if !exists => true,
+            Clobber::Overwrite => true,
+            Clobber::Skip => false,
+            Clobber::Ask(confirm) => confirm(dest),
+        };
+        if !allowed {
+            println!("Skipped: {source} ({dest} exists)");
+        }
+        Ok(allowed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opendal::services::Memory;
+
+    #[tokio::test]
+    async fn checks_existing_destinations_only() {
+        let operator = Operator::new(Memory::default()).unwrap().finish();
+        operator.write("taken", "old").await.unwrap();
+
+        assert!(Clobber::Skip.allows(&operator, "a", "free").await.unwrap());
+        assert!(!Clobber::Skip.allows(&operator, "a", "taken").await.unwrap());
+        assert!(
+            Clobber::Overwrite
+                .allows(&operator, "a", "taken")
+                .await
+                .unwrap()
+        );
+
+        let asked = Clobber::Ask(Arc::new(|dest| dest == "taken"));
+        assert!(asked.allows(&operator, "a", "taken").await.unwrap());
+        let refused = Clobber::Ask(Arc::new(|_| false));
+        assert!(refused.allows(&operator, "a", "free").await.unwrap());
+        assert!(!refused.allows(&operator, "a", "taken").await.unwrap());
+    }
+}
@@ -0,0 +1,267 @@
+//! Streaming gzip and zstd compression for transfers.
+//!
+//! Data is piped through the system `gzip` or `zstd` program, so nothing is held in memory
+//! beyond the pipe buffers. A [`CodecReader`] yields the program's output and fails at the
+//! end of the stream when the program did not exit cleanly, so a corrupt input or a missing
+//! tool can never pass for a short but complete transfer.
+
+use crate::error::{Error, Result};
+use clap::ValueEnum;
+use futures::{Stream, StreamExt};
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::process::Stdio;
+use std::task::{Context, Poll, ready};
+use tokio::io::{AsyncRead, AsyncWriteExt, ReadBuf};
+use tokio::process::{ChildStdin, ChildStdout, Command};
+
+/// Compression formats `put --compress` writes and `get`/`cat` decompress.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// Extension appended to the keys of compressed uploads.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Compression::Gzip => ".gz",
+            Compression::Zstd => ".zst",
+        }
+    }
+
+    /// `Content-Encoding` stored on compressed uploads.
+    pub fn content_encoding(self) -> &'static str {
+        match self {
+            Compression::Gzip => "gzip",
+            Compression::Zstd => "zstd",
+        }
+    }
+
+    /// Format of an object, from its content encoding or else its key's extension.
+    pub fn detect(path: &str, content_encoding: Option<&str>) -> Option<Self> {
+        let by_encoding = content_encoding.and_then(|encoding| {
+            [Compression::Gzip, Compression::Zstd]
+                .into_iter()
+                .find(|c| encoding.trim().eq_ignore_ascii_case(c.content_encoding()))
+        });
+        by_encoding.or_else(|| Self::from_extension(path))
+    }
+
+    /// Format named by the extension of `path`.
+    pub fn from_extension(path: &str) -> Option<Self> {
+        [Compression::Gzip, Compression::Zstd]
+            .into_iter()
+            .find(|c| path.ends_with(c.extension()))
+    }
+
+    /// `path` with this format's extension added, unless it already has it.
+    pub fn add_extension(self, path: &str) -> String {
+        if path.ends_with(self.extension()) || path.is_empty() || path.ends_with('/') {
+            path.to_string()
+        } else {
+            format!("{path}{}", self.extension())
+        }
+    }
+
+    /// `path` without this format's extension, or unchanged when it does not have it.
+    pub fn strip_extension(self, path: &str) -> &str {
+        match path.strip_suffix(self.extension()) {
+            Some(stripped) if !stripped.is_empty() && !stripped.ends_with('/') => stripped,
+            _ => path,
+        }
+    }
+
+    fn program(self) -> &'static str {
+        match self {
+            Compression::Gzip => "gzip",
+            Compression::Zstd => "zstd",
+        }
+    }
+
+    fn args(self, decompress: bool) -> &'static [&'static str] {
+        match (self, decompress) {
+            (Compression::Gzip, false) => &["-c"],
+            (Compression::Gzip, true) => &["-d", "-c"],
+            (Compression::Zstd, false) => &["-q", "-c"],
+            (Compression::Zstd, true) => &["-q", "-d", "-c"],
+        }
+    }
+
+    /// Compress everything `input` yields.
+    pub fn compress(
+        self,
+        mut input: impl AsyncRead + Unpin + Send + 'static,
+    ) -> Result<CodecReader> {
+        self.spawn(false, |mut stdin| async move {
+            tokio::io::copy(&mut input, &mut stdin).await?;
+            stdin.shutdown().await
+        })
+    }
+
+    /// Decompress everything `input` yields, such as a local file.
+    pub fn decompress_reader(
+        self,
+        mut input: impl AsyncRead + Unpin + Send + 'static,
+    ) -> Result<CodecReader> {
+        self.spawn(true, |mut stdin| async move {
+            tokio::io::copy(&mut input, &mut stdin).await?;
+            stdin.shutdown().await
+        })
+    }
+
+    /// Decompress the chunks of `input`, such as an object's byte stream.
+    pub fn decompress<B, S>(self, mut input: S) -> Result<CodecReader>
+    where
+        B: AsRef<[u8]> + Send,
+        S: Stream<Item = io::Result<B>> + Unpin + Send + 'static,
+    {
+        self.spawn(true, |mut stdin| async move {
+            while let Some(chunk) = input.next().await {
+                stdin.write_all(chunk?.as_ref()).await?;
+            }
+            stdin.shutdown().await
+        })
+    }
+
+    fn spawn<F, Fut>(self, decompress: bool, feed: F) -> Result<CodecReader>
+    where
+        F: FnOnce(ChildStdin) -> Fut,
+        Fut: Future<Output = io::Result<()>> + Send + 'static,
+    {
+        let program = self.program();
+        let mut child = Command::new(program)
+            .args(self.args(decompress))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| Error::InvalidArgument {
+                message: format!(
+                    "{} needs the '{program}' program: {e}",
+                    self.content_encoding()
+                ),
+            })?;
+        let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            return Err(Error::InvalidArgument {
+                message: format!("could not connect to '{program}'"),
+            });
+        };
+        let feeder = tokio::spawn(feed(stdin));
+        let done = async move {
+            let output = child.wait_with_output().await?;
+            let fed = feeder.await.map_err(io::Error::other)?;
+            if !output.status.success() {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                return Err(io::Error::other(format!(
+                    "{program} failed ({}): {}",
+                    output.status,
+                    stderr.trim()
+                )));
+            }
+            match fed {
+                // The program exited successfully without wanting the rest of the input.
+                Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+                fed => fed,
+            }
+        };
+        Ok(CodecReader {
+            stdout,
+            done: Some(Box::pin(done)),
+        })
+    }
+}
+
+/// Output of a running `gzip`/`zstd`; dropping it stops the program.
+pub struct CodecReader {
+    stdout: ChildStdout,
+    /// Waits for the program and its input, surfacing their errors at end of stream
+    done: Option<Pin<Box<dyn Future<Output = io::Result<()>> + Send>>>,
+}
+
+impl AsyncRead for CodecReader {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let before = buf.filled().len();
+        ready!(Pin::new(&mut this.stdout).poll_read(cx, buf))?;
+        if buf.filled().len() > before {
+            return Poll::Ready(Ok(()));
+        }
+        match this.done.as_mut() {
+            Some(done) => {
+                let result = ready!(done.as_mut().poll(cx));
+                this.done = None;
+                Poll::Ready(result)
+            }
+            None => Poll::Ready(Ok(())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncReadExt;
+
+    #[test]
+    fn names_and_detects_formats() {
+        assert_eq!(
+            Compression::Gzip.add_extension("logs/a.log"),
+            "logs/a.log.gz"
+        );
+        assert_eq!(Compression::Zstd.add_extension("a.zst"), "a.zst");
+        assert_eq!(Compression::Gzip.strip_extension("a.log.gz"), "a.log");
+        assert_eq!(Compression::Gzip.strip_extension("dir/.gz"), "dir/.gz");
+        assert_eq!(
+            Compression::detect("a.bin", Some("zstd")),
+            Some(Compression::Zstd)
+        );
+        assert_eq!(Compression::detect("a.gz", None), Some(Compression::Gzip));
+        assert_eq!(Compression::detect("a.txt", Some("identity")), None);
+    }
+
+    #[tokio::test]
+    async fn round_trips_through_the_program() {
+        let data = b"storify ".repeat(10_000);
+        for compression in [Compression::Gzip, Compression::Zstd] {
+            // Skip formats whose program is not installed.
+            let Ok(mut encoder) = compression.compress(std::io::Cursor::new(data.clone())) else {
+                continue;
+            };
+            let mut compressed = Vec::new();
+            encoder.read_to_end(&mut compressed).await.unwrap();
+            assert!(compressed.len() < data.len());
+
+            let chunks = futures::stream::iter(
+                compressed
+                    .chunks(1000)
+                    .map(|chunk| Ok(chunk.to_vec()))
+                    .collect::<Vec<_>>(),
+            );
+            let mut decoded = Vec::new();
+            compression
+                .decompress(chunks)
+                .unwrap()
+                .read_to_end(&mut decoded)
+                .await
+                .unwrap();
+            assert_eq!(decoded, data);
+
+            let garbage = futures::stream::iter([Ok(b"not compressed".to_vec())]);
+            let mut out = Vec::new();
+            let decoded = compression
+                .decompress(garbage)
+                .unwrap()
+                .read_to_end(&mut out)
+                .await;
+            assert!(decoded.is_err());
+        }
+    }
+}
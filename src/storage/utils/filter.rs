@@ -0,0 +1,57 @@
+// Include/exclude glob filtering of relative paths
+use crate::error::{Error, Result};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+
+/// Selects paths (relative to a transfer root) by include and exclude globs.
+///
+/// A path passes when it matches any include pattern (or there are none) and no exclude
+/// pattern. `*` also matches `/`, so `*.log` selects logs at any depth.
+#[derive(Debug, Clone, Default)]
+pub struct PathFilter {
+    include: Option<GlobSet>,
+    exclude: Option<GlobSet>,
+}
+
+impl PathFilter {
+    pub fn new(include: &[String], exclude: &[String]) -> Result<Self> {
+        Ok(Self {
+            include: build_set(include)?,
+            exclude: build_set(exclude)?,
+        })
+    }
+
+    /// Whether no patterns were given, so every path passes.
+    pub fn is_empty(&self) -> bool {
+        self.include.is_none() && self.exclude.is_none()
+    }
+
+    pub fn matches(&self, relative_path: &str) -> bool {
+        let path = relative_path.trim_start_matches('/');
+        self.include.as_ref().is_none_or(|set| set.is_match(path)) && !self.excludes(path)
+    }
+
+    /// Whether an exclude pattern matches, e.g. to skip a whole directory.
+    pub fn excludes(&self, relative_path: &str) -> bool {
+        let path = relative_path.trim_start_matches('/');
+        self.exclude.as_ref().is_some_and(|set| set.is_match(path))
+    }
+}
+
+fn build_set(patterns: &[String]) -> Result<Option<GlobSet>> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let glob = Glob::new(pattern).map_err(|e| Error::InvalidArgument {
+            message: format!("invalid glob '{pattern}': {e}"),
+        })?;
+        builder.add(glob);
+    }
+    builder
+        .build()
+        .map(Some)
+        .map_err(|e| Error::InvalidArgument {
+            message: e.to_string(),
+        })
+}
@@ -0,0 +1,223 @@
+// `--format` templates such as `{path}\t{size}` for ls, find, stat and du
+use crate::error::{Error, Result};
+use crate::storage::utils::size::format_size;
+use chrono::{DateTime, Utc};
+use std::borrow::Cow;
+
+/// A record whose fields can be printed through a [`Template`].
+pub trait Fields {
+    /// Field names a template may use.
+    const NAMES: &'static [&'static str];
+
+    /// Value of the field `name`, or `None` when the record has no value for it.
+    fn field(&self, name: &str) -> Option<String>;
+}
+
+/// A parsed `--format` string.
+///
+/// `{name}` is replaced by a field, `{{` and `}}` stand for literal braces, and `\t`, `\n`
+/// and `\\` are unescaped so templates can be passed in single quotes. Fields without a
+/// value render as an empty string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Literal(String),
+    Field(String),
+}
+
+impl Template {
+    /// Parse `text`, accepting only the fields of `R`.
+    pub fn parse<R: Fields>(text: &str) -> Result<Self> {
+        let invalid = |message: String| Error::InvalidArgument {
+            message: format!("invalid --format '{text}': {message}"),
+        };
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => return Err(invalid("unclosed '{'".to_string())),
+                        }
+                    }
+                    let name = name.trim();
+                    if !R::NAMES.contains(&name) {
+                        return Err(invalid(format!(
+                            "unknown field '{name}' (available: {})",
+                            R::NAMES.join(", ")
+                        )));
+                    }
+                    if !literal.is_empty() {
+                        parts.push(Part::Literal(std::mem::take(&mut literal)));
+                    }
+                    parts.push(Part::Field(name.to_string()));
+                }
+                '}' => {
+                    return Err(invalid(
+                        "unmatched '}' (write '}}' for a brace)".to_string(),
+                    ));
+                }
+                '\\' => match chars.peek() {
+                    Some('t') => {
+                        chars.next();
+                        literal.push('\t');
+                    }
+                    Some('n') => {
+                        chars.next();
+                        literal.push('\n');
+                    }
+                    Some('\\') => {
+                        chars.next();
+                        literal.push('\\');
+                    }
+                    _ => literal.push('\\'),
+                },
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+        Ok(Self { parts })
+    }
+
+    /// Whether any of `names` appears in the template, to skip fetching unused metadata.
+    pub fn uses_any(&self, names: &[&str]) -> bool {
+        self.parts
+            .iter()
+            .any(|part| matches!(part, Part::Field(name) if names.contains(&name.as_str())))
+    }
+
+    /// Fill the template in with the fields of `record`.
+    pub fn render(&self, record: &impl Fields) -> String {
+        let mut out = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Literal(text) => out.push_str(text),
+                Part::Field(name) => {
+                    if let Some(value) = record.field(name) {
+                        out.push_str(&value);
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+/// A listed path, as printed by `ls --format` and `find --format`.
+#[derive(Debug, Clone)]
+pub struct EntryFields<'a> {
+    pub path: &'a str,
+    pub is_dir: bool,
+    pub size: u64,
+    pub last_modified: Option<DateTime<Utc>>,
+    pub storage_class: Option<&'a str>,
+    /// Levels below the listed path (`find` only)
+    pub depth: Option<usize>,
+}
+
+impl EntryFields<'_> {
+    /// Fields that need entry metadata, which listings do not always return.
+    pub const METADATA: &'static [&'static str] = &["size", "human_size", "last_modified"];
+}
+
+impl Fields for EntryFields<'_> {
+    const NAMES: &'static [&'static str] = &[
+        "path",
+        "name",
+        "type",
+        "size",
+        "human_size",
+        "last_modified",
+        "storage_class",
+        "depth",
+    ];
+
+    fn field(&self, name: &str) -> Option<String> {
+        match name {
+            "path" => Some(self.path.to_string()),
+            "name" => self
+                .path
+                .trim_end_matches('/')
+                .rsplit('/')
+                .next()
+                .map(str::to_string),
+            "type" => Some(if self.is_dir { "dir" } else { "file" }.to_string()),
+            "size" => Some(self.size.to_string()),
+            "human_size" => Some(format_size(self.size)),
+            "last_modified" => self.last_modified.map(|t| t.to_rfc3339()),
+            "storage_class" => self.storage_class.map(str::to_string),
+            "depth" => self.depth.map(|depth| depth.to_string()),
+            _ => None,
+        }
+    }
+}
+
+/// Quote a CSV field when it contains a separator, quote or line break.
+pub fn csv_field(value: &str) -> Cow<'_, str> {
+    if value.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", value.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(path: &str) -> EntryFields<'_> {
+        EntryFields {
+            path,
+            is_dir: path.ends_with('/'),
+            size: 2048,
+            last_modified: DateTime::from_timestamp(0, 0),
+            storage_class: None,
+            depth: None,
+        }
+    }
+
+    #[test]
+    fn renders_fields_escapes_and_braces() {
+        let template =
+            Template::parse::<EntryFields>(r"{path}\t{size}\t{ human_size }\n{{{name}}}\x")
+                .unwrap();
+        assert_eq!(
+            template.render(&entry("logs/a.log")),
+            "logs/a.log\t2048\t2.0K\n{a.log}\\x"
+        );
+        assert!(template.uses_any(EntryFields::METADATA));
+
+        let template = Template::parse::<EntryFields>("{name} {type} {storage_class}").unwrap();
+        assert_eq!(template.render(&entry("logs/2024/")), "2024 dir ");
+        assert!(!template.uses_any(EntryFields::METADATA));
+        assert_eq!(
+            Template::parse::<EntryFields>("{last_modified}")
+                .unwrap()
+                .render(&entry("a")),
+            "1970-01-01T00:00:00+00:00"
+        );
+
+        for bad in ["{path", "path}", "{bogus}"] {
+            assert!(Template::parse::<EntryFields>(bad).is_err(), "{bad}");
+        }
+    }
+}
@@ -0,0 +1,88 @@
+// Expansion of remote path globs such as `logs/*.txt` into object keys
+use crate::error::{Error, Result};
+use futures::stream::TryStreamExt;
+use globset::GlobBuilder;
+use opendal::{ErrorKind, Operator};
+
+/// Whether `pattern` holds any glob syntax (`*`, `?`, `[...]` or `{...}`).
+pub fn is_glob(pattern: &str) -> bool {
+    pattern.contains(['*', '?', '[', '{'])
+}
+
+/// Directory a glob must be listed from: its components up to the first one with glob
+/// syntax, and whether anything deeper than that directory can match.
+fn list_root(pattern: &str) -> (String, bool) {
+    let components: Vec<&str> = pattern.split('/').collect();
+    let literal = components.iter().take_while(|c| !is_glob(c)).count();
+    let root: String = components[..literal]
+        .iter()
+        .map(|c| format!("{c}/"))
+        .collect();
+    let recursive = components.len() - literal > 1 || pattern.contains("**");
+    (root, recursive)
+}
+
+/// Files whose keys match `pattern`, sorted. `*`, `?` and `[...]` stay within one path
+/// component and `**` crosses them. A pattern without glob syntax names a single file.
+pub async fn expand(operator: &Operator, pattern: &str) -> Result<Vec<String>> {
+    let pattern = pattern.trim_start_matches('/');
+    if !is_glob(pattern) {
+        return match operator.stat(pattern).await {
+            Ok(meta) if !meta.is_dir() => Ok(vec![pattern.to_string()]),
+            Ok(_) => Ok(Vec::new()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(e.into()),
+        };
+    }
+    let matcher = GlobBuilder::new(pattern)
+        .literal_separator(true)
+        .build()
+        .map_err(|e| Error::InvalidArgument {
+            message: format!("invalid glob '{pattern}': {e}"),
+        })?
+        .compile_matcher();
+
+    let (root, recursive) = list_root(pattern);
+    let mut lister = match operator.lister_with(&root).recursive(recursive).await {
+        Ok(lister) => lister,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut keys = Vec::new();
+    while let Some(entry) = lister.try_next().await? {
+        let key = entry.path().trim_start_matches('/');
+        if !entry.metadata().is_dir() && matcher.is_match(key) {
+            keys.push(key.to_string());
+        }
+    }
+    keys.sort();
+    Ok(keys)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opendal::services::Memory;
+
+    #[tokio::test]
+    async fn matches_within_and_across_components() {
+        let operator = Operator::new(Memory::default()).unwrap().finish();
+        for key in ["logs/a.txt", "logs/b.txt", "logs/c.csv", "logs/old/d.txt"] {
+            operator.write(key, "x").await.unwrap();
+        }
+
+        assert_eq!(
+            expand(&operator, "logs/*.txt").await.unwrap(),
+            ["logs/a.txt", "logs/b.txt"]
+        );
+        assert_eq!(
+            expand(&operator, "logs/**/*.txt").await.unwrap(),
+            ["logs/a.txt", "logs/b.txt", "logs/old/d.txt"]
+        );
+        assert_eq!(
+            expand(&operator, "logs/c.csv").await.unwrap(),
+            ["logs/c.csv"]
+        );
+        assert!(expand(&operator, "missing/*").await.unwrap().is_empty());
+    }
+}
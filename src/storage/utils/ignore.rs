@@ -0,0 +1,122 @@
+// `.storifyignore` rules for recursive uploads, in gitignore syntax
+use crate::error::{Error, Result};
+use globset::{GlobBuilder, GlobMatcher};
+use std::path::Path;
+
+/// Name of the ignore file read from the root of a recursive upload.
+pub const IGNORE_FILE_NAME: &str = ".storifyignore";
+
+/// Gitignore-style rules for paths relative to the directory holding the ignore file.
+///
+/// Supported: `#` comments, `!` negation (the last matching rule wins), a trailing `/` for
+/// directories only, a leading or inner `/` anchoring the pattern to the root, and `*`, `?`,
+/// `[...]` and `**` globs. Unanchored patterns match at any depth.
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreRules {
+    rules: Vec<Rule>,
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    matcher: GlobMatcher,
+    negated: bool,
+    dir_only: bool,
+}
+
+impl IgnoreRules {
+    /// Rules of `dir/.storifyignore`, or `None` when there is no such file.
+    pub fn load(dir: &Path) -> Result<Option<Self>> {
+        let path = dir.join(IGNORE_FILE_NAME);
+        match std::fs::read_to_string(&path) {
+            Ok(text) => Self::parse(&text).map(Some),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut rules = Vec::new();
+        for line in text.lines() {
+            let line = line.trim_end();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (negated, pattern) = match line.strip_prefix('!') {
+                Some(rest) => (true, rest),
+                None => (false, line.strip_prefix('\\').unwrap_or(line)),
+            };
+            let (dir_only, pattern) = match pattern.strip_suffix('/') {
+                Some(rest) => (true, rest),
+                None => (false, pattern),
+            };
+            let glob = match pattern.strip_prefix('/') {
+                Some(anchored) => anchored.to_string(),
+                None if pattern.contains('/') => pattern.to_string(),
+                None => format!("**/{pattern}"),
+            };
+            if glob.is_empty() {
+                continue;
+            }
+            let matcher = GlobBuilder::new(&glob)
+                .literal_separator(true)
+                .build()
+                .map_err(|e| Error::InvalidArgument {
+                    message: format!("invalid pattern '{line}' in {IGNORE_FILE_NAME}: {e}"),
+                })?
+                .compile_matcher();
+            rules.push(Rule {
+                matcher,
+                negated,
+                dir_only,
+            });
+        }
+        Ok(Self { rules })
+    }
+
+    /// Whether `relative_path` is ignored. Paths below an ignored directory are not
+    /// checked by callers, which skip the whole directory as git does.
+    pub fn is_ignored(&self, relative_path: &str, is_dir: bool) -> bool {
+        let path = relative_path.trim_matches('/');
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| (is_dir || !rule.dir_only) && rule.matcher.is_match(path))
+            .is_some_and(|rule| !rule.negated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn follows_gitignore_rules() {
+        let rules = IgnoreRules::parse(
+            "# build output\n\
+             target/\n\
+             *.log\n\
+             !keep.log\n\
+             /dist\n\
+             docs/*.tmp\n\
+             \\#notes\n\
+             **/cache/**\n",
+        )
+        .unwrap();
+
+        assert!(rules.is_ignored("target", true));
+        assert!(rules.is_ignored("sub/target", true));
+        assert!(!rules.is_ignored("target", false));
+        assert!(rules.is_ignored("a.log", false));
+        assert!(rules.is_ignored("deep/dir/b.log", false));
+        assert!(!rules.is_ignored("deep/keep.log", false));
+        assert!(rules.is_ignored("dist", true));
+        assert!(!rules.is_ignored("sub/dist", true));
+        assert!(rules.is_ignored("docs/x.tmp", false));
+        assert!(!rules.is_ignored("docs/sub/x.tmp", false));
+        assert!(!rules.is_ignored("other/docs/x.tmp", false));
+        assert!(rules.is_ignored("#notes", false));
+        assert!(rules.is_ignored("a/cache/b/c.bin", false));
+        assert!(!rules.is_ignored("src/main.rs", false));
+        assert!(IgnoreRules::parse("[").is_err());
+    }
+}
@@ -1,8 +1,20 @@
 // Utilities for storage module
+pub mod archive;
+pub mod cipher;
+pub mod clobber;
+pub mod codec;
 pub mod error;
+pub mod filter;
+pub mod format;
+pub mod glob;
+pub mod ignore;
+pub mod paging;
 pub mod path;
 pub mod progress;
+pub mod report;
 pub mod size;
+pub mod throttle;
+pub mod validate;
 
 /// Output format for CLI commands that can render machine-readable results
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -0,0 +1,336 @@
+// Listing page size: fixed via --page-size, or tuned from observed page latency
+use futures::SinkExt;
+use futures::channel::mpsc;
+use futures::stream::{self, BoxStream, StreamExt, TryStreamExt};
+use opendal::{Entry, Lister, Operator};
+use std::time::{Duration, Instant};
+
+/// First page of a tuned listing; small so the first results show up quickly.
+const INITIAL_PAGE_SIZE: usize = 200;
+/// Smallest page the tuner shrinks to.
+const MIN_PAGE_SIZE: usize = 100;
+/// Cap that S3, OSS and GCS apply to `max-keys` anyway.
+const MAX_PAGE_SIZE: usize = 1000;
+/// Pages answered within half of this grow, pages slower than twice this shrink.
+const TARGET_PAGE_LATENCY: Duration = Duration::from_secs(1);
+/// Entries a shard listing may run ahead of the output before it waits.
+const SHARD_BUFFER: usize = MAX_PAGE_SIZE;
+
+/// How many entries each listing request asks for.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PageSize {
+    /// Tune from page latency where the provider can resume a listing after a key,
+    /// otherwise leave the provider default
+    #[default]
+    Auto,
+    /// Always request this many entries per page
+    Fixed(usize),
+}
+
+/// Adjusts the page size after every full page.
+#[derive(Debug, Clone, Copy)]
+struct PageTuner {
+    size: usize,
+}
+
+impl PageTuner {
+    fn new() -> Self {
+        Self {
+            size: INITIAL_PAGE_SIZE,
+        }
+    }
+
+    /// Double the page while pages come back fast, halve it when they get slow.
+    fn observe(&mut self, elapsed: Duration) {
+        if elapsed <= TARGET_PAGE_LATENCY / 2 {
+            self.size = (self.size * 2).min(MAX_PAGE_SIZE);
+        } else if elapsed > TARGET_PAGE_LATENCY * 2 {
+            self.size = (self.size / 2).max(MIN_PAGE_SIZE);
+        }
+    }
+}
+
+/// List `path` with the requested page size; a drop-in for `lister_with(path)`.
+pub async fn list_entries(
+    operator: &Operator,
+    path: &str,
+    recursive: bool,
+    page_size: PageSize,
+) -> opendal::Result<BoxStream<'static, opendal::Result<Entry>>> {
+    list_entries_after(operator, path, recursive, page_size, None).await
+}
+
+/// Like [`list_entries`], but only return keys sorting after `start_after`, the last key
+/// a previous `--max-results` run scanned.
+///
+/// Providers that cannot start a listing after a key are listed from the beginning and
+/// filtered here, which is only exact when they list in key order.
+pub async fn list_entries_after(
+    operator: &Operator,
+    path: &str,
+    recursive: bool,
+    page_size: PageSize,
+    start_after: Option<&str>,
+) -> opendal::Result<BoxStream<'static, opendal::Result<Entry>>> {
+    let capability = operator.info().full_capability();
+    let mut lister = operator.lister_with(path).recursive(recursive);
+    if let Some(key) = start_after
+        && capability.list_with_start_after
+    {
+        lister = lister.start_after(key);
+    }
+    let entries = match page_size {
+        PageSize::Fixed(size) => lister.limit(size.max(1)).await?.boxed(),
+        PageSize::Auto if capability.list_with_limit && capability.list_with_start_after => {
+            tuned_entries(
+                operator.clone(),
+                path.to_string(),
+                recursive,
+                start_after.map(str::to_string),
+            )
+            .await?
+        }
+        PageSize::Auto => lister.await?.boxed(),
+    };
+    match start_after {
+        Some(key) if !capability.list_with_start_after => {
+            tracing::debug!("provider cannot list after a key; skipping keys up to {key}");
+            let key = key.to_string();
+            Ok(entries
+                .try_filter(move |entry| futures::future::ready(entry.path() > key.as_str()))
+                .boxed())
+        }
+        _ => Ok(entries),
+    }
+}
+
+/// Like [`list_entries`], but a recursive listing with `jobs > 1` is split by the
+/// directories directly below `path`, listing up to `jobs` of them at once.
+///
+/// Entries come out in the order of the sorted first level, each directory's listing
+/// spliced in where the directory sorts, which is key order on providers that list in
+/// key order. The first level is held in memory; every shard runs at most
+/// [`SHARD_BUFFER`] entries ahead of the output.
+pub async fn list_entries_parallel(
+    operator: &Operator,
+    path: &str,
+    recursive: bool,
+    page_size: PageSize,
+    jobs: usize,
+) -> opendal::Result<BoxStream<'static, opendal::Result<Entry>>> {
+    if !recursive || jobs <= 1 {
+        return list_entries(operator, path, recursive, page_size).await;
+    }
+    let mut first_level: Vec<Entry> = list_entries(operator, path, false, page_size)
+        .await?
+        .try_collect()
+        .await?;
+    first_level.sort_by(|a, b| a.path().cmp(b.path()));
+    let shards = first_level
+        .iter()
+        .filter(|entry| is_shard(entry, path))
+        .count();
+    tracing::debug!("listing {path} in {shards} shards, {jobs} at a time");
+
+    let operator = operator.clone();
+    let path = path.to_string();
+    Ok(stream::iter(first_level)
+        .map(move |entry| {
+            // A recursive listing of a directory includes the directory itself where
+            // the provider reports it, so shards replace their first-level entry.
+            let part = if is_shard(&entry, &path) {
+                list_shard(operator.clone(), entry.path().to_string(), page_size)
+            } else {
+                stream::once(async { Ok(entry) }).boxed()
+            };
+            async move { part }
+        })
+        .buffered(jobs)
+        .flatten()
+        .boxed())
+}
+
+fn is_shard(entry: &Entry, path: &str) -> bool {
+    entry.metadata().mode().is_dir() && entry.path() != path
+}
+
+/// List the directory `shard` recursively in a task of its own, so several shards make
+/// progress while the output is still reading an earlier one.
+fn list_shard(
+    operator: Operator,
+    shard: String,
+    page_size: PageSize,
+) -> BoxStream<'static, opendal::Result<Entry>> {
+    let (mut tx, rx) = mpsc::channel(SHARD_BUFFER);
+    tokio::spawn(async move {
+        let mut entries = match list_entries(&operator, &shard, true, page_size).await {
+            Ok(entries) => entries,
+            Err(e) => {
+                let _ = tx.send(Err(e)).await;
+                return;
+            }
+        };
+        while let Some(entry) = entries.next().await {
+            let failed = entry.is_err();
+            // A dropped receiver means the output stopped early (`--max-keys`).
+            if tx.send(entry).await.is_err() || failed {
+                return;
+            }
+        }
+    });
+    rx.boxed()
+}
+
+/// Tell the user how to continue a scan cut short by `--max-results`.
+pub fn report_continuation(last_key: &str) {
+    eprintln!("more results may follow; continue with --start-after '{last_key}'");
+}
+
+struct TunedListing {
+    operator: Operator,
+    path: String,
+    recursive: bool,
+    tuner: PageTuner,
+    /// Greatest path returned so far; the next page starts after it
+    last: Option<String>,
+    /// Lister of the next page with the time its request started
+    next: Option<(Lister, Instant)>,
+}
+
+impl TunedListing {
+    async fn open(&self) -> opendal::Result<(Lister, Instant)> {
+        let started = Instant::now();
+        let mut lister = self
+            .operator
+            .lister_with(&self.path)
+            .recursive(self.recursive)
+            .limit(self.tuner.size);
+        if let Some(last) = &self.last {
+            lister = lister.start_after(last);
+        }
+        Ok((lister.await?, started))
+    }
+
+    /// Read one page, then drop the lister so the next page can use another size. Also
+    /// returns whether another page may follow.
+    async fn next_page(&mut self) -> opendal::Result<(Vec<Entry>, bool)> {
+        let (lister, started) = match self.next.take() {
+            Some(next) => next,
+            None => self.open().await?,
+        };
+        let size = self.tuner.size;
+        let listed: Vec<Entry> = lister.take(size).try_collect().await?;
+        let elapsed = started.elapsed();
+        let full = listed.len() >= size;
+        // Some providers return the listed directory itself on every page.
+        let page: Vec<Entry> = listed
+            .into_iter()
+            .filter(|entry| self.last.as_deref().is_none_or(|last| entry.path() > last))
+            .collect();
+        // Entries of one page are not sorted (S3 puts prefixes first); resume after the greatest.
+        if let Some(greatest) = page.iter().map(|entry| entry.path()).max() {
+            self.last = Some(greatest.to_string());
+        }
+        if full {
+            self.tuner.observe(elapsed);
+            tracing::debug!(
+                "listing page of {size} entries took {elapsed:?}, next page size {}",
+                self.tuner.size
+            );
+        }
+        // A full page of entries seen before would never make progress.
+        let progressed = !page.is_empty();
+        Ok((page, full && progressed))
+    }
+}
+
+async fn tuned_entries(
+    operator: Operator,
+    path: String,
+    recursive: bool,
+    start_after: Option<String>,
+) -> opendal::Result<BoxStream<'static, opendal::Result<Entry>>> {
+    let mut listing = TunedListing {
+        operator,
+        path,
+        recursive,
+        tuner: PageTuner::new(),
+        last: start_after,
+        next: None,
+    };
+    // Open the first page up front so a missing path fails like `lister_with` does.
+    listing.next = Some(listing.open().await?);
+    let pages = stream::try_unfold((listing, true), |(mut listing, more)| async move {
+        if !more {
+            return Ok(None);
+        }
+        let (page, more) = listing.next_page().await?;
+        Ok(Some((page, (listing, more))))
+    });
+    Ok(pages
+        .map_ok(|page| stream::iter(page.into_iter().map(Ok)))
+        .try_flatten()
+        .boxed())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn parallel_listing_matches_a_sequential_one() {
+        let dir = tempfile::tempdir().unwrap();
+        for file in ["x/a", "x/b/1", "x/b/2/3", "x/c/4", "x/c-d", "x/e/"] {
+            let path = dir.path().join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            if !file.ends_with('/') {
+                std::fs::write(path, "1").unwrap();
+            }
+        }
+        let operator =
+            Operator::new(opendal::services::Fs::default().root(dir.path().to_str().unwrap()))
+                .unwrap()
+                .finish();
+        let list = |jobs| {
+            let operator = operator.clone();
+            async move {
+                list_entries_parallel(&operator, "x/", true, PageSize::Auto, jobs)
+                    .await
+                    .unwrap()
+                    .map_ok(|entry| entry.path().to_string())
+                    .try_collect::<Vec<_>>()
+                    .await
+                    .unwrap()
+            }
+        };
+
+        let mut sequential = list(1).await;
+        let parallel = list(4).await;
+        // Shards come out in key order: x/a, x/b/..., x/c-d, x/c/..., x/e/.
+        let position = |path: &str| parallel.iter().position(|p| p == path).unwrap();
+        assert!(position("x/a") < position("x/b/2/3"));
+        assert!(position("x/b/2/3") < position("x/c-d"));
+        assert!(position("x/c-d") < position("x/c/4"));
+        let mut parallel = parallel.clone();
+        sequential.sort();
+        parallel.sort();
+        assert_eq!(parallel, sequential);
+    }
+
+    #[test]
+    fn tuner_grows_on_fast_pages_and_shrinks_on_slow_ones() {
+        let mut tuner = PageTuner::new();
+        tuner.observe(Duration::from_millis(100));
+        assert_eq!(tuner.size, 400);
+        for _ in 0..5 {
+            tuner.observe(Duration::from_millis(100));
+        }
+        assert_eq!(tuner.size, MAX_PAGE_SIZE);
+        tuner.observe(Duration::from_millis(800));
+        assert_eq!(tuner.size, MAX_PAGE_SIZE);
+        for _ in 0..5 {
+            tuner.observe(Duration::from_secs(3));
+        }
+        assert_eq!(tuner.size, MIN_PAGE_SIZE);
+    }
+}
@@ -1,4 +1,5 @@
 // Path helper utilities shared across storage operations
+use opendal::{EntryMode, Operator};
 use std::path::Path;
 
 /// Build a remote path by joining base and file name.
@@ -55,3 +56,117 @@ pub fn get_root_relative_path(full_path: &str, base_path: &str) -> String {
     rel = rel.replace("//", "/");
     rel
 }
+
+/// Resolve `.` and `..` segments of a remote path without touching storage.
+///
+/// Returns the path relative to the storage root (`""` for the root itself), or `None`
+/// when `..` climbs above the root.
+pub fn normalize_under_root(path: &str) -> Option<String> {
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in path.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop()?;
+            }
+            other => segments.push(other),
+        }
+    }
+    Some(segments.join("/"))
+}
+
+/// Whether `path` names the storage root itself (`/`, `.`, `a/..`, ...).
+pub fn is_root_path(path: &str) -> bool {
+    normalize_under_root(path).is_some_and(|normalized| normalized.is_empty())
+}
+
+/// Whether a transfer path names a directory by its spelling: it ends in a separator, or
+/// is empty, `.` or `..`.
+pub fn names_directory(path: &str) -> bool {
+    let last = path.rsplit(['/', std::path::MAIN_SEPARATOR]).next();
+    matches!(last, Some("" | "." | ".."))
+}
+
+/// Where a transfer of `src` to `dest` lands, rsync style.
+///
+/// A destination spelled as a directory (`dir/`) receives the source under its own name,
+/// or just the contents of a source that is itself spelled as a directory (`src/`). Any
+/// other destination is the exact target path.
+pub fn transfer_target(src: &str, dest: &str) -> String {
+    if !names_directory(dest) || names_directory(src) {
+        dest.to_string()
+    } else {
+        build_remote_path(dest, &basename(src))
+    }
+}
+
+/// Whether `target` is `src` itself or lies under it, so a recursive transfer from `src`
+/// would read its own output.
+pub fn is_within(target: &str, src: &str) -> bool {
+    match (normalize_under_root(target), normalize_under_root(src)) {
+        (Some(target), Some(src)) => {
+            src.is_empty() || target == src || target.starts_with(&format!("{src}/"))
+        }
+        _ => false,
+    }
+}
+
+/// Hybrid directory detection for object storage: stat first; if not available, probe prefix.
+pub async fn is_remote_directory(operator: &Operator, path: &str) -> bool {
+    match operator.stat(path).await.ok().map(|m| m.mode()) {
+        Some(EntryMode::DIR) => true,
+        Some(_) => false,
+        None => {
+            let probe = ensure_trailing_slash(path);
+            operator
+                .list_with(&probe)
+                .limit(1)
+                .await
+                .map(|entries| !entries.is_empty())
+                .unwrap_or(false)
+        }
+    }
+}
+
+/// Whether `path` is an existing remote file.
+pub async fn is_remote_file(operator: &Operator, path: &str) -> bool {
+    !path.ends_with('/')
+        && operator
+            .stat(path)
+            .await
+            .is_ok_and(|meta| meta.mode() == EntryMode::FILE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dot_segments_resolve_within_the_root() {
+        assert_eq!(normalize_under_root("a/./b/../c/").as_deref(), Some("a/c"));
+        assert_eq!(normalize_under_root("/").as_deref(), Some(""));
+        assert_eq!(normalize_under_root("a/../../etc/passwd"), None);
+        assert_eq!(normalize_under_root(".."), None);
+
+        assert!(is_root_path("/"));
+        assert!(is_root_path("./"));
+        assert!(is_root_path("logs/.."));
+        assert!(!is_root_path("logs/"));
+        assert!(!is_root_path("../"));
+    }
+
+    #[test]
+    fn trailing_slash_places_the_source_inside_the_destination() {
+        assert_eq!(transfer_target("a/report.csv", "out/"), "out/report.csv");
+        assert_eq!(transfer_target("a/report.csv", "out"), "out");
+        assert_eq!(transfer_target("logs", "backup/"), "backup/logs");
+        assert_eq!(transfer_target("logs/", "backup/"), "backup/");
+        assert_eq!(transfer_target("logs/", "backup"), "backup");
+        assert_eq!(transfer_target("data.bin", ""), "data.bin");
+        assert_eq!(transfer_target("data.bin", "."), "./data.bin");
+
+        assert!(is_within("logs/2024/", "logs"));
+        assert!(is_within("logs", "logs/"));
+        assert!(!is_within("logs-old/", "logs/"));
+    }
+}
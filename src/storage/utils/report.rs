@@ -0,0 +1,178 @@
+// Per-command totals of recursive transfers and multi-path commands
+use crate::error::{Error, Result};
+use std::fmt;
+use std::time::Duration;
+
+/// What a `put` or `get` did, summed over its files.
+///
+/// Directory transfers keep going past files that fail and report them here; the command
+/// fails afterwards through [`TransferReport::ensure_complete`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TransferReport {
+    /// Whether a directory was transferred, as opposed to a single file or stdin
+    pub directory: bool,
+    /// Files transferred
+    pub files: usize,
+    /// Bytes transferred
+    pub bytes: u64,
+    /// Files left out by filters, ignore rules or symlink policy, or gone at read time
+    pub skipped: usize,
+    /// Files that could not be transferred
+    pub failed: usize,
+    pub elapsed: Duration,
+}
+
+impl TransferReport {
+    /// Count one transferred file of `bytes` bytes.
+    pub fn add_file(&mut self, bytes: u64) {
+        self.files += 1;
+        self.bytes += bytes;
+    }
+
+    /// Fail when any file could not be transferred.
+    pub fn ensure_complete(&self) -> Result<()> {
+        if self.failed == 0 {
+            Ok(())
+        } else {
+            Err(Error::TransferIncomplete {
+                failed: self.failed,
+                total: self.files + self.failed,
+            })
+        }
+    }
+}
+
+impl fmt::Display for TransferReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} file(s), {} bytes, {} skipped, {} failed in {:.1} seconds",
+            self.files,
+            self.bytes,
+            self.skipped,
+            self.failed,
+            self.elapsed.as_secs_f64()
+        )
+    }
+}
+
+/// Outcome of a command run on several paths (`rm`, `head`, `tail`, `touch`).
+///
+/// Every path is attempted even after others fail. With more than one path, a failure is
+/// printed when it is recorded and the command fails afterwards through
+/// [`MultiResult::into_result`]; a single path fails with its own error.
+#[derive(Debug, Default)]
+pub struct MultiResult {
+    total: usize,
+    succeeded: usize,
+    failed: Vec<(String, Error)>,
+}
+
+impl MultiResult {
+    /// Start collecting the outcomes of `total` paths.
+    pub fn new(total: usize) -> Self {
+        Self {
+            total,
+            ..Default::default()
+        }
+    }
+
+    /// Record the outcome for `path`; returns whether it succeeded.
+    pub fn record(&mut self, path: &str, result: Result<()>) -> bool {
+        match result {
+            Ok(()) => {
+                self.succeeded += 1;
+                true
+            }
+            Err(e) => {
+                if self.total > 1 {
+                    let message = e.to_string();
+                    if message.contains(path) {
+                        eprintln!("{message}");
+                    } else {
+                        eprintln!("{path}: {message}");
+                    }
+                }
+                self.failed.push((path.to_string(), e));
+                false
+            }
+        }
+    }
+
+    /// Fail when any path failed: with the error of the only path, or with the one built by
+    /// `partial` from the failed paths and the total.
+    pub fn into_result(self, partial: impl FnOnce(Vec<String>, usize) -> Error) -> Result<()> {
+        let total = self.total.max(self.succeeded + self.failed.len());
+        let mut failed = self.failed;
+        match failed.len() {
+            0 => Ok(()),
+            1 if total == 1 => Err(failed.remove(0).1),
+            _ => Err(partial(
+                failed.into_iter().map(|(path, _)| path).collect(),
+                total,
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn partial(failed_paths: Vec<String>, total: usize) -> Error {
+        Error::PartialFailure {
+            failed_paths,
+            total,
+        }
+    }
+
+    #[test]
+    fn keeps_going_and_fails_when_any_path_failed() {
+        let mut results = MultiResult::new(3);
+        assert!(results.record("a", Ok(())));
+        assert!(!results.record("b", Err(Error::PathNotFound { path: "b".into() })));
+        assert!(results.record("c", Ok(())));
+        match results.into_result(partial) {
+            Err(Error::PartialFailure {
+                failed_paths,
+                total,
+            }) => {
+                assert_eq!(failed_paths, vec!["b".to_string()]);
+                assert_eq!(total, 3);
+            }
+            other => panic!("expected PartialFailure, got {other:?}"),
+        }
+
+        // A single path keeps its own error, and so its exit code.
+        let mut results = MultiResult::new(1);
+        results.record("b", Err(Error::PathNotFound { path: "b".into() }));
+        assert!(matches!(
+            results.into_result(partial),
+            Err(Error::PathNotFound { .. })
+        ));
+        assert!(MultiResult::new(2).into_result(partial).is_ok());
+    }
+
+    #[test]
+    fn summarizes_and_fails_on_failed_files() {
+        let mut report = TransferReport {
+            directory: true,
+            skipped: 2,
+            elapsed: Duration::from_millis(1300),
+            ..Default::default()
+        };
+        report.add_file(10);
+        report.add_file(5);
+        assert_eq!(
+            report.to_string(),
+            "2 file(s), 15 bytes, 2 skipped, 0 failed in 1.3 seconds"
+        );
+        assert!(report.ensure_complete().is_ok());
+
+        report.failed = 1;
+        assert_eq!(
+            report.ensure_complete().unwrap_err().to_string(),
+            "1 of 3 file(s) failed to transfer"
+        );
+    }
+}
@@ -0,0 +1,96 @@
+//! Bandwidth limiting for concurrent transfers.
+//!
+//! Waiters on a [`TokenBucket`] are served in FIFO order and each request covers a single
+//! buffer, so concurrent transfers sharing a bucket advance round-robin: a small file gets
+//! its turn between every chunk of a large one instead of queueing behind the whole object.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// A byte-rate token bucket with a one second burst.
+#[derive(Debug)]
+pub struct TokenBucket {
+    rate: f64,
+    state: Mutex<BucketState>,
+}
+
+#[derive(Debug)]
+struct BucketState {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl TokenBucket {
+    /// Create a bucket refilling at `bytes_per_sec` (clamped to at least 1).
+    pub fn new(bytes_per_sec: u64) -> Self {
+        let rate = bytes_per_sec.max(1) as f64;
+        Self {
+            rate,
+            state: Mutex::new(BucketState {
+                tokens: rate,
+                refilled_at: Instant::now(),
+            }),
+        }
+    }
+
+    /// Wait until `bytes` may be sent.
+    ///
+    /// The lock is held while sleeping so later callers queue behind the current one.
+    pub async fn acquire(&self, bytes: usize) {
+        let mut state = self.state.lock().await;
+        let now = Instant::now();
+        let elapsed = now.duration_since(state.refilled_at).as_secs_f64();
+        state.tokens = (state.tokens + elapsed * self.rate).min(self.rate);
+        state.refilled_at = now;
+
+        state.tokens -= bytes as f64;
+        if state.tokens < 0.0 {
+            tokio::time::sleep(Duration::from_secs_f64(-state.tokens / self.rate)).await;
+            state.tokens = 0.0;
+            state.refilled_at = Instant::now();
+        }
+    }
+}
+
+/// Shared limits for one transfer command.
+#[derive(Debug, Clone, Default)]
+pub struct Throttle {
+    per_file_limit: Option<u64>,
+    total: Option<Arc<TokenBucket>>,
+}
+
+impl Throttle {
+    pub fn new(per_file_limit: Option<u64>, total_limit: Option<u64>) -> Self {
+        Self {
+            per_file_limit,
+            total: total_limit.map(|limit| Arc::new(TokenBucket::new(limit))),
+        }
+    }
+
+    /// Limiter for a single file: its own per-file bucket plus the shared total bucket.
+    pub fn for_file(&self) -> FileThrottle {
+        FileThrottle {
+            own: self.per_file_limit.map(TokenBucket::new),
+            total: self.total.clone(),
+        }
+    }
+}
+
+/// Per-file view of a [`Throttle`].
+#[derive(Debug)]
+pub struct FileThrottle {
+    own: Option<TokenBucket>,
+    total: Option<Arc<TokenBucket>>,
+}
+
+impl FileThrottle {
+    pub async fn acquire(&self, bytes: usize) {
+        if let Some(own) = &self.own {
+            own.acquire(bytes).await;
+        }
+        if let Some(total) = &self.total {
+            total.acquire(bytes).await;
+        }
+    }
+}
@@ -0,0 +1,224 @@
+// Pre-flight validation of object keys and local destination paths
+use opendal::Scheme;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Maximum number of warnings printed before the rest are summarized.
+const MAX_REPORTED_WARNINGS: usize = 20;
+
+/// A non-fatal problem detected before a transfer starts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathWarning {
+    /// Key (or path segment) is longer than the backend accepts.
+    TooLong { key: String, len: usize, max: usize },
+    /// Key contains a character the backend rejects or mangles.
+    DisallowedChar { key: String, ch: char },
+    /// Two keys map to the same path on a case-insensitive destination.
+    CaseCollision { first: String, second: String },
+}
+
+impl fmt::Display for PathWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathWarning::TooLong { key, len, max } => {
+                write!(f, "key is {len} bytes, exceeds limit of {max}: {key}")
+            }
+            PathWarning::DisallowedChar { key, ch } => {
+                write!(f, "key contains disallowed character {ch:?}: {key}")
+            }
+            PathWarning::CaseCollision { first, second } => {
+                write!(
+                    f,
+                    "'{first}' and '{second}' collide on a case-insensitive destination"
+                )
+            }
+        }
+    }
+}
+
+/// Key constraints of a storage backend or local filesystem.
+#[derive(Debug, Clone, Copy)]
+pub struct KeyRules {
+    /// Limit on the whole key in bytes.
+    max_key_bytes: Option<usize>,
+    /// Limit on each '/'-separated segment in bytes (filesystems).
+    max_segment_bytes: Option<usize>,
+    /// Characters rejected in addition to control characters.
+    forbidden: &'static [char],
+}
+
+const WINDOWS_FORBIDDEN: &[char] = &['<', '>', ':', '"', '|', '?', '*', '\\'];
+
+impl KeyRules {
+    /// Rules for a remote backend identified by its OpenDAL scheme.
+    pub fn for_scheme(scheme: Scheme) -> Self {
+        match scheme {
+            Scheme::S3 => Self::object(1024, &[]),
+            Scheme::Oss => Self::object(1023, &[]),
+            Scheme::Cos => Self::object(850, &[]),
+            Scheme::Azblob => Self::object(1024, &['\\']),
+            Scheme::Gcs => Self::object(1024, &[]),
+            _ => Self::local(),
+        }
+    }
+
+    /// Rules for the local filesystem of the current platform.
+    pub fn local() -> Self {
+        Self {
+            max_key_bytes: None,
+            max_segment_bytes: Some(255),
+            forbidden: if cfg!(windows) {
+                WINDOWS_FORBIDDEN
+            } else {
+                &[]
+            },
+        }
+    }
+
+    const fn object(max_key_bytes: usize, forbidden: &'static [char]) -> Self {
+        Self {
+            max_key_bytes: Some(max_key_bytes),
+            max_segment_bytes: None,
+            forbidden,
+        }
+    }
+
+    /// Check a single key and return every rule it breaks.
+    pub fn check(&self, key: &str) -> Vec<PathWarning> {
+        let mut warnings = Vec::new();
+
+        if let Some(max) = self.max_key_bytes
+            && key.len() > max
+        {
+            warnings.push(PathWarning::TooLong {
+                key: key.to_string(),
+                len: key.len(),
+                max,
+            });
+        }
+
+        if let Some(max) = self.max_segment_bytes
+            && let Some(segment) = key.split('/').find(|s| s.len() > max)
+        {
+            warnings.push(PathWarning::TooLong {
+                key: key.to_string(),
+                len: segment.len(),
+                max,
+            });
+        }
+
+        if let Some(ch) = key
+            .chars()
+            .find(|c| c.is_control() || self.forbidden.contains(c))
+        {
+            warnings.push(PathWarning::DisallowedChar {
+                key: key.to_string(),
+                ch,
+            });
+        }
+
+        warnings
+    }
+}
+
+/// Whether the local filesystem is case-insensitive by default on this platform.
+pub fn local_is_case_insensitive() -> bool {
+    cfg!(any(windows, target_os = "macos"))
+}
+
+/// Find keys that differ only by case.
+pub fn find_case_collisions<'a>(keys: impl IntoIterator<Item = &'a str>) -> Vec<PathWarning> {
+    let mut seen: HashMap<String, &'a str> = HashMap::new();
+    let mut warnings = Vec::new();
+    for key in keys {
+        let folded = key.to_lowercase();
+        match seen.get(&folded) {
+            Some(first) if *first != key => warnings.push(PathWarning::CaseCollision {
+                first: first.to_string(),
+                second: key.to_string(),
+            }),
+            Some(_) => {}
+            None => {
+                seen.insert(folded, key);
+            }
+        }
+    }
+    warnings
+}
+
+/// Validate relative local paths a download would create.
+pub fn local_path_warnings<'a>(
+    relative_paths: impl Iterator<Item = &'a str> + Clone,
+) -> Vec<PathWarning> {
+    let rules = KeyRules::local();
+    let mut warnings: Vec<PathWarning> = relative_paths
+        .clone()
+        .flat_map(|rel| rules.check(rel))
+        .collect();
+    if local_is_case_insensitive() {
+        warnings.extend(find_case_collisions(relative_paths));
+    }
+    warnings
+}
+
+/// Validate remote keys an upload would create on the given backend.
+pub fn remote_key_warnings(scheme: Scheme, keys: &[String]) -> Vec<PathWarning> {
+    let rules = KeyRules::for_scheme(scheme);
+    let mut warnings: Vec<PathWarning> = keys.iter().flat_map(|k| rules.check(k)).collect();
+    if scheme == Scheme::Fs && local_is_case_insensitive() {
+        warnings.extend(find_case_collisions(keys.iter().map(String::as_str)));
+    }
+    warnings
+}
+
+/// Print warnings to stderr, summarizing when there are many.
+pub fn report_warnings(warnings: &[PathWarning]) {
+    for warning in warnings.iter().take(MAX_REPORTED_WARNINGS) {
+        eprintln!("Warning: {warning}");
+    }
+    if warnings.len() > MAX_REPORTED_WARNINGS {
+        eprintln!(
+            "Warning: ... and {} more path warning(s)",
+            warnings.len() - MAX_REPORTED_WARNINGS
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn s3_key_over_limit_is_reported() {
+        let rules = KeyRules::for_scheme(Scheme::S3);
+        assert!(rules.check(&"a".repeat(1024)).is_empty());
+        assert!(matches!(
+            rules.check(&"a".repeat(1025)).as_slice(),
+            [PathWarning::TooLong { max: 1024, .. }]
+        ));
+    }
+
+    #[test]
+    fn control_characters_are_disallowed() {
+        let rules = KeyRules::for_scheme(Scheme::Oss);
+        assert_eq!(
+            rules.check("logs/a\u{7}b"),
+            vec![PathWarning::DisallowedChar {
+                key: "logs/a\u{7}b".to_string(),
+                ch: '\u{7}'
+            }]
+        );
+    }
+
+    #[test]
+    fn case_collisions_are_detected_once_per_pair() {
+        let warnings = find_case_collisions(["a/Readme.md", "a/README.md", "b/x", "a/Readme.md"]);
+        assert_eq!(
+            warnings,
+            vec![PathWarning::CaseCollision {
+                first: "a/Readme.md".to_string(),
+                second: "a/README.md".to_string(),
+            }]
+        );
+    }
+}
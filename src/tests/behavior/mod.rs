@@ -32,6 +32,7 @@ pub fn run_behavior_case(name: &str) -> Result<()> {
 
 fn run_behavior_with_args(args: Arguments) -> Result<()> {
     let client = TEST_RUNTIME.block_on(init_test_service())?;
+    TEST_FIXTURE.begin_suite(client.provider());
 
     let mut tests = Vec::new();
 
@@ -48,10 +49,20 @@ fn run_behavior_with_args(args: Arguments) -> Result<()> {
     operations::upload::tests(&client, &mut tests);
     operations::cat::tests(&client, &mut tests);
     operations::usage::tests(&client, &mut tests);
+    operations::verify::tests(&client, &mut tests);
     operations::stat::tests(&client, &mut tests);
     operations::tree::tests(&client, &mut tests);
     operations::diff::tests(&client, &mut tests);
     operations::touch::tests(&client, &mut tests);
+    operations::versions::tests(&client, &mut tests);
+    operations::index::tests(&client, &mut tests);
+    operations::inventory::tests(&client, &mut tests);
+    operations::health::tests(&client, &mut tests);
+    operations::apply::tests(&client, &mut tests);
+    operations::link::tests(&client, &mut tests);
+    operations::batch::tests(&client, &mut tests);
+    operations::trash::tests(&client, &mut tests);
+    operations::undo::tests(&client, &mut tests);
 
     let _ = tracing_subscriber::fmt()
         .pretty()
@@ -61,7 +72,9 @@ fn run_behavior_with_args(args: Arguments) -> Result<()> {
 
     let conclusion = libtest_mimic::run(&args, tests);
 
-    TEST_RUNTIME.block_on(TEST_FIXTURE.cleanup(client.operator()));
+    TEST_RUNTIME
+        .block_on(TEST_FIXTURE.cleanup(client.operator()))
+        .print();
 
     if conclusion.has_failed() {
         return Err(Error::InvalidArgument {
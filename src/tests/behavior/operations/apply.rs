@@ -0,0 +1,62 @@
+use crate::async_trials;
+use crate::error::Result;
+use crate::storage::StorageClient;
+use crate::tests::behavior::*;
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+
+register_behavior_tests!(test_apply_copy_with_filter_and_prune, test_apply_dry_run,);
+
+fn write_spec(spec: &str) -> std::path::PathBuf {
+    write_temp_file(spec.as_bytes(), ".toml")
+}
+
+// Verify a copy step honours include globs and prunes stale destination objects
+async fn test_apply_copy_with_filter_and_prune(client: StorageClient) -> Result<()> {
+    let src_dir = TEST_FIXTURE.new_dir_path();
+    let dest_dir = TEST_FIXTURE.new_dir_path();
+    let op = client.operator();
+    op.write(&format!("{src_dir}keep.log"), b"keep".to_vec())
+        .await?;
+    op.write(&format!("{src_dir}skip.tmp"), b"skip".to_vec())
+        .await?;
+    op.write(&format!("{dest_dir}stale.log"), b"stale".to_vec())
+        .await?;
+
+    let spec = write_spec(&format!(
+        "[[step]]\naction = \"copy\"\nsource = \"{src_dir}\"\ndestination = \"{dest_dir}\"\ninclude = [\"*.log\"]\nprune = true\n"
+    ));
+    storify_cmd()
+        .arg("apply")
+        .arg(&spec)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("delete:"));
+
+    assert_eq!(
+        op.read(&format!("{dest_dir}keep.log")).await?.to_vec(),
+        b"keep"
+    );
+    assert!(!op.exists(&format!("{dest_dir}skip.tmp")).await?);
+    assert!(!op.exists(&format!("{dest_dir}stale.log")).await?);
+    Ok(())
+}
+
+// Verify --dry-run reports the plan without touching storage
+async fn test_apply_dry_run(client: StorageClient) -> Result<()> {
+    let dir = TEST_FIXTURE.new_dir_path();
+    let key = format!("{dir}doomed.txt");
+    client.operator().write(&key, b"data".to_vec()).await?;
+
+    let spec = write_spec(&format!(
+        "[[step]]\naction = \"delete\"\nsource = \"{dir}\"\n"
+    ));
+    storify_cmd()
+        .args(["apply", "--dry-run"])
+        .arg(&spec)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("would delete:"));
+    assert!(client.operator().exists(&key).await?);
+    Ok(())
+}
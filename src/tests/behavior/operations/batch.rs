@@ -0,0 +1,79 @@
+use crate::async_trials;
+use crate::error::Result;
+use crate::storage::StorageClient;
+use crate::tests::behavior::*;
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+
+register_behavior_tests!(test_batch_manifest, test_batch_stops_after_failure,);
+
+async fn test_batch_manifest(client: StorageClient) -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let local = dir.path().join("local.txt");
+    std::fs::write(&local, b"batch content")?;
+    let uploaded = TEST_FIXTURE.new_file_path();
+    let copied = TEST_FIXTURE.new_file_path();
+
+    let manifest = dir.path().join("ops.jsonl");
+    std::fs::write(
+        &manifest,
+        format!(
+            "{}\n{}\n",
+            serde_json::json!({"op": "put", "src": local, "dst": uploaded}),
+            serde_json::json!({"op": "cp", "src": uploaded, "dst": copied}),
+        ),
+    )?;
+
+    storify_cmd()
+        .args(["batch", "--manifest"])
+        .arg(&manifest)
+        .args(["-j", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("[ok] line 1: put"))
+        .stdout(predicate::str::contains("[ok] line 2: cp"))
+        .stdout(predicate::str::contains(
+            "Batch: 2 succeeded, 0 failed, 0 skipped of 2",
+        ));
+
+    let content = client.operator().read(&copied).await?;
+    assert_eq!(content.to_vec(), b"batch content");
+    Ok(())
+}
+
+async fn test_batch_stops_after_failure(client: StorageClient) -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let missing = TEST_FIXTURE.new_file_path();
+    let path = TEST_FIXTURE.new_file_path();
+    client.operator().write(&path, b"keep".to_vec()).await?;
+
+    let manifest = dir.path().join("ops.jsonl");
+    std::fs::write(
+        &manifest,
+        format!(
+            "{}\n{}\n",
+            serde_json::json!({"op": "cp", "src": missing, "dst": TEST_FIXTURE.new_file_path()}),
+            serde_json::json!({"op": "rm", "src": path}),
+        ),
+    )?;
+
+    storify_cmd()
+        .args(["batch", "--manifest"])
+        .arg(&manifest)
+        .args(["-j", "1"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("[failed] line 1: cp"))
+        .stdout(predicate::str::contains("1 failed, 1 skipped of 2"));
+    assert!(client.operator().exists(&path).await?);
+
+    storify_cmd()
+        .args(["batch", "--manifest"])
+        .arg(&manifest)
+        .arg("--continue-on-error")
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("[ok] line 2: rm"));
+    assert!(!client.operator().exists(&path).await?);
+    Ok(())
+}
@@ -7,13 +7,15 @@ use assert_cmd::prelude::*;
 register_behavior_tests!(
     test_cat_small_file_prints_content,
     test_cat_large_file_force_streams,
+    test_cat_latest_selector,
+    test_cat_range_and_multiple_paths,
 );
 
 // Verify cat prints the content of a small text file
 async fn test_cat_small_file_prints_content(_client: StorageClient) -> Result<()> {
     let content = b"cat small file\nHello world\n".to_vec();
     let source_path = write_temp_file(&content, ".txt");
-    let dest_prefix = TEST_FIXTURE.new_file_path();
+    let dest_prefix = TEST_FIXTURE.new_dir_path();
 
     // Upload via CLI to ensure end-to-end path
     storify_cmd()
@@ -46,7 +48,7 @@ async fn test_cat_small_file_prints_content(_client: StorageClient) -> Result<()
 async fn test_cat_large_file_force_streams(_client: StorageClient) -> Result<()> {
     let content = "Force stream content\n".repeat(4);
     let source_path = write_temp_file(content.as_bytes(), ".txt");
-    let dest_prefix = TEST_FIXTURE.new_file_path();
+    let dest_prefix = TEST_FIXTURE.new_dir_path();
     let file_name = source_path
         .file_name()
         .unwrap()
@@ -80,3 +82,63 @@ async fn test_cat_large_file_force_streams(_client: StorageClient) -> Result<()>
 
     Ok(())
 }
+
+// Verify --latest resolves a prefix to its newest object by mtime or by name
+async fn test_cat_latest_selector(client: StorageClient) -> Result<()> {
+    let dir = TEST_FIXTURE.new_dir_path();
+    client
+        .operator()
+        .write(&format!("{dir}b.log"), b"older\n".to_vec())
+        .await?;
+    // Object stores report mtimes with second precision.
+    tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+    client
+        .operator()
+        .write(&format!("{dir}a.log"), b"newer\n".to_vec())
+        .await?;
+
+    let assert = storify_cmd()
+        .args(["cat", "--latest"])
+        .arg(&dir)
+        .assert()
+        .success();
+    assert_eq!(assert.get_output().stdout, b"newer\n");
+
+    let assert = storify_cmd()
+        .args(["cat", "--latest", "--latest-by", "name"])
+        .arg(&dir)
+        .assert()
+        .success();
+    assert_eq!(assert.get_output().stdout, b"older\n");
+    Ok(())
+}
+
+// Verify --range prints only the selected bytes of each path, in order
+async fn test_cat_range_and_multiple_paths(client: StorageClient) -> Result<()> {
+    let dir = TEST_FIXTURE.new_dir_path();
+    let (first, second) = (format!("{dir}first.txt"), format!("{dir}second.txt"));
+    client
+        .operator()
+        .write(&first, b"0123456789".to_vec())
+        .await?;
+    client
+        .operator()
+        .write(&second, b"abcdefghij".to_vec())
+        .await?;
+
+    let assert = storify_cmd()
+        .args(["cat", "--range", "2-4"])
+        .arg(&first)
+        .arg(&second)
+        .assert()
+        .success();
+    assert_eq!(assert.get_output().stdout, b"234cde");
+
+    let assert = storify_cmd()
+        .args(["cat", "--range=-3"])
+        .arg(&first)
+        .assert()
+        .success();
+    assert_eq!(assert.get_output().stdout, b"789");
+    Ok(())
+}
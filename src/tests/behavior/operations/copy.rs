@@ -8,6 +8,7 @@ use predicates::prelude::*;
 register_behavior_tests!(
     test_copy_file_to_existing_directory,
     test_copy_non_existent_file,
+    test_copy_destination_trailing_slash,
 );
 
 async fn test_copy_file_to_existing_directory(client: StorageClient) -> Result<()> {
@@ -15,6 +16,7 @@ async fn test_copy_file_to_existing_directory(client: StorageClient) -> Result<(
     client.operator().create_dir(&src_dir).await?;
 
     let (src_file, content, _) = TEST_FIXTURE.new_file(client.operator());
+    let src_file = file_name(&src_file);
     let src_file_path = format!("{}{}", src_dir, src_file);
     client
         .operator()
@@ -41,11 +43,68 @@ async fn test_copy_file_to_existing_directory(client: StorageClient) -> Result<(
     Ok(())
 }
 
+async fn test_copy_destination_trailing_slash(client: StorageClient) -> Result<()> {
+    let op = client.operator();
+    let root = TEST_FIXTURE.new_dir_path();
+    let src_file = format!("{root}src/report.csv");
+    op.write(&src_file, b"a,b\n".to_vec()).await?;
+    let src_dir = format!("{root}src");
+
+    // No trailing '/': the destination is the exact target.
+    storify_cmd()
+        .args(["cp", &src_file, &format!("{root}renamed.csv")])
+        .assert()
+        .success();
+    assert_eq!(
+        op.read(&format!("{root}renamed.csv")).await?.to_vec(),
+        b"a,b\n"
+    );
+
+    // A trailing '/' places the source inside, creating the directory as needed.
+    storify_cmd()
+        .args(["cp", &src_file, &format!("{root}into/")])
+        .assert()
+        .success();
+    assert!(op.exists(&format!("{root}into/report.csv")).await?);
+
+    storify_cmd()
+        .args(["cp", &src_dir, &format!("{root}backup/")])
+        .assert()
+        .success();
+    assert!(op.exists(&format!("{root}backup/src/report.csv")).await?);
+
+    storify_cmd()
+        .args(["cp", &format!("{src_dir}/"), &format!("{root}flat/")])
+        .assert()
+        .success();
+    assert!(op.exists(&format!("{root}flat/report.csv")).await?);
+
+    // Ambiguous: an existing directory spelled without '/', a directory onto a file,
+    // and a directory into itself.
+    storify_cmd()
+        .args(["cp", &src_file, &format!("{root}into")])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("add a trailing '/'"));
+    storify_cmd()
+        .args(["cp", &src_dir, &format!("{root}renamed.csv")])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("is a file"));
+    storify_cmd()
+        .args(["cp", &src_dir, &format!("{src_dir}/nested/")])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("inside the source directory"));
+
+    Ok(())
+}
+
 async fn test_copy_non_existent_file(client: StorageClient) -> Result<()> {
     let non_existent_src = TEST_FIXTURE.new_dir_path();
     let non_exist_src_file = TEST_FIXTURE.new_file_path();
     client.operator().create_dir(&non_existent_src).await?;
-    let final_src_file = format!("{}{}", non_existent_src, non_exist_src_file);
+    let final_src_file = format!("{}{}", non_existent_src, file_name(&non_exist_src_file));
 
     let dest_path = TEST_FIXTURE.new_dir_path();
     client.operator().create_dir(&dest_path).await?;
@@ -8,6 +8,8 @@ use predicates::prelude::*;
 register_behavior_tests!(
     test_diff_basic_unified_output,
     test_diff_size_limit_blocks_without_force,
+    test_diff_directories_summary,
+    test_diff_against_local_file,
 );
 
 async fn upload_text_file(env: &E2eTestEnv, content: &str) -> Result<String> {
@@ -59,3 +61,70 @@ async fn test_diff_size_limit_blocks_without_force(_client: StorageClient) -> Re
 
     Ok(())
 }
+
+async fn test_diff_directories_summary(client: StorageClient) -> Result<()> {
+    let left = TEST_FIXTURE.new_dir_path();
+    let right = TEST_FIXTURE.new_dir_path();
+    let op = client.operator();
+    op.write(&format!("{left}same.txt"), "same").await?;
+    op.write(&format!("{right}same.txt"), "same").await?;
+    op.write(&format!("{left}sub/changed.txt"), "old").await?;
+    op.write(&format!("{right}sub/changed.txt"), "newer")
+        .await?;
+    op.write(&format!("{left}removed.txt"), "x").await?;
+    op.write(&format!("{right}added.txt"), "y").await?;
+
+    storify_cmd()
+        .arg("diff")
+        .arg(&left)
+        .arg(&right)
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::contains("+ added.txt")
+                .and(predicate::str::contains("- removed.txt"))
+                .and(predicate::str::contains("M sub/changed.txt (size 3 → 5)"))
+                .and(predicate::str::contains(
+                    "1 added, 1 removed, 1 changed, 1 unchanged",
+                )),
+        );
+
+    storify_cmd()
+        .arg("diff")
+        .arg(&left)
+        .arg(format!("{right}same.txt"))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "cannot diff a directory against a file",
+        ));
+    Ok(())
+}
+
+async fn test_diff_against_local_file(_client: StorageClient) -> Result<()> {
+    let env = E2eTestEnv::new().await;
+    let remote = upload_text_file(&env, "a\nb\nc\n").await?;
+    let dir = tempfile::tempdir()?;
+    let changed = dir.path().join("changed.txt");
+    std::fs::write(&changed, "a\nB\nc\n")?;
+    let same = dir.path().join("same.txt");
+    std::fs::write(&same, "a\nb\nc\n")?;
+
+    storify_cmd()
+        .arg("diff")
+        .arg(&remote)
+        .arg(format!("local:{}", changed.display()))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("-b").and(predicate::str::contains("+B")));
+
+    storify_cmd()
+        .arg("diff")
+        .arg("--local")
+        .arg(&remote)
+        .arg(&same)
+        .assert()
+        .success()
+        .stdout(predicate::str::is_empty());
+    Ok(())
+}
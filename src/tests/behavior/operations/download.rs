@@ -11,6 +11,12 @@ register_behavior_tests!(
     test_download_existing_file_to_directory,
     test_download_directory_recursive,
     test_download_non_existent_file,
+    test_download_without_space_check,
+    test_download_large_file_in_parts,
+    test_download_file_to_exact_path_or_into_directory,
+    test_download_directory_with_filters,
+    test_put_and_get_preserve_file_metadata,
+    test_put_and_get_symlink_policies,
 );
 
 #[derive(Clone)]
@@ -25,6 +31,7 @@ async fn stage_remote_file(client: &StorageClient) -> Result<StagedFile> {
     let src_path = TEST_FIXTURE.new_dir_path();
 
     client.operator().create_dir(&src_path).await?;
+    let src_file = file_name(&src_file).to_string();
     let src_file_path = format!("{}{}", src_path, src_file);
     client
         .operator()
@@ -57,7 +64,7 @@ async fn test_download_existing_file_to_directory(client: StorageClient) -> Resu
     storify_cmd()
         .arg("get")
         .arg(&staged_file.remote_path)
-        .arg(&local_dir)
+        .arg(local_dir.join(""))
         .assert()
         .success()
         .stdout(predicate::str::contains("Downloaded:"));
@@ -89,6 +96,34 @@ async fn test_download_directory_recursive(client: StorageClient) -> Result<()>
     Ok(())
 }
 
+async fn test_download_file_to_exact_path_or_into_directory(client: StorageClient) -> Result<()> {
+    let staged_file = stage_remote_file(&client).await?;
+    let local_dir = std::env::temp_dir().join(format!("storify-dl-exact-{}", Uuid::new_v4()));
+    fs::create_dir_all(&local_dir).await?;
+    let exact = local_dir.join("renamed.bin");
+
+    storify_cmd()
+        .arg("get")
+        .arg(&staged_file.remote_path)
+        .arg(&exact)
+        .assert()
+        .success();
+    assert_eq!(fs::read(&exact).await?, staged_file.content);
+
+    // An existing directory spelled without a trailing '/' is ambiguous.
+    storify_cmd()
+        .arg("get")
+        .arg(&staged_file.remote_path)
+        .arg(&local_dir)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("add a trailing '/'"));
+    assert!(!local_dir.join(&staged_file.file_name).exists());
+
+    let _ = fs::remove_dir_all(&local_dir).await;
+    Ok(())
+}
+
 async fn test_download_non_existent_file(_client: StorageClient) -> Result<()> {
     let remote_path = TEST_FIXTURE.new_file_path();
     let local_dir = std::env::temp_dir().join(format!("storify-dl-miss-{}", Uuid::new_v4()));
@@ -96,7 +131,7 @@ async fn test_download_non_existent_file(_client: StorageClient) -> Result<()> {
     storify_cmd()
         .arg("get")
         .arg(&remote_path)
-        .arg(&local_dir)
+        .arg(local_dir.join(""))
         .assert()
         .failure()
         .stderr(predicate::str::contains("Failed to download"));
@@ -104,3 +139,201 @@ async fn test_download_non_existent_file(_client: StorageClient) -> Result<()> {
     assert!(!local_dir.exists());
     Ok(())
 }
+
+async fn test_download_without_space_check(client: StorageClient) -> Result<()> {
+    let staged_file = stage_remote_file(&client).await?;
+    let local_dir = std::env::temp_dir().join(format!("storify-dl-nospace-{}", Uuid::new_v4()));
+
+    storify_cmd()
+        .arg("get")
+        .arg("--no-space-check")
+        .arg(&staged_file.remote_path)
+        .arg(local_dir.join(""))
+        .assert()
+        .success();
+
+    let actual_content = fs::read(local_dir.join(&staged_file.file_name)).await?;
+    assert_eq!(staged_file.content, actual_content);
+
+    let _ = fs::remove_dir_all(&local_dir).await;
+    Ok(())
+}
+
+async fn test_download_large_file_in_parts(client: StorageClient) -> Result<()> {
+    let remote_dir = TEST_FIXTURE.new_dir_path();
+    let remote_path = format!("{remote_dir}parts.bin");
+    // Not a multiple of the 1 MB part size, so the last range is short.
+    let content: Vec<u8> = (0..3 * 1024 * 1024 + 123)
+        .map(|i| (i % 251) as u8)
+        .collect();
+    client
+        .operator()
+        .write(&remote_path, content.clone())
+        .await?;
+    let local_dir = std::env::temp_dir().join(format!("storify-dl-parts-{}", Uuid::new_v4()));
+
+    storify_cmd()
+        .args(["get", "--part-size", "1", "--concurrency", "3"])
+        .arg(&remote_path)
+        .arg(local_dir.join(""))
+        .assert()
+        .success();
+
+    let actual = fs::read(local_dir.join("parts.bin")).await?;
+    assert_eq!(content.len(), actual.len());
+    assert!(content == actual, "chunked download content mismatch");
+
+    let _ = fs::remove_dir_all(&local_dir).await;
+    Ok(())
+}
+
+async fn test_download_directory_with_filters(client: StorageClient) -> Result<()> {
+    let remote_dir = TEST_FIXTURE.new_dir_path();
+    for name in ["app/a.log", "app/b.txt", "archive/old.log", "c.log"] {
+        client
+            .operator()
+            .write(&format!("{remote_dir}{name}"), name.as_bytes().to_vec())
+            .await?;
+    }
+    let local_dir = std::env::temp_dir().join(format!("storify-dl-filter-{}", Uuid::new_v4()));
+
+    storify_cmd()
+        .args(["get", "--include", "*.log", "--exclude", "archive/*"])
+        .arg(&remote_dir)
+        .arg(&local_dir)
+        .assert()
+        .success();
+    assert_eq!(fs::read(local_dir.join("app/a.log")).await?, b"app/a.log");
+    assert!(local_dir.join("c.log").exists());
+    assert!(!local_dir.join("app/b.txt").exists());
+    assert!(!local_dir.join("archive").exists());
+
+    // Everything was written just now, so nothing is newer than an hour from now.
+    let later = (chrono::Utc::now() + chrono::Duration::hours(1)).to_rfc3339();
+    let empty_dir = local_dir.join("none");
+    storify_cmd()
+        .args(["get", "--newer-than", &later])
+        .arg(&remote_dir)
+        .arg(&empty_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("matched the filters"));
+    assert!(!empty_dir.exists());
+
+    let _ = fs::remove_dir_all(&local_dir).await;
+    Ok(())
+}
+
+async fn test_put_and_get_preserve_file_metadata(client: StorageClient) -> Result<()> {
+    let local_dir = tempfile::tempdir()?;
+    let source = local_dir.path().join("tool.sh");
+    std::fs::write(&source, b"#!/bin/sh\n")?;
+    let mtime = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_600_000_000);
+    std::fs::File::options()
+        .write(true)
+        .open(&source)?
+        .set_modified(mtime)?;
+    let remote_path = format!("{}tool.sh", TEST_FIXTURE.new_dir_path());
+
+    if !client
+        .operator()
+        .info()
+        .full_capability()
+        .write_with_user_metadata
+    {
+        storify_cmd()
+            .args(["put", "--preserve"])
+            .arg(&source)
+            .arg(&remote_path)
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("--preserve needs object metadata"));
+        return Ok(());
+    }
+
+    storify_cmd()
+        .args(["put", "--preserve"])
+        .arg(&source)
+        .arg(&remote_path)
+        .assert()
+        .success();
+    let restored = local_dir.path().join("restored.sh");
+    storify_cmd()
+        .args(["get", "--preserve", &remote_path])
+        .arg(&restored)
+        .assert()
+        .success();
+    assert_eq!(std::fs::metadata(&restored)?.modified()?, mtime);
+
+    // Without --preserve the download keeps its own timestamp.
+    let plain = local_dir.path().join("plain.sh");
+    storify_cmd()
+        .args(["get", &remote_path])
+        .arg(&plain)
+        .assert()
+        .success();
+    assert_ne!(std::fs::metadata(&plain)?.modified()?, mtime);
+    Ok(())
+}
+
+#[cfg(unix)]
+async fn test_put_and_get_symlink_policies(client: StorageClient) -> Result<()> {
+    let local_dir = tempfile::tempdir()?;
+    std::fs::write(local_dir.path().join("real.txt"), b"real\n")?;
+    std::os::unix::fs::symlink("real.txt", local_dir.path().join("alias.txt"))?;
+    let remote_dir = TEST_FIXTURE.new_dir_path();
+
+    let skipped = format!("{remote_dir}skipped/");
+    storify_cmd()
+        .args(["put", "-R", "--no-follow-symlinks"])
+        .arg(local_dir.path().join(""))
+        .arg(&skipped)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Skipping 1 symlink(s)"));
+    assert!(
+        client
+            .operator()
+            .exists(&format!("{skipped}real.txt"))
+            .await?
+    );
+    assert!(
+        !client
+            .operator()
+            .exists(&format!("{skipped}alias.txt"))
+            .await?
+    );
+
+    if !client
+        .operator()
+        .info()
+        .full_capability()
+        .write_with_user_metadata
+    {
+        return Ok(());
+    }
+    let preserved = format!("{remote_dir}preserved/");
+    storify_cmd()
+        .args(["put", "-R", "--preserve-links"])
+        .arg(local_dir.path().join(""))
+        .arg(&preserved)
+        .assert()
+        .success();
+    let restored = local_dir.path().join("restored");
+    storify_cmd()
+        .args(["get", "--preserve-links", &preserved])
+        .arg(&restored)
+        .assert()
+        .success();
+    assert_eq!(
+        std::fs::read_link(restored.join("alias.txt"))?,
+        std::path::Path::new("real.txt")
+    );
+    assert_eq!(std::fs::read(restored.join("alias.txt"))?, b"real\n");
+    Ok(())
+}
+
+#[cfg(not(unix))]
+async fn test_put_and_get_symlink_policies(_client: StorageClient) -> Result<()> {
+    Ok(())
+}
@@ -5,7 +5,12 @@ use crate::tests::behavior::*;
 use assert_cmd::prelude::*;
 use predicates::prelude::*;
 
-register_behavior_tests!(test_find_by_name_glob, test_find_type_file,);
+register_behavior_tests!(
+    test_find_by_name_glob,
+    test_find_type_file,
+    test_find_max_results_continues_after_key,
+    test_find_size_depth_and_delete,
+);
 
 async fn test_find_by_name_glob(_client: StorageClient) -> Result<()> {
     let env = E2eTestEnv::new().await;
@@ -37,6 +42,53 @@ async fn test_find_by_name_glob(_client: StorageClient) -> Result<()> {
     Ok(())
 }
 
+async fn test_find_size_depth_and_delete(_client: StorageClient) -> Result<()> {
+    let env = E2eTestEnv::new().await;
+    let op = env.verifier.operator();
+    let root = TEST_FIXTURE.new_dir_path();
+    let big = format!("{root}big.bin");
+    let small = format!("{root}nested/small.txt");
+    op.write(&big, vec![0u8; 4096]).await?;
+    op.write(&small, b"tiny".to_vec()).await?;
+
+    storify_cmd()
+        .args(["find", &root, "--size", "+1k"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(&big).and(predicate::str::contains(&small).not()));
+
+    storify_cmd()
+        .args(["find", &root, "--type", "f", "--maxdepth", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(&big).and(predicate::str::contains(&small).not()));
+
+    storify_cmd()
+        .args([
+            "find",
+            &root,
+            "--mtime",
+            "-1",
+            "--type",
+            "f",
+            "--exec",
+            "echo seen {}",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("seen {small}")));
+
+    storify_cmd()
+        .args(["find", &root, "--size", "-1k", "--type", "f", "--delete"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("Deleted: {small}")));
+    assert!(!op.exists(&small).await?);
+    assert!(op.exists(&big).await?);
+
+    Ok(())
+}
+
 async fn test_find_type_file(_client: StorageClient) -> Result<()> {
     let env = E2eTestEnv::new().await;
     let root = TEST_FIXTURE.new_dir_path();
@@ -57,3 +109,30 @@ async fn test_find_type_file(_client: StorageClient) -> Result<()> {
 
     Ok(())
 }
+
+async fn test_find_max_results_continues_after_key(_client: StorageClient) -> Result<()> {
+    let env = E2eTestEnv::new().await;
+    let root = TEST_FIXTURE.new_dir_path();
+    let files: Vec<String> = (0..4).map(|i| format!("{root}f{i}.log")).collect();
+    for file in &files {
+        env.verifier.operator().write(file, b"x".to_vec()).await?;
+    }
+
+    storify_cmd()
+        .args(["find", &root, "--type", "f", "--max-results", "2"])
+        .assert()
+        .success()
+        .stdout(format!("{}\n{}\n", files[0], files[1]))
+        .stderr(predicate::str::contains(format!(
+            "--start-after '{}'",
+            files[1]
+        )));
+
+    storify_cmd()
+        .args(["find", &root, "--type", "f", "--start-after", &files[1]])
+        .assert()
+        .success()
+        .stdout(format!("{}\n{}\n", files[2], files[3]));
+
+    Ok(())
+}
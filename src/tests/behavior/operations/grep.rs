@@ -4,11 +4,18 @@ use crate::storage::StorageClient;
 use crate::tests::behavior::*;
 use assert_cmd::prelude::*;
 use predicates::prelude::*;
+use std::io::Read;
+use std::process::Stdio;
+use std::time::Duration;
 
 register_behavior_tests!(
     test_grep_basic,
     test_grep_recursive_basic,
     test_grep_directory_without_recursive_flag,
+    test_grep_follow_prints_only_new_lines,
+    test_grep_regex_invert_count_and_max_count,
+    test_grep_recursive_include_exclude,
+    test_grep_context_and_binary_files,
 );
 
 async fn prepare_remote_file(verifier: &StorageClient, content: &[u8]) -> Result<String> {
@@ -33,6 +40,78 @@ async fn test_grep_basic(_client: StorageClient) -> Result<()> {
     Ok(())
 }
 
+async fn test_grep_regex_invert_count_and_max_count(_client: StorageClient) -> Result<()> {
+    let env = E2eTestEnv::new().await;
+    let content = b"error 1\nok\nERROR 22\nwarn 3\nerror 4\n";
+    let remote_path = prepare_remote_file(&env.verifier, content).await?;
+
+    storify_cmd()
+        .args(["grep", "-Ei", r"^error \d{2}$"])
+        .arg(&remote_path)
+        .assert()
+        .success()
+        .stdout("ERROR 22\n");
+
+    storify_cmd()
+        .args(["grep", "-vc", "error"])
+        .arg(&remote_path)
+        .assert()
+        .success()
+        .stdout("3\n");
+
+    storify_cmd()
+        .args(["grep", "-n", "--max-count", "1", "error"])
+        .arg(&remote_path)
+        .assert()
+        .success()
+        .stdout("1:error 1\n");
+
+    storify_cmd()
+        .args(["grep", "-l", "warn"])
+        .arg(&remote_path)
+        .assert()
+        .success()
+        .stdout(format!("{remote_path}\n"));
+
+    Ok(())
+}
+
+async fn test_grep_context_and_binary_files(_client: StorageClient) -> Result<()> {
+    let env = E2eTestEnv::new().await;
+    let content = b"1\n2 hit\n3\n4\n5\n6\n7 hit\n8\n";
+    let remote_path = prepare_remote_file(&env.verifier, content).await?;
+
+    storify_cmd()
+        .args(["grep", "-n", "-C", "1", "hit"])
+        .arg(&remote_path)
+        .assert()
+        .success()
+        .stdout("1-1\n2:2 hit\n3-3\n--\n6-6\n7:7 hit\n8-8\n");
+
+    storify_cmd()
+        .args(["grep", "-A", "2", "--max-count", "1", "hit"])
+        .arg(&remote_path)
+        .assert()
+        .success()
+        .stdout("2 hit\n3\n4\n");
+
+    let binary_path = prepare_remote_file(&env.verifier, b"hit\0\x01\n").await?;
+    storify_cmd()
+        .args(["grep", "hit"])
+        .arg(&binary_path)
+        .assert()
+        .success()
+        .stdout("");
+    storify_cmd()
+        .args(["grep", "-a", "-c", "hit"])
+        .arg(&binary_path)
+        .assert()
+        .success()
+        .stdout("1\n");
+
+    Ok(())
+}
+
 async fn test_grep_recursive_basic(_client: StorageClient) -> Result<()> {
     let env = E2eTestEnv::new().await;
     let root_dir = TEST_FIXTURE.new_dir_path();
@@ -66,6 +145,35 @@ async fn test_grep_recursive_basic(_client: StorageClient) -> Result<()> {
     Ok(())
 }
 
+async fn test_grep_recursive_include_exclude(_client: StorageClient) -> Result<()> {
+    let env = E2eTestEnv::new().await;
+    let root_dir = TEST_FIXTURE.new_dir_path();
+    for (name, content) in [
+        ("a.log", "needle in a\n"),
+        ("b.txt", "needle in b\n"),
+        ("skip/c.log", "needle in c\n"),
+    ] {
+        env.verifier
+            .operator()
+            .write(&format!("{root_dir}{name}"), content.as_bytes().to_vec())
+            .await?;
+    }
+
+    storify_cmd()
+        .args(["grep", "-R", "--include", "*.log", "--exclude", "skip/*"])
+        .args(["--concurrency", "2", "needle"])
+        .arg(&root_dir)
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::contains("needle in a")
+                .and(predicate::str::contains("needle in b").not())
+                .and(predicate::str::contains("needle in c").not()),
+        );
+
+    Ok(())
+}
+
 async fn test_grep_directory_without_recursive_flag(_client: StorageClient) -> Result<()> {
     let env = E2eTestEnv::new().await;
     let root_dir = TEST_FIXTURE.new_dir_path();
@@ -86,3 +194,35 @@ async fn test_grep_directory_without_recursive_flag(_client: StorageClient) -> R
 
     Ok(())
 }
+
+async fn test_grep_follow_prints_only_new_lines(_client: StorageClient) -> Result<()> {
+    let env = E2eTestEnv::new().await;
+    let remote_path = prepare_remote_file(&env.verifier, b"old match\n").await?;
+
+    let mut child = storify_cmd()
+        .args(["grep", "--follow", "--interval", "1", "match"])
+        .arg(&remote_path)
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    env.verifier
+        .operator()
+        .write(&remote_path, b"old match\nskip\nnew match\n".to_vec())
+        .await?;
+    tokio::time::sleep(Duration::from_secs(3)).await;
+
+    child.kill()?;
+    let mut stdout = String::new();
+    child
+        .stdout
+        .take()
+        .expect("piped stdout")
+        .read_to_string(&mut stdout)?;
+    let _ = child.wait();
+
+    assert!(stdout.contains("new match"), "stdout: {stdout}");
+    assert!(!stdout.contains("old match"), "stdout: {stdout}");
+    assert!(!stdout.contains("skip"), "stdout: {stdout}");
+    Ok(())
+}
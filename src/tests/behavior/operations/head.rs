@@ -9,6 +9,7 @@ register_behavior_tests!(
     test_head_default_10_lines,
     test_head_n_lines,
     test_head_nonexistent_file,
+    test_head_many_continues_past_missing_file,
 );
 
 fn create_temp_file_with_content(content: &[u8]) -> String {
@@ -37,7 +38,7 @@ async fn test_head_default_10_lines(_client: StorageClient) -> Result<()> {
     let lines: Vec<String> = (1..=20).map(|i| format!("line-{i}")).collect();
     let local = create_temp_file_with_content((lines.join("\n") + "\n").as_bytes());
 
-    let remote = upload_and_remote_path(&local, &TEST_FIXTURE.new_file_path());
+    let remote = upload_and_remote_path(&local, &TEST_FIXTURE.new_dir_path());
     let assert = storify_cmd().arg("head").arg(&remote).assert().success();
     let output = String::from_utf8_lossy(&assert.get_output().stdout);
 
@@ -49,7 +50,7 @@ async fn test_head_default_10_lines(_client: StorageClient) -> Result<()> {
 
 async fn test_head_n_lines(_client: StorageClient) -> Result<()> {
     let local = create_temp_file_with_content(b"A\nB\nC\nD\n");
-    let remote = upload_and_remote_path(&local, &TEST_FIXTURE.new_file_path());
+    let remote = upload_and_remote_path(&local, &TEST_FIXTURE.new_dir_path());
 
     let assert = storify_cmd()
         .arg("head")
@@ -73,3 +74,21 @@ async fn test_head_nonexistent_file(_client: StorageClient) -> Result<()> {
         .stderr(predicate::str::contains("Failed to read head of file"));
     Ok(())
 }
+
+async fn test_head_many_continues_past_missing_file(_client: StorageClient) -> Result<()> {
+    let local = create_temp_file_with_content(b"present\n");
+    let dir = TEST_FIXTURE.new_dir_path();
+    let remote = upload_and_remote_path(&local, &dir);
+    let missing = join_remote_path(&dir, "missing.txt");
+
+    let assert = storify_cmd()
+        .arg("head")
+        .arg(&missing)
+        .arg(&remote)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("1 of 2 path(s) failed"));
+    let output = String::from_utf8_lossy(&assert.get_output().stdout);
+    assert!(output.contains("present"));
+    Ok(())
+}
@@ -0,0 +1,42 @@
+use crate::async_trials;
+use crate::error::Result;
+use crate::storage::StorageClient;
+use crate::tests::behavior::*;
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+
+register_behavior_tests!(
+    test_health_check_reports_healthy,
+    test_config_test_cli_succeeds,
+    test_config_test_cli_missing_bucket,
+);
+
+// Verify the health check API passes against a reachable bucket
+async fn test_health_check_reports_healthy(client: StorageClient) -> Result<()> {
+    let report = client.health_check().await;
+    assert!(report.diagnosis.is_none(), "unexpected report: {report:?}");
+    assert!(report.checks.iter().all(|check| check.passed()));
+    Ok(())
+}
+
+// Verify `config test` prints each probe and succeeds
+async fn test_config_test_cli_succeeds(_client: StorageClient) -> Result<()> {
+    storify_cmd()
+        .args(["config", "test"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("list /"))
+        .stdout(predicate::str::contains("Connection OK"));
+    Ok(())
+}
+
+// Verify a missing bucket is diagnosed as such
+async fn test_config_test_cli_missing_bucket(_client: StorageClient) -> Result<()> {
+    storify_cmd()
+        .env("STORAGE_BUCKET", "storify-missing-bucket-for-health-check")
+        .args(["config", "test"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("bucket or root path not found"));
+    Ok(())
+}
@@ -0,0 +1,37 @@
+use crate::async_trials;
+use crate::error::Result;
+use crate::storage::StorageClient;
+use crate::tests::behavior::*;
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+
+register_behavior_tests!(test_index_export_and_query_largest);
+
+async fn test_index_export_and_query_largest(client: StorageClient) -> Result<()> {
+    let dir = TEST_FIXTURE.new_dir_path();
+    let small = format!("{dir}small.txt");
+    let large = format!("{dir}large.bin");
+    client.operator().write(&small, vec![b'a'; 16]).await?;
+    client.operator().write(&large, vec![b'b'; 4096]).await?;
+
+    let tmp = tempfile::tempdir()?;
+    let db = tmp.path().join("index.db");
+
+    storify_cmd()
+        .arg("index")
+        .arg(&dir)
+        .arg("-o")
+        .arg(&db)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Indexed 2 objects"));
+
+    storify_cmd()
+        .args(["index", "query", "largest", "-n", "1", "-i"])
+        .arg(&db)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(large.as_str()))
+        .stdout(predicate::str::contains(small.as_str()).not());
+    Ok(())
+}
@@ -0,0 +1,64 @@
+use crate::async_trials;
+use crate::error::Result;
+use crate::storage::StorageClient;
+use crate::tests::behavior::*;
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+
+register_behavior_tests!(test_inventory_writes_csv_and_jsonl);
+
+async fn test_inventory_writes_csv_and_jsonl(client: StorageClient) -> Result<()> {
+    let dir = TEST_FIXTURE.new_dir_path();
+    client
+        .operator()
+        .write(&format!("{dir}a.txt"), "alpha")
+        .await?;
+    client
+        .operator()
+        .write(&format!("{dir}sub/b,c.txt"), "beta")
+        .await?;
+    let local_dir = tempfile::tempdir()?;
+
+    let csv = local_dir.path().join("manifest.csv");
+    storify_cmd()
+        .arg("inventory")
+        .arg(&dir)
+        .arg("-o")
+        .arg(&csv)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Wrote 2 objects"));
+    let text = std::fs::read_to_string(&csv)?;
+    let mut lines = text.lines();
+    assert_eq!(
+        lines.next(),
+        Some("key,size,etag,last_modified,storage_class")
+    );
+    assert!(text.contains(&format!("{dir}a.txt,5,")));
+    assert!(text.contains(&format!("\"{dir}sub/b,c.txt\",4,")));
+
+    storify_cmd()
+        .arg("inventory")
+        .arg(&dir)
+        .arg("-o")
+        .arg(&csv)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("already exists"));
+
+    let jsonl = local_dir.path().join("manifest.jsonl");
+    storify_cmd()
+        .arg("inventory")
+        .arg(&dir)
+        .arg("-o")
+        .arg(&jsonl)
+        .assert()
+        .success();
+    let records = std::fs::read_to_string(&jsonl)?
+        .lines()
+        .map(serde_json::from_str::<serde_json::Value>)
+        .collect::<std::result::Result<Vec<_>, _>>()?;
+    assert_eq!(records.len(), 2);
+    assert!(records.iter().all(|record| record["size"].is_u64()));
+    Ok(())
+}
@@ -0,0 +1,48 @@
+use crate::async_trials;
+use crate::error::Result;
+use crate::storage::StorageClient;
+use crate::tests::behavior::*;
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+
+register_behavior_tests!(test_ln_follow_links,);
+
+async fn test_ln_follow_links(client: StorageClient) -> Result<()> {
+    let alias = TEST_FIXTURE.new_file_path();
+    let target = TEST_FIXTURE.new_file_path();
+    let content = b"link target\n".to_vec();
+    client.operator().write(&target, content.clone()).await?;
+
+    if !client
+        .operator()
+        .info()
+        .full_capability()
+        .write_with_user_metadata
+    {
+        storify_cmd()
+            .args(["ln", &target, &alias])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("links need object metadata"));
+        return Ok(());
+    }
+
+    storify_cmd()
+        .args(["ln", &target, &alias])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Linked:"));
+
+    storify_cmd()
+        .args(["stat", &alias])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("link_target={target}")));
+
+    let output = storify_cmd()
+        .args(["cat", "--follow-links", &alias])
+        .output()?;
+    assert!(output.status.success());
+    assert_eq!(output.stdout, content);
+    Ok(())
+}
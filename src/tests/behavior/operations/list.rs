@@ -11,6 +11,9 @@ register_behavior_tests!(
     test_list_empty_directory,
     test_list_single_file,
     test_list_invalid_path,
+    test_list_long_full_reports_size,
+    test_list_sort_by_size_with_max_keys,
+    test_list_format_template,
 );
 
 async fn test_list_empty_directory(client: StorageClient) -> Result<()> {
@@ -30,10 +33,13 @@ async fn test_list_single_file(client: StorageClient) -> Result<()> {
     let (path, content, size) = TEST_FIXTURE.new_file(client.operator());
     client.operator().write(&path, content).await?;
 
-    let parent = path.rsplit('/').nth(1).unwrap_or("");
+    let parent = path
+        .rsplit_once('/')
+        .map(|(parent, _)| format!("{parent}/"))
+        .unwrap_or_else(|| "/".to_string());
     storify_cmd()
         .arg("ls")
-        .arg(if parent.is_empty() { "/" } else { parent })
+        .arg(&parent)
         .assert()
         .success()
         .stdout(predicate::str::contains(&path));
@@ -44,6 +50,26 @@ async fn test_list_single_file(client: StorageClient) -> Result<()> {
     Ok(())
 }
 
+async fn test_list_long_full_reports_size(client: StorageClient) -> Result<()> {
+    let dir = TEST_FIXTURE.new_dir_path();
+    let path = format!("{dir}sized.bin");
+    client.operator().write(&path, vec![0u8; 2048]).await?;
+
+    storify_cmd()
+        .args(["ls", "-L", "--full"])
+        .arg(&dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("2.0K").and(predicate::str::contains(path.as_str())));
+
+    storify_cmd()
+        .args(["ls", "--fast"])
+        .arg(&dir)
+        .assert()
+        .failure();
+    Ok(())
+}
+
 async fn test_list_invalid_path(_client: StorageClient) -> Result<()> {
     storify_cmd()
         .arg("ls")
@@ -53,3 +79,59 @@ async fn test_list_invalid_path(_client: StorageClient) -> Result<()> {
         .stdout(predicate::str::is_empty());
     Ok(())
 }
+
+async fn test_list_sort_by_size_with_max_keys(client: StorageClient) -> Result<()> {
+    let dir = TEST_FIXTURE.new_dir_path();
+    client.operator().create_dir(&dir).await?;
+    for (name, size) in [("small.bin", 3), ("large.bin", 300), ("medium.bin", 30)] {
+        client
+            .operator()
+            .write(&format!("{dir}{name}"), vec![b'x'; size])
+            .await?;
+    }
+    client.operator().create_dir(&format!("{dir}sub/")).await?;
+
+    storify_cmd()
+        .arg("ls")
+        .arg("--sort")
+        .arg("size")
+        .arg("--max-keys")
+        .arg("2")
+        .arg(&dir)
+        .assert()
+        .success()
+        .stdout(format!("{dir}large.bin\n{dir}medium.bin\n"));
+
+    storify_cmd()
+        .arg("ls")
+        .arg("--prefix-only")
+        .arg(&dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("{dir}sub/")))
+        .stdout(predicate::str::contains(".bin").not());
+    Ok(())
+}
+
+async fn test_list_format_template(client: StorageClient) -> Result<()> {
+    let dir = TEST_FIXTURE.new_dir_path();
+    client
+        .operator()
+        .write(&format!("{dir}a.txt"), "abc")
+        .await?;
+
+    storify_cmd()
+        .args(["ls", "--format", r"{name}\t{size}\t{type}"])
+        .arg(&dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("a.txt\t3\tfile\n"));
+
+    storify_cmd()
+        .args(["ls", "--format", "{owner}"])
+        .arg(&dir)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown field 'owner'"));
+    Ok(())
+}
@@ -9,6 +9,7 @@ use uuid::Uuid;
 register_behavior_tests!(
     test_create_single_directory,
     test_create_directory_with_parents,
+    test_mode_flags_warn_on_object_stores,
 );
 
 async fn test_create_single_directory(_client: StorageClient) -> Result<()> {
@@ -44,3 +45,27 @@ async fn test_create_directory_with_parents(_client: StorageClient) -> Result<()
         .success();
     Ok(())
 }
+
+async fn test_mode_flags_warn_on_object_stores(_client: StorageClient) -> Result<()> {
+    let dir = format!("mode-dir-{}", Uuid::new_v4());
+
+    storify_cmd()
+        .args(["mkdir", "--mode", "750", &dir])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Created directory"))
+        .stderr(predicate::str::contains("has no file modes"));
+    storify_cmd()
+        .args(["chmod", "-R", "640", &dir])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("chmod changed nothing"));
+    storify_cmd()
+        .args(["chmod", "rwx", &dir])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid mode 'rwx'"));
+
+    storify_cmd().arg("rm").arg("-R").arg(&dir).output().ok();
+    Ok(())
+}
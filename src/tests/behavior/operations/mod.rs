@@ -1,3 +1,5 @@
+pub mod apply;
+pub mod batch;
 pub mod cat;
 pub mod copy;
 pub mod delete;
@@ -6,12 +8,20 @@ pub mod download;
 pub mod find;
 pub mod grep;
 pub mod head;
+pub mod health;
+pub mod index;
+pub mod inventory;
+pub mod link;
 pub mod list;
 pub mod mkdir;
 pub mod mv;
 pub mod stat;
 pub mod tail;
 pub mod touch;
+pub mod trash;
 pub mod tree;
+pub mod undo;
 pub mod upload;
 pub mod usage;
+pub mod verify;
+pub mod versions;
@@ -8,12 +8,14 @@ use predicates::prelude::*;
 register_behavior_tests!(
     test_move_file_to_existing_directory,
     test_move_non_existent_file,
+    test_move_destination_trailing_slash,
 );
 
 async fn test_move_file_to_existing_directory(client: StorageClient) -> Result<()> {
     let src_dir = TEST_FIXTURE.new_dir_path();
     client.operator().create_dir(&src_dir).await?;
     let (file, content, _) = TEST_FIXTURE.new_file(client.operator());
+    let file = file_name(&file);
     let src_path = format!("{src_dir}{file}");
     client.operator().write(&src_path, content.clone()).await?;
 
@@ -34,6 +36,35 @@ async fn test_move_file_to_existing_directory(client: StorageClient) -> Result<(
     Ok(())
 }
 
+async fn test_move_destination_trailing_slash(client: StorageClient) -> Result<()> {
+    let op = client.operator();
+    let root = TEST_FIXTURE.new_dir_path();
+    let src = format!("{root}old.txt");
+    op.write(&src, b"moved".to_vec()).await?;
+    op.create_dir(&format!("{root}dir/")).await?;
+
+    storify_cmd()
+        .args(["mv", &src, &format!("{root}dir")])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("add a trailing '/'"));
+    assert!(op.exists(&src).await?);
+
+    storify_cmd()
+        .args(["mv", &src, &format!("{root}new.txt")])
+        .assert()
+        .success();
+    assert_eq!(op.read(&format!("{root}new.txt")).await?.to_vec(), b"moved");
+    assert!(!op.exists(&src).await?);
+
+    storify_cmd()
+        .args(["mv", &format!("{root}new.txt"), &format!("{root}dir/")])
+        .assert()
+        .success();
+    assert!(op.exists(&format!("{root}dir/new.txt")).await?);
+    Ok(())
+}
+
 async fn test_move_non_existent_file(client: StorageClient) -> Result<()> {
     let dest_dir = TEST_FIXTURE.new_dir_path();
     client.operator().create_dir(&dest_dir).await?;
@@ -5,7 +5,12 @@ use crate::tests::behavior::*;
 use assert_cmd::prelude::*;
 use predicates::prelude::*;
 
-register_behavior_tests!(test_stat_file_human, test_stat_not_found,);
+register_behavior_tests!(
+    test_stat_file_human,
+    test_stat_not_found,
+    test_stat_json_matches_schema,
+    test_stat_directory_totals,
+);
 
 async fn test_stat_file_human(client: StorageClient) -> Result<()> {
     let (path, content, _) = TEST_FIXTURE.new_file(client.operator());
@@ -32,3 +37,64 @@ async fn test_stat_not_found(_client: StorageClient) -> Result<()> {
         );
     Ok(())
 }
+
+async fn test_stat_json_matches_schema(client: StorageClient) -> Result<()> {
+    let (path, content, size) = TEST_FIXTURE.new_file(client.operator());
+    client.operator().write(&path, content).await?;
+
+    let output = storify_cmd().args(["stat", "--json"]).arg(&path).output()?;
+    assert!(output.status.success());
+    let stat: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+
+    let output = storify_cmd().args(["schema", "stat"]).output()?;
+    assert!(output.status.success());
+    let schema: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+
+    assert_eq!(
+        stat["schema_version"],
+        schema["properties"]["schema_version"]["const"]
+    );
+    assert_eq!(stat["size"], size as u64);
+    for field in schema["required"].as_array().unwrap() {
+        assert!(
+            stat.get(field.as_str().unwrap()).is_some(),
+            "missing {field}"
+        );
+    }
+    Ok(())
+}
+
+async fn test_stat_directory_totals(client: StorageClient) -> Result<()> {
+    let dir = TEST_FIXTURE.new_dir_path();
+    client
+        .operator()
+        .write(&format!("{dir}top.txt"), "abc")
+        .await?;
+    client
+        .operator()
+        .write(&format!("{dir}a/b/deep.txt"), "hello")
+        .await?;
+
+    storify_cmd()
+        .arg("stat")
+        .arg(&dir)
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::contains("files=2")
+                .and(predicate::str::contains("total_size=8"))
+                .and(predicate::str::contains("max_depth=3")),
+        );
+
+    storify_cmd()
+        .args(["stat", "--shallow"])
+        .arg(&dir)
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::contains("files=1")
+                .and(predicate::str::contains("total_size=3"))
+                .and(predicate::str::contains("shallow=true")),
+        );
+    Ok(())
+}
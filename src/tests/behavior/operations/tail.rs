@@ -35,7 +35,7 @@ fn upload_and_remote_path(local_path: &str, dest_prefix: &str) -> String {
 async fn test_tail_default_10_lines(_client: StorageClient) -> Result<()> {
     let lines: Vec<String> = (1..=30).map(|i| format!("line-{i}")).collect();
     let local = create_temp_file_with_content((lines.join("\n") + "\n").as_bytes());
-    let remote = upload_and_remote_path(&local, &TEST_FIXTURE.new_file_path());
+    let remote = upload_and_remote_path(&local, &TEST_FIXTURE.new_dir_path());
 
     let assert = storify_cmd().arg("tail").arg(&remote).assert().success();
     let output = String::from_utf8_lossy(&assert.get_output().stdout);
@@ -48,7 +48,7 @@ async fn test_tail_default_10_lines(_client: StorageClient) -> Result<()> {
 
 async fn test_tail_n_lines(_client: StorageClient) -> Result<()> {
     let local = create_temp_file_with_content(b"A\nB\nC\nD\nE\n");
-    let remote = upload_and_remote_path(&local, &TEST_FIXTURE.new_file_path());
+    let remote = upload_and_remote_path(&local, &TEST_FIXTURE.new_dir_path());
 
     let assert = storify_cmd()
         .arg("tail")
@@ -0,0 +1,78 @@
+use crate::async_trials;
+use crate::error::Result;
+use crate::storage::StorageClient;
+use crate::tests::behavior::*;
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+
+register_behavior_tests!(
+    test_trash_and_restore_file,
+    test_trash_directory_requires_recursive,
+);
+
+async fn test_trash_and_restore_file(client: StorageClient) -> Result<()> {
+    let op = client.operator();
+    let (path, content, _) = TEST_FIXTURE.new_file(op);
+    op.write(&path, content.clone()).await?;
+
+    storify_cmd()
+        .args(["rm", "--force", "--trash", &path])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Trashed:"));
+    assert!(!op.exists(&path).await?);
+
+    storify_cmd()
+        .args(["trash", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(path.as_str()));
+
+    op.write(&path, b"newer".to_vec()).await?;
+    storify_cmd()
+        .args(["trash", "restore", &path])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("use --force to overwrite"));
+    assert_eq!(op.read(&path).await?.to_vec(), b"newer");
+
+    storify_cmd()
+        .args(["trash", "restore", "--force", &path])
+        .assert()
+        .success();
+    assert_eq!(op.read(&path).await?.to_vec(), content);
+
+    storify_cmd()
+        .args(["trash", "restore", &path])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Not in the trash"));
+    Ok(())
+}
+
+async fn test_trash_directory_requires_recursive(client: StorageClient) -> Result<()> {
+    let op = client.operator();
+    let root = TEST_FIXTURE.new_dir_path();
+    let file = format!("{root}kept.txt");
+    op.write(&file, b"kept".to_vec()).await?;
+
+    storify_cmd()
+        .args(["rm", "--force", "--trash", &root])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("without -R"));
+    assert!(op.exists(&file).await?);
+
+    storify_cmd()
+        .args(["rm", "--force", "--trash", "-R", &root])
+        .assert()
+        .success();
+    assert!(!op.exists(&file).await?);
+
+    storify_cmd()
+        .args(["trash", "restore", &root])
+        .assert()
+        .success();
+    assert_eq!(op.read(&file).await?.to_vec(), b"kept");
+    Ok(())
+}
@@ -5,7 +5,11 @@ use crate::tests::behavior::*;
 use assert_cmd::prelude::*;
 use predicates::prelude::*;
 
-register_behavior_tests!(test_tree_nested, test_tree_depth_limit,);
+register_behavior_tests!(
+    test_tree_nested,
+    test_tree_depth_limit,
+    test_tree_size_and_json,
+);
 
 async fn test_tree_nested(client: StorageClient) -> Result<()> {
     let root = TEST_FIXTURE.new_dir_path();
@@ -41,3 +45,36 @@ async fn test_tree_depth_limit(client: StorageClient) -> Result<()> {
         .stdout(predicate::str::contains(&sub).not());
     Ok(())
 }
+
+async fn test_tree_size_and_json(client: StorageClient) -> Result<()> {
+    let root = TEST_FIXTURE.new_dir_path();
+    client
+        .operator()
+        .write(&format!("{root}sub/a.bin"), vec![0u8; 2048])
+        .await?;
+    client
+        .operator()
+        .write(&format!("{root}b.txt"), vec![b'x'; 10])
+        .await?;
+
+    storify_cmd()
+        .args(["tree", "--size"])
+        .arg(&root)
+        .assert()
+        .success()
+        .stdout(
+            predicate::str::contains("[  2.0K]  sub/")
+                .and(predicate::str::contains("[   10B]  b.txt"))
+                .and(predicate::str::contains("1 directory, 2 files")),
+        );
+
+    let output = storify_cmd()
+        .args(["tree", "--size", "--format", "json"])
+        .arg(&root)
+        .output()?;
+    assert!(output.status.success());
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(json[0]["size"], 2058);
+    assert_eq!(json[1]["files"], 2);
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use crate::async_trials;
+use crate::error::Result;
+use crate::storage::StorageClient;
+use crate::tests::behavior::*;
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+
+register_behavior_tests!(test_undo_rm_and_mv);
+
+async fn test_undo_rm_and_mv(client: StorageClient) -> Result<()> {
+    let op = client.operator();
+    let journal = tempfile::tempdir()?;
+    let journal_path = journal.path().join("journal.jsonl");
+    let (path, content, _) = TEST_FIXTURE.new_file(op);
+    let moved = format!("{path}.moved");
+    op.write(&path, content.clone()).await?;
+
+    storify_cmd()
+        .env("STORIFY_JOURNAL", &journal_path)
+        .args(["mv", &path, &moved])
+        .assert()
+        .success();
+    storify_cmd()
+        .env("STORIFY_JOURNAL", &journal_path)
+        .args(["rm", "--force", &moved])
+        .assert()
+        .success();
+    assert!(!op.exists(&moved).await?);
+
+    storify_cmd()
+        .env("STORIFY_JOURNAL", &journal_path)
+        .args(["undo", "--force"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Restored:"));
+    assert_eq!(op.read(&moved).await?.to_vec(), content);
+
+    storify_cmd()
+        .env("STORIFY_JOURNAL", &journal_path)
+        .args(["undo", "--force"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Moved back:"));
+    assert_eq!(op.read(&path).await?.to_vec(), content);
+    assert!(!op.exists(&moved).await?);
+
+    storify_cmd()
+        .env("STORIFY_JOURNAL", &journal_path)
+        .args(["undo", "--force"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Nothing to undo"));
+    Ok(())
+}
@@ -4,12 +4,19 @@ use crate::storage::StorageClient;
 use crate::tests::behavior::*;
 use assert_cmd::prelude::*;
 use predicates::prelude::*;
-register_behavior_tests!(test_storage_client_write, e2e_test_upload_command_succeeds,);
+register_behavior_tests!(
+    test_storage_client_write,
+    e2e_test_upload_command_succeeds,
+    test_upload_directory_with_bandwidth_limits,
+    test_upload_onto_existing_directory_needs_trailing_slash,
+    test_upload_directory_with_filters_and_ignore_file,
+    test_put_and_get_dry_run_transfer_nothing,
+);
 
 async fn test_storage_client_write(_client: StorageClient) -> Result<()> {
     let content = b"upload small file\n".to_vec();
     let source_path = write_temp_file(&content, ".txt");
-    let dest_prefix = TEST_FIXTURE.new_file_path();
+    let dest_prefix = TEST_FIXTURE.new_dir_path();
     let file_name = source_path
         .file_name()
         .unwrap()
@@ -33,9 +40,8 @@ async fn test_storage_client_write(_client: StorageClient) -> Result<()> {
 async fn e2e_test_upload_command_succeeds(_client: StorageClient) -> Result<()> {
     let content = b"upload e2e file\n".to_vec();
     let source_path = write_temp_file(&content, ".txt");
+    // Without a trailing '/', the destination is the exact object key.
     let dest_path = TEST_FIXTURE.new_file_path();
-    let final_dest_path = format!("{}", source_path.file_name().unwrap().to_string_lossy());
-    let final_dest_path = join_remote_path(&dest_path, &final_dest_path);
 
     storify_cmd()
         .arg("put")
@@ -46,8 +52,168 @@ async fn e2e_test_upload_command_succeeds(_client: StorageClient) -> Result<()>
         .stdout(predicate::str::contains("Upload"));
 
     let env = E2eTestEnv::new().await;
-    let actual_content = env.verifier.operator().read(&final_dest_path).await?;
+    let actual_content = env.verifier.operator().read(&dest_path).await?;
     assert_eq!(content, actual_content.to_vec());
 
     Ok(())
 }
+
+async fn test_upload_onto_existing_directory_needs_trailing_slash(
+    client: StorageClient,
+) -> Result<()> {
+    let source_path = write_temp_file(b"into a directory\n", ".txt");
+    let file_name = source_path
+        .file_name()
+        .unwrap()
+        .to_string_lossy()
+        .to_string();
+    let dest_dir = TEST_FIXTURE.new_dir_path();
+    client
+        .operator()
+        .write(&format!("{dest_dir}existing.txt"), b"x".to_vec())
+        .await?;
+
+    storify_cmd()
+        .arg("put")
+        .arg(&source_path)
+        .arg(dest_dir.trim_end_matches('/'))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("add a trailing '/'"));
+
+    storify_cmd()
+        .arg("put")
+        .arg(&source_path)
+        .arg(&dest_dir)
+        .assert()
+        .success();
+    assert!(
+        client
+            .operator()
+            .exists(&join_remote_path(&dest_dir, &file_name))
+            .await?
+    );
+    Ok(())
+}
+
+// Verify throttled concurrent directory uploads transfer every file intact
+async fn test_upload_directory_with_bandwidth_limits(_client: StorageClient) -> Result<()> {
+    let local_dir = tempfile::tempdir()?;
+    let large = vec![b'L'; 256 * 1024];
+    let small = b"small file\n".to_vec();
+    std::fs::write(local_dir.path().join("large.bin"), &large)?;
+    for i in 0..3 {
+        std::fs::write(local_dir.path().join(format!("small-{i}.txt")), &small)?;
+    }
+    let dest_dir = TEST_FIXTURE.new_dir_path();
+
+    storify_cmd()
+        .arg("put")
+        .arg("-R")
+        // A trailing '/' on the source uploads its contents rather than the directory itself.
+        .arg(local_dir.path().join(""))
+        .arg(&dest_dir)
+        .args([
+            "--concurrency",
+            "2",
+            "--per-file-limit",
+            "4M",
+            "--total-limit",
+            "8M",
+        ])
+        .assert()
+        .success();
+
+    let env = E2eTestEnv::new().await;
+    let op = env.verifier.operator();
+    assert_eq!(
+        op.read(&join_remote_path(&dest_dir, "large.bin"))
+            .await?
+            .to_vec(),
+        large
+    );
+    for i in 0..3 {
+        let path = join_remote_path(&dest_dir, &format!("small-{i}.txt"));
+        assert_eq!(op.read(&path).await?.to_vec(), small);
+    }
+    Ok(())
+}
+
+// Verify --include/--exclude and .storifyignore select what a recursive upload transfers
+async fn test_upload_directory_with_filters_and_ignore_file(_client: StorageClient) -> Result<()> {
+    let local_dir = tempfile::tempdir()?;
+    for name in [
+        "node_modules/dep/index.js",
+        "src/main.rs",
+        "src/debug.log",
+        "dist/app.js",
+        "README.md",
+    ] {
+        let path = local_dir.path().join(name);
+        std::fs::create_dir_all(path.parent().unwrap())?;
+        std::fs::write(path, name)?;
+    }
+    std::fs::write(
+        local_dir.path().join(".storifyignore"),
+        "node_modules/\n*.log\n",
+    )?;
+    let dest_dir = TEST_FIXTURE.new_dir_path();
+
+    storify_cmd()
+        .args(["put", "-R", "--exclude", "dist"])
+        .arg(local_dir.path().join(""))
+        .arg(&dest_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Skipping 1 file(s)"))
+        .stdout(predicate::str::contains("2 file(s), "))
+        .stdout(predicate::str::contains("1 skipped, 0 failed"));
+
+    let env = E2eTestEnv::new().await;
+    let op = env.verifier.operator();
+    for uploaded in ["src/main.rs", "README.md"] {
+        assert!(op.exists(&join_remote_path(&dest_dir, uploaded)).await?);
+    }
+    for skipped in [
+        "node_modules/dep/index.js",
+        "src/debug.log",
+        "dist/app.js",
+        ".storifyignore",
+    ] {
+        assert!(!op.exists(&join_remote_path(&dest_dir, skipped)).await?);
+    }
+    Ok(())
+}
+
+// Verify --dry-run lists the planned files without transferring them
+async fn test_put_and_get_dry_run_transfer_nothing(client: StorageClient) -> Result<()> {
+    let local_dir = tempfile::tempdir()?;
+    std::fs::write(local_dir.path().join("data.bin"), vec![b'd'; 1234])?;
+    let dest_dir = TEST_FIXTURE.new_dir_path();
+
+    storify_cmd()
+        .args(["put", "-R", "--dry-run"])
+        .arg(local_dir.path().join(""))
+        .arg(&dest_dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Would upload:"))
+        .stdout(predicate::str::contains("(1234 bytes)"))
+        .stdout(predicate::str::contains("Would upload 1 file(s)"));
+    let remote_file = join_remote_path(&dest_dir, "data.bin");
+    assert!(!client.operator().exists(&remote_file).await?);
+
+    client
+        .operator()
+        .write(&remote_file, vec![b'd'; 1234])
+        .await?;
+    let local_copy = local_dir.path().join("copy.bin");
+    storify_cmd()
+        .args(["get", "--dry-run", &remote_file])
+        .arg(&local_copy)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Would download 1 file(s)"));
+    assert!(!local_copy.exists());
+    Ok(())
+}
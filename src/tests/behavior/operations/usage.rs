@@ -5,7 +5,11 @@ use crate::tests::behavior::*;
 use assert_cmd::prelude::*;
 use predicates::prelude::*;
 
-register_behavior_tests!(test_du_summary_total_size,);
+register_behavior_tests!(
+    test_du_summary_total_size,
+    test_du_max_depth_sorted_by_size,
+    test_du_report_csv_by_top_level,
+);
 
 pub async fn test_du_summary_total_size(client: StorageClient) -> Result<()> {
     // Prepare a directory with files of deterministic sizes
@@ -42,6 +46,7 @@ pub async fn test_du_summary_total_size(client: StorageClient) -> Result<()> {
     let mut cmd = storify_cmd();
     cmd.arg("du")
         .arg("-s")
+        .arg("-h")
         .arg(&dir)
         .assert()
         .success()
@@ -55,3 +60,63 @@ pub async fn test_du_summary_total_size(client: StorageClient) -> Result<()> {
 
     Ok(())
 }
+
+pub async fn test_du_max_depth_sorted_by_size(client: StorageClient) -> Result<()> {
+    let dir = TEST_FIXTURE.new_dir_path();
+    client.operator().create_dir(&dir).await?;
+    client
+        .operator()
+        .write(&format!("{dir}small/a.bin"), vec![b'a'; 10])
+        .await?;
+    client
+        .operator()
+        .write(&format!("{dir}big/nested/b.bin"), vec![b'b'; 300])
+        .await?;
+
+    storify_cmd()
+        .arg("du")
+        .arg("--max-depth")
+        .arg("1")
+        .arg("--sort")
+        .arg("size")
+        .arg(&dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(format!(
+            "300 {dir}big/\n10 {dir}small/\n"
+        )))
+        .stdout(predicate::str::contains("nested").not())
+        .stdout(predicate::str::contains(format!("310 {dir}")));
+    Ok(())
+}
+
+pub async fn test_du_report_csv_by_top_level(client: StorageClient) -> Result<()> {
+    let dir = TEST_FIXTURE.new_dir_path();
+    for (path, size) in [
+        ("team-a/x.bin", 300),
+        ("team-a/y.bin", 100),
+        ("team-b/z.bin", 100),
+    ] {
+        client
+            .operator()
+            .write(&format!("{dir}{path}"), vec![b'a'; size])
+            .await?;
+    }
+
+    storify_cmd()
+        .args([
+            "du",
+            "--report",
+            "--group-by",
+            "top-level",
+            "--output",
+            "csv",
+        ])
+        .arg(&dir)
+        .assert()
+        .success()
+        .stdout(format!(
+            "prefix,objects,bytes,percent\n{dir}team-a/,2,400,80.0\n{dir}team-b/,1,100,20.0\n"
+        ));
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use crate::async_trials;
+use crate::error::Result;
+use crate::storage::StorageClient;
+use crate::tests::behavior::*;
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+
+register_behavior_tests!(test_verify_local_directory_against_prefix);
+
+async fn test_verify_local_directory_against_prefix(client: StorageClient) -> Result<()> {
+    let local_dir = tempfile::tempdir()?;
+    std::fs::create_dir(local_dir.path().join("sub"))?;
+    std::fs::write(local_dir.path().join("a.txt"), "alpha")?;
+    std::fs::write(local_dir.path().join("sub/b.txt"), "beta")?;
+    let dir = TEST_FIXTURE.new_dir_path();
+    client
+        .operator()
+        .write(&format!("{dir}a.txt"), "alpha")
+        .await?;
+    client
+        .operator()
+        .write(&format!("{dir}sub/b.txt"), "beta")
+        .await?;
+
+    storify_cmd()
+        .arg("verify")
+        .arg(local_dir.path())
+        .arg(&dir)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "2 verified, 0 mismatched, 0 missing, 0 without checksum",
+        ));
+
+    client
+        .operator()
+        .write(&format!("{dir}sub/b.txt"), "BETA")
+        .await?;
+    std::fs::write(local_dir.path().join("c.txt"), "gamma")?;
+    storify_cmd()
+        .arg("verify")
+        .arg(local_dir.path())
+        .arg(&dir)
+        .assert()
+        .failure()
+        .stdout(
+            predicate::str::contains("M sub/b.txt (md5 ")
+                .and(predicate::str::contains("- c.txt (missing remotely)")),
+        )
+        .stderr(predicate::str::contains(
+            "2 of 3 file(s) are missing or differ remotely",
+        ));
+    Ok(())
+}
@@ -0,0 +1,41 @@
+use crate::async_trials;
+use crate::error::Result;
+use crate::storage::StorageClient;
+use crate::tests::behavior::*;
+use assert_cmd::prelude::*;
+use predicates::prelude::*;
+
+register_behavior_tests!(
+    test_versions_lists_current_object,
+    test_restore_unknown_version_fails,
+);
+
+async fn test_versions_lists_current_object(client: StorageClient) -> Result<()> {
+    let (path, content, _) = TEST_FIXTURE.new_file(client.operator());
+    client.operator().write(&path, content).await?;
+
+    storify_cmd()
+        .arg("versions")
+        .arg(&path)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(path.as_str()));
+    Ok(())
+}
+
+async fn test_restore_unknown_version_fails(client: StorageClient) -> Result<()> {
+    let (path, content, _) = TEST_FIXTURE.new_file(client.operator());
+    client.operator().write(&path, content.clone()).await?;
+
+    storify_cmd()
+        .arg("restore")
+        .arg(&path)
+        .args(["--version-id", "does-not-exist"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Failed to restore"));
+
+    let current = client.operator().read(&path).await?;
+    assert_eq!(content, current.to_vec());
+    Ok(())
+}
@@ -1,5 +1,5 @@
 use crate::error::Result;
-use crate::storage::StorageClient;
+use crate::storage::{StorageClient, StorageProvider};
 use assert_cmd::prelude::*;
 use libtest_mimic::{Failed, Trial};
 use opendal::Operator;
@@ -138,27 +138,75 @@ pub fn join_remote_path(remote_path: &str, file_name: &str) -> String {
     }
 }
 
+/// Last segment of a fixture path, for tests that nest a generated file under another directory.
+pub fn file_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+/// Root prefix under which every behavior suite creates its scope.
+const FIXTURE_ROOT: &str = "storify-behavior";
+
+/// Hands out unique test paths inside a per-suite scope and cleans them up afterwards.
+///
+/// Each suite run gets its own prefix (`storify-behavior/<provider>-<uuid>/`), so parallel
+/// or cross-provider runs against the same bucket never see each other's objects.
 pub struct Fixture {
+    scope: std::sync::Mutex<String>,
     pub paths: std::sync::Mutex<Vec<String>>,
 }
 
+/// Outcome of tearing down a suite scope.
+#[derive(Debug, Default)]
+pub struct CleanupReport {
+    pub scope: String,
+    pub removed: usize,
+    /// Objects left in the scope that no test registered with the fixture
+    pub leaked: Vec<String>,
+}
+
 impl Fixture {
     pub const fn new() -> Self {
         Self {
+            scope: std::sync::Mutex::new(String::new()),
             paths: std::sync::Mutex::new(vec![]),
         }
     }
 
-    pub fn new_dir_path(&self) -> String {
-        let path = format!("{}/", Uuid::new_v4());
+    /// Start a new suite scope for the given provider, discarding any previous registrations.
+    pub fn begin_suite(&self, provider: StorageProvider) -> String {
+        let scope = format!(
+            "{FIXTURE_ROOT}/{}-{}/",
+            provider.as_str(),
+            Uuid::new_v4().simple()
+        );
+        *self.scope.lock().unwrap() = scope.clone();
+        self.paths.lock().unwrap().clear();
+        scope
+    }
+
+    /// Current suite scope (empty before `begin_suite`).
+    pub fn scope(&self) -> String {
+        self.scope.lock().unwrap().clone()
+    }
+
+    /// Place `name` inside the scope (unless it already is) and track it for cleanup.
+    fn register(&self, name: String) -> String {
+        let scope = self.scope();
+        let path = if name.starts_with(&scope) {
+            name
+        } else {
+            format!("{scope}{name}")
+        };
         self.paths.lock().unwrap().push(path.clone());
         path
     }
 
+    pub fn new_dir_path(&self) -> String {
+        self.register(format!("{}/", Uuid::new_v4()))
+    }
+
     pub fn new_file_path(&self) -> String {
-        let path = format!("{}", Uuid::new_v4());
-        self.paths.lock().unwrap().push(path.clone());
-        path
+        self.register(Uuid::new_v4().to_string())
     }
 
     pub fn new_file(&self, op: &Operator) -> (String, Vec<u8>, usize) {
@@ -173,11 +221,10 @@ impl Fixture {
 
     pub fn new_file_with_range(
         &self,
-        path: impl Into<String>,
+        name: impl Into<String>,
         range: std::ops::Range<usize>,
     ) -> (String, Vec<u8>, usize) {
-        let path = path.into();
-        self.paths.lock().unwrap().push(path.clone());
+        let path = self.register(name.into());
 
         let mut rng = rand::rng();
         let size = rng.random_range(range);
@@ -187,10 +234,55 @@ impl Fixture {
         (path, content, size)
     }
 
-    pub async fn cleanup(&self, op: &Operator) {
+    /// Remove registered paths, report anything else left in the scope, then sweep it.
+    pub async fn cleanup(&self, op: &Operator) -> CleanupReport {
+        let scope = self.scope();
         let paths: Vec<_> = std::mem::take(self.paths.lock().unwrap().as_mut());
-        if !paths.is_empty() {
-            let _ = op.delete_iter(paths).await;
+        let mut report = CleanupReport {
+            scope: scope.clone(),
+            ..Default::default()
+        };
+
+        for path in &paths {
+            let removed = if path.ends_with('/') {
+                op.remove_all(path).await
+            } else {
+                op.delete(path).await
+            };
+            if removed.is_ok() {
+                report.removed += 1;
+            }
+        }
+
+        if scope.is_empty() {
+            return report;
+        }
+
+        if let Ok(entries) = op.list_with(&scope).recursive(true).await {
+            report.leaked = entries
+                .into_iter()
+                .filter(|e| !e.metadata().mode().is_dir())
+                .map(|e| e.path().to_string())
+                .collect();
+        }
+        let _ = op.remove_all(&scope).await;
+        report
+    }
+}
+
+impl CleanupReport {
+    /// Print leaked objects so tests that bypass the fixture are easy to spot.
+    pub fn print(&self) {
+        if self.leaked.is_empty() {
+            return;
+        }
+        eprintln!(
+            "behavior fixture: {} unregistered object(s) left under '{}' (swept):",
+            self.leaked.len(),
+            self.scope
+        );
+        for path in &self.leaked {
+            eprintln!("  {path}");
         }
     }
 }
@@ -0,0 +1,242 @@
+//! Self-update from GitHub releases.
+//!
+//! Release assets are published as `storify-<os>-<arch>` alongside a
+//! `storify-<os>-<arch>.sha256` file. An update is only installed once the downloaded
+//! binary matches that checksum; it is then written next to the running executable and
+//! renamed over it, so an interrupted update never leaves a partial binary behind.
+
+use crate::error::{Error, Result};
+use crate::scratch::StagedFile;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Overrides the GitHub API base URL (mirrors, GitHub Enterprise, tests).
+const UPDATE_API_ENV: &str = "STORIFY_UPDATE_API";
+const DEFAULT_API: &str = "https://api.github.com";
+const REPOSITORY: &str = "QuakeWang/storify";
+/// Tag of the rolling pre-release used by the nightly channel, republished by
+/// `.github/workflows/nightly.yml`.
+const NIGHTLY_TAG: &str = "nightly";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Channel {
+    /// Latest tagged release
+    Stable,
+    /// Rolling build from the default branch
+    Nightly,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Release {
+    tag_name: String,
+    assets: Vec<Asset>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Asset {
+    name: String,
+    browser_download_url: String,
+}
+
+/// What `storify self-update` found for the running binary.
+#[derive(Debug, Clone)]
+pub struct UpdateCheck {
+    pub current: String,
+    pub latest: String,
+    pub update_available: bool,
+    binary_url: String,
+    expected_sha256: String,
+}
+
+/// Release asset name for the running platform.
+fn asset_name() -> Result<&'static str> {
+    match (env::consts::OS, env::consts::ARCH) {
+        ("linux", "x86_64") => Ok("storify-linux-amd64"),
+        ("linux", "aarch64") => Ok("storify-linux-arm64"),
+        (os, arch) => Err(Error::SelfUpdate {
+            message: format!("no prebuilt release for {os}/{arch}; build from source instead"),
+        }),
+    }
+}
+
+fn client() -> Result<reqwest::Client> {
+    reqwest::Client::builder()
+        .user_agent(concat!("storify/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .map_err(http_error)
+}
+
+fn http_error(err: reqwest::Error) -> Error {
+    Error::SelfUpdate {
+        message: err.to_string(),
+    }
+}
+
+async fn fetch_release(client: &reqwest::Client, channel: Channel) -> Result<Release> {
+    let api = env::var(UPDATE_API_ENV).unwrap_or_else(|_| DEFAULT_API.to_string());
+    let api = api.trim_end_matches('/');
+    let url = match channel {
+        Channel::Stable => format!("{api}/repos/{REPOSITORY}/releases/latest"),
+        Channel::Nightly => format!("{api}/repos/{REPOSITORY}/releases/tags/{NIGHTLY_TAG}"),
+    };
+    tracing::debug!("fetch_release url={url}");
+    client
+        .get(&url)
+        .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(http_error)?
+        .json()
+        .await
+        .map_err(http_error)
+}
+
+async fn download(client: &reqwest::Client, url: &str) -> Result<Vec<u8>> {
+    tracing::debug!("download url={url}");
+    let response = client
+        .get(url)
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(http_error)?;
+    Ok(response.bytes().await.map_err(http_error)?.to_vec())
+}
+
+/// Look up the newest release on `channel` and compare it with the running binary.
+///
+/// Stable releases are compared by version; the nightly tag is reused for every build, so
+/// nightly compares the published checksum with the running executable instead.
+pub async fn check(channel: Channel) -> Result<UpdateCheck> {
+    let asset_name = asset_name()?;
+    let client = client()?;
+    let release = fetch_release(&client, channel).await?;
+
+    let find = |name: &str| release.assets.iter().find(|asset| asset.name == name);
+    let binary = find(asset_name).ok_or_else(|| Error::SelfUpdate {
+        message: format!("release {} has no asset {asset_name}", release.tag_name),
+    })?;
+    let checksum_name = format!("{asset_name}.sha256");
+    let checksum = find(&checksum_name).ok_or_else(|| Error::SelfUpdate {
+        message: format!(
+            "release {} has no {checksum_name}; refusing to install an unverified binary",
+            release.tag_name
+        ),
+    })?;
+    let checksum_text =
+        String::from_utf8_lossy(&download(&client, &checksum.browser_download_url).await?)
+            .into_owned();
+    let expected_sha256 = parse_checksum(&checksum_text)?;
+
+    let current = env!("CARGO_PKG_VERSION").to_string();
+    let update_available = match channel {
+        Channel::Stable => is_newer(&release.tag_name, &current),
+        Channel::Nightly => sha256_hex(&fs::read(env::current_exe()?)?) != expected_sha256,
+    };
+
+    Ok(UpdateCheck {
+        current,
+        latest: release.tag_name,
+        update_available,
+        binary_url: binary.browser_download_url.clone(),
+        expected_sha256,
+    })
+}
+
+/// Download the release found by [`check`], verify it and replace the running executable.
+///
+/// Returns the path of the replaced executable.
+pub async fn install(update: &UpdateCheck) -> Result<PathBuf> {
+    let client = client()?;
+    let binary = download(&client, &update.binary_url).await?;
+    let actual = sha256_hex(&binary);
+    if actual != update.expected_sha256 {
+        return Err(Error::ChecksumMismatch {
+            expected: update.expected_sha256.clone(),
+            actual,
+        });
+    }
+
+    let exe = env::current_exe()?;
+    let exe = fs::canonicalize(&exe).unwrap_or(exe);
+    replace_executable(&exe, &binary)?;
+    Ok(exe)
+}
+
+/// Write `contents` beside `target` and rename it into place.
+fn replace_executable(target: &Path, contents: &[u8]) -> Result<()> {
+    let staged = StagedFile::new(target);
+    let mut file = fs::File::create(staged.path())?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(staged.path(), fs::Permissions::from_mode(0o755))?;
+    }
+    staged.persist(target)
+}
+
+/// Extract the digest from a `sha256sum`-style line (`<hex>  <file>`) or a bare digest.
+fn parse_checksum(text: &str) -> Result<String> {
+    let digest = text.split_whitespace().next().unwrap_or_default();
+    if digest.len() == 64 && digest.chars().all(|c| c.is_ascii_hexdigit()) {
+        Ok(digest.to_ascii_lowercase())
+    } else {
+        Err(Error::SelfUpdate {
+            message: format!("malformed checksum file: {:?}", text.trim()),
+        })
+    }
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Whether release tag `latest` (e.g. `v0.3.0`) is newer than version `current`.
+fn is_newer(latest: &str, current: &str) -> bool {
+    fn parse(version: &str) -> Vec<u64> {
+        let version = version.trim_start_matches('v');
+        let core = version.split(['-', '+']).next().unwrap_or_default();
+        core.split('.')
+            .map(|part| part.parse().unwrap_or(0))
+            .collect()
+    }
+    parse(latest) > parse(current)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compares_versions_and_parses_checksums() {
+        assert!(is_newer("v0.10.0", "0.9.3"));
+        assert!(!is_newer("v0.2.0", "0.2.0"));
+        assert!(!is_newer("v0.1.9", "0.2.0"));
+
+        let digest = sha256_hex(b"storify");
+        assert_eq!(
+            parse_checksum(&format!("{}  storify-linux-amd64\n", digest.to_uppercase())).unwrap(),
+            digest
+        );
+        assert!(parse_checksum("not-a-digest").is_err());
+    }
+
+    #[test]
+    fn replaces_file_in_place() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("storify");
+        fs::write(&target, b"old").unwrap();
+        replace_executable(&target, b"new").unwrap();
+        assert_eq!(fs::read(&target).unwrap(), b"new");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+}